@@ -0,0 +1,191 @@
+# Export the low level IL of a binary from Binary Ninja for the cwe_checker.
+#
+# Usage:
+# - Install Binary Ninja with a license that allows headless use of the Python API.
+# - Generate the dump with "python3 binja_export/export_llil.py BINARY DUMP.json"
+# - Analyze the binary with "cwe_checker BINARY --binja-dump DUMP.json"
+#
+# The format of the dump is documented in the `frontend::binja` module of the cwe_checker_lib crate.
+
+import json
+import sys
+
+import binaryninja
+from binaryninja import SymbolType, VariableSourceType
+from binaryninja.lowlevelil import LowLevelILInstruction
+
+FORMAT_VERSION = 1
+
+# Map Binary Ninja architecture names to the <processor>_<bitness> names used by Ghidra.
+ARCHITECTURES = {
+    'x86': 'x86_32',
+    'x86_64': 'x86_64',
+    'armv7': 'ARM_32',
+    'armv7eb': 'ARM_32',
+    'thumb2': 'ARM_32',
+    'thumb2eb': 'ARM_32',
+    'aarch64': 'AARCH64_64',
+    'mips32': 'MIPS_32',
+    'mipsel32': 'MIPS_32',
+    'mips64': 'MIPS_64',
+    'ppc': 'PowerPC_32',
+    'ppc_le': 'PowerPC_32',
+    'ppc64': 'PowerPC_64',
+    'ppc64_le': 'PowerPC_64',
+    'rv32gc': 'RISCV_32',
+    'rv64gc': 'RISCV_64',
+}
+
+
+def serialize_il(il):
+    operation = il.operation.name[len('LLIL_'):]
+    result = {'operation': operation, 'size': il.size}
+    for (name, operand_type), value in zip(LowLevelILInstruction.ILOperations[il.operation], il.operands):
+        if operand_type == 'expr':
+            result[name] = serialize_il(value)
+        elif operand_type in ('reg', 'flag'):
+            result[name] = value.name
+        elif operand_type == 'int':
+            if operation in ('CONST', 'CONST_PTR') and il.size > 0:
+                value &= (1 << (8 * il.size)) - 1
+            result[name] = value
+        elif operand_type == 'target_map':
+            result[name] = {str(address): index for address, index in value.items()}
+    return result
+
+
+def serialize_registers(arch):
+    registers = []
+    for name, info in arch.regs.items():
+        registers.append({
+            'register': name,
+            'base_register': info.full_width_reg,
+            'lsb': info.offset,
+            'size': info.size,
+        })
+    return registers
+
+
+def register_names(arch, registers):
+    return [arch.get_reg_name(register) if isinstance(register, int) else str(register) for register in registers]
+
+
+def serialize_calling_convention(arch, cconv):
+    return_registers = [cconv.int_return_reg, cconv.high_int_return_reg]
+    return {
+        'name': cconv.name,
+        'integer_parameter_registers': register_names(arch, cconv.int_arg_regs),
+        'float_parameter_registers': register_names(arch, cconv.float_arg_regs),
+        'integer_return_registers': register_names(arch, [reg for reg in return_registers if reg is not None]),
+        'float_return_registers': register_names(arch, [cconv.float_return_reg] if cconv.float_return_reg else []),
+        'callee_saved_registers': register_names(arch, cconv.callee_saved_regs),
+    }
+
+
+def datatype_properties(bv):
+    pointer_size = bv.arch.address_size
+    is_windows = bv.platform is not None and bv.platform.name.startswith('windows')
+    return {
+        'char_size': 1,
+        'double_size': 8,
+        'float_size': 4,
+        'integer_size': 4,
+        'long_double_size': 16 if bv.arch.name in ('x86_64', 'rv64gc') else 8,
+        'long_long_size': 8,
+        'long_size': 4 if is_windows else pointer_size,
+        'pointer_size': pointer_size,
+        'short_size': 2,
+    }
+
+
+def serialize_function(bv, function, instruction_lengths):
+    blocks = []
+    for block in function.low_level_il:
+        instructions = []
+        for il in block:
+            instructions.append({'index': il.instr_index, 'address': il.address, 'il': serialize_il(il)})
+            instruction_lengths[il.address] = bv.get_instruction_length(il.address)
+        blocks.append({'instructions': instructions})
+    return {
+        'name': function.name,
+        'address': function.start,
+        'calling_convention': function.calling_convention.name if function.calling_convention else None,
+        'blocks': blocks,
+    }
+
+
+def serialize_arg_location(bv, variable):
+    if variable.source_type == VariableSourceType.RegisterVariableSourceType:
+        return {'register': bv.arch.get_reg_name(variable.storage)}
+    # Binary Ninja stack offsets on x86 include the return address pushed by the call.
+    offset = variable.storage
+    if bv.arch.name in ('x86', 'x86_64'):
+        offset -= bv.arch.address_size
+    return {'stack': {'offset': offset, 'size': variable.type.width if variable.type else bv.arch.address_size}}
+
+
+def serialize_imports(bv):
+    addresses = {}
+    for symbol_type in (SymbolType.ImportAddressSymbol, SymbolType.ExternalSymbol):
+        for symbol in bv.get_symbols_of_type(symbol_type):
+            addresses.setdefault(symbol.name, []).append(symbol.address)
+    imports = []
+    for symbol in bv.get_symbols_of_type(SymbolType.ImportedFunctionSymbol):
+        import_entry = {
+            'name': symbol.name,
+            'address': symbol.address,
+            'addresses': addresses.pop(symbol.name, []),
+        }
+        function = bv.get_function_at(symbol.address)
+        if function is not None:
+            import_entry['calling_convention'] = function.calling_convention.name if function.calling_convention else None
+            import_entry['parameters'] = [serialize_arg_location(bv, var) for var in function.parameter_vars]
+            import_entry['return_values'] = [{'register': bv.arch.get_reg_name(reg)} for reg in function.return_regs]
+            import_entry['no_return'] = not bool(function.can_return)
+            import_entry['has_var_args'] = bool(function.has_variable_arguments)
+        imports.append(import_entry)
+    # Imports that are only called through their import address, e.g. on Windows.
+    for name, import_addresses in addresses.items():
+        imports.append({'name': name, 'address': import_addresses[0], 'addresses': import_addresses[1:]})
+    return imports
+
+
+def export(bv):
+    arch = bv.arch
+    import_addresses = {symbol.address for symbol in bv.get_symbols_of_type(SymbolType.ImportedFunctionSymbol)}
+    instruction_lengths = {}
+    functions = [
+        serialize_function(bv, function, instruction_lengths)
+        for function in bv.functions
+        if function.start not in import_addresses
+    ]
+    entry_points = [bv.entry_point] + [symbol.address for symbol in bv.get_symbols_of_type(SymbolType.FunctionSymbol)
+                                       if symbol.binding == binaryninja.SymbolBinding.GlobalBinding]
+    return {
+        'format_version': FORMAT_VERSION,
+        'cpu_architecture': ARCHITECTURES.get(arch.name, arch.name),
+        'image_base': bv.start,
+        'stack_pointer_register': arch.stack_pointer,
+        'registers': serialize_registers(arch),
+        'calling_conventions': [serialize_calling_convention(arch, cconv) for cconv in arch.calling_conventions.values()],
+        'default_calling_convention': bv.platform.default_calling_convention.name,
+        'datatype_properties': datatype_properties(bv),
+        'functions': functions,
+        'imports': serialize_imports(bv),
+        'entry_points': sorted(set(entry_points)),
+        'instruction_lengths': {str(address): length for address, length in instruction_lengths.items()},
+    }
+
+
+def main():
+    if len(sys.argv) != 3:
+        print('Usage: python3 export_llil.py BINARY DUMP.json')
+        sys.exit(1)
+    with binaryninja.load(sys.argv[1]) as bv:
+        dump = export(bv)
+    with open(sys.argv[2], 'w') as dump_file:
+        json.dump(dump, dump_file)
+
+
+if __name__ == '__main__':
+    main()
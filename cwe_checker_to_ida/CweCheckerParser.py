@@ -57,5 +57,8 @@ class Parser:
     def parse(self):
         with open(self._result_path) as fhandle:
             j = json.load(fhandle)
+            # Newer versions wrap the warnings in an object together with pipeline errors.
+            if isinstance(j, dict):
+                j = j['warnings']
             cwe_out = self.__parse_cwe(j)
             return cwe_out
@@ -5,18 +5,46 @@ extern crate cwe_checker_lib; // Needed for the docstring-link to work
 
 use anyhow::Context;
 use anyhow::Error;
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 
+use cwe_checker_lib::analysis::allocators::recognize_allocators;
+use cwe_checker_lib::analysis::callgraph;
 use cwe_checker_lib::analysis::graph;
-use cwe_checker_lib::pipeline::{disassemble_binary, AnalysisResults};
+use cwe_checker_lib::analysis::path_feasibility::{self, RefinementMode};
+#[cfg(feature = "taint")]
+use cwe_checker_lib::analysis::taint::summary::TaintSummaries;
+use cwe_checker_lib::analysis::xref::XrefIndex;
+use cwe_checker_lib::intermediate_representation::Project;
+use cwe_checker_lib::pipeline::{
+    disassemble_binary, dump_callsites, export_ir, load_binja_dump, load_core_dump,
+    load_emulated_memory, replay_recording, AnalysisResults,
+};
+use cwe_checker_lib::query::Query;
+use cwe_checker_lib::utils::baseline::Baseline;
 use cwe_checker_lib::utils::binary::BareMetalConfig;
+use cwe_checker_lib::utils::cache::{self, AnalysisCache};
+use cwe_checker_lib::utils::cancellation::CancellationToken;
+use cwe_checker_lib::utils::coverage::Coverage;
 use cwe_checker_lib::utils::debug;
-use cwe_checker_lib::utils::log::{print_all_messages, LogLevel, timed_logging, init_logging_timer};
+#[cfg(feature = "dwarf")]
+use cwe_checker_lib::utils::debug_info::DebugInfo;
+use cwe_checker_lib::utils::emulation::EmulationSettings;
+use cwe_checker_lib::utils::ghidra::{ExportSettings, GhidraProgram};
+use cwe_checker_lib::utils::key_material;
+use cwe_checker_lib::utils::log::{
+    init_logging_timer, print_all_messages, timed_logging, LogCollector, LogFilter, LogLevel,
+    LogMessage, OutputFormat, WarningAggregator,
+};
+use cwe_checker_lib::utils::metrics::MetricsCollector;
 use cwe_checker_lib::utils::read_config_file;
+use cwe_checker_lib::utils::recording::load_manifest;
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::convert::From;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+mod scan;
 
 #[derive(ValueEnum, Clone, Debug, Copy)]
 /// Selects which kind of debug output is displayed.
@@ -46,12 +74,47 @@ impl From<&CliDebugMode> for debug::Stage {
     }
 }
 
+#[derive(ValueEnum, Clone, Debug, Copy)]
+/// Selects how CWE warnings on infeasible paths are handled.
+pub enum CliRefinementMode {
+    /// Remove the warnings.
+    Suppress,
+    /// Keep the warnings, but mark them as low confidence.
+    Downgrade,
+}
+
+impl From<CliRefinementMode> for RefinementMode {
+    fn from(mode: CliRefinementMode) -> Self {
+        match mode {
+            CliRefinementMode::Suppress => RefinementMode::Suppress,
+            CliRefinementMode::Downgrade => RefinementMode::Downgrade,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug, Copy)]
+/// Selects the output format of the call graph dump.
+pub enum CallgraphFormat {
+    /// The DOT format of Graphviz.
+    Dot,
+    /// A JSON object with lists of the functions and calls of the call graph.
+    Json,
+}
+
 #[derive(Debug, Parser)]
-#[command(version, about)]
+#[command(
+    version,
+    about,
+    subcommand_negates_reqs(true),
+    args_conflicts_with_subcommands(true)
+)]
 /// Find vulnerable patterns in binary executables
 struct CmdlineArgs {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// The path to the binary.
-    #[arg(required_unless_present("module_versions"), value_parser = check_file_existence)]
+    #[arg(required_unless_present_any(["module_versions", "replay"]), value_parser = check_file_existence)]
     binary: Option<String>,
 
     /// Path to a custom configuration file to use instead of the standard one.
@@ -73,6 +136,10 @@ struct CmdlineArgs {
     #[arg(long, short)]
     json: bool,
 
+    /// Generate SARIF 2.1.0 output, e.g. for GitHub code scanning.
+    #[arg(long, conflicts_with("json"))]
+    sarif: bool,
+
     /// Do not print log messages. This prevents polluting stdout for json output.
     #[arg(long, short)]
     quiet: bool,
@@ -92,6 +159,175 @@ struct CmdlineArgs {
     #[arg(long, value_parser = check_file_existence)]
     bare_metal_config: Option<String>,
 
+    /// Path to the `.gpr` file of an existing Ghidra project containing the binary.
+    ///
+    /// If this option is set then the program is exported from the project
+    /// instead of importing and analyzing the binary with Ghidra again,
+    /// so that manual fixes of functions in the project are used by the analysis.
+    /// The project is opened read-only.
+    #[arg(long, value_parser = check_file_existence)]
+    ghidra_project: Option<String>,
+
+    /// The name of the program inside the Ghidra project.
+    /// Defaults to the file name of the binary.
+    #[arg(long, requires("ghidra_project"))]
+    program: Option<String>,
+
+    /// Only export the given functions as a comma separated list of function names
+    /// or entry addresses, e.g. 'main,0x401a20'.
+    ///
+    /// Calls to all other functions are handled like calls to library functions.
+    #[arg(long, value_delimiter = ',')]
+    functions: Vec<String>,
+
+    /// Path to an ELF core dump of a running process of the binary.
+    ///
+    /// If this option is set then the memory contents of the core dump (including loaded libraries)
+    /// are used for the analysis instead of the initial memory contents of the binary.
+    /// This allows resolving e.g. function pointer tables that are populated at runtime.
+    #[arg(long, value_parser = check_file_existence, conflicts_with = "bare_metal_config")]
+    core_dump: Option<String>,
+
+    /// Load the binary with QEMU user mode emulation to obtain its memory contents after loading.
+    ///
+    /// The memory contents then contain the relocated pointers, resolved GOT entries
+    /// and thread-local storage set up by the loader.
+    /// The binary is executed until its entry point is reached, so only use this option for trusted binaries.
+    /// Requires the QEMU user mode emulator for the architecture of the binary (e.g. `qemu-arm`) in the `PATH`.
+    #[arg(long, conflicts_with_all = ["bare_metal_config", "core_dump"])]
+    emulate_loader: bool,
+
+    /// Stop the loader emulation at the main function instead of the entry point,
+    /// so that constructors of the binary and its libraries are executed.
+    #[arg(long, requires("emulate_loader"))]
+    run_constructors: bool,
+
+    /// Path to the directory containing the dynamic loader and libraries for the architecture of the binary.
+    /// Used by the loader emulation for dynamically linked binaries.
+    #[arg(long, requires("emulate_loader"))]
+    qemu_sysroot: Option<String>,
+
+    /// Path to a shared library that the binary links against. Can be given several times.
+    ///
+    /// Taint summaries of the functions exported by the libraries are used to propagate taint
+    /// through calls to these functions in the taint-based checks of the binary.
+    /// Libraries are analyzed in the given order, i.e. a library may use the summaries of the libraries given before it.
+    #[arg(long = "library", value_parser = check_file_existence, conflicts_with = "bare_metal_config")]
+    libraries: Vec<String>,
+
+    /// Path to a coverage file recorded while executing the binary.
+    ///
+    /// Supported formats are drcov files (e.g. generated by DynamoRIO or QEMU)
+    /// and plain lists of hexadecimal addresses (one address per line).
+    /// CWE warnings at executed addresses are marked as high confidence warnings.
+    #[arg(long, value_parser = check_file_existence)]
+    coverage: Option<String>,
+
+    /// Write the normalized intermediate representation of the binary as JSON to the given file.
+    ///
+    /// The format is documented in the `pipeline::ir_export` module of the cwe_checker_lib crate.
+    /// The analysis continues normally after the export.
+    #[arg(long)]
+    export_ir: Option<String>,
+
+    /// Save the P-Code exported by Ghidra and a copy of the binary to the given directory.
+    ///
+    /// The recording can be analyzed again with the "--replay" command line option,
+    /// which does not need Ghidra. The format is documented in the `utils::recording` module of the cwe_checker_lib crate.
+    #[arg(long)]
+    record: Option<String>,
+
+    /// Cache the P-Code exported by Ghidra in the given directory.
+    ///
+    /// If the directory already contains an export of the same binary with the same settings,
+    /// the export is reused and the Ghidra analysis of the binary is skipped.
+    /// Cannot be combined with "--ghidra-project", since changes to the project cannot be detected.
+    #[arg(long, value_name = "DIR", conflicts_with = "ghidra_project")]
+    pcode_cache: Option<String>,
+
+    /// Analyze the recording in the given directory instead of a binary.
+    ///
+    /// The P-Code and the binary are read from the recording, so that Ghidra is not needed.
+    #[arg(long, value_parser = check_dir_existence, conflicts_with_all = ["binary", "record", "pcode_cache", "bare_metal_config", "ghidra_project", "functions", "emulate_loader", "pcode_raw"])]
+    replay: Option<String>,
+
+    /// Read the disassembly of the binary from the given Binary Ninja LLIL dump instead of invoking Ghidra.
+    ///
+    /// The dump can be generated with the `binja_export/export_llil.py` script.
+    #[arg(long, value_parser = check_file_existence, conflicts_with_all = ["record", "pcode_cache", "bare_metal_config", "ghidra_project", "functions", "pcode_raw"])]
+    binja_dump: Option<String>,
+
+    /// Path to a file caching the CWE warnings and parameters of each function.
+    ///
+    /// If the file exists, the results of functions that did not change since the last analysis are reused
+    /// and only the changed functions and the functions calling or called by them are analyzed again.
+    /// The file is updated with the results of the analysis.
+    #[arg(long)]
+    cache: Option<String>,
+
+    /// Path to a baseline file of known CWE warnings.
+    ///
+    /// If the file does not exist, all CWE warnings of the analysis are recorded in it.
+    /// Otherwise the warnings recorded in the file are not reported.
+    /// Warnings are identified by fingerprints that do not depend on addresses,
+    /// so that the baseline can be used for recompiled versions of the binary.
+    /// The baseline contains the warnings before they are merged by `--merge-warnings`,
+    /// so that it does not depend on that option.
+    #[arg(long)]
+    baseline: Option<String>,
+
+    /// Path to a directory containing CWE check plugins.
+    ///
+    /// If not set, plugins are loaded from the plugin directory of the cwe_checker installation if it exists.
+    /// Requires that the cwe_checker was built with the `plugins` feature.
+    #[arg(long)]
+    plugin_dir: Option<String>,
+
+    /// The number of threads used for executing the CWE checks in parallel.
+    /// Defaults to the number of available CPU cores.
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// The memory usage in megabytes above which the Pointer Inference analysis
+    /// evicts the states of functions whose analysis has already stabilized.
+    ///
+    /// Evicted states are recomputed once they are needed again,
+    /// which trades computation time for memory on very large binaries.
+    /// Overrides the "max_memory" setting of the configuration file. Only supported on Linux.
+    #[arg(long, value_name = "MB")]
+    max_memory: Option<u64>,
+
+    /// Check whether the location of each CWE warning is reachable
+    /// under the branch conditions of the shortest path to it
+    /// and suppress or downgrade the warnings on infeasible paths.
+    ///
+    /// Uses the Z3 SMT solver if the cwe_checker was built with the `smt` feature.
+    /// Otherwise only constant and trivially contradicting branch conditions are detected.
+    #[arg(long)]
+    path_feasibility: Option<CliRefinementMode>,
+
+    /// Merge CWE warnings of different checks that share the same address and root cause,
+    /// e.g. an out-of-bounds write reported by both the CWE-119 and the CWE-805 check,
+    /// into a single warning listing all check names.
+    #[arg(long)]
+    merge_warnings: bool,
+
+    /// Print the recovered arguments of all call sites in the function with the given name
+    /// instead of running the checks.
+    ///
+    /// For each call site the resolved callee and for each argument its abstract value,
+    /// the origins of the values it depends on and the string content it points to are printed.
+    /// This helps to find out why a check like CWE-78 or CWE-134 did not report a call site.
+    #[arg(long, value_name = "FUNCTION")]
+    dump_callsites: Option<String>,
+
+    /// Print the call graph of the binary in the given format instead of running the checks.
+    ///
+    /// The call graph contains the direct calls between the functions of the binary
+    /// and the indirect calls whose targets could be resolved with the Pointer Inference analysis.
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    dump_callgraph: Option<CallgraphFormat>,
+
     /// Prints out the version numbers of all known modules.
     #[arg(long)]
     module_versions: bool,
@@ -107,6 +343,116 @@ struct CmdlineArgs {
     pcode_raw: Option<String>,
 }
 
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Search the binary for call sites, string references or global variable writes.
+    ///
+    /// The queries are evaluated against a cross reference index of the binary
+    /// and the results of the Pointer Inference analysis.
+    Query(QueryArgs),
+    /// Analyze all binaries in a directory, e.g. the root file system of a firmware image.
+    ///
+    /// ELF, PE and Mach-O files are analyzed in parallel in separate processes.
+    /// The results of each binary and a consolidated report are written to the output directory.
+    Scan(ScanArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct QueryArgs {
+    /// The path to the binary.
+    #[arg(value_parser = check_file_existence)]
+    binary: String,
+
+    /// Path to a custom configuration file to use instead of the standard one.
+    #[arg(long, short, value_parser = check_file_existence)]
+    config: Option<String>,
+
+    /// Path to a configuration file for analysis of bare metal binaries.
+    #[arg(long, value_parser = check_file_existence)]
+    bare_metal_config: Option<String>,
+
+    /// Generate JSON output.
+    #[arg(long, short)]
+    json: bool,
+
+    #[command(subcommand)]
+    query: QueryCommand,
+}
+
+#[derive(Debug, clap::Args)]
+struct ScanArgs {
+    /// The directory to scan for binaries.
+    #[arg(value_parser = check_dir_existence)]
+    directory: String,
+
+    /// The directory for the consolidated report and the results of the individual binaries.
+    #[arg(long, short)]
+    out: String,
+
+    /// The number of binaries analyzed in parallel.
+    /// Defaults to the number of available CPU cores.
+    #[arg(long, short)]
+    jobs: Option<usize>,
+
+    /// Abort the analysis of a binary after the given number of seconds.
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Abort the analysis of a binary if its resident memory exceeds the given number of MiB.
+    /// Only supported on Linux.
+    #[arg(long)]
+    max_memory: Option<u64>,
+
+    /// Path to a custom configuration file to use instead of the standard one.
+    #[arg(long, short, value_parser = check_file_existence)]
+    config: Option<String>,
+
+    /// Specify a specific set of checks to be run as a comma separated list, e.g. 'CWE332,CWE476,CWE782'.
+    /// Keys shared between the binaries are only searched for if the list contains 'CWE321'.
+    /// If 'CWE321' is the only check, the binaries are not analyzed.
+    #[arg(long, short)]
+    partial: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum QueryCommand {
+    /// List all call sites of an extern function.
+    Calls {
+        /// The name of the extern function, e.g. 'memcpy'.
+        symbol: String,
+        /// Only list call sites where the parameter with the given index (starting at zero)
+        /// is not known to be a constant.
+        #[arg(long)]
+        non_constant_param: Option<usize>,
+    },
+    /// List all instructions referencing a string that contains the given text.
+    String {
+        /// The text to search for, e.g. '/etc/shadow'.
+        text: String,
+    },
+    /// List all writes to a global variable.
+    GlobalWrites {
+        /// The hexadecimal address (e.g. '0x4010') or the symbol name of the global variable.
+        global: String,
+    },
+}
+
+impl From<&QueryCommand> for Query {
+    fn from(command: &QueryCommand) -> Self {
+        match command {
+            QueryCommand::Calls {
+                symbol,
+                non_constant_param,
+            } => Query::CallSites {
+                symbol: symbol.clone(),
+                non_constant_param: *non_constant_param,
+            },
+            QueryCommand::String { text } => Query::StringReferences(text.clone()),
+            QueryCommand::GlobalWrites { global } => Query::GlobalWrites(global.clone()),
+        }
+    }
+}
+
 impl From<&CmdlineArgs> for debug::Settings {
     fn from(args: &CmdlineArgs) -> Self {
         let stage = match &args.debug {
@@ -138,7 +484,11 @@ fn main() -> Result<(), Error> {
     init_logging_timer();
     let cmdline_args = CmdlineArgs::parse();
 
-    run_with_ghidra(&cmdline_args)
+    match &cmdline_args.command {
+        Some(Command::Query(query_args)) => run_query(query_args),
+        Some(Command::Scan(scan_args)) => run_scan(scan_args),
+        None => run_with_ghidra(&cmdline_args),
+    }
 }
 
 /// Return `Ok(file_path)` only if `file_path` points to an existing file.
@@ -153,12 +503,24 @@ fn check_file_existence(file_path: &str) -> Result<String, String> {
     }
 }
 
-
+/// Return `Ok(dir_path)` only if `dir_path` points to an existing directory.
+fn check_dir_existence(dir_path: &str) -> Result<String, String> {
+    if std::fs::metadata(dir_path)
+        .map_err(|err| format!("{err}"))?
+        .is_dir()
+    {
+        Ok(dir_path.to_string())
+    } else {
+        Err(format!("{dir_path} is not a directory."))
+    }
+}
 
 /// Run the cwe_checker with Ghidra as its backend.
 fn run_with_ghidra(args: &CmdlineArgs) -> Result<(), Error> {
     let debug_settings = args.into();
     let mut modules = cwe_checker_lib::get_modules();
+    let plugin_modules = load_plugin_modules(args.plugin_dir.as_deref())?;
+    modules.extend(plugin_modules.iter().copied());
     if args.module_versions {
         // Only print the module versions and then quit.
         println!("[cwe_checker] module_versions:");
@@ -170,19 +532,86 @@ fn run_with_ghidra(args: &CmdlineArgs) -> Result<(), Error> {
 
     timed_logging("Loading configuration");
     // Get the bare metal configuration file if it is provided
-    let bare_metal_config_opt: Option<BareMetalConfig> =
-        args.bare_metal_config.as_ref().map(|config_path| {
-            let file = std::io::BufReader::new(std::fs::File::open(config_path).unwrap());
-            serde_json::from_reader(file)
-                .expect("Parsing of the bare metal configuration file failed")
-        });
+    let bare_metal_config_opt = read_bare_metal_config(args.bare_metal_config.as_deref());
 
-    timed_logging("Loading binary");
-    let binary_file_path = PathBuf::from(args.binary.clone().unwrap());
+    let (binary_file_path, (binary, mut project, mut all_logs, pipeline_errors)) =
+        if let Some(recording_directory) = args.replay.as_deref() {
+            timed_logging("Replaying recording");
+            let recording_directory = Path::new(recording_directory);
+            let manifest = load_manifest(recording_directory)?;
+            (
+                PathBuf::from(manifest.binary_name),
+                replay_recording(recording_directory, &debug_settings)?,
+            )
+        } else {
+            timed_logging("Loading binary");
+            let binary_file_path = PathBuf::from(args.binary.clone().unwrap());
+
+            timed_logging("Disassembling binary");
+            let disassembled_binary = if let Some(binja_dump) = args.binja_dump.as_deref() {
+                load_binja_dump(&binary_file_path, Path::new(binja_dump), &debug_settings)?
+            } else {
+                let export_settings = get_export_settings(args, &binary_file_path);
+                disassemble_binary(
+                    &binary_file_path,
+                    bare_metal_config_opt,
+                    &export_settings,
+                    &debug_settings,
+                )?
+            };
+            (binary_file_path, disassembled_binary)
+        };
 
-    timed_logging("Disassembling binary");
-    let (binary, project, mut all_logs) =
-        disassemble_binary(&binary_file_path, bare_metal_config_opt, &debug_settings)?;
+    if let Some(core_dump) = args.core_dump.as_deref() {
+        timed_logging("Loading core dump");
+        load_core_dump(&mut project, &binary, Path::new(core_dump))?;
+    }
+    if args.emulate_loader {
+        timed_logging("Emulating loader");
+        let emulation_settings = EmulationSettings {
+            run_constructors: args.run_constructors,
+            sysroot: args.qemu_sysroot.as_ref().map(PathBuf::from),
+            ..EmulationSettings::default()
+        };
+        load_emulated_memory(
+            &mut project,
+            &binary,
+            &binary_file_path,
+            &emulation_settings,
+        )?;
+    }
+    if let Some(export_path) = args.export_ir.as_deref() {
+        timed_logging("Exporting intermediate representation");
+        export_ir(&project, Path::new(export_path))?;
+    }
+
+    #[cfg(feature = "dwarf")]
+    let debug_info = match DebugInfo::new(&binary, &project) {
+        Ok(debug_info) => debug_info,
+        Err(error) => {
+            all_logs.push(LogMessage::new_info(format!(
+                "Could not parse the debug information of the binary: {error}"
+            )));
+            None
+        }
+    };
+
+    let coverage = if let Some(coverage_file) = args.coverage.as_deref() {
+        timed_logging("Loading coverage file");
+        let binary_name = binary_file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let coverage = Coverage::from_file(Path::new(coverage_file), &binary_name, &project)?;
+        if coverage.is_empty() {
+            all_logs.push(LogMessage::new_info(
+                "Coverage file contains no addresses of the binary.",
+            ));
+        }
+        Some(coverage)
+    } else {
+        None
+    };
 
     // Filter the modules to be executed.
     if let Some(ref partial_module_list) = args.partial {
@@ -193,39 +622,104 @@ fn run_with_ghidra(args: &CmdlineArgs) -> Result<(), Error> {
         // TODO: CWE78 is disabled on a standard run for now,
         // because it uses up huge amounts of RAM and computation time on some binaries.
         modules.retain(|module| module.name != "CWE78");
+        // CWE804 is only relevant for embedded web interfaces and has to be selected explicitly.
+        modules.retain(|module| module.name != "CWE804");
+        // CWE733, CWE1277 and CWE1419 are only meaningful for firmware images.
+        if args.bare_metal_config.is_none() {
+            modules.retain(|module| !["CWE733", "CWE1277", "CWE1419"].contains(&module.name));
+        }
     }
 
     // Get the configuration file.
-    let config: serde_json::Value = if let Some(ref config_path) = args.config {
-        let file = std::io::BufReader::new(std::fs::File::open(config_path).unwrap());
-        serde_json::from_reader(file).context("Parsing of the configuration file failed")?
-    } else if project.runtime_memory_image.is_lkm {
-        read_config_file("lkm_config.json")?
+    let mut config = read_analysis_config(args.config.as_deref(), &project)?;
+
+    // Treat custom allocation functions of the binary like the standard allocation functions.
+    if let Ok(allocator_config) = serde_json::from_value(config["Allocators"].clone()) {
+        timed_logging("Recognizing custom allocators");
+        let allocators = recognize_allocators(&project, &allocator_config);
+        all_logs.append(&mut allocators.get_log_messages());
+        allocators.replace_functions_with_extern_symbols(&mut project);
+        allocators.add_to_config(&mut config);
+    }
+
+    // Reuse the cached results of functions that did not change since the last analysis.
+    let settings_hash = cache::hash_settings(&config, &modules);
+    let mut cached_warnings = Vec::new();
+    let mut cached_parameters = BTreeMap::new();
+    let full_program = if let Some(cache_path) = args.cache.as_deref() {
+        timed_logging("Loading analysis cache");
+        let full_program = project.program.term.clone();
+        if let Some(analysis_cache) = AnalysisCache::load(Path::new(cache_path), settings_hash)? {
+            let reusable_functions = analysis_cache.get_reusable_functions(&project.program);
+            all_logs.push(LogMessage::new_info(format!(
+                "Reusing the cached results of {} of {} functions.",
+                reusable_functions.len(),
+                full_program.subs.len()
+            )));
+            project.replace_functions_with_stubs(&reusable_functions);
+            cached_warnings = AnalysisCache::get_warnings(&full_program, &reusable_functions);
+            cached_parameters = reusable_functions
+                .iter()
+                .map(|(tid, cached)| (tid.clone(), cached.parameters.clone()))
+                .collect();
+        }
+        Some(full_program)
     } else {
-        read_config_file("config.json")?
+        None
     };
 
+    // The memory limit only trades computation time for memory, so it is not part of the cache settings.
+    if let Some(max_memory) = args.max_memory {
+        config["Memory"]["max_memory"] = serde_json::Value::from(max_memory);
+    }
+
     timed_logging("Generate the control flow graph of the program");
     // Generate the control flow graph of the program
     let (control_flow_graph, mut logs_graph) = graph::get_program_cfg_with_logs(&project.program);
     all_logs.append(&mut logs_graph);
 
-    let analysis_results = AnalysisResults::new(&binary, &control_flow_graph, &project);
+    // From now on Ctrl-C stops the analysis at the next safe point
+    // and the results computed so far are still printed.
+    let cancellation_token = CancellationToken::new();
+    install_interrupt_handler(&cancellation_token)?;
+    let metrics_collector = MetricsCollector::new();
+    let analysis_results = AnalysisResults::new(&binary, &control_flow_graph, &project)
+        .with_cancellation_token(Some(&cancellation_token))
+        .with_metrics_collector(Some(&metrics_collector));
 
-    let modules_depending_on_string_abstraction = BTreeSet::from_iter(["CWE78"]);
-    let modules_depending_on_pointer_inference = BTreeSet::from_iter([
-        "CWE119", "CWE134", "CWE190", "CWE252", "CWE337", "CWE416", "CWE476", "CWE789", "Memory",
+    let modules_depending_on_string_abstraction =
+        BTreeSet::from_iter(["CWE522", "CWE759", "CWE1325"]);
+    let modules_depending_on_automaton_string_abstraction = BTreeSet::from_iter(["CWE78"]);
+    let mut modules_depending_on_pointer_inference = BTreeSet::from_iter([
+        "CWE15", "CWE119", "CWE134", "CWE190", "CWE242", "CWE252", "CWE266", "CWE277", "CWE337",
+        "CWE354", "CWE362", "CWE369", "CWE416", "CWE434", "CWE440", "CWE476", "CWE522", "CWE613",
+        "CWE649", "CWE656", "CWE666", "CWE668", "CWE693", "CWE733", "CWE759", "CWE786", "CWE787",
+        "CWE789", "CWE804", "CWE805", "CWE941", "CWE1236", "CWE1287", "CWE1292", "CWE1325",
+        "CWE1419", "Memory",
     ]);
+    // We do not know which analyses plugin checks depend on.
+    modules_depending_on_pointer_inference.extend(plugin_modules.iter().map(|module| module.name));
 
-    let string_abstraction_needed = modules
+    let string_abstraction_needed = args.dump_callsites.is_some()
+        || modules
+            .iter()
+            .any(|module| modules_depending_on_string_abstraction.contains(&module.name));
+
+    let automaton_string_abstraction_needed = modules
         .iter()
-        .any(|module| modules_depending_on_string_abstraction.contains(&module.name));
+        .any(|module| modules_depending_on_automaton_string_abstraction.contains(&module.name));
 
     let pi_analysis_needed = string_abstraction_needed
+        || automaton_string_abstraction_needed
+        || args.dump_callgraph.is_some()
         || modules
             .iter()
             .any(|module| modules_depending_on_pointer_inference.contains(&module.name));
 
+    // Compute the side effects of all functions
+    let function_effects = analysis_results.compute_function_effects();
+    let analysis_results = analysis_results.with_function_effects(Some(&function_effects));
+
     timed_logging("Compute function signatures if required");
     // Compute function signatures if required
     let function_signatures = if pi_analysis_needed {
@@ -246,6 +740,7 @@ fn run_with_ghidra(args: &CmdlineArgs) -> Result<(), Error> {
 
     timed_logging("Compute string abstraction analysis if required");
     // Compute string abstraction analysis if required
+    #[cfg(feature = "string-abstraction")]
     let string_abstraction_results =
         if string_abstraction_needed {
             Some(analysis_results.compute_string_abstraction(
@@ -255,8 +750,82 @@ fn run_with_ghidra(args: &CmdlineArgs) -> Result<(), Error> {
         } else {
             None
         };
+    #[cfg(feature = "string-abstraction")]
     let analysis_results =
         analysis_results.with_string_abstraction(string_abstraction_results.as_ref());
+    #[cfg(feature = "string-abstraction")]
+    let automaton_string_abstraction_results =
+        if automaton_string_abstraction_needed {
+            Some(analysis_results.compute_string_abstraction(
+                &config["StringAbstraction"],
+                pi_analysis_results.as_ref(),
+            ))
+        } else {
+            None
+        };
+    #[cfg(feature = "string-abstraction")]
+    let analysis_results = analysis_results
+        .with_automaton_string_abstraction(automaton_string_abstraction_results.as_ref());
+
+    // Compute the taint summaries of the given libraries
+    #[cfg(feature = "taint")]
+    let taint_summaries = if args.libraries.is_empty() {
+        None
+    } else {
+        timed_logging("Compute taint summaries of libraries");
+        Some(compute_library_taint_summaries(
+            &args.libraries,
+            &config["Memory"],
+            &cancellation_token,
+            &mut all_logs,
+        )?)
+    };
+    #[cfg(feature = "taint")]
+    let analysis_results = analysis_results.with_taint_summaries(taint_summaries.as_ref());
+    if cfg!(not(feature = "taint")) && !args.libraries.is_empty() {
+        all_logs.push(LogMessage::new_info(
+            "The given libraries are ignored, since the cwe_checker was built without the taint feature.",
+        ));
+    }
+
+    // Compute the escape analysis for stack objects if the pointer inference was computed
+    let escape_analysis_results = pi_analysis_results
+        .as_ref()
+        .map(|pi_results| analysis_results.compute_escape_analysis(pi_results));
+    let analysis_results = analysis_results.with_escape_analysis(escape_analysis_results.as_ref());
+
+    if let (Some(format), Some(pi_results)) = (args.dump_callgraph, pi_analysis_results.as_ref()) {
+        let callgraph = callgraph::get_program_callgraph_with_indirect_calls(&project, pi_results);
+        match format {
+            CallgraphFormat::Dot => {
+                print!(
+                    "{}",
+                    callgraph::callgraph_to_dot(&callgraph, &project.program.term)
+                )
+            }
+            CallgraphFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&callgraph::callgraph_to_json(
+                    &callgraph,
+                    &project.program.term
+                ))?
+            ),
+        }
+        return Ok(());
+    }
+
+    if let Some(function_name) = args.dump_callsites.as_deref() {
+        print!(
+            "{}",
+            dump_callsites(
+                &analysis_results,
+                function_name,
+                #[cfg(feature = "dwarf")]
+                debug_info.as_ref()
+            )?
+        );
+        return Ok(());
+    }
 
     // Print debug and then return.
     // Right now there is only one debug printing function.
@@ -274,14 +843,90 @@ fn run_with_ghidra(args: &CmdlineArgs) -> Result<(), Error> {
     timed_logging("Executing the modules...");
     // TODO: what ?? ça exécute tout, et en cas de partial juste on affiche pas ????
     // Execute the modules and collect their logs and CWE-warnings.
-    let mut all_cwes = Vec::new();
-    for module in modules {
-        timed_logging(format!("Executing the modules - {}", module.name));
-        let (mut logs, mut cwes) = (module.run)(&analysis_results, &config[&module.name]);
-        all_logs.append(&mut logs);
-        all_cwes.append(&mut cwes);
+    let log_collector = LogCollector::spawn(LogFilter::default());
+    let skipped_modules = cwe_checker_lib::run_modules(
+        &modules,
+        &analysis_results,
+        &config,
+        &log_collector,
+        args.threads,
+    )?;
+    let collected_logs = log_collector.collect();
+    let executed_modules: Vec<&str> = modules
+        .iter()
+        .map(|module| module.name)
+        .filter(|name| !skipped_modules.contains(name))
+        .collect();
+    let metrics = metrics_collector.get_check_metrics(&executed_modules, &collected_logs.cwes);
+    let (mut module_logs, mut all_cwes) = collected_logs.into_messages();
+    all_logs.append(&mut module_logs);
+    let cancelled = cancellation_token.is_cancelled();
+    if cancelled {
+        all_logs.push(get_cancellation_summary(&skipped_modules));
     }
+    all_cwes.append(&mut cached_warnings);
     all_cwes.sort();
+    project
+        .program
+        .term
+        .add_provenance_to_warnings(&mut all_cwes);
+    // The results of a cancelled analysis are incomplete and must not be cached.
+    if let (Some(cache_path), Some(full_program), false) =
+        (args.cache.as_deref(), full_program.as_ref(), cancelled)
+    {
+        let mut parameters = project
+            .get_function_parameters(function_signatures.as_ref().unwrap_or(&BTreeMap::new()));
+        parameters.append(&mut cached_parameters);
+        AnalysisCache::new(full_program, &all_cwes, &parameters, settings_hash)
+            .store(Path::new(cache_path))?;
+    }
+    #[cfg(feature = "dwarf")]
+    if let Some(debug_info) = debug_info.as_ref() {
+        debug_info.add_source_info_to_warnings(&mut all_cwes);
+    }
+    if let Some(mode) = args.path_feasibility {
+        timed_logging("Checking the feasibility of paths to CWE warnings");
+        let num_infeasible = path_feasibility::refine_warnings(
+            &project,
+            &mut all_cwes,
+            mode.into(),
+            path_feasibility::default_solver().as_ref(),
+        );
+        all_logs.push(LogMessage::new_info(format!(
+            "Found {num_infeasible} CWE warnings on infeasible paths."
+        )));
+    }
+    if let Some(coverage) = coverage.as_ref() {
+        coverage.mark_covered_warnings(&mut all_cwes);
+    }
+    if let Some(baseline_path) = args.baseline.as_deref() {
+        let program = full_program.as_ref().unwrap_or(&project.program.term);
+        if let Some(baseline) = Baseline::load(Path::new(baseline_path))? {
+            let num_known_warnings = baseline.filter_warnings(program, &mut all_cwes);
+            all_logs.push(LogMessage::new_info(format!(
+                "Suppressed {num_known_warnings} CWE warnings contained in the baseline."
+            )));
+        } else if cancelled {
+            all_logs.push(LogMessage::new_info(format!(
+                "The baseline {baseline_path} was not recorded, since the analysis was cancelled."
+            )));
+        } else {
+            Baseline::new(program, &all_cwes).store(Path::new(baseline_path))?;
+            all_logs.push(LogMessage::new_info(format!(
+                "Recorded {} CWE warnings in the baseline {baseline_path}.",
+                all_cwes.len()
+            )));
+        }
+    }
+    if args.merge_warnings {
+        let num_warnings = all_cwes.len();
+        all_cwes = WarningAggregator::default().aggregate(all_cwes);
+        all_logs.push(LogMessage::new_info(format!(
+            "Merged {} CWE warnings into {} warnings.",
+            num_warnings,
+            all_cwes.len()
+        )));
+    }
 
     // Print the results of the modules.
     if args.quiet {
@@ -289,15 +934,254 @@ fn run_with_ghidra(args: &CmdlineArgs) -> Result<(), Error> {
     } else {
         if args.statistics {
             cwe_checker_lib::utils::log::add_debug_log_statistics(&mut all_logs);
+            for (check, check_metrics) in metrics.iter() {
+                all_logs
+                    .push(LogMessage::new_info(format!("Metrics: {check_metrics}")).source(check));
+            }
         }
         if !args.verbose {
             all_logs.retain(|log_msg| log_msg.level != LogLevel::Debug);
         }
     }
-    print_all_messages(all_logs, all_cwes, args.out.as_deref(), args.json);
+    let binary_path = binary_file_path.to_string_lossy();
+    let output_format = if args.sarif {
+        OutputFormat::Sarif {
+            binary_path: &binary_path,
+            modules: &modules,
+        }
+    } else if args.json {
+        OutputFormat::Json
+    } else {
+        OutputFormat::Text
+    };
+    print_all_messages(
+        all_logs,
+        all_cwes,
+        pipeline_errors,
+        metrics,
+        args.out.as_deref(),
+        output_format,
+    );
+    Ok(())
+}
+
+/// Compute the taint summaries of the functions exported by the given libraries.
+///
+/// The libraries are analyzed in the given order,
+/// so that calls to functions of previously analyzed libraries are handled by their summaries.
+#[cfg(feature = "taint")]
+fn compute_library_taint_summaries(
+    libraries: &[String],
+    pointer_inference_config: &serde_json::Value,
+    cancellation_token: &CancellationToken,
+    all_logs: &mut Vec<LogMessage>,
+) -> Result<TaintSummaries, Error> {
+    let mut taint_summaries = TaintSummaries::default();
+    for library in libraries {
+        if cancellation_token.is_cancelled() {
+            break;
+        }
+        timed_logging(format!("Compute taint summaries of {library}"));
+        let (binary, project, mut logs, _pipeline_errors) = disassemble_binary(
+            Path::new(library),
+            None,
+            &ExportSettings::default(),
+            &debug::Settings::default(),
+        )?;
+        all_logs.append(&mut logs);
+        let control_flow_graph = graph::get_program_cfg(&project.program);
+        let analysis_results = AnalysisResults::new(&binary, &control_flow_graph, &project)
+            .with_cancellation_token(Some(cancellation_token));
+        let (function_signatures, _) = analysis_results.compute_function_signatures();
+        let analysis_results =
+            analysis_results.with_function_signatures(Some(&function_signatures));
+        let pi_analysis_results =
+            analysis_results.compute_pointer_inference(pointer_inference_config, false);
+        let analysis_results = analysis_results.with_pointer_inference(Some(&pi_analysis_results));
+        let library_summaries = analysis_results.compute_taint_summaries(Some(&taint_summaries));
+        all_logs.push(LogMessage::new_info(format!(
+            "Computed taint summaries for {} functions exported by {library}",
+            library_summaries.len()
+        )));
+        taint_summaries.extend(library_summaries);
+    }
+    Ok(taint_summaries)
+}
+
+/// Cancel the analysis on the first Ctrl-C, so that the results computed so far are still printed.
+/// A second Ctrl-C terminates the process immediately.
+fn install_interrupt_handler(cancellation_token: &CancellationToken) -> Result<(), Error> {
+    let cancellation_token = cancellation_token.clone();
+    ctrlc::set_handler(move || {
+        if cancellation_token.is_cancelled() {
+            std::process::exit(130);
+        }
+        eprintln!("Cancelling the analysis. Press Ctrl-C again to exit immediately.");
+        cancellation_token.cancel();
+    })
+    .context("Could not install the Ctrl-C handler")
+}
+
+/// Generate the log message summarizing what was not analyzed because the analysis was cancelled.
+fn get_cancellation_summary(skipped_modules: &[&str]) -> LogMessage {
+    let mut text = "Analysis cancelled. The results are incomplete: \
+        analyses running at the time of cancellation were stopped early"
+        .to_string();
+    if !skipped_modules.is_empty() {
+        text.push_str(&format!(
+            " and the following modules were skipped: {}",
+            skipped_modules.join(", ")
+        ));
+    }
+    text.push('.');
+    LogMessage::new_error(text)
+}
+
+/// Run a query on the given binary and print the results.
+fn run_query(args: &QueryArgs) -> Result<(), Error> {
+    let debug_settings = debug::SettingsBuilder::default().build();
+    let bare_metal_config_opt = read_bare_metal_config(args.bare_metal_config.as_deref());
+    let (binary, project, _logs, _pipeline_errors) = disassemble_binary(
+        &PathBuf::from(&args.binary),
+        bare_metal_config_opt,
+        &ExportSettings::default(),
+        &debug_settings,
+    )?;
+    let config = read_analysis_config(args.config.as_deref(), &project)?;
+
+    let control_flow_graph = graph::get_program_cfg(&project.program);
+    let analysis_results = AnalysisResults::new(&binary, &control_flow_graph, &project);
+    let (function_signatures, _logs) = analysis_results.compute_function_signatures();
+    let analysis_results = analysis_results.with_function_signatures(Some(&function_signatures));
+    let pi_analysis_results = analysis_results.compute_pointer_inference(&config["Memory"], false);
+    let analysis_results = analysis_results.with_pointer_inference(Some(&pi_analysis_results));
+
+    let index = XrefIndex::new(&project, Some(&pi_analysis_results));
+    let matches =
+        cwe_checker_lib::query::evaluate(&analysis_results, &index, &(&args.query).into())?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+    } else {
+        for query_match in matches {
+            println!("{query_match}");
+        }
+    }
     Ok(())
 }
 
+/// Analyze all binaries in the given directory.
+fn run_scan(args: &ScanArgs) -> Result<(), Error> {
+    let mut analysis_args = Vec::new();
+    if let Some(config) = &args.config {
+        analysis_args.extend(["--config".to_string(), config.clone()]);
+    }
+    // The search for shared keys is not a module of the analysis of the individual binaries.
+    let mut find_shared_keys = true;
+    let mut analyze_binaries = true;
+    if let Some(partial) = &args.partial {
+        let (shared_keys, modules): (Vec<&str>, Vec<&str>) = partial
+            .split(',')
+            .partition(|name| *name == key_material::CWE_NAME);
+        find_shared_keys = !shared_keys.is_empty();
+        analyze_binaries = !modules.is_empty();
+        analysis_args.extend(["--partial".to_string(), modules.join(",")]);
+    }
+    // The binaries are already analyzed in parallel.
+    analysis_args.extend(["--threads".to_string(), "1".to_string()]);
+    let settings = scan::ScanSettings {
+        out_dir: PathBuf::from(&args.out),
+        jobs: args.jobs.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|parallelism| parallelism.get())
+                .unwrap_or(1)
+        }),
+        timeout: args.timeout.map(Duration::from_secs),
+        max_memory_mib: args.max_memory,
+        analysis_args,
+        analyze_binaries,
+        find_shared_keys,
+    };
+    scan::scan_directory(Path::new(&args.directory), &settings)
+}
+
+/// Get the settings for the export of the binary by Ghidra from the command line arguments.
+fn get_export_settings(args: &CmdlineArgs, binary_file_path: &Path) -> ExportSettings {
+    let ghidra_program = args
+        .ghidra_project
+        .as_ref()
+        .map(|ghidra_project| GhidraProgram {
+            project_path: PathBuf::from(ghidra_project),
+            program_name: args.program.clone().unwrap_or_else(|| {
+                binary_file_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            }),
+        });
+    ExportSettings {
+        ghidra_program,
+        function_filter: args.functions.clone(),
+        recording: args.record.as_ref().map(PathBuf::from),
+        pcode_cache: args.pcode_cache.as_ref().map(PathBuf::from),
+    }
+}
+
+/// Read the bare metal configuration file if a path to it is provided.
+fn read_bare_metal_config(config_path: Option<&str>) -> Option<BareMetalConfig> {
+    config_path.map(|config_path| {
+        let file = std::io::BufReader::new(std::fs::File::open(config_path).unwrap());
+        serde_json::from_reader(file).expect("Parsing of the bare metal configuration file failed")
+    })
+}
+
+/// Read the custom configuration file if a path to it is provided.
+/// Else read the standard configuration file for the type of the binary.
+fn read_analysis_config(
+    config_path: Option<&str>,
+    project: &Project,
+) -> Result<serde_json::Value, Error> {
+    if let Some(config_path) = config_path {
+        let file = std::io::BufReader::new(std::fs::File::open(config_path).unwrap());
+        serde_json::from_reader(file).context("Parsing of the configuration file failed")
+    } else if project.runtime_memory_image.is_lkm {
+        read_config_file("lkm_config.json")
+    } else {
+        read_config_file("config.json")
+    }
+}
+
+/// Load the CWE checks of all plugins in the plugin directory.
+#[cfg(feature = "plugins")]
+fn load_plugin_modules(
+    plugin_dir: Option<&str>,
+) -> Result<Vec<&'static cwe_checker_lib::CweModule>, Error> {
+    let plugin_dir = match plugin_dir {
+        Some(plugin_dir) => PathBuf::from(plugin_dir),
+        None => {
+            let plugin_dir = cwe_checker_lib::utils::get_plugin_dir();
+            if !plugin_dir.is_dir() {
+                return Ok(Vec::new());
+            }
+            plugin_dir
+        }
+    };
+    cwe_checker_lib::plugins::load_plugins(&plugin_dir)
+}
+
+/// Plugins are not supported without the `plugins` feature.
+/// Returns an error if a plugin directory was explicitly requested.
+#[cfg(not(feature = "plugins"))]
+fn load_plugin_modules(
+    plugin_dir: Option<&str>,
+) -> Result<Vec<&'static cwe_checker_lib::CweModule>, Error> {
+    if plugin_dir.is_some() {
+        return Err(anyhow::anyhow!(
+            "The cwe_checker was built without plugin support. Rebuild it with the `plugins` feature to load plugins."
+        ));
+    }
+    Ok(Vec::new())
+}
+
 /// Only keep the modules specified by the `--partial` parameter in the `modules` list.
 /// The parameter is a comma-separated list of module names, e.g. 'CWE332,CWE476,CWE782'.
 fn filter_modules_for_partial_run(
@@ -4,6 +4,7 @@
 //! Currently there are benchmarks for the following steps:
 //!
 //! - CFG construction,
+//! - worklist orders of the fixpoint algorithm,
 //! - individual normalization passes,
 //! - function signatures analysis,
 //! - pointer inference,
@@ -98,6 +99,7 @@ use criterion::{
     BatchSize, BenchmarkGroup, BenchmarkId, Criterion, SamplingMode, Throughput,
 };
 
+use cwe_checker_lib::abstract_domain::BricksDomain;
 use cwe_checker_lib::analysis::{self, graph};
 use cwe_checker_lib::intermediate_representation::{
     propagate_control_flow, Project, RuntimeMemoryImage,
@@ -449,7 +451,7 @@ mod core_analyses {
                         BenchmarkId::new("string_abstractions", pcode_project_json),
                         |b| {
                             b.iter_with_large_drop(|| {
-                                analysis_results.compute_string_abstraction(
+                                analysis_results.compute_string_abstraction::<BricksDomain>(
                                     &config["StringAbstraction"],
                                     black_box(Some(&pi_result)),
                                 )
@@ -653,6 +655,75 @@ mod cfg {
     }
 }
 
+mod fixpoint {
+    //! Benchmarks for the worklist orders of the fixpoint algorithm.
+    //!
+    //! Uses the expression propagation as a loop-heavy intraprocedural
+    //! analysis on the whole program CFG.
+
+    use super::helpers::*;
+    use super::inputs::*;
+    use super::*;
+
+    use cwe_checker_lib::analysis::expression_propagation;
+    use cwe_checker_lib::analysis::fixpoint::WorklistOrder;
+    use cwe_checker_lib::analysis::forward_interprocedural_fixpoint::create_computation_with_worklist_order;
+    use cwe_checker_lib::analysis::interprocedural_fixpoint_generic::NodeValue;
+    use std::collections::HashMap;
+
+    const WORKLIST_ORDERS: [WorklistOrder; 3] = [
+        WorklistOrder::StronglyConnectedComponents,
+        WorklistOrder::ReversePostOrder,
+        WorklistOrder::InDegree,
+    ];
+
+    fn compute_expression_propagation(cfg: &graph::Graph, worklist_order: WorklistOrder) -> bool {
+        let context = expression_propagation::Context::new(cfg);
+        let mut computation = create_computation_with_worklist_order(context, None, worklist_order);
+        for node in cfg.node_indices() {
+            if let graph::Node::BlkStart(blk, sub) = cfg[node] {
+                if sub.term.blocks.first().map(|first| &first.tid) == Some(&blk.tid) {
+                    computation.set_node_value(node, NodeValue::Value(HashMap::new()));
+                }
+            }
+        }
+        computation.compute_with_max_steps(100);
+        computation.has_stabilized()
+    }
+
+    pub fn bench_worklist_orders(c: &mut Criterion) {
+        let bench_with_input_loop =
+            |pcode_projects: &[&str], binaries: &[&str], mut group: BenchmarkGroup<WallTime>| {
+                for (pcode_project_json, binary) in iter::zip(pcode_projects, binaries) {
+                    let mut project = get_project(pcode_project_json, binary);
+                    let _ = project.normalize_basic();
+                    let cfg = graph::get_program_cfg(&project.program);
+
+                    group.throughput(Throughput::Elements(cfg.edge_count() as u64));
+                    for worklist_order in WORKLIST_ORDERS {
+                        group.bench_with_input(
+                            BenchmarkId::new(format!("{worklist_order:?}"), pcode_project_json),
+                            &cfg,
+                            |b, cfg| {
+                                b.iter(|| {
+                                    compute_expression_propagation(cfg, black_box(worklist_order))
+                                })
+                            },
+                        );
+                    }
+                }
+
+                group.finish();
+            };
+
+        let group_ls = c.benchmark_group("ls_worklist_orders");
+        bench_with_input_loop(&LS_PCODE_PROJECTS, &LS_BINARIES, group_ls);
+
+        let group_netfs = c.benchmark_group("netfs_worklist_orders");
+        bench_with_input_loop(&NETFS_PCODE_PROJECTS, &NETFS_BINARIES, group_netfs);
+    }
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default()
@@ -660,6 +731,7 @@ criterion_group!(
         .warm_up_time(time::Duration::new(5, 0))
         .measurement_time(time::Duration::new(10, 0));
     targets = cfg::bench_cfg_construction,
+        fixpoint::bench_worklist_orders,
         normalization::bench_normalize_basic,
         normalization::bench_normalize_expression_propagation,
         normalization::bench_normalize_substitute_trivial_expressions,
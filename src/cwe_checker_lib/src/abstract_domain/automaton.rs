@@ -0,0 +1,524 @@
+//! This module contains the Automaton Domain.
+//!
+//! The Automaton Domain represents a set of strings by a deterministic finite automaton over characters.
+//! Besides transitions for single characters each state may have a *default* transition,
+//! which is taken for all characters without an explicit transition in that state.
+//! Default transitions represent characters that are unknown to the analysis,
+//! e.g. user input that is inserted into a string.
+//!
+//! In contrast to the Bricks Domain, concatenations with unknown strings keep the order of the known parts,
+//! regardless of how many strings were merged before.
+//! For example, appending an unknown string to "sh -c " results in an automaton accepting
+//! exactly the strings starting with "sh -c ":
+//! ```txt
+//! 0 -'s'-> 1 -'h'-> 2 -' '-> 3 -'-'-> 4 -'c'-> 5 -' '-> (6) -*-> (6)
+//! ```
+//!
+//! The automata are always minimal and their states are numbered in a canonical order,
+//! so that two automata are equal if and only if they accept the same strings.
+//! Merging two automata computes the automaton of the union of their languages.
+//! If the resulting automaton has more than [`MAX_STATES`] states, the merge results in *Top*,
+//! which ensures that fixpoint computations terminate on loops that keep extending a string.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::{AbstractDomain, DomainInsertion, HasTop};
+use crate::prelude::*;
+
+/// The maximal number of states of an automaton.
+/// Operations resulting in bigger automata return *Top*.
+pub const MAX_STATES: usize = 64;
+
+/// The maximal number of states during the determinization of an intermediate nondeterministic automaton.
+const MAX_DETERMINIZATION_STATES: usize = 1024;
+
+/// The characters that appear in integer values (besides a leading minus sign).
+const INTEGER_CHARACTERS: &str = "0123456789";
+
+/// The characters that appear in float values, including `inf` and `nan`.
+const FLOAT_CHARACTERS: &str = "0123456789.-ainfeE";
+
+/// The `AutomatonDomain` represents a set of strings by a minimal deterministic finite automaton.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub enum AutomatonDomain {
+    /// The *Top* value represents all strings.
+    Top,
+    /// The set of strings accepted by the automaton.
+    Value(Automaton),
+}
+
+/// A minimal deterministic finite automaton over characters.
+///
+/// The automaton starts in state 0.
+/// Missing transitions lead to an implicit state that rejects all strings.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Automaton {
+    states: Vec<AutomatonState>,
+}
+
+/// A state of a deterministic automaton.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Default)]
+struct AutomatonState {
+    /// The transitions for single characters.
+    transitions: BTreeMap<char, usize>,
+    /// The transition for all characters without an explicit transition.
+    default_transition: Option<usize>,
+    /// Whether the automaton accepts strings ending in this state.
+    accepting: bool,
+}
+
+/// A state of a nondeterministic automaton.
+/// Used for the intermediate results of concatenations and unions.
+#[derive(Clone, Default)]
+struct NfaState {
+    transitions: BTreeMap<char, BTreeSet<usize>>,
+    default_transitions: BTreeSet<usize>,
+    accepting: bool,
+}
+
+impl NfaState {
+    /// Add the outgoing transitions of the other state to this state.
+    fn add_outgoing_transitions(&mut self, other: &NfaState) {
+        for (character, targets) in other.transitions.iter() {
+            self.transitions
+                .entry(*character)
+                .or_default()
+                .extend(targets.iter().copied());
+        }
+        self.default_transitions
+            .extend(other.default_transitions.iter().copied());
+    }
+}
+
+/// The transitions of a state in terms of the equivalence classes of their targets,
+/// i.e. the class of the default transition and the explicit transitions to other classes.
+type ClassTransitions = (Option<usize>, Vec<(char, usize)>);
+
+impl Automaton {
+    /// Create an automaton accepting all strings.
+    fn any_string() -> Automaton {
+        Automaton {
+            states: vec![AutomatonState {
+                default_transition: Some(0),
+                accepting: true,
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// Create an automaton accepting all non-empty strings consisting of the given characters.
+    fn non_empty_strings_of(characters: &str) -> Automaton {
+        let mut states = vec![AutomatonState::default(), AutomatonState::default()];
+        for character in characters.chars() {
+            states[0].transitions.insert(character, 1);
+            states[1].transitions.insert(character, 1);
+        }
+        states[1].accepting = true;
+        Automaton { states }
+    }
+
+    /// Return the number of states of the automaton.
+    pub fn get_number_of_states(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns `true` if the automaton accepts strings with unknown substrings of arbitrary length,
+    /// i.e. if a default transition lies on a cycle of the automaton.
+    pub fn contains_unknown_substring(&self) -> bool {
+        self.states.iter().enumerate().any(|(index, state)| {
+            state
+                .default_transition
+                .is_some_and(|target| self.get_reachable_states(target).contains(&index))
+        })
+    }
+
+    /// Get all states reachable from the given state.
+    fn get_reachable_states(&self, start: usize) -> BTreeSet<usize> {
+        let mut reachable = BTreeSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            let state = &self.states[index];
+            for target in state.transitions.values().chain(&state.default_transition) {
+                if reachable.insert(*target) {
+                    stack.push(*target);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Convert the automaton to the states of a nondeterministic automaton,
+    /// where the state indices are shifted by the given offset.
+    fn to_nfa_states(&self, offset: usize) -> Vec<NfaState> {
+        self.states
+            .iter()
+            .map(|state| NfaState {
+                transitions: state
+                    .transitions
+                    .iter()
+                    .map(|(character, target)| (*character, BTreeSet::from([target + offset])))
+                    .collect(),
+                default_transitions: state
+                    .default_transition
+                    .map(|target| target + offset)
+                    .into_iter()
+                    .collect(),
+                accepting: state.accepting,
+            })
+            .collect()
+    }
+
+    /// Compute the automaton accepting the concatenations of the strings of both automata.
+    ///
+    /// Returns `None` if the result has too many states.
+    fn concat(&self, other: &Automaton) -> Option<Automaton> {
+        let mut states = self.to_nfa_states(0);
+        let other_states = other.to_nfa_states(states.len());
+        for state in states.iter_mut().filter(|state| state.accepting) {
+            state.add_outgoing_transitions(&other_states[0]);
+            state.accepting = other_states[0].accepting;
+        }
+        states.extend(other_states);
+        Automaton::determinize(&states)
+    }
+
+    /// Compute the automaton accepting the strings of both automata.
+    ///
+    /// Returns `None` if the result has too many states.
+    fn union(&self, other: &Automaton) -> Option<Automaton> {
+        let self_states = self.to_nfa_states(1);
+        let other_states = other.to_nfa_states(1 + self_states.len());
+        let mut start = NfaState::default();
+        start.add_outgoing_transitions(&self_states[0]);
+        start.add_outgoing_transitions(&other_states[0]);
+        start.accepting = self_states[0].accepting || other_states[0].accepting;
+        let mut states = vec![start];
+        states.extend(self_states);
+        states.extend(other_states);
+        Automaton::determinize(&states)
+    }
+
+    /// Compute the minimal deterministic automaton of the given nondeterministic automaton
+    /// with start state 0 through the subset construction.
+    ///
+    /// Returns `None` if the result has too many states.
+    fn determinize(nfa_states: &[NfaState]) -> Option<Automaton> {
+        let mut subsets = vec![BTreeSet::from([0])];
+        let mut subset_indices = BTreeMap::from([(BTreeSet::from([0]), 0)]);
+        let mut get_subset_index = |subset: BTreeSet<usize>, subsets: &mut Vec<BTreeSet<usize>>| {
+            *subset_indices.entry(subset.clone()).or_insert_with(|| {
+                subsets.push(subset);
+                subsets.len() - 1
+            })
+        };
+        let mut states = Vec::new();
+        while states.len() < subsets.len() {
+            if subsets.len() > MAX_DETERMINIZATION_STATES {
+                return None;
+            }
+            let subset = subsets[states.len()].clone();
+            let members: Vec<&NfaState> = subset.iter().map(|index| &nfa_states[*index]).collect();
+            let default_targets: BTreeSet<usize> = members
+                .iter()
+                .flat_map(|member| member.default_transitions.iter().copied())
+                .collect();
+            let characters: BTreeSet<char> = members
+                .iter()
+                .flat_map(|member| member.transitions.keys().copied())
+                .collect();
+            let mut state = AutomatonState {
+                accepting: members.iter().any(|member| member.accepting),
+                ..Default::default()
+            };
+            for character in characters {
+                let mut targets = default_targets.clone();
+                for member in members.iter() {
+                    targets.extend(member.transitions.get(&character).into_iter().flatten());
+                }
+                let target = get_subset_index(targets, &mut subsets);
+                state.transitions.insert(character, target);
+            }
+            if !default_targets.is_empty() {
+                state.default_transition = Some(get_subset_index(default_targets, &mut subsets));
+            }
+            states.push(state);
+        }
+        let minimized = Automaton { states }.minimize();
+        (minimized.states.len() <= MAX_STATES).then_some(minimized)
+    }
+
+    /// Get the states from which an accepting state is reachable.
+    fn get_productive_states(&self) -> Vec<bool> {
+        let mut productive: Vec<bool> = self.states.iter().map(|state| state.accepting).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (index, state) in self.states.iter().enumerate() {
+                if !productive[index]
+                    && state
+                        .transitions
+                        .values()
+                        .chain(&state.default_transition)
+                        .any(|target| productive[*target])
+                {
+                    productive[index] = true;
+                    changed = true;
+                }
+            }
+        }
+        productive
+    }
+
+    /// Get the transitions of a state in terms of the equivalence classes of their targets.
+    ///
+    /// Transitions to unproductive states are removed.
+    /// Explicit transitions to the same class as the default transition are redundant and also removed.
+    /// Note that explicit transitions never lead to unproductive states if the default transition does not,
+    /// since the subset construction adds the targets of the default transition to all explicit transitions.
+    fn get_class_transitions(&self, index: usize, classes: &[Option<usize>]) -> ClassTransitions {
+        let state = &self.states[index];
+        let default_class = state.default_transition.and_then(|target| classes[target]);
+        let transitions = state
+            .transitions
+            .iter()
+            .filter_map(|(character, target)| Some((*character, classes[*target]?)))
+            .filter(|(_, class)| Some(*class) != default_class)
+            .collect();
+        (default_class, transitions)
+    }
+
+    /// Compute the minimal automaton accepting the same strings with canonically numbered states.
+    ///
+    /// Equivalent states are computed by partition refinement, unproductive states are removed
+    /// and the remaining states are numbered in the order of a breadth-first search from the start state.
+    fn minimize(&self) -> Automaton {
+        let productive = self.get_productive_states();
+        if !productive[0] {
+            // The automaton accepts no strings at all.
+            return Automaton {
+                states: vec![AutomatonState::default()],
+            };
+        }
+        let mut classes: Vec<Option<usize>> = self
+            .states
+            .iter()
+            .zip(productive.iter())
+            .map(|(state, is_productive)| is_productive.then_some(usize::from(state.accepting)))
+            .collect();
+        let mut num_classes = 0;
+        loop {
+            let mut signatures: BTreeMap<(usize, ClassTransitions), usize> = BTreeMap::new();
+            let refined_classes: Vec<Option<usize>> = (0..self.states.len())
+                .map(|index| {
+                    let class = classes[index]?;
+                    let signature = (class, self.get_class_transitions(index, &classes));
+                    let next_class = signatures.len();
+                    Some(*signatures.entry(signature).or_insert(next_class))
+                })
+                .collect();
+            classes = refined_classes;
+            if signatures.len() == num_classes {
+                break;
+            }
+            num_classes = signatures.len();
+        }
+
+        let mut representatives: BTreeMap<usize, usize> = BTreeMap::new();
+        for (index, class) in classes.iter().enumerate() {
+            if let Some(class) = class {
+                representatives.entry(*class).or_insert(index);
+            }
+        }
+        let start_class = classes[0].unwrap();
+        let mut order = vec![start_class];
+        let mut numbering = BTreeMap::from([(start_class, 0)]);
+        let mut class_transitions = Vec::new();
+        while class_transitions.len() < order.len() {
+            let (default_class, transitions) = self
+                .get_class_transitions(representatives[&order[class_transitions.len()]], &classes);
+            for class in transitions
+                .iter()
+                .map(|(_, class)| *class)
+                .chain(default_class)
+            {
+                numbering.entry(class).or_insert_with(|| {
+                    order.push(class);
+                    order.len() - 1
+                });
+            }
+            class_transitions.push((default_class, transitions));
+        }
+        let states = order
+            .iter()
+            .zip(class_transitions)
+            .map(|(class, (default_class, transitions))| AutomatonState {
+                transitions: transitions
+                    .into_iter()
+                    .map(|(character, class)| (character, numbering[&class]))
+                    .collect(),
+                default_transition: default_class.map(|class| numbering[&class]),
+                accepting: self.states[representatives[class]].accepting,
+            })
+            .collect();
+        Automaton { states }
+    }
+}
+
+impl AutomatonDomain {
+    /// Returns `true` if the domain represents strings with unknown substrings of arbitrary length,
+    /// e.g. because user input was inserted into the string.
+    ///
+    /// Always returns `true` for *Top*.
+    pub fn contains_unknown_substring(&self) -> bool {
+        match self {
+            AutomatonDomain::Top => true,
+            AutomatonDomain::Value(automaton) => automaton.contains_unknown_substring(),
+        }
+    }
+
+    /// Convert the domain to an automaton, where *Top* is represented by an automaton accepting all strings.
+    fn to_automaton(&self) -> Automaton {
+        match self {
+            AutomatonDomain::Top => Automaton::any_string(),
+            AutomatonDomain::Value(automaton) => automaton.clone(),
+        }
+    }
+}
+
+impl From<String> for AutomatonDomain {
+    fn from(string: String) -> Self {
+        let mut states: Vec<AutomatonState> = string
+            .chars()
+            .enumerate()
+            .map(|(index, character)| AutomatonState {
+                transitions: BTreeMap::from([(character, index + 1)]),
+                ..Default::default()
+            })
+            .collect();
+        states.push(AutomatonState {
+            accepting: true,
+            ..Default::default()
+        });
+        AutomatonDomain::Value(Automaton { states })
+    }
+}
+
+impl DomainInsertion for AutomatonDomain {
+    /// Append string domain as part of a concatenation.
+    /// *Top* values are treated as unknown strings, so that the known parts of the other domain are kept.
+    fn append_string_domain(&self, string_domain: &Self) -> Self {
+        if self.is_top() && string_domain.is_top() {
+            return AutomatonDomain::Top;
+        }
+        match self.to_automaton().concat(&string_domain.to_automaton()) {
+            Some(automaton) => AutomatonDomain::Value(automaton),
+            None => AutomatonDomain::Top,
+        }
+    }
+
+    /// Create a string domain that approximates float values.
+    fn create_float_value_domain() -> Self {
+        AutomatonDomain::Value(Automaton::non_empty_strings_of(FLOAT_CHARACTERS))
+    }
+
+    /// Create a string domain that approximates char values, i.e. a single unknown character.
+    fn create_char_domain() -> Self {
+        AutomatonDomain::Value(Automaton {
+            states: vec![
+                AutomatonState {
+                    default_transition: Some(1),
+                    ..Default::default()
+                },
+                AutomatonState {
+                    accepting: true,
+                    ..Default::default()
+                },
+            ],
+        })
+    }
+
+    /// Create a string domain that approximates integer values, i.e. digits with an optional minus sign.
+    fn create_integer_domain() -> Self {
+        let minus_sign =
+            AutomatonDomain::from("-".to_string()).merge(&Self::create_empty_string_domain());
+        minus_sign.append_string_domain(&AutomatonDomain::Value(Automaton::non_empty_strings_of(
+            INTEGER_CHARACTERS,
+        )))
+    }
+
+    /// Create a string domain that approximates pointer values.
+    fn create_pointer_value_domain() -> Self {
+        AutomatonDomain::Top
+    }
+
+    /// Creates a top value of the domain.
+    fn create_top_value_domain() -> Self {
+        AutomatonDomain::Top
+    }
+
+    /// Create a string domain that represents an empty string.
+    fn create_empty_string_domain() -> Self {
+        AutomatonDomain::from("".to_string())
+    }
+}
+
+impl AbstractDomain for AutomatonDomain {
+    /// Merge two values by computing the automaton of the union of their strings.
+    /// Returns *Top* if either domain represents it or if the resulting automaton has too many states.
+    fn merge(&self, other: &Self) -> Self {
+        match (self, other) {
+            (AutomatonDomain::Value(automaton), AutomatonDomain::Value(other_automaton)) => {
+                if automaton == other_automaton {
+                    return self.clone();
+                }
+                match automaton.union(other_automaton) {
+                    Some(merged) => AutomatonDomain::Value(merged),
+                    None => AutomatonDomain::Top,
+                }
+            }
+            _ => AutomatonDomain::Top,
+        }
+    }
+
+    /// Check if the value is *Top*.
+    fn is_top(&self) -> bool {
+        matches!(self, AutomatonDomain::Top)
+    }
+}
+
+impl HasTop for AutomatonDomain {
+    /// Return a *Top* value
+    fn top(&self) -> Self {
+        AutomatonDomain::Top
+    }
+}
+
+impl fmt::Display for AutomatonDomain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AutomatonDomain::Top => write!(f, "Top"),
+            AutomatonDomain::Value(automaton) => {
+                write!(f, "Automaton:")?;
+                for (index, state) in automaton.states.iter().enumerate() {
+                    if state.accepting {
+                        write!(f, " ({index})")?;
+                    } else {
+                        write!(f, " {index}")?;
+                    }
+                    for (character, target) in state.transitions.iter() {
+                        write!(f, " -{character:?}-> {target}")?;
+                    }
+                    if let Some(target) = state.default_transition {
+                        write!(f, " -*-> {target}")?;
+                    }
+                    write!(f, ";")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
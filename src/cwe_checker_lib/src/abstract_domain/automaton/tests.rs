@@ -0,0 +1,65 @@
+use super::*;
+
+fn automaton(string: &str) -> AutomatonDomain {
+    AutomatonDomain::from(string.to_string())
+}
+
+#[test]
+fn canonical_representation() {
+    let ab_or_cb = automaton("ab").merge(&automaton("cb"));
+    assert_eq!(ab_or_cb, automaton("cb").merge(&automaton("ab")));
+    assert_eq!(
+        ab_or_cb,
+        automaton("a")
+            .merge(&automaton("c"))
+            .append_string_domain(&automaton("b"))
+    );
+    assert_eq!(ab_or_cb.merge(&automaton("ab")), ab_or_cb);
+    assert_ne!(ab_or_cb, automaton("ab"));
+    assert_eq!(
+        automaton("sh").append_string_domain(&automaton(" -c")),
+        automaton("sh -c")
+    );
+
+    let AutomatonDomain::Value(merged) = automaton("ls -l").merge(&automaton("ls -a")) else {
+        panic!()
+    };
+    assert_eq!(merged.get_number_of_states(), 6);
+}
+
+#[test]
+fn concatenation_with_unknown_strings() {
+    let command = automaton("sh -c ").append_string_domain(&AutomatonDomain::Top);
+    assert!(!command.is_top());
+    assert!(command.contains_unknown_substring());
+    assert_eq!(
+        format!("{command}"),
+        "Automaton: 0 -'s'-> 1; 1 -'h'-> 2; 2 -' '-> 3; 3 -'-'-> 4; 4 -'c'-> 5; 5 -' '-> 6; (6) -*-> 6;"
+    );
+    // Merging with other commands keeps the known prefix.
+    let merged = command.merge(&automaton("ls -l"));
+    assert!(!merged.is_top());
+    assert!(merged.contains_unknown_substring());
+    assert!(AutomatonDomain::Top
+        .append_string_domain(&automaton(" > /dev/null"))
+        .contains_unknown_substring());
+
+    assert!(!automaton("ls -l").contains_unknown_substring());
+    let formatted = automaton("kill -9 ")
+        .append_string_domain(&AutomatonDomain::create_integer_domain())
+        .append_string_domain(&automaton(" ; echo "))
+        .append_string_domain(&AutomatonDomain::create_char_domain());
+    assert!(!formatted.contains_unknown_substring());
+    assert!(AutomatonDomain::create_top_value_domain().contains_unknown_substring());
+}
+
+#[test]
+fn widening_to_top() {
+    let mut domain = automaton("");
+    for length in 1..MAX_STATES {
+        domain = domain.merge(&automaton(&"a".repeat(length)));
+        assert!(!domain.is_top());
+    }
+    assert!(domain.merge(&automaton(&"a".repeat(MAX_STATES))).is_top());
+    assert!(domain.merge(&AutomatonDomain::Top).is_top());
+}
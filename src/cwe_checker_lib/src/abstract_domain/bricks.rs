@@ -179,6 +179,56 @@ impl BricksDomain {
         BricksDomain::Value(new_list)
     }
 
+    /// Returns an upper bound for the length of the represented strings.
+    ///
+    /// Returns `None` if the length is unbounded,
+    /// i.e. if the domain or one of its bricks is *Top*
+    /// or if a brick may be repeated arbitrarily often.
+    /// The latter is the result of widening string values that grow in each iteration of a loop.
+    pub fn get_max_length(&self) -> Option<u64> {
+        let BricksDomain::Value(bricks) = self else {
+            return None;
+        };
+        bricks.iter().try_fold(0u64, |length, brick| {
+            let BrickDomain::Value(brick) = brick else {
+                return None;
+            };
+            if brick.get_max() == u32::MAX {
+                return None;
+            }
+            let longest_sequence = brick
+                .get_sequence()
+                .iter()
+                .map(|sequence| sequence.len())
+                .max()
+                .unwrap_or(0);
+            length.checked_add(u64::from(brick.get_max()) * longest_sequence as u64)
+        })
+    }
+
+    /// Returns the represented string if the domain represents exactly one string.
+    ///
+    /// Returns `None` if the domain or one of its bricks is *Top*
+    /// or if a brick represents more than one character sequence.
+    pub fn get_constant_string(&self) -> Option<String> {
+        let BricksDomain::Value(bricks) = self else {
+            return None;
+        };
+        bricks.iter().try_fold(String::new(), |mut string, brick| {
+            let BrickDomain::Value(brick) = brick else {
+                return None;
+            };
+            if brick.is_empty_string() {
+                return Some(string);
+            }
+            if brick.get_min() != 1 || brick.get_max() != 1 || brick.get_sequence().len() != 1 {
+                return None;
+            }
+            string.push_str(brick.get_sequence().first()?);
+            Some(string)
+        })
+    }
+
     /// Unwraps a list of BrickDomains and panic if it's *Top*
     fn unwrap_value(&self) -> Vec<BrickDomain> {
         match self {
@@ -483,3 +483,59 @@ fn test_append_string_domain() {
         bricks_one.append_string_domain(&bricks_two)
     );
 }
+
+#[test]
+fn max_length() {
+    let bounded = BricksDomain::Value(vec![
+        BrickDomain::Value(Brick::mock_brick(vec!["id=".to_string()], 1, 1)),
+        BrickDomain::Value(Brick::mock_brick(
+            vec!["a".to_string(), "bcd".to_string()],
+            0,
+            2,
+        )),
+    ]);
+    assert_eq!(bounded.get_max_length(), Some(9));
+    assert_eq!(BricksDomain::Value(Vec::new()).get_max_length(), Some(0));
+
+    let widened = BrickDomain::Value(Brick::mock_brick(vec!["x".to_string()], 1, 1)).widen(
+        &BrickDomain::Value(Brick::mock_brick(
+            vec!["x".to_string()],
+            1,
+            INTERVAL_THRESHOLD as u32 + 2,
+        )),
+    );
+    assert_eq!(BricksDomain::Value(vec![widened]).get_max_length(), None);
+    assert_eq!(
+        BricksDomain::Value(vec![BrickDomain::Top]).get_max_length(),
+        None
+    );
+    assert_eq!(BricksDomain::Top.get_max_length(), None);
+}
+
+#[test]
+fn constant_string() {
+    let constant = BricksDomain::Value(vec![
+        BrickDomain::Value(Brick::mock_brick(vec!["salt".to_string()], 1, 1)),
+        BrickDomain::Value(Brick::new()),
+        BrickDomain::Value(Brick::mock_brick(vec!["42".to_string()], 1, 1)),
+    ]);
+    assert_eq!(constant.get_constant_string(), Some("salt42".to_string()));
+    assert_eq!(
+        BricksDomain::Value(Vec::new()).get_constant_string(),
+        Some(String::new())
+    );
+
+    let alternatives = BricksDomain::Value(vec![BrickDomain::Value(Brick::mock_brick(
+        vec!["a".to_string(), "b".to_string()],
+        1,
+        1,
+    ))]);
+    assert_eq!(alternatives.get_constant_string(), None);
+    let repeated = BricksDomain::Value(vec![BrickDomain::Value(Brick::mock_brick(
+        vec!["a".to_string()],
+        0,
+        2,
+    ))]);
+    assert_eq!(repeated.get_constant_string(), None);
+    assert_eq!(BricksDomain::Top.get_constant_string(), None);
+}
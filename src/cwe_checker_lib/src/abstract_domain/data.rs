@@ -334,4 +334,27 @@ mod tests {
         data.remove_ids(&ids_to_remove);
         assert_eq!(data, bv(42).into());
     }
+
+    #[test]
+    fn pointer_alignment() {
+        let pointer = DataDomain::from_target(new_id("Rax"), IntervalDomain::mock(0x20, 0x20));
+        let aligned = pointer.bin_op(BinOpType::IntAnd, &IntervalDomain::mock(-16, -16).into());
+        assert_eq!(
+            aligned,
+            DataDomain::from_target(new_id("Rax"), IntervalDomain::mock(0x11, 0x20))
+        );
+        let with_low_bits_set =
+            pointer.bin_op(BinOpType::IntOr, &IntervalDomain::mock(3, 3).into());
+        assert_eq!(
+            with_low_bits_set,
+            DataDomain::from_target(new_id("Rax"), IntervalDomain::mock(0x20, 0x23))
+        );
+        // Without known bits the offsets are unknown.
+        let pointer = DataDomain::from_target(new_id("Rax"), bv(0x20));
+        let aligned = pointer.bin_op(BinOpType::IntAnd, &bv(-16).into());
+        assert_eq!(
+            aligned.get_relative_values().get(&new_id("Rax")),
+            Some(&BitvectorDomain::new_top(ByteSize::new(8)))
+        );
+    }
 }
@@ -105,6 +105,47 @@ impl<T: RegisterDomain> DataDomain<T> {
         }
     }
 
+    /// Compute `self & rhs` or `self | rhs` if one of the operands may be a pointer.
+    ///
+    /// If one of the operands is an absolute value `c`, the result is computed via the identities
+    /// `x & c == x - (x & !c)` and `x | c == x + (!x & c)`,
+    /// where the unknown value of `x` is approximated by `Top`.
+    /// Thus pointer targets are kept and only the offsets get less exact.
+    /// E.g. for the pointer alignment operation `ptr & 0xfffffff0`
+    /// the offset of the result lies in the interval `[offset - 15, offset]`
+    /// (assuming that the value domain keeps track of the known bits of masked values).
+    fn compute_bitmask_with_pointer(&self, op: BinOpType, rhs: &Self) -> Self {
+        let (pointer, bitmask) = if rhs.relative_values.is_empty() {
+            (self, rhs)
+        } else if self.relative_values.is_empty() {
+            (rhs, self)
+        } else {
+            return self.preserve_relative_targets_for_binop(rhs);
+        };
+        let Some(bitmask_value) = bitmask.absolute_value.as_ref() else {
+            return self.preserve_relative_targets_for_binop(rhs);
+        };
+        let unknown = T::new_top(self.bytesize());
+        let mut result = match op {
+            BinOpType::IntAnd => {
+                let cleared_bits =
+                    unknown.bin_op(BinOpType::IntAnd, &bitmask_value.un_op(UnOpType::IntNegate));
+                pointer.subtract_offset(&cleared_bits)
+            }
+            BinOpType::IntOr => {
+                let set_bits = unknown.bin_op(BinOpType::IntAnd, bitmask_value);
+                pointer.add_offset(&set_bits)
+            }
+            _ => panic!("Unexpected binary operation for bitmask computation"),
+        };
+        result.absolute_value = pointer
+            .absolute_value
+            .as_ref()
+            .map(|value| value.bin_op(op, bitmask_value));
+        result.contains_top_values |= bitmask.contains_top_values;
+        result
+    }
+
     /// Compute the result of a byte size preserving binary operation
     /// where it is unknown whether the result is an absolute or relative value.
     ///
@@ -146,7 +187,8 @@ impl<T: RegisterDomain> RegisterDomain for DataDomain<T> {
                 // Case 3: Subtraction
                 IntSub => self.compute_sub(rhs),
                 // Case 4: An operation where the result may be a pointer.
-                IntAnd | IntOr | IntXOr => self.preserve_relative_targets_for_binop(rhs),
+                IntAnd | IntOr => self.compute_bitmask_with_pointer(op, rhs),
+                IntXOr => self.preserve_relative_targets_for_binop(rhs),
                 // Case 5: An operation with result being a boolean.
                 IntEqual | IntNotEqual | IntLess | IntLessEqual | IntSLess | IntSLessEqual
                 | IntCarry | IntSCarry | IntSBorrow | BoolXOr | BoolOr | BoolAnd | FloatEqual
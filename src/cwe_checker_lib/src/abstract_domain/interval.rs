@@ -3,13 +3,17 @@ use std::fmt::Display;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 
-use super::{AbstractDomain, HasTop, RegisterDomain, SizedDomain, SpecializeByConditional};
+use super::{
+    AbstractDomain, HasTop, KnownBitsDomain, RegisterDomain, SizedDomain, SpecializeByConditional,
+};
 use super::{TryToBitvec, TryToInterval};
 
 mod simple_interval;
 pub use simple_interval::*;
 
 mod bin_ops;
+mod widening;
+pub use widening::WideningConfig;
 
 /// An abstract domain representing values in an interval range with strides and widening hints.
 ///
@@ -140,16 +144,29 @@ impl IntervalDomain {
     /// If the merged interval equals one of the input intervals as value sets, do not perform widening.
     /// Else widening is performed if and only if
     /// the length of the interval is greater than the widening delay plus the stride of the interval.
+    /// The widening delay is at least the delay of the [`WideningConfig`] of the current thread.
     ///
     /// ### How to widen
     ///
-    /// If no suitable widening bounds for widening exist, widen to the `Top` value.
-    /// If exactly one widening bound exists, widen up to the bound,
-    /// but do not perform widening in the other direction of the interval.
-    /// If widening bounds for both directions exist, widen up to the bounds in both directions.
+    /// If a widening bound exists for a changed direction of the interval, widen up to the bound.
+    /// Else widen up to the nearest threshold of the [`WideningConfig`] of the current thread if one exists.
+    /// Directions in which the interval cannot be widened this way are not widened.
+    /// If the interval could not be widened in any direction, widen to the `Top` value.
     ///
     /// After that the `widening_delay` is set to the length of the resulting interval.
     pub fn signed_merge_and_widen(&self, other: &IntervalDomain) -> IntervalDomain {
+        WideningConfig::with_current(|config| {
+            self.signed_merge_and_widen_with_config(other, config)
+        })
+    }
+
+    /// Merge as signed intervals and perform widening according to the given configuration if necessary.
+    /// See [`IntervalDomain::signed_merge_and_widen`] for the widening strategy.
+    pub fn signed_merge_and_widen_with_config(
+        &self,
+        other: &IntervalDomain,
+        config: &WideningConfig,
+    ) -> IntervalDomain {
         let mut merged_domain = self.signed_merge(other);
         if merged_domain.equal_as_value_sets(self)
             || merged_domain.equal_as_value_sets(other)
@@ -162,9 +179,10 @@ impl IntervalDomain {
         if let Ok(length) =
             (merged_domain.interval.end.clone() - &merged_domain.interval.start).try_to_u64()
         {
+            let widening_delay = std::cmp::max(merged_domain.widening_delay, config.delay);
             let widening_threshold = std::cmp::max(
-                merged_domain.widening_delay + 1,
-                merged_domain.widening_delay + merged_domain.interval.stride,
+                widening_delay + 1,
+                widening_delay + merged_domain.interval.stride,
             );
             if length <= widening_threshold {
                 // Do not widen below the widening threshold.
@@ -173,21 +191,29 @@ impl IntervalDomain {
             }
         }
         let mut has_been_widened = false;
-        if self.interval.start != other.interval.start
-            && merged_domain.widening_lower_bound.is_some()
-        {
-            // widen to the lower bound
-            merged_domain.interval.start = merged_domain.widening_lower_bound.unwrap();
-            merged_domain.interval.adjust_start_to_value_in_stride();
-            merged_domain.widening_lower_bound = None;
-            has_been_widened = true;
-        }
-        if self.interval.end != other.interval.end && merged_domain.widening_upper_bound.is_some() {
-            // widen to the upper bound
-            merged_domain.interval.end = merged_domain.widening_upper_bound.unwrap();
-            merged_domain.interval.adjust_end_to_value_in_stride();
-            merged_domain.widening_upper_bound = None;
-            has_been_widened = true;
+        if self.interval.start != other.interval.start {
+            // widen to the lower bound or to the next threshold below the interval
+            if let Some(bound) = merged_domain
+                .widening_lower_bound
+                .take()
+                .or_else(|| config.get_lower_threshold(&merged_domain.interval.start))
+            {
+                merged_domain.interval.start = bound;
+                merged_domain.interval.adjust_start_to_value_in_stride();
+                has_been_widened = true;
+            }
+        }
+        if self.interval.end != other.interval.end {
+            // widen to the upper bound or to the next threshold above the interval
+            if let Some(bound) = merged_domain
+                .widening_upper_bound
+                .take()
+                .or_else(|| config.get_upper_threshold(&merged_domain.interval.end))
+            {
+                merged_domain.interval.end = bound;
+                merged_domain.interval.adjust_end_to_value_in_stride();
+                has_been_widened = true;
+            }
         }
         if has_been_widened {
             merged_domain.widening_delay = (merged_domain.interval.end.clone()
@@ -561,14 +587,14 @@ impl RegisterDomain for IntervalDomain {
     ///
     /// For binary operations that are not explicitly implemented
     /// the result is only exact if both intervals contain exactly one value.
+    /// Bitwise operations and right shifts are computed on the known bits of the intervals.
     fn bin_op(&self, op: BinOpType, rhs: &Self) -> Self {
         use BinOpType::*;
         match op {
             IntEqual | IntNotEqual | IntLess | IntSLess | IntLessEqual | IntSLessEqual
-            | IntCarry | IntSCarry | IntSBorrow | IntAnd | IntOr | IntXOr | IntRight
-            | IntSRight | IntDiv | IntSDiv | IntRem | IntSRem | BoolAnd | BoolOr | BoolXOr
-            | FloatEqual | FloatNotEqual | FloatLess | FloatLessEqual | FloatAdd | FloatSub
-            | FloatMult | FloatDiv => {
+            | IntCarry | IntSCarry | IntSBorrow | IntDiv | IntSDiv | IntRem | IntSRem | BoolAnd
+            | BoolOr | BoolXOr | FloatEqual | FloatNotEqual | FloatLess | FloatLessEqual
+            | FloatAdd | FloatSub | FloatMult | FloatDiv => {
                 let new_interval = if self.interval.start == self.interval.end
                     && rhs.interval.start == rhs.interval.end
                 {
@@ -592,6 +618,7 @@ impl RegisterDomain for IntervalDomain {
             IntSub => self.sub(rhs),
             IntMult => self.signed_mul(rhs),
             IntLeft => self.shift_left(rhs),
+            IntAnd | IntOr | IntXOr | IntRight | IntSRight => self.bitwise_bin_op(op, rhs),
         }
     }
 
@@ -1,6 +1,22 @@
 use super::*;
 
 impl IntervalDomain {
+    /// Compute the interval of possible results of a bitwise operation or a right shift.
+    ///
+    /// The operation is computed in the [`KnownBitsDomain`] (i.e. on the bits that are equal for all values of an interval)
+    /// and the result is converted back to an interval.
+    /// This keeps information about the result of masking operations, e.g. `x & 0xf` always lies in the interval `[0, 15]`.
+    pub fn bitwise_bin_op(&self, op: BinOpType, rhs: &Self) -> Self {
+        let lhs_bits = KnownBitsDomain::from_interval(&self.interval);
+        let rhs_bits = KnownBitsDomain::from_interval(&rhs.interval);
+        IntervalDomain {
+            interval: lhs_bits.bin_op(op, &rhs_bits).to_interval(),
+            widening_lower_bound: None,
+            widening_upper_bound: None,
+            widening_delay: std::cmp::max(self.widening_delay, rhs.widening_delay),
+        }
+    }
+
     /// Compute the interval of possible results
     /// if one adds a value from `self` to a value from `rhs`.
     pub fn add(&self, rhs: &Self) -> Self {
@@ -140,6 +140,47 @@ fn signed_merge() {
     assert_eq!(var, expected_result);
 }
 
+#[test]
+fn widening_with_config() {
+    use std::collections::BTreeSet;
+    let config = WideningConfig {
+        delay: 4,
+        thresholds: BTreeSet::from([-100, 16, 1000, 5_000_000_000]),
+        loop_condition_thresholds: false,
+    };
+    // No widening if the interval length does not exceed the delay
+    let a = IntervalDomain::mock(0, 1);
+    let b = IntervalDomain::mock(2, 3);
+    assert_eq!(
+        a.signed_merge_and_widen_with_config(&b, &config),
+        IntervalDomain::mock(0, 3)
+    );
+    // Without widening hints both bounds are widened to the next thresholds
+    let a = IntervalDomain::mock(0, 3);
+    let b = IntervalDomain::mock(5, 10);
+    assert_eq!(
+        a.signed_merge_and_widen_with_config(&b, &config),
+        IntervalDomain::mock(-100, 16).as_freshly_widened()
+    );
+    assert_eq!(
+        a.signed_merge_and_widen_with_config(&b, &WideningConfig::default()),
+        IntervalDomain::new_top(ByteSize::new(8))
+    );
+    // Widening hints take precedence over thresholds
+    let a = IntervalDomain::mock_with_bounds(None, 0, 3, Some(50));
+    assert_eq!(
+        a.signed_merge_and_widen_with_config(&b, &config),
+        IntervalDomain::mock(-100, 50).as_freshly_widened()
+    );
+    // Thresholds not representable in the byte size of the interval are ignored
+    let a = IntervalDomain::mock_i32(0, 3);
+    let b = IntervalDomain::mock_i32(5, 1500);
+    assert_eq!(
+        a.signed_merge_and_widen_with_config(&b, &config),
+        IntervalDomain::mock_i32(-100, 1500).as_freshly_widened()
+    );
+}
+
 #[test]
 fn cast_zero_and_signed_extend() {
     // Zero extend
@@ -672,3 +713,28 @@ fn lzcount_op() {
     let result = interval.cast(CastOpType::LzCount, ByteSize::new(8));
     assert_eq!(result, Interval::mock(0, 0).into());
 }
+
+#[test]
+fn bitwise_operations() {
+    use BinOpType::*;
+    let top = IntervalDomain::new_top(ByteSize::new(8));
+    assert_eq!(
+        top.bin_op(IntAnd, &IntervalDomain::mock(0xf, 0xf)),
+        IntervalDomain::mock(0, 15)
+    );
+    let aligned =
+        IntervalDomain::mock(0x100, 0x1ff).bin_op(IntAnd, &IntervalDomain::mock(-16, -16));
+    assert_eq!(
+        aligned,
+        IntervalDomain::from(Interval::new(bitvec!("0x100:8"), bitvec!("0x1f0:8"), 16))
+    );
+    assert_eq!(
+        IntervalDomain::mock(0, 100).bin_op(IntRight, &IntervalDomain::mock(2, 2)),
+        IntervalDomain::mock(0, 31)
+    );
+    assert_eq!(
+        IntervalDomain::mock(0x10, 0x13).bin_op(IntOr, &IntervalDomain::mock(4, 4)),
+        IntervalDomain::mock(0x14, 0x17)
+    );
+    assert!(top.bin_op(IntXOr, &IntervalDomain::mock(0xf, 0xf)).is_top());
+}
@@ -0,0 +1,76 @@
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+thread_local! {
+    /// The widening configuration used by interval merges on the current thread.
+    static WIDENING_CONFIG: RefCell<WideningConfig> = RefCell::new(WideningConfig::default());
+}
+
+/// Configurable parameters of the widening operator of the [`IntervalDomain`](super::IntervalDomain).
+///
+/// The default configuration corresponds to the widening strategy
+/// described in [`IntervalDomain::signed_merge_and_widen`](super::IntervalDomain::signed_merge_and_widen).
+/// Since the merge operation of abstract domains has no access to an analysis context,
+/// the configuration is set per thread for the duration of a computation with [`WideningConfig::scope`].
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct WideningConfig {
+    /// Intervals whose length does not exceed the delay are merged without widening.
+    #[serde(default)]
+    pub delay: u64,
+    /// Values that interval bounds are widened to if no widening hint is available for the bound.
+    /// The bound is widened to the nearest threshold instead of to the minimal or maximal value.
+    #[serde(default)]
+    pub thresholds: BTreeSet<i64>,
+    /// If set, the analyses add the constants compared against in the conditions of loops to the thresholds.
+    #[serde(default)]
+    pub loop_condition_thresholds: bool,
+}
+
+impl WideningConfig {
+    /// Run `computation` with `self` as the widening configuration of all interval merges on the current thread.
+    /// Afterwards the previous configuration is restored.
+    pub fn scope<T>(&self, computation: impl FnOnce() -> T) -> T {
+        let previous = WIDENING_CONFIG.with(|config| config.replace(self.clone()));
+        let result = computation();
+        WIDENING_CONFIG.with(|config| config.replace(previous));
+        result
+    }
+
+    /// Call `function` with the widening configuration of the current thread.
+    pub fn with_current<T>(function: impl FnOnce(&WideningConfig) -> T) -> T {
+        WIDENING_CONFIG.with(|config| function(&config.borrow()))
+    }
+
+    /// Get the smallest threshold greater than `bound` that is representable with the byte size of `bound`.
+    pub fn get_upper_threshold(&self, bound: &Bitvector) -> Option<Bitvector> {
+        let value = bound.try_to_i64().ok()?;
+        self.thresholds
+            .range(value.checked_add(1)?..)
+            .find_map(|threshold| threshold_to_bitvector(*threshold, bound.bytesize()))
+    }
+
+    /// Get the largest threshold smaller than `bound` that is representable with the byte size of `bound`.
+    pub fn get_lower_threshold(&self, bound: &Bitvector) -> Option<Bitvector> {
+        let value = bound.try_to_i64().ok()?;
+        self.thresholds
+            .range(..value)
+            .rev()
+            .find_map(|threshold| threshold_to_bitvector(*threshold, bound.bytesize()))
+    }
+}
+
+/// Convert the threshold to a bitvector of the given size
+/// if it is representable as a signed integer of this size.
+fn threshold_to_bitvector(threshold: i64, size: ByteSize) -> Option<Bitvector> {
+    let bit_length = size.as_bit_length();
+    if bit_length == 0 || bit_length > 64 {
+        return None;
+    }
+    let max = (1i128 << (bit_length - 1)) - 1;
+    let min = -(1i128 << (bit_length - 1));
+    (min..=max)
+        .contains(&i128::from(threshold))
+        .then(|| Bitvector::from_i64(threshold).into_resize_signed(size))
+}
@@ -0,0 +1,467 @@
+use super::Interval;
+use super::{AbstractDomain, HasTop, RegisterDomain, SizedDomain, TryToBitvec, TryToInterval};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use apint::BitWidth;
+
+/// The `KnownBitsDomain` is an abstract domain tracking which bits of a bitvector are known.
+///
+/// Each bit is either known to be zero, known to be one or unknown (a so called *tristate number*).
+/// The domain is precise for bitwise operations like masks and flag manipulations,
+/// where intervals lose all information.
+/// On the other hand it cannot represent ranges of values that do not share a common bit prefix.
+/// Thus the domain is mostly useful in combination with the [`IntervalDomain`](super::IntervalDomain),
+/// see [`KnownBitsDomain::from_interval`] and [`KnownBitsDomain::to_interval`] for the conversions between them.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct KnownBitsDomain {
+    /// The values of the known bits. Unknown bits are always set to zero.
+    value: Bitvector,
+    /// The unknown bits, i.e. a bit is set iff the value of the bit is unknown.
+    mask: Bitvector,
+}
+
+impl KnownBitsDomain {
+    /// Create a new value where the set bits of `mask` are unknown
+    /// and all other bits are given by `value`.
+    ///
+    /// Panics if `value` and `mask` have different widths.
+    pub fn new(value: Bitvector, mask: Bitvector) -> KnownBitsDomain {
+        assert_eq!(value.width(), mask.width());
+        KnownBitsDomain {
+            value: value & &mask.clone().into_bitnot(),
+            mask,
+        }
+    }
+
+    /// Get the values of the known bits. Unknown bits are set to zero.
+    pub fn value(&self) -> &Bitvector {
+        &self.value
+    }
+
+    /// Get the mask of the unknown bits.
+    pub fn unknown_bits(&self) -> &Bitvector {
+        &self.mask
+    }
+
+    /// Compute the known bits of all values contained in the given interval.
+    ///
+    /// All values of an interval not containing both negative and non-negative values
+    /// share the bits above the highest bit in which start and end of the interval differ.
+    /// Furthermore, if the stride of the interval is a multiple of `2^n`,
+    /// then the lowest `n` bits are the same for all values in the interval.
+    pub fn from_interval(interval: &Interval) -> KnownBitsDomain {
+        let width = interval.start.width();
+        let differing_bits = interval.start.clone() ^ &interval.end;
+        let mut mask = low_bits_mask(width, width.to_usize() - differing_bits.leading_zeros());
+        if interval.stride != 0 && !mask.is_zero() {
+            let equal_low_bits = interval.stride.trailing_zeros() as usize;
+            mask &= &low_bits_mask(width, equal_low_bits).into_bitnot();
+        }
+        KnownBitsDomain::new(interval.start.clone(), mask)
+    }
+
+    /// Compute the smallest interval of signed integers containing all values represented by `self`.
+    ///
+    /// The stride of the interval is determined by the number of known trailing bits.
+    pub fn to_interval(&self) -> Interval {
+        if self.mask.is_zero() {
+            return self.value.clone().into();
+        }
+        let width = self.value.width();
+        let sign_bit = Bitvector::signed_min_value(width);
+        let mut start = self.value.clone();
+        let mut end = self.value.clone() | &self.mask;
+        if !(self.mask.clone() & &sign_bit).is_zero() {
+            // The sign bit is unknown, so the smallest value has the sign bit set and the largest value not.
+            start |= &sign_bit;
+            end &= &sign_bit.into_bitnot();
+        }
+        let known_trailing_bits = self.mask.trailing_zeros();
+        let stride = if known_trailing_bits < 64 {
+            1u64 << known_trailing_bits
+        } else {
+            1
+        };
+        Interval::new(start, end, stride)
+    }
+
+    /// Return the number of bits that are known to be zero at the lower end of the represented values.
+    fn known_trailing_zeros(&self) -> usize {
+        (self.value.clone() | &self.mask).trailing_zeros()
+    }
+
+    /// Compute `self & rhs`.
+    fn and(&self, rhs: &Self) -> Self {
+        let lhs_possible_ones = self.value.clone() | &self.mask;
+        let rhs_possible_ones = rhs.value.clone() | &rhs.mask;
+        let value = self.value.clone() & &rhs.value;
+        let mask = lhs_possible_ones & &rhs_possible_ones & &value.clone().into_bitnot();
+        KnownBitsDomain { value, mask }
+    }
+
+    /// Compute `self | rhs`.
+    fn or(&self, rhs: &Self) -> Self {
+        let value = self.value.clone() | &rhs.value;
+        let mask = (self.mask.clone() | &rhs.mask) & &value.clone().into_bitnot();
+        KnownBitsDomain { value, mask }
+    }
+
+    /// Compute `self ^ rhs`.
+    fn xor(&self, rhs: &Self) -> Self {
+        let mask = self.mask.clone() | &rhs.mask;
+        let value = (self.value.clone() ^ &rhs.value) & &mask.clone().into_bitnot();
+        KnownBitsDomain { value, mask }
+    }
+
+    /// Compute `self + rhs`.
+    ///
+    /// Unknown bits may generate unknown carry bits,
+    /// so all bits that differ between the smallest and the largest possible sum are unknown.
+    fn add(&self, rhs: &Self) -> Self {
+        let mask_sum = self.mask.clone() + &rhs.mask;
+        let value_sum = self.value.clone() + &rhs.value;
+        let carries = (mask_sum + &value_sum) ^ &value_sum;
+        let mask = carries | &self.mask | &rhs.mask;
+        KnownBitsDomain::new(value_sum, mask)
+    }
+
+    /// Compute `self - rhs`.
+    fn sub(&self, rhs: &Self) -> Self {
+        let value_difference = self.value.clone() - &rhs.value;
+        let largest = value_difference.clone() + &self.mask;
+        let smallest = value_difference.clone() - &rhs.mask;
+        let borrows = largest ^ &smallest;
+        let mask = borrows | &self.mask | &rhs.mask;
+        KnownBitsDomain::new(value_difference, mask)
+    }
+
+    /// Compute `self * rhs`.
+    ///
+    /// Only the trailing zeros of the factors are tracked if one of the factors is not exactly known.
+    fn mult(&self, rhs: &Self) -> Self {
+        let width = self.value.width();
+        let trailing_zeros = std::cmp::min(
+            self.known_trailing_zeros() + rhs.known_trailing_zeros(),
+            width.to_usize(),
+        );
+        KnownBitsDomain {
+            value: Bitvector::zero(width),
+            mask: low_bits_mask(width, trailing_zeros).into_bitnot(),
+        }
+    }
+
+    /// Shift `self` by a known amount of bits.
+    fn shift(&self, op: BinOpType, shift_amount: &Bitvector) -> Self {
+        // Shift operations on bitvectors cannot fail.
+        KnownBitsDomain {
+            value: self.value.bin_op(op, shift_amount).unwrap(),
+            mask: self.mask.bin_op(op, shift_amount).unwrap(),
+        }
+    }
+}
+
+/// Return a bitvector of the given width where exactly the lowest `count` bits are set.
+fn low_bits_mask(width: BitWidth, count: usize) -> Bitvector {
+    if count == 0 {
+        Bitvector::zero(width)
+    } else if count >= width.to_usize() {
+        Bitvector::all_set(width)
+    } else {
+        Bitvector::all_set(width)
+            .into_checked_lshr(width.to_usize() - count)
+            .unwrap()
+    }
+}
+
+impl AbstractDomain for KnownBitsDomain {
+    /// Merge two values. A bit of the result is only known if it is known and equal in both values.
+    fn merge(&self, other: &Self) -> Self {
+        let mask = self.mask.clone() | &other.mask | &(self.value.clone() ^ &other.value);
+        KnownBitsDomain::new(self.value.clone(), mask)
+    }
+
+    /// Check if no bit of the value is known.
+    fn is_top(&self) -> bool {
+        self.mask.is_all_set()
+    }
+}
+
+impl HasTop for KnownBitsDomain {
+    /// Return a *Top* value with the same bytesize as `self`.
+    fn top(&self) -> KnownBitsDomain {
+        KnownBitsDomain::new_top(self.bytesize())
+    }
+}
+
+impl SizedDomain for KnownBitsDomain {
+    /// Return the bytesize of `self`.
+    fn bytesize(&self) -> ByteSize {
+        self.value.width().into()
+    }
+
+    /// Get a *Top* element with the given bytesize.
+    fn new_top(bytesize: ByteSize) -> KnownBitsDomain {
+        KnownBitsDomain {
+            value: Bitvector::zero(bytesize.into()),
+            mask: Bitvector::all_set(bytesize.into()),
+        }
+    }
+}
+
+impl RegisterDomain for KnownBitsDomain {
+    /// Compute the result of a binary operation.
+    ///
+    /// If both operands are exactly known, the result is computed exactly.
+    /// Bitwise operations, additions, subtractions and shifts by known amounts
+    /// also track the known bits of inexact operands.
+    /// For all other operations the result is *Top*.
+    fn bin_op(&self, op: BinOpType, rhs: &Self) -> Self {
+        use BinOpType::*;
+        if let (Ok(lhs_bitvec), Ok(rhs_bitvec)) = (self.try_to_bitvec(), rhs.try_to_bitvec()) {
+            if let Ok(result) = lhs_bitvec.bin_op(op, &rhs_bitvec) {
+                return result.into();
+            }
+        }
+        match op {
+            IntAnd => self.and(rhs),
+            IntOr => self.or(rhs),
+            IntXOr => self.xor(rhs),
+            IntAdd => self.add(rhs),
+            IntSub => self.sub(rhs),
+            IntMult => self.mult(rhs),
+            IntLeft | IntRight | IntSRight => match rhs.try_to_bitvec() {
+                Ok(shift_amount) => self.shift(op, &shift_amount),
+                Err(_) => Self::new_top(self.bytesize()),
+            },
+            Piece => KnownBitsDomain {
+                value: self.value.bin_op(Piece, &rhs.value).unwrap(),
+                mask: self.mask.bin_op(Piece, &rhs.mask).unwrap(),
+            },
+            IntEqual | IntNotEqual | IntLess | IntLessEqual | IntSLess | IntSLessEqual
+            | IntCarry | IntSCarry | IntSBorrow | IntDiv | IntSDiv | IntRem | IntSRem | BoolAnd
+            | BoolOr | BoolXOr | FloatEqual | FloatNotEqual | FloatLess | FloatLessEqual
+            | FloatAdd | FloatSub | FloatMult | FloatDiv => {
+                Self::new_top(self.bin_op_bytesize(op, rhs))
+            }
+        }
+    }
+
+    /// Compute the result of a unary operation.
+    fn un_op(&self, op: UnOpType) -> Self {
+        use UnOpType::*;
+        if let Ok(bitvec) = self.try_to_bitvec() {
+            if let Ok(result) = bitvec.un_op(op) {
+                return result.into();
+            }
+        }
+        match op {
+            IntNegate => KnownBitsDomain::new(
+                (self.value.clone() | &self.mask).into_bitnot(),
+                self.mask.clone(),
+            ),
+            Int2Comp => KnownBitsDomain::from(Bitvector::zero(self.value.width())).sub(self),
+            BoolNegate | FloatNaN => Self::new_top(ByteSize::new(1)),
+            FloatNegate | FloatAbs | FloatSqrt | FloatCeil | FloatFloor | FloatRound => {
+                Self::new_top(self.bytesize())
+            }
+        }
+    }
+
+    /// Extract a sub-bitvector.
+    fn subpiece(&self, low_byte: ByteSize, size: ByteSize) -> Self {
+        KnownBitsDomain {
+            value: self.value.subpiece(low_byte, size),
+            mask: self.mask.subpiece(low_byte, size),
+        }
+    }
+
+    /// Perform a size-changing cast.
+    ///
+    /// Zero extensions and sign extensions preserve the known bits.
+    /// All other casts return *Top* if the value is not exactly known.
+    fn cast(&self, kind: CastOpType, width: ByteSize) -> Self {
+        if let Ok(bitvec) = self.try_to_bitvec() {
+            if let Ok(result) = bitvec.cast(kind, width) {
+                return result.into();
+            }
+        }
+        match kind {
+            CastOpType::IntZExt | CastOpType::IntSExt => KnownBitsDomain {
+                // Extending the mask with the cast itself marks the new bits as unknown
+                // if and only if the sign bit is unknown in the case of sign extensions.
+                value: self.value.cast(kind, width).unwrap(),
+                mask: self.mask.cast(kind, width).unwrap(),
+            },
+            CastOpType::Trunc => self.subpiece(ByteSize::new(0), width),
+            CastOpType::PopCount
+            | CastOpType::LzCount
+            | CastOpType::Int2Float
+            | CastOpType::Float2Float => Self::new_top(width),
+        }
+    }
+}
+
+impl From<Bitvector> for KnownBitsDomain {
+    /// Create a value where all bits are known.
+    fn from(bitvector: Bitvector) -> KnownBitsDomain {
+        let mask = Bitvector::zero(bitvector.width());
+        KnownBitsDomain {
+            value: bitvector,
+            mask,
+        }
+    }
+}
+
+impl TryToBitvec for KnownBitsDomain {
+    /// If all bits are known, return the represented value.
+    fn try_to_bitvec(&self) -> Result<Bitvector, Error> {
+        if self.mask.is_zero() {
+            Ok(self.value.clone())
+        } else {
+            Err(anyhow!("Not all bits are known"))
+        }
+    }
+}
+
+impl TryToInterval for KnownBitsDomain {
+    /// Return the smallest interval containing all represented values.
+    /// Returns an error if no bit is known.
+    fn try_to_interval(&self) -> Result<Interval, Error> {
+        if self.is_top() {
+            Err(anyhow!("Value is Top"))
+        } else {
+            Ok(self.to_interval())
+        }
+    }
+}
+
+impl std::fmt::Display for KnownBitsDomain {
+    /// Print the value as a hexadecimal number
+    /// where nibbles containing unknown bits are replaced by an `x`.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let width = self.value.width().to_usize();
+        let mut digits = String::new();
+        for nibble in (0..width.div_ceil(4)).rev() {
+            let low_byte = ByteSize::new((nibble / 2) as u64);
+            let byte_value = self.value.subpiece(low_byte, ByteSize::new(1)).try_to_u8();
+            let byte_mask = self.mask.subpiece(low_byte, ByteSize::new(1)).try_to_u8();
+            let shift = 4 * (nibble % 2);
+            match (byte_value, byte_mask) {
+                (Ok(value), Ok(mask)) if (mask >> shift) & 0xf == 0 => {
+                    digits.push_str(&format!("{:x}", (value >> shift) & 0xf))
+                }
+                _ => digits.push('x'),
+            }
+        }
+        write!(formatter, "0x{digits}:u{width}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitvec;
+
+    fn known(value: &str, mask: &str) -> KnownBitsDomain {
+        KnownBitsDomain::new(bitvec!(value), bitvec!(mask))
+    }
+
+    fn bv(value: i64) -> KnownBitsDomain {
+        bitvec!(format!("{}:8", value)).into()
+    }
+
+    #[test]
+    fn bitwise_operations() {
+        use BinOpType::*;
+        let unknown_low_byte = known("0x1000:8", "0xff:8");
+        assert_eq!(
+            unknown_low_byte.bin_op(IntAnd, &bv(!0xf)),
+            known("0x1000:8", "0xf0:8")
+        );
+        assert_eq!(
+            unknown_low_byte.bin_op(IntOr, &bv(0xf)),
+            known("0x100f:8", "0xf0:8")
+        );
+        assert_eq!(
+            unknown_low_byte.bin_op(IntXOr, &bv(0x1100)),
+            known("0x100:8", "0xff:8")
+        );
+        assert_eq!(
+            unknown_low_byte.bin_op(IntLeft, &bitvec!("4:1").into()),
+            known("0x10000:8", "0xff0:8")
+        );
+        assert_eq!(
+            unknown_low_byte.bin_op(IntRight, &bitvec!("4:1").into()),
+            known("0x100:8", "0xf:8")
+        );
+        assert_eq!(bv(3).bin_op(IntMult, &bv(5)), bv(15));
+        assert_eq!(
+            known("0:8", "0xf0:8").bin_op(IntMult, &known("0:8", "0xf8:8")),
+            known("0:8", "0xffffffffffffff80:8")
+        );
+        assert!(unknown_low_byte.bin_op(IntDiv, &bv(2)).is_top());
+    }
+
+    #[test]
+    fn arithmetic_operations() {
+        use BinOpType::*;
+        use UnOpType::*;
+        let aligned = known("0x1000:8", "0xf0:8");
+        assert_eq!(aligned.bin_op(IntAdd, &bv(4)), known("0x1004:8", "0xf0:8"));
+        assert_eq!(
+            aligned.bin_op(IntAdd, &bv(0x10)),
+            known("0x1000:8", "0x1f0:8")
+        );
+        assert_eq!(aligned.bin_op(IntSub, &bv(4)), known("0xffc:8", "0x1ff0:8"));
+        assert_eq!(
+            aligned.un_op(IntNegate),
+            known("0xffffffffffffef0f:8", "0xf0:8")
+        );
+        assert_eq!(bv(3).un_op(Int2Comp), bv(-3));
+    }
+
+    #[test]
+    fn merge_and_casts() {
+        use CastOpType::*;
+        assert_eq!(bv(0x10).merge(&bv(0x30)), known("0x10:8", "0x20:8"));
+        assert!(KnownBitsDomain::new_top(ByteSize::new(8)).is_top());
+        let low_byte = known("0:1", "0x7f:1");
+        assert_eq!(
+            low_byte.cast(IntZExt, ByteSize::new(4)),
+            known("0:4", "0x7f:4")
+        );
+        assert_eq!(
+            low_byte.cast(IntSExt, ByteSize::new(4)),
+            known("0:4", "0x7f:4")
+        );
+        assert_eq!(
+            known("0:1", "0xff:1").cast(IntSExt, ByteSize::new(4)),
+            known("0:4", "0xffffffff:4")
+        );
+        assert_eq!(
+            known("0x1234:8", "0xf0:8").subpiece(ByteSize::new(0), ByteSize::new(1)),
+            known("0x4:1", "0xf0:1")
+        );
+    }
+
+    #[test]
+    fn interval_conversion() {
+        let interval = Interval::new(bitvec!("0x100:8"), bitvec!("0x1f0:8"), 0x10);
+        let known_bits = KnownBitsDomain::from_interval(&interval);
+        assert_eq!(known_bits, known("0x100:8", "0xf0:8"));
+        assert_eq!(known_bits.to_interval(), interval);
+
+        let interval = Interval::new(bitvec!("-1:8"), bitvec!("1:8"), 1);
+        assert!(KnownBitsDomain::from_interval(&interval).is_top());
+
+        assert_eq!(
+            known("0:4", "0xf:4").to_interval(),
+            Interval::new(bitvec!("0:4"), bitvec!("15:4"), 1)
+        );
+        assert_eq!(
+            known("0x8:4", "0xfffffff0:4").to_interval(),
+            Interval::new(bitvec!("0x80000008:4"), bitvec!("0x7ffffff8:4"), 16)
+        );
+        assert_eq!(format!("{}", known("0x1200:4", "0xf0:4")), "0x000012x0:u32");
+    }
+}
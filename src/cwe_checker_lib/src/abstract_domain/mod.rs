@@ -17,11 +17,17 @@ mod mem_region;
 pub use mem_region::MemRegion;
 
 mod interval;
-pub use interval::{Interval, IntervalDomain};
+pub use interval::{Interval, IntervalDomain, WideningConfig};
+
+mod known_bits;
+pub use known_bits::KnownBitsDomain;
 
 mod bricks;
 pub use bricks::{BrickDomain, BricksDomain};
 
+mod automaton;
+pub use automaton::AutomatonDomain;
+
 mod character_inclusion;
 pub use character_inclusion::{CharacterInclusionDomain, CharacterSet};
 
@@ -0,0 +1,629 @@
+//! Recognition of custom heap allocators.
+//!
+//! Many firmware and RTOS code bases never call `malloc` or `free` directly.
+//! Instead they use custom allocation functions, e.g. wrappers around `sbrk` or `mmap` or pool allocators.
+//! Since the pointer inference and the heap-related checks only know allocation and deallocation functions by name,
+//! they cannot track heap objects in such programs.
+//!
+//! This module recognizes custom allocation and deallocation functions of the binary by their behavior.
+//! The recognized functions are then replaced by extern symbols
+//! and added to the allocation and deallocation symbols of the analysis configuration,
+//! so that all analyses treat them like `malloc` and `free`.
+//!
+//! ## Behavioral fingerprints
+//!
+//! A function is recognized as an allocation function if
+//! - it returns the return value of a call to an allocation function (possibly plus an offset), or
+//! - it removes the first element from a free list stored in global memory and returns it,
+//!   i.e. it loads the head of the list from a global variable,
+//!   stores the next pointer of the head back to the global variable and returns the head.
+//!
+//! A function is recognized as a deallocation function if
+//! - it passes its first parameter (possibly plus an offset) to a deallocation function, or
+//! - it inserts its first parameter as the new first element of a free list stored in global memory,
+//!   i.e. it stores the old head of the list into the memory pointed to by the parameter
+//!   and stores the parameter to the global variable.
+//!
+//! Wrappers around recognized functions are also recognized.
+//! Values are only tracked in registers of the standard calling convention.
+//!
+//! Functions that manipulate global linked lists of other objects may be misclassified as pool allocators.
+//! Such functions can be excluded by adding them to the `ignored_functions` in the configuration.
+//! Conversely, allocation functions not recognized by their behavior can be added manually.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The configuration of the allocator recognition.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Names of extern functions that allocate memory.
+    pub allocation_symbols: Vec<String>,
+    /// Names of extern functions that deallocate memory.
+    pub deallocation_symbols: Vec<String>,
+    /// Names of functions of the binary that should be treated as allocation functions.
+    #[serde(default)]
+    pub custom_allocation_functions: Vec<String>,
+    /// Names of functions of the binary that should be treated as deallocation functions.
+    #[serde(default)]
+    pub custom_deallocation_functions: Vec<String>,
+    /// Names of functions of the binary that should never be recognized as allocation or deallocation functions.
+    #[serde(default)]
+    pub ignored_functions: Vec<String>,
+    /// If set, functions are recognized by their behavior.
+    /// Otherwise only the custom functions given in the configuration are used.
+    pub recognize_by_behavior: bool,
+}
+
+/// The origin of a value held in a register.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Origin {
+    /// The first parameter of the function.
+    Parameter,
+    /// The return value of a call to an allocation function.
+    Allocation,
+    /// The head of a free list stored at the given global address.
+    FreeListHead(u64),
+    /// The next pointer of the head of a free list stored at the given global address.
+    FreeListNext(u64),
+}
+
+/// The behavior of a function regarding allocations.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct Fingerprint {
+    /// The function returns the return value of an allocation function.
+    returns_allocation: bool,
+    /// The function returns the heads of free lists stored at these global addresses.
+    returned_list_heads: BTreeSet<u64>,
+    /// The function stores the next pointers of the heads of free lists to these global addresses.
+    removed_list_heads: BTreeSet<u64>,
+    /// The function passes its first parameter to a deallocation function.
+    frees_parameter: bool,
+    /// The function stores its first parameter to these global addresses.
+    parameter_stored_as_list_head: BTreeSet<u64>,
+    /// The function stores the heads of free lists at these global addresses into the memory pointed to by its first parameter.
+    list_head_stored_in_parameter: BTreeSet<u64>,
+}
+
+impl Fingerprint {
+    /// Check whether the behavior matches an allocation function.
+    fn is_allocation_function(&self) -> bool {
+        self.returns_allocation
+            || self
+                .returned_list_heads
+                .intersection(&self.removed_list_heads)
+                .next()
+                .is_some()
+    }
+
+    /// Check whether the behavior matches a deallocation function.
+    fn is_deallocation_function(&self) -> bool {
+        self.frees_parameter
+            || self
+                .parameter_stored_as_list_head
+                .intersection(&self.list_head_stored_in_parameter)
+                .next()
+                .is_some()
+    }
+}
+
+/// The custom allocation and deallocation functions of a binary.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Allocators {
+    /// The allocation functions, mapped to their names.
+    pub allocation_functions: BTreeMap<Tid, String>,
+    /// The deallocation functions, mapped to their names.
+    pub deallocation_functions: BTreeMap<Tid, String>,
+}
+
+impl Allocators {
+    /// Returns `true` if no custom allocation or deallocation functions were found.
+    pub fn is_empty(&self) -> bool {
+        self.allocation_functions.is_empty() && self.deallocation_functions.is_empty()
+    }
+
+    /// Replace the custom allocation and deallocation functions in the program with extern symbols of the same name.
+    ///
+    /// The extern symbols take their first parameter and return their result
+    /// in the registers of the standard calling convention.
+    /// Calls to the functions are not changed, since the extern symbols keep the TIDs of the functions.
+    pub fn replace_functions_with_extern_symbols(&self, project: &mut Project) {
+        let Some(calling_convention) = project.get_standard_calling_convention().cloned() else {
+            return;
+        };
+        let functions = self
+            .allocation_functions
+            .iter()
+            .chain(self.deallocation_functions.iter());
+        for (tid, name) in functions {
+            let program = &mut project.program.term;
+            if program.subs.remove(tid).is_none() {
+                continue;
+            }
+            program.entry_points.remove(tid);
+            let symbol = ExternSymbol {
+                tid: tid.clone(),
+                addresses: vec![tid.address.clone()],
+                name: name.clone(),
+                calling_convention: Some(calling_convention.name.clone()),
+                parameters: calling_convention
+                    .integer_parameter_register
+                    .iter()
+                    .take(1)
+                    .map(|register| Arg::from_var(register.clone(), None))
+                    .collect(),
+                return_values: calling_convention
+                    .integer_return_register
+                    .iter()
+                    .take(1)
+                    .map(|register| Arg::from_var(register.clone(), None))
+                    .collect(),
+                no_return: false,
+                has_var_args: false,
+            };
+            program.extern_symbols.insert(tid.clone(), symbol);
+        }
+    }
+
+    /// Add the names of the custom allocation and deallocation functions
+    /// to the `allocation_symbols` and `deallocation_symbols` lists of all sections of the analysis configuration.
+    pub fn add_to_config(&self, config: &mut serde_json::Value) {
+        let Some(sections) = config.as_object_mut() else {
+            return;
+        };
+        for section in sections.values_mut() {
+            for (key, functions) in [
+                ("allocation_symbols", &self.allocation_functions),
+                ("deallocation_symbols", &self.deallocation_functions),
+            ] {
+                if let Some(symbols) = section.get_mut(key).and_then(|list| list.as_array_mut()) {
+                    symbols.extend(
+                        functions
+                            .values()
+                            .map(|name| serde_json::Value::String(name.clone())),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Generate log messages listing the custom allocation and deallocation functions.
+    pub fn get_log_messages(&self) -> Vec<LogMessage> {
+        let allocations = self
+            .allocation_functions
+            .iter()
+            .map(|(tid, name)| (tid, name, "allocation"));
+        let deallocations = self
+            .deallocation_functions
+            .iter()
+            .map(|(tid, name)| (tid, name, "deallocation"));
+        allocations
+            .chain(deallocations)
+            .map(|(tid, name, kind)| {
+                LogMessage::new_info(format!("Treating {name} as custom {kind} function"))
+                    .location(tid.clone())
+                    .source("Allocators")
+            })
+            .collect()
+    }
+}
+
+/// Recognize the custom allocation and deallocation functions of the project.
+///
+/// The recognition is repeated until no new functions are found,
+/// so that wrappers around recognized functions are also recognized.
+pub fn recognize_allocators(project: &Project, config: &Config) -> Allocators {
+    let program = &project.program.term;
+    let mut allocation_tids: BTreeSet<Tid> = program
+        .extern_symbols
+        .values()
+        .filter(|symbol| config.allocation_symbols.contains(&symbol.name))
+        .map(|symbol| symbol.tid.clone())
+        .collect();
+    let mut deallocation_tids: BTreeSet<Tid> = program
+        .extern_symbols
+        .values()
+        .filter(|symbol| config.deallocation_symbols.contains(&symbol.name))
+        .map(|symbol| symbol.tid.clone())
+        .collect();
+
+    let mut allocators = Allocators::default();
+    for sub in program.subs.values() {
+        if config.custom_allocation_functions.contains(&sub.term.name) {
+            allocators
+                .allocation_functions
+                .insert(sub.tid.clone(), sub.term.name.clone());
+            allocation_tids.insert(sub.tid.clone());
+        }
+        if config
+            .custom_deallocation_functions
+            .contains(&sub.term.name)
+        {
+            allocators
+                .deallocation_functions
+                .insert(sub.tid.clone(), sub.term.name.clone());
+            deallocation_tids.insert(sub.tid.clone());
+        }
+    }
+    let Some(calling_convention) = project.get_standard_calling_convention() else {
+        return allocators;
+    };
+    if !config.recognize_by_behavior {
+        return allocators;
+    }
+
+    let candidates: Vec<&Term<Sub>> = program
+        .subs
+        .values()
+        .filter(|sub| {
+            !sub.term.blocks.is_empty()
+                && sub.term.name != "main"
+                && !program.entry_points.contains(&sub.tid)
+                && !config.ignored_functions.contains(&sub.term.name)
+        })
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for sub in candidates.iter() {
+            if allocation_tids.contains(&sub.tid) || deallocation_tids.contains(&sub.tid) {
+                continue;
+            }
+            let fingerprint = compute_fingerprint(
+                project,
+                calling_convention,
+                sub,
+                &allocation_tids,
+                &deallocation_tids,
+            );
+            if fingerprint.is_allocation_function() {
+                allocators
+                    .allocation_functions
+                    .insert(sub.tid.clone(), sub.term.name.clone());
+                allocation_tids.insert(sub.tid.clone());
+                changed = true;
+            }
+            if fingerprint.is_deallocation_function() {
+                allocators
+                    .deallocation_functions
+                    .insert(sub.tid.clone(), sub.term.name.clone());
+                deallocation_tids.insert(sub.tid.clone());
+                changed = true;
+            }
+        }
+    }
+    allocators
+}
+
+/// If the expression is a constant address in global memory, return the address.
+fn as_global_address(project: &Project, expression: &Expression) -> Option<u64> {
+    match expression {
+        Expression::Const(constant)
+            if project
+                .runtime_memory_image
+                .is_global_memory_address(constant) =>
+        {
+            constant.try_to_u64().ok()
+        }
+        _ => None,
+    }
+}
+
+/// Get the origin of the value of the expression.
+///
+/// Adding or subtracting constant offsets does not change the origin of a value.
+fn get_origin(expression: &Expression, state: &HashMap<Variable, Origin>) -> Option<Origin> {
+    match expression {
+        Expression::Var(var) => state.get(var).copied(),
+        Expression::BinOp {
+            op: BinOpType::IntAdd | BinOpType::IntSub,
+            lhs,
+            rhs,
+        } => match (lhs.as_ref(), rhs.as_ref()) {
+            (Expression::Const(_), operand) | (operand, Expression::Const(_)) => {
+                get_origin(operand, state)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Compute the behavior of the function regarding allocations.
+///
+/// The origins of register values are propagated along the control flow of the function.
+/// If a register may have different origins at the start of a block, only one of them is tracked.
+fn compute_fingerprint(
+    project: &Project,
+    calling_convention: &CallingConvention,
+    sub: &Term<Sub>,
+    allocation_tids: &BTreeSet<Tid>,
+    deallocation_tids: &BTreeSet<Tid>,
+) -> Fingerprint {
+    let mut fingerprint = Fingerprint::default();
+    let (Some(parameter_register), Some(return_register)) = (
+        calling_convention.integer_parameter_register.first(),
+        calling_convention.integer_return_register.first(),
+    ) else {
+        return fingerprint;
+    };
+    let blocks: HashMap<&Tid, &Term<Blk>> =
+        sub.term.blocks.iter().map(|blk| (&blk.tid, blk)).collect();
+    let mut block_states: HashMap<&Tid, HashMap<Variable, Origin>> = HashMap::new();
+    let mut worklist: Vec<(&Tid, HashMap<Variable, Origin>)> = vec![(
+        &sub.term.blocks[0].tid,
+        HashMap::from([(parameter_register.clone(), Origin::Parameter)]),
+    )];
+    while let Some((blk_tid, incoming_state)) = worklist.pop() {
+        let Some(blk) = blocks.get(blk_tid) else {
+            continue;
+        };
+        let is_first_visit = !block_states.contains_key(blk_tid);
+        let block_state = block_states.entry(blk_tid).or_default();
+        let old_len = block_state.len();
+        for (var, origin) in incoming_state {
+            block_state.entry(var).or_insert(origin);
+        }
+        if !is_first_visit && block_state.len() == old_len {
+            continue;
+        }
+        let mut state = block_state.clone();
+        for def in blk.term.defs.iter() {
+            match &def.term {
+                Def::Assign { var, value } => match get_origin(value, &state) {
+                    Some(origin) => state.insert(var.clone(), origin),
+                    None => state.remove(var),
+                },
+                Def::Load { var, address } => {
+                    let origin = match (
+                        as_global_address(project, address),
+                        get_origin(address, &state),
+                    ) {
+                        (Some(global), _) => Some(Origin::FreeListHead(global)),
+                        (None, Some(Origin::FreeListHead(global))) => {
+                            Some(Origin::FreeListNext(global))
+                        }
+                        _ => None,
+                    };
+                    match origin {
+                        Some(origin) => state.insert(var.clone(), origin),
+                        None => state.remove(var),
+                    }
+                }
+                Def::Store { address, value } => {
+                    let value_origin = get_origin(value, &state);
+                    match (as_global_address(project, address), value_origin) {
+                        (Some(global), Some(Origin::FreeListNext(list))) if global == list => {
+                            fingerprint.removed_list_heads.insert(global);
+                        }
+                        (Some(global), Some(Origin::Parameter)) => {
+                            fingerprint.parameter_stored_as_list_head.insert(global);
+                        }
+                        (None, Some(Origin::FreeListHead(global)))
+                            if get_origin(address, &state) == Some(Origin::Parameter) =>
+                        {
+                            fingerprint.list_head_stored_in_parameter.insert(global);
+                        }
+                        _ => (),
+                    }
+                    None
+                }
+            };
+        }
+        for jmp in blk.term.jmps.iter() {
+            let successor = match &jmp.term {
+                Jmp::Branch(target) | Jmp::CBranch { target, .. } => Some(target),
+                Jmp::Call { target, return_ } => {
+                    if deallocation_tids.contains(target)
+                        && state.get(parameter_register) == Some(&Origin::Parameter)
+                    {
+                        fingerprint.frees_parameter = true;
+                    }
+                    let mut return_state = state.clone();
+                    return_state
+                        .retain(|var, _| calling_convention.callee_saved_register.contains(var));
+                    if allocation_tids.contains(target) {
+                        return_state.insert(return_register.clone(), Origin::Allocation);
+                    }
+                    if let Some(return_) = return_ {
+                        worklist.push((return_, return_state));
+                    }
+                    continue;
+                }
+                Jmp::CallInd { return_, .. } => {
+                    let mut return_state = state.clone();
+                    return_state
+                        .retain(|var, _| calling_convention.callee_saved_register.contains(var));
+                    if let Some(return_) = return_ {
+                        worklist.push((return_, return_state));
+                    }
+                    continue;
+                }
+                Jmp::CallOther { return_, .. } => return_.as_ref(),
+                Jmp::Return(_) => {
+                    match state.get(return_register) {
+                        Some(Origin::Allocation) => fingerprint.returns_allocation = true,
+                        Some(Origin::FreeListHead(global)) => {
+                            fingerprint.returned_list_heads.insert(*global);
+                        }
+                        _ => (),
+                    }
+                    None
+                }
+                Jmp::BranchInd(_) => None,
+            };
+            if let Some(successor) = successor {
+                worklist.push((successor, state.clone()));
+            }
+        }
+    }
+    fingerprint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{def, expr};
+
+    fn mock_config() -> Config {
+        Config {
+            allocation_symbols: vec!["malloc".to_string()],
+            deallocation_symbols: vec!["free".to_string()],
+            custom_allocation_functions: Vec::new(),
+            custom_deallocation_functions: Vec::new(),
+            ignored_functions: Vec::new(),
+            recognize_by_behavior: true,
+        }
+    }
+
+    /// Create a function consisting of one block with the given defs and jumps.
+    fn mock_sub(name: &str, defs: Vec<Term<Def>>, jmps: Vec<Term<Jmp>>) -> Term<Sub> {
+        let mut blk = Blk::mock_with_tid(&format!("{name}_blk"));
+        blk.term.defs = defs;
+        blk.term.jmps = jmps;
+        let mut sub = Sub::mock(name);
+        sub.term.blocks = vec![blk];
+        sub
+    }
+
+    fn mock_return(name: &str) -> Term<Jmp> {
+        Term {
+            tid: Tid::new(format!("{name}_return")),
+            term: Jmp::Return(expr!("0x0:8")),
+        }
+    }
+
+    /// Create a function whose block calls the target and returns in a second block.
+    fn mock_wrapper(name: &str, target: &str, defs: Vec<Term<Def>>) -> Term<Sub> {
+        let mut sub = mock_sub(
+            name,
+            Vec::new(),
+            vec![Jmp::call(
+                &format!("{name}_call"),
+                target,
+                Some(&format!("{name}_return_blk")),
+            )],
+        );
+        let mut return_blk = Blk::mock_with_tid(&format!("{name}_return_blk"));
+        return_blk.term.defs = defs;
+        return_blk.term.jmps = vec![mock_return(name)];
+        sub.term.blocks.push(return_blk);
+        sub
+    }
+
+    fn mock_project(subs: Vec<Term<Sub>>) -> Project {
+        let mut project = Project::mock_x64();
+        for name in ["malloc", "free"] {
+            let symbol = ExternSymbol::mock_x64(name);
+            project
+                .program
+                .term
+                .extern_symbols
+                .insert(symbol.tid.clone(), symbol);
+        }
+        for sub in subs {
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        project
+    }
+
+    #[test]
+    fn allocation_wrappers() {
+        let project = mock_project(vec![
+            mock_wrapper("xalloc", "malloc", vec![def!["RAX:8 = RAX:8 + 0x10:8"]]),
+            mock_wrapper("alloc_node", "xalloc", Vec::new()),
+            mock_wrapper("discard", "malloc", vec![def!["RAX:8 = 0x0:8"]]),
+            mock_wrapper("xfree", "free", Vec::new()),
+        ]);
+        let allocators = recognize_allocators(&project, &mock_config());
+        assert_eq!(
+            allocators.allocation_functions,
+            BTreeMap::from([
+                (Tid::new("alloc_node"), "alloc_node".to_string()),
+                (Tid::new("xalloc"), "xalloc".to_string()),
+            ])
+        );
+        assert_eq!(
+            allocators.deallocation_functions,
+            BTreeMap::from([(Tid::new("xfree"), "xfree".to_string())])
+        );
+    }
+
+    #[test]
+    fn pool_allocator() {
+        let project = mock_project(vec![
+            mock_sub(
+                "pool_alloc",
+                vec![
+                    def!["RAX:8 := Load from 0x3000:8"],
+                    def!["RCX:8 := Load from RAX:8"],
+                    def!["Store at 0x3000:8 := RCX:8"],
+                ],
+                vec![mock_return("pool_alloc")],
+            ),
+            mock_sub(
+                "pool_free",
+                vec![
+                    def!["RAX:8 := Load from 0x3000:8"],
+                    def!["Store at RDI:8 := RAX:8"],
+                    def!["Store at 0x3000:8 := RDI:8"],
+                ],
+                vec![mock_return("pool_free")],
+            ),
+            mock_sub(
+                "get_head",
+                vec![def!["RAX:8 := Load from 0x3000:8"]],
+                vec![mock_return("get_head")],
+            ),
+        ]);
+        let allocators = recognize_allocators(&project, &mock_config());
+        assert_eq!(
+            allocators.allocation_functions.values().collect::<Vec<_>>(),
+            vec!["pool_alloc"]
+        );
+        assert_eq!(
+            allocators
+                .deallocation_functions
+                .values()
+                .collect::<Vec<_>>(),
+            vec!["pool_free"]
+        );
+
+        let mut config = mock_config();
+        config.recognize_by_behavior = false;
+        config.custom_allocation_functions = vec!["get_head".to_string()];
+        let allocators = recognize_allocators(&project, &config);
+        assert_eq!(
+            allocators.allocation_functions.values().collect::<Vec<_>>(),
+            vec!["get_head"]
+        );
+        assert!(allocators.deallocation_functions.is_empty());
+    }
+
+    #[test]
+    fn replace_with_extern_symbols() {
+        let mut project = mock_project(vec![mock_wrapper("xalloc", "malloc", Vec::new())]);
+        let allocators = recognize_allocators(&project, &mock_config());
+        allocators.replace_functions_with_extern_symbols(&mut project);
+        assert!(project.program.term.subs.is_empty());
+        let symbol = &project.program.term.extern_symbols[&Tid::new("xalloc")];
+        assert_eq!(symbol.name, "xalloc");
+        assert_eq!(symbol.get_unique_return_register().unwrap().name, "RAX");
+
+        let mut config = serde_json::json!({
+            "Memory": {"allocation_symbols": ["malloc"]},
+            "CWE416": {"deallocation_symbols": ["free"]},
+        });
+        allocators.add_to_config(&mut config);
+        assert_eq!(
+            config["Memory"]["allocation_symbols"],
+            serde_json::json!(["malloc", "xalloc"])
+        );
+        assert_eq!(
+            config["CWE416"]["deallocation_symbols"],
+            serde_json::json!(["free"])
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Demand-driven queries for backward interprocedural fixpoint problems.
+
+use super::{create_computation, Context, GeneralizedContext};
+use crate::analysis::fixpoint::Computation;
+use crate::analysis::graph::Graph;
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use fnv::FnvHashMap;
+use petgraph::graph::NodeIndex;
+use std::collections::HashMap;
+
+/// The result of a demand-driven query.
+///
+/// Only nodes reached by the backward propagation from the starting node of the query have values.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct QueryResult<V: PartialEq + Eq + Clone> {
+    /// The values of all reached nodes.
+    node_values: FnvHashMap<NodeIndex, NodeValue<V>>,
+    /// Whether the computation of the query stabilized.
+    stabilized: bool,
+}
+
+impl<V: PartialEq + Eq + Clone> QueryResult<V> {
+    /// Get the value of the given node if the node was reached by the query.
+    ///
+    /// Returns `None` for the artificial combinator nodes of calls.
+    pub fn get_value(&self, node: NodeIndex) -> Option<&V> {
+        match self.node_values.get(&node)? {
+            NodeValue::Value(value) => Some(value),
+            NodeValue::CallFlowCombinator { .. } => None,
+        }
+    }
+
+    /// Get the values of all nodes reached by the query.
+    pub fn node_values(&self) -> &FnvHashMap<NodeIndex, NodeValue<V>> {
+        &self.node_values
+    }
+
+    /// Returns `false` if the computation was aborted after the maximum number of steps
+    /// before it reached a fixpoint.
+    pub fn is_stabilized(&self) -> bool {
+        self.stabilized
+    }
+}
+
+/// A solver for demand-driven queries on a backward interprocedural fixpoint problem.
+///
+/// Each query starts with a single value at a single node
+/// and only computes the part of the fixpoint that is reachable backwards from this node.
+/// The propagation of a value stops if [`Context::is_demand_satisfied`] returns `true` for it.
+/// Results are memoized, so that repeating a query with the same starting node and value
+/// returns the previous result without any computation.
+pub struct DemandSolver<'a, T: Context<'a>> {
+    /// The computation object that is reused for all queries.
+    computation: Computation<GeneralizedContext<'a, T>>,
+    /// The maximum number of times a node is visited in a single query.
+    /// If `None`, the computation of a query runs until it stabilizes.
+    max_steps: Option<u64>,
+    /// The results of previous queries, indexed by their starting node.
+    cache: HashMap<NodeIndex, Vec<(T::Value, QueryResult<T::Value>)>>,
+}
+
+impl<'a, T: Context<'a>> DemandSolver<'a, T> {
+    /// Create a new solver for the given context.
+    ///
+    /// If `max_steps` is set, each node is visited at most `max_steps` times per query.
+    pub fn new(context: T, max_steps: Option<u64>) -> Self {
+        DemandSolver {
+            computation: create_computation(context, None),
+            max_steps,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Compute the backward dataflow starting with the given value at the given node.
+    ///
+    /// The node index refers to the (reversed) graph of the context.
+    pub fn query(&mut self, node: NodeIndex, value: T::Value) -> &QueryResult<T::Value> {
+        let cached_index = self
+            .cache
+            .get(&node)
+            .and_then(|results| results.iter().position(|(start, _)| *start == value));
+        let index = match cached_index {
+            Some(index) => index,
+            None => {
+                let result = self.compute_query(node, value.clone());
+                let results = self.cache.entry(node).or_default();
+                results.push((value, result));
+                results.len() - 1
+            }
+        };
+        &self.cache[&node][index].1
+    }
+
+    /// Compute a query without looking at the memoized results.
+    fn compute_query(&mut self, node: NodeIndex, value: T::Value) -> QueryResult<T::Value> {
+        self.computation.reset();
+        self.computation
+            .set_node_value(node, NodeValue::Value(value));
+        match self.max_steps {
+            Some(max_steps) => self.computation.compute_with_max_steps(max_steps),
+            None => self.computation.compute(),
+        }
+        QueryResult {
+            node_values: self.computation.node_values().clone(),
+            stabilized: self.computation.has_stabilized(),
+        }
+    }
+
+    /// Get the number of memoized query results.
+    pub fn num_cached_queries(&self) -> usize {
+        self.cache.values().map(Vec::len).sum()
+    }
+
+    /// Remove all memoized query results.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Get a reference to the graph of the context.
+    pub fn get_graph(&self) -> &Graph<'a> {
+        self.computation.get_graph()
+    }
+
+    /// Get a reference to the context object.
+    pub fn get_context(&self) -> &T {
+        self.computation.get_context().get_context()
+    }
+}
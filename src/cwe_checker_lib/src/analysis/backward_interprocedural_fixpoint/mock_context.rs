@@ -15,6 +15,8 @@ pub enum StartEnd {
 pub struct Context<'a> {
     pub graph: Graph<'a>,
     pub tid_to_node_index: HashMap<(Tid, Tid, StartEnd), NodeIndex>,
+    /// Values greater or equal to the limit are not propagated further.
+    pub demand_limit: Option<u64>,
 }
 
 impl<'a> Context<'a> {
@@ -49,6 +51,7 @@ impl<'a> Context<'a> {
         Context {
             graph,
             tid_to_node_index,
+            demand_limit: None,
         }
     }
 }
@@ -128,4 +131,9 @@ impl<'a> crate::analysis::backward_interprocedural_fixpoint::Context<'a> for Con
     ) -> Option<u64> {
         Some(*value_after_jump)
     }
+
+    /// Stop the propagation at values reaching the demand limit
+    fn is_demand_satisfied(&self, value: &u64) -> bool {
+        self.demand_limit.is_some_and(|limit| *value >= limit)
+    }
 }
@@ -9,6 +9,15 @@
 //! first construct a context object implementing the `Context`trait.
 //! Use it to construct a `Computation` object.
 //! The `Computation` object provides the necessary methods for the actual fixpoint computation.
+//!
+//! # Demand-driven queries
+//!
+//! Analyses that only need the backward dataflow from a few specific sinks
+//! do not need to compute a whole-program backward fixpoint.
+//! Instead they can use a [`DemandSolver`] to compute the part of the fixpoint
+//! reachable from a single starting node and value.
+//! Propagation stops at values for which [`Context::is_demand_satisfied`] returns `true`,
+//! and the results of queries are memoized.
 
 use super::fixpoint::Context as GeneralFPContext;
 use super::forward_interprocedural_fixpoint;
@@ -18,6 +27,9 @@ use crate::intermediate_representation::*;
 use petgraph::graph::EdgeIndex;
 use std::marker::PhantomData;
 
+mod demand;
+pub use demand::{DemandSolver, QueryResult};
+
 /// The context for an backward interprocedural fixpoint computation.
 ///
 /// Basically, a `Context` object needs to contain a reference to the actual graph,
@@ -95,6 +107,14 @@ pub trait Context<'a> {
         condition: &Expression,
         is_true: bool,
     ) -> Option<Self::Value>;
+
+    /// Return `true` if the given value already answers the query of a demand-driven analysis,
+    /// so that it does not need to be propagated further backwards.
+    ///
+    /// The default implementation always returns `false`, i.e. all values are propagated.
+    fn is_demand_satisfied(&self, _value: &Self::Value) -> bool {
+        false
+    }
 }
 
 impl<'a, T: Context<'a>> GeneralFPContext for GeneralizedContext<'a, T> {
@@ -139,6 +159,11 @@ impl<'a, T: Context<'a>> GeneralFPContext for GeneralizedContext<'a, T> {
         node_value: &Self::NodeValue,
         edge: EdgeIndex,
     ) -> Option<Self::NodeValue> {
+        if let NodeValue::Value(value) = node_value {
+            if self.context.is_demand_satisfied(value) {
+                return None;
+            }
+        }
         let graph = self.context.get_graph();
         let (start_node, end_node) = graph.edge_endpoints(edge).unwrap();
 
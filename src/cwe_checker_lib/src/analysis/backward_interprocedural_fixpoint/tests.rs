@@ -1,4 +1,4 @@
-use super::{create_computation, mock_context, NodeValue};
+use super::{create_computation, mock_context, DemandSolver, NodeValue};
 use crate::def;
 use crate::expr;
 use crate::intermediate_representation::*;
@@ -98,6 +98,9 @@ fn mock_program() -> Term<Program> {
             extern_symbols: BTreeMap::new(),
             entry_points: BTreeSet::new(),
             address_base_offset: 0,
+            stack_canary_checks: BTreeMap::new(),
+            fortified_symbols: BTreeMap::new(),
+            provenance: ProvenanceTable::default(),
         },
     };
     program
@@ -265,3 +268,39 @@ fn backward_fixpoint() {
         5 as u64
     );
 }
+
+#[test]
+fn demand_query() {
+    let mut project = Project::mock_x64();
+    project.program = mock_program();
+    let mut mock_con = Context::new(&project);
+    mock_con.demand_limit = Some(2);
+    let node = |sub: &str, blk: &str, start_end: StartEnd| {
+        *mock_con
+            .tid_to_node_index
+            .get(&(Tid::new(sub), Tid::new(blk), start_end))
+            .unwrap()
+    };
+    let start_node = node("sub1", "sub1_blk1", StartEnd::Start);
+    let sub1_blk1_end = node("sub1", "sub1_blk1", StartEnd::End);
+    let sub2_blk2_start = node("sub2", "sub2_blk2", StartEnd::Start);
+    let sub2_blk1_end = node("sub2", "sub2_blk1", StartEnd::End);
+
+    let mut solver = DemandSolver::new(mock_con.clone(), None);
+    let result = solver.query(start_node, 0);
+    assert!(result.is_stabilized());
+    assert_eq!(result.get_value(sub1_blk1_end), Some(&1));
+    // The value 2 satisfies the demand and is not propagated to the predecessor blocks.
+    assert_eq!(result.get_value(sub2_blk2_start), Some(&2));
+    assert_eq!(result.get_value(sub2_blk1_end), None);
+    let result = result.clone();
+
+    // Repeated queries are answered from the cache.
+    assert_eq!(solver.query(start_node, 0), &result);
+    assert_eq!(solver.num_cached_queries(), 1);
+    let other_result = solver.query(start_node, 1);
+    assert_eq!(other_result.get_value(sub1_blk1_end), None);
+    assert_eq!(solver.num_cached_queries(), 2);
+    solver.clear_cache();
+    assert_eq!(solver.num_cached_queries(), 0);
+}
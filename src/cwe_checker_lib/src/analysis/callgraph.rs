@@ -1,8 +1,19 @@
 //! Generate call graphs out of a program term.
+//!
+//! The call graph returned by [`get_program_callgraph`] only contains direct calls.
+//! [`get_program_callgraph_with_indirect_calls`] additionally resolves the targets of indirect calls
+//! with the results of the pointer inference (see [`IndirectCallResolver`]).
+//! Call graphs can be exported in the DOT format of Graphviz or as JSON.
 
+use crate::abstract_domain::TryToBitvec;
+use crate::analysis::pointer_inference::{Data, ObjectKind, PointerInference};
+use crate::analysis::vsa_results::VsaResult;
 use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::parse_hex_string_to_u64;
 use petgraph::{graph::DiGraph, graph::NodeIndex, visit::EdgeRef};
 use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
 
 /// The graph type of a call graph
 pub type CallGraph<'a> = DiGraph<Tid, &'a Term<Jmp>>;
@@ -14,8 +25,8 @@ pub type CallGraph<'a> = DiGraph<Tid, &'a Term<Jmp>>;
 ///
 /// Note that calls to external symbols are not represented in the graph,
 /// i.e. there are neither nodes nor edges representing (calls to) external symbols in the graph.
-/// Also, there are currently no edges for indirect calls,
-/// because a corresponding analysis for resolving indirect calls is not implemented yet.
+/// Also, there are no edges for indirect calls.
+/// Use [`get_program_callgraph_with_indirect_calls`] if the pointer inference results are available.
 pub fn get_program_callgraph(program: &Term<Program>) -> CallGraph {
     let mut callgraph = CallGraph::new();
     let mut tid_to_node_index_map = HashMap::new();
@@ -39,6 +50,221 @@ pub fn get_program_callgraph(program: &Term<Program>) -> CallGraph {
     callgraph
 }
 
+/// Generate a call graph for the given project that also contains edges for indirect calls.
+///
+/// The targets of indirect calls are resolved with an [`IndirectCallResolver`].
+/// The edges of an indirect call are labeled with the `CallInd` jump term,
+/// so that an indirect call with several possible targets results in several edges with the same label.
+pub fn get_program_callgraph_with_indirect_calls<'a>(
+    project: &'a Project,
+    pi_result: &'a PointerInference<'a>,
+) -> CallGraph<'a> {
+    let mut callgraph = get_program_callgraph(&project.program);
+    let tid_to_node_index_map: HashMap<Tid, NodeIndex> = callgraph
+        .node_indices()
+        .map(|node| (callgraph[node].clone(), node))
+        .collect();
+    let resolver = IndirectCallResolver::new(project, pi_result);
+    for sub in project.program.term.subs.values() {
+        let source_index = tid_to_node_index_map[&sub.tid];
+        for block in &sub.term.blocks {
+            for jump in &block.term.jmps {
+                for target in resolver.resolve(block, jump) {
+                    if let Some(target_index) = tid_to_node_index_map.get(&target) {
+                        callgraph.add_edge(source_index, *target_index, jump);
+                    }
+                }
+            }
+        }
+    }
+    callgraph
+}
+
+/// Resolves the targets of indirect calls with the results of the pointer inference.
+pub struct IndirectCallResolver<'a> {
+    /// The project containing the indirect calls.
+    project: &'a Project,
+    /// The results of the pointer inference for the project.
+    pi_result: &'a PointerInference<'a>,
+    /// Maps the start addresses of the functions of the program to their TIDs.
+    function_addresses: HashMap<u64, &'a Tid>,
+}
+
+impl<'a> IndirectCallResolver<'a> {
+    /// Create a new resolver for the indirect calls of the given project.
+    pub fn new(project: &'a Project, pi_result: &'a PointerInference<'a>) -> Self {
+        let function_addresses = project
+            .program
+            .term
+            .subs
+            .keys()
+            .filter_map(|tid| Some((parse_hex_string_to_u64(&tid.address).ok()?, tid)))
+            .collect();
+        IndirectCallResolver {
+            project,
+            pi_result,
+            function_addresses,
+        }
+    }
+
+    /// Get the TIDs of all functions of the program that the jump may call if it is an indirect call.
+    ///
+    /// The targets are resolved in two ways:
+    /// - If the pointer inference knows the call target to be the address of a function,
+    ///   e.g. for function pointers propagated through registers, the stack or read-only memory,
+    ///   then this function is a target of the call.
+    /// - Otherwise, if the call target was loaded in the same block from a constant address in global memory,
+    ///   the pointer at that address in the binary is read.
+    ///   This resolves calls through vtables and function pointer tables in writeable memory,
+    ///   e.g. in the `.data.rel.ro` section, under the assumption that they are not changed at runtime.
+    ///
+    /// Returns an empty set for direct calls and for unresolved indirect calls.
+    pub fn resolve(&self, block: &Term<Blk>, jump: &Term<Jmp>) -> BTreeSet<Tid> {
+        let Jmp::CallInd { target, .. } = &jump.term else {
+            return BTreeSet::new();
+        };
+        let mut addresses: Vec<u64> = self
+            .pi_result
+            .eval_at_jmp(&jump.tid, target)
+            .map(|value| self.get_global_addresses(&value))
+            .unwrap_or_default();
+        if !addresses
+            .iter()
+            .any(|address| self.function_addresses.contains_key(address))
+        {
+            addresses = self.get_initial_values_of_loaded_pointers(block, target);
+        }
+        addresses
+            .into_iter()
+            .filter_map(|address| self.function_addresses.get(&address))
+            .map(|tid| (*tid).clone())
+            .collect()
+    }
+
+    /// Get the constant global addresses that the value may point to.
+    fn get_global_addresses(&self, value: &Data) -> Vec<u64> {
+        self.pi_result
+            .get_pointer_targets(value)
+            .into_iter()
+            .filter(|target| {
+                matches!(
+                    target.kind,
+                    ObjectKind::GlobalReadOnly | ObjectKind::GlobalWritable
+                )
+            })
+            .filter_map(|target| target.offset.try_to_bitvec().ok()?.try_to_u64().ok())
+            .collect()
+    }
+
+    /// If the call target is a register loaded from global memory in the given block,
+    /// return the pointers contained in the binary at the possible load addresses.
+    fn get_initial_values_of_loaded_pointers(
+        &self,
+        block: &Term<Blk>,
+        target: &Expression,
+    ) -> Vec<u64> {
+        let Expression::Var(target_var) = target else {
+            return Vec::new();
+        };
+        let Some(load_def) = block.term.defs.iter().rev().find(|def| match &def.term {
+            Def::Load { var, .. } | Def::Assign { var, .. } => var == target_var,
+            Def::Store { .. } => false,
+        }) else {
+            return Vec::new();
+        };
+        if !matches!(load_def.term, Def::Load { .. }) {
+            return Vec::new();
+        }
+        let Some(load_address) = self.pi_result.eval_address_at_def(&load_def.tid) else {
+            return Vec::new();
+        };
+        let memory_image = &self.project.runtime_memory_image;
+        self.get_global_addresses(&load_address)
+            .into_iter()
+            .filter_map(|address| {
+                memory_image
+                    .read_initial_value(&Bitvector::from_u64(address), target_var.size)
+                    .ok()?
+                    .try_to_u64()
+                    .ok()
+            })
+            .collect()
+    }
+}
+
+/// Render the call graph in the DOT format of Graphviz.
+///
+/// Nodes are labeled with the names of the functions and edges of indirect calls are dashed.
+pub fn callgraph_to_dot(callgraph: &CallGraph, program: &Program) -> String {
+    let mut dot = "digraph callgraph {\n".to_string();
+    for node in callgraph.node_indices() {
+        let tid = &callgraph[node];
+        let name = program
+            .subs
+            .get(tid)
+            .map(|sub| sub.term.name.as_str())
+            .unwrap_or_default();
+        writeln!(
+            dot,
+            "    n{} [label=\"{}\\n{}\"];",
+            node.index(),
+            name.replace('\\', "\\\\").replace('"', "\\\""),
+            tid.address
+        )
+        .unwrap();
+    }
+    for edge in callgraph.edge_references() {
+        let style = match edge.weight().term {
+            Jmp::CallInd { .. } => " [style=dashed]",
+            _ => "",
+        };
+        writeln!(
+            dot,
+            "    n{} -> n{}{style};",
+            edge.source().index(),
+            edge.target().index()
+        )
+        .unwrap();
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Generate a JSON representation of the call graph.
+///
+/// The JSON object contains a list of the `functions` of the call graph
+/// and a list of the `calls` between them, where each call is given by the TIDs of the call instruction,
+/// the calling function and the called function.
+pub fn callgraph_to_json(callgraph: &CallGraph, program: &Program) -> serde_json::Value {
+    let functions: Vec<serde_json::Value> = callgraph
+        .node_indices()
+        .map(|node| {
+            let tid = &callgraph[node];
+            serde_json::json!({
+                "tid": tid.to_string(),
+                "name": program.subs.get(tid).map(|sub| sub.term.name.as_str()),
+                "address": tid.address,
+            })
+        })
+        .collect();
+    let calls: Vec<serde_json::Value> = callgraph
+        .edge_references()
+        .map(|edge| {
+            serde_json::json!({
+                "tid": edge.weight().tid.to_string(),
+                "address": edge.weight().tid.address,
+                "caller": callgraph[edge.source()].to_string(),
+                "callee": callgraph[edge.target()].to_string(),
+                "indirect": matches!(edge.weight().term, Jmp::CallInd { .. }),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "functions": functions,
+        "calls": calls,
+    })
+}
+
 /// Collect and return all call TIDs of call sequences that start in the function given by the `source_sub_tid`
 /// and end in the function given by the `target_sub_tid`.
 pub fn find_call_sequences_to_target(
@@ -108,6 +334,8 @@ fn find_call_sequences_from_node_to_target(
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::analysis::pointer_inference::State;
+    use crate::{bitvec, expr, variable};
     use std::collections::BTreeMap;
 
     /// Mock a function with calls to the given list of Sub-TIDs.
@@ -153,6 +381,76 @@ pub mod tests {
         assert_eq!(&call_tids[2], "sub2_call_sub3_0");
     }
 
+    #[test]
+    fn test_indirect_calls() {
+        let mut project = Project::mock_x64();
+        let mut caller = mock_sub_with_calls("caller", &[]);
+        let mut block = Blk::mock_with_tid("caller_blk");
+        block.term.defs.push(Term {
+            tid: Tid::new("load_vtable_entry"),
+            term: Def::Load {
+                var: variable!("RBX:8"),
+                address: expr!("RDI:8"),
+            },
+        });
+        for (call_tid, target) in [("call_pointer", "RAX:8"), ("call_vtable", "RBX:8")] {
+            block.term.jmps.push(Term {
+                tid: Tid::new(call_tid),
+                term: Jmp::CallInd {
+                    target: expr!(target),
+                    return_: None,
+                },
+            });
+        }
+        caller.term.blocks.push(block);
+        project.program.term.subs.insert(caller.tid.clone(), caller);
+        for (sub_tid, address) in [("func", "00001000"), ("virtual_func", "00003002")] {
+            let mut sub = mock_sub_with_calls(sub_tid, &[]);
+            sub.tid.address = address.to_string();
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        // The vtable entry in writeable memory points to `virtual_func`.
+        project.runtime_memory_image.memory_segments[1].bytes = vec![0x02, 0x30, 0, 0, 0, 0, 0, 0];
+
+        let mut pi_result = PointerInference::mock(&project);
+        let mut state = State::new(&variable!("RSP:8"), Tid::new("caller"), BTreeSet::new());
+        state.set_register(&variable!("RAX:8"), bitvec!("0x1000:8").into());
+        for call_tid in ["call_pointer", "call_vtable"] {
+            pi_result
+                .get_mut_states_at_tids()
+                .insert(Tid::new(call_tid), state.clone());
+        }
+        pi_result
+            .get_mut_addresses_at_defs()
+            .insert(Tid::new("load_vtable_entry"), bitvec!("0x2000:8").into());
+
+        let callgraph = get_program_callgraph_with_indirect_calls(&project, &pi_result);
+        let mut calls: Vec<(String, String)> = callgraph
+            .edge_references()
+            .map(|edge| {
+                (
+                    edge.weight().tid.to_string(),
+                    callgraph[edge.target()].to_string(),
+                )
+            })
+            .collect();
+        calls.sort();
+        assert_eq!(
+            calls,
+            vec![
+                ("call_pointer".to_string(), "func".to_string()),
+                ("call_vtable".to_string(), "virtual_func".to_string()),
+            ]
+        );
+
+        let json = callgraph_to_json(&callgraph, &project.program.term);
+        assert_eq!(json["functions"].as_array().unwrap().len(), 3);
+        assert_eq!(json["calls"][0]["indirect"], serde_json::Value::Bool(true));
+        let dot = callgraph_to_dot(&callgraph, &project.program.term);
+        assert!(dot.starts_with("digraph callgraph {"));
+        assert_eq!(dot.matches("[style=dashed]").count(), 2);
+    }
+
     #[test]
     fn test_get_program_callgraph() {
         // Create a program with 2 functions and one call between them
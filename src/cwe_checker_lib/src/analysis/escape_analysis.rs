@@ -0,0 +1,485 @@
+//! An escape analysis for objects on the stack.
+//!
+//! A stack object *escapes* if its address can be accessed after the function owning the object
+//! has returned or from code that the function does not control.
+//! The analysis determines for each stack object whether its address
+//! - is written to global memory,
+//! - is written to a heap object (or any other object returned by a call),
+//! - is written to memory provided by the caller through a parameter,
+//! - is passed to a called function that lets the corresponding parameter escape,
+//! - or is returned to the caller.
+//!
+//! The analysis is based on the results of the [Pointer Inference analysis](crate::analysis::pointer_inference).
+//! Stack objects are identified by the function owning the stack frame
+//! and the (smallest) stack offset that the escaping pointer may point to.
+//!
+//! To determine whether a callee lets a pointer escape, the analysis also tracks parameters of functions
+//! whose values are written to global memory, heap objects or memory provided by the caller.
+//! These escapes are propagated from callees to their callers
+//! using the mapping of callee identifiers to caller values at each call.
+//!
+//! Limitations:
+//! - Pointers passed to external functions are assumed to not escape.
+//! - Pointers with unknown stack offsets and stores to unknown target addresses are ignored.
+//! - A parameter written to memory provided by the caller is assumed to escape,
+//!   even if the caller provided a pointer to its own stack frame.
+
+use crate::abstract_domain::{AbstractDomain, AbstractIdentifier, IntervalDomain, TryToInterval};
+use crate::analysis::pointer_inference::Data;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The ways in which an object can escape.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum EscapeKind {
+    /// The address is written to global memory.
+    Global,
+    /// The address is written to a heap object or to another object returned by a call.
+    Heap,
+    /// The address is written to memory provided by the caller through a parameter.
+    Caller,
+    /// The address is passed to a function that lets the corresponding parameter escape.
+    Callee,
+    /// The address is returned to the caller.
+    Return,
+}
+
+/// A single escape of an object.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct Escape {
+    /// The way in which the object escapes.
+    pub kind: EscapeKind,
+    /// The TID of the instruction through which the object escapes.
+    pub tid: Tid,
+}
+
+/// An object on the stack frame of a function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct StackObject {
+    /// The TID of the function owning the stack frame.
+    pub sub: Tid,
+    /// The offset of the object relative to the stack pointer value at the start of the function.
+    pub offset: i64,
+}
+
+/// The results of the escape analysis.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct EscapeAnalysis {
+    /// The escapes of all escaping stack objects.
+    stack_escapes: BTreeMap<StackObject, BTreeSet<Escape>>,
+    /// The escapes of all escaping parameter objects,
+    /// i.e. of the values of parameters of a function (as seen from inside the function).
+    parameter_escapes: BTreeMap<AbstractIdentifier, BTreeSet<Escape>>,
+}
+
+impl EscapeAnalysis {
+    /// Compute the escape analysis for the given project
+    /// using the results of the Pointer Inference analysis.
+    pub fn new(project: &Project, pointer_inference: &impl VsaResult<ValueDomain = Data>) -> Self {
+        let mut analysis = EscapeAnalysis::default();
+        for sub in project.program.term.subs.values() {
+            let stack_id =
+                AbstractIdentifier::from_var(sub.tid.clone(), &project.stack_pointer_register);
+            for blk in sub.term.blocks.iter() {
+                for def in blk.term.defs.iter() {
+                    if let Def::Store { .. } = def.term {
+                        analysis.add_store_escapes(sub, &stack_id, &def.tid, pointer_inference);
+                    }
+                }
+                for jmp in blk.term.jmps.iter() {
+                    if let Jmp::Return(_) = jmp.term {
+                        analysis.add_return_escapes(
+                            project,
+                            sub,
+                            &stack_id,
+                            &jmp.tid,
+                            pointer_inference,
+                        );
+                    }
+                }
+            }
+        }
+        analysis.propagate_callee_escapes(project, pointer_inference);
+        analysis
+    }
+
+    /// Get the escapes of the given stack object.
+    /// Returns `None` if the object does not escape.
+    pub fn get_stack_escapes(&self, object: &StackObject) -> Option<&BTreeSet<Escape>> {
+        self.stack_escapes.get(object)
+    }
+
+    /// Check whether a stack object of the given function escapes in one of the given ways.
+    /// If no kinds are given, any escape is reported.
+    pub fn does_stack_object_escape(&self, object: &StackObject, kinds: &[EscapeKind]) -> bool {
+        self.stack_escapes.get(object).is_some_and(|escapes| {
+            kinds.is_empty() || escapes.iter().any(|escape| kinds.contains(&escape.kind))
+        })
+    }
+
+    /// Iterate over all escaping stack objects of the given function together with their escapes.
+    pub fn iter_stack_escapes_of_sub<'a>(
+        &'a self,
+        sub: &'a Tid,
+    ) -> impl Iterator<Item = (&'a StackObject, &'a BTreeSet<Escape>)> + 'a {
+        self.stack_escapes
+            .iter()
+            .filter(move |(object, _)| object.sub == *sub)
+    }
+
+    /// Iterate over all escaping stack objects together with their escapes.
+    pub fn iter_stack_escapes(&self) -> impl Iterator<Item = (&StackObject, &BTreeSet<Escape>)> {
+        self.stack_escapes.iter()
+    }
+
+    /// Get the escapes of the given parameter object.
+    /// Returns `None` if the parameter does not escape.
+    pub fn get_parameter_escapes(&self, param: &AbstractIdentifier) -> Option<&BTreeSet<Escape>> {
+        self.parameter_escapes.get(param)
+    }
+
+    /// Record that the stack objects and parameter objects that the given value may point to
+    /// escape through the given instruction.
+    ///
+    /// Returns `true` if a parameter object that did not escape before escapes now.
+    fn add_escapes(
+        &mut self,
+        sub: &Term<Sub>,
+        stack_id: &AbstractIdentifier,
+        value: &Data,
+        escape: Escape,
+    ) -> bool {
+        let mut new_parameter_escape = false;
+        for (id, offset) in value.get_relative_values() {
+            if id == stack_id {
+                self.add_stack_escape(&sub.tid, offset, escape.clone());
+            } else if id.get_tid() == &sub.tid {
+                let escapes = self.parameter_escapes.entry(id.clone()).or_insert_with(|| {
+                    new_parameter_escape = true;
+                    BTreeSet::new()
+                });
+                escapes.insert(escape.clone());
+            }
+        }
+        new_parameter_escape
+    }
+
+    /// Record that the stack object at the given offset escapes through the given instruction.
+    /// Escapes of pointers with unknown offsets are ignored.
+    fn add_stack_escape(&mut self, sub_tid: &Tid, offset: &IntervalDomain, escape: Escape) {
+        let Ok((offset, _)) = offset.try_to_offset_interval() else {
+            return;
+        };
+        if offset != i64::MIN {
+            let object = StackObject {
+                sub: sub_tid.clone(),
+                offset,
+            };
+            self.stack_escapes.entry(object).or_default().insert(escape);
+        }
+    }
+
+    /// Add the escapes caused by the given store instruction.
+    fn add_store_escapes(
+        &mut self,
+        sub: &Term<Sub>,
+        stack_id: &AbstractIdentifier,
+        store_tid: &Tid,
+        pointer_inference: &impl VsaResult<ValueDomain = Data>,
+    ) {
+        let (Some(address), Some(value)) = (
+            pointer_inference.eval_address_at_def(store_tid),
+            pointer_inference.eval_value_at_def(store_tid),
+        ) else {
+            return;
+        };
+        for kind in classify_store_target(&address, &sub.tid, stack_id) {
+            self.add_escapes(
+                sub,
+                stack_id,
+                &value,
+                Escape {
+                    kind,
+                    tid: store_tid.clone(),
+                },
+            );
+        }
+    }
+
+    /// Add the escapes of stack objects whose addresses are returned by the given return instruction.
+    fn add_return_escapes(
+        &mut self,
+        project: &Project,
+        sub: &Term<Sub>,
+        stack_id: &AbstractIdentifier,
+        return_tid: &Tid,
+        pointer_inference: &impl VsaResult<ValueDomain = Data>,
+    ) {
+        let Some(calling_convention) =
+            project.get_specific_calling_convention(&sub.term.calling_convention)
+        else {
+            return;
+        };
+        for register in calling_convention.integer_return_register.iter() {
+            let Some(value) =
+                pointer_inference.eval_at_jmp(return_tid, &Expression::Var(register.clone()))
+            else {
+                continue;
+            };
+            // Returning a parameter does not let it escape from the caller.
+            if let Some(offset) = value.get_relative_values().get(stack_id) {
+                let escape = Escape {
+                    kind: EscapeKind::Return,
+                    tid: return_tid.clone(),
+                };
+                self.add_stack_escape(&sub.tid, offset, escape);
+            }
+        }
+    }
+
+    /// Add the escapes caused by passing pointers to callees that let the corresponding parameter escape.
+    ///
+    /// Since escaping parameters of a caller may in turn cause escapes in its callers,
+    /// this is repeated until no new escaping parameters are found.
+    fn propagate_callee_escapes(
+        &mut self,
+        project: &Project,
+        pointer_inference: &impl VsaResult<ValueDomain = Data>,
+    ) {
+        let mut calls = Vec::new();
+        for sub in project.program.term.subs.values() {
+            let stack_id =
+                AbstractIdentifier::from_var(sub.tid.clone(), &project.stack_pointer_register);
+            for blk in sub.term.blocks.iter() {
+                for jmp in blk.term.jmps.iter() {
+                    if let Jmp::Call { target, .. } = &jmp.term {
+                        if project.program.term.subs.contains_key(target) {
+                            calls.push((sub, stack_id.clone(), &jmp.tid, target));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (sub, stack_id, call_tid, callee_tid) in calls.iter() {
+                let Some(renaming_map) = pointer_inference.get_call_renaming_map(call_tid) else {
+                    continue;
+                };
+                let escaping_params: Vec<AbstractIdentifier> = self
+                    .parameter_escapes
+                    .keys()
+                    .filter(|param| param.get_tid() == *callee_tid)
+                    .cloned()
+                    .collect();
+                for param in escaping_params {
+                    if let Some(value) = renaming_map.get(&param) {
+                        changed |= self.add_escapes(
+                            sub,
+                            stack_id,
+                            value,
+                            Escape {
+                                kind: EscapeKind::Callee,
+                                tid: (*call_tid).clone(),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Determine the ways in which a value escapes if it is written to the given target address.
+///
+/// Writes to the stack frame of the current function do not cause escapes.
+/// Unknown targets are ignored.
+fn classify_store_target(
+    address: &Data,
+    sub_tid: &Tid,
+    stack_id: &AbstractIdentifier,
+) -> BTreeSet<EscapeKind> {
+    let mut kinds = BTreeSet::new();
+    for id in address.get_relative_values().keys() {
+        if id == stack_id {
+            continue;
+        } else if id.get_tid() == sub_tid {
+            kinds.insert(EscapeKind::Caller);
+        } else {
+            kinds.insert(EscapeKind::Heap);
+        }
+    }
+    if address
+        .get_absolute_value()
+        .is_some_and(|absolute_value| !absolute_value.is_top())
+    {
+        kinds.insert(EscapeKind::Global);
+    }
+    kinds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstract_domain::AbstractLocation;
+    use crate::analysis::graph::NodeIndex;
+    use crate::{bitvec, variable};
+    use std::collections::HashMap;
+
+    /// A mock of the Pointer Inference results containing only values and addresses at `Def`s
+    /// and the callee ID renaming maps at calls.
+    #[derive(Default)]
+    struct MockPointerInference {
+        values: HashMap<Tid, Data>,
+        addresses: HashMap<Tid, Data>,
+        renaming_maps: HashMap<Tid, BTreeMap<AbstractIdentifier, Data>>,
+    }
+
+    impl VsaResult for MockPointerInference {
+        type ValueDomain = Data;
+
+        fn eval_value_at_def(&self, def_tid: &Tid) -> Option<Data> {
+            self.values.get(def_tid).cloned()
+        }
+
+        fn eval_address_at_def(&self, def_tid: &Tid) -> Option<Data> {
+            self.addresses.get(def_tid).cloned()
+        }
+
+        fn eval_parameter_arg_at_call(&self, _jmp_tid: &Tid, _param: &Arg) -> Option<Data> {
+            None
+        }
+
+        fn eval_parameter_location_at_call(
+            &self,
+            _jmp_tid: &Tid,
+            _param: &AbstractLocation,
+        ) -> Option<Data> {
+            None
+        }
+
+        fn eval_at_jmp(&self, _jmp_tid: &Tid, _expression: &Expression) -> Option<Data> {
+            None
+        }
+
+        fn eval_at_node(&self, _node: NodeIndex, _expression: &Expression) -> Option<Data> {
+            None
+        }
+
+        fn get_call_renaming_map(&self, call: &Tid) -> Option<&BTreeMap<AbstractIdentifier, Data>> {
+            self.renaming_maps.get(call)
+        }
+    }
+
+    fn pointer(sub: &str, register: &str, offset: i64) -> Data {
+        Data::from_target(
+            AbstractIdentifier::from_var(Tid::new(sub), &variable!(format!("{register}:8"))),
+            IntervalDomain::from(Bitvector::from_i64(offset)),
+        )
+    }
+
+    /// Create a project where `caller` passes pointers to its stack to `callee`
+    /// and also writes a pointer to its stack to global memory.
+    /// The callee writes its parameter `RDI` into a heap object.
+    fn mock_setup() -> (Project, MockPointerInference) {
+        let mut project = Project::mock_x64();
+        let mut caller = Sub::mock("caller");
+        let mut blk = Blk::mock_with_tid("caller_blk");
+        blk.term.defs.push(Def::store_var_content_at_temp_var(
+            "store_global",
+            "$U1",
+            "RAX",
+        ));
+        blk.term
+            .jmps
+            .push(Jmp::call("call_callee", "callee", Some("caller_end")));
+        caller.term.blocks.push(blk);
+        let mut callee = Sub::mock("callee");
+        let mut blk = Blk::mock_with_tid("callee_blk");
+        blk.term.defs.push(Def::store_var_content_at_temp_var(
+            "store_heap",
+            "$U2",
+            "RDI",
+        ));
+        callee.term.blocks.push(blk);
+        project.program.term.subs =
+            BTreeMap::from([(caller.tid.clone(), caller), (callee.tid.clone(), callee)]);
+
+        let mut pointer_inference = MockPointerInference::default();
+        pointer_inference
+            .addresses
+            .insert(Tid::new("store_global"), Data::from(bitvec!("0x3000:8")));
+        pointer_inference
+            .values
+            .insert(Tid::new("store_global"), pointer("caller", "RSP", -16));
+        pointer_inference
+            .addresses
+            .insert(Tid::new("store_heap"), pointer("malloc_call", "RAX", 0));
+        pointer_inference
+            .values
+            .insert(Tid::new("store_heap"), pointer("callee", "RDI", 0));
+        pointer_inference.renaming_maps.insert(
+            Tid::new("call_callee"),
+            BTreeMap::from([
+                (
+                    AbstractIdentifier::from_var(Tid::new("callee"), &variable!("RDI:8")),
+                    pointer("caller", "RSP", -32),
+                ),
+                (
+                    AbstractIdentifier::from_var(Tid::new("callee"), &variable!("RSI:8")),
+                    pointer("caller", "RSP", -48),
+                ),
+            ]),
+        );
+        (project, pointer_inference)
+    }
+
+    #[test]
+    fn stack_escapes() {
+        let (project, pointer_inference) = mock_setup();
+        let analysis = EscapeAnalysis::new(&project, &pointer_inference);
+        let object = |offset| StackObject {
+            sub: Tid::new("caller"),
+            offset,
+        };
+
+        assert_eq!(
+            analysis.get_stack_escapes(&object(-16)),
+            Some(&BTreeSet::from([Escape {
+                kind: EscapeKind::Global,
+                tid: Tid::new("store_global")
+            }]))
+        );
+        assert_eq!(
+            analysis.get_stack_escapes(&object(-32)),
+            Some(&BTreeSet::from([Escape {
+                kind: EscapeKind::Callee,
+                tid: Tid::new("call_callee")
+            }]))
+        );
+        assert!(analysis.does_stack_object_escape(&object(-32), &[]));
+        assert!(!analysis.does_stack_object_escape(&object(-32), &[EscapeKind::Global]));
+        // The callee does not let its second parameter escape.
+        assert!(!analysis.does_stack_object_escape(&object(-48), &[]));
+        assert_eq!(
+            analysis
+                .iter_stack_escapes_of_sub(&Tid::new("caller"))
+                .count(),
+            2
+        );
+        assert_eq!(
+            analysis.get_parameter_escapes(&AbstractIdentifier::from_var(
+                Tid::new("callee"),
+                &variable!("RDI:8")
+            )),
+            Some(&BTreeSet::from([Escape {
+                kind: EscapeKind::Heap,
+                tid: Tid::new("store_heap")
+            }]))
+        );
+    }
+}
@@ -34,12 +34,60 @@
 //! to match the intended starting conditions of the fixpoint computation.
 //! The `Computation` object also contains methods to actually run the fixpoint computation after the starting values are set
 //! and methods to retrieve the results of the computation.
+//!
+//! # Worklist order
+//!
+//! The order in which nodes are taken from the worklist does not change the resulting fixpoint,
+//! but it can change the number of node updates needed to reach it considerably.
+//! The order can be chosen through the [`WorklistOrder`] enum
+//! when creating a computation with [`Computation::new_with_worklist_order`].
+//! Analyses with special requirements on the order can also provide their own node ordering
+//! through [`Computation::from_node_priority_list`].
+//!
+//! # Budgets
+//!
+//! Some fixpoint problems need a huge number of node updates to stabilize on parts of the graph,
+//! e.g. on the nodes of very large functions.
+//! A [`Budget`] set through [`Computation::set_budget`] limits the number of node visits
+//! and the time spent on each group of nodes (as defined by [`Context::get_budget_group`]).
+//! If a group exhausts its budget, its node values are replaced by the over-approximations
+//! computed by [`Context::over_approximate`] instead of continuing the precise computation.
+//!
+//! # Cancellation
+//!
+//! A [`CancellationToken`] set through [`Computation::set_cancellation_token`] is checked before each node update.
+//! Once it is cancelled, the computation stops and keeps its intermediate node values.
+//! The nodes that were not yet stabilized remain in the worklist, see [`Computation::has_stabilized`].
+//!
+//! # Memory limits
+//!
+//! Keeping the values of all nodes of very large graphs may need more memory than available.
+//! If a memory limit is set through [`Computation::set_memory_limit`],
+//! the resident memory of the process is checked regularly during the computation.
+//! If it exceeds the limit, the values of all groups of nodes (as defined by [`Context::get_budget_group`])
+//! that contain no node of the worklist are evicted.
+//! Only the values of the entry nodes of each group are kept,
+//! i.e. of nodes with incoming edges from other groups, without incoming edges
+//! or whose value was set from outside of the computation.
+//! The values of the other nodes are recomputed from them once they are needed again.
+//! Since merging may involve widening, recomputed values may differ from the evicted ones,
+//! but they are still sound with respect to the values at the entry nodes.
 
-use fnv::FnvHashMap;
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::get_resident_memory;
+use crate::utils::log::timed_logging;
+use fnv::{FnvHashMap, FnvHashSet};
 use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
-use petgraph::visit::EdgeRef;
+use petgraph::visit::{DfsPostOrder, EdgeRef};
+use petgraph::Direction;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 use std::collections::{BTreeMap, BTreeSet};
-use crate::utils::log::timed_logging;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// The default number of node visits between two checks of the memory usage of a computation with a memory limit.
+const MEMORY_CHECK_INTERVAL: u64 = 1000;
 
 /// The context of a fixpoint computation.
 ///
@@ -63,6 +111,150 @@ pub trait Context {
     /// The function can return None to indicate that no end value gets generated through this edge.
     /// E.g. In a control flow graph, if the edge cannot be taken for the given start value, this function should return None.
     fn update_edge(&self, value: &Self::NodeValue, edge: EdgeIndex) -> Option<Self::NodeValue>;
+
+    /// Get the group of the node whose work is limited by the [`Budget`] of the computation,
+    /// e.g. the index of the function containing the node.
+    /// The work spent on nodes without a group is not limited.
+    fn get_budget_group(&self, _node: NodeIndex) -> Option<usize> {
+        None
+    }
+
+    /// Over-approximate the value of a node whose group exhausted its [`Budget`].
+    ///
+    /// The result should be a sound approximation of all values that the node could reach
+    /// if the computation was continued.
+    /// It should also be stable under merging with other values,
+    /// so that the group stabilizes after few additional node visits.
+    /// The default implementation returns the value unchanged.
+    fn over_approximate(&self, value: &Self::NodeValue) -> Self::NodeValue {
+        value.clone()
+    }
+}
+
+/// The strategy used to order the nodes of the worklist of a fixpoint computation.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum WorklistOrder {
+    /// Order the strongly connected components of the graph in topological order,
+    /// i.e. a strongly connected component is only worked on
+    /// if no node of a preceding component is contained in the worklist.
+    /// The order of the nodes inside a component is arbitrary.
+    #[default]
+    StronglyConnectedComponents,
+    /// Order the nodes in reverse post-order of a depth-first search
+    /// starting at the nodes without incoming edges.
+    /// Nodes not reachable from such a node are covered by additional searches afterwards.
+    ReversePostOrder,
+    /// Order the strongly connected components as in [`WorklistOrder::StronglyConnectedComponents`],
+    /// but inside each component prefer nodes with less incoming edges.
+    /// Thus merge points inside loops are worked on after their predecessors in the loop,
+    /// which reduces the number of times the same loop head has to be recomputed.
+    InDegree,
+}
+
+impl WorklistOrder {
+    /// Return all nodes of the graph sorted by their priority in the worklist,
+    /// i.e. nodes with a higher index in the returned list are worked on first.
+    pub fn compute_node_priority_list<N, E>(&self, graph: &DiGraph<N, E>) -> Vec<NodeIndex> {
+        match self {
+            WorklistOrder::StronglyConnectedComponents => petgraph::algo::kosaraju_scc(graph)
+                .into_iter()
+                .flatten()
+                .collect(),
+            WorklistOrder::ReversePostOrder => {
+                let roots = graph
+                    .node_indices()
+                    .filter(|node| {
+                        graph
+                            .neighbors_directed(*node, Direction::Incoming)
+                            .next()
+                            .is_none()
+                    })
+                    .chain(graph.node_indices());
+                let mut dfs = DfsPostOrder::empty(graph);
+                let mut post_order = Vec::with_capacity(graph.node_count());
+                for root in roots {
+                    if dfs.discovered.contains(root.index()) {
+                        continue;
+                    }
+                    dfs.move_to(root);
+                    while let Some(node) = dfs.next(graph) {
+                        post_order.push(node);
+                    }
+                }
+                // Nodes finished last in the post-order get the highest priority.
+                post_order
+            }
+            WorklistOrder::InDegree => petgraph::algo::kosaraju_scc(graph)
+                .into_iter()
+                .flat_map(|mut component| {
+                    component.sort_by_key(|node| {
+                        Reverse(graph.edges_directed(*node, Direction::Incoming).count())
+                    });
+                    component
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Limits on the work spent on each group of nodes of a fixpoint computation,
+/// e.g. on the nodes of each function.
+///
+/// If a group exceeds one of the limits, the values of all its nodes are over-approximated,
+/// see [`Context::over_approximate`].
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct Budget {
+    /// The maximal number of node visits per group.
+    /// If `None`, the number of node visits is not limited.
+    #[serde(default)]
+    pub max_node_visits: Option<u64>,
+    /// The maximal time in milliseconds spent on visiting the nodes of a group.
+    /// If `None`, the time is not limited.
+    #[serde(default)]
+    pub max_milliseconds: Option<u64>,
+}
+
+impl Budget {
+    /// Returns `true` if the budget limits the work spent on a group.
+    pub fn is_limited(&self) -> bool {
+        self.max_node_visits.is_some() || self.max_milliseconds.is_some()
+    }
+
+    /// Returns `true` if the work spent on a group exceeds the budget.
+    fn is_exceeded(&self, usage: &BudgetUsage) -> bool {
+        self.max_node_visits
+            .is_some_and(|max_visits| usage.node_visits > max_visits)
+            || self.max_milliseconds.is_some_and(|max_milliseconds| {
+                usage.duration > Duration::from_millis(max_milliseconds)
+            })
+    }
+}
+
+/// The work spent on a group of nodes.
+#[derive(Debug, Clone, Copy, Default)]
+struct BudgetUsage {
+    /// The number of node visits.
+    node_visits: u64,
+    /// The time spent on visiting the nodes.
+    duration: Duration,
+}
+
+/// The nodes of a group of a computation with a memory limit.
+#[derive(Debug, Clone, Default)]
+struct NodeGroup {
+    /// All nodes of the group.
+    nodes: Vec<NodeIndex>,
+    /// The nodes of the group with incoming edges from other groups or without incoming edges.
+    entry_nodes: FnvHashSet<NodeIndex>,
+}
+
+/// The values of a group of nodes that were evicted to save memory.
+struct EvictedGroup<V> {
+    /// The values of the entry nodes of the group, from which all other values get recomputed.
+    entry_values: FnvHashMap<NodeIndex, V>,
+    /// The recomputed values of all nodes of the group,
+    /// if they were needed while the computation could not be modified.
+    restored: OnceLock<FnvHashMap<NodeIndex, V>>,
 }
 
 /// The computation struct contains an intermediate result of a fixpoint computation
@@ -95,18 +287,47 @@ pub struct Computation<T: Context> {
     worklist: BTreeSet<usize>,
     /// The internal map containing all known node values.
     node_values: FnvHashMap<NodeIndex, T::NodeValue>,
+    /// The budget limiting the work spent on each group of nodes.
+    budget: Budget,
+    /// The work spent on each group of nodes so far.
+    budget_usage: BTreeMap<usize, BudgetUsage>,
+    /// The groups of nodes that exhausted their budget.
+    exhausted_groups: BTreeSet<usize>,
+    /// The token that stops the computation once it is cancelled.
+    cancellation_token: Option<CancellationToken>,
+    /// The resident memory of the process in bytes above which the values of stabilized groups get evicted.
+    memory_limit: Option<u64>,
+    /// The number of node visits between two checks of the memory usage.
+    memory_check_interval: u64,
+    /// The number of node visits since the last check of the memory usage.
+    node_visits_since_memory_check: u64,
+    /// The nodes of each group. Only computed if a memory limit is set.
+    node_groups: BTreeMap<usize, NodeGroup>,
+    /// The groups whose node values were evicted to save memory.
+    evicted_groups: BTreeMap<usize, EvictedGroup<T::NodeValue>>,
+    /// The nodes whose values were set from outside of the computation.
+    /// Their values are never evicted, since they cannot be recomputed.
+    seeded_nodes: FnvHashSet<NodeIndex>,
 }
 
 impl<T: Context> Computation<T> {
     /// Create a new fixpoint computation from a fixpoint problem, the corresponding graph
     /// and a default value for all nodes if one should exists.
+    ///
+    /// The worklist uses the default [`WorklistOrder`].
     pub fn new(fp_context: T, default_value: Option<T::NodeValue>) -> Self {
-        let graph = fp_context.get_graph();
-        // order the nodes in weak topological order
-        let priority_sorted_nodes: Vec<NodeIndex> = petgraph::algo::kosaraju_scc(&graph)
-            .into_iter()
-            .flatten()
-            .collect();
+        Self::new_with_worklist_order(fp_context, default_value, WorklistOrder::default())
+    }
+
+    /// Create a new fixpoint computation from a fixpoint problem, an optional default value
+    /// and the strategy that determines the order in which nodes are taken from the worklist.
+    pub fn new_with_worklist_order(
+        fp_context: T,
+        default_value: Option<T::NodeValue>,
+        worklist_order: WorklistOrder,
+    ) -> Self {
+        let priority_sorted_nodes =
+            worklist_order.compute_node_priority_list(fp_context.get_graph());
         Self::from_node_priority_list(fp_context, default_value, priority_sorted_nodes)
     }
 
@@ -139,29 +360,129 @@ impl<T: Context> Computation<T> {
             priority_to_node_list: priority_sorted_nodes,
             worklist,
             node_values,
+            budget: Budget::default(),
+            budget_usage: BTreeMap::new(),
+            exhausted_groups: BTreeSet::new(),
+            cancellation_token: None,
+            memory_limit: None,
+            memory_check_interval: MEMORY_CHECK_INTERVAL,
+            node_visits_since_memory_check: 0,
+            node_groups: BTreeMap::new(),
+            evicted_groups: BTreeMap::new(),
+            seeded_nodes: FnvHashSet::default(),
+        }
+    }
+
+    /// Set the budget limiting the work spent on each group of nodes.
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
+    /// Set the token that stops the computation once it is cancelled.
+    pub fn set_cancellation_token(&mut self, token: Option<&CancellationToken>) {
+        self.cancellation_token = token.cloned();
+    }
+
+    /// Get the token that stops the computation once it is cancelled.
+    pub fn get_cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation_token.as_ref()
+    }
+
+    /// Returns `true` if the cancellation token of the computation was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Set the resident memory of the process in bytes
+    /// above which the values of stabilized groups of nodes get evicted.
+    /// If `None`, no values are evicted.
+    ///
+    /// Note that the memory usage can only be determined on Linux.
+    /// On other operating systems the limit has no effect.
+    pub fn set_memory_limit(&mut self, max_bytes: Option<u64>) {
+        self.restore_evicted_groups();
+        self.memory_limit = max_bytes;
+        self.node_groups.clear();
+        if max_bytes.is_none() {
+            return;
         }
+        let graph = self.fp_context.get_graph();
+        for node in graph.node_indices() {
+            let Some(group) = self.fp_context.get_budget_group(node) else {
+                continue;
+            };
+            let mut predecessors = graph
+                .neighbors_directed(node, Direction::Incoming)
+                .peekable();
+            let is_entry_node = predecessors.peek().is_none()
+                || predecessors.any(|predecessor| {
+                    self.fp_context.get_budget_group(predecessor) != Some(group)
+                });
+            let node_group = self.node_groups.entry(group).or_default();
+            node_group.nodes.push(node);
+            if is_entry_node {
+                node_group.entry_nodes.insert(node);
+            }
+        }
+    }
+
+    /// Get the groups of nodes that exhausted their budget.
+    /// The values of their nodes are over-approximations of the values of the smallest fixpoint.
+    pub fn get_exhausted_budget_groups(&self) -> &BTreeSet<usize> {
+        &self.exhausted_groups
     }
 
     /// Get the value of a node.
+    ///
+    /// If the value was evicted to save memory, the values of its group are recomputed
+    /// and kept until [`Computation::release_restored_groups`] is called.
     pub fn get_node_value(&self, node: NodeIndex) -> Option<&T::NodeValue> {
-        self.node_values.get(&node)
+        if let Some(value) = self.node_values.get(&node) {
+            return Some(value);
+        }
+        if self.evicted_groups.is_empty() {
+            return None;
+        }
+        let group = self.fp_context.get_budget_group(node)?;
+        let evicted_group = self.evicted_groups.get(&group)?;
+        evicted_group
+            .restored
+            .get_or_init(|| self.recompute_group(group, &evicted_group.entry_values))
+            .get(&node)
     }
 
     /// Set the value of a node and mark the node as not yet stabilized.
     pub fn set_node_value(&mut self, node: NodeIndex, value: T::NodeValue) {
+        self.ensure_resident(node);
+        self.seeded_nodes.insert(node);
+        self.update_node_value(node, value);
+    }
+
+    /// Replace the value of a node and mark the node as not yet stabilized.
+    fn update_node_value(&mut self, node: NodeIndex, value: T::NodeValue) {
         self.node_values.insert(node, value);
         self.worklist.insert(self.node_priority_list[node.index()]);
     }
 
     /// Merge the value at a node with some new value.
+    ///
+    /// If the group of the node exhausted its budget, the new value is over-approximated first.
     fn merge_node_value(&mut self, node: NodeIndex, value: T::NodeValue) {
+        self.ensure_resident(node);
+        let value = if self.is_budget_exhausted(node) {
+            self.fp_context.over_approximate(&value)
+        } else {
+            value
+        };
         if let Some(old_value) = self.node_values.get(&node) {
             let merged_value = self.fp_context.merge(&value, old_value);
             if merged_value != *old_value {
-                self.set_node_value(node, merged_value);
+                self.update_node_value(node, merged_value);
             }
         } else {
-            self.set_node_value(node, value);
+            self.update_node_value(node, value);
         }
     }
 
@@ -192,6 +513,217 @@ impl<T: Context> Computation<T> {
         }
     }
 
+    /// Update all outgoing edges of a node and add the work to the budget usage of its group.
+    ///
+    /// If the group exhausts its budget, the values of all its nodes are over-approximated.
+    /// If the memory limit is exceeded afterwards, the values of stabilized groups are evicted.
+    fn update_node_within_budget(&mut self, node: NodeIndex) {
+        self.update_node_within_budget_usage(node);
+        self.enforce_memory_limit();
+    }
+
+    /// Update all outgoing edges of a node and add the work to the budget usage of its group.
+    fn update_node_within_budget_usage(&mut self, node: NodeIndex) {
+        let group = if self.budget.is_limited() {
+            self.fp_context.get_budget_group(node)
+        } else {
+            None
+        };
+        let Some(group) = group else {
+            self.update_node(node);
+            return;
+        };
+        let start_time = Instant::now();
+        self.update_node(node);
+        let usage = self.budget_usage.entry(group).or_default();
+        usage.node_visits += 1;
+        usage.duration += start_time.elapsed();
+        if self.budget.is_exceeded(usage) && self.exhausted_groups.insert(group) {
+            self.over_approximate_group(group);
+        }
+    }
+
+    /// Returns `true` if the node belongs to a group that exhausted its budget.
+    fn is_budget_exhausted(&self, node: NodeIndex) -> bool {
+        !self.exhausted_groups.is_empty()
+            && self
+                .fp_context
+                .get_budget_group(node)
+                .is_some_and(|group| self.exhausted_groups.contains(&group))
+    }
+
+    /// Replace the values of all nodes of the group by their over-approximations
+    /// and mark the changed nodes as not yet stabilized.
+    fn over_approximate_group(&mut self, group: usize) {
+        let changed_values: Vec<(NodeIndex, T::NodeValue)> = self
+            .node_values
+            .iter()
+            .filter(|(node, _)| self.fp_context.get_budget_group(**node) == Some(group))
+            .filter_map(|(node, value)| {
+                let approximation = self.fp_context.over_approximate(value);
+                (approximation != *value).then_some((*node, approximation))
+            })
+            .collect();
+        for (node, value) in changed_values {
+            self.update_node_value(node, value);
+        }
+    }
+
+    /// Evict the values of all groups that contain no node of the worklist
+    /// if the resident memory of the process exceeds the memory limit.
+    ///
+    /// The memory usage is only checked every `memory_check_interval` calls.
+    /// Groups that exhausted their budget are never evicted,
+    /// since their values cannot be recomputed without over-approximation.
+    fn enforce_memory_limit(&mut self) {
+        let Some(memory_limit) = self.memory_limit else {
+            return;
+        };
+        self.node_visits_since_memory_check += 1;
+        if self.node_visits_since_memory_check < self.memory_check_interval {
+            return;
+        }
+        self.node_visits_since_memory_check = 0;
+        match get_resident_memory() {
+            Some(memory) if memory > memory_limit => (),
+            _ => return,
+        }
+        let active_groups: BTreeSet<usize> = self
+            .worklist
+            .iter()
+            .filter_map(|priority| {
+                self.fp_context
+                    .get_budget_group(self.priority_to_node_list[*priority])
+            })
+            .collect();
+        let evictable_groups: Vec<usize> = self
+            .node_groups
+            .keys()
+            .filter(|group| {
+                !active_groups.contains(group)
+                    && !self.exhausted_groups.contains(group)
+                    && !self.evicted_groups.contains_key(group)
+            })
+            .copied()
+            .collect();
+        if evictable_groups.is_empty() {
+            return;
+        }
+        timed_logging(format!(
+            "Fixpoint: Memory limit exceeded. Evicting the values of {} groups.",
+            evictable_groups.len()
+        ));
+        for group in evictable_groups {
+            self.evict_group(group);
+        }
+    }
+
+    /// Remove the values of all nodes of the group except for the values of its entry nodes.
+    fn evict_group(&mut self, group: usize) {
+        let Some(node_group) = self.node_groups.get(&group) else {
+            return;
+        };
+        let mut entry_values = FnvHashMap::default();
+        for node in node_group.nodes.iter() {
+            if let Some(value) = self.node_values.remove(node) {
+                if node_group.entry_nodes.contains(node) || self.seeded_nodes.contains(node) {
+                    entry_values.insert(*node, value);
+                }
+            }
+        }
+        self.evicted_groups.insert(
+            group,
+            EvictedGroup {
+                entry_values,
+                restored: OnceLock::new(),
+            },
+        );
+    }
+
+    /// Recompute the values of all nodes of an evicted group from the values of its entry nodes.
+    ///
+    /// Only edges inside the group are taken into account.
+    fn recompute_group(
+        &self,
+        group: usize,
+        entry_values: &FnvHashMap<NodeIndex, T::NodeValue>,
+    ) -> FnvHashMap<NodeIndex, T::NodeValue> {
+        let graph = self.fp_context.get_graph();
+        let mut values = entry_values.clone();
+        let mut worklist: BTreeSet<usize> = values
+            .keys()
+            .map(|node| self.node_priority_list[node.index()])
+            .collect();
+        while let Some(priority) = worklist.pop_last() {
+            let node = self.priority_to_node_list[priority];
+            let Some(value) = values.get(&node) else {
+                continue;
+            };
+            let new_values: Vec<(NodeIndex, T::NodeValue)> = graph
+                .edges(node)
+                .filter(|edge| self.fp_context.get_budget_group(edge.target()) == Some(group))
+                .filter_map(|edge| {
+                    self.fp_context
+                        .update_edge(value, edge.id())
+                        .map(|new_value| (edge.target(), new_value))
+                })
+                .collect();
+            for (end_node, new_value) in new_values {
+                let merged_value = match values.get(&end_node) {
+                    Some(old_value) => {
+                        let merged_value = self.fp_context.merge(&new_value, old_value);
+                        if merged_value == *old_value {
+                            continue;
+                        }
+                        merged_value
+                    }
+                    None => new_value,
+                };
+                values.insert(end_node, merged_value);
+                worklist.insert(self.node_priority_list[end_node.index()]);
+            }
+        }
+        values
+    }
+
+    /// Restore the values of the group of the node if they were evicted.
+    fn ensure_resident(&mut self, node: NodeIndex) {
+        if self.evicted_groups.is_empty() {
+            return;
+        }
+        if let Some(group) = self.fp_context.get_budget_group(node) {
+            self.restore_group(group);
+        }
+    }
+
+    /// Restore the values of the group if they were evicted.
+    fn restore_group(&mut self, group: usize) {
+        let Some(mut evicted_group) = self.evicted_groups.remove(&group) else {
+            return;
+        };
+        let values = match evicted_group.restored.take() {
+            Some(values) => values,
+            None => self.recompute_group(group, &evicted_group.entry_values),
+        };
+        self.node_values.extend(values);
+    }
+
+    /// Restore the values of all groups that were evicted to save memory.
+    pub fn restore_evicted_groups(&mut self) {
+        let groups: Vec<usize> = self.evicted_groups.keys().copied().collect();
+        for group in groups {
+            self.restore_group(group);
+        }
+    }
+
+    /// Free the values of evicted groups that were recomputed by [`Computation::get_node_value`].
+    /// They are recomputed again once they are needed.
+    pub fn release_restored_groups(&mut self) {
+        for evicted_group in self.evicted_groups.values_mut() {
+            evicted_group.restored.take();
+        }
+    }
+
     /// Remove the highest priority node from the internal worklist and return it.
     fn take_next_node_from_worklist(&mut self) -> Option<NodeIndex> {
         if let Some(priority) = self.worklist.iter().next_back().cloned() {
@@ -205,42 +737,89 @@ impl<T: Context> Computation<T> {
     /// Compute the fixpoint of the fixpoint problem.
     /// Each node will be visited at most max_steps times.
     /// If a node does not stabilize after max_steps visits, the end result will not be a fixpoint but only an intermediate result of a fixpoint computation.
+    /// The same holds if the computation is cancelled.
     pub fn compute_with_max_steps(&mut self, max_steps: u64) {
         timed_logging("Fixpoint: calling compute_with_max_steps");
         let mut max_seen_step = 0;
         let mut steps = vec![0; self.fp_context.get_graph().node_count()];
         let mut non_stabilized_nodes = BTreeSet::new();
         while let Some(priority) = self.worklist.iter().next_back().cloned() {
+            if self.is_cancelled() {
+                break;
+            }
             let priority = self.worklist.take(&priority).unwrap();
             let node = self.priority_to_node_list[priority];
-            if steps[node.index()] < 100000 { // TODO : remettre max_steps
+            if steps[node.index()] < 100000 {
+                // TODO : remettre max_steps
                 steps[node.index()] += 1;
-                self.update_node(node);
+                self.update_node_within_budget(node);
             } else {
                 non_stabilized_nodes.insert(priority);
-                timed_logging(format!("Fixpoint: Non-stabilized nodes after {} steps.",max_steps));
+                timed_logging(format!(
+                    "Fixpoint: Non-stabilized nodes after {} steps.",
+                    max_steps
+                ));
             }
-            if steps[node.index()] > max_seen_step
-            {
+            if steps[node.index()] > max_seen_step {
                 max_seen_step = steps[node.index()];
-                timed_logging(format!("Fixpoint:  max seen step {}",max_seen_step));
+                timed_logging(format!("Fixpoint:  max seen step {}", max_seen_step));
             }
-
-
         }
         // After the algorithm finished, the new worklist is the list of non-stabilized nodes
-        self.worklist = non_stabilized_nodes;
+        self.worklist.append(&mut non_stabilized_nodes);
     }
 
     /// Compute the fixpoint of the fixpoint problem.
-    /// If the fixpoint algorithm does not converge to a fixpoint, this function will not terminate.
+    /// If the fixpoint algorithm does not converge to a fixpoint, this function will not terminate
+    /// unless the computation is cancelled.
     pub fn compute(&mut self) {
-        while let Some(node) = self.take_next_node_from_worklist() {
-            self.update_node(node);
+        while !self.is_cancelled() {
+            let Some(node) = self.take_next_node_from_worklist() else {
+                break;
+            };
+            self.update_node_within_budget(node);
+        }
+    }
+
+    /// Remove all node values and mark all nodes as stabilized,
+    /// so that the computation can be reused for new starting values.
+    pub fn reset(&mut self) {
+        self.node_values.clear();
+        self.worklist.clear();
+        self.budget_usage.clear();
+        self.exhausted_groups.clear();
+        self.evicted_groups.clear();
+        self.seeded_nodes.clear();
+    }
+
+    /// Merge the node values of another computation on the same graph into this computation.
+    ///
+    /// Nodes with a value in both computations get the merge of both values.
+    /// Only nodes marked as not yet stabilized in the other computation are added to the worklist.
+    /// Groups that exhausted their budget in the other computation are also marked as exhausted.
+    /// Note that the merged values are only a fixpoint
+    /// if both computations were stabilized and did not influence each other.
+    pub fn merge_computation(&mut self, mut other: Computation<T>) {
+        self.restore_evicted_groups();
+        other.restore_evicted_groups();
+        self.exhausted_groups.extend(&other.exhausted_groups);
+        self.seeded_nodes.extend(other.node_values.keys());
+        for node in other.get_worklist() {
+            self.worklist.insert(self.node_priority_list[node.index()]);
+        }
+        for (node, value) in other.node_values {
+            let merged_value = match self.node_values.get(&node) {
+                Some(old_value) => self.fp_context.merge(&value, old_value),
+                None => value,
+            };
+            self.node_values.insert(node, merged_value);
         }
     }
 
     /// Get a reference to the internal map where one can look up the current values of all nodes
+    ///
+    /// Values that were evicted to save memory are not contained in the map,
+    /// see [`Computation::restore_evicted_groups`].
     pub fn node_values(&self) -> &FnvHashMap<NodeIndex, T::NodeValue> {
         &self.node_values
     }
@@ -248,9 +827,11 @@ impl<T: Context> Computation<T> {
     /// Get a mutable iterator over all node values.
     /// Also add all nodes that have values to the worklist, because one can change all their values through the iterator.
     pub fn node_values_mut(&mut self) -> impl Iterator<Item = &mut T::NodeValue> {
+        self.restore_evicted_groups();
         for node in self.node_values.keys() {
             let priority = self.node_priority_list[node.index()];
             self.worklist.insert(priority);
+            self.seeded_nodes.insert(*node);
         }
         self.node_values.values_mut()
     }
@@ -327,6 +908,62 @@ mod tests {
         assert_eq!(0, *solution.get_node_value(NodeIndex::new(5)).unwrap());
     }
 
+    #[test]
+    fn merge_computation() {
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..5 {
+            graph.add_node(());
+        }
+        graph.add_edge(NodeIndex::new(0), NodeIndex::new(1), 1);
+        graph.add_edge(NodeIndex::new(1), NodeIndex::new(4), 1);
+        graph.add_edge(NodeIndex::new(2), NodeIndex::new(3), 2);
+        graph.add_edge(NodeIndex::new(3), NodeIndex::new(4), 2);
+
+        let context = || FPContext {
+            graph: graph.clone(),
+        };
+        let mut first = Computation::new(context(), None);
+        first.set_node_value(NodeIndex::new(0), 0);
+        first.compute();
+        let mut second = Computation::new(context(), None);
+        second.set_node_value(NodeIndex::new(2), 3);
+        second.compute();
+        let mut combined = Computation::new(context(), None);
+        combined.set_node_value(NodeIndex::new(0), 0);
+        combined.set_node_value(NodeIndex::new(2), 3);
+        combined.compute();
+
+        first.merge_computation(second);
+        assert!(first.has_stabilized());
+        assert_eq!(first.node_values(), combined.node_values());
+        assert_eq!(2, *first.get_node_value(NodeIndex::new(4)).unwrap());
+    }
+
+    #[test]
+    fn cancellation() {
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..3 {
+            graph.add_node(());
+        }
+        graph.add_edge(NodeIndex::new(0), NodeIndex::new(1), 1);
+        graph.add_edge(NodeIndex::new(1), NodeIndex::new(2), 1);
+
+        let token = CancellationToken::new();
+        let mut computation = Computation::new(FPContext { graph }, None);
+        computation.set_cancellation_token(Some(&token));
+        computation.set_node_value(NodeIndex::new(0), 0);
+        token.cancel();
+        computation.compute_with_max_steps(20);
+        assert!(!computation.has_stabilized());
+        assert_eq!(computation.get_worklist(), vec![NodeIndex::new(0)]);
+        assert!(computation.get_node_value(NodeIndex::new(1)).is_none());
+
+        computation.set_cancellation_token(None);
+        computation.compute();
+        assert!(computation.has_stabilized());
+        assert_eq!(2, *computation.get_node_value(NodeIndex::new(2)).unwrap());
+    }
+
     #[test]
     fn fixpoint_with_default_value() {
         let mut graph: DiGraph<(), u64> = DiGraph::new();
@@ -387,4 +1024,256 @@ mod tests {
             Some(NodeIndex::new(20))
         );
     }
+
+    #[test]
+    fn worklist_orders_reach_same_fixpoint() {
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..101 {
+            graph.add_node(());
+        }
+        for i in 0..100 {
+            graph.add_edge(NodeIndex::new(i), NodeIndex::new(i + 1), i as u64 % 10 + 1);
+        }
+        for i in 0..10 {
+            graph.add_edge(NodeIndex::new(i * 10 + 9), NodeIndex::new(i * 10), 0);
+            graph.add_edge(NodeIndex::new(i * 10), NodeIndex::new(i * 10 + 5), 0);
+        }
+        let mut reference = Computation::new(
+            FPContext {
+                graph: graph.clone(),
+            },
+            None,
+        );
+        reference.set_node_value(NodeIndex::new(0), 0);
+        reference.compute();
+
+        for order in [
+            WorklistOrder::StronglyConnectedComponents,
+            WorklistOrder::ReversePostOrder,
+            WorklistOrder::InDegree,
+        ] {
+            let mut computation = Computation::new_with_worklist_order(
+                FPContext {
+                    graph: graph.clone(),
+                },
+                None,
+                order,
+            );
+            assert_eq!(
+                order.compute_node_priority_list(&graph).len(),
+                graph.node_count()
+            );
+            computation.set_node_value(NodeIndex::new(0), 0);
+            computation.compute();
+            assert!(computation.has_stabilized());
+            assert_eq!(computation.node_values(), reference.node_values());
+        }
+    }
+
+    #[test]
+    fn reverse_post_order() {
+        // A loop 1 -> 2 -> 3 -> 1 entered from node 0, a diamond 3 -> {4, 5} -> 6
+        // and a cycle 7 <-> 8 that is not reachable from a node without incoming edges.
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..9 {
+            graph.add_node(());
+        }
+        for (start, end) in [
+            (0, 1),
+            (1, 2),
+            (2, 3),
+            (3, 1),
+            (3, 4),
+            (3, 5),
+            (4, 6),
+            (5, 6),
+            (7, 8),
+            (8, 7),
+        ] {
+            graph.add_edge(NodeIndex::new(start), NodeIndex::new(end), 1);
+        }
+        let priority_list = WorklistOrder::ReversePostOrder.compute_node_priority_list(&graph);
+        assert_eq!(priority_list.len(), 9);
+        let priority = |node: usize| {
+            priority_list
+                .iter()
+                .position(|index| index.index() == node)
+                .unwrap()
+        };
+        assert!(priority(0) > priority(1));
+        assert!(priority(1) > priority(2));
+        assert!(priority(2) > priority(3));
+        assert!(priority(3) > priority(4));
+        assert!(priority(3) > priority(5));
+        assert!(priority(4) > priority(6));
+        assert!(priority(5) > priority(6));
+        // The depth-first search reaches the cycle last, so it is worked on first.
+        assert!(priority(7) > priority(0));
+        assert!(priority(8) > priority(0));
+    }
+
+    #[test]
+    fn in_degree_order() {
+        // Inside the loop 0 -> 1 -> 2 -> 0 with additional edges 0 -> 2 and 1 -> 2
+        // the node 2 has the most incoming edges and is thus worked on last.
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..3 {
+            graph.add_node(());
+        }
+        for (start, end) in [(0, 1), (1, 2), (2, 0), (0, 2), (1, 2)] {
+            graph.add_edge(NodeIndex::new(start), NodeIndex::new(end), 1);
+        }
+        let priority_list = WorklistOrder::InDegree.compute_node_priority_list(&graph);
+        assert_eq!(priority_list[0], NodeIndex::new(2));
+    }
+
+    /// A context where values grow forever inside loops.
+    /// Nodes with an index below `num_grouped_nodes` form the budget group 0.
+    struct GrowingContext {
+        graph: DiGraph<(), u64>,
+        num_grouped_nodes: usize,
+    }
+
+    impl Context for GrowingContext {
+        type EdgeLabel = u64;
+        type NodeLabel = ();
+        type NodeValue = u64;
+
+        fn get_graph(&self) -> &DiGraph<(), u64> {
+            &self.graph
+        }
+
+        fn merge(&self, val1: &Self::NodeValue, val2: &Self::NodeValue) -> Self::NodeValue {
+            std::cmp::max(*val1, *val2)
+        }
+
+        fn update_edge(&self, value: &Self::NodeValue, edge: EdgeIndex) -> Option<Self::NodeValue> {
+            Some(value.saturating_add(*self.graph.edge_weight(edge).unwrap()))
+        }
+
+        fn get_budget_group(&self, node: NodeIndex) -> Option<usize> {
+            (node.index() < self.num_grouped_nodes).then_some(0)
+        }
+
+        fn over_approximate(&self, _value: &Self::NodeValue) -> Self::NodeValue {
+            u64::MAX
+        }
+    }
+
+    #[test]
+    fn exhausted_budget() {
+        // The loop 0 -> 1 -> 2 -> 0 never stabilizes, the node 3 after the loop is not grouped.
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..4 {
+            graph.add_node(());
+        }
+        for (start, end) in [(0, 1), (1, 2), (2, 0), (2, 3)] {
+            graph.add_edge(NodeIndex::new(start), NodeIndex::new(end), 1);
+        }
+        let mut computation = Computation::new(
+            GrowingContext {
+                graph,
+                num_grouped_nodes: 3,
+            },
+            None,
+        );
+        computation.set_budget(Budget {
+            max_node_visits: Some(30),
+            max_milliseconds: None,
+        });
+        computation.set_node_value(NodeIndex::new(0), 0);
+        computation.compute();
+
+        assert!(computation.has_stabilized());
+        assert_eq!(
+            computation.get_exhausted_budget_groups(),
+            &BTreeSet::from([0])
+        );
+        for node in 0..4 {
+            assert_eq!(
+                *computation.get_node_value(NodeIndex::new(node)).unwrap(),
+                u64::MAX
+            );
+        }
+    }
+
+    /// A context where each group contains `group_size` consecutive nodes.
+    struct GroupedContext {
+        graph: DiGraph<(), u64>,
+        group_size: usize,
+    }
+
+    impl Context for GroupedContext {
+        type EdgeLabel = u64;
+        type NodeLabel = ();
+        type NodeValue = u64;
+
+        fn get_graph(&self) -> &DiGraph<(), u64> {
+            &self.graph
+        }
+
+        fn merge(&self, val1: &Self::NodeValue, val2: &Self::NodeValue) -> Self::NodeValue {
+            std::cmp::min(*val1, *val2)
+        }
+
+        fn update_edge(&self, value: &Self::NodeValue, edge: EdgeIndex) -> Option<Self::NodeValue> {
+            Some(value + self.graph.edge_weight(edge).unwrap())
+        }
+
+        fn get_budget_group(&self, node: NodeIndex) -> Option<usize> {
+            Some(node.index() / self.group_size)
+        }
+    }
+
+    #[test]
+    fn memory_limit() {
+        // Two groups 0..5 and 5..10 connected by the edge 4 -> 5, each containing a loop.
+        let mut graph: DiGraph<(), u64> = DiGraph::new();
+        for _i in 0..10 {
+            graph.add_node(());
+        }
+        for i in 0..9 {
+            graph.add_edge(NodeIndex::new(i), NodeIndex::new(i + 1), 1);
+        }
+        graph.add_edge(NodeIndex::new(4), NodeIndex::new(1), 0);
+        graph.add_edge(NodeIndex::new(9), NodeIndex::new(6), 0);
+        let mut computation = Computation::new(
+            GroupedContext {
+                graph,
+                group_size: 5,
+            },
+            None,
+        );
+        // Every check of the memory usage exceeds the limit.
+        computation.set_memory_limit(Some(0));
+        computation.memory_check_interval = 1;
+        computation.set_node_value(NodeIndex::new(0), 0);
+        computation.compute();
+
+        assert!(computation.has_stabilized());
+        if get_resident_memory().is_some() {
+            assert_eq!(computation.evicted_groups.len(), 2);
+            assert_eq!(computation.node_values().len(), 0);
+        }
+        for node in 0..10 {
+            assert_eq!(
+                computation.get_node_value(NodeIndex::new(node)),
+                Some(&(node as u64))
+            );
+        }
+        computation.release_restored_groups();
+
+        // Changing the entry value of an evicted group recomputes the group.
+        computation.set_node_value(NodeIndex::new(5), 0);
+        computation.compute();
+        for node in 5..10 {
+            assert_eq!(
+                computation.get_node_value(NodeIndex::new(node)),
+                Some(&(node as u64 - 5))
+            );
+        }
+        computation.restore_evicted_groups();
+        assert_eq!(computation.node_values().len(), 10);
+        assert_eq!(computation.node_values()[&NodeIndex::new(3)], 3);
+    }
 }
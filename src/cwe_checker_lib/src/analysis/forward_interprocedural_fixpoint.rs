@@ -10,11 +10,13 @@
 //! The `Computation` object provides the necessary methods for the actual fixpoint computation.
 
 use super::fixpoint::Context as GeneralFPContext;
+use super::fixpoint::WorklistOrder;
 use super::graph::*;
 use super::interprocedural_fixpoint_generic::*;
 use crate::intermediate_representation::*;
 use petgraph::graph::EdgeIndex;
 use petgraph::graph::NodeIndex;
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
 
 /// The context for an interprocedural fixpoint computation.
@@ -85,23 +87,64 @@ pub trait Context<'a> {
         block_before_condition: &Term<Blk>,
         is_true: bool,
     ) -> Option<Self::Value>;
+
+    /// Over-approximate a value at a node of a function that exhausted its analysis budget,
+    /// see [`Budget`](super::fixpoint::Budget).
+    /// The default implementation returns the value unchanged,
+    /// i.e. the analysis of the function continues without approximation.
+    fn over_approximate(&self, value: &Self::Value) -> Self::Value {
+        value.clone()
+    }
 }
 
 /// This struct is a wrapper to create a general fixpoint context out of an interprocedural fixpoint context.
+///
+/// The nodes of each function form a budget group of the fixpoint computation,
+/// so that the [`Budget`](super::fixpoint::Budget) of the computation limits the work spent on each function.
 pub struct GeneralizedContext<'a, T: Context<'a>> {
     context: T,
+    /// Maps the index of each node to the budget group of the function containing it.
+    node_to_budget_group: Vec<usize>,
+    /// The TIDs of the functions of the graph, indexed by their budget group.
+    budget_group_functions: Vec<Tid>,
     _phantom_graph_reference: PhantomData<Graph<'a>>,
 }
 
 impl<'a, T: Context<'a>> GeneralizedContext<'a, T> {
     /// Create a new generalized context out of an interprocedural context object.
     pub fn new(context: T) -> Self {
+        let mut function_to_budget_group: BTreeMap<&Tid, usize> = BTreeMap::new();
+        let mut budget_group_functions = Vec::new();
+        let node_to_budget_group = context
+            .get_graph()
+            .node_weights()
+            .map(|node| {
+                let sub = match node {
+                    Node::BlkStart(_, sub) | Node::BlkEnd(_, sub) => sub,
+                    Node::CallReturn { call: (_, sub), .. }
+                    | Node::CallSource {
+                        source: (_, sub), ..
+                    } => sub,
+                };
+                *function_to_budget_group.entry(&sub.tid).or_insert_with(|| {
+                    budget_group_functions.push(sub.tid.clone());
+                    budget_group_functions.len() - 1
+                })
+            })
+            .collect();
         GeneralizedContext {
             context,
+            node_to_budget_group,
+            budget_group_functions,
             _phantom_graph_reference: PhantomData,
         }
     }
 
+    /// Get the TID of the function whose nodes form the given budget group.
+    pub fn get_function_of_budget_group(&self, group: usize) -> Option<&Tid> {
+        self.budget_group_functions.get(group)
+    }
+
     /// Get the inner context object.
     pub fn get_context(&self) -> &T {
         &self.context
@@ -142,6 +185,29 @@ impl<'a, T: Context<'a>> GeneralFPContext for GeneralizedContext<'a, T> {
         }
     }
 
+    /// The nodes of each function form a budget group.
+    fn get_budget_group(&self, node: NodeIndex) -> Option<usize> {
+        self.node_to_budget_group.get(node.index()).copied()
+    }
+
+    /// Over-approximate the contained values using the interprocedural context object.
+    fn over_approximate(&self, value: &Self::NodeValue) -> Self::NodeValue {
+        match value {
+            NodeValue::Value(value) => NodeValue::Value(self.context.over_approximate(value)),
+            NodeValue::CallFlowCombinator {
+                call_stub,
+                interprocedural_flow,
+            } => NodeValue::CallFlowCombinator {
+                call_stub: call_stub
+                    .as_ref()
+                    .map(|value| self.context.over_approximate(value)),
+                interprocedural_flow: interprocedural_flow
+                    .as_ref()
+                    .map(|value| self.context.over_approximate(value)),
+            },
+        }
+    }
+
     /// Forward edge transition function.
     /// Applies the transition functions from the interprocedural context object
     /// corresponding to the type of the provided edge.
@@ -268,6 +334,21 @@ pub fn create_computation<'a, T: Context<'a>>(
     super::fixpoint::Computation::new(generalized_problem, default_value.map(NodeValue::Value))
 }
 
+/// Generate a new computation from the corresponding context and an optional default value for nodes.
+/// The nodes are taken from the worklist in the order given by `worklist_order`.
+pub fn create_computation_with_worklist_order<'a, T: Context<'a>>(
+    problem: T,
+    default_value: Option<T::Value>,
+    worklist_order: WorklistOrder,
+) -> super::fixpoint::Computation<GeneralizedContext<'a, T>> {
+    let generalized_problem = GeneralizedContext::new(problem);
+    super::fixpoint::Computation::new_with_worklist_order(
+        generalized_problem,
+        default_value.map(NodeValue::Value),
+        worklist_order,
+    )
+}
+
 /// Returns a node ordering with callee nodes behind caller nodes.
 pub fn create_bottom_up_worklist(graph: &Graph) -> Vec<NodeIndex> {
     let mut graph = graph.clone();
@@ -0,0 +1,440 @@
+//! A classification of the side effects of functions.
+//!
+//! For each function in the program we compute which kinds of side effects it may have,
+//! e.g. whether it writes to global memory, allocates memory or performs I/O,
+//! and which registers it may overwrite.
+//! The classification includes the effects of all (direct) callees of the function.
+//!
+//! The classification is computed on the intermediate representation alone,
+//! so that it is also available to normalization passes that run before the other analyses.
+//! Therefore it is a coarse overapproximation:
+//! - Memory accesses are only recognized as accesses to the current stack frame
+//!   if the address is computed from the stack pointer inside the same basic block.
+//!   All other accesses through pointers are classified as writes to (non-global) memory.
+//! - Accesses to constant addresses are classified as accesses to global memory.
+//! - The effects of extern functions are looked up in hardcoded tables of well-known library functions.
+//!   Calls to unknown extern functions, indirect calls and user-defined operations are classified as unknown effects.
+//!   The registers overwritten by such calls are also unknown.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Extern functions that allocate memory.
+const ALLOCATING_SYMBOLS: &[&str] = &[
+    "calloc",
+    "malloc",
+    "mmap",
+    "operator.new",
+    "operator.new[]",
+    "realloc",
+    "reallocarray",
+    "strdup",
+    "strndup",
+    "xmalloc",
+];
+
+/// Extern functions that free memory.
+const FREEING_SYMBOLS: &[&str] = &[
+    "free",
+    "munmap",
+    "operator.delete",
+    "operator.delete[]",
+    "realloc",
+    "reallocarray",
+];
+
+/// Extern functions that perform I/O operations.
+const IO_SYMBOLS: &[&str] = &[
+    "accept", "close", "connect", "fclose", "fgets", "fopen", "fprintf", "fputs", "fread",
+    "fscanf", "fwrite", "getchar", "ioctl", "open", "perror", "popen", "printf", "putchar", "puts",
+    "read", "recv", "recvfrom", "scanf", "send", "sendto", "socket", "system", "write",
+];
+
+/// Extern functions that write to memory provided through their parameters.
+const MEMORY_WRITING_SYMBOLS: &[&str] = &[
+    "memcpy", "memmove", "memset", "snprintf", "sprintf", "strcat", "strcpy", "strncat", "strncpy",
+];
+
+/// Extern functions without side effects (apart from reading memory provided through their parameters).
+const SIDE_EFFECT_FREE_SYMBOLS: &[&str] = &[
+    "abs",
+    "isalnum",
+    "isalpha",
+    "isdigit",
+    "isspace",
+    "labs",
+    "memcmp",
+    "strcasecmp",
+    "strchr",
+    "strcmp",
+    "strlen",
+    "strncmp",
+    "strnlen",
+    "strrchr",
+    "strstr",
+    "tolower",
+    "toupper",
+];
+
+/// The kinds of side effects that a function may have.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum SideEffect {
+    /// The function reads from global memory.
+    ReadsGlobals,
+    /// The function writes to global memory.
+    WritesGlobals,
+    /// The function writes to memory that is neither global memory nor its own stack frame,
+    /// e.g. to memory provided by the caller.
+    WritesMemory,
+    /// The function allocates memory.
+    Allocates,
+    /// The function frees memory.
+    Frees,
+    /// The function performs I/O operations.
+    PerformsIo,
+    /// The function may not return to its caller.
+    MayNotReturn,
+    /// The function calls code whose effects are unknown.
+    Unknown,
+}
+
+/// The side effects of a function, including the effects of its callees.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct FunctionEffects {
+    /// The kinds of side effects that the function may have.
+    pub effects: BTreeSet<SideEffect>,
+    /// The registers that the function may overwrite.
+    /// `None` if the overwritten registers are unknown,
+    /// e.g. because the function calls extern functions.
+    pub written_registers: Option<BTreeSet<Variable>>,
+}
+
+impl FunctionEffects {
+    /// Create a new object for a function without any side effects.
+    pub fn new() -> FunctionEffects {
+        FunctionEffects {
+            effects: BTreeSet::new(),
+            written_registers: Some(BTreeSet::new()),
+        }
+    }
+
+    /// Returns `true` if the function has no side effects and does not read global memory.
+    /// Note that the function may still overwrite registers.
+    pub fn is_pure(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Returns `true` if the function has no side effects apart from reading global memory.
+    /// Note that the function may still overwrite registers.
+    pub fn is_side_effect_free(&self) -> bool {
+        self.effects
+            .iter()
+            .all(|effect| *effect == SideEffect::ReadsGlobals)
+    }
+
+    /// Returns `true` if the function may overwrite the given register.
+    pub fn may_write_register(&self, var: &Variable) -> bool {
+        match &self.written_registers {
+            Some(written_registers) => written_registers.contains(var),
+            None => true,
+        }
+    }
+
+    /// Add the effects of `other` to `self`.
+    /// Returns `true` if `self` changed.
+    fn add(&mut self, other: &FunctionEffects) -> bool {
+        let old_num_effects = self.effects.len();
+        self.effects.extend(other.effects.iter().copied());
+        let mut changed = self.effects.len() != old_num_effects;
+        match (&mut self.written_registers, &other.written_registers) {
+            (Some(written_registers), Some(other_registers)) => {
+                let old_num_registers = written_registers.len();
+                written_registers.extend(other_registers.iter().cloned());
+                changed |= written_registers.len() != old_num_registers;
+            }
+            (Some(_), None) => {
+                self.written_registers = None;
+                changed = true;
+            }
+            (None, _) => (),
+        }
+        changed
+    }
+}
+
+impl Default for FunctionEffects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compute the side effects of all functions in the project.
+pub fn compute_function_effects(project: &Project) -> BTreeMap<Tid, FunctionEffects> {
+    let mut function_effects = BTreeMap::new();
+    let mut callees = BTreeMap::new();
+    for (sub_tid, sub) in project.program.term.subs.iter() {
+        let (effects, sub_callees) = compute_local_effects(project, sub);
+        function_effects.insert(sub_tid.clone(), effects);
+        callees.insert(sub_tid.clone(), sub_callees);
+    }
+    // Add the effects of callees to their callers until a fixpoint is reached.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (sub_tid, sub_callees) in callees.iter() {
+            for callee_tid in sub_callees {
+                if callee_tid == sub_tid {
+                    continue;
+                }
+                let callee_effects = function_effects[callee_tid].clone();
+                changed |= function_effects
+                    .get_mut(sub_tid)
+                    .unwrap()
+                    .add(&callee_effects);
+            }
+        }
+    }
+    function_effects
+}
+
+/// Compute the side effects of the instructions of the given function
+/// and the effects of calls to extern functions.
+/// Also return the TIDs of all internal functions called by the function.
+fn compute_local_effects(project: &Project, sub: &Term<Sub>) -> (FunctionEffects, BTreeSet<Tid>) {
+    let mut effects = FunctionEffects::new();
+    let mut callees = BTreeSet::new();
+    let mut has_return = false;
+    for block in sub.term.blocks.iter() {
+        add_effects_of_defs(project, &block.term.defs, &mut effects);
+        for jmp in block.term.jmps.iter() {
+            match &jmp.term {
+                Jmp::Call { target, .. } => {
+                    if project.program.term.subs.contains_key(target) {
+                        callees.insert(target.clone());
+                    } else if let Some(symbol) = project.program.term.extern_symbols.get(target) {
+                        effects.add(&get_extern_symbol_effects(symbol));
+                    } else {
+                        effects.add(&unknown_effects());
+                    }
+                }
+                Jmp::CallInd { .. } | Jmp::CallOther { .. } => {
+                    effects.add(&unknown_effects());
+                }
+                Jmp::Return(_) => has_return = true,
+                Jmp::Branch(_) | Jmp::BranchInd(_) | Jmp::CBranch { .. } => (),
+            }
+        }
+    }
+    if !has_return {
+        effects.effects.insert(SideEffect::MayNotReturn);
+    }
+    (effects, callees)
+}
+
+/// Add the effects of the given `Def` instructions to `effects`.
+///
+/// Memory accesses through addresses computed from the stack pointer inside the block
+/// are accesses to the current stack frame and thus have no side effect.
+fn add_effects_of_defs(project: &Project, defs: &[Term<Def>], effects: &mut FunctionEffects) {
+    let mut stack_derived_vars = BTreeSet::from([project.stack_pointer_register.clone()]);
+    let is_stack_address = |address: &Expression, stack_derived_vars: &BTreeSet<Variable>| {
+        let input_vars = address.input_vars();
+        !input_vars.is_empty()
+            && input_vars
+                .into_iter()
+                .all(|var| stack_derived_vars.contains(var))
+    };
+    for def in defs {
+        match &def.term {
+            Def::Store { address, .. } => {
+                if let Expression::Const(_) = address {
+                    effects.effects.insert(SideEffect::WritesGlobals);
+                } else if !is_stack_address(address, &stack_derived_vars) {
+                    effects.effects.insert(SideEffect::WritesMemory);
+                }
+            }
+            Def::Load { var, address } => {
+                if let Expression::Const(_) = address {
+                    effects.effects.insert(SideEffect::ReadsGlobals);
+                }
+                stack_derived_vars.remove(var);
+                add_written_register(effects, var);
+            }
+            Def::Assign { var, value } => {
+                if is_stack_address(value, &stack_derived_vars) {
+                    stack_derived_vars.insert(var.clone());
+                } else {
+                    stack_derived_vars.remove(var);
+                }
+                add_written_register(effects, var);
+            }
+        }
+    }
+}
+
+/// Add the given variable to the written registers, unless it is a temporary variable.
+fn add_written_register(effects: &mut FunctionEffects, var: &Variable) {
+    if let (Some(written_registers), false) = (&mut effects.written_registers, var.is_temp) {
+        written_registers.insert(var.clone());
+    }
+}
+
+/// Get the effects of calls to code whose effects are unknown.
+fn unknown_effects() -> FunctionEffects {
+    FunctionEffects {
+        effects: BTreeSet::from([SideEffect::Unknown]),
+        written_registers: None,
+    }
+}
+
+/// Get the effects of a call to the given extern symbol.
+///
+/// Since we do not know which registers an extern function overwrites,
+/// the overwritten registers are always unknown.
+fn get_extern_symbol_effects(symbol: &ExternSymbol) -> FunctionEffects {
+    let name = symbol.name.as_str();
+    let mut effects = BTreeSet::new();
+    if ALLOCATING_SYMBOLS.contains(&name) {
+        effects.insert(SideEffect::Allocates);
+    }
+    if FREEING_SYMBOLS.contains(&name) {
+        effects.insert(SideEffect::Frees);
+    }
+    if IO_SYMBOLS.contains(&name) {
+        effects.insert(SideEffect::PerformsIo);
+    }
+    if MEMORY_WRITING_SYMBOLS.contains(&name) {
+        effects.insert(SideEffect::WritesMemory);
+    }
+    if effects.is_empty() && !SIDE_EFFECT_FREE_SYMBOLS.contains(&name) {
+        effects.insert(SideEffect::Unknown);
+    }
+    if symbol.no_return {
+        effects.insert(SideEffect::MayNotReturn);
+    }
+    FunctionEffects {
+        effects,
+        written_registers: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{defs, expr, variable};
+
+    fn mock_sub(name: &str, defs: Vec<Term<Def>>, jmps: Vec<Term<Jmp>>) -> Term<Sub> {
+        let block = Term {
+            tid: Tid::new(format!("{name}_blk")),
+            term: Blk {
+                defs,
+                jmps,
+                indirect_jmp_targets: Vec::new(),
+            },
+        };
+        Term {
+            tid: Tid::new(name),
+            term: Sub {
+                name: name.to_string(),
+                blocks: vec![block],
+                calling_convention: None,
+            },
+        }
+    }
+
+    fn mock_jmp(name: &str, jmp: Jmp) -> Term<Jmp> {
+        Term {
+            tid: Tid::new(name),
+            term: jmp,
+        }
+    }
+
+    #[test]
+    fn function_effects() {
+        let mut project = Project::mock_x64();
+        let free = ExternSymbol::mock_x64("free");
+        project
+            .program
+            .term
+            .extern_symbols
+            .insert(free.tid.clone(), free.clone());
+        let return_jmp = || Jmp::Return(expr!("0x0:8"));
+        let subs = [
+            mock_sub(
+                "pure",
+                defs![
+                    "RSP:8 = RSP:8 + -8:8",
+                    "Store at RSP:8 := RDI:8",
+                    "RAX:8 := Load from RSP:8"
+                ],
+                vec![mock_jmp("pure_ret", return_jmp())],
+            ),
+            mock_sub(
+                "global_reader",
+                defs!["RAX:8 := Load from 0x1000:8"],
+                vec![mock_jmp("global_reader_ret", return_jmp())],
+            ),
+            mock_sub(
+                "writer",
+                defs!["Store at RDI:8 := RSI:8", "Store at 0x1000:8 := RSI:8"],
+                vec![mock_jmp("writer_ret", return_jmp())],
+            ),
+            mock_sub(
+                "caller",
+                defs!["RBX:8 = RAX:8"],
+                vec![mock_jmp(
+                    "caller_call",
+                    Jmp::Call {
+                        target: Tid::new("global_reader"),
+                        return_: Some(Tid::new("caller_blk")),
+                    },
+                )],
+            ),
+            mock_sub(
+                "freeing",
+                Vec::new(),
+                vec![mock_jmp(
+                    "freeing_call",
+                    Jmp::Call {
+                        target: free.tid.clone(),
+                        return_: None,
+                    },
+                )],
+            ),
+        ];
+        project.program.term.subs = subs.into_iter().map(|sub| (sub.tid.clone(), sub)).collect();
+
+        let effects = compute_function_effects(&project);
+        let pure = &effects[&Tid::new("pure")];
+        assert!(pure.is_pure());
+        assert!(pure.may_write_register(&variable!("RAX:8")));
+        assert!(!pure.may_write_register(&variable!("RBX:8")));
+
+        let global_reader = &effects[&Tid::new("global_reader")];
+        assert!(!global_reader.is_pure());
+        assert!(global_reader.is_side_effect_free());
+
+        assert_eq!(
+            effects[&Tid::new("writer")].effects,
+            BTreeSet::from([SideEffect::WritesGlobals, SideEffect::WritesMemory])
+        );
+
+        let caller = &effects[&Tid::new("caller")];
+        assert_eq!(
+            caller.effects,
+            BTreeSet::from([SideEffect::ReadsGlobals, SideEffect::MayNotReturn])
+        );
+        assert_eq!(
+            caller.written_registers,
+            Some(BTreeSet::from([variable!("RAX:8"), variable!("RBX:8")]))
+        );
+
+        let freeing = &effects[&Tid::new("freeing")];
+        assert_eq!(
+            freeing.effects,
+            BTreeSet::from([SideEffect::Frees, SideEffect::MayNotReturn])
+        );
+        assert!(freeing.may_write_register(&variable!("RBX:8")));
+    }
+}
@@ -45,12 +45,13 @@
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::{debug::ToJsonCompact, log::LogMessage};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub use petgraph::graph::NodeIndex;
 use petgraph::{
     graph::DiGraph,
     visit::{EdgeRef, IntoNodeReferences},
+    Direction::Incoming,
 };
 
 /// The graph type of an interprocedural control flow graph
@@ -204,79 +205,75 @@ impl<'a> std::fmt::Display for Edge<'a> {
     }
 }
 
-/// A builder struct for building graphs
-struct GraphBuilder<'a> {
-    program: &'a Term<Program>,
-    extern_subs: HashSet<Tid>,
+/// The intraprocedural part of the control flow graph of a single function.
+struct SubGraph<'a> {
+    sub: &'a Term<Sub>,
+    graph: Graph<'a>,
+    /// The `BlkStart` and `BlkEnd` nodes of the first block of the function.
+    entry: Option<(NodeIndex, NodeIndex)>,
+    /// Calls to functions inside the program.
+    /// The values are of the form (BlkEnd-node-index of the callsite, call, BlkStart-node-index of the return-to site).
+    calls: Vec<(NodeIndex, &'a Term<Jmp>, Option<NodeIndex>)>,
+}
+
+/// A builder struct for the intraprocedural part of the control flow graph of a single function.
+///
+/// The graphs of different functions do not depend on each other,
+/// so they are built in parallel and then merged into the interprocedural graph by the [`GraphBuilder`].
+/// Calls to functions inside the program are only recorded,
+/// since the corresponding edges can only be added after all functions are merged.
+struct SubGraphBuilder<'a, 'b> {
+    sub: &'a Term<Sub>,
+    /// The blocks of the program indexed by their TID.
+    blocks: &'b HashMap<&'a Tid, &'a Term<Blk>>,
+    extern_subs: &'b HashSet<Tid>,
     graph: Graph<'a>,
-    /// Denotes the NodeIndices of possible call targets
-    call_targets: HashMap<Tid, (NodeIndex, NodeIndex)>,
     /// Denotes the NodeIndices of possible intraprocedural jump targets.
-    /// The keys are of the form (block_tid, sub_tid).
+    /// The keys are the block TIDs.
     /// The values are of the form (BlkStart-node-index, BlkEnd-node-index).
-    jump_targets: HashMap<(Tid, Tid), (NodeIndex, NodeIndex)>,
-    /// for each function the list of return addresses of the corresponding call sites
-    return_addresses: HashMap<Tid, Vec<(NodeIndex, NodeIndex)>>,
+    jump_targets: HashMap<Tid, (NodeIndex, NodeIndex)>,
+    /// Calls to functions inside the program, see [`SubGraph`].
+    calls: Vec<(NodeIndex, &'a Term<Jmp>, Option<NodeIndex>)>,
     /// A list of `BlkEnd` nodes for which outgoing edges still have to be added to the graph.
     block_worklist: Vec<NodeIndex>,
-    /// List of `LogMessage` generated by `build` function.
-    log_messages: Vec<LogMessage>,
 }
 
-impl<'a> GraphBuilder<'a> {
-    /// create a new builder with an emtpy graph
-    pub fn new(program: &'a Term<Program>, extern_subs: HashSet<Tid>) -> GraphBuilder<'a> {
-        GraphBuilder {
-            program,
+impl<'a, 'b> SubGraphBuilder<'a, 'b> {
+    /// Create a new builder with an empty graph.
+    fn new(
+        sub: &'a Term<Sub>,
+        blocks: &'b HashMap<&'a Tid, &'a Term<Blk>>,
+        extern_subs: &'b HashSet<Tid>,
+    ) -> SubGraphBuilder<'a, 'b> {
+        SubGraphBuilder {
+            sub,
+            blocks,
             extern_subs,
             graph: Graph::new(),
-            call_targets: HashMap::new(),
             jump_targets: HashMap::new(),
-            return_addresses: HashMap::new(),
+            calls: Vec::new(),
             block_worklist: Vec::new(),
-            log_messages: Vec::new(),
         }
     }
 
     /// Add start and end nodes of a block and the connecting edge.
     /// Also add the end node to the `block_worklist`.
-    fn add_block(&mut self, block: &'a Term<Blk>, sub: &'a Term<Sub>) -> (NodeIndex, NodeIndex) {
-        let start = self.graph.add_node(Node::BlkStart(block, sub));
-        let end = self.graph.add_node(Node::BlkEnd(block, sub));
-        self.jump_targets
-            .insert((block.tid.clone(), sub.tid.clone()), (start, end));
+    fn add_block(&mut self, block: &'a Term<Blk>) -> (NodeIndex, NodeIndex) {
+        let start = self.graph.add_node(Node::BlkStart(block, self.sub));
+        let end = self.graph.add_node(Node::BlkEnd(block, self.sub));
+        self.jump_targets.insert(block.tid.clone(), (start, end));
         self.graph.add_edge(start, end, Edge::Block);
         self.block_worklist.push(end);
         (start, end)
     }
 
-    /// Add all blocks of the program to the graph.
-    ///
-    /// Each block is only added once,
-    /// i.e. for blocks contained in more than one function the extra nodes have to be added separately later.
-    /// The `sub` a block is associated with is the `sub` that the block is contained in in the `program` struct.
-    fn add_program_blocks(&mut self) {
-        let subs = self.program.term.subs.values();
-        for sub in subs {
-            for block in sub.term.blocks.iter() {
-                self.add_block(block, sub);
-            }
-        }
-    }
-
-    /// add all subs to the call targets so that call instructions can be linked to the starting block of the corresponding sub.
-    fn add_subs_to_call_targets(&mut self) {
-        for sub in self.program.term.subs.values() {
-            if !sub.term.blocks.is_empty() {
-                let start_block = &sub.term.blocks[0];
-                let target_index = self.jump_targets[&(start_block.tid.clone(), sub.tid.clone())];
-                self.call_targets.insert(sub.tid.clone(), target_index);
-            } else {
-                self.log_messages.push(LogMessage::new_info(format!(
-                    "{} contains no blocks",
-                    sub.tid
-                )))
-            }
+    /// Return the `BlkStart` node of the block with the given TID.
+    /// If no node corresponding to the block exists yet, new nodes for the block are created.
+    fn get_or_add_block(&mut self, block_tid: &Tid) -> NodeIndex {
+        if let Some((start, _)) = self.jump_targets.get(block_tid) {
+            *start
+        } else {
+            self.add_block(self.blocks[block_tid]).0
         }
     }
 
@@ -290,22 +287,9 @@ impl<'a> GraphBuilder<'a> {
         jump: &'a Term<Jmp>,
         untaken_conditional: Option<&'a Term<Jmp>>,
     ) {
-        let sub_term = match self.graph[source] {
-            Node::BlkEnd(_, sub_term) => sub_term,
-            _ => panic!(),
-        };
-        if let Some((target_node, _)) = self
-            .jump_targets
-            .get(&(target_tid.clone(), sub_term.tid.clone()))
-        {
-            self.graph
-                .add_edge(source, *target_node, Edge::Jump(jump, untaken_conditional));
-        } else {
-            let target_block = self.program.term.find_block(target_tid).unwrap();
-            let (target_node, _) = self.add_block(target_block, sub_term);
-            self.graph
-                .add_edge(source, target_node, Edge::Jump(jump, untaken_conditional));
-        }
+        let target_node = self.get_or_add_block(target_tid);
+        self.graph
+            .add_edge(source, target_node, Edge::Jump(jump, untaken_conditional));
     }
 
     /// Read in target hints for indirect intraprocedural jumps from the source block
@@ -318,26 +302,20 @@ impl<'a> GraphBuilder<'a> {
         jump: &'a Term<Jmp>,
         untaken_conditional: Option<&'a Term<Jmp>>,
     ) {
-        let source_block = match self.graph[source] {
-            Node::BlkEnd(source_block, _) => source_block,
-            _ => panic!(),
-        };
+        let source_block = self.graph[source].get_block();
         for target_tid in source_block.term.indirect_jmp_targets.iter() {
             self.add_intraprocedural_edge(source, target_tid, jump, untaken_conditional);
         }
     }
 
-    /// add call edges and interprocedural jump edges for a specific jump term to the graph
+    /// Add intraprocedural and extern call edges for a specific jump term to the graph.
+    /// Calls to functions inside the program are added to the list of `calls`.
     fn add_jump_edge(
         &mut self,
         source: NodeIndex,
         jump: &'a Term<Jmp>,
         untaken_conditional: Option<&'a Term<Jmp>>,
     ) {
-        let (source_block, sub_term) = match self.graph[source] {
-            Node::BlkEnd(source_block, sub_term) => (source_block, sub_term),
-            _ => panic!(),
-        };
         match &jump.term {
             Jmp::Branch(tid)
             | Jmp::CBranch {
@@ -351,70 +329,23 @@ impl<'a> GraphBuilder<'a> {
             }
             Jmp::Call { target, return_ } => {
                 // first make sure that the return block exists
-                let return_to_node_option = if let Some(return_tid) = return_ {
-                    if let Some((return_to_node, _)) = self
-                        .jump_targets
-                        .get(&(return_tid.clone(), sub_term.tid.clone()))
-                    {
-                        Some(*return_to_node)
-                    } else {
-                        let return_block = self.program.term.find_block(return_tid).unwrap();
-                        Some(self.add_block(return_block, sub_term).0)
-                    }
-                } else {
-                    None
-                };
-                // now add the call edge
+                let return_to_node_option = return_
+                    .as_ref()
+                    .map(|return_tid| self.get_or_add_block(return_tid));
                 if self.extern_subs.contains(target) {
                     if let Some(return_to_node) = return_to_node_option {
                         self.graph
                             .add_edge(source, return_to_node, Edge::ExternCallStub(jump));
                     }
                 } else {
-                    let mut call_source_node: Option<NodeIndex> = None;
-                    if let Some((target_node, _)) = self.call_targets.get(target) {
-                        let (target_block, target_sub) = match self.graph[*target_node] {
-                            Node::BlkStart(target_block, target_sub) => (target_block, target_sub),
-                            _ => panic!(),
-                        };
-                        call_source_node = Some(self.graph.add_node(Node::CallSource {
-                            source: (source_block, sub_term),
-                            target: (target_block, target_sub),
-                        }));
-                        self.graph.add_edge(
-                            source,
-                            *call_source_node.as_ref().unwrap(),
-                            Edge::CallCombine(jump),
-                        );
-                        self.graph.add_edge(
-                            *call_source_node.as_ref().unwrap(),
-                            *target_node,
-                            Edge::Call(jump),
-                        );
-                    } // TODO: Log message for the else-case?
-                    if let Some(return_node) = return_to_node_option {
-                        if let Some(cs_node) = call_source_node {
-                            self.return_addresses
-                                .entry(target.clone())
-                                .and_modify(|vec| vec.push((cs_node, return_node)))
-                                .or_insert_with(|| vec![(cs_node, return_node)]);
-                        }
-                    }
+                    self.calls.push((source, jump, return_to_node_option));
                 }
             }
             Jmp::CallInd { target: _, return_ } => {
                 // Right now we only add an artificial extern call stub for indirect calls.
                 // TODO: Handle cases where the call target may be known.
                 if let Some(return_tid) = return_ {
-                    let return_to_node = if let Some((return_to_node, _)) = self
-                        .jump_targets
-                        .get(&(return_tid.clone(), sub_term.tid.clone()))
-                    {
-                        *return_to_node
-                    } else {
-                        let return_block = self.program.term.find_block(return_tid).unwrap();
-                        self.add_block(return_block, sub_term).0
-                    };
+                    let return_to_node = self.get_or_add_block(return_tid);
                     self.graph
                         .add_edge(source, return_to_node, Edge::ExternCallStub(jump));
                 }
@@ -426,7 +357,7 @@ impl<'a> GraphBuilder<'a> {
                 // TODO: Decide how to represent CallOther edges.
                 // Right now they are dead ends in the control flow graph.
             }
-            Jmp::Return(_) => {} // return edges are handled in a different function
+            Jmp::Return(_) => {} // return edges are handled by the `GraphBuilder`
         }
     }
 
@@ -445,6 +376,182 @@ impl<'a> GraphBuilder<'a> {
         }
     }
 
+    /// Build the intraprocedural control flow graph of the function.
+    ///
+    /// The blocks of the function are added first (in the order of the function),
+    /// followed by blocks of other functions that are jumped to.
+    fn build(mut self) -> SubGraph<'a> {
+        for block in self.sub.term.blocks.iter() {
+            self.add_block(block);
+        }
+        while let Some(node) = self.block_worklist.pop() {
+            let block = self.graph[node].get_block();
+            self.add_outgoing_edges(node, block);
+        }
+        let entry = self
+            .sub
+            .term
+            .blocks
+            .first()
+            .map(|block| self.jump_targets[&block.tid]);
+        SubGraph {
+            sub: self.sub,
+            graph: self.graph,
+            entry,
+            calls: self.calls,
+        }
+    }
+}
+
+/// A builder struct for building graphs
+struct GraphBuilder<'a> {
+    program: &'a Term<Program>,
+    extern_subs: HashSet<Tid>,
+    graph: Graph<'a>,
+    /// Denotes the NodeIndices of possible call targets
+    call_targets: HashMap<Tid, (NodeIndex, NodeIndex)>,
+    /// for each function the list of return addresses of the corresponding call sites
+    return_addresses: HashMap<Tid, Vec<(NodeIndex, NodeIndex)>>,
+    /// List of `LogMessage` generated by `build` function.
+    log_messages: Vec<LogMessage>,
+}
+
+impl<'a> GraphBuilder<'a> {
+    /// create a new builder with an emtpy graph
+    pub fn new(program: &'a Term<Program>, extern_subs: HashSet<Tid>) -> GraphBuilder<'a> {
+        GraphBuilder {
+            program,
+            extern_subs,
+            graph: Graph::new(),
+            call_targets: HashMap::new(),
+            return_addresses: HashMap::new(),
+            log_messages: Vec::new(),
+        }
+    }
+
+    /// Build the intraprocedural graphs of all functions in parallel.
+    ///
+    /// The graphs are returned in the order of the functions in the program.
+    fn build_sub_graphs(&self) -> Vec<SubGraph<'a>> {
+        let mut blocks: HashMap<&'a Tid, &'a Term<Blk>> = HashMap::new();
+        for sub in self.program.term.subs.values() {
+            for block in sub.term.blocks.iter() {
+                // Like `Program::find_block` we use the first block with a given TID.
+                blocks.entry(&block.tid).or_insert(block);
+            }
+        }
+        let blocks = &blocks;
+        let extern_subs = &self.extern_subs;
+        let subs: Vec<&'a Term<Sub>> = self.program.term.subs.values().collect();
+        let thread_count = std::thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1);
+        let chunk_size = std::cmp::max(subs.len().div_ceil(thread_count), 1);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = subs
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|sub| SubGraphBuilder::new(sub, blocks, extern_subs).build())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("CFG construction thread panicked."))
+                .collect()
+        })
+    }
+
+    /// Add the nodes and edges of the intraprocedural graph of a function to the graph
+    /// and add the function to the call targets so that call instructions can be linked to its starting block.
+    ///
+    /// Returns the calls to functions inside the program contained in the function
+    /// with node indices of the graph.
+    fn add_sub_graph(
+        &mut self,
+        sub_graph: SubGraph<'a>,
+    ) -> Vec<(NodeIndex, &'a Term<Jmp>, Option<NodeIndex>)> {
+        let node_map: Vec<NodeIndex> = sub_graph
+            .graph
+            .node_weights()
+            .map(|node| self.graph.add_node(*node))
+            .collect();
+        for edge in sub_graph.graph.edge_references() {
+            self.graph.add_edge(
+                node_map[edge.source().index()],
+                node_map[edge.target().index()],
+                *edge.weight(),
+            );
+        }
+        let sub = sub_graph.sub;
+        if let Some((start, end)) = sub_graph.entry {
+            self.call_targets.insert(
+                sub.tid.clone(),
+                (node_map[start.index()], node_map[end.index()]),
+            );
+        } else {
+            self.log_messages.push(LogMessage::new_info(format!(
+                "{} contains no blocks",
+                sub.tid
+            )))
+        }
+        sub_graph
+            .calls
+            .into_iter()
+            .map(|(source, jump, return_to_node)| {
+                (
+                    node_map[source.index()],
+                    jump,
+                    return_to_node.map(|node| node_map[node.index()]),
+                )
+            })
+            .collect()
+    }
+
+    /// Add the `CallSource` node and the call edges for a call to a function inside the program
+    /// and record the return-to site of the call.
+    fn add_call_edges(
+        &mut self,
+        source: NodeIndex,
+        jump: &'a Term<Jmp>,
+        return_to_node: Option<NodeIndex>,
+    ) {
+        let Jmp::Call { target, .. } = &jump.term else {
+            panic!()
+        };
+        let (source_block, sub_term) = match self.graph[source] {
+            Node::BlkEnd(source_block, sub_term) => (source_block, sub_term),
+            _ => panic!(),
+        };
+        let Some((target_node, _)) = self.call_targets.get(target) else {
+            // TODO: Log message for calls to unknown functions?
+            return;
+        };
+        let target_node = *target_node;
+        let (target_block, target_sub) = match self.graph[target_node] {
+            Node::BlkStart(target_block, target_sub) => (target_block, target_sub),
+            _ => panic!(),
+        };
+        let call_source_node = self.graph.add_node(Node::CallSource {
+            source: (source_block, sub_term),
+            target: (target_block, target_sub),
+        });
+        self.graph
+            .add_edge(source, call_source_node, Edge::CallCombine(jump));
+        self.graph
+            .add_edge(call_source_node, target_node, Edge::Call(jump));
+        if let Some(return_node) = return_to_node {
+            self.return_addresses
+                .entry(target.clone())
+                .or_default()
+                .push((call_source_node, return_node));
+        }
+    }
+
     /// For each return instruction and each corresponding call, add the following to the graph:
     /// - a CallReturn node.
     /// - edges from the callsite and from the returning-from site to the CallReturn node
@@ -505,21 +612,20 @@ impl<'a> GraphBuilder<'a> {
         }
     }
 
-    /// Add all non-return-instruction-related jump edges to the graph.
-    fn add_jump_and_call_edges(&mut self) {
-        while let Some(node) = self.block_worklist.pop() {
-            match self.graph[node] {
-                Node::BlkEnd(block, _) => self.add_outgoing_edges(node, block),
-                _ => panic!(),
-            }
-        }
-    }
-
     /// Build the interprocedural control flow graph.
+    ///
+    /// The intraprocedural graphs of the functions are built in parallel.
+    /// Merging them and adding the interprocedural edges is done sequentially
+    /// (in the order of the functions in the program),
+    /// so that the resulting graph does not depend on the number of threads.
     pub fn build(&mut self) -> Graph<'a> {
-        self.add_program_blocks();
-        self.add_subs_to_call_targets();
-        self.add_jump_and_call_edges();
+        let mut calls = Vec::new();
+        for sub_graph in self.build_sub_graphs() {
+            calls.append(&mut self.add_sub_graph(sub_graph));
+        }
+        for (source, jump, return_to_node) in calls {
+            self.add_call_edges(source, jump, return_to_node);
+        }
         self.add_return_edges();
         self.graph.clone()
     }
@@ -553,6 +659,76 @@ pub fn get_entry_nodes_of_subs(graph: &Graph) -> HashMap<Tid, NodeIndex> {
     sub_to_entry_node_map
 }
 
+/// The number of incoming edges of the `BlkStart` nodes of a control flow graph.
+///
+/// Unlike the graph itself the in-degrees do not borrow the program.
+/// Thus they can be kept up to date while a normalization pass retargets jumps,
+/// instead of rebuilding the whole control flow graph after the pass.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BlockInDegrees {
+    /// The in-degrees indexed by (block TID, function TID).
+    in_degrees: HashMap<(Tid, Tid), usize>,
+}
+
+impl BlockInDegrees {
+    /// Count the incoming edges of the `BlkStart` nodes of the given graph.
+    pub fn new(graph: &Graph) -> BlockInDegrees {
+        let mut in_degrees = HashMap::new();
+        for node in graph.node_indices() {
+            if let Node::BlkStart(block, sub) = graph[node] {
+                *in_degrees
+                    .entry((block.tid.clone(), sub.tid.clone()))
+                    .or_insert(0) += graph.edges_directed(node, Incoming).count();
+            }
+        }
+        BlockInDegrees { in_degrees }
+    }
+
+    /// Update the in-degrees for retargeted jumps.
+    ///
+    /// The given map contains the new target block for each retargeted jump
+    /// (or the new return-to block for calls).
+    /// The graph has to be the graph that the in-degrees were computed from,
+    /// i.e. the graph before the jumps were retargeted.
+    /// Only the edges corresponding to the retargeted jumps are updated.
+    pub fn retarget_jumps(&mut self, graph: &Graph, retargeted_jumps: &BTreeMap<Tid, Tid>) {
+        for edge in graph.edge_references() {
+            let jump = match edge.weight() {
+                Edge::Jump(jump, _) | Edge::ExternCallStub(jump) | Edge::ReturnCombine(jump) => {
+                    jump
+                }
+                _ => continue,
+            };
+            let Some(new_target) = retargeted_jumps.get(&jump.tid) else {
+                continue;
+            };
+            let Node::BlkStart(old_target, sub) = graph[edge.target()] else {
+                continue;
+            };
+            if let Some(in_degree) = self
+                .in_degrees
+                .get_mut(&(old_target.tid.clone(), sub.tid.clone()))
+            {
+                *in_degree -= 1;
+            }
+            *self
+                .in_degrees
+                .entry((new_target.clone(), sub.tid.clone()))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Return the TIDs of all blocks that have a `BlkStart` node without incoming edges
+    /// in at least one function.
+    pub fn get_blocks_without_incoming_edges(&self) -> HashSet<Tid> {
+        self.in_degrees
+            .iter()
+            .filter(|(_, in_degree)| **in_degree == 0)
+            .map(|((block_tid, _), _)| block_tid.clone())
+            .collect()
+    }
+}
+
 impl ToJsonCompact for Graph<'_> {
     fn to_json_compact(&self) -> serde_json::Value {
         let mut map = serde_json::Map::new();
@@ -721,6 +897,9 @@ mod tests {
                 extern_symbols: BTreeMap::new(),
                 entry_points: BTreeSet::new(),
                 address_base_offset: 0,
+                stack_canary_checks: BTreeMap::new(),
+                fortified_symbols: BTreeMap::new(),
+                provenance: ProvenanceTable::default(),
             },
         };
         program
@@ -735,6 +914,25 @@ mod tests {
         assert_eq!(graph.edge_count(), 20);
     }
 
+    #[test]
+    fn update_block_in_degrees() {
+        let mut program = mock_program();
+        let graph = get_program_cfg(&program);
+        let mut in_degrees = BlockInDegrees::new(&graph);
+        assert!(in_degrees.get_blocks_without_incoming_edges().is_empty());
+
+        let retargeted_jumps = BTreeMap::from([(Tid::new("jump"), Tid::new("sub1_blk2"))]);
+        in_degrees.retarget_jumps(&graph, &retargeted_jumps);
+        let sub1 = program.term.subs.get_mut(&Tid::new("sub1")).unwrap();
+        sub1.term.blocks[1].term.jmps[0].term = Jmp::Branch(Tid::new("sub1_blk2"));
+        let graph = get_program_cfg(&program);
+        assert_eq!(in_degrees, BlockInDegrees::new(&graph));
+        assert_eq!(
+            in_degrees.get_blocks_without_incoming_edges(),
+            HashSet::from([Tid::new("sub1_blk1")])
+        );
+    }
+
     #[test]
     fn add_indirect_jumps() {
         let indirect_jmp_term = Term {
@@ -1,17 +1,24 @@
 //! Modules necessary for graph-based and fixpoint-based analyses,
 //! as well as analyses depending on these modules.
 
+pub mod allocators;
 pub mod backward_interprocedural_fixpoint;
 pub mod callgraph;
 pub mod dead_variable_elimination;
+pub mod escape_analysis;
 pub mod expression_propagation;
 pub mod fixpoint;
 pub mod forward_interprocedural_fixpoint;
+pub mod function_effects;
 pub mod function_signature;
 pub mod graph;
 pub mod interprocedural_fixpoint_generic;
+pub mod path_feasibility;
 pub mod pointer_inference;
 pub mod stack_alignment_substitution;
+#[cfg(feature = "string-abstraction")]
 pub mod string_abstraction;
+#[cfg(feature = "taint")]
 pub mod taint;
 pub mod vsa_results;
+pub mod xref;
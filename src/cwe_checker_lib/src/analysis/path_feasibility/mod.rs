@@ -0,0 +1,432 @@
+//! Path-sensitive refinement of CWE warnings.
+//!
+//! Most checks report a warning as soon as the abstract state at a program point looks dangerous,
+//! without considering whether the program point can be reached at all under the conditions
+//! that lead to the dangerous state.
+//! This module implements a post-processing stage for the generated CWE warnings
+//! that removes some of the resulting false positives.
+//!
+//! # How the refinement works
+//!
+//! For each warning:
+//! 1. The location of the warning is the first TID of the warning that belongs to a `Def` or `Jmp` term of the program.
+//!    Warnings without such a TID are not refined.
+//! 2. The shortest path from the entry block of the function containing the location to the location is computed.
+//!    Calls are followed to their return sites, i.e. the path is intraprocedural.
+//! 3. The path is executed symbolically.
+//!    The values of registers are tracked as expressions over the register values at the function start.
+//!    Loaded values and registers that are not callee-saved after calls are represented by fresh unknown values.
+//! 4. The conditions of the conditional jumps along the path are checked for satisfiability by a [`ConstraintSolver`].
+//!
+//! If the conditions are unsatisfiable, the warning is suppressed or its confidence is downgraded to [`Confidence::Low`],
+//! depending on the [`RefinementMode`].
+//!
+//! The [`ConstantSolver`] is always available.
+//! It only detects conditions that evaluate to constant false
+//! and conditions that contradict each other syntactically.
+//! If the `smt` cargo feature is enabled, the [`Z3Solver`] checks the conditions with the Z3 SMT solver.
+//! [`default_solver`] returns the most precise available solver.
+//!
+//! # Limitations
+//!
+//! Only the shortest path to a warning is checked.
+//! If it is infeasible, the location may still be reachable through a longer path.
+//! Memory is not modelled, so conditions on values stored in memory are never found to be contradictory.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{Confidence, CweWarning};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+#[cfg(feature = "smt")]
+mod z3_solver;
+#[cfg(feature = "smt")]
+pub use z3_solver::Z3Solver;
+
+/// The maximal recursion depth of symbolic expressions.
+/// Deeper expressions are replaced by unknown values to keep the constraints small.
+const MAX_EXPRESSION_DEPTH: u64 = 16;
+
+/// The result of checking the feasibility of a path.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Feasibility {
+    /// The path conditions are satisfiable.
+    Feasible,
+    /// The path conditions are unsatisfiable, i.e. the path cannot be taken.
+    Infeasible,
+    /// The solver could not decide whether the path conditions are satisfiable.
+    Unknown,
+}
+
+/// How warnings on infeasible paths are handled.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum RefinementMode {
+    /// Remove the warnings.
+    Suppress,
+    /// Set the confidence of the warnings to [`Confidence::Low`].
+    Downgrade,
+}
+
+/// A solver deciding the satisfiability of path conditions.
+pub trait ConstraintSolver {
+    /// Check whether all given conditions can be non-zero at the same time.
+    ///
+    /// The conditions are expressions of size one byte.
+    /// Variables in the conditions represent arbitrary values.
+    fn check(&self, conditions: &[Expression]) -> Feasibility;
+}
+
+/// A solver that only evaluates constant conditions
+/// and detects conditions that are (after simplification) syntactically negations of each other.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ConstantSolver;
+
+impl ConstraintSolver for ConstantSolver {
+    fn check(&self, conditions: &[Expression]) -> Feasibility {
+        let mut all_constant = true;
+        for condition in conditions {
+            match evaluate_constant(condition) {
+                Some(value) if value.is_zero() => return Feasibility::Infeasible,
+                Some(_) => (),
+                None => all_constant = false,
+            }
+            let mut negated_condition = Expression::UnOp {
+                op: UnOpType::BoolNegate,
+                arg: Box::new(condition.clone()),
+            };
+            negated_condition.substitute_trivial_operations();
+            if conditions.contains(&negated_condition) {
+                return Feasibility::Infeasible;
+            }
+        }
+        if all_constant {
+            Feasibility::Feasible
+        } else {
+            Feasibility::Unknown
+        }
+    }
+}
+
+/// Get the most precise solver available in the current build.
+pub fn default_solver() -> Box<dyn ConstraintSolver> {
+    #[cfg(feature = "smt")]
+    {
+        Box::new(Z3Solver::default())
+    }
+    #[cfg(not(feature = "smt"))]
+    {
+        Box::new(ConstantSolver)
+    }
+}
+
+/// Evaluate the expression if it does not contain variables or unknown values.
+fn evaluate_constant(expression: &Expression) -> Option<Bitvector> {
+    match expression {
+        Expression::Const(value) => Some(value.clone()),
+        Expression::Var(_) | Expression::Unknown { .. } => None,
+        Expression::BinOp { op, lhs, rhs } => evaluate_constant(lhs)?
+            .bin_op(*op, &evaluate_constant(rhs)?)
+            .ok(),
+        Expression::UnOp { op, arg } => evaluate_constant(arg)?.un_op(*op).ok(),
+        Expression::Cast { op, size, arg } => evaluate_constant(arg)?.cast(*op, *size).ok(),
+        Expression::Subpiece {
+            low_byte,
+            size,
+            arg,
+        } => Some(evaluate_constant(arg)?.subpiece(*low_byte, *size)),
+    }
+}
+
+/// The location of a term in the program.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Location<'a> {
+    /// The function containing the term.
+    sub: &'a Term<Sub>,
+    /// The index of the block containing the term.
+    block: usize,
+}
+
+/// Map the string representations of the TIDs of all `Def` and `Jmp` terms to their locations.
+fn index_locations(project: &Project) -> HashMap<String, Location<'_>> {
+    let mut locations = HashMap::new();
+    for sub in project.program.term.subs.values() {
+        for (block_index, blk) in sub.term.blocks.iter().enumerate() {
+            let location = Location {
+                sub,
+                block: block_index,
+            };
+            let def_tids = blk.term.defs.iter().map(|def| &def.tid);
+            let jmp_tids = blk.term.jmps.iter().map(|jmp| &jmp.tid);
+            for tid in def_tids.chain(jmp_tids) {
+                locations.insert(format!("{tid}"), location);
+            }
+        }
+    }
+    locations
+}
+
+/// Get the TIDs of the intraprocedural successor blocks of the given jump.
+fn get_successors<'a>(jmp: &'a Jmp, blk: &'a Blk) -> Vec<&'a Tid> {
+    match jmp {
+        Jmp::Branch(target) | Jmp::CBranch { target, .. } => vec![target],
+        Jmp::BranchInd(_) => blk.indirect_jmp_targets.iter().collect(),
+        Jmp::Call { return_, .. }
+        | Jmp::CallInd { return_, .. }
+        | Jmp::CallOther { return_, .. } => return_.iter().collect(),
+        Jmp::Return(_) => Vec::new(),
+    }
+}
+
+/// Compute the shortest path from the entry block of the function to the given block.
+///
+/// The path is returned as the list of the taken jumps,
+/// given by the index of the block and the index of the jump in the block.
+fn find_shortest_path(sub: &Term<Sub>, target_block: usize) -> Option<Vec<(usize, usize)>> {
+    let blocks = &sub.term.blocks;
+    let block_indices: HashMap<&Tid, usize> = blocks
+        .iter()
+        .enumerate()
+        .map(|(index, blk)| (&blk.tid, index))
+        .collect();
+    let mut predecessors: HashMap<usize, (usize, usize)> = HashMap::new();
+    let mut visited = HashSet::from([0]);
+    let mut queue = VecDeque::from([0]);
+    while let Some(block) = queue.pop_front() {
+        if block == target_block {
+            let mut path = Vec::new();
+            let mut current = block;
+            while let Some(&(predecessor, jmp_index)) = predecessors.get(&current) {
+                path.push((predecessor, jmp_index));
+                current = predecessor;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        let blk = &blocks[block].term;
+        for (jmp_index, jmp) in blk.jmps.iter().enumerate() {
+            for successor in get_successors(&jmp.term, blk) {
+                if let Some(&successor) = block_indices.get(successor) {
+                    if visited.insert(successor) {
+                        predecessors.insert(successor, (block, jmp_index));
+                        queue.push_back(successor);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The state of the symbolic execution of a path.
+struct SymbolicState<'a> {
+    /// The values of all registers that were changed on the path so far.
+    /// Registers without a value still contain their value at the function start.
+    values: HashMap<Variable, Expression>,
+    /// The number of fresh unknown values generated so far.
+    num_unknowns: usize,
+    /// The registers that are preserved by calls.
+    callee_saved: HashSet<&'a Variable>,
+    /// The registers of the CPU architecture.
+    register_set: &'a BTreeSet<Variable>,
+}
+
+impl<'a> SymbolicState<'a> {
+    /// Create the state at the start of a function.
+    fn new(project: &'a Project) -> Self {
+        SymbolicState {
+            values: HashMap::new(),
+            num_unknowns: 0,
+            callee_saved: project
+                .get_standard_calling_convention()
+                .map(|cconv| cconv.callee_saved_register.iter().collect())
+                .unwrap_or_default(),
+            register_set: &project.register_set,
+        }
+    }
+
+    /// Generate a fresh variable representing an unknown value of the given size.
+    fn fresh_unknown(&mut self, size: ByteSize) -> Expression {
+        self.num_unknowns += 1;
+        Expression::Var(Variable {
+            name: format!("$unknown_{}", self.num_unknowns),
+            size,
+            is_temp: true,
+        })
+    }
+
+    /// Replace all variables in the expression by their current symbolic values.
+    fn evaluate(&mut self, expression: &Expression) -> Expression {
+        let mut value = self.substitute(expression);
+        value.substitute_trivial_operations();
+        if value.recursion_depth() > MAX_EXPRESSION_DEPTH {
+            return self.fresh_unknown(value.bytesize());
+        }
+        value
+    }
+
+    /// Replace all variables in the expression by their current symbolic values without simplification.
+    fn substitute(&self, expression: &Expression) -> Expression {
+        match expression {
+            Expression::Var(var) => self
+                .values
+                .get(var)
+                .cloned()
+                .unwrap_or_else(|| expression.clone()),
+            Expression::Const(_) | Expression::Unknown { .. } => expression.clone(),
+            Expression::BinOp { op, lhs, rhs } => Expression::BinOp {
+                op: *op,
+                lhs: Box::new(self.substitute(lhs)),
+                rhs: Box::new(self.substitute(rhs)),
+            },
+            Expression::UnOp { op, arg } => Expression::UnOp {
+                op: *op,
+                arg: Box::new(self.substitute(arg)),
+            },
+            Expression::Cast { op, size, arg } => Expression::Cast {
+                op: *op,
+                size: *size,
+                arg: Box::new(self.substitute(arg)),
+            },
+            Expression::Subpiece {
+                low_byte,
+                size,
+                arg,
+            } => Expression::Subpiece {
+                low_byte: *low_byte,
+                size: *size,
+                arg: Box::new(self.substitute(arg)),
+            },
+        }
+    }
+
+    /// Update the state for the given `Def` term.
+    fn handle_def(&mut self, def: &Def) {
+        match def {
+            Def::Assign { var, value } => {
+                let value = match value {
+                    Expression::Unknown { size, .. } => self.fresh_unknown(*size),
+                    _ => self.evaluate(value),
+                };
+                self.values.insert(var.clone(), value);
+            }
+            Def::Load { var, .. } => {
+                let value = self.fresh_unknown(var.size);
+                self.values.insert(var.clone(), value);
+            }
+            Def::Store { .. } => (),
+        }
+    }
+
+    /// Forget the values of all registers that are not preserved by calls.
+    fn handle_call(&mut self) {
+        for register in self.register_set.iter() {
+            if !self.callee_saved.contains(register) {
+                let value = self.fresh_unknown(register.size);
+                self.values.insert(register.clone(), value);
+            }
+        }
+        self.values.retain(|var, _| !var.is_temp);
+    }
+
+    /// Get the condition that has to hold for the given jump to be taken.
+    ///
+    /// For jumps directly following a conditional jump in the same block
+    /// this is the negation of the condition of the conditional jump.
+    fn get_jump_condition(&mut self, blk: &Blk, jmp_index: usize) -> Option<Expression> {
+        match &blk.jmps[jmp_index].term {
+            Jmp::CBranch { condition, .. } => Some(self.evaluate(condition)),
+            _ if jmp_index > 0 => match &blk.jmps[jmp_index - 1].term {
+                Jmp::CBranch { condition, .. } => Some(self.evaluate(&Expression::UnOp {
+                    op: UnOpType::BoolNegate,
+                    arg: Box::new(condition.clone()),
+                })),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Collect the conditions of the conditional jumps on the shortest path to the given location.
+///
+/// Returns `None` if the location is not reachable from the entry block of its function.
+fn collect_path_conditions(project: &Project, location: &Location) -> Option<Vec<Expression>> {
+    let blocks = &location.sub.term.blocks;
+    let path = find_shortest_path(location.sub, location.block)?;
+    let mut state = SymbolicState::new(project);
+    let mut conditions = Vec::new();
+    for (block, jmp_index) in path {
+        let blk = &blocks[block].term;
+        for def in blk.defs.iter() {
+            state.handle_def(&def.term);
+        }
+        if let Some(condition) = state.get_jump_condition(blk, jmp_index) {
+            conditions.push(condition);
+        }
+        if matches!(
+            blk.jmps[jmp_index].term,
+            Jmp::Call { .. } | Jmp::CallInd { .. } | Jmp::CallOther { .. }
+        ) {
+            state.handle_call();
+        }
+    }
+    Some(conditions)
+}
+
+/// Check whether the location of the given warning is reachable
+/// under the conditions of the shortest path to it.
+pub fn check_warning(
+    project: &Project,
+    warning: &CweWarning,
+    solver: &dyn ConstraintSolver,
+) -> Feasibility {
+    check_warning_with_index(&index_locations(project), project, warning, solver)
+}
+
+/// Check a warning using a precomputed index of term locations.
+fn check_warning_with_index(
+    locations: &HashMap<String, Location>,
+    project: &Project,
+    warning: &CweWarning,
+    solver: &dyn ConstraintSolver,
+) -> Feasibility {
+    let Some(location) = warning.tids.iter().find_map(|tid| locations.get(tid)) else {
+        return Feasibility::Unknown;
+    };
+    match collect_path_conditions(project, location) {
+        Some(conditions) => solver.check(&conditions),
+        None => Feasibility::Unknown,
+    }
+}
+
+/// Suppress or downgrade all warnings whose location is not reachable
+/// under the conditions of the shortest path to it.
+///
+/// Returns the number of suppressed or downgraded warnings.
+pub fn refine_warnings(
+    project: &Project,
+    warnings: &mut Vec<CweWarning>,
+    mode: RefinementMode,
+    solver: &dyn ConstraintSolver,
+) -> usize {
+    let locations = index_locations(project);
+    let mut num_infeasible = 0;
+    warnings.retain_mut(|warning| {
+        if check_warning_with_index(&locations, project, warning, solver) != Feasibility::Infeasible
+        {
+            return true;
+        }
+        num_infeasible += 1;
+        match mode {
+            RefinementMode::Suppress => false,
+            RefinementMode::Downgrade => {
+                warning.confidence = Confidence::Low;
+                true
+            }
+        }
+    });
+    num_infeasible
+}
+
+#[cfg(test)]
+mod tests;
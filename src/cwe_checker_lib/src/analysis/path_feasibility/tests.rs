@@ -0,0 +1,146 @@
+use super::*;
+use crate::{expr, variable};
+
+/// Create the condition `RAX == 0`.
+fn rax_is_zero() -> Expression {
+    Expression::BinOp {
+        op: BinOpType::IntEqual,
+        lhs: Box::new(expr!("RAX:8")),
+        rhs: Box::new(expr!("0x0:8")),
+    }
+}
+
+/// Create a project with a function "main" consisting of the following blocks:
+/// - `blk0` sets `ZF` to `RAX == 0` and jumps to `blk1` if `ZF` is set, else to `blk_ok`.
+/// - `blk1` jumps to `blk_warn` if `ZF` is not set, else to `blk_ok`.
+/// - `blk_warn` is thus unreachable.
+///
+/// If `rax_value` is set, `RAX` is set to it at the start of `blk0`.
+fn mock_project(rax_value: Option<Expression>) -> Project {
+    let mut blk0 = Blk::mock_with_tid("blk0");
+    if let Some(value) = rax_value {
+        blk0.term
+            .defs
+            .push(Def::assign("def_rax", variable!("RAX:8"), value));
+    }
+    blk0.term
+        .defs
+        .push(Def::assign("def_zf", variable!("ZF:1"), rax_is_zero()));
+    blk0.term.jmps = vec![
+        Term {
+            tid: Tid::new("jmp0_cond"),
+            term: Jmp::CBranch {
+                target: Tid::new("blk1"),
+                condition: expr!("ZF:1"),
+            },
+        },
+        Jmp::branch("jmp0", "blk_ok"),
+    ];
+    let mut blk1 = Blk::mock_with_tid("blk1");
+    blk1.term.jmps = vec![
+        Term {
+            tid: Tid::new("jmp1_cond"),
+            term: Jmp::CBranch {
+                target: Tid::new("blk_warn"),
+                condition: expr!("ZF:1").un_op(UnOpType::BoolNegate),
+            },
+        },
+        Jmp::branch("jmp1", "blk_ok"),
+    ];
+    let mut blk_warn = Blk::mock_with_tid("blk_warn");
+    blk_warn.term.defs = vec![Def::assign("def_warn", variable!("RBX:8"), expr!("RAX:8"))];
+    let mut blk_ok = Blk::mock_with_tid("blk_ok");
+    blk_ok.term.defs = vec![Def::assign("def_ok", variable!("RBX:8"), expr!("0x0:8"))];
+
+    let mut sub = Sub::mock("main");
+    sub.term.blocks = vec![blk0, blk1, blk_warn, blk_ok];
+    let mut project = Project::mock_x64();
+    project.program.term.subs.insert(sub.tid.clone(), sub);
+    project
+}
+
+/// Create a warning at the given TID.
+fn mock_warning(tid: &str) -> CweWarning {
+    CweWarning::new("CWE476", "0.1", "warning").tids(vec![tid.to_string()])
+}
+
+#[test]
+fn contradicting_conditions() {
+    let project = mock_project(None);
+    let solver = ConstantSolver;
+
+    assert_eq!(
+        check_warning(&project, &mock_warning("def_warn"), &solver),
+        Feasibility::Infeasible
+    );
+    assert_eq!(
+        check_warning(&project, &mock_warning("def_ok"), &solver),
+        Feasibility::Unknown
+    );
+    assert_eq!(
+        check_warning(&project, &mock_warning("jmp1"), &solver),
+        Feasibility::Unknown
+    );
+    // Warnings without a location in the program are not refined.
+    assert_eq!(
+        check_warning(&project, &mock_warning("no_such_tid"), &solver),
+        Feasibility::Unknown
+    );
+}
+
+#[test]
+fn constant_conditions() {
+    let project = mock_project(Some(expr!("0x5:8")));
+    let solver = ConstantSolver;
+
+    assert_eq!(
+        check_warning(&project, &mock_warning("jmp1"), &solver),
+        Feasibility::Infeasible
+    );
+    assert_eq!(
+        check_warning(&project, &mock_warning("def_ok"), &solver),
+        Feasibility::Feasible
+    );
+}
+
+#[test]
+fn constant_solver() {
+    let solver = ConstantSolver;
+    let negated = rax_is_zero().un_op(UnOpType::BoolNegate);
+
+    assert_eq!(solver.check(&[]), Feasibility::Feasible);
+    assert_eq!(solver.check(&[rax_is_zero()]), Feasibility::Unknown);
+    assert_eq!(
+        solver.check(&[rax_is_zero(), negated]),
+        Feasibility::Infeasible
+    );
+    assert_eq!(solver.check(&[expr!("0x0:1")]), Feasibility::Infeasible);
+}
+
+#[test]
+fn refinement_modes() {
+    let project = mock_project(None);
+    let warnings = vec![mock_warning("def_warn"), mock_warning("def_ok")];
+
+    let mut suppressed = warnings.clone();
+    let num_infeasible = refine_warnings(
+        &project,
+        &mut suppressed,
+        RefinementMode::Suppress,
+        &ConstantSolver,
+    );
+    assert_eq!(num_infeasible, 1);
+    assert_eq!(suppressed, vec![mock_warning("def_ok")]);
+
+    let mut downgraded = warnings;
+    let num_infeasible = refine_warnings(
+        &project,
+        &mut downgraded,
+        RefinementMode::Downgrade,
+        &ConstantSolver,
+    );
+    assert_eq!(num_infeasible, 1);
+    assert_eq!(downgraded.len(), 2);
+    assert_eq!(downgraded[0].confidence, Confidence::Low);
+    assert_eq!(downgraded[1].confidence, Confidence::Normal);
+}
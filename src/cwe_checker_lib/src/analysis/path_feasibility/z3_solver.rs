@@ -0,0 +1,190 @@
+//! A constraint solver using the Z3 SMT solver.
+
+use super::{ConstraintSolver, Feasibility};
+use crate::intermediate_representation::*;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use z3::ast::{Ast, Bool, BV};
+
+/// A solver checking path conditions with the Z3 SMT solver.
+///
+/// All expressions are translated to bitvector formulas.
+/// Floating point operations are translated to unconstrained values.
+#[derive(Debug, Clone, Copy)]
+pub struct Z3Solver {
+    /// The timeout for a single satisfiability check in milliseconds.
+    pub timeout_ms: u64,
+}
+
+impl Default for Z3Solver {
+    fn default() -> Self {
+        Z3Solver { timeout_ms: 1000 }
+    }
+}
+
+impl ConstraintSolver for Z3Solver {
+    fn check(&self, conditions: &[Expression]) -> Feasibility {
+        let mut config = z3::Config::new();
+        config.set_timeout_msec(self.timeout_ms);
+        let context = z3::Context::new(&config);
+        let solver = z3::Solver::new(&context);
+        let translator = Translator::new(&context);
+        for condition in conditions {
+            let value = translator.translate(condition);
+            let zero = BV::from_u64(&context, 0, value.get_size());
+            solver.assert(&value._eq(&zero).not());
+        }
+        match solver.check() {
+            z3::SatResult::Sat => Feasibility::Feasible,
+            z3::SatResult::Unsat => Feasibility::Infeasible,
+            z3::SatResult::Unknown => Feasibility::Unknown,
+        }
+    }
+}
+
+/// Translation of expressions into Z3 bitvector formulas.
+struct Translator<'ctx> {
+    /// The Z3 context.
+    context: &'ctx z3::Context,
+    /// The bitvector constants representing the variables of the expressions.
+    variables: RefCell<HashMap<Variable, BV<'ctx>>>,
+    /// The number of unconstrained values generated so far.
+    num_unknowns: Cell<usize>,
+}
+
+impl<'ctx> Translator<'ctx> {
+    /// Create a new translator for the given context.
+    fn new(context: &'ctx z3::Context) -> Self {
+        Translator {
+            context,
+            variables: RefCell::new(HashMap::new()),
+            num_unknowns: Cell::new(0),
+        }
+    }
+
+    /// Generate an unconstrained bitvector of the given size.
+    fn unknown(&self, size: ByteSize) -> BV<'ctx> {
+        let num_unknowns = self.num_unknowns.get() + 1;
+        self.num_unknowns.set(num_unknowns);
+        BV::new_const(
+            self.context,
+            format!("$unknown_{num_unknowns}"),
+            size_in_bits(size),
+        )
+    }
+
+    /// Convert a boolean formula to a bitvector of size one byte.
+    fn bool_to_bv(&self, value: Bool<'ctx>) -> BV<'ctx> {
+        value.ite(
+            &BV::from_u64(self.context, 1, 8),
+            &BV::from_u64(self.context, 0, 8),
+        )
+    }
+
+    /// Convert a bitvector to a boolean formula that is true if the bitvector is non-zero.
+    fn bv_to_bool(&self, value: &BV<'ctx>) -> Bool<'ctx> {
+        value
+            ._eq(&BV::from_u64(self.context, 0, value.get_size()))
+            .not()
+    }
+
+    /// Resize the bitvector to the given number of bits by zero-extension or truncation.
+    fn resize(value: BV<'ctx>, bits: u32) -> BV<'ctx> {
+        let size = value.get_size();
+        match size.cmp(&bits) {
+            std::cmp::Ordering::Less => value.zero_ext(bits - size),
+            std::cmp::Ordering::Equal => value,
+            std::cmp::Ordering::Greater => value.extract(bits - 1, 0),
+        }
+    }
+
+    /// Translate the expression to a bitvector formula.
+    fn translate(&self, expression: &Expression) -> BV<'ctx> {
+        match expression {
+            Expression::Var(var) => self
+                .variables
+                .borrow_mut()
+                .entry(var.clone())
+                .or_insert_with(|| {
+                    BV::new_const(self.context, var.name.clone(), size_in_bits(var.size))
+                })
+                .clone(),
+            Expression::Const(value) => match value.try_to_u64() {
+                Ok(value) if expression.bytesize() <= ByteSize::new(8) => {
+                    BV::from_u64(self.context, value, size_in_bits(expression.bytesize()))
+                }
+                _ => self.unknown(expression.bytesize()),
+            },
+            Expression::Unknown { size, .. } => self.unknown(*size),
+            Expression::BinOp { op, lhs, rhs } => self.translate_bin_op(*op, lhs, rhs),
+            Expression::UnOp { op, arg } => {
+                let value = self.translate(arg);
+                match op {
+                    UnOpType::IntNegate => value.bvnot(),
+                    UnOpType::Int2Comp => value.bvneg(),
+                    UnOpType::BoolNegate => self.bool_to_bv(self.bv_to_bool(&value).not()),
+                    _ => self.unknown(expression.bytesize()),
+                }
+            }
+            Expression::Cast { op, size, arg } => {
+                let value = self.translate(arg);
+                let extension = size_in_bits(*size).saturating_sub(value.get_size());
+                match op {
+                    CastOpType::IntZExt => value.zero_ext(extension),
+                    CastOpType::IntSExt => value.sign_ext(extension),
+                    _ => self.unknown(*size),
+                }
+            }
+            Expression::Subpiece {
+                low_byte,
+                size,
+                arg,
+            } => {
+                let low_bit = size_in_bits(*low_byte);
+                self.translate(arg)
+                    .extract(low_bit + size_in_bits(*size) - 1, low_bit)
+            }
+        }
+    }
+
+    /// Translate a binary operation to a bitvector formula.
+    fn translate_bin_op(&self, op: BinOpType, lhs: &Expression, rhs: &Expression) -> BV<'ctx> {
+        use BinOpType::*;
+        let left = self.translate(lhs);
+        let right = self.translate(rhs);
+        match op {
+            Piece => left.concat(&right),
+            IntAdd => left.bvadd(&right),
+            IntSub => left.bvsub(&right),
+            IntMult => left.bvmul(&right),
+            IntDiv => left.bvudiv(&right),
+            IntSDiv => left.bvsdiv(&right),
+            IntRem => left.bvurem(&right),
+            IntSRem => left.bvsrem(&right),
+            IntAnd | BoolAnd => left.bvand(&right),
+            IntOr | BoolOr => left.bvor(&right),
+            IntXOr | BoolXOr => left.bvxor(&right),
+            IntLeft => left.bvshl(&Self::resize(right, left.get_size())),
+            IntRight => left.bvlshr(&Self::resize(right, left.get_size())),
+            IntSRight => left.bvashr(&Self::resize(right, left.get_size())),
+            IntEqual => self.bool_to_bv(left._eq(&right)),
+            IntNotEqual => self.bool_to_bv(left._eq(&right).not()),
+            IntLess => self.bool_to_bv(left.bvult(&right)),
+            IntSLess => self.bool_to_bv(left.bvslt(&right)),
+            IntLessEqual => self.bool_to_bv(left.bvule(&right)),
+            IntSLessEqual => self.bool_to_bv(left.bvsle(&right)),
+            IntCarry => self.bool_to_bv(left.bvadd_no_overflow(&right, false).not()),
+            IntSCarry => self.bool_to_bv(left.bvadd_no_overflow(&right, true).not()),
+            IntSBorrow => self.bool_to_bv(left.bvsub_no_overflow(&right).not()),
+            FloatEqual | FloatNotEqual | FloatLess | FloatLessEqual => {
+                self.unknown(ByteSize::new(1))
+            }
+            FloatAdd | FloatSub | FloatMult | FloatDiv => self.unknown(lhs.bytesize()),
+        }
+    }
+}
+
+/// Convert a size in bytes to a size in bits.
+fn size_in_bits(size: ByteSize) -> u32 {
+    u64::from(size) as u32 * 8
+}
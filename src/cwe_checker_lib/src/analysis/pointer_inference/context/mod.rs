@@ -6,10 +6,11 @@ use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::log::*;
 use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
 
 use super::object::AbstractObject;
 use super::state::State;
-use super::{Config, Data, VERSION};
+use super::{AllocatorFunction, Config, Data, MemoryFunction, MAX_MODELED_WRITE_SIZE, VERSION};
 
 /// Contains methods of the `Context` struct that deal with the manipulation of abstract IDs.
 mod id_manipulation;
@@ -41,6 +42,18 @@ pub struct Context<'a> {
     pub log_collector: crossbeam_channel::Sender<LogThreadMsg>,
     /// Names of `malloc`-like extern functions.
     pub allocation_symbols: Vec<String>,
+    /// The maximal number of memory objects tracked in a state, if limited.
+    pub max_tracked_objects: Option<usize>,
+    /// Maps the TIDs of functions to their overrides of the maximal number of tracked memory objects.
+    pub max_tracked_objects_overrides: BTreeMap<Tid, usize>,
+    /// Extern functions copying or setting memory whose effects are modeled precisely.
+    pub memory_functions: BTreeMap<String, MemoryFunction>,
+    /// Extern functions allocating or deallocating memory together with their parameter layouts,
+    /// overriding the built-in layouts of the standard allocation functions.
+    pub allocator_functions: BTreeMap<String, AllocatorFunction>,
+    /// Maps the TIDs of functions to the IDs of memory objects
+    /// that had to be removed from their states because the object limit was exceeded.
+    collapsed_objects: Mutex<BTreeMap<Tid, BTreeSet<AbstractIdentifier>>>,
 }
 
 impl<'a> Context<'a> {
@@ -60,9 +73,103 @@ impl<'a> Context<'a> {
                 crate::analysis::function_signature::stubs::generate_param_access_stubs(),
             log_collector,
             allocation_symbols: config.allocation_symbols,
+            max_tracked_objects: config.max_tracked_objects,
+            max_tracked_objects_overrides: analysis_results
+                .project
+                .program
+                .term
+                .subs
+                .iter()
+                .filter_map(|(sub_tid, sub)| {
+                    let limit = config.max_tracked_objects_overrides.get(&sub.term.name)?;
+                    Some((sub_tid.clone(), *limit))
+                })
+                .collect(),
+            memory_functions: config.memory_functions,
+            allocator_functions: config.allocator_functions,
+            collapsed_objects: Mutex::new(BTreeMap::new()),
         }
     }
 
+    /// Get the parameter layout of the allocation or deallocation function with the given name.
+    ///
+    /// Configured layouts take precedence over the built-in layouts of the standard allocation functions.
+    pub fn get_allocator_function(&self, name: &str) -> Option<AllocatorFunction> {
+        self.allocator_functions
+            .get(name)
+            .cloned()
+            .or_else(|| AllocatorFunction::get_builtin(name))
+    }
+
+    /// Returns `true` if calls to the extern function with the given name return a new heap object.
+    fn is_allocation_symbol(&self, name: &str) -> bool {
+        self.allocation_symbols.iter().any(|symbol| symbol == name)
+            || self
+                .allocator_functions
+                .get(name)
+                .is_some_and(AllocatorFunction::is_allocation)
+    }
+
+    /// Get the maximal number of memory objects that may be tracked in states of the given function.
+    fn get_object_limit(&self, fn_tid: &Tid) -> Option<usize> {
+        self.max_tracked_objects_overrides
+            .get(fn_tid)
+            .copied()
+            .or(self.max_tracked_objects)
+    }
+
+    /// If the number of memory objects in the state exceeds the object limit of its function,
+    /// remove excess objects from the state and record the removed objects.
+    ///
+    /// The stack frame, global memory and parameter objects are never removed.
+    /// Of the remaining objects, those with the longest path hints are removed first,
+    /// since they were created deepest in the call hierarchy.
+    fn enforce_object_limit(&self, state: &mut State) {
+        let fn_tid = state.get_fn_tid().clone();
+        let Some(limit) = self.get_object_limit(&fn_tid) else {
+            return;
+        };
+        let num_objects = state.memory.get_num_objects();
+        if num_objects <= limit {
+            return;
+        }
+        let global_mem_id = state.get_global_mem_id();
+        let mut candidates: Vec<AbstractIdentifier> = state
+            .memory
+            .get_all_object_ids()
+            .into_iter()
+            .filter(|id| {
+                *id != state.stack_id
+                    && *id != global_mem_id
+                    && (id.get_tid() != &fn_tid || !id.get_path_hints().is_empty())
+            })
+            .collect();
+        candidates.sort_by(|id1, id2| {
+            id2.get_path_hints()
+                .len()
+                .cmp(&id1.get_path_hints().len())
+                .then_with(|| id1.cmp(id2))
+        });
+        let ids_to_collapse: BTreeSet<AbstractIdentifier> =
+            candidates.into_iter().take(num_objects - limit).collect();
+        if ids_to_collapse.is_empty() {
+            return;
+        }
+        state.collapse_objects(&ids_to_collapse);
+        self.collapsed_objects
+            .lock()
+            .unwrap()
+            .entry(fn_tid)
+            .or_default()
+            .extend(ids_to_collapse);
+    }
+
+    /// Get the IDs of all memory objects that were removed from states because of the object limit,
+    /// sorted by the functions to which the states belong.
+    pub fn get_collapsed_objects(&self) -> BTreeMap<Tid, BTreeSet<AbstractIdentifier>> {
+        self.collapsed_objects.lock().unwrap().clone()
+    }
+
     /// Return `true` if the all of the following properties hold:
     /// * The CPU architecture is a MIPS variant and `var` is the MIPS global pointer register `gp`
     /// * Loading the value at `address` into the register `var` would overwrite the value of `var` with a `Top` value.
@@ -163,6 +270,7 @@ impl<'a> Context<'a> {
                     Bitvector::zero(apint::BitWidth::from(address_bytesize)).into(),
                 );
                 state.set_register(return_register, pointer);
+                self.enforce_object_limit(&mut state);
                 state
             }
             Err(err) => {
@@ -305,6 +413,9 @@ impl<'a> Context<'a> {
                 "(NULL Pointer Dereference) Memory access at {} may result in a NULL dereference",
                 tid.address
             ),
+            confidence: Confidence::Normal,
+            provenance: Vec::new(),
+            merged_cwes: Vec::new(),
         };
         let _ = self.log_collector.send(LogThreadMsg::Cwe(warning));
     }
@@ -94,6 +94,102 @@ impl<'a> Context<'a> {
         }
     }
 
+    /// Handle a call to an extern function that copies or sets memory,
+    /// as configured in the `memory_functions` of the [`Config`](super::super::Config).
+    ///
+    /// If the destination and the size of the written memory are known,
+    /// only the written bytes of the destination object are overwritten,
+    /// so that the values of all other bytes of the object are kept.
+    /// Values (including pointers) copied from a known source are written to the corresponding bytes of the destination.
+    /// Otherwise the call is handled like calls to other stubbed or unknown extern functions.
+    pub fn handle_memory_function_call(
+        &self,
+        state: &State,
+        mut new_state: State,
+        call: &Term<Jmp>,
+        extern_symbol: &ExternSymbol,
+    ) -> State {
+        let function = &self.memory_functions[&extern_symbol.name];
+        if let Err(err) = self.apply_memory_function(state, &mut new_state, function, extern_symbol)
+        {
+            self.log_debug(Err(err), Some(&call.tid));
+            if !self
+                .extern_fn_param_access_patterns
+                .contains_key(extern_symbol.name.as_str())
+            {
+                return self.handle_generic_extern_call(state, new_state, call, extern_symbol);
+            }
+            self.handle_parameter_access_for_stubbed_functions(
+                state,
+                &mut new_state,
+                extern_symbol,
+            );
+        }
+        let cconv = self.project.get_calling_convention(extern_symbol);
+        let return_value = get_param(
+            state,
+            extern_symbol,
+            function.destination(),
+            &self.project.runtime_memory_image,
+        )
+        .unwrap_or_else(|_| Data::new_top(self.project.get_pointer_bytesize()));
+        new_state.set_register(&cconv.integer_return_register[0], return_value);
+        new_state
+    }
+
+    /// Write the values copied or set by a call to a memory function to the destination object in `new_state`.
+    ///
+    /// Returns an error if the size or the destination of the written memory is not known.
+    fn apply_memory_function(
+        &self,
+        state: &State,
+        new_state: &mut State,
+        function: &MemoryFunction,
+        extern_symbol: &ExternSymbol,
+    ) -> Result<(), Error> {
+        let global_memory = &self.project.runtime_memory_image;
+        let destination = get_param(state, extern_symbol, function.destination(), global_memory)?;
+        let (size, values) = match *function {
+            MemoryFunction::Copy { source, size, .. } => {
+                let size = get_constant(&get_param(state, extern_symbol, size, global_memory)?)
+                    .ok_or_else(|| anyhow!("Unknown size of copied memory"))?;
+                let source = get_param(state, extern_symbol, source, global_memory)?;
+                (
+                    size,
+                    state.read_memory_interval(&source, size, global_memory),
+                )
+            }
+            MemoryFunction::StringCopy { source, .. } => {
+                let source = get_param(state, extern_symbol, source, global_memory)?;
+                let string_length = get_constant(&source)
+                    .and_then(|address| {
+                        global_memory
+                            .read_string_until_null_terminator(&Bitvector::from_u64(address))
+                            .ok()
+                    })
+                    .ok_or_else(|| anyhow!("Unknown length of copied string"))?
+                    .len() as u64;
+                let size = string_length + 1;
+                (
+                    size,
+                    state.read_memory_interval(&source, size, global_memory),
+                )
+            }
+            MemoryFunction::Set { value, size, .. } => {
+                let size = get_constant(&get_param(state, extern_symbol, size, global_memory)?)
+                    .ok_or_else(|| anyhow!("Unknown size of set memory"))?;
+                let value = get_param(state, extern_symbol, value, global_memory)?;
+                let values = get_constant(&value)
+                    .map(|byte| {
+                        get_memset_values(byte as u8, size, self.project.get_pointer_bytesize())
+                    })
+                    .unwrap_or_default();
+                (size, values)
+            }
+        };
+        new_state.write_memory_interval(&destination, size, &values)
+    }
+
     /// Compute the return values for stubbed extern symbols.
     /// Note that this function does not handle malloc-like symbols that return a newly created heap object as a return value.
     pub fn compute_return_value_for_stubbed_function(
@@ -123,6 +219,52 @@ impl<'a> Context<'a> {
     }
 }
 
+/// Evaluate the parameter with the given index of a call to the extern symbol.
+fn get_param(
+    state: &State,
+    extern_symbol: &ExternSymbol,
+    index: usize,
+    global_memory: &RuntimeMemoryImage,
+) -> Result<Data, Error> {
+    let arg = extern_symbol
+        .parameters
+        .get(index)
+        .ok_or_else(|| anyhow!("Missing parameter {index} of {}", extern_symbol.name))?;
+    state.eval_parameter_arg(arg, global_memory)
+}
+
+/// Get the value of `data` if it is a known constant.
+fn get_constant(data: &Data) -> Option<u64> {
+    data.get_if_absolute_value()?
+        .try_to_bitvec()
+        .ok()?
+        .try_to_u64()
+        .ok()
+}
+
+/// Get the values written by a `memset`-like function setting `size` bytes to `byte`,
+/// split into chunks of the pointer size.
+///
+/// For sizes larger than `MAX_MODELED_WRITE_SIZE` no values are returned,
+/// i.e. the written memory is only marked as overwritten.
+fn get_memset_values(byte: u8, size: u64, pointer_size: ByteSize) -> Vec<(i64, Data)> {
+    if size > MAX_MODELED_WRITE_SIZE {
+        return Vec::new();
+    }
+    let mut values = Vec::new();
+    let mut offset = 0;
+    while offset < size {
+        let chunk_size = u64::from(pointer_size).min(size - offset);
+        let pattern = u64::from_le_bytes([byte; 8]);
+        let value = Bitvector::from_u64(pattern)
+            .into_truncate(apint::BitWidth::from(ByteSize::new(chunk_size)))
+            .unwrap();
+        values.push((offset as i64, value.into()));
+        offset += chunk_size;
+    }
+    values
+}
+
 /// Helper functions for computing return values for extern symbol calls.
 pub mod return_value_stubs {
     use super::*;
@@ -1,5 +1,6 @@
 use super::super::ValueDomain;
 use super::*;
+use crate::analysis::fixpoint::Budget;
 use crate::{bitvec, def, expr, variable};
 
 fn bv(value: i64) -> ValueDomain {
@@ -39,6 +40,13 @@ fn mock_project() -> (Project, Config) {
         project,
         Config {
             allocation_symbols: vec!["malloc".into()],
+            max_tracked_objects: None,
+            max_tracked_objects_overrides: BTreeMap::new(),
+            memory_functions: BTreeMap::new(),
+            allocator_functions: BTreeMap::new(),
+            widening: WideningConfig::default(),
+            budget: Budget::default(),
+            max_memory: None,
         },
     )
 }
@@ -364,6 +372,122 @@ fn handle_extern_symbol_stubs() {
     );
 }
 
+#[test]
+fn handle_memory_function_calls() {
+    let mut context = mock_context();
+    context.memory_functions = BTreeMap::from([
+        (
+            "memcpy".to_string(),
+            MemoryFunction::Copy {
+                destination: 0,
+                source: 1,
+                size: 2,
+            },
+        ),
+        (
+            "memset".to_string(),
+            MemoryFunction::Set {
+                destination: 0,
+                value: 1,
+                size: 2,
+            },
+        ),
+    ]);
+    let global_memory = &context.project.runtime_memory_image;
+    let stack_address = |offset: i64| Data::from_target(new_id("main", "RSP"), bv(offset));
+    let load = |state: &State, offset: i64| {
+        state
+            .load_value_from_address(&stack_address(offset), ByteSize::new(8), global_memory)
+            .unwrap()
+    };
+    let heap_pointer = Data::from_target(new_id("heap", "RAX"), bv(0));
+    let mut state = State::new(&variable!("RSP:8"), Tid::new("main"), BTreeSet::new());
+    for (offset, value) in [
+        (-32, heap_pointer.clone()),
+        (-24, bv(42).into()),
+        (-56, bv(7).into()),
+        (-48, bv(9).into()),
+    ] {
+        state
+            .store_value(&stack_address(offset), &value, global_memory)
+            .unwrap();
+    }
+    state.set_register(&variable!("RDI:8"), stack_address(-64));
+    state.set_register(&variable!("RSI:8"), stack_address(-32));
+    state.set_register(&variable!("RDX:8"), bv(16).into());
+    let mut memcpy = ExternSymbol::mock_x64("memcpy");
+    memcpy.parameters = vec![
+        Arg::mock_register("RDI", 8),
+        Arg::mock_register("RSI", 8),
+        Arg::mock_register("RDX", 8),
+    ];
+    let mut memset = memcpy.clone();
+    memset.name = "memset".to_string();
+
+    // The copied values (including pointers) overwrite the destination, all other values are kept.
+    let new_state =
+        context.handle_memory_function_call(&state, state.clone(), &call_term("memcpy"), &memcpy);
+    assert_eq!(load(&new_state, -64), heap_pointer);
+    assert_eq!(load(&new_state, -56), bv(42).into());
+    assert_eq!(load(&new_state, -48), bv(9).into());
+    assert_eq!(
+        new_state.get_register(&variable!("RAX:8")),
+        stack_address(-64)
+    );
+
+    let mut memset_state = state.clone();
+    memset_state.set_register(&variable!("RSI:8"), bv(0).into());
+    let new_state = context.handle_memory_function_call(
+        &memset_state,
+        memset_state.clone(),
+        &call_term("memset"),
+        &memset,
+    );
+    assert_eq!(load(&new_state, -64), bv(0).into());
+    assert_eq!(load(&new_state, -56), bv(0).into());
+    assert_eq!(load(&new_state, -48), bv(9).into());
+
+    // With an unknown size the whole destination object may have been overwritten.
+    state.set_register(&variable!("RDX:8"), Data::new_top(ByteSize::new(8)));
+    let new_state =
+        context.handle_memory_function_call(&state, state.clone(), &call_term("memcpy"), &memcpy);
+    assert!(load(&new_state, -48).contains_top());
+}
+
+#[test]
+fn configured_allocator_functions() {
+    let mut context = mock_context();
+    context.allocator_functions = BTreeMap::from([
+        (
+            "pool_alloc".to_string(),
+            AllocatorFunction::Allocation { size: vec![1] },
+        ),
+        (
+            "my_free".to_string(),
+            AllocatorFunction::Deallocation { pointer: 1 },
+        ),
+        (
+            "free".to_string(),
+            AllocatorFunction::Deallocation { pointer: 1 },
+        ),
+    ]);
+    assert!(context.is_allocation_symbol("malloc"));
+    assert!(context.is_allocation_symbol("pool_alloc"));
+    assert!(!context.is_allocation_symbol("my_free"));
+    assert!(!context.is_allocation_symbol("calloc"));
+    assert_eq!(
+        context.get_allocator_function("calloc"),
+        Some(AllocatorFunction::Allocation { size: vec![0, 1] })
+    );
+    assert_eq!(
+        context
+            .get_allocator_function("free")
+            .and_then(|function| function.pointer_parameter()),
+        Some(1)
+    );
+    assert_eq!(context.get_allocator_function("strdup"), None);
+}
+
 #[test]
 fn test_merge_global_mem_from_callee() {
     let context = mock_context();
@@ -424,3 +548,59 @@ fn test_merge_global_mem_from_callee() {
     assert_eq!(load(&caller_state, 0x2002), expected_result);
     assert_eq!(load(&caller_state, 0x3000), bitvec!("4:2").into());
 }
+
+#[test]
+fn enforce_object_limit() {
+    let mut context = mock_context();
+    let mut state = State::new(
+        &context.project.stack_pointer_register,
+        Tid::new("main"),
+        BTreeSet::new(),
+    );
+    let param_id = new_id("main", "RDI");
+    let heap_id = new_id("call_malloc", "RAX");
+    let nested_heap_id = new_id("call_malloc_2", "RAX")
+        .with_path_hint(Tid::new("call_callee"))
+        .unwrap();
+    for id in [&param_id, &heap_id, &nested_heap_id] {
+        state
+            .memory
+            .add_abstract_object(id.clone(), ByteSize::new(8), None);
+    }
+    let num_objects = state.memory.get_num_objects();
+
+    // Without a limit nothing gets removed.
+    let mut unlimited_state = state.clone();
+    context.enforce_object_limit(&mut unlimited_state);
+    assert_eq!(unlimited_state, state);
+
+    // Objects created deeper in the call hierarchy are removed first.
+    context.max_tracked_objects = Some(num_objects - 1);
+    let mut limited_state = state.clone();
+    context.enforce_object_limit(&mut limited_state);
+    assert_eq!(limited_state.memory.get_num_objects(), num_objects - 1);
+    assert!(limited_state.memory.get_object(&nested_heap_id).is_none());
+    assert!(limited_state.memory.get_object(&heap_id).is_some());
+
+    // Parameter objects, the stack frame and global memory are never removed.
+    context.max_tracked_objects = Some(0);
+    let mut limited_state = state.clone();
+    context.enforce_object_limit(&mut limited_state);
+    assert_eq!(limited_state.memory.get_num_objects(), num_objects - 2);
+    assert!(limited_state.memory.get_object(&param_id).is_some());
+    assert_eq!(
+        context.get_collapsed_objects(),
+        BTreeMap::from([(
+            Tid::new("main"),
+            BTreeSet::from([heap_id.clone(), nested_heap_id.clone()])
+        )])
+    );
+
+    // Per-function overrides take precedence over the global limit.
+    context
+        .max_tracked_objects_overrides
+        .insert(Tid::new("main"), num_objects);
+    let mut overridden_state = state.clone();
+    context.enforce_object_limit(&mut overridden_state);
+    assert_eq!(overridden_state, state);
+}
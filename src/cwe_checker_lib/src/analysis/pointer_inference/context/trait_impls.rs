@@ -225,6 +225,7 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
         }
         // Cleanup
         state_after_return.remove_unreferenced_objects();
+        self.enforce_object_limit(&mut state_after_return);
 
         Some(state_after_return)
     }
@@ -265,12 +266,11 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
                     );
                     Some(new_state)
                 }
-                malloc_like_fn if self.allocation_symbols.iter().any(|x| x == malloc_like_fn) => {
-                    Some(self.add_new_object_in_call_return_register(
-                        new_state,
-                        call,
-                        extern_symbol,
-                    ))
+                malloc_like_fn if self.is_allocation_symbol(malloc_like_fn) => Some(
+                    self.add_new_object_in_call_return_register(new_state, call, extern_symbol),
+                ),
+                memory_fn if self.memory_functions.contains_key(memory_fn) => {
+                    Some(self.handle_memory_function_call(state, new_state, call, extern_symbol))
                 }
                 stubbed_fn
                     if self
@@ -313,4 +313,12 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
             Err(_) => None,
         }
     }
+
+    /// Forget all values except for the stack pointer,
+    /// since the analysis of the function exhausted its budget.
+    fn over_approximate(&self, state: &State) -> State {
+        let mut approximated_state = state.clone();
+        approximated_state.forget_all_values(&self.project.stack_pointer_register);
+        approximated_state
+    }
 }
@@ -0,0 +1,98 @@
+use super::*;
+
+/// The pointer inference states at the call sites and returns of a function.
+///
+/// Each state contains the abstract values of all registers
+/// and of all memory objects known to the function at the corresponding instruction.
+/// Pointers in the states are relative to the abstract identifiers of the function,
+/// i.e. to its stack frame, its parameters and the global memory.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FunctionSummary<'b> {
+    /// The states immediately before the call instructions of the function, indexed by the TIDs of the calls.
+    pub call_sites: BTreeMap<Tid, &'b State>,
+    /// The states immediately before the return instructions of the function, indexed by the TIDs of the returns.
+    pub returns: BTreeMap<Tid, &'b State>,
+}
+
+impl<'a> PointerInference<'a> {
+    /// Get the states at the call sites and returns of each function of the program, indexed by the function TIDs.
+    ///
+    /// Call sites and returns that are unreachable according to the analysis have no state
+    /// and are not contained in the summaries.
+    /// This function only yields results after the fixpoint has been computed.
+    pub fn function_summaries(&self) -> BTreeMap<Tid, FunctionSummary<'_>> {
+        let project = self.computation.get_context().get_context().project;
+        let mut summaries = BTreeMap::new();
+        for sub in project.program.term.subs.values() {
+            let mut summary = FunctionSummary::default();
+            for jmp in sub.term.blocks.iter().flat_map(|blk| blk.term.jmps.iter()) {
+                let Some(state) = self.states_at_tids.get(&jmp.tid) else {
+                    continue;
+                };
+                match &jmp.term {
+                    Jmp::Call { .. } | Jmp::CallInd { .. } | Jmp::CallOther { .. } => {
+                        summary.call_sites.insert(jmp.tid.clone(), state);
+                    }
+                    Jmp::Return(_) => {
+                        summary.returns.insert(jmp.tid.clone(), state);
+                    }
+                    _ => (),
+                }
+            }
+            summaries.insert(sub.tid.clone(), summary);
+        }
+        summaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstract_domain::TryToBitvec;
+    use crate::{bitvec, defs, expr};
+
+    #[test]
+    fn states_at_calls_and_returns() {
+        let mut project = Project::mock_x64();
+        let symbol = ExternSymbol::mock_x64("puts");
+        project
+            .program
+            .term
+            .extern_symbols
+            .insert(symbol.tid.clone(), symbol);
+        let mut blk_call = Blk::mock_with_tid("blk_call");
+        blk_call.term.defs = defs!["arg: RDI:8 = 0x42:8"];
+        blk_call
+            .term
+            .jmps
+            .push(Jmp::call("call_puts", "puts", Some("blk_return")));
+        let mut blk_return = Blk::mock_with_tid("blk_return");
+        blk_return.term.jmps.push(Term {
+            tid: Tid::new("return"),
+            term: Jmp::Return(expr!("0x0:8")),
+        });
+        let mut sub = Sub::mock("main");
+        sub.term.blocks = vec![blk_call, blk_return];
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+        project.program.term.entry_points.insert(Tid::new("main"));
+
+        let analysis_results = AnalysisResults::mock_from_project(&project);
+        let pointer_inference = analysis_results
+            .compute_pointer_inference(&serde_json::json!({"allocation_symbols": []}), false);
+        let summaries = pointer_inference.function_summaries();
+        let summary = &summaries[&Tid::new("main")];
+        assert_eq!(
+            summary.call_sites.keys().collect::<Vec<_>>(),
+            vec![&Tid::new("call_puts")]
+        );
+        assert_eq!(
+            summary.returns.keys().collect::<Vec<_>>(),
+            vec![&Tid::new("return")]
+        );
+        let state_at_call = summary.call_sites[&Tid::new("call_puts")];
+        assert_eq!(
+            state_at_call.eval(&expr!("RDI:8")).try_to_bitvec().unwrap(),
+            bitvec!("0x42:8")
+        );
+    }
+}
@@ -24,33 +24,45 @@
 //!
 //! See the `Config` struct for configurable analysis parameters.
 
-use super::fixpoint::Computation;
+use super::fixpoint::{Budget, Computation, Context as _};
 use super::forward_interprocedural_fixpoint::GeneralizedContext;
 use super::interprocedural_fixpoint_generic::NodeValue;
-use crate::abstract_domain::{AbstractIdentifier, DataDomain, IntervalDomain, SizedDomain};
+use crate::abstract_domain::{
+    AbstractIdentifier, DataDomain, IntervalDomain, SizedDomain, WideningConfig,
+};
 use crate::analysis::forward_interprocedural_fixpoint::Context as _;
-use crate::analysis::graph::{Graph, Node};
+use crate::analysis::graph::{Edge, Graph, Node};
+use crate::analysis::vsa_results::VsaResult;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::log::*;
 use petgraph::graph::NodeIndex;
 use petgraph::visit::IntoNodeReferences;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 mod context;
+mod function_summary;
 pub mod object;
 mod object_list;
+mod provenance;
 mod state;
 mod statistics;
 mod vsa_result_impl;
 
 use context::Context;
+pub use function_summary::FunctionSummary;
+use object::ObjectType;
+pub use provenance::{ObjectKind, PointerTarget};
 pub use state::State;
 
 /// The version number of the analysis.
 const VERSION: &str = "0.2";
 /// The recursion limit for nested pointers.
 const POINTER_RECURSION_DEPTH_LIMIT: u64 = 2;
+/// The maximal size in bytes of memory written by a modeled memory function
+/// for which the written values are computed chunk by chunk,
+/// e.g. for `memset` calls or for copies from read-only global memory.
+const MAX_MODELED_WRITE_SIZE: u64 = 1024;
 
 /// The name and version number of the "Memory" CWE check.
 pub static CWE_MODULE: crate::CweModule = crate::CweModule {
@@ -71,6 +83,138 @@ pub struct Config {
     /// Names of extern functions that are `malloc`-like,
     /// i.e. the unique return value is a pointer to a newly allocated chunk of memory or a NULL pointer.
     pub allocation_symbols: Vec<String>,
+    /// The maximal number of memory objects tracked in a state.
+    /// If a state exceeds the limit, the objects with the longest call chains are removed from it
+    /// and all pointers to them are replaced by *Top* values.
+    /// If `None`, the number of tracked objects is not limited.
+    #[serde(default)]
+    pub max_tracked_objects: Option<usize>,
+    /// Per-function overrides for `max_tracked_objects`, given by function name.
+    #[serde(default)]
+    pub max_tracked_objects_overrides: BTreeMap<String, usize>,
+    /// Extern functions that copy or set memory, given by function name,
+    /// whose effect on the destination object is modeled precisely.
+    /// Calls where the size or the destination is not known are handled like calls to other extern functions.
+    #[serde(default)]
+    pub memory_functions: BTreeMap<String, MemoryFunction>,
+    /// Extern functions that allocate or deallocate memory, given by function name,
+    /// together with the indices of their size and pointer parameters.
+    /// Allocation functions in this table are handled like the `allocation_symbols`.
+    /// Entries override the built-in parameter layouts of the standard allocation functions,
+    /// see [`AllocatorFunction::get_builtin`].
+    #[serde(default)]
+    pub allocator_functions: BTreeMap<String, AllocatorFunction>,
+    /// The configuration of the widening of value intervals, e.g. of loop counters.
+    #[serde(default)]
+    pub widening: WideningConfig,
+    /// The maximal number of node visits and the maximal time spent on the analysis of each function.
+    /// If a function exceeds its budget, all its states are replaced by states
+    /// where only the value of the stack pointer is known.
+    #[serde(default)]
+    pub budget: Budget,
+    /// The resident memory of the process in megabytes above which the states of functions
+    /// whose analysis has already stabilized are evicted and recomputed once they are needed again.
+    /// If `None`, all states are kept in memory.
+    #[serde(default)]
+    pub max_memory: Option<u64>,
+}
+
+/// The effect of an extern function that copies or sets memory,
+/// together with the indices of the relevant parameters.
+///
+/// All modeled functions return the destination pointer.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum MemoryFunction {
+    /// Copies `size` bytes from `source` to `destination`, like `memcpy`.
+    Copy {
+        destination: usize,
+        source: usize,
+        size: usize,
+    },
+    /// Copies the null-terminated string at `source` to `destination`, like `strcpy`.
+    ///
+    /// The copied values are only known if the source is a constant string in read-only memory.
+    StringCopy { destination: usize, source: usize },
+    /// Sets `size` bytes at `destination` to the byte `value`, like `memset`.
+    Set {
+        destination: usize,
+        value: usize,
+        size: usize,
+    },
+}
+
+impl MemoryFunction {
+    /// The index of the destination parameter.
+    pub fn destination(&self) -> usize {
+        match self {
+            MemoryFunction::Copy { destination, .. }
+            | MemoryFunction::StringCopy { destination, .. }
+            | MemoryFunction::Set { destination, .. } => *destination,
+        }
+    }
+}
+
+/// The effect of an extern function that allocates or deallocates memory,
+/// together with the indices of the relevant parameters.
+///
+/// The size of an allocated object is the product of the values of the `size` parameters.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub enum AllocatorFunction {
+    /// Returns a pointer to a new object, like `malloc` or `calloc`.
+    Allocation { size: Vec<usize> },
+    /// Frees the object that `pointer` points to and returns a pointer to a new object, like `realloc`.
+    Reallocation { pointer: usize, size: Vec<usize> },
+    /// Frees the object that `pointer` points to, like `free`.
+    Deallocation { pointer: usize },
+}
+
+impl AllocatorFunction {
+    /// Get the parameter layout of a standard allocation or deallocation function.
+    pub fn get_builtin(name: &str) -> Option<AllocatorFunction> {
+        let function = match name {
+            "malloc" | "operator.new" | "operator.new[]" => {
+                AllocatorFunction::Allocation { size: vec![0] }
+            }
+            "calloc" => AllocatorFunction::Allocation { size: vec![0, 1] },
+            "realloc" => AllocatorFunction::Reallocation {
+                pointer: 0,
+                size: vec![1],
+            },
+            "reallocarray" => AllocatorFunction::Reallocation {
+                pointer: 0,
+                size: vec![1, 2],
+            },
+            "free" | "operator.delete" | "operator.delete[]" => {
+                AllocatorFunction::Deallocation { pointer: 0 }
+            }
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    /// The indices of the parameters whose product is the size of the allocated object.
+    /// Empty for deallocation functions.
+    pub fn size_parameters(&self) -> &[usize] {
+        match self {
+            AllocatorFunction::Allocation { size }
+            | AllocatorFunction::Reallocation { size, .. } => size,
+            AllocatorFunction::Deallocation { .. } => &[],
+        }
+    }
+
+    /// The index of the parameter pointing to the freed object, if the function frees an object.
+    pub fn pointer_parameter(&self) -> Option<usize> {
+        match self {
+            AllocatorFunction::Allocation { .. } => None,
+            AllocatorFunction::Reallocation { pointer, .. }
+            | AllocatorFunction::Deallocation { pointer } => Some(*pointer),
+        }
+    }
+
+    /// Returns `true` if the function returns a pointer to a new object.
+    pub fn is_allocation(&self) -> bool {
+        !matches!(self, AllocatorFunction::Deallocation { .. })
+    }
 }
 
 /// A wrapper struct for the pointer inference computation object.
@@ -78,6 +222,9 @@ pub struct Config {
 pub struct PointerInference<'a> {
     /// The pointer inference fixpoint computation object.
     computation: Computation<GeneralizedContext<'a, Context<'a>>>,
+    /// The widening configuration used for the fixpoint computation.
+    /// Contains the constants of loop conditions if they are configured as thresholds.
+    widening_config: WideningConfig,
     /// A sender channel that can be used to collect logs in the corresponding log thread.
     log_collector: crossbeam_channel::Sender<LogThreadMsg>,
     /// The log messages and CWE warnings that have been generated during the pointer inference analysis.
@@ -94,6 +241,9 @@ pub struct PointerInference<'a> {
     /// Maps the TIDs of call instructions to a map mapping callee IDs to the corresponding value in the caller.
     /// The map will be filled after the fixpoint computation finished.
     id_renaming_maps_at_calls: HashMap<Tid, BTreeMap<AbstractIdentifier, Data>>,
+    /// Maps the IDs of all memory objects created during the analysis to their object type.
+    /// The map will be filled after the fixpoint computation finished.
+    object_types: HashMap<AbstractIdentifier, ObjectType>,
 }
 
 impl<'a> PointerInference<'a> {
@@ -104,6 +254,16 @@ impl<'a> PointerInference<'a> {
         log_sender: crossbeam_channel::Sender<LogThreadMsg>,
         print_stats: bool,
     ) -> PointerInference<'a> {
+        let mut widening_config = config.widening.clone();
+        if widening_config.loop_condition_thresholds {
+            widening_config
+                .thresholds
+                .extend(get_loop_condition_constants(
+                    analysis_results.control_flow_graph,
+                ));
+        }
+        let budget = config.budget;
+        let max_memory = config.max_memory;
         let context = Context::new(analysis_results, config, log_sender.clone());
         let project = analysis_results.project;
         let function_signatures = analysis_results.function_signatures.unwrap();
@@ -136,14 +296,19 @@ impl<'a> PointerInference<'a> {
                 super::interprocedural_fixpoint_generic::NodeValue::Value(fn_entry_state),
             );
         }
+        fixpoint_computation.set_budget(budget);
+        fixpoint_computation.set_memory_limit(max_memory.map(|megabytes| megabytes * 1024 * 1024));
+        fixpoint_computation.set_cancellation_token(analysis_results.cancellation_token);
         PointerInference {
             computation: fixpoint_computation,
+            widening_config,
             log_collector: log_sender,
             collected_logs: (Vec::new(), Vec::new()),
             values_at_defs: HashMap::new(),
             addresses_at_defs: HashMap::new(),
             states_at_tids: HashMap::new(),
             id_renaming_maps_at_calls: HashMap::new(),
+            object_types: HashMap::new(),
         }
     }
 
@@ -152,26 +317,82 @@ impl<'a> PointerInference<'a> {
     ///
     /// If `print_stats` is `true` then some extra log messages with statistics about the computation are generated.
     pub fn compute(&mut self, print_stats: bool) {
-        self.computation.compute_with_max_steps(100); // TODO: make max_steps configurable!
+        let computation = &mut self.computation;
+        self.widening_config.scope(|| {
+            computation.compute_with_max_steps(100); // TODO: make max_steps configurable!
+        });
         if print_stats {
             self.count_blocks_with_state();
         }
-        if !self.computation.has_stabilized() {
+        if self.computation.is_cancelled() {
+            let worklist_size = self.computation.get_worklist().len();
+            self.log_info(format!(
+                "Analysis incomplete: Fixpoint computation cancelled. Remaining worklist size: {worklist_size}"
+            ));
+        } else if !self.computation.has_stabilized() {
             let worklist_size = self.computation.get_worklist().len();
             self.log_info(format!(
                 "Fixpoint did not stabilize. Remaining worklist size: {worklist_size}"
             ));
         }
+        self.log_collapsed_objects(print_stats);
+        self.log_exhausted_budgets();
         if print_stats {
             statistics::compute_and_log_mem_access_stats(self);
         }
+        self.computation.release_restored_groups();
+    }
+
+    /// Log the functions in which memory objects had to be removed from states
+    /// because the maximal number of tracked objects was exceeded.
+    ///
+    /// If `print_stats` is `true` then an additional summary message is generated.
+    fn log_collapsed_objects(&self, print_stats: bool) {
+        let collapsed_objects = self.get_context().get_collapsed_objects();
+        for (fn_tid, ids) in collapsed_objects.iter() {
+            let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+            let log_msg = LogMessage::new_info(format!(
+                "Object limit exceeded. Precision lost for {} memory objects: {}",
+                ids.len(),
+                ids.join(", ")
+            ))
+            .location(fn_tid.clone())
+            .source("Pointer Inference");
+            let _ = self.log_collector.send(LogThreadMsg::Log(log_msg));
+        }
+        if print_stats {
+            let num_objects: usize = collapsed_objects.values().map(BTreeSet::len).sum();
+            self.log_info(format!(
+                "Object limit exceeded in {} functions. Collapsed memory objects: {num_objects}",
+                collapsed_objects.len()
+            ));
+        }
+    }
+
+    /// Log the functions whose analysis was aborted because they exhausted their budget.
+    fn log_exhausted_budgets(&self) {
+        let context = self.computation.get_context();
+        for group in self.computation.get_exhausted_budget_groups() {
+            let Some(fn_tid) = context.get_function_of_budget_group(*group) else {
+                continue;
+            };
+            let log_msg = LogMessage::new_info(
+                "Analysis incomplete: Budget exhausted. All values in the function are treated as unknown.",
+            )
+            .location(fn_tid.clone())
+            .source("Pointer Inference");
+            let _ = self.log_collector.send(LogThreadMsg::Log(log_msg));
+        }
     }
 
     /// Print results serialized as YAML to stdout
     pub fn print_yaml(&self) {
         let graph = self.computation.get_graph();
-        for (node_index, value) in self.computation.node_values().iter() {
-            let node = graph.node_weight(*node_index).unwrap();
+        for node_index in graph.node_indices() {
+            let Some(value) = self.get_node_value(node_index) else {
+                continue;
+            };
+            let node = graph.node_weight(node_index).unwrap();
             if let Ok(string) = serde_yaml::to_string(&(node, value)) {
                 println!("{string}");
             } else {
@@ -189,9 +410,9 @@ impl<'a> PointerInference<'a> {
     pub fn generate_compact_json(&self) -> serde_json::Value {
         let graph = self.computation.get_graph();
         let mut json_nodes = serde_json::Map::new();
-        for (node_index, node_value) in self.computation.node_values().iter() {
-            let node = graph.node_weight(*node_index).unwrap();
-            if let NodeValue::Value(value) = node_value {
+        for node_index in graph.node_indices() {
+            let node = graph.node_weight(node_index).unwrap();
+            if let Some(NodeValue::Value(value)) = self.get_node_value(node_index) {
                 json_nodes.insert(format!("{node}"), value.to_json_compact());
             }
         }
@@ -219,7 +440,9 @@ impl<'a> PointerInference<'a> {
     /// (or intermediate state of the algorithm if the fixpoint has not been reached yet).
     /// Returns `None` if no value is associated to the Node.
     pub fn get_node_value(&self, node_id: NodeIndex) -> Option<&NodeValue<State>> {
-        self.computation.get_node_value(node_id)
+        // States evicted to save memory are recomputed with the widening configuration of the analysis.
+        self.widening_config
+            .scope(|| self.computation.get_node_value(node_id))
     }
 
     /// Print the number of blocks that have a state associated to them.
@@ -231,7 +454,7 @@ impl<'a> PointerInference<'a> {
         for (node_id, node) in graph.node_references() {
             if let Node::BlkStart(_block, _sub) = node {
                 all_blocks += 1;
-                if self.computation.get_node_value(node_id).is_some() {
+                if self.get_node_value(node_id).is_some() {
                     stateful_blocks += 1;
                 }
             }
@@ -248,83 +471,104 @@ impl<'a> PointerInference<'a> {
     }
 
     /// Fill the various result maps of `self` that are needed for the [`VsaResult`](crate::analysis::vsa_results::VsaResult) trait implementation.
+    ///
+    /// If states were evicted to save memory, the states of each function are recomputed
+    /// and freed again once the nodes of the next function are reached.
     fn fill_vsa_result_maps(&mut self) {
+        let widening_config = self.widening_config.clone();
+        let mut previous_function = None;
+        for node in self.computation.get_graph().node_indices() {
+            let function = self.computation.get_context().get_budget_group(node);
+            if function != previous_function {
+                self.computation.release_restored_groups();
+                previous_function = function;
+            }
+            widening_config.scope(|| self.fill_vsa_result_maps_at_node(node));
+        }
+        self.computation.release_restored_groups();
+    }
+
+    /// Fill the result maps of `self` with the results at the given node.
+    fn fill_vsa_result_maps_at_node(&mut self, node: NodeIndex) {
         let context = self.computation.get_context().get_context();
         let graph = self.computation.get_graph();
-        for node in graph.node_indices() {
-            match graph[node] {
-                Node::BlkStart(blk, _sub) => {
-                    let node_state = match self.computation.get_node_value(node) {
-                        Some(NodeValue::Value(value)) => value,
-                        _ => continue,
-                    };
-                    let mut state = node_state.clone();
-                    for def in &blk.term.defs {
-                        match &def.term {
-                            Def::Assign { var: _, value } => {
-                                self.values_at_defs
-                                    .insert(def.tid.clone(), state.eval(value));
-                            }
-                            Def::Load { var, address } => {
-                                let loaded_value = state
-                                    .load_value(
-                                        address,
-                                        var.size,
-                                        &context.project.runtime_memory_image,
-                                    )
-                                    .unwrap_or_else(|_| Data::new_top(var.size));
-                                self.values_at_defs.insert(def.tid.clone(), loaded_value);
-                                self.addresses_at_defs
-                                    .insert(def.tid.clone(), state.eval(address));
-                            }
-                            Def::Store { address, value } => {
-                                self.values_at_defs
-                                    .insert(def.tid.clone(), state.eval(value));
-                                self.addresses_at_defs
-                                    .insert(def.tid.clone(), state.eval(address));
-                            }
+        match graph[node] {
+            Node::BlkStart(blk, _sub) => {
+                let node_state = match self.computation.get_node_value(node) {
+                    Some(NodeValue::Value(value)) => value,
+                    _ => return,
+                };
+                let mut state = node_state.clone();
+                for def in &blk.term.defs {
+                    match &def.term {
+                        Def::Assign { var: _, value } => {
+                            self.values_at_defs
+                                .insert(def.tid.clone(), state.eval(value));
                         }
-                        state = match context.update_def(&state, def) {
-                            Some(new_state) => new_state,
-                            None => break,
+                        Def::Load { var, address } => {
+                            let loaded_value = state
+                                .load_value(
+                                    address,
+                                    var.size,
+                                    &context.project.runtime_memory_image,
+                                )
+                                .unwrap_or_else(|_| Data::new_top(var.size));
+                            self.values_at_defs.insert(def.tid.clone(), loaded_value);
+                            self.addresses_at_defs
+                                .insert(def.tid.clone(), state.eval(address));
                         }
+                        Def::Store { address, value } => {
+                            self.values_at_defs
+                                .insert(def.tid.clone(), state.eval(value));
+                            self.addresses_at_defs
+                                .insert(def.tid.clone(), state.eval(address));
+                        }
+                    }
+                    state = match context.update_def(&state, def) {
+                        Some(new_state) => new_state,
+                        None => break,
                     }
                 }
-                Node::BlkEnd(blk, _sub) => {
-                    let node_state = match self.computation.get_node_value(node) {
-                        Some(NodeValue::Value(value)) => value,
-                        _ => continue,
-                    };
-                    for jmp in &blk.term.jmps {
-                        self.states_at_tids
-                            .insert(jmp.tid.clone(), node_state.clone());
+            }
+            Node::BlkEnd(blk, _sub) => {
+                let node_state = match self.computation.get_node_value(node) {
+                    Some(NodeValue::Value(value)) => value,
+                    _ => return,
+                };
+                for jmp in &blk.term.jmps {
+                    self.states_at_tids
+                        .insert(jmp.tid.clone(), node_state.clone());
+                }
+                for (id, object) in node_state.memory.iter() {
+                    if let Some(object_type) = object.get_object_type() {
+                        self.object_types.insert(id.clone(), object_type);
                     }
                 }
-                Node::CallSource { .. } => (),
-                Node::CallReturn {
-                    call: (caller_blk, _caller_sub),
-                    return_: _,
-                } => {
-                    let call_tid = match caller_blk.term.jmps.first() {
-                        Some(call) => &call.tid,
-                        _ => continue,
+            }
+            Node::CallSource { .. } => (),
+            Node::CallReturn {
+                call: (caller_blk, _caller_sub),
+                return_: _,
+            } => {
+                let call_tid = match caller_blk.term.jmps.first() {
+                    Some(call) => &call.tid,
+                    _ => return,
+                };
+                let (state_before_call, state_before_return) =
+                    match self.computation.get_node_value(node) {
+                        Some(NodeValue::CallFlowCombinator {
+                            call_stub: Some(state_before_call),
+                            interprocedural_flow: Some(state_before_return),
+                        }) => (state_before_call, state_before_return),
+                        _ => return,
                     };
-                    let (state_before_call, state_before_return) =
-                        match self.computation.get_node_value(node) {
-                            Some(NodeValue::CallFlowCombinator {
-                                call_stub: Some(state_before_call),
-                                interprocedural_flow: Some(state_before_return),
-                            }) => (state_before_call, state_before_return),
-                            _ => continue,
-                        };
-                    let id_to_data_map = context.create_full_callee_id_to_caller_data_map(
-                        state_before_call,
-                        state_before_return,
-                        call_tid,
-                    );
-                    self.id_renaming_maps_at_calls
-                        .insert(call_tid.clone(), id_to_data_map);
-                }
+                let id_to_data_map = context.create_full_callee_id_to_caller_data_map(
+                    state_before_call,
+                    state_before_return,
+                    call_tid,
+                );
+                self.id_renaming_maps_at_calls
+                    .insert(call_tid.clone(), id_to_data_map);
             }
         }
     }
@@ -335,6 +579,38 @@ impl<'a> PointerInference<'a> {
         self.states_at_tids.get(jmp_tid)
     }
 
+    /// Get the parameter layout of the allocation or deallocation function with the given name.
+    ///
+    /// Configured layouts take precedence over the built-in layouts of the standard allocation functions.
+    pub fn get_allocator_function(&self, name: &str) -> Option<AllocatorFunction> {
+        self.get_context().get_allocator_function(name)
+    }
+
+    /// Get the allocation and deallocation functions configured in the `allocator_functions` of the configuration.
+    pub fn get_configured_allocator_functions(&self) -> &BTreeMap<String, AllocatorFunction> {
+        &self.get_context().allocator_functions
+    }
+
+    /// Compute the size of the object allocated by the given call to an extern allocation function.
+    ///
+    /// Returns `None` if the parameter layout of the function is not known.
+    /// Returns *Top* if the value of a size parameter is unknown.
+    pub fn eval_allocation_size(&self, call_tid: &Tid, symbol: &ExternSymbol) -> Option<Data> {
+        let function = self.get_allocator_function(&symbol.name)?;
+        let mut size: Option<Data> = None;
+        for index in function.size_parameters() {
+            let parameter = symbol.parameters.get(*index)?;
+            let value = self
+                .eval_parameter_arg_at_call(call_tid, parameter)
+                .unwrap_or_else(|| Data::new_top(parameter.bytesize()));
+            size = Some(match size {
+                Some(size) => size.bin_op(BinOpType::IntMult, &value),
+                None => value,
+            });
+        }
+        size
+    }
+
     /// Get the mapping from callee IDs to caller values for the given call.
     /// This function only yields results after the fixpoint has been computed.
     ///
@@ -353,10 +629,10 @@ impl<'a> PointerInference<'a> {
     fn print_cfg_dead_ends(&self) {
         let graph = self.computation.get_graph();
         for (node_id, node) in graph.node_references() {
-            if let Some(node_value) = self.computation.get_node_value(node_id) {
+            if let Some(node_value) = self.get_node_value(node_id) {
                 if !graph
                     .neighbors(node_id)
-                    .any(|neighbor| self.computation.get_node_value(neighbor).is_some())
+                    .any(|neighbor| self.get_node_value(neighbor).is_some())
                 {
                     match node {
                         Node::BlkEnd(block, _sub) => {
@@ -428,6 +704,65 @@ pub fn extract_pi_analysis_results(
     pi_anaylsis.collected_logs.clone()
 }
 
+/// Collect the constants that values are compared against in the conditions of loops.
+///
+/// A block is considered part of a loop if it is contained in a cycle of intraprocedural control flow edges.
+fn get_loop_condition_constants(graph: &Graph) -> BTreeSet<i64> {
+    let intraprocedural_graph = graph.filter_map(
+        |_, node| Some(*node),
+        |_, edge| match edge {
+            Edge::Block
+            | Edge::Jump(..)
+            | Edge::ExternCallStub(_)
+            | Edge::CrCallStub
+            | Edge::ReturnCombine(_) => Some(()),
+            Edge::Call(_) | Edge::CrReturnStub | Edge::CallCombine(_) => None,
+        },
+    );
+    let mut constants = BTreeSet::new();
+    for component in petgraph::algo::kosaraju_scc(&intraprocedural_graph) {
+        if component.len() < 2 {
+            continue;
+        }
+        for node in component {
+            let Node::BlkEnd(blk, _) = intraprocedural_graph[node] else {
+                continue;
+            };
+            let conditions = blk
+                .term
+                .defs
+                .iter()
+                .filter_map(|def| match &def.term {
+                    Def::Assign { value, .. } => Some(value),
+                    _ => None,
+                })
+                .chain(blk.term.jmps.iter().filter_map(|jmp| match &jmp.term {
+                    Jmp::CBranch { condition, .. } => Some(condition),
+                    _ => None,
+                }));
+            for condition in conditions {
+                if let Expression::BinOp { op, lhs, rhs } = condition {
+                    use BinOpType::*;
+                    if !matches!(
+                        op,
+                        IntEqual | IntNotEqual | IntLess | IntSLess | IntLessEqual | IntSLessEqual
+                    ) {
+                        continue;
+                    }
+                    for operand in [lhs, rhs] {
+                        if let Expression::Const(constant) = &**operand {
+                            if let Ok(value) = constant.try_to_i64() {
+                                constants.insert(value);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    constants
+}
+
 /// Compute the pointer inference analysis and return its results.
 ///
 /// If `print_debug` is set to `true` print debug information to *stdout*.
@@ -466,6 +801,7 @@ pub mod tests {
     use crate::analysis::vsa_results::VsaResult;
     use crate::intermediate_representation::{Arg, Expression, RuntimeMemoryImage, Tid};
     use crate::ByteSize;
+    use crate::{bitvec, expr, variable};
 
     impl<'a> PointerInference<'a> {
         pub fn mock(project: &'a Project) -> PointerInference<'a> {
@@ -473,6 +809,13 @@ pub mod tests {
             let analysis_results: &'a AnalysisResults = Box::leak(analysis_results);
             let config = Config {
                 allocation_symbols: vec!["malloc".to_string()],
+                max_tracked_objects: None,
+                max_tracked_objects_overrides: BTreeMap::new(),
+                memory_functions: BTreeMap::new(),
+                allocator_functions: BTreeMap::new(),
+                widening: WideningConfig::default(),
+                budget: Budget::default(),
+                max_memory: None,
             };
             let (log_sender, _) = crossbeam_channel::unbounded();
             PointerInference::new(analysis_results, config, log_sender, false)
@@ -575,4 +918,67 @@ pub mod tests {
             Some(self.state.eval(expression))
         }
     }
+
+    #[test]
+    fn eval_allocation_size() {
+        let project = Project::mock_x64();
+        let mut pi_results = PointerInference::mock(&project);
+        let mut malloc_state = State::new(&variable!("RSP:8"), Tid::new("func"), BTreeSet::new());
+        malloc_state.set_register(&variable!("RDI:8"), bitvec!("3:8").into());
+        *pi_results.get_mut_states_at_tids() =
+            HashMap::from([(Tid::new("malloc_call"), malloc_state)]);
+        let malloc_symbol = ExternSymbol::mock_x64("malloc");
+
+        assert_eq!(
+            pi_results
+                .eval_allocation_size(&Tid::new("malloc_call"), &malloc_symbol)
+                .unwrap(),
+            bitvec!("3:8").into()
+        );
+        assert!(pi_results
+            .eval_allocation_size(&Tid::new("other"), &ExternSymbol::mock_x64("other"))
+            .is_none());
+    }
+
+    #[test]
+    fn loop_condition_constants() {
+        let comparison = |constant: &str| Expression::BinOp {
+            op: BinOpType::IntSLess,
+            lhs: Box::new(expr!("RAX:8")),
+            rhs: Box::new(expr!(constant)),
+        };
+        let mut blk_loop = Blk::mock_with_tid("blk_loop");
+        blk_loop.term.defs = vec![Term {
+            tid: Tid::new("loop_condition"),
+            term: Def::Assign {
+                var: variable!("ZF:1"),
+                value: comparison("0x10:8"),
+            },
+        }];
+        blk_loop.term.jmps = vec![
+            Term {
+                tid: Tid::new("loop_branch"),
+                term: Jmp::CBranch {
+                    target: Tid::new("blk_loop"),
+                    condition: expr!("ZF:1"),
+                },
+            },
+            Jmp::branch("loop_exit", "blk_exit"),
+        ];
+        let mut blk_exit = Blk::mock_with_tid("blk_exit");
+        blk_exit.term.defs = vec![Term {
+            tid: Tid::new("exit_condition"),
+            term: Def::Assign {
+                var: variable!("ZF:1"),
+                value: comparison("0x20:8"),
+            },
+        }];
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![blk_loop, blk_exit];
+        let mut project = Project::mock_x64();
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+        let graph = crate::analysis::graph::get_program_cfg(&project.program);
+
+        assert_eq!(get_loop_condition_constants(&graph), BTreeSet::from([0x10]));
+    }
 }
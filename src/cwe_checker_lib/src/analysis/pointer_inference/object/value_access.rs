@@ -56,6 +56,42 @@ impl AbstractObject {
         }
     }
 
+    /// Overwrite the interval of the given size starting at `offset` with the given values,
+    /// whose offsets are relative to the start of the interval.
+    /// Bytes of the interval not covered by the values are marked as `Top`.
+    ///
+    /// If the object is not unique, the values in the interval are only merged with `Top`,
+    /// since the written values may not have been written to all represented objects.
+    pub fn overwrite_interval(&mut self, offset: i64, size: u64, values: &[(i64, Data)]) {
+        let inner = Arc::make_mut(&mut self.inner);
+        for (_, value) in values {
+            inner
+                .pointer_targets
+                .extend(value.referenced_ids().cloned());
+        }
+        if size == 0 {
+            return;
+        }
+        if inner.is_unique {
+            let address_bytesize = inner.memory.get_address_bytesize();
+            inner.memory.remove(
+                Bitvector::from_i64(offset).into_sign_resize(address_bytesize),
+                Bitvector::from_u64(size).into_zero_resize(address_bytesize),
+            );
+            for (value_offset, value) in values {
+                inner
+                    .memory
+                    .insert_at_byte_index(value.clone(), offset + value_offset);
+            }
+        } else {
+            inner.memory.mark_interval_values_as_top(
+                offset,
+                offset + size as i64 - 1,
+                ByteSize::new(1),
+            );
+        }
+    }
+
     /// Marks all memory as `Top` and adds the `additional_targets` to the pointer targets.
     /// Represents the effect of unknown write instructions to the object
     /// which may include writing pointers to targets from the `additional_targets` set to the object.
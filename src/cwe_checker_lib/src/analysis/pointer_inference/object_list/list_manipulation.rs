@@ -69,7 +69,6 @@ impl AbstractObjectList {
     }
 
     /// Get the number of objects that are currently tracked.
-    #[cfg(test)]
     pub fn get_num_objects(&self) -> usize {
         self.objects.len()
     }
@@ -0,0 +1,182 @@
+//! Classification of the memory objects that pointers point to.
+//!
+//! The pointer inference tracks pointers relative to abstract memory objects.
+//! For checks it is often important to know what kind of memory such an object represents,
+//! e.g. to detect calls to `free` on stack pointers or writes to read-only global memory.
+//! The [`PointerInference::get_pointer_targets`] method classifies the targets of a value
+//! into the kinds defined by [`ObjectKind`]
+//! and provides the allocation site of heap objects.
+
+use super::object::ObjectType;
+use super::{Data, PointerInference, ValueDomain};
+use crate::abstract_domain::{AbstractIdentifier, TryToBitvec};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+
+/// The kind of memory that a pointer points to.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum ObjectKind {
+    /// The stack frame of a function.
+    Stack,
+    /// An object returned by an allocation function.
+    Heap,
+    /// Global memory that is not writeable, e.g. the `.rodata` section.
+    GlobalReadOnly,
+    /// Writeable global memory.
+    /// Also used for pointers to global memory at unknown addresses.
+    GlobalWritable,
+    /// An absolute address outside of all memory segments of the binary.
+    /// On embedded devices such addresses usually belong to memory-mapped peripherals.
+    Mmio,
+    /// An object not created by the function or its callees,
+    /// e.g. an object that a parameter of the function points to.
+    Extern,
+}
+
+/// A memory object that a value may point to.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PointerTarget {
+    /// The kind of the memory object.
+    pub kind: ObjectKind,
+    /// The identifier of the memory object.
+    /// Is `None` for absolute addresses.
+    pub id: Option<AbstractIdentifier>,
+    /// The offset of the pointer relative to the start of the memory object.
+    /// For global memory and memory-mapped peripherals the offset equals the address.
+    pub offset: ValueDomain,
+}
+
+impl PointerTarget {
+    /// Get the allocation site of the memory object.
+    ///
+    /// For heap objects this is the TID of the allocating call instruction.
+    /// For stack frames and objects of parameters this is the TID of the corresponding function.
+    /// Returns `None` for global memory and memory-mapped peripherals.
+    pub fn get_allocation_site(&self) -> Option<&Tid> {
+        match self.kind {
+            ObjectKind::Stack | ObjectKind::Heap | ObjectKind::Extern => {
+                self.id.as_ref().map(|id| id.get_tid())
+            }
+            ObjectKind::GlobalReadOnly | ObjectKind::GlobalWritable | ObjectKind::Mmio => None,
+        }
+    }
+}
+
+impl<'a> PointerInference<'a> {
+    /// Get all memory objects that the given value may point to.
+    ///
+    /// The absolute part of the value is only taken into account if it is an exact non-zero constant.
+    /// Since any integer can be interpreted as an address,
+    /// the classification of absolute values is only meaningful for values used as pointers.
+    pub fn get_pointer_targets(&self, value: &Data) -> Vec<PointerTarget> {
+        let memory_image = &self.get_context().project.runtime_memory_image;
+        let mut targets: Vec<PointerTarget> = value
+            .get_relative_values()
+            .iter()
+            .map(|(id, offset)| {
+                let kind = match self.object_types.get(id) {
+                    Some(ObjectType::Stack) => ObjectKind::Stack,
+                    Some(ObjectType::Heap) => ObjectKind::Heap,
+                    Some(ObjectType::GlobalMem) => classify_global_address(offset, memory_image),
+                    None => ObjectKind::Extern,
+                };
+                PointerTarget {
+                    kind,
+                    id: Some(id.clone()),
+                    offset: offset.clone(),
+                }
+            })
+            .collect();
+        if let Some(address) = value.get_absolute_value() {
+            if matches!(address.try_to_bitvec(), Ok(constant) if !constant.is_zero()) {
+                targets.push(PointerTarget {
+                    kind: classify_global_address(address, memory_image),
+                    id: None,
+                    offset: address.clone(),
+                });
+            }
+        }
+        targets
+    }
+}
+
+/// Classify a global memory address by the memory segment it points to.
+///
+/// Addresses outside of all memory segments are classified as memory-mapped peripherals.
+/// Non-constant addresses are classified as writeable global memory.
+fn classify_global_address(address: &ValueDomain, memory_image: &RuntimeMemoryImage) -> ObjectKind {
+    let Ok(address) = address.try_to_bitvec() else {
+        return ObjectKind::GlobalWritable;
+    };
+    match memory_image.is_address_writeable(&address) {
+        Ok(true) => ObjectKind::GlobalWritable,
+        Ok(false) => ObjectKind::GlobalReadOnly,
+        Err(_) => ObjectKind::Mmio,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitvec;
+
+    #[test]
+    fn pointer_targets() {
+        let project = Project::mock_x64();
+        let mut pi_results = PointerInference::mock(&project);
+        let stack_id = AbstractIdentifier::mock("func", "RSP", 8);
+        let heap_id = AbstractIdentifier::mock("malloc_call", "RAX", 8);
+        let global_id = AbstractIdentifier::mock("func", "global", 8);
+        let param_id = AbstractIdentifier::mock("func", "RDI", 8);
+        pi_results
+            .object_types
+            .insert(stack_id.clone(), ObjectType::Stack);
+        pi_results
+            .object_types
+            .insert(heap_id.clone(), ObjectType::Heap);
+        pi_results
+            .object_types
+            .insert(global_id.clone(), ObjectType::GlobalMem);
+
+        let mut value = Data::from_target(stack_id, bitvec!("-0x8:8").into());
+        value.insert_relative_value(heap_id.clone(), bitvec!("0x0:8").into());
+        value.insert_relative_value(global_id.clone(), bitvec!("0x1000:8").into());
+        value.insert_relative_value(param_id, bitvec!("0x0:8").into());
+        let kinds: Vec<ObjectKind> = pi_results
+            .get_pointer_targets(&value)
+            .iter()
+            .map(|target| target.kind)
+            .collect();
+        assert_eq!(kinds.len(), 4);
+        assert!(kinds.contains(&ObjectKind::Stack));
+        assert!(kinds.contains(&ObjectKind::Heap));
+        assert!(kinds.contains(&ObjectKind::GlobalReadOnly));
+        assert!(kinds.contains(&ObjectKind::Extern));
+
+        let heap_target = PointerTarget {
+            kind: ObjectKind::Heap,
+            id: Some(heap_id.clone()),
+            offset: bitvec!("0x0:8").into(),
+        };
+        assert_eq!(
+            heap_target.get_allocation_site(),
+            Some(&Tid::new("malloc_call"))
+        );
+
+        let value = Data::from_target(global_id, bitvec!("0x2000:8").into());
+        assert_eq!(
+            pi_results.get_pointer_targets(&value)[0].kind,
+            ObjectKind::GlobalWritable
+        );
+        for (address, kind) in [
+            ("0x1002:8", Some(ObjectKind::GlobalReadOnly)),
+            ("0x40000000:8", Some(ObjectKind::Mmio)),
+            ("0x0:8", None),
+        ] {
+            let value = Data::from(bitvec!(address));
+            let targets = pi_results.get_pointer_targets(&value);
+            assert_eq!(targets.first().map(|target| target.kind), kind);
+            assert!(targets.iter().all(|target| target.id.is_none()));
+        }
+    }
+}
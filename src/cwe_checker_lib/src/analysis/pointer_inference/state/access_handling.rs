@@ -216,6 +216,81 @@ impl State {
         }
     }
 
+    /// Read the values contained in the memory interval of the given size starting at `address`.
+    ///
+    /// The offsets of the returned values are relative to `address`.
+    /// Only values completely contained in the interval are returned.
+    /// Values in read-only global memory are read in chunks of the pointer size
+    /// if the interval is not larger than `MAX_MODELED_WRITE_SIZE`.
+    /// If the target of `address` is not exactly known, no values are returned.
+    pub fn read_memory_interval(
+        &self,
+        address: &Data,
+        size: u64,
+        global_memory: &RuntimeMemoryImage,
+    ) -> Vec<(i64, Data)> {
+        if let Some((id, offset)) = address.get_if_unique_target() {
+            let (Ok(offset), Some(object)) = (offset.try_to_offset(), self.memory.get_object(id))
+            else {
+                return Vec::new();
+            };
+            let end = offset + size as i64;
+            object
+                .get_mem_region()
+                .entry_map()
+                .range(offset..end)
+                .filter(|(position, value)| **position + u64::from(value.bytesize()) as i64 <= end)
+                .map(|(position, value)| (position - offset, value.clone()))
+                .collect()
+        } else if let Some(start) = address
+            .get_if_absolute_value()
+            .and_then(|address| address.try_to_bitvec().ok()?.try_to_u64().ok())
+        {
+            if size > MAX_MODELED_WRITE_SIZE {
+                return Vec::new();
+            }
+            let address_bytesize = self.stack_id.bytesize();
+            let mut values = Vec::new();
+            let mut offset = 0;
+            while offset < size {
+                let chunk_size = u64::from(address_bytesize).min(size - offset);
+                if let Ok(Some(value)) = global_memory.read(
+                    &Bitvector::from_u64(start + offset),
+                    ByteSize::new(chunk_size),
+                ) {
+                    values.push((offset as i64, value.into()));
+                }
+                offset += chunk_size;
+            }
+            values
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Overwrite the memory interval of the given size starting at `address` with the given values,
+    /// whose offsets are relative to `address`.
+    /// Bytes of the interval not covered by the values are marked as `Top`.
+    ///
+    /// Returns an error if the target of `address` is not exactly known.
+    pub fn write_memory_interval(
+        &mut self,
+        address: &Data,
+        size: u64,
+        values: &[(i64, Data)],
+    ) -> Result<(), Error> {
+        let (id, offset) = address
+            .get_if_unique_target()
+            .ok_or_else(|| anyhow!("Target of memory write not exactly known"))?;
+        let offset = offset.try_to_offset()?;
+        let object = self
+            .memory
+            .get_object_mut(id)
+            .ok_or_else(|| anyhow!("Target object of memory write not tracked"))?;
+        object.overwrite_interval(offset, size, values);
+        Ok(())
+    }
+
     /// Evaluate the value of a parameter of an extern symbol for the given state.
     pub fn eval_parameter_arg(
         &self,
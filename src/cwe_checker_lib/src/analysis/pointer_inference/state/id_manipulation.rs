@@ -153,6 +153,40 @@ impl State {
         self.register.retain(|_var, value| !value.is_top());
     }
 
+    /// Remove the memory objects with the given IDs from the state.
+    ///
+    /// All pointers to the removed objects are replaced by `Top` values,
+    /// both in registers and in the remaining memory objects.
+    /// Registers that only contain `Top` values afterwards are removed from the state.
+    pub fn collapse_objects(&mut self, ids_to_collapse: &BTreeSet<AbstractIdentifier>) {
+        for id in ids_to_collapse {
+            self.memory.remove(id);
+        }
+        let mut remaining_ids = self.memory.get_all_object_ids();
+        for value in self.register.values() {
+            remaining_ids.extend(value.referenced_ids().cloned());
+        }
+        for object_id in self.memory.get_all_object_ids() {
+            remaining_ids.extend(self.memory.get_referenced_ids_overapproximation(&object_id));
+        }
+        // IDs missing in the replacement map get replaced by `Top` values.
+        let id_replacement_map: BTreeMap<AbstractIdentifier, Data> = remaining_ids
+            .into_iter()
+            .filter(|id| !ids_to_collapse.contains(id))
+            .map(|id| {
+                let zero = Bitvector::zero(id.bytesize().into()).into();
+                (id.clone(), Data::from_target(id, zero))
+            })
+            .collect();
+        for value in self.register.values_mut() {
+            value.replace_all_ids(&id_replacement_map);
+        }
+        for object in self.memory.iter_objects_mut() {
+            object.replace_ids(&id_replacement_map);
+        }
+        self.register.retain(|_var, value| !value.is_top());
+    }
+
     /// Explicitly insert pointers to unified objects at the locations specified by their abstract location.
     ///
     /// Note that these are the only locations where we (by definition) know
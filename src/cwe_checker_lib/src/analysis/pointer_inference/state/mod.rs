@@ -1,6 +1,6 @@
 use super::object::AbstractObject;
 use super::object_list::AbstractObjectList;
-use super::Data;
+use super::{Data, MAX_MODELED_WRITE_SIZE};
 use crate::abstract_domain::*;
 use crate::analysis::function_signature::AccessPattern;
 use crate::analysis::function_signature::FunctionSignature;
@@ -310,6 +310,20 @@ impl State {
         self.register = register;
     }
 
+    /// Forget all knowledge about the contents of registers and memory objects,
+    /// except for the value of the stack register.
+    ///
+    /// Afterwards each memory object may contain pointers to all memory objects of the state.
+    /// The result is a sound over-approximation of all states reachable from this state
+    /// that does not change when repeated.
+    pub fn forget_all_values(&mut self, stack_register: &Variable) {
+        self.clear_non_callee_saved_register(std::slice::from_ref(stack_register));
+        let all_ids = self.memory.get_all_object_ids();
+        for object in self.memory.iter_objects_mut() {
+            object.assume_arbitrary_writes(&all_ids);
+        }
+    }
+
     /// Mark those parameter values of an extern function call, that are passed on the stack,
     /// as unknown data (since the function may modify them).
     pub fn clear_stack_parameter(
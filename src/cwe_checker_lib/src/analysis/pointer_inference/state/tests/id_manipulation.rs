@@ -310,3 +310,35 @@ fn test_insert_pointers_to_unified_objects() {
         Data::from_target(new_id_2.clone(), bitvec!("0x0:4").into())
     );
 }
+
+#[test]
+fn test_collapse_objects() {
+    let global_memory = RuntimeMemoryImage::mock();
+    let mut state = State::new(&variable!("RSP:8"), Tid::new("func"), BTreeSet::new());
+    let heap_id = new_id("heap", "RAX");
+    let heap_id_2 = new_id("heap_2", "RAX");
+    for id in [&heap_id, &heap_id_2] {
+        state
+            .memory
+            .add_abstract_object(id.clone(), ByteSize::new(8), Some(ObjectType::Heap));
+    }
+    let heap_pointer = Data::from_target(heap_id.clone(), bitvec!("0:8").into());
+    let heap_pointer_2 = Data::from_target(heap_id_2.clone(), bitvec!("0:8").into());
+    state.set_register(&variable!("RAX:8"), heap_pointer.clone());
+    state.set_register(&variable!("RBX:8"), heap_pointer.merge(&heap_pointer_2));
+    state
+        .store_value(&heap_pointer_2, &heap_pointer, &global_memory)
+        .unwrap();
+
+    state.collapse_objects(&BTreeSet::from([heap_id.clone()]));
+    assert!(state.memory.get_object(&heap_id).is_none());
+    assert!(state.memory.get_object(&heap_id_2).is_some());
+    assert!(state.get_register(&variable!("RAX:8")).is_top());
+    let mut expected_rbx = heap_pointer_2.clone();
+    expected_rbx.set_contains_top_flag();
+    assert_eq!(state.get_register(&variable!("RBX:8")), expected_rbx);
+    assert!(state
+        .load_value(&expr!("RBX:8"), ByteSize::new(8), &global_memory)
+        .unwrap()
+        .is_top());
+}
@@ -82,6 +82,29 @@ fn state() {
     assert_eq!(state.memory.get_num_objects(), 2);
 }
 
+#[test]
+fn forget_all_values() {
+    let global_memory = RuntimeMemoryImage::mock();
+    let mut state = State::new(&variable!("RSP:8"), Tid::new("time0"), BTreeSet::new());
+    let stack_addr = Data::from_target(new_id("time0", "RSP"), bv(-8));
+    state
+        .store_value(&stack_addr, &bv(42).into(), &global_memory)
+        .unwrap();
+    state.set_register(&variable!("RAX:8"), bv(42).into());
+    state.set_register(&variable!("RSP:8"), stack_addr.clone());
+
+    state.forget_all_values(&variable!("RSP:8"));
+    assert_eq!(state.get_register(&variable!("RSP:8")), stack_addr);
+    assert!(state.get_register(&variable!("RAX:8")).is_top());
+    assert!(state
+        .load_value(&expr!("RSP:8"), ByteSize::new(8), &global_memory)
+        .unwrap()
+        .contains_top());
+    let approximated_state = state.clone();
+    state.forget_all_values(&variable!("RSP:8"));
+    assert_eq!(state, approximated_state);
+}
+
 #[test]
 fn clear_parameters_on_the_stack_on_extern_calls() {
     let global_memory = RuntimeMemoryImage::mock();
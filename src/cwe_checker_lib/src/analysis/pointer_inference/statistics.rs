@@ -149,7 +149,7 @@ impl MemAccessStats {
         let global_memory = &context.project.runtime_memory_image;
         for (node_id, node) in graph.node_references() {
             if let Node::BlkStart(block, _sub) = node {
-                if let Some(state) = pointer_inference.computation.get_node_value(node_id) {
+                if let Some(state) = pointer_inference.get_node_value(node_id) {
                     let mut state = state.unwrap_value().clone();
                     for def in &block.term.defs {
                         stats.count_for_def(&state, def, global_memory);
@@ -149,6 +149,7 @@ pub fn substitute_and_on_stackpointer(project: &mut Project) -> Option<Vec<LogMe
         "x86_32" => 16,
         "x86_64" => 16,
         "arm32" => 4,
+        "RISCV_32" | "RISCV_64" => 16,
         _ => 0,
     };
 
@@ -27,6 +27,7 @@ mod trait_impls;
 /// Contains all context information needed for the string abstract fixpoint computation.
 ///
 /// The struct also implements the `interprocedural_fixpoint::Context` trait to enable the fixpoint computation.
+#[derive(Clone)]
 pub struct Context<'a, T: AbstractDomain + DomainInsertion + HasTop + Eq + From<String>> {
     /// A reference to the `Project` object representing the binary
     pub project: &'a Project,
@@ -63,7 +63,7 @@ impl<'a, T: AbstractDomain + DomainInsertion + HasTop + Eq + From<String>> Conte
                 self.handle_sprintf_and_snprintf_calls(state, extern_symbol)
             }
             "strcat" | "strncat" => self.handle_strcat_and_strncat_calls(state, extern_symbol),
-            "memcpy" => self.handle_memcpy_calls(state, extern_symbol),
+            "memcpy" | "strcpy" => self.handle_memcpy_calls(state, extern_symbol),
             "free" => self.handle_free(state, extern_symbol),
             _ => panic!("Unexpected Extern Symbol."),
         };
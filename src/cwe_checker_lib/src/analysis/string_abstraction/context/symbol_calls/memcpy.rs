@@ -14,7 +14,7 @@ use std::collections::BTreeMap;
 use crate::prelude::*;
 
 impl<'a, T: AbstractDomain + DomainInsertion + HasTop + Eq + From<String>> Context<'a, T> {
-    /// Handles the detection of string parameters to memcpy calls.
+    /// Handles the detection of string parameters to memcpy and strcpy calls.
     pub fn handle_memcpy_calls(&self, state: &State<T>, extern_symbol: &ExternSymbol) -> State<T> {
         let mut new_state = state.clone();
         if let Some(pi_state) = state.get_pointer_inference_state() {
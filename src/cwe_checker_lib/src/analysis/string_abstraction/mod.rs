@@ -1,21 +1,24 @@
 //! A fixpoint analysis that abstracts strings in the program using various string abstract domains.
-//! These include the Character Inclusion Domain and Bricks Domain among others.
+//! These include the Character Inclusion Domain, the Bricks Domain and the Automaton Domain among others.
 
 use std::{
     collections::{BTreeMap, HashMap},
     fmt::Debug,
+    num::NonZeroUsize,
 };
 
 use crate::{
     abstract_domain::{AbstractDomain, DomainInsertion, HasTop},
     intermediate_representation::Project,
     prelude::*,
+    utils::cancellation::CancellationToken,
 };
 
 use self::state::State;
 
 use super::{
-    fixpoint::Computation, forward_interprocedural_fixpoint::GeneralizedContext, graph::Graph,
+    callgraph::get_program_callgraph, fixpoint::Computation,
+    forward_interprocedural_fixpoint::GeneralizedContext, graph::Graph,
     interprocedural_fixpoint_generic::NodeValue,
     pointer_inference::PointerInference as PointerInferenceComputation,
 };
@@ -37,9 +40,14 @@ pub struct Config {
     pub format_string_index: BTreeMap<String, usize>,
 }
 
+/// The maximum number of times the fixpoint algorithm visits a node.
+const MAX_STEPS: u64 = 100; // TODO: make max_steps configurable!
+
 /// A wrapper struct for the string abstraction computation object.
 pub struct StringAbstraction<'a, T: AbstractDomain + DomainInsertion + HasTop + Eq + From<String>> {
     computation: Computation<GeneralizedContext<'a, Context<'a, T>>>,
+    /// Maps the TID of each function to the node index of the start node of its entry block.
+    sub_to_entry_node_map: HashMap<Tid, NodeIndex>,
 }
 
 impl<'a, T: AbstractDomain + DomainInsertion + HasTop + Eq + From<String>>
@@ -78,25 +86,104 @@ impl<'a, T: AbstractDomain + DomainInsertion + HasTop + Eq + From<String>>
         let mut fixpoint_computation =
             super::forward_interprocedural_fixpoint::create_computation(context, None);
 
-        for (_, start_node_index) in sub_to_entry_node_map.into_iter() {
+        for start_node_index in sub_to_entry_node_map.values() {
             fixpoint_computation.set_node_value(
-                start_node_index,
-                super::interprocedural_fixpoint_generic::NodeValue::Value(State::new(
-                    start_node_index,
-                    pointer_inference_results,
-                )),
+                *start_node_index,
+                NodeValue::Value(State::new(*start_node_index, pointer_inference_results)),
             );
         }
 
         StringAbstraction {
             computation: fixpoint_computation,
+            sub_to_entry_node_map,
         }
     }
 
-    /// Compute the fixpoint of the string abstraction analysis.
+    /// Compute the fixpoint of the string abstraction analysis
+    /// using as many threads as the system supports.
     /// Has a `max_steps` bound for the fixpoint algorithm to prevent infinite loops.
-    pub fn compute(&mut self) {
-        self.computation.compute_with_max_steps(100); // TODO: make max_steps configurable!
+    pub fn compute(&mut self)
+    where
+        T: Send + Sync,
+    {
+        let num_threads = std::thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1);
+        self.compute_in_parallel(num_threads);
+    }
+
+    /// Compute the fixpoint of the string abstraction analysis using up to `num_threads` threads.
+    ///
+    /// The analysis never propagates states from a caller into a callee or back,
+    /// so the fixpoint of a function only depends on the function itself
+    /// and on the (read-only) results of the pointer inference analysis.
+    /// Thus the functions are partitioned along the strongly connected components of the call graph
+    /// and the fixpoint of each partition is computed in a separate thread.
+    /// The results are merged afterwards and are the same as for a single-threaded computation.
+    pub fn compute_in_parallel(&mut self, num_threads: usize)
+    where
+        T: Send + Sync,
+    {
+        let project = self.get_context().project;
+        let partitions =
+            partition_functions(project, &self.sub_to_entry_node_map, num_threads.max(1));
+        if partitions.len() <= 1 {
+            self.computation.compute_with_max_steps(MAX_STEPS);
+            return;
+        }
+        let priority_sorted_nodes: Vec<NodeIndex> = petgraph::algo::kosaraju_scc(self.get_graph())
+            .into_iter()
+            .flatten()
+            .collect();
+        let context = self.get_context();
+        let pointer_inference_results = context.pointer_inference_results;
+        let cancellation_token = self.computation.get_cancellation_token().cloned();
+        let new_computation = || {
+            let mut computation = Computation::from_node_priority_list(
+                GeneralizedContext::new(context.clone()),
+                None,
+                priority_sorted_nodes.clone(),
+            );
+            computation.set_cancellation_token(cancellation_token.as_ref());
+            computation
+        };
+
+        let partial_computations: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = partitions
+                .iter()
+                .map(|entry_nodes| {
+                    let mut computation = new_computation();
+                    scope.spawn(move || {
+                        for start_node_index in entry_nodes {
+                            computation.set_node_value(
+                                *start_node_index,
+                                NodeValue::Value(State::new(
+                                    *start_node_index,
+                                    pointer_inference_results,
+                                )),
+                            );
+                        }
+                        computation.compute_with_max_steps(MAX_STEPS);
+                        computation
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("String abstraction thread panicked."))
+                .collect()
+        });
+
+        let mut computation = new_computation();
+        for partial_computation in partial_computations {
+            computation.merge_computation(partial_computation);
+        }
+        self.computation = computation;
+    }
+
+    /// Set the token that stops the computation once it is cancelled.
+    pub fn set_cancellation_token(&mut self, token: Option<&CancellationToken>) {
+        self.computation.set_cancellation_token(token);
     }
 
     /// Get the string abstraction computation.
@@ -122,8 +209,50 @@ impl<'a, T: AbstractDomain + DomainInsertion + HasTop + Eq + From<String>>
     }
 }
 
+/// Partition the functions with an entry node into at most `num_partitions` partitions
+/// and return the entry nodes of the functions in each partition.
+///
+/// Functions in the same strongly connected component of the call graph are always put into the same partition.
+/// The components are distributed so that the partitions contain roughly the same number of blocks.
+fn partition_functions(
+    project: &Project,
+    sub_to_entry_node_map: &HashMap<Tid, NodeIndex>,
+    num_partitions: usize,
+) -> Vec<Vec<NodeIndex>> {
+    let callgraph = get_program_callgraph(&project.program);
+    let mut components: Vec<(usize, Vec<NodeIndex>)> = petgraph::algo::kosaraju_scc(&callgraph)
+        .into_iter()
+        .filter_map(|component| {
+            let mut size = 0;
+            let mut entry_nodes = Vec::new();
+            for sub_tid in component.into_iter().map(|node| &callgraph[node]) {
+                if let Some(entry_node) = sub_to_entry_node_map.get(sub_tid) {
+                    size += project.program.term.subs[sub_tid].term.blocks.len();
+                    entry_nodes.push(*entry_node);
+                }
+            }
+            (!entry_nodes.is_empty()).then_some((size, entry_nodes))
+        })
+        .collect();
+    // Assign the largest components first, each to the currently smallest partition.
+    components.sort_by(|(size1, nodes1), (size2, nodes2)| {
+        size2.cmp(size1).then_with(|| nodes1.cmp(nodes2))
+    });
+    let mut partitions: Vec<(usize, Vec<NodeIndex>)> =
+        vec![(0, Vec::new()); num_partitions.min(components.len())];
+    for (size, entry_nodes) in components {
+        let (partition_size, partition_nodes) = partitions
+            .iter_mut()
+            .min_by_key(|(partition_size, _)| *partition_size)
+            .unwrap();
+        *partition_size += size;
+        partition_nodes.extend(entry_nodes);
+    }
+    partitions.into_iter().map(|(_, nodes)| nodes).collect()
+}
+
 /// Compute the string abstraction and return its results.
-pub fn run<'a, T: AbstractDomain + HasTop + Eq + From<String> + DomainInsertion>(
+pub fn run<'a, T: AbstractDomain + HasTop + Eq + From<String> + DomainInsertion + Send + Sync>(
     project: &'a Project,
     control_flow_graph: &'a Graph<'a>,
     pointer_inference: &'a PointerInferenceComputation<'a>,
@@ -1,4 +1,11 @@
-use crate::{def, defs, intermediate_representation::*};
+use std::collections::BTreeMap;
+
+use super::{Config, StringAbstraction};
+use crate::{
+    abstract_domain::CharacterInclusionDomain,
+    analysis::pointer_inference::PointerInference as PointerInferenceComputation, def, defs,
+    intermediate_representation::*,
+};
 
 fn mock_defs_for_sprintf(format_known: bool, blk_num: usize) -> Vec<Term<Def>> {
     /*
@@ -436,3 +443,76 @@ pub fn mock_project_with_intraprocedural_control_flow(
     project.program.term = program;
     project
 }
+
+#[test]
+fn parallel_computation() {
+    let mut project = mock_project_with_intraprocedural_control_flow(
+        vec![
+            (ExternSymbol::mock_sprintf_symbol_arm(), vec![true]),
+            (ExternSymbol::mock_strcat_symbol_arm(), vec![false]),
+        ],
+        "func",
+    );
+    let mut other_sub = mock_sub_with_name_and_symbol_calls(
+        "other_func",
+        vec![
+            (ExternSymbol::mock_malloc_symbol_arm(), vec![]),
+            (ExternSymbol::mock_memcpy_symbol_arm(), vec![true]),
+        ],
+    );
+    for blk in other_sub.term.blocks.iter_mut() {
+        blk.tid = Tid::new(format!("other_{}", blk.tid));
+        for jmp in blk.term.jmps.iter_mut() {
+            if let Jmp::Call {
+                return_: Some(return_tid),
+                ..
+            } = &mut jmp.term
+            {
+                *return_tid = Tid::new(format!("other_{}", return_tid));
+            }
+        }
+    }
+    project
+        .program
+        .term
+        .subs
+        .insert(other_sub.tid.clone(), other_sub);
+    // The analysis expects the addresses of `Def`s to be hexadecimal numbers.
+    let mut address = 0x10_0000;
+    for sub in project.program.term.subs.values_mut() {
+        for blk in sub.term.blocks.iter_mut() {
+            for def in blk.term.defs.iter_mut() {
+                def.tid.address = format!("{address:x}");
+                address += 4;
+            }
+        }
+    }
+    let mut pi_results = PointerInferenceComputation::mock(&project);
+    pi_results.compute(false);
+    let config = Config {
+        string_symbols: vec![
+            "sprintf".to_string(),
+            "strcat".to_string(),
+            "memcpy".to_string(),
+        ],
+        format_string_index: BTreeMap::from([("sprintf".to_string(), 1)]),
+    };
+
+    let mut sequential: StringAbstraction<CharacterInclusionDomain> = StringAbstraction::new(
+        &project,
+        pi_results.get_graph(),
+        &pi_results,
+        config.clone(),
+    );
+    sequential.compute_in_parallel(1);
+    let mut parallel: StringAbstraction<CharacterInclusionDomain> =
+        StringAbstraction::new(&project, pi_results.get_graph(), &pi_results, config);
+    parallel.compute_in_parallel(4);
+
+    assert!(parallel.get_computation().has_stabilized());
+    assert!(!parallel.get_computation().node_values().is_empty());
+    assert_eq!(
+        sequential.get_computation().node_values(),
+        parallel.get_computation().node_values()
+    );
+}
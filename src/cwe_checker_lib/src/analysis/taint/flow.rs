@@ -0,0 +1,434 @@
+//! Source-to-sink taint analyses defined by tables of functions.
+//!
+//! Many checks search for data flows from functions returning
+//! externally controlled data (sources) to parameters of dangerous functions
+//! (sinks). This module implements such an analysis once, so that a check only
+//! has to provide a [`TaintFlowConfig`] and turn the found [`TaintFlow`]s into
+//! CWE warnings.
+//!
+//! For each call to a source a separate taint analysis is started at the
+//! return site of the call. The taint is propagated
+//! - through calls to extern functions according to the configured
+//!   propagation rules and the taint summaries of imported libraries,
+//! - into called functions of the program via the tainted parameter registers,
+//! - and back to the callers via the return registers and the memory that is
+//!   also visible to the callers.
+//!
+//! Calls to sanitizers remove the taint from the memory pointed to by their
+//! parameters and do not return tainted values.
+//!
+//! # Limitations
+//!
+//! - Only register taint is passed to called functions. Taint in memory of the
+//!   caller is not visible in the callee until the callee returns.
+//! - The analysis is context-insensitive, i.e. taint returned by a function is
+//!   propagated to all of its callers.
+//! - Sanitizers only remove the taint at the exact address their parameters
+//!   point to, like taint is only seeded at the exact address for sources
+//!   returning data through a parameter.
+
+use super::summary::{TaintFlowTarget, TaintSummaries, TaintSummary};
+use super::{state::State, Taint, TaintAnalysis};
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, HasCfg, Node};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{Data as PiData, PointerInference};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::symbol_utils::get_symbol_map;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The configuration of a source-to-sink taint analysis.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct TaintFlowConfig {
+    /// Functions returning tainted data, together with the location that the data is returned in.
+    pub sources: BTreeMap<String, TaintFlowTarget>,
+    /// Functions together with the indices of their parameters that must not be tainted.
+    /// A parameter is also reported if it points to tainted memory.
+    pub sinks: BTreeMap<String, Vec<usize>>,
+    /// Functions that neutralize the data pointed to by their parameters.
+    #[serde(default)]
+    pub sanitizers: BTreeSet<String>,
+    /// Taint propagation rules for extern functions.
+    /// They take precedence over the taint summaries of imported libraries.
+    #[serde(default)]
+    pub propagators: BTreeMap<String, TaintSummary>,
+}
+
+/// A call to a source or a sink of a taint flow.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct CallSite {
+    /// The TID of the call.
+    pub call_tid: Tid,
+    /// The name of the called function.
+    pub function: String,
+}
+
+/// A flow of tainted data from the return of a source to a parameter of a sink.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct TaintFlow {
+    /// The call to the source function.
+    pub source: CallSite,
+    /// The call to the sink function that is reached by the taint.
+    pub sink: CallSite,
+}
+
+/// Compute all taint flows from calls to the sources to calls to the sinks of the configuration.
+///
+/// Returns no flows if the pointer inference was not computed.
+pub fn find_taint_flows(
+    analysis_results: &AnalysisResults,
+    config: &TaintFlowConfig,
+) -> BTreeSet<TaintFlow> {
+    let mut flows = BTreeSet::new();
+    let Some(pi_result) = analysis_results.pointer_inference else {
+        return flows;
+    };
+    let project = analysis_results.project;
+    let source_names: Vec<String> = config.sources.keys().cloned().collect();
+    let source_map = get_symbol_map(project, &source_names);
+    if source_map.is_empty() || config.sinks.is_empty() {
+        return flows;
+    }
+
+    let graph = analysis_results.control_flow_graph;
+    let (sink_sender, sink_receiver) = crossbeam_channel::unbounded();
+    let context = Context {
+        project,
+        pi_result,
+        control_flow_graph: graph,
+        config,
+        taint_summaries: analysis_results.taint_summaries,
+        sink_collector: sink_sender,
+    };
+    for edge in graph.edge_references() {
+        let Edge::ExternCallStub(jmp) = edge.weight() else {
+            continue;
+        };
+        let Jmp::Call { target, .. } = &jmp.term else {
+            continue;
+        };
+        let Some(symbol) = source_map.get(target) else {
+            continue;
+        };
+        let start_state = get_source_state(
+            project,
+            pi_result,
+            &jmp.tid,
+            symbol,
+            &config.sources[&symbol.name],
+        );
+        if start_state.is_empty() {
+            continue;
+        }
+        let mut computation = create_computation(context.clone(), None);
+        computation.set_cancellation_token(analysis_results.cancellation_token);
+        computation.set_node_value(edge.target(), NodeValue::Value(start_state));
+        // FIXME: This number should be in the config.
+        computation.compute_with_max_steps(100);
+        let source = CallSite {
+            call_tid: jmp.tid.clone(),
+            function: symbol.name.clone(),
+        };
+        for sink in sink_receiver.try_iter() {
+            flows.insert(TaintFlow {
+                source: source.clone(),
+                sink,
+            });
+        }
+    }
+    flows
+}
+
+/// Get the taint state after a call to a source,
+/// where the location containing the returned data is tainted.
+pub fn get_source_state(
+    project: &Project,
+    pi_result: &PointerInference,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    target: &TaintFlowTarget,
+) -> State {
+    let calling_convention = project.get_calling_convention(symbol);
+    let mut state = State::new_empty();
+    match target {
+        TaintFlowTarget::ReturnValue => {
+            for register in calling_convention.integer_return_register.iter() {
+                state.set_register_taint(register, Taint::Tainted(register.size));
+            }
+        }
+        TaintFlowTarget::ParameterTarget(index) => {
+            if let Some(param) = project.get_parameter_arg(symbol, *index) {
+                if let Some(address) = pi_result.eval_parameter_arg_at_call(call_tid, &param) {
+                    state.save_taint_to_memory(&address, Taint::Tainted(param.bytesize()));
+                }
+            }
+        }
+    }
+    state
+}
+
+/// Get taint propagation rules for functions that assemble their arguments into a buffer,
+/// e.g. `sprintf` or `strcat`.
+///
+/// The builders are mapped to the parameter index of their destination buffer.
+/// Taint in any other parameter flows to the memory pointed to by the destination parameter.
+pub fn get_builder_summaries(
+    project: &Project,
+    builders: &BTreeMap<String, usize>,
+) -> BTreeMap<String, TaintSummary> {
+    let parameter_count = project
+        .get_standard_calling_convention()
+        .map(|calling_convention| calling_convention.integer_parameter_register.len())
+        .unwrap_or_default();
+    builders
+        .iter()
+        .map(|(name, destination)| {
+            let flows = (0..parameter_count)
+                .filter(|index| index != destination)
+                .map(|index| {
+                    (
+                        index,
+                        BTreeSet::from([TaintFlowTarget::ParameterTarget(*destination)]),
+                    )
+                })
+                .collect();
+            (name.clone(), TaintSummary { flows })
+        })
+        .collect()
+}
+
+/// Check whether the given parameter of a call is tainted or points to tainted memory.
+pub fn is_parameter_tainted(
+    state: &State,
+    vsa_result: &impl VsaResult<ValueDomain = PiData>,
+    call_tid: &Tid,
+    param: &Arg,
+) -> bool {
+    let value_is_tainted = match param {
+        Arg::Register { expr, .. } => state.eval(expr).is_tainted(),
+        Arg::Stack { address, size, .. } => vsa_result
+            .eval_at_jmp(call_tid, address)
+            .is_some_and(|address| state.load_taint_from_memory(&address, *size).is_tainted()),
+    };
+    value_is_tainted
+        || vsa_result
+            .eval_parameter_arg_at_call(call_tid, param)
+            .is_some_and(|value| state.check_if_address_points_to_taint(value))
+}
+
+/// The Context struct for the source-to-sink taint analysis.
+#[derive(Clone)]
+struct Context<'a> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInference<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// The sources, sinks, sanitizers and propagation rules of the analysis.
+    config: &'a TaintFlowConfig,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where calls to sinks reached by the taint are sent to.
+    sink_collector: crossbeam_channel::Sender<CallSite>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
+    /// Report calls to sinks with tainted parameters and apply sanitizers and propagation rules.
+    ///
+    /// The analysis continues after a call to a sink, since the same data may reach several sinks.
+    fn update_extern_call(
+        &self,
+        state: &State,
+        call: &Term<Jmp>,
+        project: &Project,
+        extern_symbol: &ExternSymbol,
+    ) -> Option<State> {
+        if let Some(indices) = self.config.sinks.get(&extern_symbol.name) {
+            let is_tainted = indices.iter().any(|index| {
+                project
+                    .get_parameter_arg(extern_symbol, *index)
+                    .is_some_and(|param| {
+                        is_parameter_tainted(state, self.pi_result, &call.tid, &param)
+                    })
+            });
+            if is_tainted {
+                let _ = self.sink_collector.send(CallSite {
+                    call_tid: call.tid.clone(),
+                    function: extern_symbol.name.clone(),
+                });
+            }
+        }
+
+        let calling_conv = project.get_calling_convention(extern_symbol);
+        let mut new_state = state.clone();
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        let summary = self
+            .config
+            .propagators
+            .get(&extern_symbol.name)
+            .or_else(|| self.taint_summaries?.get(&extern_symbol.name));
+        if self.config.sanitizers.contains(&extern_symbol.name) {
+            for param in extern_symbol.parameters.iter() {
+                if let Some(address) = self.pi_result.eval_parameter_arg_at_call(&call.tid, param) {
+                    new_state.save_taint_to_memory(&address, Taint::Top(param.bytesize()));
+                }
+            }
+        } else if let Some(summary) = summary {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.pi_result,
+                &call.tid,
+                calling_conv,
+            );
+        }
+        Some(new_state)
+    }
+
+    /// Pass the taint of the parameter registers to the called function.
+    ///
+    /// Calls with an unknown calling convention are not followed.
+    fn update_call(
+        &self,
+        state: &State,
+        _call: &Term<Jmp>,
+        _target: &Node,
+        calling_convention: &Option<String>,
+    ) -> Option<State> {
+        let calling_conv = self
+            .project
+            .get_specific_calling_convention(calling_convention)?;
+        let mut callee_state = State::new_empty();
+        for register in calling_conv.get_all_parameter_register() {
+            let taint = state.get_register_taint(register);
+            if taint.is_tainted() {
+                callee_state.set_register_taint(register, taint);
+            }
+        }
+        if callee_state.is_empty() {
+            None
+        } else {
+            Some(callee_state)
+        }
+    }
+
+    /// Return the taint of the return registers and of the memory visible to the caller.
+    fn update_return_callee(
+        &self,
+        state: &State,
+        call_term: &Term<Jmp>,
+        _return_term: &Term<Jmp>,
+        calling_convention: &Option<String>,
+    ) -> Option<State> {
+        let (mut register_taint, mut memory_taint) = state.clone().into_mem_reg_taint();
+        let renaming_map = self.pi_result.get_call_renaming_map(&call_term.tid);
+        memory_taint.retain(|aid, _| renaming_map.is_some_and(|map| map.contains_key(aid)));
+        if let Some(calling_conv) = self
+            .project
+            .get_specific_calling_convention(calling_convention)
+        {
+            let return_registers = calling_conv.get_all_return_register();
+            register_taint
+                .retain(|reg, taint| return_registers.contains(&reg) && taint.is_tainted());
+        }
+        Some(State::from_mem_reg_taint(register_taint, memory_taint))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{defs, expr};
+
+    fn mock_project() -> Project {
+        let mut project = Project::mock_x64();
+        for name in ["getenv", "system", "escape"] {
+            let symbol = ExternSymbol::mock_x64(name);
+            project
+                .program
+                .term
+                .extern_symbols
+                .insert(symbol.tid.clone(), symbol);
+        }
+        // `main` passes the input to `run`, which passes it to `system`.
+        let mut blk_input = Blk::mock_with_tid("blk_input");
+        blk_input
+            .term
+            .jmps
+            .push(Jmp::call("call_getenv", "getenv", Some("blk_run")));
+        let mut blk_run = Blk::mock_with_tid("blk_run");
+        blk_run.term.defs = defs!["arg: RDI:8 = RAX:8"];
+        blk_run
+            .term
+            .jmps
+            .push(Jmp::call("call_run", "run", Some("blk_end")));
+        let mut main = Sub::mock("main");
+        main.term.blocks = vec![blk_input, blk_run, Blk::mock_with_tid("blk_end")];
+
+        let mut blk_system = Blk::mock_with_tid("blk_system");
+        blk_system
+            .term
+            .jmps
+            .push(Jmp::call("call_system", "system", Some("blk_return")));
+        let mut blk_return = Blk::mock_with_tid("blk_return");
+        blk_return.term.jmps.push(Term {
+            tid: Tid::new("return"),
+            term: Jmp::Return(expr!("0x0:8")),
+        });
+        let mut run = Sub::mock("run");
+        run.term.blocks = vec![blk_system, blk_return];
+
+        for sub in [main, run] {
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        project.program.term.entry_points.insert(Tid::new("main"));
+        project
+    }
+
+    #[test]
+    fn interprocedural_flow() {
+        let project = mock_project();
+        let analysis_results = AnalysisResults::mock_from_project(&project);
+        let pointer_inference = analysis_results
+            .compute_pointer_inference(&serde_json::json!({"allocation_symbols": []}), false);
+        let analysis_results = analysis_results.with_pointer_inference(Some(&pointer_inference));
+        let config: TaintFlowConfig = serde_json::from_value(serde_json::json!({
+            "sources": {"getenv": "ReturnValue"},
+            "sinks": {"system": [0]}
+        }))
+        .unwrap();
+
+        let flows = find_taint_flows(&analysis_results, &config);
+        assert_eq!(flows.len(), 1);
+        let flow = flows.iter().next().unwrap();
+        assert_eq!(flow.source.call_tid, Tid::new("call_getenv"));
+        assert_eq!(flow.sink.call_tid, Tid::new("call_system"));
+        assert_eq!(flow.sink.function, "system");
+    }
+}
@@ -9,6 +9,10 @@
 //! A user defines the specific *instance* of a Taint Analyses that they want to
 //! perform by implementing the [`TaintAnalysis`] trait. See its documentation
 //! for further information.
+//!
+//! For the common case of source-to-sink analyses defined by tables of
+//! sources, sinks, sanitizers and propagation rules, the [`flow`] module
+//! provides a ready-to-use interprocedural instance.
 
 use crate::abstract_domain::{AbstractDomain, HasTop, RegisterDomain, SizedDomain};
 use crate::analysis::graph::Node as CfgNode;
@@ -24,9 +28,12 @@ use crate::utils::debug::ToJsonCompact;
 use std::convert::AsRef;
 use std::fmt::Display;
 
+pub mod flow;
 pub mod state;
+pub mod summary;
 
 use state::State;
+use summary::TaintSummaries;
 
 /// Trait representing the definition of a Taint Analysis.
 ///
@@ -108,21 +115,47 @@ pub trait TaintAnalysis<'a>: HasCfg<'a> + HasVsaResult<PiData> + AsRef<Project>
         None
     }
 
+    /// Returns the taint summaries of functions exported by other binaries.
+    ///
+    /// Summaries are matched against extern symbols by name. See the
+    /// [`summary`] module for more information.
+    ///
+    /// # Default
+    ///
+    /// Returns `None`, i.e., no summaries are used.
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        None
+    }
+
     /// Transition function for calls to external functions.
     ///
     /// # Default
     ///
-    /// Removes taint from non-callee-saved registers.
+    /// Removes taint from non-callee-saved registers and applies the taint
+    /// summary of the called function if one is known.
     fn update_extern_call(
         &self,
         state: &State,
-        _call: &Term<Jmp>,
+        call: &Term<Jmp>,
         project: &Project,
         extern_symbol: &ExternSymbol,
     ) -> Option<State> {
+        let calling_conv = project.get_calling_convention(extern_symbol);
         let mut new_state = state.clone();
 
-        new_state.remove_non_callee_saved_taint(project.get_calling_convention(extern_symbol));
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        if let Some(summary) = self
+            .taint_summaries()
+            .and_then(|summaries| summaries.get(&extern_symbol.name))
+        {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.vsa_result(),
+                &call.tid,
+                calling_conv,
+            );
+        }
 
         Some(new_state)
     }
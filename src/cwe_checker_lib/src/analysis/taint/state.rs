@@ -398,32 +398,45 @@ impl State {
         call_tid: &Tid,
     ) -> bool {
         extern_symbol.parameters.iter().any(|parameter| {
-            match parameter {
-                Arg::Register { expr, .. } => {
-                    // Check for taint directly in value of parameter register.
-                    self.eval(expr).is_tainted()
-                    ||
-                    // Check if value in parameter register points to taint.
-                    (POINTER_TAINT && vsa_result.eval_at_jmp(call_tid, expr).is_some_and(|register_value| {
-                        self.check_if_address_points_to_taint(register_value)
-                    }))
-                }
-                Arg::Stack { address, size, .. } => {
-                    // Check for taint directly in the stack-based argument.
-                    vsa_result.eval_at_jmp(call_tid, address).is_some_and(|address_value| {
-                        self
-                            .load_taint_from_memory(&address_value, *size)
-                            .is_tainted()})
-                    ||
-                    // Check if stack-based argument points to taint.
-                    (POINTER_TAINT && vsa_result.eval_parameter_arg_at_call(call_tid, parameter).is_some_and(|stack_value| {
-                        self.check_if_address_points_to_taint(stack_value)
-                    }))
-                },
-            }
+            self.check_extern_parameter_for_taint::<POINTER_TAINT>(vsa_result, parameter, call_tid)
         })
     }
 
+    /// Check a single parameter of a call to an extern symbol for taint.
+    ///
+    /// If `POINTER_TAINT` is selected, we also return true if the parameter
+    /// is a pointer to tainted memory.
+    pub fn check_extern_parameter_for_taint<const POINTER_TAINT: bool>(
+        &self,
+        vsa_result: &impl VsaResult<ValueDomain = PiData>,
+        parameter: &Arg,
+        call_tid: &Tid,
+    ) -> bool {
+        match parameter {
+            Arg::Register { expr, .. } => {
+                // Check for taint directly in value of parameter register.
+                self.eval(expr).is_tainted()
+                ||
+                // Check if value in parameter register points to taint.
+                (POINTER_TAINT && vsa_result.eval_at_jmp(call_tid, expr).is_some_and(|register_value| {
+                    self.check_if_address_points_to_taint(register_value)
+                }))
+            }
+            Arg::Stack { address, size, .. } => {
+                // Check for taint directly in the stack-based argument.
+                vsa_result.eval_at_jmp(call_tid, address).is_some_and(|address_value| {
+                    self
+                        .load_taint_from_memory(&address_value, *size)
+                        .is_tainted()})
+                ||
+                // Check if stack-based argument points to taint.
+                (POINTER_TAINT && vsa_result.eval_parameter_arg_at_call(call_tid, parameter).is_some_and(|stack_value| {
+                    self.check_if_address_points_to_taint(stack_value)
+                }))
+            }
+        }
+    }
+
     /// Check whether `self` contains any taint at all.
     pub fn is_empty(&self) -> bool {
         !self.has_memory_taint() && !self.has_register_taint()
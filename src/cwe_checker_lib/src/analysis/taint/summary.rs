@@ -0,0 +1,571 @@
+//! Taint summaries of exported functions.
+//!
+//! A taint summary describes how taint contained in a parameter of a function
+//! (or in the memory pointed to by the parameter) flows to locations that are
+//! visible to the caller of the function, i.e. to the return value or to memory
+//! pointed to by other parameters.
+//!
+//! Summaries are computed for the exported functions of a library and are
+//! matched by symbol name against the extern symbols of the binaries linking
+//! against the library. Taint analyses use them in
+//! [`TaintAnalysis::update_extern_call`] to propagate taint through calls to
+//! library functions, so that the analysis of a set of binaries reflects the
+//! data flows of the whole system.
+//!
+//! # Limitations
+//!
+//! - Only integer parameter registers of the calling convention are tracked.
+//!   Parameters passed on the stack are ignored.
+//! - For memory pointed to by a parameter we only seed taint at the exact
+//!   address of the pointer, i.e. taint in later elements of arrays or structs
+//!   is not tracked.
+//! - Symbols are matched by name only, symbol versions are not taken into
+//!   account.
+
+use super::{state::State, Taint, TaintAnalysis};
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{get_entry_nodes_of_subs, Graph, HasCfg, Node, NodeIndex};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{Data as PiData, PointerInference};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::intermediate_representation::*;
+use crate::pipeline::AnalysisResults;
+use crate::prelude::*;
+use crate::utils::cancellation::CancellationToken;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// A location visible to the caller of a function that taint may flow to.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub enum TaintFlowTarget {
+    /// The return value of the function.
+    ReturnValue,
+    /// The memory pointed to by the integer parameter with the given index.
+    ParameterTarget(usize),
+}
+
+/// The taint summary of a function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct TaintSummary {
+    /// Maps the index of an integer parameter register to the locations that
+    /// taint in the parameter or in the memory pointed to by it may flow to.
+    pub flows: BTreeMap<usize, BTreeSet<TaintFlowTarget>>,
+}
+
+impl TaintSummary {
+    /// Returns true if the summary contains no taint flows.
+    pub fn is_empty(&self) -> bool {
+        self.flows.values().all(|targets| targets.is_empty())
+    }
+
+    /// Apply the summary to a call of the summarized function.
+    ///
+    /// The `state` is the taint state before the call.
+    /// Taint flows caused by the call are added to `new_state`.
+    pub fn apply(
+        &self,
+        state: &State,
+        new_state: &mut State,
+        vsa_result: &impl VsaResult<ValueDomain = PiData>,
+        call_tid: &Tid,
+        calling_convention: &CallingConvention,
+    ) {
+        let param_registers = &calling_convention.integer_parameter_register;
+        for (index, targets) in self.flows.iter() {
+            let Some(param) = param_registers.get(*index) else {
+                continue;
+            };
+            let is_tainted = state.get_register_taint(param).is_tainted()
+                || vsa_result
+                    .eval_parameter_arg_at_call(call_tid, &Arg::from_var(param.clone(), None))
+                    .is_some_and(|value| state.check_if_address_points_to_taint(value));
+            if !is_tainted {
+                continue;
+            }
+            for target in targets {
+                match target {
+                    TaintFlowTarget::ReturnValue => {
+                        for register in calling_convention.integer_return_register.iter() {
+                            new_state.set_register_taint(register, Taint::Tainted(register.size));
+                        }
+                    }
+                    TaintFlowTarget::ParameterTarget(target_index) => {
+                        let Some(target_param) = param_registers.get(*target_index) else {
+                            continue;
+                        };
+                        if let Some(address) = vsa_result.eval_parameter_arg_at_call(
+                            call_tid,
+                            &Arg::from_var(target_param.clone(), None),
+                        ) {
+                            new_state
+                                .save_taint_to_memory(&address, Taint::Tainted(target_param.size));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The taint summaries of the exported functions of one or more binaries,
+/// indexed by the symbol name of the functions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct TaintSummaries {
+    summaries: BTreeMap<String, TaintSummary>,
+}
+
+impl TaintSummaries {
+    /// Get the summary of the function with the given symbol name.
+    pub fn get(&self, name: &str) -> Option<&TaintSummary> {
+        self.summaries.get(name)
+    }
+
+    /// Add the summary of the function with the given symbol name.
+    /// Summaries without taint flows are not stored.
+    pub fn insert(&mut self, name: String, summary: TaintSummary) {
+        if !summary.is_empty() {
+            self.summaries.insert(name, summary);
+        }
+    }
+
+    /// Add all summaries of `other` to `self`.
+    /// If both contain a summary for the same symbol, the summary of `other` is used.
+    pub fn extend(&mut self, other: TaintSummaries) {
+        self.summaries.extend(other.summaries);
+    }
+
+    /// Returns the number of summarized functions.
+    pub fn len(&self) -> usize {
+        self.summaries.len()
+    }
+
+    /// Returns true if no function is summarized.
+    pub fn is_empty(&self) -> bool {
+        self.summaries.is_empty()
+    }
+}
+
+/// Compute the taint summaries of all exported functions of the binary.
+///
+/// The summaries of callees inside the binary are computed first
+/// and applied at their call sites, until the summaries do not change anymore.
+/// Calls to extern symbols use the given summaries of imported functions,
+/// which allows to chain the summaries of several libraries.
+///
+/// Returns no summaries if the pointer inference was not computed.
+pub fn compute_taint_summaries(
+    analysis_results: &AnalysisResults,
+    imported_summaries: Option<&TaintSummaries>,
+) -> TaintSummaries {
+    let Some(pi_result) = analysis_results.pointer_inference else {
+        return TaintSummaries::default();
+    };
+    let project = analysis_results.project;
+    let call_targets = get_internal_call_targets(project);
+    let relevant_subs = get_subs_reachable_from_entry_points(project, &call_targets);
+    let entry_nodes = get_entry_nodes_of_subs(analysis_results.control_flow_graph);
+
+    let mut internal_summaries = BTreeMap::new();
+    loop {
+        let mut changed = false;
+        for sub_tid in relevant_subs.iter() {
+            let Some(entry_node) = entry_nodes.get(sub_tid) else {
+                continue;
+            };
+            let context = Context {
+                project,
+                pi_result,
+                control_flow_graph: analysis_results.control_flow_graph,
+                imported_summaries,
+                internal_summaries: &internal_summaries,
+                call_targets: &call_targets,
+            };
+            let summary = context.compute_summary(&project.program.term.subs[sub_tid], *entry_node);
+            if internal_summaries.get(sub_tid) != Some(&summary) {
+                internal_summaries.insert(sub_tid.clone(), summary);
+                changed = true;
+            }
+        }
+        let cancelled = analysis_results
+            .cancellation_token
+            .is_some_and(CancellationToken::is_cancelled);
+        if !changed || cancelled {
+            break;
+        }
+    }
+
+    let mut summaries = TaintSummaries::default();
+    for sub_tid in project.program.term.entry_points.iter() {
+        if let (Some(sub), Some(summary)) = (
+            project.program.term.subs.get(sub_tid),
+            internal_summaries.remove(sub_tid),
+        ) {
+            summaries.insert(sub.term.name.clone(), summary);
+        }
+    }
+    summaries
+}
+
+/// Map the TIDs of all direct calls to functions inside the binary to the TID of the called function.
+fn get_internal_call_targets(project: &Project) -> HashMap<Tid, Tid> {
+    let subs = &project.program.term.subs;
+    subs.values()
+        .flat_map(|sub| sub.term.blocks.iter())
+        .flat_map(|block| block.term.jmps.iter())
+        .filter_map(|jmp| match &jmp.term {
+            Jmp::Call { target, .. } if subs.contains_key(target) => {
+                Some((jmp.tid.clone(), target.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Get the TIDs of all exported functions and of all functions that they (transitively) call.
+///
+/// Callees are ordered before their callers where possible,
+/// so that fewer iterations are needed to compute the summaries.
+fn get_subs_reachable_from_entry_points(
+    project: &Project,
+    call_targets: &HashMap<Tid, Tid>,
+) -> Vec<Tid> {
+    let subs = &project.program.term.subs;
+    let mut visited = HashSet::new();
+    let mut ordered_subs = Vec::new();
+    for entry_point in project.program.term.entry_points.iter() {
+        if !subs.contains_key(entry_point) || !visited.insert(entry_point.clone()) {
+            continue;
+        }
+        // Iterative post-order depth-first search
+        let mut stack = vec![(entry_point.clone(), false)];
+        while let Some((sub_tid, callees_handled)) = stack.pop() {
+            if callees_handled {
+                ordered_subs.push(sub_tid);
+                continue;
+            }
+            stack.push((sub_tid.clone(), true));
+            for callee in subs[&sub_tid]
+                .term
+                .blocks
+                .iter()
+                .flat_map(|block| block.term.jmps.iter())
+                .filter_map(|jmp| call_targets.get(&jmp.tid))
+            {
+                if visited.insert(callee.clone()) {
+                    stack.push((callee.clone(), false));
+                }
+            }
+        }
+    }
+    ordered_subs
+}
+
+/// The context of the taint analyses used to compute the summary of a function.
+#[derive(Clone)]
+struct Context<'a, 'b> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInference<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// Summaries of functions imported from other binaries.
+    imported_summaries: Option<&'b TaintSummaries>,
+    /// The summaries of functions inside the binary computed so far.
+    internal_summaries: &'b BTreeMap<Tid, TaintSummary>,
+    /// Maps the TIDs of calls to functions inside the binary to the TID of the called function.
+    call_targets: &'b HashMap<Tid, Tid>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a, '_> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a, '_> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a, '_> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a, '_> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.imported_summaries
+    }
+
+    /// Remove taint from non-callee-saved registers
+    /// and apply the summary of the callee if the callee is known.
+    fn update_call_generic(
+        &self,
+        state: &State,
+        call_tid: &Tid,
+        calling_convention_hint: &Option<String>,
+    ) -> Option<State> {
+        let calling_conv = self
+            .project
+            .get_specific_calling_convention(calling_convention_hint)?;
+        let mut new_state = state.clone();
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        if let Some(summary) = self
+            .call_targets
+            .get(call_tid)
+            .and_then(|callee| self.internal_summaries.get(callee))
+        {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.pi_result,
+                call_tid,
+                calling_conv,
+            );
+        }
+
+        if new_state.is_empty() {
+            self.handle_empty_state_out(call_tid)
+        } else {
+            Some(new_state)
+        }
+    }
+}
+
+impl<'a> Context<'a, '_> {
+    /// Compute the summary of the given function
+    /// by running one intraprocedural taint analysis per integer parameter register.
+    fn compute_summary(&self, sub: &Term<Sub>, entry_node: NodeIndex) -> TaintSummary {
+        let mut summary = TaintSummary::default();
+        let Some(calling_conv) = self
+            .project
+            .get_specific_calling_convention(&sub.term.calling_convention)
+        else {
+            return summary;
+        };
+        let param_values: Vec<Option<PiData>> = calling_conv
+            .integer_parameter_register
+            .iter()
+            .map(|param| {
+                self.pi_result
+                    .eval_at_node(entry_node, &Expression::Var(param.clone()))
+            })
+            .collect();
+
+        for (index, param) in calling_conv.integer_parameter_register.iter().enumerate() {
+            let mut start_state = State::new_empty();
+            start_state.set_register_taint(param, Taint::Tainted(param.size));
+            if let Some(param_value) = &param_values[index] {
+                start_state.save_taint_to_memory(param_value, Taint::Tainted(param.size));
+            }
+            let mut computation = create_computation(self.clone(), None);
+            computation.set_node_value(entry_node, NodeValue::Value(start_state));
+            computation.compute_with_max_steps(100);
+
+            let mut targets = BTreeSet::new();
+            for node in self.control_flow_graph.node_indices() {
+                let Node::BlkEnd(block, node_sub) = &self.control_flow_graph[node] else {
+                    continue;
+                };
+                if node_sub.tid != sub.tid {
+                    continue;
+                }
+                let Some(NodeValue::Value(state)) = computation.get_node_value(node) else {
+                    continue;
+                };
+                for jmp in block.term.jmps.iter() {
+                    if matches!(jmp.term, Jmp::Return(_)) {
+                        self.add_flow_targets_at_return(
+                            state,
+                            &jmp.tid,
+                            index,
+                            &param_values,
+                            calling_conv,
+                            &mut targets,
+                        );
+                    }
+                }
+            }
+            if !targets.is_empty() {
+                summary.flows.insert(index, targets);
+            }
+        }
+        summary
+    }
+
+    /// Add the locations visible to the caller that contain taint at the given return instruction
+    /// to the flow targets of the parameter with the given index.
+    fn add_flow_targets_at_return(
+        &self,
+        state: &State,
+        return_tid: &Tid,
+        param_index: usize,
+        param_values: &[Option<PiData>],
+        calling_conv: &CallingConvention,
+        targets: &mut BTreeSet<TaintFlowTarget>,
+    ) {
+        let param = &calling_conv.integer_parameter_register[param_index];
+        if calling_conv.integer_return_register.iter().any(|register| {
+            let value = self
+                .pi_result
+                .eval_at_jmp(return_tid, &Expression::Var(register.clone()));
+            // A parameter register that is also a return register and still contains
+            // the parameter value does not indicate a flow to the return value.
+            if register == param && value.is_some() && value == param_values[param_index] {
+                return false;
+            }
+            state.get_register_taint(register).is_tainted()
+                || value.is_some_and(|value| state.check_if_address_points_to_taint(value))
+        }) {
+            targets.insert(TaintFlowTarget::ReturnValue);
+        }
+        for (index, param_value) in param_values.iter().enumerate() {
+            if index == param_index {
+                continue;
+            }
+            if let Some(param_value) = param_value {
+                if param_value
+                    .get_relative_values()
+                    .keys()
+                    .any(|id| state.check_mem_id_for_taint(id))
+                {
+                    targets.insert(TaintFlowTarget::ParameterTarget(index));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{def, expr, variable};
+
+    /// Create a library with an exported function `outer` that calls the internal function `inner`,
+    /// which returns its first parameter.
+    /// `outer` stores the returned value to the memory pointed to by its second parameter.
+    fn mock_library() -> Project {
+        let mut project = Project::mock_x64();
+        let mut blk_inner = Blk::mock_with_tid("blk_inner");
+        blk_inner.term.defs = vec![
+            def!["copy: RAX:8 = RDI:8"],
+            def!["pop_return_address: RSP:8 = RSP:8 + 0x8:8"],
+        ];
+        blk_inner.term.jmps.push(Term {
+            tid: Tid::new("return_inner"),
+            term: Jmp::Return(expr!("0x0:8")),
+        });
+        let mut inner = Sub::mock("inner");
+        inner.term.blocks = vec![blk_inner];
+
+        let mut blk_outer = Blk::mock_with_tid("blk_outer");
+        blk_outer.term.defs = vec![def!["save_dest: RBX:8 = RSI:8"]];
+        blk_outer
+            .term
+            .jmps
+            .push(Jmp::call("call_inner", "inner", Some("blk_outer_return")));
+        let mut blk_outer_return = Blk::mock_with_tid("blk_outer_return");
+        blk_outer_return.term.defs = vec![
+            def!["store: Store at RBX:8 := RAX:8"],
+            def!["pop_return_address_outer: RSP:8 = RSP:8 + 0x8:8"],
+        ];
+        blk_outer_return.term.jmps.push(Term {
+            tid: Tid::new("return_outer"),
+            term: Jmp::Return(expr!("0x0:8")),
+        });
+        let mut outer = Sub::mock("outer");
+        outer.term.blocks = vec![blk_outer, blk_outer_return];
+
+        for sub in [inner, outer] {
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        project.program.term.entry_points.insert(Tid::new("outer"));
+        project
+    }
+
+    #[test]
+    fn summaries_of_exported_functions() {
+        let project = mock_library();
+        let analysis_results = AnalysisResults::mock_from_project(&project);
+        let pointer_inference = analysis_results
+            .compute_pointer_inference(&serde_json::json!({"allocation_symbols": []}), false);
+        let analysis_results = analysis_results.with_pointer_inference(Some(&pointer_inference));
+
+        let summaries = compute_taint_summaries(&analysis_results, None);
+        assert_eq!(summaries.len(), 1);
+        assert!(summaries.get("inner").is_none());
+        let expected_flows = BTreeMap::from([(
+            0,
+            BTreeSet::from([
+                TaintFlowTarget::ReturnValue,
+                TaintFlowTarget::ParameterTarget(1),
+            ]),
+        )]);
+        assert_eq!(summaries.get("outer").unwrap().flows, expected_flows);
+    }
+
+    #[test]
+    fn apply_summary_at_call_site() {
+        let mut project = Project::mock_x64();
+        let outer = ExternSymbol::mock_x64("outer");
+        project
+            .program
+            .term
+            .extern_symbols
+            .insert(outer.tid.clone(), outer);
+        let mut blk = Blk::mock_with_tid("blk");
+        blk.term.defs = vec![def!["buffer: RSI:8 = RSP:8 + 0xfffffffffffffff0:8"]];
+        blk.term
+            .jmps
+            .push(Jmp::call("call_outer", "outer", Some("blk_end")));
+        let mut sub = Sub::mock("caller");
+        sub.term.blocks = vec![blk, Blk::mock_with_tid("blk_end")];
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+        let analysis_results = AnalysisResults::mock_from_project(&project);
+        let pointer_inference = analysis_results
+            .compute_pointer_inference(&serde_json::json!({"allocation_symbols": []}), false);
+
+        let summary = TaintSummary {
+            flows: BTreeMap::from([(
+                0,
+                BTreeSet::from([
+                    TaintFlowTarget::ReturnValue,
+                    TaintFlowTarget::ParameterTarget(1),
+                ]),
+            )]),
+        };
+        let calling_conv = CallingConvention::mock_x64();
+        let call_tid = Tid::new("call_outer");
+        let buffer = pointer_inference
+            .eval_parameter_arg_at_call(&call_tid, &Arg::from_var(variable!("RSI:8"), None))
+            .unwrap();
+
+        let untainted_state = State::new_empty();
+        let mut new_state = State::new_empty();
+        summary.apply(
+            &untainted_state,
+            &mut new_state,
+            &pointer_inference,
+            &call_tid,
+            &calling_conv,
+        );
+        assert!(new_state.is_empty());
+
+        let mut state = State::new_empty();
+        state.set_register_taint(&variable!("RDI:8"), Taint::Tainted(ByteSize::new(8)));
+        let mut new_state = State::new_empty();
+        summary.apply(
+            &state,
+            &mut new_state,
+            &pointer_inference,
+            &call_tid,
+            &calling_conv,
+        );
+        assert!(new_state
+            .get_register_taint(&variable!("RAX:8"))
+            .is_tainted());
+        assert!(new_state.check_if_address_points_to_taint(buffer));
+    }
+}
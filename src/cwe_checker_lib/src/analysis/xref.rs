@@ -0,0 +1,227 @@
+//! An index of cross references from instructions to global memory.
+//!
+//! The index maps addresses of global memory to the instructions that reference them.
+//! References are found by searching for constants pointing into the runtime memory image
+//! in the expressions of all `Def` terms of the program.
+//! If the results of the [Pointer Inference analysis](crate::analysis::pointer_inference)
+//! are available, they are additionally used to resolve the target addresses of load and store instructions
+//! that are not given as constants.
+
+use crate::abstract_domain::{AbstractLocation, TryToBitvec};
+use crate::analysis::pointer_inference::{Data, PointerInference};
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The kind of access to global memory represented by a cross reference.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum XrefKind {
+    /// The address is used as a value, e.g. as a pointer argument to a function call.
+    Reference,
+    /// The instruction reads from the address.
+    Read,
+    /// The instruction writes to the address.
+    Write,
+}
+
+/// A cross reference from an instruction to an address in global memory.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct Xref {
+    /// The TID of the instruction containing the reference.
+    pub tid: Tid,
+    /// The TID of the function containing the instruction.
+    pub sub: Tid,
+    /// The kind of the access.
+    pub kind: XrefKind,
+}
+
+/// An index mapping addresses of global memory to the instructions referencing them.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct XrefIndex {
+    xrefs: BTreeMap<u64, BTreeSet<Xref>>,
+}
+
+impl XrefIndex {
+    /// Generate the cross reference index for the given project.
+    ///
+    /// If the Pointer Inference results are provided,
+    /// they are used to resolve target addresses of load and store instructions.
+    pub fn new(project: &Project, pointer_inference: Option<&PointerInference>) -> XrefIndex {
+        let mut index = XrefIndex::default();
+        let memory_image = &project.runtime_memory_image;
+        for sub in project.program.term.subs.values() {
+            for blk in sub.term.blocks.iter() {
+                for def in blk.term.defs.iter() {
+                    let (addresses, kind) = match &def.term {
+                        Def::Assign { value, .. } => (
+                            collect_global_constants(value, memory_image)
+                                .into_iter()
+                                .collect(),
+                            XrefKind::Reference,
+                        ),
+                        Def::Load { .. } => (
+                            get_accessed_global_addresses(def, project, pointer_inference),
+                            XrefKind::Read,
+                        ),
+                        Def::Store { value, .. } => {
+                            // The stored value may itself be a pointer to global memory.
+                            for constant in collect_global_constants(value, memory_image) {
+                                index.insert(constant, &def.tid, &sub.tid, XrefKind::Reference);
+                            }
+                            (
+                                get_accessed_global_addresses(def, project, pointer_inference),
+                                XrefKind::Write,
+                            )
+                        }
+                    };
+                    for address in addresses {
+                        index.insert(address, &def.tid, &sub.tid, kind);
+                    }
+                }
+            }
+        }
+        index
+    }
+
+    /// Add a cross reference to the index.
+    fn insert(&mut self, address: u64, tid: &Tid, sub: &Tid, kind: XrefKind) {
+        self.xrefs.entry(address).or_default().insert(Xref {
+            tid: tid.clone(),
+            sub: sub.clone(),
+            kind,
+        });
+    }
+
+    /// Get all cross references to the given address.
+    pub fn get_xrefs(&self, address: u64) -> impl Iterator<Item = &Xref> {
+        self.xrefs.get(&address).into_iter().flatten()
+    }
+
+    /// Get all cross references to addresses in the interval `[start, end)`
+    /// together with the referenced address.
+    pub fn get_xrefs_in_range(&self, start: u64, end: u64) -> impl Iterator<Item = (u64, &Xref)> {
+        self.xrefs
+            .range(start..end)
+            .flat_map(|(address, xrefs)| xrefs.iter().map(move |xref| (*address, xref)))
+    }
+
+    /// Iterate over all referenced addresses in ascending order.
+    pub fn referenced_addresses(&self) -> impl Iterator<Item = u64> + '_ {
+        self.xrefs.keys().copied()
+    }
+}
+
+/// Get the global addresses accessed by the given load or store instruction.
+///
+/// Constants pointing to global memory inside the address expression are always considered.
+/// If Pointer Inference results are provided, the exactly known global targets of the address are added.
+/// Returns an empty set for assignments.
+pub fn get_accessed_global_addresses(
+    def: &Term<Def>,
+    project: &Project,
+    pointer_inference: Option<&PointerInference>,
+) -> BTreeSet<u64> {
+    let (Def::Load { address, .. } | Def::Store { address, .. }) = &def.term else {
+        return BTreeSet::new();
+    };
+    let mut addresses: BTreeSet<u64> =
+        collect_global_constants(address, &project.runtime_memory_image)
+            .into_iter()
+            .collect();
+    if let Some(pi_address) = pointer_inference.and_then(|pi| pi.eval_address_at_def(&def.tid)) {
+        addresses.extend(get_global_addresses(&pi_address));
+    }
+    addresses
+}
+
+/// Collect all constants in the expression that point to global memory.
+fn collect_global_constants(
+    expression: &Expression,
+    memory_image: &RuntimeMemoryImage,
+) -> Vec<u64> {
+    use Expression::*;
+    match expression {
+        Const(constant) => match constant.try_to_u64() {
+            Ok(address)
+                if memory_image
+                    .is_interval_readable(address, address + 1)
+                    .is_ok() =>
+            {
+                vec![address]
+            }
+            _ => Vec::new(),
+        },
+        Var(_) | Unknown { .. } => Vec::new(),
+        BinOp { lhs, rhs, .. } => {
+            let mut constants = collect_global_constants(lhs, memory_image);
+            constants.append(&mut collect_global_constants(rhs, memory_image));
+            constants
+        }
+        UnOp { arg, .. } | Cast { arg, .. } | Subpiece { arg, .. } => {
+            collect_global_constants(arg, memory_image)
+        }
+    }
+}
+
+/// Get the exactly known global addresses that the given Pointer Inference value may point to.
+///
+/// Pointers into global memory are represented relative to the global memory object,
+/// whose abstract location is the global address zero.
+pub fn get_global_addresses(value: &Data) -> Vec<u64> {
+    let mut addresses = Vec::new();
+    if let Some(absolute) = value.get_absolute_value() {
+        if let Ok(address) = absolute.try_to_bitvec() {
+            addresses.extend(address.try_to_u64());
+        }
+    }
+    for (id, offset) in value.get_relative_values() {
+        if let AbstractLocation::GlobalAddress { address: 0, .. } = id.get_location() {
+            if let Ok(address) = offset.try_to_bitvec() {
+                addresses.extend(address.try_to_u64());
+            }
+        }
+    }
+    addresses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{def, expr};
+
+    #[test]
+    fn index_generation() {
+        let mut project = Project::mock_x64();
+        let mut blk = Blk::mock();
+        blk.term.defs = vec![
+            def!["assign: RDI:8 = 0x3002:8"],
+            def!["load: RAX:8 := Load from 0x2000:8"],
+            def!["store: Store at 0x2004:8 := RAX:8"],
+            def!["no_global: RAX:8 = 0x42:8"],
+        ];
+        let mut sub = Sub::mock("func");
+        sub.term.blocks.push(blk);
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+
+        let index = XrefIndex::new(&project, None);
+        assert_eq!(
+            index.referenced_addresses().collect::<Vec<_>>(),
+            vec![0x2000, 0x2004, 0x3002]
+        );
+        let xref = index.get_xrefs(0x3002).next().unwrap();
+        assert_eq!(xref.tid, Tid::new("assign"));
+        assert_eq!(xref.sub, Tid::new("func"));
+        assert_eq!(xref.kind, XrefKind::Reference);
+        let writes: Vec<_> = index
+            .get_xrefs_in_range(0x2000, 0x2008)
+            .filter(|(_, xref)| xref.kind == XrefKind::Write)
+            .collect();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].0, 0x2004);
+        assert_eq!(
+            collect_global_constants(&expr!("RAX:8 + 0x1000:8"), &project.runtime_memory_image),
+            vec![0x1000]
+        );
+    }
+}
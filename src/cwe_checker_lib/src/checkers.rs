@@ -6,26 +6,153 @@
 //! See there for detailed information about this check.
 
 /// Checkers that are supported for Linux kernel modules.
-pub const MODULES_LKM: [&str; 10] = [
-    "CWE134", "CWE190", "CWE215", "CWE252", "CWE416", "CWE457", "CWE467", "CWE476", "CWE676",
+///
+/// Checkers that are not compiled in because of disabled cargo features are left out.
+pub const MODULES_LKM: &[&str] = &[
+    #[cfg(feature = "taint")]
+    "CWE134",
+    "CWE190",
+    "CWE215",
+    "CWE242",
+    #[cfg(feature = "taint")]
+    "CWE252",
+    "CWE416",
+    "CWE457",
+    "CWE467",
+    #[cfg(feature = "taint")]
+    "CWE476",
+    "CWE676",
     "CWE789",
 ];
 
+pub mod cwe_1007;
+pub mod cwe_1177;
 pub mod cwe_119;
+#[cfg(feature = "taint")]
+pub mod cwe_1236;
+pub mod cwe_1269;
+pub mod cwe_1277;
+pub mod cwe_1287;
+#[cfg(feature = "taint")]
+pub mod cwe_1292;
+#[cfg(feature = "string-abstraction")]
+pub mod cwe_1325;
+pub mod cwe_1332;
+#[cfg(feature = "taint")]
 pub mod cwe_134;
+pub mod cwe_1419;
+#[cfg(feature = "taint")]
+pub mod cwe_15;
 pub mod cwe_190;
+pub mod cwe_204;
 pub mod cwe_215;
+pub mod cwe_242;
 pub mod cwe_243;
+#[cfg(feature = "taint")]
 pub mod cwe_252;
+pub mod cwe_266;
+pub mod cwe_277;
+pub mod cwe_324;
 pub mod cwe_332;
+#[cfg(feature = "taint")]
 pub mod cwe_337;
+pub mod cwe_347;
+pub mod cwe_354;
+#[cfg(feature = "taint")]
+pub mod cwe_362;
 pub mod cwe_367;
+#[cfg(feature = "taint")]
+pub mod cwe_369;
 pub mod cwe_416;
 pub mod cwe_426;
+#[cfg(feature = "taint")]
+pub mod cwe_434;
+pub mod cwe_440;
 pub mod cwe_467;
+#[cfg(feature = "taint")]
 pub mod cwe_476;
+#[cfg(feature = "string-abstraction")]
+pub mod cwe_522;
 pub mod cwe_560;
+pub mod cwe_613;
+pub mod cwe_649;
+#[cfg(feature = "taint")]
+pub mod cwe_656;
+pub mod cwe_666;
+#[cfg(feature = "taint")]
+pub mod cwe_668;
 pub mod cwe_676;
+pub mod cwe_693;
+pub mod cwe_733;
+#[cfg(all(feature = "string-abstraction", feature = "taint"))]
+pub mod cwe_759;
+pub mod cwe_770;
+#[cfg(all(feature = "string-abstraction", feature = "taint"))]
 pub mod cwe_78;
 pub mod cwe_782;
+pub mod cwe_786;
+pub mod cwe_787;
 pub mod cwe_789;
+#[cfg(feature = "taint")]
+pub mod cwe_804;
+pub mod cwe_805;
+pub mod cwe_925;
+#[cfg(feature = "taint")]
+pub mod cwe_941;
+
+/// Helper functions shared by the tests of the CWE checks.
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use crate::intermediate_representation::*;
+    use crate::prelude::*;
+    use crate::utils::log::CweWarning;
+    use crate::CweModuleFn;
+
+    /// Create a mock x64 project containing the given functions
+    /// and mock extern symbols with the given names.
+    pub fn mock_x64_project(extern_symbols: &[&str], subs: Vec<Term<Sub>>) -> Project {
+        let mut project = Project::mock_x64();
+        for name in extern_symbols {
+            let symbol = ExternSymbol::mock_x64(name);
+            project
+                .program
+                .term
+                .extern_symbols
+                .insert(symbol.tid.clone(), symbol);
+        }
+        for sub in subs {
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        project
+    }
+
+    /// Run the check on the project with the given configuration and return the generated warnings.
+    ///
+    /// The Pointer Inference analysis is computed beforehand without any allocation symbols.
+    pub fn run_check(
+        check: CweModuleFn,
+        project: &Project,
+        config: &serde_json::Value,
+    ) -> Vec<CweWarning> {
+        run_check_with_allocation_symbols(check, project, config, &[])
+    }
+
+    /// Run the check on the project with the given configuration and return the generated warnings.
+    ///
+    /// The Pointer Inference analysis is computed beforehand with the given allocation symbols.
+    pub fn run_check_with_allocation_symbols(
+        check: CweModuleFn,
+        project: &Project,
+        config: &serde_json::Value,
+        allocation_symbols: &[&str],
+    ) -> Vec<CweWarning> {
+        let analysis_results = AnalysisResults::mock_from_project(project);
+        let pointer_inference = analysis_results.compute_pointer_inference(
+            &serde_json::json!({ "allocation_symbols": allocation_symbols }),
+            false,
+        );
+        let analysis_results = analysis_results.with_pointer_inference(Some(&pointer_inference));
+        let (_, warnings) = check(&analysis_results, config);
+        warnings
+    }
+}
@@ -0,0 +1,413 @@
+//! This module implements a check for CWE-1007: Insufficient Visual Distinction of Homoglyphs Presented to User.
+//!
+//! Homoglyphs are characters that look identical or very similar to other characters,
+//! e.g. the Cyrillic `а` and the Latin `a`.
+//! If they appear inside strings that are shown to users in authentication or security dialogs,
+//! or inside constants that user input is compared against,
+//! users can be tricked into trusting a spoofed prompt or an attacker-chosen password can be hidden
+//! in plain sight.
+//! Such strings have been found in firmware images that were compromised in the supply chain.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1007.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! This is an informational check.
+//! It scans the readable memory segments of the binary for null-terminated UTF-8 strings
+//! that contain both ASCII letters and non-ASCII characters that are confusable with ASCII characters
+//! (or invisible characters like zero-width spaces).
+//! Strings consisting only of non-Latin characters (e.g. translated UI strings) are ignored.
+//!
+//! The remaining strings are classified by configurable string classifiers.
+//! A classifier matches if the string contains one of its keywords
+//! after all homoglyphs have been replaced by the ASCII characters that they resemble.
+//! Additionally, strings referenced by functions that call one of the configured comparison functions
+//! are classified as comparison constants.
+//! A warning is generated for each classified string.
+//!
+//! ## False Positives
+//!
+//! - Legitimate mixed-script strings (e.g. names or localized strings containing ASCII keywords) are flagged.
+//! - Strings referenced in a function calling a comparison function are not necessarily compared.
+//!
+//! ## False Negatives
+//!
+//! - Only a list of common homoglyphs (Cyrillic, Greek, fullwidth Latin and invisible characters) is known to the check.
+//! - Strings that are not null-terminated UTF-8 strings (e.g. UTF-16 strings) are not found.
+//! - Strings that are constructed at runtime are not found.
+//!
+//! ## Configuration
+//!
+//! The string classifiers, the comparison functions
+//! and the minimal length of strings are configurable in config.json.
+
+use crate::analysis::xref::XrefIndex;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1007",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// Homoglyphs of ASCII characters together with the ASCII characters they resemble.
+const HOMOGLYPHS: &[(char, char)] = &[
+    // Cyrillic
+    ('\u{0430}', 'a'),
+    ('\u{0435}', 'e'),
+    ('\u{043E}', 'o'),
+    ('\u{0440}', 'p'),
+    ('\u{0441}', 'c'),
+    ('\u{0443}', 'y'),
+    ('\u{0445}', 'x'),
+    ('\u{0455}', 's'),
+    ('\u{0456}', 'i'),
+    ('\u{0458}', 'j'),
+    ('\u{0501}', 'd'),
+    ('\u{0410}', 'A'),
+    ('\u{0412}', 'B'),
+    ('\u{0415}', 'E'),
+    ('\u{041A}', 'K'),
+    ('\u{041C}', 'M'),
+    ('\u{041D}', 'H'),
+    ('\u{041E}', 'O'),
+    ('\u{0420}', 'P'),
+    ('\u{0421}', 'C'),
+    ('\u{0422}', 'T'),
+    ('\u{0425}', 'X'),
+    ('\u{0405}', 'S'),
+    ('\u{0406}', 'I'),
+    ('\u{0408}', 'J'),
+    // Greek
+    ('\u{03B1}', 'a'),
+    ('\u{03BD}', 'v'),
+    ('\u{03BF}', 'o'),
+    ('\u{03C1}', 'p'),
+    ('\u{0391}', 'A'),
+    ('\u{0392}', 'B'),
+    ('\u{0395}', 'E'),
+    ('\u{0396}', 'Z'),
+    ('\u{0397}', 'H'),
+    ('\u{0399}', 'I'),
+    ('\u{039A}', 'K'),
+    ('\u{039C}', 'M'),
+    ('\u{039D}', 'N'),
+    ('\u{039F}', 'O'),
+    ('\u{03A1}', 'P'),
+    ('\u{03A4}', 'T'),
+    ('\u{03A5}', 'Y'),
+    ('\u{03A7}', 'X'),
+    // Latin
+    ('\u{0131}', 'i'),
+    ('\u{0261}', 'g'),
+];
+
+/// Invisible characters that can be used to make two strings look identical.
+const INVISIBLE_CHARACTERS: &[char] = &['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+
+/// A string classifier.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Classifier {
+    /// The name of the string class.
+    name: String,
+    /// A string belongs to the class if it contains one of these keywords (case-insensitive).
+    keywords: Vec<String>,
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Classifiers for security-relevant strings.
+    classifiers: Vec<Classifier>,
+    /// Functions comparing strings or memory regions.
+    comparison_symbols: Vec<String>,
+    /// The minimal number of characters of a string.
+    min_length: usize,
+}
+
+/// A string in the memory image of the binary.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct FoundString {
+    /// The address of the first byte of the string.
+    address: u64,
+    /// The content of the string.
+    content: String,
+}
+
+/// Get the ASCII character that the given character resembles,
+/// if the character is a known non-ASCII homoglyph.
+fn get_ascii_lookalike(character: char) -> Option<char> {
+    let fullwidth_range = '\u{FF01}'..='\u{FF5E}';
+    if fullwidth_range.contains(&character) {
+        return char::from_u32(character as u32 - 0xFEE0);
+    }
+    HOMOGLYPHS
+        .iter()
+        .find(|(homoglyph, _)| *homoglyph == character)
+        .map(|(_, ascii)| *ascii)
+}
+
+/// Return the homoglyphs and invisible characters contained in the string
+/// if it also contains ASCII letters.
+fn find_deceptive_characters(string: &str) -> BTreeSet<char> {
+    if !string.chars().any(|c| c.is_ascii_alphabetic()) {
+        return BTreeSet::new();
+    }
+    string
+        .chars()
+        .filter(|c| get_ascii_lookalike(*c).is_some() || INVISIBLE_CHARACTERS.contains(c))
+        .collect()
+}
+
+/// Replace all homoglyphs in the string with the ASCII characters they resemble
+/// and remove all invisible characters.
+fn get_skeleton(string: &str) -> String {
+    string
+        .chars()
+        .filter(|c| !INVISIBLE_CHARACTERS.contains(c))
+        .map(|c| get_ascii_lookalike(c).unwrap_or(c))
+        .collect()
+}
+
+/// Find all null-terminated UTF-8 strings with non-ASCII characters in the readable memory segments.
+fn find_non_ascii_strings(
+    memory_image: &RuntimeMemoryImage,
+    min_length: usize,
+) -> Vec<FoundString> {
+    let mut strings = Vec::new();
+    for (_, segment) in memory_image
+        .iter_segments()
+        .filter(|(_, segment)| segment.read_flag)
+    {
+        let mut offset = 0;
+        for bytes in segment.bytes.split(|byte| *byte == 0) {
+            let start = offset;
+            offset += bytes.len() + 1;
+            if bytes.is_ascii() {
+                continue;
+            }
+            let Ok(content) = std::str::from_utf8(bytes) else {
+                continue;
+            };
+            if content.chars().count() < min_length
+                || content
+                    .chars()
+                    .any(|c| c.is_control() && !c.is_ascii_whitespace())
+            {
+                continue;
+            }
+            strings.push(FoundString {
+                address: segment.base_address + start as u64,
+                content: content.to_string(),
+            });
+        }
+    }
+    strings
+}
+
+/// Get the TIDs of all functions that call one of the comparison functions.
+fn get_subs_calling_comparisons(project: &Project, config: &Config) -> BTreeSet<Tid> {
+    let comparison_symbols = get_symbol_map(project, &config.comparison_symbols);
+    project
+        .program
+        .term
+        .subs
+        .values()
+        .filter(|sub| {
+            sub.term.blocks.iter().any(|blk| {
+                blk.term.jmps.iter().any(|jmp| {
+                    matches!(&jmp.term, Jmp::Call { target, .. } if comparison_symbols.contains_key(target))
+                })
+            })
+        })
+        .map(|sub| sub.tid.clone())
+        .collect()
+}
+
+/// Get the names of the classes of the given string.
+fn classify(
+    string: &FoundString,
+    config: &Config,
+    xrefs: &XrefIndex,
+    comparing_subs: &BTreeSet<Tid>,
+) -> BTreeSet<String> {
+    let skeleton = get_skeleton(&string.content).to_lowercase();
+    let mut classes: BTreeSet<String> = config
+        .classifiers
+        .iter()
+        .filter(|classifier| {
+            classifier
+                .keywords
+                .iter()
+                .any(|keyword| skeleton.contains(&keyword.to_lowercase()))
+        })
+        .map(|classifier| classifier.name.clone())
+        .collect();
+    if xrefs
+        .get_xrefs(string.address)
+        .any(|xref| comparing_subs.contains(&xref.sub))
+    {
+        classes.insert("comparison constant".to_string());
+    }
+    classes
+}
+
+/// Run the check.
+///
+/// Search for strings containing homoglyphs and generate a warning for each such string
+/// that is classified as security-relevant.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1007.");
+    let xrefs = XrefIndex::new(project, analysis_results.pointer_inference);
+    let comparing_subs = get_subs_calling_comparisons(project, &config);
+    let mut cwe_warnings = Vec::new();
+    // The examined sites are the strings containing deceptive characters.
+    let mut site_counts = SiteCounts::default();
+    for string in find_non_ascii_strings(&project.runtime_memory_image, config.min_length) {
+        let deceptive_characters = find_deceptive_characters(&string.content);
+        if deceptive_characters.is_empty() {
+            continue;
+        }
+        let classes = classify(&string, &config, &xrefs, &comparing_subs);
+        if classes.is_empty() {
+            site_counts.add_safe();
+            continue;
+        }
+        site_counts.add_flagged();
+        cwe_warnings.push(generate_warning(
+            &string,
+            &deceptive_characters,
+            &classes,
+            &xrefs,
+        ));
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Generate the warning for a string containing homoglyphs.
+fn generate_warning(
+    string: &FoundString,
+    deceptive_characters: &BTreeSet<char>,
+    classes: &BTreeSet<String>,
+    xrefs: &XrefIndex,
+) -> CweWarning {
+    let address = format!("{:x}", string.address);
+    let referencing_tids: BTreeMap<String, String> = xrefs
+        .get_xrefs(string.address)
+        .map(|xref| (format!("{}", xref.tid), xref.tid.address.clone()))
+        .collect();
+    let mut addresses = vec![address.clone()];
+    addresses.extend(referencing_tids.values().cloned());
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Homoglyphs in Security-Relevant String) String \"{}\" at {address} contains characters resembling \"{}\"",
+            string.content.escape_debug(),
+            get_skeleton(&string.content).escape_debug()
+        ),
+    )
+    .addresses(addresses)
+    .tids(referencing_tids.into_keys().collect())
+    .other(vec![
+        std::iter::once("classes".to_string())
+            .chain(classes.iter().cloned())
+            .collect(),
+        std::iter::once("characters".to_string())
+            .chain(
+                deceptive_characters
+                    .iter()
+                    .map(|c| format!("U+{:04X}", *c as u32)),
+            )
+            .collect(),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::binary::MemorySegment;
+
+    fn mock_config() -> Config {
+        Config {
+            classifiers: vec![Classifier {
+                name: "authentication".to_string(),
+                keywords: vec!["password".to_string(), "login".to_string()],
+            }],
+            comparison_symbols: vec!["strcmp".to_string()],
+            min_length: 4,
+        }
+    }
+
+    #[test]
+    fn homoglyph_detection() {
+        // Cyrillic `а` inside an otherwise Latin string.
+        let spoofed = "Enter p\u{0430}ssword:";
+        assert_eq!(
+            find_deceptive_characters(spoofed),
+            BTreeSet::from(['\u{0430}'])
+        );
+        assert_eq!(get_skeleton(spoofed), "Enter password:");
+        // Zero-width space and fullwidth letters.
+        assert_eq!(get_skeleton("lo\u{200B}g\u{FF49}n"), "login");
+        // Purely Cyrillic strings are not suspicious.
+        assert!(
+            find_deceptive_characters("\u{043F}\u{0430}\u{0440}\u{043E}\u{043B}\u{044C}")
+                .is_empty()
+        );
+        // Non-ASCII characters without ASCII lookalike are not suspicious.
+        assert!(find_deceptive_characters("Passwort f\u{00FC}r Benutzer").is_empty());
+    }
+
+    #[test]
+    fn string_classification() {
+        let mut project = Project::mock_x64();
+        let mut bytes = b"\x00\x00Welcome\x00".to_vec();
+        let spoofed_address = 0x2000 + bytes.len() as u64;
+        bytes.extend("p\u{0430}ssword\x00".as_bytes());
+        let other_address = 0x2000 + bytes.len() as u64;
+        bytes.extend("\u{0455}ecret\x00".as_bytes());
+        project.runtime_memory_image.memory_segments = vec![MemorySegment {
+            bytes,
+            base_address: 0x2000,
+            read_flag: true,
+            write_flag: false,
+            execute_flag: false,
+        }];
+        let config = mock_config();
+        let strings = find_non_ascii_strings(&project.runtime_memory_image, config.min_length);
+        assert_eq!(
+            strings,
+            vec![
+                FoundString {
+                    address: spoofed_address,
+                    content: "p\u{0430}ssword".to_string(),
+                },
+                FoundString {
+                    address: other_address,
+                    content: "\u{0455}ecret".to_string(),
+                }
+            ]
+        );
+        let xrefs = XrefIndex::default();
+        assert_eq!(
+            classify(&strings[0], &config, &xrefs, &BTreeSet::new()),
+            BTreeSet::from(["authentication".to_string()])
+        );
+        assert!(classify(&strings[1], &config, &xrefs, &BTreeSet::new()).is_empty());
+    }
+}
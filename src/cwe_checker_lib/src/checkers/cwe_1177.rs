@@ -0,0 +1,374 @@
+//! This module implements a check for CWE-1177: Use of Prohibited Code.
+//!
+//! Coding standards for safety-critical software (e.g. MISRA C or DO-178C-based guidelines)
+//! prohibit programming primitives whose behavior is hard to bound or verify,
+//! like dynamic memory allocation, recursion, non-local jumps with `setjmp`/`longjmp`
+//! or variadic functions.
+//! The check reports uses of such primitives in safety-critical code.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1177.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! The check uses a table of rules. Each rule consists of a prohibited primitive
+//! and a short description presented to the user. The supported primitives are
+//! - calls to the functions in a list of symbols, e.g. `malloc` or `longjmp`.
+//!   Both extern symbols and functions of the binary (e.g. statically linked library functions) are matched by name,
+//! - recursive calls, i.e. calls to functions in the same strongly connected component of the call graph as the caller,
+//! - calls to variadic extern functions.
+//!
+//! If a list of safety-critical functions is configured, only these functions
+//! and all functions reachable from them in the call graph are checked.
+//! Otherwise the whole binary is checked.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The rule table and the names of the safety-critical functions are configurable in config.json.
+//!
+//! ## False Positives
+//!
+//! - Calls in safety-critical functions may only happen on paths that are never executed in safety-critical operation,
+//!   e.g. during the initialization of the system.
+//! - Recursion with a bounded depth may be allowed by some coding standards.
+//!
+//! ## False Negatives
+//!
+//! - Indirect calls are not represented in the call graph,
+//!   so recursion through function pointers and functions only reachable through indirect calls are missed.
+//! - Internal functions are not marked as variadic, so only calls to variadic extern functions are reported.
+
+use crate::analysis::callgraph::{get_program_callgraph, CallGraph};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::CweModule;
+use petgraph::visit::{Dfs, EdgeRef};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1177",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The names of the safety-critical functions.
+    /// If empty, the whole binary is checked.
+    safety_critical_functions: Vec<String>,
+    /// The table of prohibited primitives.
+    rules: Vec<Rule>,
+}
+
+/// An entry of the table of prohibited primitives.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Rule {
+    /// The prohibited primitive.
+    primitive: Primitive,
+    /// A short explanation why the primitive is prohibited, presented to the user.
+    description: String,
+}
+
+/// The primitives that rules can prohibit.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Primitive {
+    /// Calls to functions with the given names.
+    Symbols(Vec<String>),
+    /// Calls to functions in the same strongly connected component of the call graph as the caller.
+    Recursion,
+    /// Calls to extern functions with a variable number of arguments.
+    VariadicCall,
+}
+
+/// Run the check.
+///
+/// First the functions to check are computed from the safety-critical functions.
+/// Then each call in these functions is checked against the rules.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1177.");
+
+    let callgraph = get_program_callgraph(&project.program);
+    let scope = compute_scope(&callgraph, project, &config.safety_critical_functions);
+    let mut site_counts = SiteCounts::default();
+    let cwe_warnings = check_rules(project, &callgraph, &config.rules, &scope, &mut site_counts);
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Compute the functions to check.
+///
+/// Each function is mapped to one of the safety-critical functions it is reachable from,
+/// or to `None` if no safety-critical functions are configured and the whole binary is checked.
+fn compute_scope(
+    callgraph: &CallGraph,
+    project: &Project,
+    safety_critical_functions: &[String],
+) -> BTreeMap<Tid, Option<Tid>> {
+    if safety_critical_functions.is_empty() {
+        return project
+            .program
+            .term
+            .subs
+            .keys()
+            .map(|tid| (tid.clone(), None))
+            .collect();
+    }
+    let mut scope = BTreeMap::new();
+    let is_safety_critical =
+        |tid: &Tid| safety_critical_functions.contains(&project.program.term.subs[tid].term.name);
+    for start in callgraph
+        .node_indices()
+        .filter(|node| is_safety_critical(&callgraph[*node]))
+    {
+        let mut dfs = Dfs::new(callgraph, start);
+        while let Some(node) = dfs.next(callgraph) {
+            scope
+                .entry(callgraph[node].clone())
+                .or_insert_with(|| Some(callgraph[start].clone()));
+        }
+    }
+    scope
+}
+
+/// Check all calls in the functions of the scope against the rules.
+fn check_rules(
+    project: &Project,
+    callgraph: &CallGraph,
+    rules: &[Rule],
+    scope: &BTreeMap<Tid, Option<Tid>>,
+    site_counts: &mut SiteCounts,
+) -> Vec<CweWarning> {
+    let program = &project.program.term;
+    let mut component_of: HashMap<&Tid, usize> = HashMap::new();
+    for (index, component) in petgraph::algo::kosaraju_scc(callgraph).iter().enumerate() {
+        for node in component {
+            component_of.insert(&callgraph[*node], index);
+        }
+    }
+    let recursive_calls: HashSet<&Tid> = callgraph
+        .edge_references()
+        .filter(|edge| {
+            component_of[&callgraph[edge.source()]] == component_of[&callgraph[edge.target()]]
+        })
+        .map(|edge| &edge.weight().tid)
+        .collect();
+
+    let mut cwe_warnings = Vec::new();
+    for (sub_tid, root) in scope {
+        let sub = &program.subs[sub_tid];
+        let root = root.as_ref().map(|root| &program.subs[root]);
+        for blk in sub.term.blocks.iter() {
+            for jmp in blk.term.jmps.iter() {
+                let Jmp::Call { target, .. } = &jmp.term else {
+                    continue;
+                };
+                let (callee_name, is_variadic) =
+                    match (program.extern_symbols.get(target), program.subs.get(target)) {
+                        (Some(symbol), _) => (&symbol.name, symbol.has_var_args),
+                        (None, Some(callee)) => (&callee.term.name, false),
+                        (None, None) => continue,
+                    };
+                let mut is_flagged = false;
+                for rule in rules {
+                    let is_prohibited = match &rule.primitive {
+                        Primitive::Symbols(symbols) => symbols.contains(callee_name),
+                        Primitive::Recursion => recursive_calls.contains(&jmp.tid),
+                        Primitive::VariadicCall => is_variadic,
+                    };
+                    if is_prohibited {
+                        is_flagged = true;
+                        cwe_warnings.push(generate_cwe_warning(
+                            rule,
+                            sub,
+                            &jmp.tid,
+                            callee_name,
+                            root,
+                        ));
+                    }
+                }
+                if is_flagged {
+                    site_counts.add_flagged();
+                } else {
+                    site_counts.add_safe();
+                }
+            }
+        }
+    }
+    cwe_warnings
+}
+
+/// Generate the CWE warning for a prohibited call.
+/// The safety-critical function is given if the call is reachable from it.
+fn generate_cwe_warning(
+    rule: &Rule,
+    sub: &Term<Sub>,
+    call_tid: &Tid,
+    callee_name: &str,
+    root: Option<&Term<Sub>>,
+) -> CweWarning {
+    let (context, mut tids, mut symbols) = match root {
+        Some(root) => (
+            format!(
+                " in safety-critical code (reachable from {})",
+                root.term.name
+            ),
+            vec![format!("{call_tid}"), format!("{}", root.tid)],
+            vec![
+                callee_name.to_string(),
+                sub.term.name.clone(),
+                root.term.name.clone(),
+            ],
+        ),
+        None => (
+            String::new(),
+            vec![format!("{call_tid}")],
+            vec![callee_name.to_string(), sub.term.name.clone()],
+        ),
+    };
+    tids.dedup();
+    symbols.dedup();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Use of Prohibited Code) Call to {callee_name} at {} ({}){context}: {}",
+            call_tid.address, sub.term.name, rule.description
+        ),
+    )
+    .tids(tids)
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::mock_x64_project;
+
+    fn mock_sub(name: &str, calls: &[&str]) -> Term<Sub> {
+        let mut sub = Sub::mock(name);
+        let mut blk = Blk::mock_with_tid(&format!("{name}_blk"));
+        for (i, target) in calls.iter().enumerate() {
+            blk.term
+                .jmps
+                .push(Jmp::call(&format!("{name}_call_{i}"), target, None));
+        }
+        sub.term.blocks.push(blk);
+        sub
+    }
+
+    fn mock_project() -> Project {
+        // `control_loop` is the safety-critical function,
+        // `parse` and `eval` are mutually recursive.
+        let subs = vec![
+            mock_sub("main", &["malloc", "control_loop"]),
+            mock_sub("control_loop", &["parse", "printf"]),
+            mock_sub("parse", &["eval", "malloc"]),
+            mock_sub("eval", &["parse"]),
+            mock_sub("log_error", &["log_error", "printf"]),
+        ];
+        let mut project = mock_x64_project(&["malloc", "printf"], subs);
+        project
+            .program
+            .term
+            .extern_symbols
+            .get_mut(&Tid::new("printf"))
+            .unwrap()
+            .has_var_args = true;
+        project
+    }
+
+    fn mock_rules() -> Vec<Rule> {
+        vec![
+            Rule {
+                primitive: Primitive::Symbols(vec!["malloc".to_string()]),
+                description: "Dynamic memory allocation".to_string(),
+            },
+            Rule {
+                primitive: Primitive::Recursion,
+                description: "Recursion".to_string(),
+            },
+            Rule {
+                primitive: Primitive::VariadicCall,
+                description: "Variadic function".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn safety_critical_functions() {
+        let project = mock_project();
+        let callgraph = get_program_callgraph(&project.program);
+        let scope = compute_scope(&callgraph, &project, &["control_loop".to_string()]);
+        assert_eq!(scope.len(), 3);
+        assert!(!scope.contains_key(&Tid::new("main")));
+        assert_eq!(scope[&Tid::new("eval")], Some(Tid::new("control_loop")));
+
+        let mut site_counts = SiteCounts::default();
+        let warnings = check_rules(
+            &project,
+            &callgraph,
+            &mock_rules(),
+            &scope,
+            &mut site_counts,
+        );
+        let mut tids: Vec<&str> = warnings
+            .iter()
+            .map(|warning| warning.tids[0].as_str())
+            .collect();
+        tids.sort();
+        assert_eq!(
+            tids,
+            vec![
+                "control_loop_call_1",
+                "eval_call_0",
+                "parse_call_0",
+                "parse_call_1"
+            ]
+        );
+        let malloc_warning = warnings
+            .iter()
+            .find(|warning| warning.tids[0] == "parse_call_1")
+            .unwrap();
+        assert_eq!(
+            malloc_warning.symbols,
+            vec!["malloc", "parse", "control_loop"]
+        );
+        assert_eq!(site_counts.examined - site_counts.safe, 4);
+    }
+
+    #[test]
+    fn whole_binary() {
+        let project = mock_project();
+        let callgraph = get_program_callgraph(&project.program);
+        let scope = compute_scope(&callgraph, &project, &[]);
+        assert_eq!(scope.len(), 5);
+
+        let rules = &mock_rules()[1..];
+        let warnings = check_rules(
+            &project,
+            &callgraph,
+            rules,
+            &scope,
+            &mut SiteCounts::default(),
+        );
+        let log_error_warnings: Vec<&CweWarning> = warnings
+            .iter()
+            .filter(|warning| warning.tids[0].starts_with("log_error"))
+            .collect();
+        assert_eq!(log_error_warnings.len(), 2);
+        assert_eq!(log_error_warnings[0].symbols, vec!["log_error"]);
+        assert_eq!(log_error_warnings[0].tids, vec!["log_error_call_0"]);
+        assert_eq!(warnings.len(), 5);
+    }
+}
@@ -213,6 +213,9 @@ fn compute_callee_to_call_sites_map(project: &Project) -> HashMap<Tid, HashSet<T
 
 /// Compute a map mapping the TIDs of malloc-like calls (e.g. malloc, realloc, calloc)
 /// to the size value of the allocated object according to the pointer inference analysis.
+///
+/// The size parameters of the allocation functions are configured in the pointer inference configuration,
+/// see [`AllocatorFunction`](crate::analysis::pointer_inference::AllocatorFunction).
 fn compute_size_values_of_malloc_calls(analysis_results: &AnalysisResults) -> HashMap<Tid, Data> {
     let project = analysis_results.project;
     let pointer_inference = analysis_results.pointer_inference.unwrap();
@@ -222,11 +225,9 @@ fn compute_size_values_of_malloc_calls(analysis_results: &AnalysisResults) -> Ha
             for jmp in &blk.term.jmps {
                 if let Jmp::Call { target, .. } = &jmp.term {
                     if let Some(symbol) = project.program.term.extern_symbols.get(target) {
-                        if let Some(size_value) = compute_size_value_of_malloc_like_call(
-                            &jmp.tid,
-                            symbol,
-                            pointer_inference,
-                        ) {
+                        if let Some(size_value) =
+                            pointer_inference.eval_allocation_size(&jmp.tid, symbol)
+                        {
                             malloc_size_map.insert(jmp.tid.clone(), size_value);
                         }
                     }
@@ -237,70 +238,6 @@ fn compute_size_values_of_malloc_calls(analysis_results: &AnalysisResults) -> Ha
     malloc_size_map
 }
 
-/// Compute the size value of a call to a malloc-like function according to the
-/// pointer inference and return it.
-///
-/// Returns `None` if the called symbol is not an allocating function, the size
-/// computation for the symbol is not yet implemented, or Ghidra did not supply
-/// storage locations for the arguments.
-///
-/// Currently this function computes the size values for the symbols `malloc`,
-/// `realloc`, `reallocarray`, `calloc` and the C++-`new` operator.
-fn compute_size_value_of_malloc_like_call(
-    jmp_tid: &Tid,
-    called_symbol: &ExternSymbol,
-    pointer_inference: &PointerInference,
-) -> Option<Data> {
-    match called_symbol.name.as_str() {
-        "malloc" | "operator.new" | "operator.new[]" => {
-            let size_param = &called_symbol.parameters[0];
-            match pointer_inference.eval_parameter_arg_at_call(jmp_tid, size_param) {
-                Some(size_value) => Some(size_value),
-                None => Some(Data::new_top(size_param.bytesize())),
-            }
-        }
-        "realloc" => {
-            let size_param = &called_symbol.parameters[1];
-            match pointer_inference.eval_parameter_arg_at_call(jmp_tid, size_param) {
-                Some(size_value) => Some(size_value),
-                None => Some(Data::new_top(size_param.bytesize())),
-            }
-        }
-        "reallocarray" => {
-            match called_symbol.parameters.as_slice() {
-                [_, count_param, size_param] => {
-                    match (
-                        pointer_inference.eval_parameter_arg_at_call(jmp_tid, count_param),
-                        pointer_inference.eval_parameter_arg_at_call(jmp_tid, size_param),
-                    ) {
-                        (Some(count_value), Some(size_value)) => {
-                            Some(count_value.bin_op(BinOpType::IntMult, &size_value))
-                        }
-                        _ => Some(Data::new_top(size_param.bytesize())),
-                    }
-                }
-                // Ghidra may not be supplying parameter information for
-                // reallocarray.
-                _ => None,
-            }
-        }
-        "calloc" => {
-            let count_param = &called_symbol.parameters[0];
-            let size_param = &called_symbol.parameters[1];
-            match (
-                pointer_inference.eval_parameter_arg_at_call(jmp_tid, count_param),
-                pointer_inference.eval_parameter_arg_at_call(jmp_tid, size_param),
-            ) {
-                (Some(count_value), Some(size_value)) => {
-                    Some(count_value.bin_op(BinOpType::IntMult, &size_value))
-                }
-                _ => Some(Data::new_top(size_param.bytesize())),
-            }
-        }
-        _ => None,
-    }
-}
-
 /// Compute a map that maps the TIDs of call instructions to the TID of the caller function.
 fn compute_call_to_caller_map(project: &Project) -> HashMap<Tid, Tid> {
     let mut call_to_caller_map = HashMap::new();
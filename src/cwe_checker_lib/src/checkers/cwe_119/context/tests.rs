@@ -1,6 +1,4 @@
 use super::*;
-use crate::{bitvec, variable};
-use std::collections::BTreeSet;
 
 impl<'a> Context<'a> {
     /// Create a mock context.
@@ -24,33 +22,6 @@ impl<'a> Context<'a> {
     }
 }
 
-#[test]
-fn test_compute_size_value_of_malloc_like_call() {
-    use crate::analysis::pointer_inference::State as PiState;
-    let project = Project::mock_x64();
-    let mut pi_results = PointerInference::mock(&project);
-    let mut malloc_state = PiState::new(&variable!("RSP:8"), Tid::new("func"), BTreeSet::new());
-    malloc_state.set_register(&variable!("RDI:8"), bitvec!("3:8").into());
-    *pi_results.get_mut_states_at_tids() = HashMap::from([(Tid::new("malloc_call"), malloc_state)]);
-    let malloc_symbol = ExternSymbol::mock_x64("malloc");
-
-    assert_eq!(
-        compute_size_value_of_malloc_like_call(
-            &Tid::new("malloc_call"),
-            &malloc_symbol,
-            &pi_results
-        )
-        .unwrap(),
-        bitvec!("3:8").into()
-    );
-    assert!(compute_size_value_of_malloc_like_call(
-        &Tid::new("other"),
-        &ExternSymbol::mock_x64("other"),
-        &pi_results
-    )
-    .is_none());
-}
-
 #[test]
 fn test_malloc_zero_case() {
     let mut context = Context::mock_x64();
@@ -2,6 +2,7 @@ use super::super::State;
 use super::*;
 use crate::analysis::graph::Graph;
 use crate::checkers::cwe_119::stubs::ExternCallHandler;
+use crate::intermediate_representation::simd_normalization::is_vector_size;
 
 impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Context<'a> {
     type Value = State;
@@ -27,7 +28,15 @@ impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Cont
                     Some(address) => address,
                     None => return None, // There seems to be no pointer inference state here.
                 };
-                let warnings = state.check_address_access(&address, var.size, self);
+                // Vectorized string functions read whole aligned vectors that may extend past the end of the string.
+                // Thus we only check that vector-sized reads start inside the memory object.
+                // Vector-sized copies are checked through the pointer-sized reads generated by the IR normalization.
+                let access_size = if is_vector_size(var.size) {
+                    ByteSize::new(1)
+                } else {
+                    var.size
+                };
+                let warnings = state.check_address_access(&address, access_size, self);
                 if !warnings.is_empty() {
                     let mut cwe_warning = CweWarning::new(
                         "CWE125",
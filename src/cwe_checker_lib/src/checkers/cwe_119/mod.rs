@@ -34,6 +34,7 @@
 //! - The Pointer Inference analysis cannot distinguish different objects located on the same stack frame.
 //! Thus buffer overflows on the stack can only be detected if they may reach outside of the whole stack frame.
 //! This leads to false negatives, especially for buffer overflows caused by off-by-one bugs.
+//! Writes beyond the end of individual stack buffers are detected by the check for CWE-787.
 //! - For parameters of extern calls where a corresponding call stub is defined
 //! the analysis approximates size parameters as small as possible, which can lead to false negatives.
 //! Currently, analysis imprecision would lead to too many false positives if we would approximate by larger possible size parameters.
@@ -61,6 +62,7 @@
 use crate::analysis::pointer_inference::Data;
 use crate::prelude::*;
 use crate::utils::log::{CweWarning, LogMessage, LogThread};
+use crate::utils::metrics::count_function_sites;
 use crate::CweModule;
 
 mod context;
@@ -91,6 +93,7 @@ pub fn check_cwe(
 
     let mut fixpoint_computation =
         crate::analysis::forward_interprocedural_fixpoint::create_computation(context, None);
+    fixpoint_computation.set_cancellation_token(analysis_results.cancellation_token);
 
     for (sub_tid, entry_node_of_sub) in
         crate::analysis::graph::get_entry_nodes_of_subs(analysis_results.control_flow_graph)
@@ -107,5 +110,13 @@ pub fn check_cwe(
     fixpoint_computation.compute_with_max_steps(100);
 
     let (logs, cwe_warnings) = log_thread.collect();
+    // Functions without a function signature are not analyzed.
+    let site_counts = count_function_sites(analysis_results.project, &cwe_warnings, |sub_tid| {
+        analysis_results
+            .function_signatures
+            .unwrap()
+            .contains_key(sub_tid)
+    });
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
     (logs, cwe_warnings)
 }
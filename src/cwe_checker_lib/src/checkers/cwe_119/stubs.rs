@@ -68,6 +68,9 @@ impl<'a, 'b> ExternCallHandler<'a, 'b> {
     }
 
     /// Check whether the buffer parameter is at least as large as the access size given by the size parameter.
+    ///
+    /// For fortified variants of functions the access to the destination buffer
+    /// is bounded by the object size parameter, since larger accesses abort the program.
     fn check_buffer_size(
         &mut self,
         buffer_param_index: usize,
@@ -77,9 +80,30 @@ impl<'a, 'b> ExternCallHandler<'a, 'b> {
             Some(size) => size,
             None => ByteSize::new(1),
         };
+        let size = match self.compute_fortified_object_size(buffer_param_index) {
+            Some(object_size) => std::cmp::min(size, object_size),
+            None => size,
+        };
         self.check_buffer_with_concrete_size(buffer_param_index, size)
     }
 
+    /// If the called function is a fortified variant checking the size of the given buffer parameter,
+    /// compute the object size that the buffer is checked against.
+    /// Returns `None` if the size is unknown, in which case the fortified variant does not check the access.
+    fn compute_fortified_object_size(&self, buffer_param_index: usize) -> Option<ByteSize> {
+        let fortified = self
+            .context
+            .project
+            .program
+            .term
+            .fortified_symbols
+            .get(&self.fn_symbol.tid)?;
+        if fortified.destination_index != buffer_param_index {
+            return None;
+        }
+        self.compute_buffer_size_from_param(fortified.object_size_index)
+    }
+
     /// Check whether the buffer parameter is at least `size * count` bytes large,
     /// where `size` and `count` are given by the corresponding parameters.
     fn check_buffer_size_and_count(
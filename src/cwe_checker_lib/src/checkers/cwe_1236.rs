@@ -0,0 +1,353 @@
+//! This module implements a check for CWE-1236: Improper Neutralization of Formula Elements in a CSV File.
+//!
+//! Spreadsheet programs interpret cells starting with `=`, `+`, `-` or `@` as formulas.
+//! If user-controlled data is written into a CSV file without escaping these characters,
+//! opening the file in a spreadsheet program may execute attacker-controlled formulas.
+//! Embedded devices often offer such CSV exports for their logs or client lists.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1236.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! For each call to a configured file output function (e.g. `fprintf`)
+//! we read the format string parameter with the help of the Pointer Inference.
+//! The format string is considered CSV-like
+//! if the text between two consecutive conversion specifiers consists of a single separator character
+//! (e.g. `,` or `;`), optionally surrounded by whitespace and double quotes, as in `"%s;%s\n"`.
+//! The string arguments (`%s`) of CSV-like calls are the sinks of a taint analysis
+//! (see [`crate::analysis::taint::flow`]) starting at the configured input sources (e.g. `recv` or `getenv`).
+//! A CWE warning is generated for each CSV-like call reached by the taint.
+//!
+//! Fields that are prefixed with a single quote in the format string (e.g. `'%s`)
+//! are treated as escaped, since spreadsheet programs do not interpret them as formulas.
+//! Calls to the configured sanitizers remove the taint from the data pointed to by their parameters.
+//!
+//! ### Symbols configurable in config.json
+//!
+//! - `format_symbols`: File output functions, mapped to the index of their format string parameter.
+//! - `input_sources`: Functions returning user-controlled input.
+//!   For each function it is configured whether the input is returned (`ReturnValue`)
+//!   or written to the memory pointed to by the parameter with the given index (`{"ParameterTarget": index}`).
+//! - `sanitizers`: Functions escaping the formula characters of the data pointed to by their parameters.
+//! - `separators`: The characters separating the fields of a CSV file.
+//!
+//! ## False Positives
+//!
+//! - The input is escaped by code that is not a call to a configured sanitizer,
+//!   e.g. an inlined check of the first character.
+//! - The sink parameters are configured per output function and not per call.
+//!   Thus a call may be reported if a tainted value is passed as a non-string argument
+//!   and another CSV-like call to the same function has a string argument at the same position.
+//!
+//! ## False Negatives
+//!
+//! - Format strings that the Pointer Inference cannot locate in global memory are not checked.
+//! - CSV lines that are assembled in a buffer (e.g. by `sprintf` or `strcat`) and written with `fputs` or `write`
+//!   are not recognized.
+//! - Floating point arguments and `*` width or precision arguments before a string argument are not accounted for
+//!   when computing the parameter index of the string argument.
+//! - The taint analysis is limited to a fixed number of steps per source call,
+//!   so input passing through long call chains may not reach the output function.
+
+use crate::abstract_domain::TryToBitvec;
+use crate::analysis::taint::flow::{find_taint_flows, CallSite, TaintFlowConfig};
+use crate::analysis::taint::summary::TaintFlowTarget;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::arguments::parse_format_string_destination_and_return_content;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1236",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// File output functions, mapped to the index of their format string parameter.
+    format_symbols: BTreeMap<String, usize>,
+    /// Functions returning user-controlled input, together with the location that the input is returned in.
+    input_sources: BTreeMap<String, TaintFlowTarget>,
+    /// Functions escaping the formula characters of the data pointed to by their parameters.
+    #[serde(default)]
+    sanitizers: BTreeSet<String>,
+    /// The characters separating the fields of a CSV file.
+    separators: Vec<char>,
+}
+
+/// A call to a file output function with a CSV-like format string.
+struct CsvCall {
+    /// The called output function.
+    symbol: String,
+    /// The format string of the call.
+    format_string: String,
+    /// The parameter indices of the unescaped string fields of the format string.
+    field_indices: Vec<usize>,
+}
+
+/// Search for calls to file output functions with CSV-like format strings
+/// whose unescaped string fields are reached by user-controlled input.
+///
+/// A CWE warning is generated for each such call.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1236.");
+    let csv_calls = find_csv_calls(analysis_results, &config);
+    let mut site_counts = SiteCounts::default();
+    if csv_calls.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut sinks: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for call in csv_calls.values() {
+        let indices = sinks.entry(call.symbol.clone()).or_default();
+        indices.extend(call.field_indices.iter().copied());
+        indices.sort_unstable();
+        indices.dedup();
+    }
+    let taint_config = TaintFlowConfig {
+        sources: config.input_sources.clone(),
+        sinks,
+        sanitizers: config.sanitizers.clone(),
+        ..Default::default()
+    };
+    let mut tainted_calls: BTreeMap<Tid, BTreeSet<CallSite>> = BTreeMap::new();
+    for flow in find_taint_flows(analysis_results, &taint_config) {
+        if csv_calls.contains_key(&flow.sink.call_tid) {
+            tainted_calls
+                .entry(flow.sink.call_tid)
+                .or_default()
+                .insert(flow.source);
+        }
+    }
+
+    for call_tid in csv_calls.keys() {
+        if tainted_calls.contains_key(call_tid) {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    let cwe_warnings = tainted_calls
+        .iter()
+        .map(|(call_tid, sources)| generate_cwe_warning(call_tid, &csv_calls[call_tid], sources))
+        .collect();
+    (Vec::new(), cwe_warnings)
+}
+
+/// Collect the calls to the configured output functions whose format string is CSV-like
+/// and contains at least one unescaped string field.
+fn find_csv_calls(analysis_results: &AnalysisResults, config: &Config) -> HashMap<Tid, CsvCall> {
+    let project = analysis_results.project;
+    let Some(pi_result) = analysis_results.pointer_inference else {
+        return HashMap::new();
+    };
+    let symbol_names: Vec<String> = config.format_symbols.keys().cloned().collect();
+    let symbol_map = get_symbol_map(project, &symbol_names);
+    let mut csv_calls = HashMap::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            for jmp in blk.term.jmps.iter() {
+                let Jmp::Call { target, .. } = &jmp.term else {
+                    continue;
+                };
+                let Some(symbol) = symbol_map.get(target) else {
+                    continue;
+                };
+                let format_index = config.format_symbols[&symbol.name];
+                let Some(format_string) = project
+                    .get_parameter_arg(symbol, format_index)
+                    .and_then(|param| pi_result.eval_parameter_arg_at_call(&jmp.tid, &param))
+                    .and_then(|value| value.get_if_absolute_value()?.try_to_bitvec().ok())
+                    .and_then(|address| {
+                        parse_format_string_destination_and_return_content(
+                            address,
+                            &project.runtime_memory_image,
+                        )
+                        .ok()
+                    })
+                else {
+                    continue;
+                };
+                let Some(fields) = get_unescaped_string_fields(&format_string, &config.separators)
+                else {
+                    continue;
+                };
+                if fields.is_empty() {
+                    continue;
+                }
+                csv_calls.insert(
+                    jmp.tid.clone(),
+                    CsvCall {
+                        symbol: symbol.name.clone(),
+                        format_string,
+                        field_indices: fields
+                            .into_iter()
+                            .map(|position| format_index + 1 + position)
+                            .collect(),
+                    },
+                );
+            }
+        }
+    }
+    csv_calls
+}
+
+/// If the format string is CSV-like, return the positions of its unescaped string conversions
+/// among all conversions of the format string.
+///
+/// Returns `None` if the format string is not CSV-like.
+fn get_unescaped_string_fields(format_string: &str, separators: &[char]) -> Option<Vec<usize>> {
+    let re = Regex::new(r"%[+\-#0]{0,1}\d*[\.]?\d*(hh|h|ll|l|L|z|j|t)?([cCdiouxXeEfFgGaAnpsS])")
+        .expect("No valid regex!");
+    let conversions: Vec<_> = re.captures_iter(format_string).collect();
+    let is_csv_like = conversions.windows(2).any(|pair| {
+        let text = &format_string[pair[0].get(0).unwrap().end()..pair[1].get(0).unwrap().start()];
+        let mut chars = text.chars().filter(|c| *c != '"' && !c.is_whitespace());
+        matches!((chars.next(), chars.next()), (Some(c), None) if separators.contains(&c))
+    });
+    if !is_csv_like {
+        return None;
+    }
+    let fields = conversions
+        .iter()
+        .enumerate()
+        .filter(|(_, conversion)| matches!(&conversion[2], "s" | "S"))
+        .filter(|(_, conversion)| {
+            let start = conversion.get(0).unwrap().start();
+            !format_string[..start].ends_with('\'')
+        })
+        .map(|(position, _)| position)
+        .collect();
+    Some(fields)
+}
+
+/// Generate the CWE warning for a CSV-like output call reached by user input.
+fn generate_cwe_warning(
+    call_tid: &Tid,
+    csv_call: &CsvCall,
+    sources: &BTreeSet<CallSite>,
+) -> CweWarning {
+    let source_descriptions: Vec<String> = sources
+        .iter()
+        .map(|source| format!("{} at {}", source.function, source.call_tid.address))
+        .collect();
+    let mut tids = vec![format!("{call_tid}")];
+    tids.extend(sources.iter().map(|source| format!("{}", source.call_tid)));
+    let mut symbols = vec![csv_call.symbol.clone()];
+    symbols.extend(sources.iter().map(|source| source.function.clone()));
+    symbols.dedup();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(CSV Formula Injection) Call to {} at {} writes user input returned by {} into the CSV format {:?} without escaping formula characters",
+            csv_call.symbol,
+            call_tid.address,
+            source_descriptions.join(", "),
+            csv_call.format_string
+        ),
+    )
+    .tids(tids)
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::defs;
+    use crate::utils::binary::MemorySegment;
+
+    #[test]
+    fn csv_like_format_strings() {
+        let separators = [',', ';'];
+        assert_eq!(
+            get_unescaped_string_fields("%d,%s,%s\n", &separators),
+            Some(vec![1, 2])
+        );
+        assert_eq!(
+            get_unescaped_string_fields("\"%s\"; \"%lu\"\n", &separators),
+            Some(vec![0])
+        );
+        assert_eq!(
+            get_unescaped_string_fields("'%s,%s\n", &separators),
+            Some(vec![1])
+        );
+        assert_eq!(
+            get_unescaped_string_fields("User %s logged in from %s\n", &separators),
+            None
+        );
+        assert_eq!(get_unescaped_string_fields("%s\n", &separators), None);
+    }
+
+    #[test]
+    fn user_input_in_csv_export() {
+        let mut blk_input = Blk::mock_with_tid("blk_input");
+        blk_input.term.defs = defs!["name: RDI:8 = 0x9100:8"];
+        blk_input
+            .term
+            .jmps
+            .push(Jmp::call("call_getenv", "getenv", Some("blk_export")));
+        let mut blk_export = Blk::mock_with_tid("blk_export");
+        blk_export.term.defs = defs![
+            "save_input: RBX:8 = RAX:8",
+            "export_format: RSI:8 = 0x9000:8",
+            "export_field: RDX:8 = RBX:8"
+        ];
+        blk_export
+            .term
+            .jmps
+            .push(Jmp::call("call_export", "fprintf", Some("blk_log")));
+        let mut blk_log = Blk::mock_with_tid("blk_log");
+        blk_log.term.defs = defs!["log_format: RSI:8 = 0x9007:8", "log_field: RDX:8 = RBX:8"];
+        blk_log
+            .term
+            .jmps
+            .push(Jmp::call("call_log", "fprintf", Some("blk_end")));
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![
+            blk_input,
+            blk_export,
+            blk_log,
+            Blk::mock_with_tid("blk_end"),
+        ];
+        let mut project = mock_x64_project(&["getenv", "fprintf"], vec![sub]);
+        project
+            .runtime_memory_image
+            .memory_segments
+            .push(MemorySegment {
+                bytes: b"%s,%s\n\x00User %s logged in\n\x00".to_vec(),
+                base_address: 0x9000,
+                read_flag: true,
+                write_flag: false,
+                execute_flag: false,
+            });
+
+        let config = serde_json::json!({
+            "format_symbols": {"fprintf": 1},
+            "input_sources": {"getenv": "ReturnValue"},
+            "separators": [",", ";"]
+        });
+        let warnings = run_check(check_cwe, &project, &config);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["call_export", "call_getenv"]);
+        assert!(warnings[0].description.contains("getenv"));
+    }
+}
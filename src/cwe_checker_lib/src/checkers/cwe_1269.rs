@@ -0,0 +1,362 @@
+//! This module implements a check for CWE-1269: Product Released in Non-Release Configuration.
+//!
+//! Debug and test code like factory test menus, debug shells or backdoors for engineering access
+//! is often left in production firmware instead of being compiled out.
+//! If it is reachable from the entry points of the program,
+//! attackers may be able to trigger it, e.g. by sending a magic value.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1269.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! This is an informational check.
+//! A function is classified as debug or test code if
+//! - its name starts with one of the configured prefixes (e.g. `test_` or `debug_`),
+//! - it references a string containing one of the configured markers (e.g. `TEST MODE`),
+//! - or it compares a value for equality with one of the configured magic values (e.g. `0xdeadbeef`).
+//!
+//! Each such function that is reachable in the call graph from an entry point of the program is reported
+//! together with the indicators that it was classified by.
+//! Name and string comparisons are case-insensitive.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The function name prefixes, the string markers and the magic values are configurable in config.json.
+//!
+//! ## False Positives
+//!
+//! - Functions may use a name prefix, a marker string or a magic value for other purposes,
+//!   e.g. a `debug_` prefix for functions writing to a log file.
+//! - Debug code may be left in the binary on purpose if it can only be activated with physical access to the device.
+//!
+//! ## False Negatives
+//!
+//! - Debug code without one of the configured indicators is not found.
+//!   In particular the name prefixes are not helpful for stripped binaries.
+//! - Indirect calls are not represented in the call graph,
+//!   so debug code only reachable through indirect calls (e.g. command tables) is missed.
+
+use crate::analysis::callgraph::{get_program_callgraph, CallGraph};
+use crate::analysis::xref::XrefIndex;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::parse_hex_string_to_u64;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::CweModule;
+use petgraph::visit::Dfs;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1269",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions whose name starts with one of these prefixes (case-insensitive) are considered debug or test code.
+    debug_function_prefixes: Vec<String>,
+    /// Functions referencing a string that contains one of these markers (case-insensitive)
+    /// are considered debug or test code.
+    debug_string_markers: Vec<String>,
+    /// Functions comparing a value for equality with one of these values (given as hexadecimal strings)
+    /// are considered debug or test code.
+    magic_values: Vec<String>,
+}
+
+/// An indicator that a function contains debug or test code.
+#[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
+enum DebugIndicator {
+    /// The name of the function starts with the given prefix.
+    NamePrefix(String),
+    /// The function references the given string.
+    MarkerString(String),
+    /// The `Def` term with the given TID compares a value with the given magic value.
+    MagicValue(Tid, u64),
+}
+
+impl std::fmt::Display for DebugIndicator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DebugIndicator::NamePrefix(prefix) => write!(f, "name starts with \"{prefix}\""),
+            DebugIndicator::MarkerString(string) => write!(f, "references the string \"{string}\""),
+            DebugIndicator::MagicValue(tid, value) => {
+                write!(
+                    f,
+                    "compares with the magic value {value:#x} at {}",
+                    tid.address
+                )
+            }
+        }
+    }
+}
+
+/// Run the check.
+///
+/// First the debug and test functions are classified by their indicators.
+/// Then the functions reachable from the entry points of the program are reported.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1269.");
+    let magic_values: Vec<u64> = config
+        .magic_values
+        .iter()
+        .map(|value| {
+            parse_hex_string_to_u64(value)
+                .expect("Invalid magic value inside config.json for CWE1269.")
+        })
+        .collect();
+
+    let indicators = find_debug_indicators(project, &config, &magic_values);
+    // The examined sites are the functions containing debug indicators.
+    let mut site_counts = SiteCounts::default();
+    if indicators.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let callgraph = get_program_callgraph(&project.program);
+    let reachable = compute_reachable_functions(&callgraph, &project.program.term.entry_points);
+    let mut cwe_warnings = Vec::new();
+    for (sub_tid, indicators) in indicators.iter() {
+        // Debug functions that are not reachable from an entry point are dead code.
+        let Some(entry_point) = reachable.get(sub_tid) else {
+            site_counts.add_safe();
+            continue;
+        };
+        site_counts.add_flagged();
+        cwe_warnings.push(generate_cwe_warning(
+            &project.program.term.subs[sub_tid],
+            &project.program.term.subs[entry_point],
+            indicators,
+        ));
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Find the indicators of debug or test code for all functions of the program.
+/// Functions without indicators are not contained in the returned map.
+fn find_debug_indicators(
+    project: &Project,
+    config: &Config,
+    magic_values: &[u64],
+) -> BTreeMap<Tid, BTreeSet<DebugIndicator>> {
+    let mut indicators: BTreeMap<Tid, BTreeSet<DebugIndicator>> = BTreeMap::new();
+    for sub in project.program.term.subs.values() {
+        let name = sub.term.name.to_lowercase();
+        if let Some(prefix) = config
+            .debug_function_prefixes
+            .iter()
+            .find(|prefix| name.starts_with(&prefix.to_lowercase()))
+        {
+            indicators
+                .entry(sub.tid.clone())
+                .or_default()
+                .insert(DebugIndicator::NamePrefix(prefix.clone()));
+        }
+        for def in sub.term.blocks.iter().flat_map(|blk| blk.term.defs.iter()) {
+            if let Def::Assign { value, .. } = &def.term {
+                if let Some(magic_value) = find_magic_value_comparison(value, magic_values) {
+                    indicators
+                        .entry(sub.tid.clone())
+                        .or_default()
+                        .insert(DebugIndicator::MagicValue(def.tid.clone(), magic_value));
+                }
+            }
+        }
+    }
+
+    let xrefs = XrefIndex::new(project, None);
+    for address in xrefs.referenced_addresses() {
+        let Ok(string) = project
+            .runtime_memory_image
+            .read_string_until_null_terminator(&Bitvector::from_u64(address))
+        else {
+            continue;
+        };
+        let lowercase_string = string.to_lowercase();
+        if !config
+            .debug_string_markers
+            .iter()
+            .any(|marker| lowercase_string.contains(&marker.to_lowercase()))
+        {
+            continue;
+        }
+        for xref in xrefs.get_xrefs(address) {
+            indicators
+                .entry(xref.sub.clone())
+                .or_default()
+                .insert(DebugIndicator::MarkerString(string.to_string()));
+        }
+    }
+    indicators
+}
+
+/// Returns the magic value if the expression compares a value for equality or inequality with one of the magic values.
+fn find_magic_value_comparison(expression: &Expression, magic_values: &[u64]) -> Option<u64> {
+    use Expression::*;
+    match expression {
+        BinOp {
+            op: BinOpType::IntEqual | BinOpType::IntNotEqual,
+            lhs,
+            rhs,
+        } => [lhs, rhs]
+            .into_iter()
+            .find_map(|operand| match &**operand {
+                Const(constant) => constant
+                    .try_to_u64()
+                    .ok()
+                    .filter(|value| magic_values.contains(value)),
+                _ => None,
+            })
+            .or_else(|| find_magic_value_comparison(lhs, magic_values))
+            .or_else(|| find_magic_value_comparison(rhs, magic_values)),
+        BinOp { lhs, rhs, .. } => find_magic_value_comparison(lhs, magic_values)
+            .or_else(|| find_magic_value_comparison(rhs, magic_values)),
+        UnOp { arg, .. } | Cast { arg, .. } | Subpiece { arg, .. } => {
+            find_magic_value_comparison(arg, magic_values)
+        }
+        Const(_) | Var(_) | Unknown { .. } => None,
+    }
+}
+
+/// Compute the functions reachable from the given entry points.
+/// Each function is mapped to one of the entry points it is reachable from.
+fn compute_reachable_functions(
+    callgraph: &CallGraph,
+    entry_points: &BTreeSet<Tid>,
+) -> BTreeMap<Tid, Tid> {
+    let mut reachable = BTreeMap::new();
+    for start in callgraph
+        .node_indices()
+        .filter(|node| entry_points.contains(&callgraph[*node]))
+    {
+        let mut dfs = Dfs::new(callgraph, start);
+        while let Some(node) = dfs.next(callgraph) {
+            reachable
+                .entry(callgraph[node].clone())
+                .or_insert_with(|| callgraph[start].clone());
+        }
+    }
+    reachable
+}
+
+/// Generate the CWE warning for a debug or test function reachable from the given entry point.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    entry_point: &Term<Sub>,
+    indicators: &BTreeSet<DebugIndicator>,
+) -> CweWarning {
+    let descriptions: Vec<String> = indicators
+        .iter()
+        .map(|indicator| indicator.to_string())
+        .collect();
+    let mut tids = vec![format!("{}", sub.tid)];
+    tids.extend(indicators.iter().filter_map(|indicator| match indicator {
+        DebugIndicator::MagicValue(tid, _) => Some(format!("{tid}")),
+        _ => None,
+    }));
+    let mut symbols = vec![sub.term.name.clone(), entry_point.term.name.clone()];
+    symbols.dedup();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Product Released in Non-Release Configuration) Debug or test function {} at {} is reachable from the entry point {}: {}",
+            sub.term.name,
+            sub.tid.address,
+            entry_point.term.name,
+            descriptions.join(", ")
+        ),
+    )
+    .tids(tids)
+    .addresses(vec![sub.tid.address.clone()])
+    .symbols(symbols)
+    .other(vec![vec!["severity".to_string(), "info".to_string()]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::mock_x64_project;
+    use crate::def;
+    use crate::utils::binary::MemorySegment;
+
+    fn mock_sub(name: &str, calls: &[&str], defs: Vec<Term<Def>>) -> Term<Sub> {
+        let mut sub = Sub::mock(name);
+        let mut blk = Blk::mock_with_tid(&format!("{name}_blk"));
+        blk.term.defs = defs;
+        for (i, target) in calls.iter().enumerate() {
+            blk.term
+                .jmps
+                .push(Jmp::call(&format!("{name}_call_{i}"), target, None));
+        }
+        sub.term.blocks.push(blk);
+        sub
+    }
+
+    fn mock_config() -> Config {
+        Config {
+            debug_function_prefixes: vec!["test_".to_string(), "debug_".to_string()],
+            debug_string_markers: vec!["test mode".to_string()],
+            magic_values: vec!["0xdeadbeef".to_string()],
+        }
+    }
+
+    #[test]
+    fn debug_functions_reachable_from_entry_point() {
+        // `handle_command` checks for a backdoor value and prints a test mode banner,
+        // `test_unused` is not reachable from `main`.
+        let subs = vec![
+            mock_sub("main", &["handle_command", "Debug_Shell"], Vec::new()),
+            mock_sub(
+                "handle_command",
+                &[],
+                vec![
+                    def!["magic: ZF:1 = RDI:8 == 0xdeadbeef:8"],
+                    def!["banner: RDI:8 = 0x9000:8"],
+                ],
+            ),
+            mock_sub("Debug_Shell", &[], Vec::new()),
+            mock_sub("test_unused", &[], Vec::new()),
+        ];
+        let mut project = mock_x64_project(&[], subs);
+        project
+            .runtime_memory_image
+            .memory_segments
+            .push(MemorySegment {
+                bytes: b"Entering TEST MODE\x00".to_vec(),
+                base_address: 0x9000,
+                read_flag: true,
+                write_flag: false,
+                execute_flag: false,
+            });
+        project.program.term.entry_points.insert(Tid::new("main"));
+        let config = mock_config();
+
+        let indicators = find_debug_indicators(&project, &config, &[0xdeadbeef]);
+        assert_eq!(indicators.len(), 3);
+        assert_eq!(
+            indicators[&Tid::new("handle_command")],
+            BTreeSet::from([
+                DebugIndicator::MarkerString("Entering TEST MODE".to_string()),
+                DebugIndicator::MagicValue(Tid::new("magic"), 0xdeadbeef),
+            ])
+        );
+
+        let analysis_results = AnalysisResults::mock_from_project(&project);
+        let (_, warnings) = check_cwe(&analysis_results, &serde_json::to_value(&config).unwrap());
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].symbols, vec!["Debug_Shell", "main"]);
+        assert_eq!(warnings[1].tids, vec!["handle_command", "magic"]);
+        assert_eq!(warnings[1].other[0], vec!["severity", "info"]);
+    }
+}
@@ -0,0 +1,374 @@
+//! This module implements a check for CWE-1277: Firmware Not Updateable.
+//!
+//! If the firmware of a device cannot be updated, vulnerabilities found after deployment
+//! cannot be fixed and the device stays vulnerable for its whole lifetime.
+//! Update mechanisms that write new firmware to flash memory without verifying its signature
+//! allow attackers to install malicious firmware instead.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1277.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! This is an informational check.
+//! On a standard run it is only executed for bare metal binaries.
+//! For other binaries it has to be selected explicitly via the `--partial` command line option.
+//! It searches the binary for three kinds of indicators of a firmware update mechanism:
+//! - Calls to routines that write to flash memory.
+//! - Calls to signature verification functions.
+//! - Strings referenced by the code that look like update URLs.
+//!
+//! Each function calling a flash write routine is reported as an update mechanism.
+//! The mechanism is classified by searching its update context for the other indicators.
+//! The update context consists of the function itself, its callers (up to two levels up in the call graph)
+//! and the functions directly called by these.
+//! If a signature verification function is called in the update context, the mechanism is classified as verified.
+//! If an update URL is referenced in the update context, the mechanism is classified as remote.
+//!
+//! If no flash write routine is found at all, a warning is generated that the firmware may not be updateable.
+//!
+//! ## False Positives
+//!
+//! - Flash write routines may be used for other purposes than firmware updates, e.g. for storing configuration data.
+//! - The firmware may be updated by a separate bootloader that is not part of the analyzed binary.
+//!
+//! ## False Negatives
+//!
+//! - Update mechanisms using flash write routines that are not contained in the configured list
+//!   (e.g. inlined or statically linked routines without symbols) are not found.
+//! - Signature verifications outside of the update context of a flash write lead to a mechanism
+//!   being wrongly classified as unverified.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The flash write routines, the signature verification functions
+//! and the keywords identifying update URLs are configurable in config.json.
+
+use crate::analysis::callgraph::{get_program_callgraph, CallGraph};
+use crate::analysis::xref::XrefIndex;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::graph::NodeIndex;
+use petgraph::Direction;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1277",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The number of call graph levels above a flash writing function that belong to its update context.
+const MAX_CALLER_DEPTH: usize = 2;
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions that write to flash memory.
+    flash_write_symbols: Vec<String>,
+    /// Functions that verify cryptographic signatures.
+    verification_symbols: Vec<String>,
+    /// A string containing `://` and one of these keywords (case-insensitive) is considered an update URL.
+    update_url_keywords: Vec<String>,
+}
+
+/// The indicators of an update mechanism found in one function.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct Indicators {
+    /// Names of called flash write routines.
+    flash_writes: BTreeSet<String>,
+    /// Names of called signature verification functions.
+    verifications: BTreeSet<String>,
+    /// Referenced update URLs.
+    update_urls: BTreeSet<String>,
+}
+
+impl Indicators {
+    /// Add all indicators of `other` to `self`.
+    fn extend(&mut self, other: &Indicators) {
+        self.flash_writes.extend(other.flash_writes.iter().cloned());
+        self.verifications
+            .extend(other.verifications.iter().cloned());
+        self.update_urls.extend(other.update_urls.iter().cloned());
+    }
+}
+
+/// Run the check.
+///
+/// Collect the update mechanism indicators of all functions
+/// and generate a warning for each function calling a flash write routine.
+/// If no such function exists, generate a warning that the firmware may not be updateable.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1277.");
+
+    let indicators = collect_indicators(project, &config);
+    let callgraph = get_program_callgraph(&project.program);
+    let mut cwe_warnings = Vec::new();
+    for (sub_tid, sub_indicators) in indicators.iter() {
+        if sub_indicators.flash_writes.is_empty() {
+            continue;
+        }
+        let context = collect_update_context(&callgraph, sub_tid, &indicators);
+        let sub = &project.program.term.subs[sub_tid];
+        cwe_warnings.push(generate_mechanism_warning(sub, &context));
+    }
+    if cwe_warnings.is_empty() {
+        let mut found = Indicators::default();
+        for sub_indicators in indicators.values() {
+            found.extend(sub_indicators);
+        }
+        cwe_warnings.push(generate_not_updateable_warning(&found));
+    }
+    // The examined sites are the functions writing to flash memory,
+    // or the binary itself if it contains no such function.
+    let mut site_counts = SiteCounts::default();
+    for _warning in cwe_warnings.iter() {
+        site_counts.add_flagged();
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Collect the update mechanism indicators for each function of the program.
+/// Functions without any indicators are not contained in the returned map.
+fn collect_indicators(project: &Project, config: &Config) -> BTreeMap<Tid, Indicators> {
+    let flash_write_symbols = get_symbol_map(project, &config.flash_write_symbols);
+    let verification_symbols = get_symbol_map(project, &config.verification_symbols);
+    let mut indicators: BTreeMap<Tid, Indicators> = BTreeMap::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            for jmp in blk.term.jmps.iter() {
+                let Jmp::Call { target, .. } = &jmp.term else {
+                    continue;
+                };
+                if let Some(symbol) = flash_write_symbols.get(target) {
+                    let entry = indicators.entry(sub.tid.clone()).or_default();
+                    entry.flash_writes.insert(symbol.name.clone());
+                }
+                if let Some(symbol) = verification_symbols.get(target) {
+                    let entry = indicators.entry(sub.tid.clone()).or_default();
+                    entry.verifications.insert(symbol.name.clone());
+                }
+            }
+        }
+    }
+    let xrefs = XrefIndex::new(project, None);
+    for address in xrefs.referenced_addresses() {
+        let Ok(string) = project
+            .runtime_memory_image
+            .read_string_until_null_terminator(&Bitvector::from_u64(address))
+        else {
+            continue;
+        };
+        if is_update_url(string, &config.update_url_keywords) {
+            for xref in xrefs.get_xrefs(address) {
+                let entry = indicators.entry(xref.sub.clone()).or_default();
+                entry.update_urls.insert(string.to_string());
+            }
+        }
+    }
+    indicators
+}
+
+/// Check whether the given string looks like an update URL.
+fn is_update_url(string: &str, keywords: &[String]) -> bool {
+    let string = string.to_lowercase();
+    string.contains("://")
+        && keywords
+            .iter()
+            .any(|keyword| string.contains(&keyword.to_lowercase()))
+}
+
+/// Collect the indicators of all functions in the update context of the given function.
+fn collect_update_context(
+    callgraph: &CallGraph,
+    sub_tid: &Tid,
+    indicators: &BTreeMap<Tid, Indicators>,
+) -> Indicators {
+    let node_map: HashMap<&Tid, NodeIndex> = callgraph
+        .node_indices()
+        .map(|node| (&callgraph[node], node))
+        .collect();
+    let mut callers = BTreeSet::from([node_map[sub_tid]]);
+    let mut current_level = callers.clone();
+    for _ in 0..MAX_CALLER_DEPTH {
+        current_level = current_level
+            .iter()
+            .flat_map(|node| callgraph.neighbors_directed(*node, Direction::Incoming))
+            .filter(|node| !callers.contains(node))
+            .collect();
+        callers.extend(current_level.iter().copied());
+    }
+    let mut context_nodes = callers.clone();
+    for caller in callers {
+        context_nodes.extend(callgraph.neighbors_directed(caller, Direction::Outgoing));
+    }
+
+    let mut context = Indicators::default();
+    for node in context_nodes {
+        if let Some(sub_indicators) = indicators.get(&callgraph[node]) {
+            context.extend(sub_indicators);
+        }
+    }
+    // Only the flash writes of the function itself characterize the mechanism.
+    context.flash_writes = indicators[sub_tid].flash_writes.clone();
+    context
+}
+
+/// Generate the warning describing the update mechanism in the given function.
+fn generate_mechanism_warning(sub: &Term<Sub>, context: &Indicators) -> CweWarning {
+    let verification = if context.verifications.is_empty() {
+        "unverified"
+    } else {
+        "verified"
+    };
+    let source = if context.update_urls.is_empty() {
+        "local"
+    } else {
+        "remote"
+    };
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Firmware Update Mechanism) Function {} at {} writes to flash memory ({verification}, {source} update)",
+            sub.term.name, sub.tid.address
+        ),
+    )
+    .tids(vec![format!("{}", sub.tid)])
+    .addresses(vec![sub.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(indicator_lists(context))
+}
+
+/// Generate the warning that no update mechanism was found.
+/// Indicators found outside of any update mechanism are listed in the warning.
+fn generate_not_updateable_warning(found: &Indicators) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        "(Firmware Not Updateable) No routine for writing firmware updates to flash memory found"
+            .to_string(),
+    )
+    .other(indicator_lists(found))
+}
+
+/// Convert the indicators to the lists contained in the `other` field of a warning.
+fn indicator_lists(indicators: &Indicators) -> Vec<Vec<String>> {
+    [
+        ("flash_writes", &indicators.flash_writes),
+        ("verifications", &indicators.verifications),
+        ("update_urls", &indicators.update_urls),
+    ]
+    .into_iter()
+    .filter(|(_, values)| !values.is_empty())
+    .map(|(name, values)| {
+        std::iter::once(name.to_string())
+            .chain(values.iter().cloned())
+            .collect()
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::mock_x64_project;
+    use crate::def;
+    use crate::utils::binary::MemorySegment;
+
+    fn mock_call(tid: &str, target: &str) -> Term<Jmp> {
+        Term {
+            tid: Tid::new(tid),
+            term: Jmp::Call {
+                target: Tid::new(target),
+                return_: None,
+            },
+        }
+    }
+
+    fn mock_config() -> Config {
+        Config {
+            flash_write_symbols: vec!["flash_write".to_string()],
+            verification_symbols: vec!["RSA_verify".to_string()],
+            update_url_keywords: vec!["firmware".to_string()],
+        }
+    }
+
+    #[test]
+    fn update_mechanisms() {
+        // `do_update` downloads, verifies and writes the update.
+        // `save_config` writes to flash without verification.
+        let mut update = Sub::mock("do_update");
+        let mut blk = Blk::mock_with_tid("update_blk");
+        blk.term.defs = vec![def!["load_url: RDI:8 = 0x9000:8"]];
+        blk.term.jmps = vec![
+            mock_call("call_verify", "RSA_verify"),
+            mock_call("call_write", "write_image"),
+        ];
+        update.term.blocks.push(blk);
+        let mut write = Sub::mock("write_image");
+        let mut blk = Blk::mock_with_tid("write_blk");
+        blk.term.jmps = vec![mock_call("call_flash_1", "flash_write")];
+        write.term.blocks.push(blk);
+        let mut config = Sub::mock("save_config");
+        let mut blk = Blk::mock_with_tid("config_blk");
+        blk.term.jmps = vec![mock_call("call_flash_2", "flash_write")];
+        config.term.blocks.push(blk);
+        let mut project =
+            mock_x64_project(&["flash_write", "RSA_verify"], vec![update, write, config]);
+        let url = b"https://example.com/FIRMWARE.bin\0";
+        project
+            .runtime_memory_image
+            .memory_segments
+            .push(MemorySegment {
+                bytes: url.to_vec(),
+                base_address: 0x9000,
+                read_flag: true,
+                write_flag: false,
+                execute_flag: false,
+            });
+
+        let indicators = collect_indicators(&project, &mock_config());
+        assert_eq!(indicators.len(), 3);
+        let callgraph = get_program_callgraph(&project.program);
+        let context = collect_update_context(&callgraph, &Tid::new("write_image"), &indicators);
+        assert_eq!(
+            context.verifications,
+            BTreeSet::from(["RSA_verify".to_string()])
+        );
+        assert_eq!(
+            context.update_urls,
+            BTreeSet::from(["https://example.com/FIRMWARE.bin".to_string()])
+        );
+        let context = collect_update_context(&callgraph, &Tid::new("save_config"), &indicators);
+        assert!(context.verifications.is_empty());
+        assert!(context.update_urls.is_empty());
+        assert_eq!(
+            generate_mechanism_warning(
+                &project.program.term.subs[&Tid::new("save_config")],
+                &context
+            )
+            .other,
+            vec![vec!["flash_writes".to_string(), "flash_write".to_string()]]
+        );
+    }
+
+    #[test]
+    fn update_urls() {
+        let keywords = vec!["firmware".to_string(), "ota".to_string()];
+        assert!(is_update_url("http://10.0.0.1/OTA/image", &keywords));
+        assert!(!is_update_url("http://example.com/index.html", &keywords));
+        assert!(!is_update_url("firmware.bin", &keywords));
+    }
+}
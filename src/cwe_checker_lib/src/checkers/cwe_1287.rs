@@ -0,0 +1,603 @@
+//! This module implements a check for CWE-1287: Improper Validation of Specified Type of Input.
+//!
+//! File and network formats usually start with magic bytes identifying the format,
+//! followed by version or type fields that select how the rest of the input is parsed.
+//! A parser that dispatches on the version or type fields without validating the magic bytes first
+//! interprets arbitrary inputs as the expected format,
+//! which exposes the (often less tested) handlers for unusual versions and types to attackers.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1287.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [Pointer Inference analysis](`crate::analysis::pointer_inference`)
+//! we find all calls to input functions (e.g. `read` or `fread`) and the buffers that the input is written to.
+//! Starting at the return site of each input call we follow the control flow inside the calling function.
+//! On each path we look for the first branch depending on a value loaded from the buffer:
+//! - If the value is compared to a magic constant, the path validates the magic bytes of the input.
+//!   A magic constant is a constant that does not fit into less than `min_magic_bytes` bytes.
+//!   Passing the buffer together with a pointer to global memory to a comparison function (e.g. `memcmp`)
+//!   is also considered a validation of the magic bytes.
+//! - Otherwise the branch dispatches on a field of the input without validating the magic bytes.
+//!
+//! The magic constants compared on all paths form the comparison set of the binary.
+//! A path dispatching on the input without validating the magic bytes is only reported
+//! if the comparison set contains magic constants compared on the paths following other input calls.
+//! This shows that the binary knows the magic bytes of (at least some of) its input formats
+//! and keeps the number of false positives for formats without magic bytes low.
+//!
+//! ## False Positives
+//!
+//! - The magic bytes may have been validated by an earlier read of the same input, e.g. in a caller.
+//! - The input read at the call may not contain a header at all, e.g. because it reads the payload of a record.
+//! - The magic constants of the comparison set may belong to a different input format.
+//!
+//! ## False Negatives
+//!
+//! - Loaded values are only tracked inside a basic block,
+//!   i.e. values loaded in one block and compared in another block are missed.
+//! - Each path is only followed inside the function calling the input function.
+//! - Any comparison with a magic constant is accepted as validation, even if it checks the wrong field.
+//! - Binaries without any magic number comparison on input buffers are not reported.
+//!
+//! ## Configuration
+//!
+//! The input functions together with the indices of their buffer and length parameters,
+//! the comparison functions and the minimal size of magic constants are configurable in config.json.
+//! Comparison functions are assumed to compare the first two parameters
+//! and to take the number of compared bytes as third parameter.
+
+use crate::abstract_domain::TryToBitvec;
+use crate::analysis::pointer_inference::PointerInference;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::buffer_utils::{get_input_buffers, points_into, Buffer, BufferParameters};
+use crate::utils::graph_utils::get_successor_block;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1287",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions reading input, mapped to their buffer parameters.
+    input_symbols: BTreeMap<String, BufferParameters>,
+    /// Functions comparing two memory regions, e.g. `memcmp`.
+    comparison_symbols: Vec<String>,
+    /// The minimal number of bytes needed to represent a magic constant.
+    min_magic_bytes: u64,
+}
+
+/// A branch depending on the content of an input buffer.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum InputBranch {
+    /// The branch validates the magic bytes of the input against the given magic constant.
+    MagicCheck(String),
+    /// The branch dispatches on a field of the input at the jump with the given TID.
+    Dispatch(Tid),
+}
+
+/// The result of analyzing the paths following a call to an input function.
+#[derive(Debug, Clone)]
+struct InputCall<'a> {
+    /// The function containing the call.
+    sub: &'a Term<Sub>,
+    /// The call to the input function.
+    call_tid: Tid,
+    /// The input function.
+    input_symbol: &'a ExternSymbol,
+    /// The magic constants compared against the content of the input buffer.
+    magic_constants: BTreeSet<String>,
+    /// The first dispatch on the input reachable without validating the magic bytes.
+    unvalidated_dispatch: Option<Tid>,
+}
+
+/// Returns the constant formatted as a magic constant
+/// if it does not fit into less than `min_magic_bytes` bytes.
+fn as_magic_constant(constant: &Bitvector, min_magic_bytes: u64) -> Option<String> {
+    let value = constant.try_to_u64().ok()?;
+    let shift = 8 * min_magic_bytes.saturating_sub(1);
+    if shift >= 64 || value >> shift == 0 {
+        return None;
+    }
+    Some(format!("{value:#x}"))
+}
+
+/// If the expression compares a value derived from the input buffer with a magic constant,
+/// return the magic constant.
+fn get_magic_comparison(
+    expression: &Expression,
+    loaded_vars: &HashSet<&Variable>,
+    min_magic_bytes: u64,
+) -> Option<String> {
+    use BinOpType::*;
+    let Expression::BinOp { op, lhs, rhs } = expression else {
+        return None;
+    };
+    if !matches!(
+        op,
+        IntEqual
+            | IntNotEqual
+            | IntLess
+            | IntSLess
+            | IntLessEqual
+            | IntSLessEqual
+            | IntSub
+            | IntSBorrow
+            | IntXOr
+    ) {
+        return None;
+    }
+    let ((Expression::Const(constant), operand) | (operand, Expression::Const(constant))) =
+        (lhs.as_ref(), rhs.as_ref())
+    else {
+        return None;
+    };
+    if !operand
+        .input_vars()
+        .iter()
+        .any(|input| loaded_vars.contains(input))
+    {
+        return None;
+    }
+    as_magic_constant(constant, min_magic_bytes)
+}
+
+/// If the call passes a pointer into one of the buffers and a pointer to global memory
+/// to a comparison function, return a description of the magic bytes in global memory.
+fn get_magic_comparison_call(
+    project: &Project,
+    pi_result: &PointerInference,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    buffers: &[Buffer],
+    min_magic_bytes: u64,
+) -> Option<String> {
+    let mut args = (0..3).map(|index| {
+        project
+            .get_parameter_arg(symbol, index)
+            .and_then(|arg| pi_result.eval_parameter_arg_at_call(call_tid, &arg))
+    });
+    let (first, second, length) = (args.next()??, args.next()??, args.next()?);
+    let magic_address = if points_into(buffers, &first) {
+        second
+    } else if points_into(buffers, &second) {
+        first
+    } else {
+        return None;
+    };
+    let magic_address = magic_address.try_to_bitvec().ok()?;
+    if !project
+        .runtime_memory_image
+        .is_global_memory_address(&magic_address)
+    {
+        return None;
+    }
+    let address = magic_address.try_to_u64().ok()?;
+    match length.and_then(|length| length.try_to_offset().ok()) {
+        Some(length) if length < min_magic_bytes as i64 => None,
+        Some(length) => Some(format!("{length} bytes at {address:#x}")),
+        None => Some(format!("bytes at {address:#x}")),
+    }
+}
+
+/// Get the first branch of the block depending on the content of one of the buffers.
+///
+/// Values loaded from the buffers are tracked through assignments inside the block.
+/// Values derived from comparisons with magic constants are tracked separately,
+/// so that branches on them are recognized as magic number checks.
+fn get_input_branch(
+    project: &Project,
+    pi_result: &PointerInference,
+    blk: &Term<Blk>,
+    buffers: &[Buffer],
+    comparison_symbols: &HashMap<Tid, &ExternSymbol>,
+    min_magic_bytes: u64,
+) -> Option<InputBranch> {
+    let mut loaded_vars: HashSet<&Variable> = HashSet::new();
+    let mut magic_vars: HashMap<&Variable, String> = HashMap::new();
+    for def in blk.term.defs.iter() {
+        match &def.term {
+            Def::Load { var, .. } => {
+                magic_vars.remove(var);
+                let loads_from_buffer = pi_result
+                    .eval_address_at_def(&def.tid)
+                    .is_some_and(|address| points_into(buffers, &address));
+                if loads_from_buffer {
+                    loaded_vars.insert(var);
+                } else {
+                    loaded_vars.remove(var);
+                }
+            }
+            Def::Assign { var, value } => {
+                let inputs = value.input_vars();
+                let magic_constant = get_magic_comparison(value, &loaded_vars, min_magic_bytes)
+                    .or_else(|| {
+                        inputs
+                            .iter()
+                            .find_map(|input| magic_vars.get(input).cloned())
+                    });
+                if let Some(magic_constant) = magic_constant {
+                    magic_vars.insert(var, magic_constant);
+                    loaded_vars.remove(var);
+                } else if inputs.iter().any(|input| loaded_vars.contains(input)) {
+                    magic_vars.remove(var);
+                    loaded_vars.insert(var);
+                } else {
+                    magic_vars.remove(var);
+                    loaded_vars.remove(var);
+                }
+            }
+            Def::Store { .. } => (),
+        }
+    }
+    for jmp in blk.term.jmps.iter() {
+        match &jmp.term {
+            Jmp::CBranch {
+                condition: expression,
+                ..
+            }
+            | Jmp::BranchInd(expression) => {
+                let inputs = expression.input_vars();
+                if let Some(magic_constant) = inputs.iter().find_map(|input| magic_vars.get(input))
+                {
+                    return Some(InputBranch::MagicCheck(magic_constant.clone()));
+                }
+                if inputs.iter().any(|input| loaded_vars.contains(input)) {
+                    return Some(InputBranch::Dispatch(jmp.tid.clone()));
+                }
+            }
+            Jmp::Call { target, .. } => {
+                if let Some(symbol) = comparison_symbols.get(target) {
+                    if let Some(magic_constant) = get_magic_comparison_call(
+                        project,
+                        pi_result,
+                        &jmp.tid,
+                        symbol,
+                        buffers,
+                        min_magic_bytes,
+                    ) {
+                        return Some(InputBranch::MagicCheck(magic_constant));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Analyze the paths following the given call to an input function inside the calling function.
+fn analyze_input_call<'a>(
+    project: &Project,
+    pi_result: &PointerInference,
+    sub: &'a Term<Sub>,
+    call: &Term<Jmp>,
+    input_symbol: &'a ExternSymbol,
+    config: &Config,
+    comparison_symbols: &HashMap<Tid, &ExternSymbol>,
+) -> InputCall<'a> {
+    let buffers = get_input_buffers(
+        project,
+        pi_result,
+        &call.tid,
+        input_symbol,
+        config.input_symbols[&input_symbol.name],
+    );
+    let branches: HashMap<&Tid, InputBranch> = sub
+        .term
+        .blocks
+        .iter()
+        .filter_map(|blk| {
+            get_input_branch(
+                project,
+                pi_result,
+                blk,
+                &buffers,
+                comparison_symbols,
+                config.min_magic_bytes,
+            )
+            .map(|branch| (&blk.tid, branch))
+        })
+        .collect();
+    let magic_constants = branches
+        .values()
+        .filter_map(|branch| match branch {
+            InputBranch::MagicCheck(magic_constant) => Some(magic_constant.clone()),
+            InputBranch::Dispatch(_) => None,
+        })
+        .collect();
+
+    let blocks: HashMap<&Tid, &Term<Blk>> =
+        sub.term.blocks.iter().map(|blk| (&blk.tid, blk)).collect();
+    let mut unvalidated_dispatch = None;
+    let mut visited: HashSet<&Tid> = HashSet::new();
+    let mut worklist: VecDeque<&Tid> = get_successor_block(call).into_iter().collect();
+    while let Some(blk_tid) = worklist.pop_front() {
+        if !visited.insert(blk_tid) {
+            continue;
+        }
+        match branches.get(blk_tid) {
+            Some(InputBranch::Dispatch(jmp_tid)) => {
+                unvalidated_dispatch = Some(jmp_tid.clone());
+                break;
+            }
+            Some(InputBranch::MagicCheck(_)) => continue,
+            None => (),
+        }
+        if let Some(blk) = blocks.get(blk_tid) {
+            worklist.extend(blk.term.jmps.iter().filter_map(get_successor_block));
+        }
+    }
+    InputCall {
+        sub,
+        call_tid: call.tid.clone(),
+        input_symbol,
+        magic_constants,
+        unvalidated_dispatch,
+    }
+}
+
+/// Run the check.
+///
+/// First the paths following all calls to input functions are analyzed.
+/// Then the input calls followed by a dispatch on the input without magic number validation are reported
+/// if magic constants are compared on the paths following other input calls.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1287.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let input_names: Vec<String> = config.input_symbols.keys().cloned().collect();
+    let input_symbols = get_symbol_map(project, &input_names);
+    let mut site_counts = SiteCounts::default();
+    if input_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let comparison_symbols = get_symbol_map(project, &config.comparison_symbols);
+
+    let mut input_calls = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for jmp in sub.term.blocks.iter().flat_map(|blk| blk.term.jmps.iter()) {
+            let Jmp::Call { target, .. } = &jmp.term else {
+                continue;
+            };
+            let Some(symbol) = input_symbols.get(target) else {
+                continue;
+            };
+            input_calls.push(analyze_input_call(
+                project,
+                pi_result,
+                sub,
+                jmp,
+                symbol,
+                &config,
+                &comparison_symbols,
+            ));
+        }
+    }
+
+    let mut cwe_warnings = Vec::new();
+    for (index, input_call) in input_calls.iter().enumerate() {
+        let Some(dispatch) = &input_call.unvalidated_dispatch else {
+            site_counts.add_safe();
+            continue;
+        };
+        let other_magic_constants: BTreeSet<&String> = input_calls
+            .iter()
+            .enumerate()
+            .filter(|(other_index, _)| *other_index != index)
+            .flat_map(|(_, other)| other.magic_constants.iter())
+            .collect();
+        if !other_magic_constants.is_empty() {
+            site_counts.add_flagged();
+            cwe_warnings.push(generate_cwe_warning(
+                input_call,
+                dispatch,
+                &other_magic_constants,
+            ));
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    (Vec::new(), cwe_warnings)
+}
+
+/// Generate the CWE warning for a dispatch on input without magic number validation.
+fn generate_cwe_warning(
+    input_call: &InputCall,
+    dispatch: &Tid,
+    magic_constants: &BTreeSet<&String>,
+) -> CweWarning {
+    let magic_constants: Vec<&str> = magic_constants
+        .iter()
+        .map(|magic_constant| magic_constant.as_str())
+        .collect();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Missing Magic Number Check) Function {} dispatches on input read by {} at {} without validating its magic bytes, while other input paths compare against the magic constants {}",
+            input_call.sub.term.name,
+            input_call.input_symbol.name,
+            input_call.call_tid.address,
+            magic_constants.join(", ")
+        ),
+    )
+    .tids(vec![
+        format!("{}", input_call.call_tid),
+        format!("{}", dispatch),
+    ])
+    .addresses(vec![
+        input_call.call_tid.address.clone(),
+        dispatch.address.clone(),
+    ])
+    .symbols(vec![input_call.sub.term.name.clone()])
+    .other(vec![magic_constants
+        .iter()
+        .map(|magic_constant| magic_constant.to_string())
+        .collect()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::{bitvec, def, expr};
+
+    fn mock_project(subs: Vec<Term<Sub>>) -> Project {
+        mock_x64_project(&["read"], subs)
+    }
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "input_symbols": {"read": {"buffer": 1, "length": 2}},
+            "comparison_symbols": ["memcmp"],
+            "min_magic_bytes": 2
+        })
+    }
+
+    /// Create a parser function that reads a header into a stack buffer and dispatches on its version byte.
+    /// If `magic` is given, the first four bytes of the header are compared with it before the dispatch.
+    fn mock_parser(name: &str, magic: Option<&str>) -> Term<Sub> {
+        let mut read = Blk::mock_with_tid(&format!("{name}_read"));
+        read.term.defs = vec![
+            def![format!("{name}_buffer: RSI:8 = RSP:8 + 0x10:8")],
+            def![format!("{name}_length: RDX:8 = 0x20:8")],
+        ];
+        let dispatch_block = format!("{name}_dispatch");
+        let after_read = if magic.is_some() {
+            format!("{name}_check_magic")
+        } else {
+            dispatch_block.clone()
+        };
+        read.term.jmps = vec![Jmp::call(
+            &format!("{name}_call_read"),
+            "read",
+            Some(&after_read),
+        )];
+        let mut blocks = vec![read];
+        if let Some(magic) = magic {
+            let mut check_magic = Blk::mock_with_tid(&after_read);
+            check_magic.term.defs = vec![
+                def![format!(
+                    "{name}_load_magic: EAX:4 := Load from RSP:8 + 0x10:8"
+                )],
+                def![format!("{name}_sub_magic: EAX:4 = EAX:4 - {magic}:4")],
+                def![format!("{name}_zf_magic: ZF:1 = EAX:4 == 0x0:4")],
+            ];
+            check_magic.term.jmps = vec![
+                Term {
+                    tid: Tid::new(format!("{name}_branch_magic")),
+                    term: Jmp::CBranch {
+                        target: Tid::new(format!("{name}_exit")),
+                        condition: expr!("ZF:1"),
+                    },
+                },
+                Jmp::branch(&format!("{name}_magic_ok"), &dispatch_block),
+            ];
+            blocks.push(check_magic);
+        }
+        let mut dispatch = Blk::mock_with_tid(&dispatch_block);
+        dispatch.term.defs = vec![
+            def![format!(
+                "{name}_load_version: RAX:1 := Load from RSP:8 + 0x14:8"
+            )],
+            def![format!("{name}_compare: ZF:1 = RAX:1 == 0x2:1")],
+        ];
+        dispatch.term.jmps = vec![
+            Term {
+                tid: Tid::new(format!("{name}_branch")),
+                term: Jmp::CBranch {
+                    target: Tid::new(format!("{name}_exit")),
+                    condition: expr!("ZF:1"),
+                },
+            },
+            Jmp::branch(&format!("{name}_fallthrough"), &format!("{name}_exit")),
+        ];
+        let mut exit = Blk::mock_with_tid(&format!("{name}_exit"));
+        exit.term.jmps = vec![Term {
+            tid: Tid::new(format!("{name}_return")),
+            term: Jmp::Return(expr!("0x0:8")),
+        }];
+        blocks.append(&mut vec![dispatch, exit]);
+        let mut sub = Sub::mock(name);
+        sub.term.blocks = blocks;
+        sub
+    }
+
+    #[test]
+    fn missing_magic_check() {
+        let warnings = run_check(
+            check_cwe,
+            &mock_project(vec![
+                mock_parser("validating", Some("0x464c457f")),
+                mock_parser("unvalidating", None),
+            ]),
+            &mock_config(),
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].symbols, vec!["unvalidating".to_string()]);
+        assert_eq!(
+            warnings[0].tids,
+            vec![
+                "unvalidating_call_read".to_string(),
+                "unvalidating_branch".to_string()
+            ]
+        );
+        assert_eq!(warnings[0].other, vec![vec!["0x464c457f".to_string()]]);
+    }
+
+    #[test]
+    fn no_magic_constant_in_binary() {
+        assert!(run_check(
+            check_cwe,
+            &mock_project(vec![mock_parser("unvalidating", None)]),
+            &mock_config()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn small_constants_are_no_magic_constants() {
+        assert!(run_check(
+            check_cwe,
+            &mock_project(vec![
+                mock_parser("version_check", Some("0x3")),
+                mock_parser("unvalidating", None),
+            ]),
+            &mock_config()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn magic_constant() {
+        assert_eq!(
+            as_magic_constant(&bitvec!("0x464c457f:4"), 2),
+            Some("0x464c457f".to_string())
+        );
+        assert_eq!(as_magic_constant(&bitvec!("0xff:4"), 2), None);
+        assert_eq!(
+            as_magic_constant(&bitvec!("0x100:2"), 2),
+            Some("0x100".to_string())
+        );
+        assert_eq!(as_magic_constant(&bitvec!("0x100:2"), 3), None);
+    }
+}
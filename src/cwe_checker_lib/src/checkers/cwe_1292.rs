@@ -0,0 +1,487 @@
+//! This module implements a check for CWE-1292: unvalidated use of configuration data
+//! that was loaded from external storage.
+//!
+//! Embedded devices often persist their configuration as binary blobs on flash memory
+//! or in files. Such blobs frequently contain offsets, pointers or lengths of embedded records.
+//! If the configuration storage can be modified by an attacker
+//! (e.g. by a configuration upload or by physical access to the flash chip)
+//! and the firmware trusts the embedded offsets and lengths when parsing the blob,
+//! the attacker gains arbitrary read, write or copy primitives.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1292.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! For each call to a function reading data from storage into a buffer (e.g. `read` or `fread`)
+//! we mark the read part of the buffer as tainted.
+//! Each value loaded from the tainted part of the buffer is tainted, regardless of its size.
+//! A taint analysis then follows the loaded values through the function and emits a warning if
+//! - a tainted value is used as (part of) the address of a memory access,
+//!   i.e. an embedded offset or pointer is dereferenced, or
+//! - a tainted value is used as the length parameter of a copy function (e.g. `memcpy`).
+//!
+//! The warning contains both the call reading the configuration and the trusting use.
+//!
+//! ## False Positives
+//!
+//! - Embedded offsets and lengths may have been validated (e.g. by a bounds check or a checksum)
+//!   before they are used. Such validations are not recognized by the check.
+//! - The read functions are also used to read data from other sources than configuration storage.
+//!
+//! ## False Negatives
+//!
+//! - The taint analysis is intraprocedural.
+//!   Configuration blobs parsed in other functions than the one reading them are not checked.
+//! - If the size of the read data is not known, only a prefix of the buffer
+//!   of configurable size is tainted.
+//! - If the buffer is not an exactly known memory object, nothing is tainted.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The functions reading configuration data (together with the indices of their buffer and size parameters)
+//! and the copy functions (together with the index of their length parameter)
+//! are configurable in config.json.
+
+use crate::abstract_domain::{IntervalDomain, SizedDomain, TryToBitvec};
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, HasCfg};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{
+    Data as PiData, PointerInference as PointerInferenceComputation,
+};
+use crate::analysis::taint::{
+    state::State as TaState, summary::TaintSummaries, Taint, TaintAnalysis,
+};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1292",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The parameters of a function reading configuration data into a buffer.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SourceParameters {
+    /// The index of the parameter pointing to the buffer.
+    buffer: usize,
+    /// The index of the parameter containing the number of read bytes, if there is one.
+    size: Option<usize>,
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions reading configuration data from storage into a buffer.
+    sources: BTreeMap<String, SourceParameters>,
+    /// Copy functions mapped to the index of their length parameter.
+    copy_symbols: BTreeMap<String, usize>,
+    /// The maximal number of bytes of a buffer that get tainted.
+    /// Also used for reads where the number of read bytes is not known.
+    max_tainted_bytes: u64,
+}
+
+/// The kind of trusting use of a tainted configuration value.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum TrustingUse {
+    /// The value is used as the address of a memory access.
+    Dereference,
+    /// The value is used as the length parameter of the copy function with the given name.
+    CopyLength(String),
+}
+
+/// Run the check.
+///
+/// For each call to a source function, a separate taint analysis is started
+/// at the call, so that warnings can be attributed to the read of the configuration data.
+/// For each trusting use only the first source reaching it is reported.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1292.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+    let graph = analysis_results.control_flow_graph;
+
+    let source_names: Vec<String> = config.sources.keys().cloned().collect();
+    let source_map = get_symbol_map(project, &source_names);
+    let copy_names: Vec<String> = config.copy_symbols.keys().cloned().collect();
+    let copy_symbols: HashMap<Tid, usize> = get_symbol_map(project, &copy_names)
+        .into_iter()
+        .map(|(tid, symbol)| (tid, config.copy_symbols[&symbol.name]))
+        .collect();
+    // The examined sites are the calls to source functions.
+    let mut site_counts = SiteCounts::default();
+    if source_map.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut trusting_uses: BTreeMap<Tid, (Tid, String, TrustingUse)> = BTreeMap::new();
+    for edge in graph.edge_references() {
+        let Edge::ExternCallStub(jmp) = edge.weight() else {
+            continue;
+        };
+        let Jmp::Call { target, .. } = &jmp.term else {
+            continue;
+        };
+        let Some(source) = source_map.get(target) else {
+            continue;
+        };
+        let state = match generate_source_state(
+            source,
+            &config.sources[&source.name],
+            &jmp.tid,
+            pi_result,
+            config.max_tainted_bytes,
+        ) {
+            Some(state) => state,
+            None => {
+                site_counts.add_skipped();
+                continue;
+            }
+        };
+
+        let (cwe_sender, cwe_receiver) = crossbeam_channel::unbounded();
+        let context = Context {
+            project,
+            pi_result,
+            control_flow_graph: graph,
+            copy_symbols: copy_symbols.clone(),
+            taint_summaries: analysis_results.taint_summaries,
+            cwe_collector: cwe_sender,
+        };
+        let mut computation = create_computation(context, None);
+        computation.set_cancellation_token(analysis_results.cancellation_token);
+        computation.set_node_value(edge.target(), NodeValue::Value(state));
+        computation.compute_with_max_steps(100);
+
+        let mut is_flagged = false;
+        for (use_tid, trusting_use) in cwe_receiver.try_iter() {
+            is_flagged = true;
+            trusting_uses
+                .entry(use_tid)
+                .or_insert_with(|| (jmp.tid.clone(), source.name.clone(), trusting_use));
+        }
+        if is_flagged {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            let use_tids = blk
+                .term
+                .defs
+                .iter()
+                .map(|def| &def.tid)
+                .chain(blk.term.jmps.iter().map(|jmp| &jmp.tid));
+            for use_tid in use_tids {
+                if let Some((source_tid, source_name, trusting_use)) = trusting_uses.get(use_tid) {
+                    cwe_warnings.push(generate_cwe_warning(
+                        sub,
+                        source_tid,
+                        source_name,
+                        use_tid,
+                        trusting_use,
+                    ));
+                }
+            }
+        }
+    }
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Generate the taint state after a call to a source function.
+///
+/// Each byte of the read part of the buffer gets tainted separately,
+/// so that loads of arbitrary size from the buffer can be recognized as tainted.
+/// Returns `None` if the buffer is not known.
+fn generate_source_state(
+    source: &ExternSymbol,
+    parameters: &SourceParameters,
+    call_tid: &Tid,
+    pi_result: &PointerInferenceComputation,
+    max_tainted_bytes: u64,
+) -> Option<TaState> {
+    let buffer = pi_result
+        .eval_parameter_arg_at_call(call_tid, source.parameters.get(parameters.buffer)?)?;
+    let size = parameters
+        .size
+        .and_then(|index| source.parameters.get(index))
+        .and_then(|param| pi_result.eval_parameter_arg_at_call(call_tid, param))
+        .and_then(|size| size.get_if_absolute_value()?.try_to_bitvec().ok())
+        .and_then(|size| size.try_to_u64().ok())
+        .map(|size| std::cmp::min(size, max_tainted_bytes))
+        .unwrap_or(max_tainted_bytes);
+
+    let mut state = TaState::new_empty();
+    for offset in 0..size {
+        let offset = Bitvector::from_u64(offset).into_resize_unsigned(buffer.bytesize());
+        state.save_taint_to_memory(
+            &buffer.add_offset(&IntervalDomain::from(offset)),
+            Taint::Tainted(ByteSize::new(1)),
+        );
+    }
+    if state.is_empty() {
+        None
+    } else {
+        Some(state)
+    }
+}
+
+/// The Context struct for the taint analysis.
+struct Context<'a> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInferenceComputation<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// The TIDs of the copy functions mapped to the index of their length parameter.
+    copy_symbols: HashMap<Tid, usize>,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where the TIDs of trusting uses of tainted values are sent to.
+    cwe_collector: crossbeam_channel::Sender<(Tid, TrustingUse)>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> Context<'a> {
+    /// Check whether the given parameter of the call is tainted.
+    fn is_parameter_tainted(&self, state: &TaState, call_tid: &Tid, parameter: &Arg) -> bool {
+        match parameter {
+            Arg::Register { expr, .. } => state.eval(expr).is_tainted(),
+            Arg::Stack { address, size, .. } => self
+                .pi_result
+                .eval_at_jmp(call_tid, address)
+                .is_some_and(|address| state.load_taint_from_memory(&address, *size).is_tainted()),
+        }
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
+    /// Taint the target register if any byte of the loaded value is tainted.
+    fn update_def_load(
+        &self,
+        state: &TaState,
+        tid: &Tid,
+        var: &Variable,
+        _address: &Expression,
+    ) -> TaState {
+        let mut new_state = state.clone();
+        let taint = match self.pi_result.eval_address_at_def(tid) {
+            Some(address) => {
+                let is_any_byte_tainted = (0..u64::from(var.size)).any(|offset| {
+                    let offset =
+                        Bitvector::from_u64(offset).into_resize_unsigned(address.bytesize());
+                    state
+                        .load_taint_from_memory(
+                            &address.add_offset(&IntervalDomain::from(offset)),
+                            ByteSize::new(1),
+                        )
+                        .is_tainted()
+                });
+                if is_any_byte_tainted {
+                    Taint::Tainted(var.size)
+                } else {
+                    state.load_taint_from_memory(&address, var.size)
+                }
+            }
+            None => Taint::Top(var.size),
+        };
+        new_state.set_register_taint(var, taint);
+        new_state
+    }
+
+    /// Report memory accesses where the address is tainted.
+    fn update_def_post(
+        &self,
+        old_state: &TaState,
+        new_state: TaState,
+        def: &Term<Def>,
+    ) -> Option<TaState> {
+        if let Def::Load { address, .. } | Def::Store { address, .. } = &def.term {
+            if old_state.eval(address).is_tainted() {
+                let _ = self
+                    .cwe_collector
+                    .send((def.tid.clone(), TrustingUse::Dereference));
+            }
+        }
+        if new_state.is_empty() {
+            self.handle_empty_state_out(&def.tid)
+        } else {
+            Some(new_state)
+        }
+    }
+
+    /// Report calls to copy functions where the length parameter is tainted.
+    fn update_extern_call(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        project: &Project,
+        extern_symbol: &ExternSymbol,
+    ) -> Option<TaState> {
+        if let Some(length_index) = self.copy_symbols.get(&extern_symbol.tid) {
+            if let Some(length) = extern_symbol.parameters.get(*length_index) {
+                if self.is_parameter_tainted(state, &call.tid, length) {
+                    let _ = self.cwe_collector.send((
+                        call.tid.clone(),
+                        TrustingUse::CopyLength(extern_symbol.name.clone()),
+                    ));
+                }
+            }
+        }
+        let calling_conv = project.get_calling_convention(extern_symbol);
+        let mut new_state = state.clone();
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        if let Some(summary) = self
+            .taint_summaries
+            .and_then(|summaries| summaries.get(&extern_symbol.name))
+        {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.pi_result,
+                &call.tid,
+                calling_conv,
+            );
+        }
+        Some(new_state)
+    }
+}
+
+/// Generate the CWE warning for a trusting use of configuration data read by the given source call.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    source_tid: &Tid,
+    source_name: &str,
+    use_tid: &Tid,
+    trusting_use: &TrustingUse,
+) -> CweWarning {
+    let use_description = match trusting_use {
+        TrustingUse::Dereference => "used as a memory address".to_string(),
+        TrustingUse::CopyLength(copy_name) => format!("used as the length of {copy_name}"),
+    };
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Unvalidated Configuration Data) Data read by {} at {} is {} without validation at {} ({})",
+            source_name, source_tid.address, use_description, use_tid.address, sub.term.name
+        ),
+    )
+    .tids(vec![format!("{use_tid}"), format!("{source_tid}")])
+    .addresses(vec![use_tid.address.clone(), source_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec!["source".to_string(), source_name.to_string()]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::def;
+
+    /// Create a project with a function reading 16 bytes into a stack buffer,
+    /// dereferencing a pointer loaded from the buffer
+    /// and using a length loaded from the buffer for a call to `memcpy`.
+    /// Note that the stack pointer is increased by 8 by the call to `read`.
+    fn mock_project() -> Project {
+        let mut blk_read = Blk::mock_with_tid("blk_read");
+        blk_read.term.defs = vec![
+            def!["buffer: RSI:8 = RSP:8 + 0xffffffffffffffc0:8"],
+            def!["size: RDX:8 = 0x10:8"],
+        ];
+        blk_read
+            .term
+            .jmps
+            .push(Jmp::call("call_read", "read", Some("blk_parse")));
+        let mut blk_parse = Blk::mock_with_tid("blk_parse");
+        blk_parse.term.defs = vec![
+            def!["load_pointer: RAX:8 := Load from RSP:8 + 0xffffffffffffffb8:8"],
+            def!["deref: RBX:8 := Load from RAX:8"],
+            def!["load_length: RDX:8 := Load from RSP:8 + 0xffffffffffffffc4:8"],
+            def!["untainted: RCX:8 := Load from RSP:8 + 0xffffffffffffffd0:8"],
+            def!["no_deref: RBX:8 := Load from RCX:8"],
+        ];
+        blk_parse
+            .term
+            .jmps
+            .push(Jmp::call("call_memcpy", "memcpy", Some("blk_end")));
+        let blk_end = Blk::mock_with_tid("blk_end");
+
+        let mut sub = Sub::mock("parse_config");
+        sub.term.blocks = vec![blk_read, blk_parse, blk_end];
+        let mut project = mock_x64_project(&["read", "memcpy"], vec![sub]);
+        for name in ["read", "memcpy"] {
+            let symbol = project
+                .program
+                .term
+                .extern_symbols
+                .get_mut(&Tid::new(name))
+                .unwrap();
+            symbol.parameters = vec![
+                Arg::mock_register("RDI", 8),
+                Arg::mock_register("RSI", 8),
+                Arg::mock_register("RDX", 8),
+            ];
+        }
+        project
+    }
+
+    #[test]
+    fn unvalidated_configuration_data() {
+        let project = mock_project();
+        let config = serde_json::json!({
+            "sources": {"read": {"buffer": 1, "size": 2}},
+            "copy_symbols": {"memcpy": 2},
+            "max_tainted_bytes": 64,
+        });
+
+        let warnings = run_check(check_cwe, &project, &config);
+        let use_tids: Vec<&String> = warnings.iter().map(|warning| &warning.tids[0]).collect();
+        assert_eq!(use_tids, vec!["deref", "call_memcpy"]);
+        assert!(warnings
+            .iter()
+            .all(|warning| warning.tids[1] == "call_read"));
+    }
+}
@@ -0,0 +1,442 @@
+//! This module implements a check for CWE-1325: Improperly Controlled Sequential Memory Allocation.
+//!
+//! Log line builders or CSV emitters often accumulate a string in a fixed stack buffer
+//! by appending to it in a loop, e.g. with `strcat(line, field)` or `sprintf(line, "%s,%d", line, value)`.
+//! Each single call may look bounded, but if the accumulated length is not tracked,
+//! the total amount of data written into the buffer is only limited by the number of loop iterations.
+//! The buffer overflow checks analyzing each call in isolation usually miss this pattern.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1325.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! We search for loops in the intraprocedural control flow graph of each function.
+//! For each call to an accumulating function (e.g. `strcat` or `sprintf`) in a loop
+//! we use the [Pointer Inference analysis](`crate::analysis::pointer_inference`)
+//! to check whether the destination is the same fixed buffer on the stack frame of the function in every iteration.
+//! For functions with a format string the call only accumulates
+//! if the destination buffer is also passed as one of the formatted arguments.
+//!
+//! The accumulated length is considered tracked if the loop computes the length of the buffer
+//! with a length function (e.g. `strlen`).
+//! If the [String Abstraction analysis](`crate::analysis::string_abstraction`) was computed,
+//! it also has to show that the length of the string in the buffer at the call is unbounded,
+//! i.e. that the string grows with each loop iteration.
+//! Otherwise we report the call.
+//!
+//! ## False Positives
+//!
+//! - The number of loop iterations may be limited to a value small enough for the buffer.
+//! - The accumulated length may be tracked by other means than a length function,
+//!   e.g. by summing up the return values of `sprintf`.
+//!
+//! ## False Negatives
+//!
+//! - Buffers that are not on the stack frame of the function containing the loop are not checked.
+//! - Appending at a moving position (e.g. `sprintf(line + strlen(line), ...)`) is not detected.
+//! - A loop is considered length-tracking if it computes the length of the buffer,
+//!   even if the length is never compared to the buffer size.
+//! - Loops spanning several functions are not detected.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The accumulating functions together with the index of their destination parameter
+//! and optionally the index of their format string parameter
+//! as well as the length functions are configurable in config.json.
+
+use crate::abstract_domain::{AbstractIdentifier, BricksDomain, TryToBitvec};
+use crate::analysis::graph::{Edge, Graph, Node};
+use crate::analysis::pointer_inference::{Data, PointerInference};
+use crate::analysis::string_abstraction::context::Context as StringAbstractionContext;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::arguments::get_variable_parameters;
+use crate::utils::graph_utils::get_extern_call_nodes;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeFiltered;
+use std::collections::{BTreeMap, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1325",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The parameters of an accumulating function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+struct AccumulatingParameters {
+    /// The index of the destination buffer parameter.
+    destination: usize,
+    /// The index of the format string parameter, if the function has one.
+    #[serde(default)]
+    format: Option<usize>,
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions that write into a destination buffer, mapped to the indices of their parameters.
+    accumulating_symbols: BTreeMap<String, AccumulatingParameters>,
+    /// Functions computing the length of a string given as their first parameter.
+    length_symbols: Vec<String>,
+}
+
+/// A call in a loop that accumulates a string in a fixed stack buffer.
+struct AccumulatingCall<'a> {
+    /// The function containing the loop.
+    sub: &'a Term<Sub>,
+    /// The call instruction.
+    jmp: &'a Term<Jmp>,
+    /// The called accumulating function.
+    symbol: &'a ExternSymbol,
+    /// The destination buffer parameter.
+    destination_arg: Arg,
+    /// The pointer to the destination buffer.
+    destination: Data,
+}
+
+/// Return the pointer to the destination buffer of a call
+/// if it points to a fixed offset on the stack frame of the calling function.
+fn get_stack_buffer_destination(
+    pi_result: &PointerInference,
+    call_tid: &Tid,
+    arg: &Arg,
+    stack_id: &AbstractIdentifier,
+) -> Option<Data> {
+    let destination = pi_result.eval_parameter_arg_at_call(call_tid, arg)?;
+    let (target, offset) = destination.get_if_unique_target()?;
+    if target == stack_id && offset.try_to_offset().is_ok() {
+        Some(destination)
+    } else {
+        None
+    }
+}
+
+/// Check whether the destination buffer is also passed as one of the formatted arguments of the call,
+/// i.e. whether the call appends to the current content of the buffer.
+fn destination_is_formatted_argument(
+    project: &Project,
+    pi_result: &PointerInference,
+    jmp: &Term<Jmp>,
+    symbol: &ExternSymbol,
+    format_index: usize,
+    destination: &Data,
+) -> bool {
+    let Some(pi_state) = pi_result.get_state_at_jmp_tid(&jmp.tid) else {
+        return false;
+    };
+    let format_string_index_map = HashMap::from([(symbol.name.clone(), format_index)]);
+    let Ok(arguments) =
+        get_variable_parameters(project, pi_state, symbol, &format_string_index_map)
+    else {
+        return false;
+    };
+    arguments.iter().any(|arg| {
+        pi_result.eval_parameter_arg_at_call(&jmp.tid, arg).as_ref() == Some(destination)
+    })
+}
+
+/// Check whether the string abstraction shows that the length of the string in the destination buffer
+/// is bounded at the call.
+///
+/// Returns `false` if the string abstraction was not computed or does not track the buffer.
+fn has_bounded_length(
+    analysis_results: &AnalysisResults,
+    call_node: Option<NodeIndex>,
+    arg: &Arg,
+) -> bool {
+    let (
+        Some(string_abstraction),
+        Some(pointer_inference),
+        Some(call_node),
+        Arg::Register {
+            expr: Expression::Var(var),
+            ..
+        },
+    ) = (
+        analysis_results.string_abstraction,
+        analysis_results.pointer_inference,
+        call_node,
+        arg,
+    )
+    else {
+        return false;
+    };
+    let (Some(string_state), Some(pi_state)) = (
+        string_abstraction.get_node_value(call_node),
+        pointer_inference.get_node_value(call_node),
+    ) else {
+        return false;
+    };
+    let string_state = string_state.unwrap_value();
+    let Some(pointer) = string_state.get_variable_to_pointer_map().get(var) else {
+        return false;
+    };
+    if pointer.get_relative_values().is_empty() {
+        return false;
+    }
+    StringAbstractionContext::<BricksDomain>::merge_domains_from_multiple_pointer_targets(
+        string_state,
+        pi_state.unwrap_value(),
+        pointer.get_relative_values(),
+    )
+    .get_max_length()
+    .is_some()
+}
+
+/// Find all calls to accumulating functions in loops
+/// whose destination is a fixed buffer on the stack frame of the calling function
+/// and whose loop does not compute the length of the buffer.
+///
+/// The loops are the non-trivial strongly connected components of the intraprocedural control flow graph.
+fn find_accumulating_calls<'a>(
+    project: &'a Project,
+    graph: &Graph<'a>,
+    pi_result: &PointerInference,
+    accumulating_symbols: &HashMap<Tid, (&'a ExternSymbol, AccumulatingParameters)>,
+    length_symbols: &HashMap<Tid, &'a ExternSymbol>,
+) -> Vec<AccumulatingCall<'a>> {
+    let intraprocedural_graph = EdgeFiltered::from_fn(graph, |edge| {
+        !matches!(edge.weight(), Edge::Call(_) | Edge::CrReturnStub)
+    });
+    let mut accumulating_calls = Vec::new();
+    for component in petgraph::algo::kosaraju_scc(&intraprocedural_graph) {
+        // Every loop contains at least the start and the end node of a block.
+        if component.len() < 2 {
+            continue;
+        }
+        let mut loop_calls = Vec::new();
+        let mut measured_buffers = Vec::new();
+        for node in component {
+            let Node::BlkEnd(block, sub) = graph[node] else {
+                continue;
+            };
+            let stack_id =
+                AbstractIdentifier::from_var(sub.tid.clone(), &project.stack_pointer_register);
+            for jmp in block.term.jmps.iter() {
+                let Jmp::Call { target, .. } = &jmp.term else {
+                    continue;
+                };
+                if let Some(symbol) = length_symbols.get(target) {
+                    if let Some(buffer) = project.get_parameter_arg(symbol, 0).and_then(|arg| {
+                        get_stack_buffer_destination(pi_result, &jmp.tid, &arg, &stack_id)
+                    }) {
+                        measured_buffers.push(buffer);
+                    }
+                }
+                let Some((symbol, parameters)) = accumulating_symbols.get(target) else {
+                    continue;
+                };
+                let Some(destination_arg) =
+                    project.get_parameter_arg(symbol, parameters.destination)
+                else {
+                    continue;
+                };
+                let Some(destination) =
+                    get_stack_buffer_destination(pi_result, &jmp.tid, &destination_arg, &stack_id)
+                else {
+                    continue;
+                };
+                if let Some(format_index) = parameters.format {
+                    if !destination_is_formatted_argument(
+                        project,
+                        pi_result,
+                        jmp,
+                        symbol,
+                        format_index,
+                        &destination,
+                    ) {
+                        continue;
+                    }
+                }
+                loop_calls.push(AccumulatingCall {
+                    sub,
+                    jmp,
+                    symbol,
+                    destination_arg,
+                    destination,
+                });
+            }
+        }
+        accumulating_calls.extend(
+            loop_calls
+                .into_iter()
+                .filter(|call| !measured_buffers.contains(&call.destination)),
+        );
+    }
+    accumulating_calls
+}
+
+/// Generate the CWE warning for a call accumulating a string in a stack buffer without tracking its length.
+fn generate_cwe_warning(call: &AccumulatingCall) -> CweWarning {
+    let offset = call
+        .destination
+        .get_if_unique_target()
+        .and_then(|(_, offset)| offset.try_to_offset().ok())
+        .unwrap_or_default();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Unbounded String Accumulation) The loop in function {} repeatedly appends to the stack buffer at offset {} with {} at {} without tracking the accumulated length",
+            call.sub.term.name, offset, call.symbol.name, call.jmp.tid.address
+        ),
+    )
+    .tids(vec![format!("{}", call.jmp.tid)])
+    .addresses(vec![call.jmp.tid.address.clone()])
+    .symbols(vec![call.sub.term.name.clone()])
+    .other(vec![vec![
+        "stack_offset".to_string(),
+        format!("{offset}"),
+    ]])
+}
+
+/// Run the check.
+///
+/// For each call in a loop that appends to a fixed stack buffer we check
+/// whether the loop tracks the accumulated length of the string in the buffer.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let graph = analysis_results.control_flow_graph;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1325.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let symbol_names: Vec<String> = config.accumulating_symbols.keys().cloned().collect();
+    let accumulating_symbols: HashMap<Tid, (&ExternSymbol, AccumulatingParameters)> =
+        get_symbol_map(project, &symbol_names)
+            .into_iter()
+            .map(|(tid, symbol)| (tid, (symbol, config.accumulating_symbols[&symbol.name])))
+            .collect();
+    let mut site_counts = SiteCounts::default();
+    if accumulating_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let length_symbols = get_symbol_map(project, &config.length_symbols);
+    let call_nodes = get_extern_call_nodes(graph);
+
+    let mut cwe_warnings: Vec<CweWarning> = Vec::new();
+    for call in find_accumulating_calls(
+        project,
+        graph,
+        pi_result,
+        &accumulating_symbols,
+        &length_symbols,
+    ) {
+        if has_bounded_length(
+            analysis_results,
+            call_nodes.get(&call.jmp.tid).copied(),
+            &call.destination_arg,
+        ) {
+            site_counts.add_safe();
+        } else {
+            site_counts.add_flagged();
+            cwe_warnings.push(generate_cwe_warning(&call));
+        }
+    }
+    cwe_warnings.sort();
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::def;
+
+    /// Create a project with a function `build_line` that calls `strcat` on a stack buffer in a loop.
+    /// If `with_strlen` is set, the loop also computes the length of the buffer.
+    /// If `in_loop` is not set, the back edge of the loop is removed.
+    fn mock_project(with_strlen: bool, in_loop: bool) -> Project {
+        let mut blk_entry = Blk::mock_with_tid("blk_entry");
+        blk_entry
+            .term
+            .jmps
+            .push(Jmp::branch("jump_loop", "blk_loop"));
+        let mut blk_loop = Blk::mock_with_tid("blk_loop");
+        blk_loop.term.defs = vec![
+            def!["buffer: RDI:8 = RSP:8 + 0xffffffffffffffb8:8"],
+            def!["field: RSI:8 = 0x3000:8"],
+            def!["push_return: RSP:8 = RSP:8 + 0xfffffffffffffff8:8"],
+        ];
+        let strcat_return = if with_strlen {
+            "blk_length"
+        } else {
+            "blk_next"
+        };
+        blk_loop
+            .term
+            .jmps
+            .push(Jmp::call("call_strcat", "strcat", Some(strcat_return)));
+        let mut blk_length = Blk::mock_with_tid("blk_length");
+        blk_length.term.defs = vec![
+            def!["buffer_2: RDI:8 = RSP:8 + 0xffffffffffffffb8:8"],
+            def!["push_return_2: RSP:8 = RSP:8 + 0xfffffffffffffff8:8"],
+        ];
+        blk_length
+            .term
+            .jmps
+            .push(Jmp::call("call_strlen", "strlen", Some("blk_next")));
+        let mut blk_next = Blk::mock_with_tid("blk_next");
+        if in_loop {
+            blk_next
+                .term
+                .jmps
+                .push(Jmp::branch("jump_back", "blk_loop"));
+        }
+
+        let mut sub = Sub::mock("build_line");
+        sub.term.blocks = vec![blk_entry, blk_loop];
+        if with_strlen {
+            sub.term.blocks.push(blk_length);
+        }
+        sub.term.blocks.push(blk_next);
+        mock_x64_project(&["strcat", "strlen"], vec![sub])
+    }
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "accumulating_symbols": {
+                "strcat": {"destination": 0},
+                "sprintf": {"destination": 0, "format": 1}
+            },
+            "length_symbols": ["strlen"]
+        })
+    }
+
+    #[test]
+    fn unbounded_accumulation_in_loop() {
+        let warnings = run_check(check_cwe, &mock_project(false, true), &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["call_strcat".to_string()]);
+        assert_eq!(warnings[0].symbols, vec!["build_line".to_string()]);
+        assert_eq!(
+            warnings[0].other,
+            vec![vec!["stack_offset".to_string(), "-72".to_string()]]
+        );
+    }
+
+    #[test]
+    fn tracked_length_in_loop() {
+        let warnings = run_check(check_cwe, &mock_project(true, true), &mock_config());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn accumulation_outside_of_loop() {
+        let warnings = run_check(check_cwe, &mock_project(false, false), &mock_config());
+        assert!(warnings.is_empty());
+    }
+}
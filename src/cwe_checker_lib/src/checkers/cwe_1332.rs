@@ -0,0 +1,467 @@
+//! This module implements a check for CWE-1332: Improper Handling of Faults that Lead to Instruction Skips.
+//!
+//! Fault injection attacks (e.g. voltage or clock glitching) can make a processor skip single instructions.
+//! If the result of a security-critical verification, like the signature check of a secure boot loader,
+//! is only checked by a single conditional branch, skipping this branch bypasses the verification.
+//! Hardened code therefore checks such results redundantly, e.g. a second time on every path
+//! or with a complementary condition.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1332.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! This is an informational check meant for fault injection resistance reviews of bare-metal code.
+//! For each call to one of the configured verification functions
+//! we follow the return value through the registers and memory locations of the calling function.
+//! A warning is generated if exactly one conditional branch in the calling function depends on the return value.
+//!
+//! The return value is assumed to be handled by hardened code if it is returned to the caller
+//! or passed as a parameter to another function, so that no warning is generated in these cases.
+//! If a return value is not checked at all, no warning is generated either,
+//! since this is reported by the check for CWE-252.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The names of the verification functions are configurable in config.json.
+//! They may be extern symbols or functions of the binary.
+//! Optionally the check can be restricted to calls in the functions given by `checked_functions`.
+//!
+//! ## False Positives
+//!
+//! - Verification results that are checked redundantly in a called function are only recognized
+//!   if the result is passed to the function in a register parameter.
+//! - Memory locations are identified by their address expressions.
+//!   If the result is stored to memory and reloaded through a different address expression,
+//!   the second check is not recognized.
+//!
+//! ## False Negatives
+//!
+//! - Two conditional branches depending on the return value are always assumed to be redundant checks,
+//!   even if they are located on different paths.
+//! - Other fault injection targets, like loop counters or the instructions storing the verification result,
+//!   are not checked.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::CweModule;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1332",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The names of functions whose return value is the result of a security-critical verification.
+    verification_functions: Vec<String>,
+    /// Only calls in the functions with these names are checked.
+    /// If the list is empty, calls in all functions are checked.
+    checked_functions: Vec<String>,
+}
+
+/// The registers and memory locations containing the return value of a verification function.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct ResultLocations {
+    /// The registers containing the return value.
+    registers: BTreeSet<Variable>,
+    /// The address expressions of memory locations containing the return value.
+    memory: HashSet<Expression>,
+}
+
+impl ResultLocations {
+    /// Returns `true` if the return value is not contained in any location.
+    fn is_empty(&self) -> bool {
+        self.registers.is_empty() && self.memory.is_empty()
+    }
+
+    /// Returns `true` if the expression depends on the return value.
+    fn is_contained_in(&self, expression: &Expression) -> bool {
+        expression
+            .input_vars()
+            .into_iter()
+            .any(|var| self.registers.contains(var))
+    }
+
+    /// Add all locations of `other` to `self`.
+    /// Returns `true` if `self` changed.
+    fn merge(&mut self, other: &ResultLocations) -> bool {
+        let num_locations = self.registers.len() + self.memory.len();
+        self.registers.extend(other.registers.iter().cloned());
+        self.memory.extend(other.memory.iter().cloned());
+        self.registers.len() + self.memory.len() != num_locations
+    }
+
+    /// Update the locations according to the effects of the given `Def` term.
+    fn update_def(&mut self, def: &Def) {
+        match def {
+            Def::Assign { var, value } => {
+                if self.is_contained_in(value) {
+                    self.registers.insert(var.clone());
+                } else {
+                    self.registers.remove(var);
+                }
+            }
+            Def::Load { var, address } => {
+                if self.memory.contains(address) {
+                    self.registers.insert(var.clone());
+                } else {
+                    self.registers.remove(var);
+                }
+            }
+            Def::Store { address, value } => {
+                if self.is_contained_in(value) {
+                    self.memory.insert(address.clone());
+                } else {
+                    self.memory.remove(address);
+                }
+            }
+        }
+    }
+}
+
+/// The ways in which the return value of a verification function is used in the calling function.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct ResultUsage {
+    /// The TIDs of the conditional branches depending on the return value.
+    conditional_branches: BTreeSet<Tid>,
+    /// Is set to `true` if the return value is returned to the caller or passed to another function.
+    is_passed_on: bool,
+}
+
+/// Run the check.
+///
+/// For each call to a verification function we compute the usage of its return value in the calling function
+/// and report return values that are checked by exactly one conditional branch.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1332.");
+    let verification_functions = get_verification_functions(project, &config);
+    // The examined sites are the calls to verification functions.
+    let mut site_counts = SiteCounts::default();
+    if verification_functions.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        if !config.checked_functions.is_empty()
+            && !config.checked_functions.contains(&sub.term.name)
+        {
+            continue;
+        }
+        for blk in sub.term.blocks.iter() {
+            for jmp in blk.term.jmps.iter() {
+                let Jmp::Call {
+                    target,
+                    return_: Some(return_target),
+                } = &jmp.term
+                else {
+                    continue;
+                };
+                let Some((callee_name, return_registers)) = verification_functions.get(target)
+                else {
+                    continue;
+                };
+                let usage = compute_result_usage(
+                    project,
+                    sub,
+                    return_target,
+                    ResultLocations {
+                        registers: return_registers.clone(),
+                        memory: HashSet::new(),
+                    },
+                );
+                if !usage.is_passed_on && usage.conditional_branches.len() == 1 {
+                    site_counts.add_flagged();
+                    cwe_warnings.push(generate_cwe_warning(
+                        sub,
+                        &jmp.tid,
+                        callee_name,
+                        usage.conditional_branches.first().unwrap(),
+                    ));
+                } else {
+                    site_counts.add_safe();
+                }
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Map the TIDs of the configured verification functions to their names and their return registers.
+///
+/// The return registers of extern symbols are taken from their return values
+/// and the return registers of functions of the binary from the standard calling convention.
+fn get_verification_functions(
+    project: &Project,
+    config: &Config,
+) -> BTreeMap<Tid, (String, BTreeSet<Variable>)> {
+    let mut verification_functions = BTreeMap::new();
+    for symbol in project.program.term.extern_symbols.values() {
+        if config.verification_functions.contains(&symbol.name) {
+            let return_registers = symbol
+                .return_values
+                .iter()
+                .filter_map(|arg| match arg {
+                    Arg::Register { expr, .. } => Some(expr.input_vars()),
+                    Arg::Stack { .. } => None,
+                })
+                .flatten()
+                .cloned()
+                .collect();
+            verification_functions
+                .insert(symbol.tid.clone(), (symbol.name.clone(), return_registers));
+        }
+    }
+    if let Some(calling_convention) = project.get_standard_calling_convention() {
+        for sub in project.program.term.subs.values() {
+            if config.verification_functions.contains(&sub.term.name) {
+                let return_registers = calling_convention
+                    .integer_return_register
+                    .iter()
+                    .cloned()
+                    .collect();
+                verification_functions
+                    .insert(sub.tid.clone(), (sub.term.name.clone(), return_registers));
+            }
+        }
+    }
+    verification_functions
+}
+
+/// Follow the return value of a call through the calling function, starting at the return target of the call.
+///
+/// Other calls only preserve the locations in callee-saved registers and in memory.
+fn compute_result_usage(
+    project: &Project,
+    sub: &Term<Sub>,
+    start_block: &Tid,
+    start_locations: ResultLocations,
+) -> ResultUsage {
+    let blocks: HashMap<&Tid, &Term<Blk>> =
+        sub.term.blocks.iter().map(|blk| (&blk.tid, blk)).collect();
+    let calling_convention = project.get_standard_calling_convention();
+    let mut usage = ResultUsage::default();
+    let mut block_start_locations: HashMap<&Tid, ResultLocations> = HashMap::new();
+    let mut worklist = vec![(start_block, start_locations)];
+    while let Some((blk_tid, locations)) = worklist.pop() {
+        let Some(blk) = blocks.get(blk_tid) else {
+            continue;
+        };
+        let mut locations = match block_start_locations.entry(blk_tid) {
+            Entry::Occupied(mut entry) => {
+                if !entry.get_mut().merge(&locations) {
+                    continue;
+                }
+                entry.get().clone()
+            }
+            Entry::Vacant(entry) => entry.insert(locations).clone(),
+        };
+        for def in blk.term.defs.iter() {
+            locations.update_def(&def.term);
+        }
+        if locations.is_empty() {
+            continue;
+        }
+        for jmp in blk.term.jmps.iter() {
+            match &jmp.term {
+                Jmp::Branch(target) => worklist.push((target, locations.clone())),
+                Jmp::CBranch { target, condition } => {
+                    if locations.is_contained_in(condition) {
+                        usage.conditional_branches.insert(jmp.tid.clone());
+                    }
+                    worklist.push((target, locations.clone()));
+                }
+                Jmp::Call { target, return_ } => {
+                    let parameter_registers: Vec<&Variable> =
+                        match project.program.term.extern_symbols.get(target) {
+                            Some(symbol) => symbol
+                                .parameters
+                                .iter()
+                                .filter_map(|arg| match arg {
+                                    Arg::Register { expr, .. } => Some(expr.input_vars()),
+                                    Arg::Stack { .. } => None,
+                                })
+                                .flatten()
+                                .collect(),
+                            None => calling_convention
+                                .map(|cconv| cconv.integer_parameter_register.iter().collect())
+                                .unwrap_or_default(),
+                        };
+                    if parameter_registers
+                        .iter()
+                        .any(|register| locations.registers.contains(*register))
+                    {
+                        usage.is_passed_on = true;
+                    }
+                    if let Some(return_target) = return_ {
+                        worklist
+                            .push((return_target, get_locations_after_call(&locations, project)));
+                    }
+                }
+                Jmp::CallInd { return_, .. } | Jmp::CallOther { return_, .. } => {
+                    if let Some(return_target) = return_ {
+                        worklist
+                            .push((return_target, get_locations_after_call(&locations, project)));
+                    }
+                }
+                Jmp::Return(_) => {
+                    if let Some(calling_convention) = calling_convention {
+                        if calling_convention
+                            .integer_return_register
+                            .iter()
+                            .any(|register| locations.registers.contains(register))
+                        {
+                            usage.is_passed_on = true;
+                        }
+                    }
+                }
+                Jmp::BranchInd(_) => (),
+            }
+        }
+    }
+    usage
+}
+
+/// Get the locations still containing the return value after a call.
+/// Only callee-saved registers and memory locations are preserved.
+fn get_locations_after_call(locations: &ResultLocations, project: &Project) -> ResultLocations {
+    let registers = match project.get_standard_calling_convention() {
+        Some(calling_convention) => locations
+            .registers
+            .iter()
+            .filter(|register| calling_convention.callee_saved_register.contains(*register))
+            .cloned()
+            .collect(),
+        None => BTreeSet::new(),
+    };
+    ResultLocations {
+        registers,
+        memory: locations.memory.clone(),
+    }
+}
+
+/// Generate the CWE warning for a verification result that is only checked by a single conditional branch.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    call_tid: &Tid,
+    callee_name: &str,
+    branch_tid: &Tid,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Improper Handling of Faults that Lead to Instruction Skips) The result of the call to {} at {} ({}) is only checked by a single conditional branch at {}",
+            callee_name, call_tid.address, sub.term.name, branch_tid.address
+        ),
+    )
+    .tids(vec![format!("{call_tid}"), format!("{branch_tid}")])
+    .addresses(vec![call_tid.address.clone(), branch_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone(), callee_name.to_string()])
+    .other(vec![vec!["severity".to_string(), "info".to_string()]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::{defs, expr};
+
+    /// Create a function that calls `verify_signature` and checks the result with the given blocks.
+    fn mock_sub(name: &str, check_blocks: Vec<Term<Blk>>) -> Term<Sub> {
+        let return_tid = check_blocks[0].tid.to_string();
+        let mut call_blk = Blk::mock_with_tid(&format!("{name}_call_blk"));
+        call_blk.term.jmps = vec![Jmp::call(
+            &format!("{name}_call"),
+            "verify_signature",
+            Some(return_tid.as_str()),
+        )];
+        let mut sub = Sub::mock(name);
+        sub.term.blocks = vec![call_blk];
+        sub.term.blocks.extend(check_blocks);
+        sub
+    }
+
+    /// Create a block with the given `Def` terms, branching to `boot_failed` if the given flag is set.
+    fn mock_check_blk(tid: &str, defs: Vec<Term<Def>>, flag: &str, next: &str) -> Term<Blk> {
+        let mut blk = Blk::mock_with_tid(tid);
+        blk.term.defs = defs;
+        blk.term.jmps = vec![
+            Term {
+                tid: Tid::new(format!("{tid}_cbranch")),
+                term: Jmp::CBranch {
+                    target: Tid::new("boot_failed"),
+                    condition: expr!(flag),
+                },
+            },
+            Jmp::branch(&format!("{tid}_branch"), next),
+        ];
+        blk
+    }
+
+    #[test]
+    fn single_checks_of_verification_results() {
+        let single_check = mock_sub(
+            "boot",
+            vec![mock_check_blk(
+                "boot_check",
+                defs!["boot_cmp: ZF:1 = RAX:8 == 0x0:8"],
+                "ZF:1",
+                "boot_image",
+            )],
+        );
+        // The result is spilled to the stack and checked a second time after reloading it.
+        let redundant_check = mock_sub(
+            "hardened_boot",
+            vec![
+                mock_check_blk(
+                    "hardened_check",
+                    defs![
+                        "spill: Store at RBP:8 - 0x4:8 := RAX:8",
+                        "hardened_cmp: ZF:1 = RAX:8 == 0x0:8"
+                    ],
+                    "ZF:1",
+                    "hardened_recheck",
+                ),
+                mock_check_blk(
+                    "hardened_recheck",
+                    defs![
+                        "reload: RCX:8 := Load from RBP:8 - 0x4:8",
+                        "hardened_recmp: CF:1 = RCX:8 != 0x0:8"
+                    ],
+                    "CF:1",
+                    "boot_image",
+                ),
+            ],
+        );
+        let project = mock_x64_project(&["verify_signature"], vec![single_check, redundant_check]);
+        let mut config = Config {
+            verification_functions: vec!["verify_signature".to_string()],
+            checked_functions: Vec::new(),
+        };
+
+        let warnings = run_check(check_cwe, &project, &serde_json::to_value(&config).unwrap());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["boot_call", "boot_check_cbranch"]);
+        assert_eq!(warnings[0].other[0], vec!["severity", "info"]);
+
+        config.checked_functions = vec!["hardened_boot".to_string()];
+        let warnings = run_check(check_cwe, &project, &serde_json::to_value(&config).unwrap());
+        assert!(warnings.is_empty());
+    }
+}
@@ -11,16 +11,32 @@
 //! (e.g. sprintf). Then we check the content of the format string parameter and if it is not part of the global read only
 //! memory of the binary, a CWE warning is generated.
 //!
+//! Additionally, the user-controlled input returned by the configured input sources (e.g. `recv` or `getenv`)
+//! is tracked by an interprocedural taint analysis, see [`crate::analysis::taint::flow`].
+//! If the format string parameter of a call contains or points to tainted input,
+//! the warning names the input sources that the format string was derived from.
+//! Such warnings are generated regardless of the memory location of the format string.
+//!
 //! ### Symbols configurable in config.json
 //!
 //! - symbols that take a format string parameter.
+//! - `input_sources`: Functions returning user-controlled input.
+//!   For each function it is configured whether the input is returned (`ReturnValue`)
+//!   or written to the memory pointed to by the parameter with the given index (`{"ParameterTarget": index}`).
 //!
 //! ## False Positives
 //!
 //! - The input was externally provided on purpose and originates from a trusted source.
 //! - A pointer target could be lost but the format string was not externally provided.
+//!
+//! ## False Negatives
+//!
+//! - The taint analysis is limited to a fixed number of steps per source call,
+//!   so input passing through long call chains may not reach the format string parameter.
+//! - Format strings in memory that the pointer inference analysis cannot locate are not checked.
+//!   They are counted as skipped sites in the metrics of the check, see [`crate::utils::metrics`].
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use petgraph::graph::NodeIndex;
 use petgraph::visit::EdgeRef;
@@ -29,12 +45,13 @@ use crate::abstract_domain::TryToBitvec;
 use crate::analysis::graph::Edge;
 use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
 use crate::analysis::pointer_inference::PointerInference;
-use crate::intermediate_representation::ExternSymbol;
-use crate::intermediate_representation::Jmp;
-use crate::intermediate_representation::RuntimeMemoryImage;
+use crate::analysis::taint::flow::{find_taint_flows, CallSite, TaintFlowConfig};
+use crate::analysis::taint::summary::TaintFlowTarget;
+use crate::intermediate_representation::{ExternSymbol, Jmp, RuntimeMemoryImage};
 use crate::prelude::*;
 use crate::utils::log::CweWarning;
 use crate::utils::log::LogMessage;
+use crate::utils::metrics::SiteCounts;
 use crate::CweModule;
 
 /// The module name and version
@@ -51,6 +68,9 @@ pub struct Config {
     format_string_symbols: Vec<String>,
     /// The index of the format string paramater of the symbol.
     format_string_index: HashMap<String, usize>,
+    /// Functions returning user-controlled input, together with the location that the input is returned in.
+    #[serde(default)]
+    input_sources: BTreeMap<String, TaintFlowTarget>,
 }
 
 /// The categorization of the string location based on kinds of different memory.
@@ -67,7 +87,8 @@ pub enum StringLocation {
 }
 
 /// This check searches for external symbols that take a format string as an input parameter.
-/// It then checks whether the parameter points to read only memory.
+/// If the parameter is derived from user-controlled input, a CWE warning naming the input sources is generated.
+/// Otherwise it checks whether the parameter points to read only memory.
 /// If not, a CWE warning is generated.
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
@@ -80,12 +101,20 @@ pub fn check_cwe(
     let format_string_index = config.format_string_index.clone();
 
     let pointer_inference_results = analysis_results.pointer_inference.unwrap();
+    let tainted_calls =
+        find_tainted_format_strings(analysis_results, &config, &format_string_symbols);
     let mut cwe_warnings = Vec::new();
+    let mut site_counts = SiteCounts::default();
 
     for edge in pointer_inference_results.get_graph().edge_references() {
         if let Edge::ExternCallStub(jmp) = edge.weight() {
             if let Jmp::Call { target, .. } = &jmp.term {
                 if let Some(symbol) = format_string_symbols.get(target) {
+                    if let Some(sources) = tainted_calls.get(&jmp.tid) {
+                        cwe_warnings.push(generate_taint_cwe_warning(&jmp.tid, symbol, sources));
+                        site_counts.add_flagged();
+                        continue;
+                    }
                     let location = locate_format_string(
                         &edge.source(),
                         symbol,
@@ -94,20 +123,51 @@ pub fn check_cwe(
                         &analysis_results.project.runtime_memory_image,
                     );
 
-                    if matches!(
-                        location,
-                        StringLocation::GlobalWriteable | StringLocation::NonGlobal
-                    ) {
-                        cwe_warnings.push(generate_cwe_warning(&jmp.tid, symbol, &location));
+                    match location {
+                        StringLocation::GlobalWriteable | StringLocation::NonGlobal => {
+                            cwe_warnings.push(generate_cwe_warning(&jmp.tid, symbol, &location));
+                            site_counts.add_flagged();
+                        }
+                        StringLocation::GlobalReadable => site_counts.add_safe(),
+                        StringLocation::Unknown => site_counts.add_skipped(),
                     }
                 }
             }
         }
     }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (Vec::new(), cwe_warnings)
 }
 
+/// Run a taint analysis for each call to an input source
+/// and collect the calls to format string functions whose format string parameter is reached by the input.
+fn find_tainted_format_strings(
+    analysis_results: &AnalysisResults,
+    config: &Config,
+    format_string_symbols: &HashMap<Tid, &ExternSymbol>,
+) -> HashMap<Tid, BTreeSet<CallSite>> {
+    let taint_config = TaintFlowConfig {
+        sources: config.input_sources.clone(),
+        sinks: format_string_symbols
+            .values()
+            .filter_map(|symbol| {
+                let index = *config.format_string_index.get(&symbol.name)?;
+                Some((symbol.name.clone(), vec![index]))
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let mut tainted_calls: HashMap<Tid, BTreeSet<CallSite>> = HashMap::new();
+    for flow in find_taint_flows(analysis_results, &taint_config) {
+        tainted_calls
+            .entry(flow.sink.call_tid)
+            .or_default()
+            .insert(flow.source);
+    }
+    tainted_calls
+}
+
 /// Returns a StringLocation based on the kind of memory
 /// holding the string.
 /// If no assumption about the string location can be made,
@@ -173,9 +233,40 @@ fn generate_cwe_warning(
         .symbols(vec![called_symbol.name.clone()])
 }
 
+/// Generate the CWE warning for a call whose format string is derived from user-controlled input.
+fn generate_taint_cwe_warning(
+    callsite: &Tid,
+    called_symbol: &ExternSymbol,
+    sources: &BTreeSet<CallSite>,
+) -> CweWarning {
+    let source_descriptions: Vec<String> = sources
+        .iter()
+        .map(|source| format!("{} at {}", source.function, source.call_tid.address))
+        .collect();
+    let mut tids = vec![format!("{callsite}")];
+    tids.extend(sources.iter().map(|source| format!("{}", source.call_tid)));
+    let mut symbols = vec![called_symbol.name.clone()];
+    symbols.extend(sources.iter().map(|source| source.function.clone()));
+    symbols.dedup();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Externally Controlled Format String) Format string for call to {} at {} is derived from user input returned by {}",
+            called_symbol.name,
+            callsite.address,
+            source_descriptions.join(", ")
+        ),
+    )
+    .tids(tids)
+    .addresses(vec![callsite.address.clone()])
+    .symbols(symbols)
+}
+
 #[cfg(test)]
 pub mod tests {
     use crate::analysis::pointer_inference::PointerInference as PointerInferenceComputation;
+    use crate::checkers::test_utils::run_check;
     use crate::{defs, intermediate_representation::*};
 
     use super::*;
@@ -231,4 +322,42 @@ pub mod tests {
             StringLocation::GlobalReadable
         );
     }
+
+    #[test]
+    fn format_string_from_user_input() {
+        let mut project = Project::mock_x64();
+        for name in ["getenv", "printf"] {
+            let symbol = ExternSymbol::mock_x64(name);
+            project
+                .program
+                .term
+                .extern_symbols
+                .insert(symbol.tid.clone(), symbol);
+        }
+        let mut blk_input = Blk::mock_with_tid("blk_input");
+        blk_input.term.defs = defs!["name: RDI:8 = 0x3002:8"];
+        blk_input
+            .term
+            .jmps
+            .push(Jmp::call("call_getenv", "getenv", Some("blk_print")));
+        let mut blk_print = Blk::mock_with_tid("blk_print");
+        blk_print.term.defs = defs!["format: RDI:8 = RAX:8"];
+        blk_print
+            .term
+            .jmps
+            .push(Jmp::call("call_printf", "printf", Some("blk_end")));
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![blk_input, blk_print, Blk::mock_with_tid("blk_end")];
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+
+        let config = serde_json::json!({
+            "format_string_symbols": ["printf"],
+            "format_string_index": {"printf": 0},
+            "input_sources": {"getenv": "ReturnValue"}
+        });
+        let warnings = run_check(check_cwe, &project, &config);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["call_printf", "call_getenv"]);
+        assert!(warnings[0].description.contains("getenv"));
+    }
 }
@@ -0,0 +1,964 @@
+//! This module implements a check for CWE-1419: Incorrect Initialization of Resource,
+//! in the form of missing or incorrect initialization of hardware security peripherals.
+//!
+//! Microcontrollers contain peripherals that only provide their security guarantees after initialization,
+//! e.g. a true random number generator that has to be enabled,
+//! a memory protection unit that has to be configured
+//! or the readout protection of the flash memory.
+//! If the firmware does not initialize these peripherals before entering its main loop,
+//! or only initializes them on some paths, the device runs without the corresponding protection.
+//!
+//! See <https://cwe.mitre.org/data/definitions/1419.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! This check is only executed for bare metal binaries.
+//! For other binaries it has to be selected explicitly via the `--partial` command line option.
+//!
+//! The security peripherals that have to be initialized are configured in config.json
+//! through the address of a memory-mapped register, a bit mask and the expected value of the masked bits.
+//! We search for all `Store` instructions to these registers
+//! and use the results of the Pointer Inference analysis and the known bits of the stored value
+//! (e.g. for read-modify-write sequences) to decide whether the expected value is written.
+//!
+//! Starting at the reset handler we then compute which peripherals are initialized on all paths
+//! and which peripherals are initialized on some paths.
+//! Calls to functions are handled by summaries of the peripherals that the callee initializes.
+//! The state is checked at the entry of the main loop,
+//! i.e. of the loop in `main` from which the function never returns.
+//! If `main` contains no such loop, the state is checked when `main` returns.
+//! If `main` is not found, the main loop or the return instructions of the reset handler are used instead.
+//!
+//! Warnings are generated for peripherals that are not initialized on any path,
+//! peripherals that are only initialized on some paths
+//! and writes of unexpected values to the peripheral registers.
+//!
+//! The reset handler is identified by its symbol name.
+//! If no symbol is found, the second entry of the vector table of ARM Cortex-M binaries is used.
+//!
+//! ## False Positives
+//!
+//! - Peripherals that are initialized by a bootloader before the analyzed firmware starts are flagged.
+//! - Peripherals that are initialized through indirect calls are flagged.
+//! - Conditional initialization is also flagged if the condition always holds on the device.
+//!
+//! ## False Negatives
+//!
+//! - Writes to the peripheral registers with unknown values are assumed to initialize the peripheral correctly.
+//! - A later write that disables the peripheral again is not detected.
+//! - Peripherals initialized through DMA transfers or by extern functions are not tracked.
+
+use crate::abstract_domain::TryToBitvec;
+use crate::analysis::pointer_inference::PointerInference;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::parse_hex_string_to_u64;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::CweModule;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE1419",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The maximal number of rounds for the computation of the function summaries.
+const MAX_SUMMARY_ROUNDS: usize = 20;
+
+/// The maximal depth up to which variables are resolved when computing the known bits of a stored value.
+const MAX_RESOLUTION_DEPTH: usize = 4;
+
+/// A security peripheral that has to be initialized.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct PeripheralConfig {
+    /// A human-readable name of the peripheral setting.
+    name: String,
+    /// The address of the memory-mapped register as a hexadecimal number.
+    address: String,
+    /// The bits of the register that are relevant for the setting as a hexadecimal number.
+    mask: String,
+    /// The expected value of the masked bits as a hexadecimal number.
+    value: String,
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Names of the reset handler.
+    reset_handler_symbols: Vec<String>,
+    /// Names of the main function.
+    main_symbols: Vec<String>,
+    /// The security peripherals that have to be initialized.
+    peripherals: Vec<PeripheralConfig>,
+}
+
+/// A security peripheral with parsed register address, mask and value.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Peripheral {
+    name: String,
+    address: u64,
+    mask: u64,
+    value: u64,
+}
+
+impl Peripheral {
+    /// Parse the peripheral configuration.
+    fn from_config(config: &PeripheralConfig) -> Result<Peripheral, Error> {
+        let mask = parse_hex_string_to_u64(&config.mask)?;
+        Ok(Peripheral {
+            name: config.name.clone(),
+            address: parse_hex_string_to_u64(&config.address)?,
+            mask,
+            value: parse_hex_string_to_u64(&config.value)? & mask,
+        })
+    }
+
+    /// Check whether a value with the given known bits matches the expected value.
+    /// Returns `None` if not all masked bits are known and the known bits do not contradict the expected value.
+    fn matches(&self, known_bits: KnownBits) -> Option<bool> {
+        let known_masked_bits = known_bits.mask & self.mask;
+        if (known_bits.value ^ self.value) & known_masked_bits != 0 {
+            Some(false)
+        } else if known_masked_bits == self.mask {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// The indices of the peripherals correctly initialized by each basic block.
+type BlockWrites = HashMap<Tid, BTreeSet<usize>>;
+
+/// The known bits of a value.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct KnownBits {
+    /// The bits whose value is known.
+    mask: u64,
+    /// The value of the known bits. Unknown bits are zero.
+    value: u64,
+}
+
+impl KnownBits {
+    /// A value where no bits are known.
+    const UNKNOWN: KnownBits = KnownBits { mask: 0, value: 0 };
+
+    /// A value where all bits are known.
+    fn exact(value: u64) -> KnownBits {
+        KnownBits {
+            mask: u64::MAX,
+            value,
+        }
+    }
+
+    fn or(self, other: KnownBits) -> KnownBits {
+        let ones = self.value | other.value;
+        let zeros = (self.mask & !self.value) & (other.mask & !other.value);
+        KnownBits {
+            mask: ones | zeros,
+            value: ones,
+        }
+    }
+
+    fn and(self, other: KnownBits) -> KnownBits {
+        let ones = self.value & other.value;
+        let zeros = (self.mask & !self.value) | (other.mask & !other.value);
+        KnownBits {
+            mask: ones | zeros,
+            value: ones,
+        }
+    }
+}
+
+/// The initialization state of the security peripherals at a program point.
+/// The peripherals are given by their index in the configuration.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct InitState {
+    /// Peripherals initialized on all paths.
+    must: BTreeSet<usize>,
+    /// Peripherals initialized on some paths.
+    may: BTreeSet<usize>,
+}
+
+impl InitState {
+    /// The state at the reset handler, where no peripheral is initialized.
+    fn new() -> InitState {
+        InitState {
+            must: BTreeSet::new(),
+            may: BTreeSet::new(),
+        }
+    }
+
+    /// Merge states of two paths.
+    fn merge(&self, other: &InitState) -> InitState {
+        InitState {
+            must: self.must.intersection(&other.must).copied().collect(),
+            may: self.may.union(&other.may).copied().collect(),
+        }
+    }
+
+    /// Add the peripherals initialized by a sequence of instructions described by `other`.
+    fn extend(&mut self, other: &InitState) {
+        self.must.extend(other.must.iter().copied());
+        self.may.extend(other.may.iter().copied());
+    }
+}
+
+/// The initialization states computed for a function.
+struct SubStates {
+    /// The states at the start of each reachable basic block.
+    block_start: BTreeMap<Tid, InitState>,
+    /// The merged state at all return instructions of the function.
+    /// `None` if no return instruction is reachable.
+    on_return: Option<InitState>,
+}
+
+/// The analysis of the initialization of the security peripherals.
+struct Analysis<'a> {
+    project: &'a Project,
+    /// The peripherals initialized by the `Def` instructions of each basic block.
+    block_writes: HashMap<Tid, InitState>,
+    /// The states at the return of each function, when the function is called with no peripheral initialized.
+    summaries: HashMap<Tid, Option<InitState>>,
+}
+
+impl<'a> Analysis<'a> {
+    /// Create a new analysis object.
+    /// The `block_writes` are the correct writes to peripheral registers per basic block.
+    fn new(project: &'a Project, block_writes: BlockWrites) -> Analysis<'a> {
+        let block_writes = block_writes
+            .into_iter()
+            .map(|(tid, writes)| {
+                (
+                    tid,
+                    InitState {
+                        must: writes.clone(),
+                        may: writes,
+                    },
+                )
+            })
+            .collect();
+        Analysis {
+            project,
+            block_writes,
+            summaries: HashMap::new(),
+        }
+    }
+
+    /// Compute the summaries of all functions.
+    ///
+    /// Function summaries start with all peripherals initialized on all paths,
+    /// but none on some paths, and are refined until a fixpoint is reached.
+    fn compute_summaries(&mut self, num_peripherals: usize) {
+        let initial_summary = InitState {
+            must: (0..num_peripherals).collect(),
+            may: BTreeSet::new(),
+        };
+        self.summaries = self
+            .project
+            .program
+            .term
+            .subs
+            .keys()
+            .map(|tid| (tid.clone(), Some(initial_summary.clone())))
+            .collect();
+        for _ in 0..MAX_SUMMARY_ROUNDS {
+            let mut changed = false;
+            for (sub_tid, sub) in self.project.program.term.subs.iter() {
+                let summary = self.analyze_sub(sub, InitState::new()).on_return;
+                if self.summaries[sub_tid] != summary {
+                    self.summaries.insert(sub_tid.clone(), summary);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// Compute the states at the start of the basic blocks of a function,
+    /// given the state at the function entry.
+    fn analyze_sub(&self, sub: &Term<Sub>, entry_state: InitState) -> SubStates {
+        let mut block_start = BTreeMap::new();
+        let mut on_return: Option<InitState> = None;
+        let Some(entry_block) = sub.term.blocks.first() else {
+            return SubStates {
+                block_start,
+                on_return,
+            };
+        };
+        let blocks: HashMap<&Tid, &Term<Blk>> =
+            sub.term.blocks.iter().map(|blk| (&blk.tid, blk)).collect();
+        block_start.insert(entry_block.tid.clone(), entry_state);
+        let mut worklist = vec![entry_block.tid.clone()];
+        while let Some(block_tid) = worklist.pop() {
+            let block = blocks[&block_tid];
+            let state = self.get_block_end_state(&block_tid, &block_start[&block_tid]);
+            for (successor, successor_state) in self.get_successor_states(block, state) {
+                let Some(successor) = successor else {
+                    on_return = Some(match on_return {
+                        Some(old_state) => old_state.merge(&successor_state),
+                        None => successor_state,
+                    });
+                    continue;
+                };
+                if !blocks.contains_key(successor) {
+                    continue;
+                }
+                let new_state = match block_start.get(successor) {
+                    Some(old_state) => old_state.merge(&successor_state),
+                    None => successor_state,
+                };
+                if block_start.get(successor) != Some(&new_state) {
+                    block_start.insert(successor.clone(), new_state);
+                    worklist.push(successor.clone());
+                }
+            }
+        }
+        SubStates {
+            block_start,
+            on_return,
+        }
+    }
+
+    /// Get the state after the `Def` instructions of the given block.
+    fn get_block_end_state(&self, block_tid: &Tid, start_state: &InitState) -> InitState {
+        let mut state = start_state.clone();
+        if let Some(writes) = self.block_writes.get(block_tid) {
+            state.extend(writes);
+        }
+        state
+    }
+
+    /// Get the successors of the given block together with the states at their start.
+    /// The successor `None` represents a return instruction.
+    fn get_successor_states<'b>(
+        &self,
+        block: &'b Term<Blk>,
+        state: InitState,
+    ) -> Vec<(Option<&'b Tid>, InitState)> {
+        let mut successors = Vec::new();
+        for jmp in block.term.jmps.iter() {
+            match &jmp.term {
+                Jmp::Branch(target) | Jmp::CBranch { target, .. } => {
+                    successors.push((Some(target), state.clone()))
+                }
+                Jmp::Call {
+                    target,
+                    return_: Some(return_target),
+                } => match self.summaries.get(target) {
+                    Some(Some(summary)) => {
+                        let mut return_state = state.clone();
+                        return_state.extend(summary);
+                        successors.push((Some(return_target), return_state));
+                    }
+                    // The callee never returns.
+                    Some(None) => (),
+                    // Calls to extern functions.
+                    None => successors.push((Some(return_target), state.clone())),
+                },
+                Jmp::CallInd {
+                    return_: Some(return_target),
+                    ..
+                }
+                | Jmp::CallOther {
+                    return_: Some(return_target),
+                    ..
+                } => successors.push((Some(return_target), state.clone())),
+                Jmp::Return(_) => successors.push((None, state.clone())),
+                Jmp::BranchInd(_) => {
+                    for target in block.term.indirect_jmp_targets.iter() {
+                        successors.push((Some(target), state.clone()));
+                    }
+                }
+                Jmp::Call { return_: None, .. }
+                | Jmp::CallInd { return_: None, .. }
+                | Jmp::CallOther { return_: None, .. } => (),
+            }
+        }
+        successors
+    }
+}
+
+/// Compute the known bits of the given expression.
+/// Variables are resolved through the assignments to them in `previous_defs`.
+fn compute_known_bits(
+    expression: &Expression,
+    previous_defs: &[Term<Def>],
+    depth: usize,
+) -> KnownBits {
+    match expression {
+        Expression::Const(bitvec) => bitvec
+            .try_to_u64()
+            .map(KnownBits::exact)
+            .unwrap_or(KnownBits::UNKNOWN),
+        Expression::BinOp { op, lhs, rhs }
+            if matches!(op, BinOpType::IntOr | BinOpType::IntAnd) =>
+        {
+            let lhs = compute_known_bits(lhs, previous_defs, depth);
+            let rhs = compute_known_bits(rhs, previous_defs, depth);
+            if *op == BinOpType::IntOr {
+                lhs.or(rhs)
+            } else {
+                lhs.and(rhs)
+            }
+        }
+        Expression::Var(var) if depth < MAX_RESOLUTION_DEPTH => {
+            for (index, def) in previous_defs.iter().enumerate().rev() {
+                match &def.term {
+                    Def::Assign {
+                        var: assigned_var,
+                        value,
+                    } if assigned_var == var => {
+                        return compute_known_bits(value, &previous_defs[..index], depth + 1)
+                    }
+                    Def::Load {
+                        var: assigned_var, ..
+                    } if assigned_var == var => return KnownBits::UNKNOWN,
+                    _ => (),
+                }
+            }
+            KnownBits::UNKNOWN
+        }
+        _ => KnownBits::UNKNOWN,
+    }
+}
+
+/// Get the value of the given data as an unsigned integer if it is a unique absolute value.
+fn get_unique_absolute_value(
+    data: Option<crate::analysis::pointer_inference::Data>,
+) -> Option<u64> {
+    data?
+        .get_if_absolute_value()?
+        .try_to_bitvec()
+        .ok()?
+        .try_to_u64()
+        .ok()
+}
+
+/// Find all writes to peripheral registers.
+///
+/// Returns the peripherals correctly initialized per block
+/// and the TIDs of `Store` instructions writing unexpected values to peripheral registers.
+fn find_peripheral_writes(
+    project: &Project,
+    pi_result: Option<&PointerInference>,
+    peripherals: &[Peripheral],
+) -> (BlockWrites, Vec<(Tid, usize)>) {
+    let mut block_writes: BlockWrites = HashMap::new();
+    let mut incorrect_writes = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for block in sub.term.blocks.iter() {
+            for (def_index, def) in block.term.defs.iter().enumerate() {
+                let Def::Store { address, value } = &def.term else {
+                    continue;
+                };
+                let address = match address {
+                    Expression::Const(bitvec) => bitvec.try_to_u64().ok(),
+                    _ => get_unique_absolute_value(
+                        pi_result.and_then(|pi| pi.eval_address_at_def(&def.tid)),
+                    ),
+                };
+                let Some(address) = address else {
+                    continue;
+                };
+                let mut known_bits = compute_known_bits(value, &block.term.defs[..def_index], 0);
+                if let Some(exact_value) = get_unique_absolute_value(
+                    pi_result.and_then(|pi| pi.eval_value_at_def(&def.tid)),
+                ) {
+                    known_bits = KnownBits::exact(exact_value);
+                }
+                for (index, peripheral) in peripherals.iter().enumerate() {
+                    if peripheral.address != address {
+                        continue;
+                    }
+                    if peripheral.matches(known_bits) == Some(false) {
+                        incorrect_writes.push((def.tid.clone(), index));
+                    } else {
+                        block_writes
+                            .entry(block.tid.clone())
+                            .or_default()
+                            .insert(index);
+                    }
+                }
+            }
+        }
+    }
+    (block_writes, incorrect_writes)
+}
+
+/// Find the reset handler, either by its symbol name
+/// or through the vector table of ARM Cortex-M binaries.
+fn find_reset_handler<'a>(project: &'a Project, config: &Config) -> Option<&'a Term<Sub>> {
+    if let Some(sub) = find_sub_by_name(project, &config.reset_handler_symbols) {
+        return Some(sub);
+    }
+    if !project.cpu_architecture.to_uppercase().contains("ARM")
+        || project.get_pointer_bytesize() != ByteSize::new(4)
+    {
+        return None;
+    }
+    // The second entry of the vector table is the address of the reset handler.
+    // The lowest bit of the address is set to indicate Thumb mode.
+    let segment = *project.runtime_memory_image.get_code_segments().first()?;
+    let entry: [u8; 4] = segment.bytes.get(4..8)?.try_into().unwrap();
+    let address = if project.runtime_memory_image.is_little_endian_byte_order() {
+        u32::from_le_bytes(entry)
+    } else {
+        u32::from_be_bytes(entry)
+    };
+    project
+        .program
+        .term
+        .subs
+        .values()
+        .find(|sub| parse_hex_string_to_u64(&sub.tid.address).ok() == Some(u64::from(address) & !1))
+}
+
+/// Find a function with one of the given names.
+fn find_sub_by_name<'a>(project: &'a Project, names: &[String]) -> Option<&'a Term<Sub>> {
+    project
+        .program
+        .term
+        .subs
+        .values()
+        .find(|sub| names.contains(&sub.term.name))
+}
+
+/// Get the blocks of the given function that can reach a return instruction
+/// (ignoring calls that never return).
+fn get_blocks_reaching_return(sub: &Term<Sub>) -> BTreeSet<&Tid> {
+    let mut reaching_return: BTreeSet<&Tid> = sub
+        .term
+        .blocks
+        .iter()
+        .filter(|blk| {
+            blk.term
+                .jmps
+                .iter()
+                .any(|jmp| matches!(jmp.term, Jmp::Return(_)))
+        })
+        .map(|blk| &blk.tid)
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for blk in sub.term.blocks.iter() {
+            if reaching_return.contains(&blk.tid) {
+                continue;
+            }
+            if get_intraprocedural_successors(blk)
+                .iter()
+                .any(|successor| reaching_return.contains(successor))
+            {
+                reaching_return.insert(&blk.tid);
+                changed = true;
+            }
+        }
+    }
+    reaching_return
+}
+
+/// Get the intraprocedural successors of a block.
+fn get_intraprocedural_successors(blk: &Term<Blk>) -> Vec<&Tid> {
+    let mut successors = Vec::new();
+    for jmp in blk.term.jmps.iter() {
+        match &jmp.term {
+            Jmp::Branch(target)
+            | Jmp::CBranch { target, .. }
+            | Jmp::Call {
+                return_: Some(target),
+                ..
+            }
+            | Jmp::CallInd {
+                return_: Some(target),
+                ..
+            }
+            | Jmp::CallOther {
+                return_: Some(target),
+                ..
+            } => successors.push(target),
+            Jmp::BranchInd(_) => successors.extend(blk.term.indirect_jmp_targets.iter()),
+            _ => (),
+        }
+    }
+    successors
+}
+
+/// Get the entry blocks of the main loop of a function,
+/// i.e. of loops from which the function never returns.
+///
+/// An entry block is a block on such a loop
+/// that is the function entry or has a predecessor outside of the loop.
+fn get_main_loop_entries(sub: &Term<Sub>) -> Vec<&Tid> {
+    let reaching_return = get_blocks_reaching_return(sub);
+    let non_returning: BTreeMap<&Tid, &Term<Blk>> = sub
+        .term
+        .blocks
+        .iter()
+        .filter(|blk| !reaching_return.contains(&blk.tid))
+        .map(|blk| (&blk.tid, blk))
+        .collect();
+    let mut predecessors: BTreeMap<&Tid, BTreeSet<&Tid>> = BTreeMap::new();
+    for blk in sub.term.blocks.iter() {
+        for successor in get_intraprocedural_successors(blk) {
+            predecessors.entry(successor).or_default().insert(&blk.tid);
+        }
+    }
+    // The blocks of the non-returning region reachable from the given block.
+    let get_reachable = |start: &Tid| -> BTreeSet<&Tid> {
+        let mut visited = BTreeSet::new();
+        let mut stack = get_intraprocedural_successors(non_returning[start]);
+        while let Some(tid) = stack.pop() {
+            if let Some(blk) = non_returning.get(tid) {
+                if visited.insert(tid) {
+                    stack.extend(get_intraprocedural_successors(blk));
+                }
+            }
+        }
+        visited
+    };
+    non_returning
+        .keys()
+        .filter(|tid| {
+            let reachable = get_reachable(tid);
+            if !reachable.contains(**tid) {
+                return false;
+            }
+            sub.term.blocks.first().map(|blk| &blk.tid) == Some(**tid)
+                || predecessors
+                    .get(**tid)
+                    .map(|preds| preds.iter().any(|pred| !reachable.contains(pred)))
+                    .unwrap_or(false)
+        })
+        .copied()
+        .collect()
+}
+
+/// Get the states at the check points of the given function, i.e. at the entries of its main loop
+/// or at its return instructions, together with the TID of the check point.
+fn get_check_point_states<'a>(sub: &'a Term<Sub>, states: &SubStates) -> Vec<(&'a Tid, InitState)> {
+    let main_loop_entries = get_main_loop_entries(sub);
+    if !main_loop_entries.is_empty() {
+        return main_loop_entries
+            .into_iter()
+            .filter_map(|tid| Some((tid, states.block_start.get(tid)?.clone())))
+            .collect();
+    }
+    match &states.on_return {
+        Some(state) => vec![(&sub.tid, state.clone())],
+        None => Vec::new(),
+    }
+}
+
+/// Run the check.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE1419.");
+    let mut logs = Vec::new();
+    let mut peripherals = Vec::new();
+    for peripheral_config in config.peripherals.iter() {
+        match Peripheral::from_config(peripheral_config) {
+            Ok(peripheral) => peripherals.push(peripheral),
+            Err(err) => logs.push(
+                LogMessage::new_error(format!(
+                    "Invalid peripheral configuration for {}: {err}",
+                    peripheral_config.name
+                ))
+                .source(CWE_MODULE.name),
+            ),
+        }
+    }
+    // The examined sites are the configured peripherals.
+    let mut site_counts = SiteCounts::default();
+    if peripherals.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (logs, Vec::new());
+    }
+    let Some(reset_handler) = find_reset_handler(project, &config) else {
+        logs.push(
+            LogMessage::new_info("No reset handler found. Check skipped.").source(CWE_MODULE.name),
+        );
+        for _peripheral in peripherals.iter() {
+            site_counts.add_skipped();
+        }
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (logs, Vec::new());
+    };
+
+    let (block_writes, incorrect_writes) =
+        find_peripheral_writes(project, analysis_results.pointer_inference, &peripherals);
+    let mut analysis = Analysis::new(project, block_writes);
+    analysis.compute_summaries(peripherals.len());
+
+    // Compute the states at the check points.
+    let reset_states = analysis.analyze_sub(reset_handler, InitState::new());
+    let main_entry_state =
+        find_sub_by_name(project, &config.main_symbols).and_then(|main_sub| {
+            let mut entry_state: Option<InitState> = None;
+            for blk in reset_handler.term.blocks.iter() {
+                let Some(start_state) = reset_states.block_start.get(&blk.tid) else {
+                    continue;
+                };
+                if blk.term.jmps.iter().any(
+                    |jmp| matches!(&jmp.term, Jmp::Call { target, .. } if *target == main_sub.tid),
+                ) {
+                    let state = analysis.get_block_end_state(&blk.tid, start_state);
+                    entry_state = Some(match entry_state {
+                        Some(old_state) => old_state.merge(&state),
+                        None => state,
+                    });
+                }
+            }
+            Some((main_sub, entry_state?))
+        });
+    let check_points = match main_entry_state {
+        Some((main_sub, entry_state)) => {
+            get_check_point_states(main_sub, &analysis.analyze_sub(main_sub, entry_state))
+        }
+        None => get_check_point_states(reset_handler, &reset_states),
+    };
+
+    let mut warnings = Vec::new();
+    let mut reported_peripherals = BTreeSet::new();
+    for (check_point, state) in check_points.iter() {
+        for (index, peripheral) in peripherals.iter().enumerate() {
+            if state.must.contains(&index) || reported_peripherals.contains(&index) {
+                continue;
+            }
+            reported_peripherals.insert(index);
+            let description = if state.may.contains(&index) {
+                format!(
+                    "(Incorrect Initialization of Hardware Security Peripheral) {} is only initialized on some paths to the main loop at {}",
+                    peripheral.name, check_point.address
+                )
+            } else {
+                format!(
+                    "(Incorrect Initialization of Hardware Security Peripheral) {} is not initialized before the main loop at {}",
+                    peripheral.name, check_point.address
+                )
+            };
+            warnings.push(generate_warning(description, check_point, peripheral));
+        }
+    }
+    for (store_tid, index) in incorrect_writes.iter() {
+        let peripheral = &peripherals[*index];
+        let description = format!(
+            "(Incorrect Initialization of Hardware Security Peripheral) {} is written with an unexpected value at {}",
+            peripheral.name, store_tid.address
+        );
+        warnings.push(generate_warning(description, store_tid, peripheral));
+        reported_peripherals.insert(*index);
+    }
+    for index in 0..peripherals.len() {
+        if reported_peripherals.contains(&index) {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (logs, warnings)
+}
+
+/// Generate a CWE warning for the given peripheral at the given location.
+fn generate_warning(description: String, location: &Tid, peripheral: &Peripheral) -> CweWarning {
+    CweWarning::new(CWE_MODULE.name, CWE_MODULE.version, description)
+        .tids(vec![format!("{location}")])
+        .addresses(vec![location.address.clone()])
+        .other(vec![vec![
+            "peripheral_register".to_string(),
+            format!("{:#x}", peripheral.address),
+        ]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{defs, expr, variable};
+
+    fn mock_block(name: &str, defs: Vec<Term<Def>>, jmps: Vec<Jmp>) -> Term<Blk> {
+        Term {
+            tid: Tid::new(name),
+            term: Blk {
+                defs,
+                jmps: jmps
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, jmp)| Term {
+                        tid: Tid::new(format!("{name}_jmp_{index}")),
+                        term: jmp,
+                    })
+                    .collect(),
+                indirect_jmp_targets: Vec::new(),
+            },
+        }
+    }
+
+    fn mock_sub(name: &str, blocks: Vec<Term<Blk>>) -> Term<Sub> {
+        Term {
+            tid: Tid::new(name),
+            term: Sub {
+                name: name.to_string(),
+                blocks,
+                calling_convention: None,
+            },
+        }
+    }
+
+    fn mock_peripheral(address: u64, mask: u64, value: u64) -> Peripheral {
+        Peripheral {
+            name: "peripheral".to_string(),
+            address,
+            mask,
+            value,
+        }
+    }
+
+    #[test]
+    fn known_bits_of_stored_values() {
+        let peripheral = mock_peripheral(0x1000, 0x4, 0x4);
+        let mut defs = defs!["r2:4 := Load from r3:4"];
+        defs.push(Def::assign(
+            "or",
+            variable!("r2:4"),
+            Expression::BinOp {
+                op: BinOpType::IntOr,
+                lhs: Box::new(expr!("r2:4")),
+                rhs: Box::new(expr!("0x4:4")),
+            },
+        ));
+        defs.push(Def::assign(
+            "and",
+            variable!("r1:4"),
+            Expression::BinOp {
+                op: BinOpType::IntAnd,
+                lhs: Box::new(expr!("r0:4")),
+                rhs: Box::new(expr!("0xfffffffb:4")),
+            },
+        ));
+        assert_eq!(
+            peripheral.matches(compute_known_bits(&expr!("r2:4"), &defs, 0)),
+            Some(true)
+        );
+        assert_eq!(
+            peripheral.matches(compute_known_bits(&expr!("r1:4"), &defs, 0)),
+            Some(false)
+        );
+        assert_eq!(
+            peripheral.matches(compute_known_bits(&expr!("r0:4"), &defs, 0)),
+            None
+        );
+        let peripheral = mock_peripheral(0x1000, 0xff00, 0xbb00);
+        assert_eq!(
+            peripheral.matches(compute_known_bits(&expr!("0xcc00:4"), &defs, 0)),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn initialization_on_paths_to_main_loop() {
+        let mut project = Project::mock_arm32();
+        let peripherals = vec![
+            mock_peripheral(0x1000, 0x1, 0x1),
+            mock_peripheral(0x2000, 0x1, 0x1),
+            mock_peripheral(0x3000, 0x1, 0x1),
+        ];
+        let reset_handler = mock_sub(
+            "Reset_Handler",
+            vec![mock_block(
+                "reset",
+                defs!["Store at 0x1000:4 := 0x1:4"],
+                vec![Jmp::Call {
+                    target: Tid::new("main"),
+                    return_: None,
+                }],
+            )],
+        );
+        let init_rng = mock_sub(
+            "init_rng",
+            vec![
+                mock_block(
+                    "init_rng_cond",
+                    Vec::new(),
+                    vec![
+                        Jmp::CBranch {
+                            target: Tid::new("init_rng_write"),
+                            condition: expr!("ZF:1"),
+                        },
+                        Jmp::Branch(Tid::new("init_rng_ret")),
+                    ],
+                ),
+                mock_block(
+                    "init_rng_write",
+                    defs!["Store at 0x2000:4 := 0x1:4"],
+                    vec![Jmp::Branch(Tid::new("init_rng_ret"))],
+                ),
+                mock_block("init_rng_ret", Vec::new(), vec![Jmp::Return(expr!("lr:4"))]),
+            ],
+        );
+        let main = mock_sub(
+            "main",
+            vec![
+                mock_block(
+                    "main_init",
+                    Vec::new(),
+                    vec![Jmp::Call {
+                        target: Tid::new("init_rng"),
+                        return_: Some(Tid::new("main_loop")),
+                    }],
+                ),
+                mock_block(
+                    "main_loop",
+                    Vec::new(),
+                    vec![Jmp::Branch(Tid::new("main_loop"))],
+                ),
+            ],
+        );
+        project.program.term.subs = [reset_handler, init_rng, main]
+            .into_iter()
+            .map(|sub| (sub.tid.clone(), sub))
+            .collect();
+
+        let (block_writes, incorrect_writes) = find_peripheral_writes(&project, None, &peripherals);
+        assert!(incorrect_writes.is_empty());
+        let mut analysis = Analysis::new(&project, block_writes);
+        analysis.compute_summaries(peripherals.len());
+        assert_eq!(
+            analysis.summaries[&Tid::new("init_rng")],
+            Some(InitState {
+                must: BTreeSet::new(),
+                may: BTreeSet::from([1]),
+            })
+        );
+        assert_eq!(analysis.summaries[&Tid::new("main")], None);
+
+        let main = &project.program.term.subs[&Tid::new("main")];
+        assert_eq!(get_main_loop_entries(main), vec![&Tid::new("main_loop")]);
+        let entry_state = InitState {
+            must: BTreeSet::from([0]),
+            may: BTreeSet::from([0]),
+        };
+        let check_points = get_check_point_states(main, &analysis.analyze_sub(main, entry_state));
+        assert_eq!(
+            check_points,
+            vec![(
+                &Tid::new("main_loop"),
+                InitState {
+                    must: BTreeSet::from([0]),
+                    may: BTreeSet::from([0, 1]),
+                }
+            )]
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! This module implements a check for CWE-15: External Control of System or Configuration Setting.
+//!
+//! Functions like `sysctl`, `setsockopt` or `ioctl` change settings of the operating system,
+//! of network connections or of devices.
+//! If the setting or the new value is derived from user input,
+//! an attacker may weaken the security posture of the system,
+//! e.g. by enabling IP forwarding, disabling socket options or reconfiguring a device.
+//!
+//! See <https://cwe.mitre.org/data/definitions/15.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! We use a taint analysis starting at the return values of user input sources
+//! (e.g. `getenv` or `strtoul`) and the buffers filled by input functions (e.g. `read` or `recv`).
+//! For each call to a configuration sink we check the configured parameters of the sink,
+//! e.g. the option name and the option value of `setsockopt` or the request code and the argument of `ioctl`.
+//! If such a parameter is tainted or points to tainted memory, a warning is emitted.
+//!
+//! ## False Positives
+//!
+//! - The tainted value may be validated (e.g. compared against a list of allowed settings)
+//!   before it is passed to the sink.
+//! - Some settings are not security relevant, e.g. the size of socket buffers.
+//!
+//! ## False Negatives
+//!
+//! - For input buffers only the first pointer-sized value is tainted.
+//! - The taint analysis is intraprocedural.
+//! - Parameters of variadic sinks (like the argument of `ioctl`) that are not known to Ghidra
+//!   are only checked if they are passed in registers.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The user input sources, the input functions together with the index of their buffer parameter
+//! and the sinks together with the indices of their checked parameters are configurable in config.json.
+
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, HasCfg};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{
+    Data as PiData, PointerInference as PointerInferenceComputation,
+};
+use crate::analysis::taint::{
+    state::State as TaState, summary::TaintSummaries, Taint, TaintAnalysis,
+};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE15",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions whose return values are controlled by the user.
+    sources: Vec<String>,
+    /// Functions that write user input into a buffer,
+    /// mapped to the index of the parameter pointing to the buffer.
+    buffer_sources: BTreeMap<String, usize>,
+    /// Functions that change system or configuration settings,
+    /// mapped to the indices of the parameters determining the setting or its new value.
+    sinks: BTreeMap<String, Vec<usize>>,
+}
+
+/// Run the check.
+///
+/// We taint the results of user input sources and generate a warning
+/// for each call to a sink where one of the checked parameters may be tainted.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE15.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let sink_map = get_sink_parameter_map(project, &config.sinks);
+    let source_map = get_symbol_map(project, &config.sources);
+    let buffer_source_names: Vec<String> = config.buffer_sources.keys().cloned().collect();
+    let buffer_source_map = get_symbol_map(project, &buffer_source_names);
+    // The examined sites are the calls to sink functions.
+    let mut site_counts = SiteCounts::default();
+    if sink_map.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    // Sink calls without tainted parameters are counted as safe.
+    let sink_tids: HashSet<Tid> = sink_map.keys().cloned().collect();
+
+    let graph = analysis_results.control_flow_graph;
+    let (cwe_sender, cwe_receiver) = crossbeam_channel::unbounded();
+    let context = Context {
+        project,
+        pi_result,
+        control_flow_graph: graph,
+        sink_map,
+        taint_summaries: analysis_results.taint_summaries,
+        cwe_collector: cwe_sender,
+    };
+    let mut computation = create_computation(context, None);
+    computation.set_cancellation_token(analysis_results.cancellation_token);
+
+    for edge in graph.edge_references() {
+        let Edge::ExternCallStub(jmp) = edge.weight() else {
+            continue;
+        };
+        let Jmp::Call { target, .. } = &jmp.term else {
+            continue;
+        };
+        let return_node = edge.target();
+        let mut state = match source_map.get(target) {
+            Some(symbol) => TaState::new_return(symbol, pi_result, return_node),
+            None => TaState::new_empty(),
+        };
+        if let Some(symbol) = buffer_source_map.get(target) {
+            let buffer = config.buffer_sources[&symbol.name];
+            if let Some(param) = symbol.parameters.get(buffer) {
+                if let Some(address) = pi_result.eval_parameter_arg_at_call(&jmp.tid, param) {
+                    state.save_taint_to_memory(
+                        &address,
+                        Taint::Tainted(project.get_pointer_bytesize()),
+                    );
+                }
+            }
+        }
+        if !state.is_empty() {
+            computation.set_node_value(return_node, NodeValue::Value(state));
+        }
+    }
+
+    computation.compute_with_max_steps(100);
+
+    // Maps the TIDs of sink calls to the name of the sink and the indices of the tainted parameters.
+    let mut tainted_calls: BTreeMap<Tid, (String, Vec<usize>)> = BTreeMap::new();
+    for (call_tid, sink_name, tainted_params) in cwe_receiver.try_iter() {
+        let (_, params) = tainted_calls
+            .entry(call_tid)
+            .or_insert_with(|| (sink_name, Vec::new()));
+        params.extend(tainted_params);
+        params.sort_unstable();
+        params.dedup();
+    }
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            for jmp in blk.term.jmps.iter() {
+                if let Some((sink_name, params)) = tainted_calls.get(&jmp.tid) {
+                    site_counts.add_flagged();
+                    cwe_warnings.push(generate_cwe_warning(sub, &jmp.tid, sink_name, params));
+                } else if matches!(&jmp.term, Jmp::Call { target, .. } if sink_tids.contains(target))
+                {
+                    site_counts.add_safe();
+                }
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Map the TIDs of the sinks contained in the project to the symbol
+/// and the checked parameters of the sink together with their indices.
+///
+/// Parameters of variadic functions may be missing in the parameter list of the symbol.
+/// For these we use the corresponding integer parameter register of the calling convention.
+fn get_sink_parameter_map<'a>(
+    project: &'a Project,
+    sinks: &BTreeMap<String, Vec<usize>>,
+) -> HashMap<Tid, (&'a ExternSymbol, Vec<(usize, Arg)>)> {
+    let mut sink_map = HashMap::new();
+    for (tid, symbol) in project.program.term.extern_symbols.iter() {
+        let Some(indices) = sinks.get(&symbol.name) else {
+            continue;
+        };
+        let calling_conv = project.get_calling_convention(symbol);
+        let params: Vec<(usize, Arg)> = indices
+            .iter()
+            .filter_map(|&index| {
+                let param = symbol.parameters.get(index).cloned().or_else(|| {
+                    calling_conv
+                        .integer_parameter_register
+                        .get(index)
+                        .map(|register| Arg::from_var(register.clone(), None))
+                })?;
+                Some((index, param))
+            })
+            .collect();
+        if !params.is_empty() {
+            sink_map.insert(tid.clone(), (symbol, params));
+        }
+    }
+    sink_map
+}
+
+/// The Context struct for the taint analysis.
+struct Context<'a> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInferenceComputation<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// Maps the TIDs of the sinks to the symbol and the checked parameters together with their indices.
+    sink_map: HashMap<Tid, (&'a ExternSymbol, Vec<(usize, Arg)>)>,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where the TIDs of sink calls with tainted parameters are sent to
+    /// together with the name of the sink and the indices of the tainted parameters.
+    cwe_collector: crossbeam_channel::Sender<(Tid, String, Vec<usize>)>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
+    /// Generate a CWE warning if a checked parameter of a sink may be tainted
+    /// or may point to tainted memory.
+    fn update_extern_call(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        project: &Project,
+        extern_symbol: &ExternSymbol,
+    ) -> Option<TaState> {
+        if let Some((_, params)) = self.sink_map.get(&extern_symbol.tid) {
+            let tainted_params: Vec<usize> = params
+                .iter()
+                .filter(|(_, param)| {
+                    state.check_extern_parameter_for_taint::<true>(
+                        self.vsa_result(),
+                        param,
+                        &call.tid,
+                    )
+                })
+                .map(|(index, _)| *index)
+                .collect();
+            if !tainted_params.is_empty() {
+                let _ = self.cwe_collector.send((
+                    call.tid.clone(),
+                    extern_symbol.name.clone(),
+                    tainted_params,
+                ));
+            }
+        }
+        let calling_conv = project.get_calling_convention(extern_symbol);
+        let mut new_state = state.clone();
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        if let Some(summary) = self
+            .taint_summaries
+            .and_then(|summaries| summaries.get(&extern_symbol.name))
+        {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.pi_result,
+                &call.tid,
+                calling_conv,
+            );
+        }
+        Some(new_state)
+    }
+}
+
+/// Generate the CWE warning for a call to a sink with the given tainted parameters.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    call_tid: &Tid,
+    sink_name: &str,
+    tainted_params: &[usize],
+) -> CweWarning {
+    let param_list: Vec<String> = tainted_params
+        .iter()
+        .map(|index| index.to_string())
+        .collect();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Externally Controlled Setting) User input is passed as parameter {} to {} at {} ({})",
+            param_list.join(", "),
+            sink_name,
+            call_tid.address,
+            sub.term.name
+        ),
+    )
+    .tids(vec![format!("{call_tid}")])
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone(), sink_name.to_string()])
+    .other(vec![
+        [vec!["tainted_parameters".to_string()], param_list].concat()
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::mock_x64_project;
+    use crate::variable;
+
+    #[test]
+    fn sink_parameters() {
+        let project = mock_x64_project(&["ioctl", "setsockopt"], Vec::new());
+        let sinks = BTreeMap::from([
+            ("ioctl".to_string(), vec![1, 2]),
+            ("sysctl".to_string(), vec![0, 4]),
+            ("setsockopt".to_string(), vec![7]),
+        ]);
+
+        let sink_map = get_sink_parameter_map(&project, &sinks);
+        assert_eq!(sink_map.len(), 1);
+        let (symbol, params) = &sink_map[&Tid::new("ioctl")];
+        assert_eq!(symbol.name, "ioctl");
+        assert_eq!(
+            params,
+            &vec![
+                (1, Arg::from_var(variable!("RSI:8"), None)),
+                (2, Arg::from_var(variable!("RDX:8"), None)),
+            ]
+        );
+    }
+}
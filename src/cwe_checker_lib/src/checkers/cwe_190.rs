@@ -37,6 +37,7 @@ use crate::analysis::vsa_results::*;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
 use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
 use crate::CweModule;
 
@@ -150,8 +151,10 @@ pub fn check_cwe(
     let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
     let mut cwe_warnings = Vec::new();
     let symbol_map = get_symbol_map(project, &config.symbols);
+    let mut site_counts = SiteCounts::default();
     for sub in project.program.term.subs.values() {
         for (block, jump, symbol) in get_callsites(sub, &symbol_map) {
+            let warning_count = cwe_warnings.len();
             if block_contains_multiplication(block) {
                 let parms = match symbol.name.as_str() {
                     "calloc" => {
@@ -172,8 +175,14 @@ pub fn check_cwe(
                     cwe_warnings.push(generate_cwe_warning(&jump.tid, symbol));
                 }
             }
+            if cwe_warnings.len() > warning_count {
+                site_counts.add_flagged();
+            } else {
+                site_counts.add_safe();
+            }
         }
     }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (Vec::new(), cwe_warnings)
 }
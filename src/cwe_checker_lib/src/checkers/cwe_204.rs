@@ -0,0 +1,371 @@
+//! This module implements a check for CWE-204: Observable Response Discrepancy.
+//!
+//! Authentication routines that respond differently depending on the stage at which the authentication failed,
+//! e.g. with "unknown user" for non-existing users and "wrong password" for existing users,
+//! allow attackers to enumerate valid user names.
+//!
+//! See <https://cwe.mitre.org/data/definitions/204.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! This is a heuristic, informational check.
+//! Functions whose name contains one of the configured authentication patterns (e.g. `login` or `auth`)
+//! are considered authentication routines.
+//! Such a function is reported if
+//! - it references failure messages of at least two different failure stages,
+//!   e.g. a message containing "user not found" and a message containing "wrong password",
+//! - or it returns at least the configured number of distinct constant values,
+//!   e.g. `0` for success, `-1` for unknown users and `-2` for wrong passwords.
+//!
+//! The constant return values are collected per return instruction
+//! from the last assignment to the return register of the standard calling convention
+//! in the returning block or, if the block does not assign the return register,
+//! in the blocks jumping to it.
+//! Name and string comparisons are case-insensitive.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The authentication name patterns, the failure messages per failure stage
+//! and the minimal number of distinct return values are configurable in config.json.
+//!
+//! ## False Positives
+//!
+//! - The distinguishable responses may only be logged internally and never be presented to the user.
+//! - Functions may return distinct values for reasons unrelated to the failure stage,
+//!   e.g. for a locked account.
+//!
+//! ## False Negatives
+//!
+//! - Authentication routines without one of the configured name patterns are not checked.
+//!   In particular this is the case for stripped binaries.
+//! - Responses generated in helper functions or by the callers of the authentication routine are not found.
+//! - Return values that are not assigned as constants near the return instruction are not found.
+
+use crate::analysis::xref::XrefIndex;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::CweModule;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE204",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions whose name contains one of these patterns (case-insensitive) are considered authentication routines.
+    authentication_functions: Vec<String>,
+    /// Maps the name of a failure stage (e.g. `user`) to markers (case-insensitive) of failure messages for this stage.
+    failure_messages: BTreeMap<String, Vec<String>>,
+    /// The minimal number of distinct constant return values of an authentication routine to report it.
+    min_distinct_return_values: usize,
+}
+
+/// The evidence for distinguishable responses of an authentication routine.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct Responses {
+    /// The referenced failure messages, mapped to their failure stage.
+    messages: BTreeMap<String, String>,
+    /// The distinct constant return values.
+    return_values: BTreeSet<i64>,
+}
+
+/// Run the check.
+///
+/// First the authentication routines are determined by their names.
+/// Then the failure messages referenced by them and their constant return values are collected.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE204.");
+    let patterns: Vec<String> = config
+        .authentication_functions
+        .iter()
+        .map(|pattern| pattern.to_lowercase())
+        .collect();
+    let mut responses: BTreeMap<Tid, Responses> = project
+        .program
+        .term
+        .subs
+        .values()
+        .filter(|sub| {
+            let name = sub.term.name.to_lowercase();
+            patterns.iter().any(|pattern| name.contains(pattern))
+        })
+        .map(|sub| {
+            let responses = Responses {
+                messages: BTreeMap::new(),
+                return_values: get_constant_return_values(project, sub),
+            };
+            (sub.tid.clone(), responses)
+        })
+        .collect();
+    // The examined sites are the authentication functions.
+    let mut site_counts = SiteCounts::default();
+    if responses.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    collect_failure_messages(project, &config.failure_messages, &mut responses);
+
+    let mut cwe_warnings = Vec::new();
+    for (sub_tid, responses) in responses.iter() {
+        let stages: BTreeSet<&String> = responses.messages.values().collect();
+        if stages.len() >= 2 || responses.return_values.len() >= config.min_distinct_return_values {
+            site_counts.add_flagged();
+            cwe_warnings.push(generate_cwe_warning(
+                &project.program.term.subs[sub_tid],
+                responses,
+            ));
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Add the failure messages referenced by the given functions to their responses.
+fn collect_failure_messages(
+    project: &Project,
+    failure_messages: &BTreeMap<String, Vec<String>>,
+    responses: &mut BTreeMap<Tid, Responses>,
+) {
+    let xrefs = XrefIndex::new(project, None);
+    for address in xrefs.referenced_addresses() {
+        let Ok(string) = project
+            .runtime_memory_image
+            .read_string_until_null_terminator(&Bitvector::from_u64(address))
+        else {
+            continue;
+        };
+        let lowercase_string = string.to_lowercase();
+        let Some(stage) = failure_messages.iter().find_map(|(stage, markers)| {
+            markers
+                .iter()
+                .any(|marker| lowercase_string.contains(&marker.to_lowercase()))
+                .then_some(stage)
+        }) else {
+            continue;
+        };
+        for xref in xrefs.get_xrefs(address) {
+            if let Some(responses) = responses.get_mut(&xref.sub) {
+                responses.messages.insert(string.to_string(), stage.clone());
+            }
+        }
+    }
+}
+
+/// Get the distinct constant values returned by the given function.
+///
+/// For each returning block the last assignment to the return register in the block is used.
+/// If the block does not assign the return register,
+/// the last assignments in the blocks jumping to it without calling another function are used instead.
+fn get_constant_return_values(project: &Project, sub: &Term<Sub>) -> BTreeSet<i64> {
+    let mut return_values = BTreeSet::new();
+    let Some(return_register) = project
+        .get_standard_calling_convention()
+        .and_then(|calling_convention| calling_convention.integer_return_register.first())
+    else {
+        return return_values;
+    };
+    let mut predecessors: HashMap<&Tid, Vec<&Term<Blk>>> = HashMap::new();
+    for blk in sub.term.blocks.iter() {
+        for jmp in blk.term.jmps.iter() {
+            if let Jmp::Branch(target) | Jmp::CBranch { target, .. } = &jmp.term {
+                predecessors.entry(target).or_default().push(blk);
+            }
+        }
+    }
+    for blk in sub.term.blocks.iter() {
+        if !blk
+            .term
+            .jmps
+            .iter()
+            .any(|jmp| matches!(jmp.term, Jmp::Return(_)))
+        {
+            continue;
+        }
+        match get_last_assignment(blk, return_register) {
+            Some(value) => return_values.extend(get_constant(value)),
+            None => {
+                for predecessor in predecessors.get(&blk.tid).into_iter().flatten() {
+                    if predecessor
+                        .term
+                        .jmps
+                        .iter()
+                        .any(|jmp| matches!(jmp.term, Jmp::Call { .. } | Jmp::CallInd { .. }))
+                    {
+                        continue;
+                    }
+                    if let Some(value) = get_last_assignment(predecessor, return_register) {
+                        return_values.extend(get_constant(value));
+                    }
+                }
+            }
+        }
+    }
+    return_values
+}
+
+/// Get the value of the last assignment to the given register in the block.
+fn get_last_assignment<'a>(blk: &'a Term<Blk>, register: &Variable) -> Option<&'a Expression> {
+    blk.term.defs.iter().rev().find_map(|def| match &def.term {
+        Def::Assign { var, value } if var == register => Some(value),
+        _ => None,
+    })
+}
+
+/// Get the value of the expression if it is a (possibly extended) constant.
+fn get_constant(expression: &Expression) -> Option<i64> {
+    match expression {
+        Expression::Const(constant) => constant.try_to_i64().ok(),
+        Expression::Cast {
+            op: op @ (CastOpType::IntZExt | CastOpType::IntSExt),
+            size,
+            arg,
+        } => match &**arg {
+            Expression::Const(constant) => constant.cast(*op, *size).ok()?.try_to_i64().ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Generate the CWE warning for an authentication routine with distinguishable responses.
+fn generate_cwe_warning(sub: &Term<Sub>, responses: &Responses) -> CweWarning {
+    let mut evidence = Vec::new();
+    if !responses.messages.is_empty() {
+        let messages: Vec<String> = responses
+            .messages
+            .iter()
+            .map(|(message, stage)| format!("\"{message}\" ({stage})"))
+            .collect();
+        evidence.push(format!("references the messages {}", messages.join(", ")));
+    }
+    if !responses.return_values.is_empty() {
+        let values: Vec<String> = responses
+            .return_values
+            .iter()
+            .map(|value| value.to_string())
+            .collect();
+        evidence.push(format!("returns the constant values {}", values.join(", ")));
+    }
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Observable Response Discrepancy) Authentication function {} at {} may respond differently depending on the failure stage: {}",
+            sub.term.name,
+            sub.tid.address,
+            evidence.join("; ")
+        ),
+    )
+    .tids(vec![format!("{}", sub.tid)])
+    .addresses(vec![sub.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec!["severity".to_string(), "info".to_string()]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::utils::binary::MemorySegment;
+    use crate::{def, expr};
+
+    fn mock_return_block(name: &str, defs: Vec<Term<Def>>) -> Term<Blk> {
+        let mut blk = Blk::mock_with_tid(name);
+        blk.term.defs = defs;
+        blk.term.jmps.push(Term {
+            tid: Tid::new(format!("{name}_return")),
+            term: Jmp::Return(expr!("0x0:8")),
+        });
+        blk
+    }
+
+    fn mock_project() -> Project {
+        // `check_login` prints distinguishable messages,
+        // `authenticate` returns distinguishable error codes through a shared epilogue.
+        let mut check_login = Sub::mock("check_login");
+        check_login.term.blocks = vec![
+            mock_return_block("user_message", vec![def!["user: RDI:8 = 0x9000:8"]]),
+            mock_return_block("password_message", vec![def!["password: RDI:8 = 0x900f:8"]]),
+        ];
+        let mut authenticate = Sub::mock("Authenticate");
+        let mut unknown_user = Blk::mock_with_tid("unknown_user");
+        unknown_user.term.defs = vec![def!["unknown_user_code: RAX:8 = 0xffffffffffffffff:8"]];
+        unknown_user
+            .term
+            .jmps
+            .push(Jmp::branch("unknown_user_jmp", "epilogue"));
+        let mut wrong_password = Blk::mock_with_tid("wrong_password");
+        wrong_password.term.defs = vec![def!["wrong_password_code: RAX:8 = 0xfffffffffffffffe:8"]];
+        wrong_password
+            .term
+            .jmps
+            .push(Jmp::branch("wrong_password_jmp", "epilogue"));
+        authenticate.term.blocks = vec![
+            unknown_user,
+            wrong_password,
+            mock_return_block("success", vec![def!["success_code: RAX:8 = 0x0:8"]]),
+            mock_return_block("epilogue", Vec::new()),
+        ];
+        let mut parse = Sub::mock("parse_config");
+        parse.term.blocks = vec![mock_return_block(
+            "parse_message",
+            vec![def!["parse: RDI:8 = 0x9000:8"]],
+        )];
+        let mut project = mock_x64_project(&[], vec![check_login, authenticate, parse]);
+        project
+            .runtime_memory_image
+            .memory_segments
+            .push(MemorySegment {
+                bytes: b"User not found\x00Wrong password\x00".to_vec(),
+                base_address: 0x9000,
+                read_flag: true,
+                write_flag: false,
+                execute_flag: false,
+            });
+        project
+    }
+
+    #[test]
+    fn distinguishable_responses() {
+        let project = mock_project();
+        assert_eq!(
+            get_constant_return_values(
+                &project,
+                &project.program.term.subs[&Tid::new("Authenticate")]
+            ),
+            BTreeSet::from([-2, -1, 0])
+        );
+
+        let config = serde_json::json!({
+            "authentication_functions": ["auth", "login"],
+            "failure_messages": {
+                "user": ["user not found"],
+                "password": ["wrong password"]
+            },
+            "min_distinct_return_values": 3
+        });
+        let warnings = run_check(check_cwe, &project, &config);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].symbols, vec!["Authenticate"]);
+        assert!(warnings[0].description.contains("-2, -1, 0"));
+        assert_eq!(warnings[1].symbols, vec!["check_login"]);
+        assert!(warnings[1]
+            .description
+            .contains("\"User not found\" (user), \"Wrong password\" (password)"));
+    }
+}
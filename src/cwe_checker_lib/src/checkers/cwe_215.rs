@@ -20,6 +20,7 @@
 
 use crate::prelude::*;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
 use crate::CweModule;
 
 /// The module name and version
@@ -38,24 +39,32 @@ pub fn check_cwe(
     _cwe_params: &serde_json::Value,
 ) -> (Vec<LogMessage>, Vec<CweWarning>) {
     let binary = analysis_results.binary;
+    // The only examined site is the binary itself.
+    let mut site_counts = SiteCounts::default();
 
-    match goblin::Object::parse(binary) {
+    let result = match goblin::Object::parse(binary) {
         Ok(goblin::Object::Elf(elf_binary)) => {
-            for section_header in elf_binary.section_headers {
-                if let Some(section_name) = elf_binary.shdr_strtab.get_at(section_header.sh_name) {
-                    if section_name.starts_with(".debug") {
-                        let cwe_warning = CweWarning::new(
-                            CWE_MODULE.name,
-                            CWE_MODULE.version,
-                            "(Information Exposure Through Debug Information) The binary contains debug symbols."
-                        );
-                        return (Vec::new(), vec![cwe_warning]);
-                    }
-                }
+            let has_debug_section = elf_binary.section_headers.iter().any(|section_header| {
+                elf_binary
+                    .shdr_strtab
+                    .get_at(section_header.sh_name)
+                    .is_some_and(|section_name| section_name.starts_with(".debug"))
+            });
+            if has_debug_section {
+                site_counts.add_flagged();
+                let cwe_warning = CweWarning::new(
+                    CWE_MODULE.name,
+                    CWE_MODULE.version,
+                    "(Information Exposure Through Debug Information) The binary contains debug symbols."
+                );
+                (Vec::new(), vec![cwe_warning])
+            } else {
+                site_counts.add_safe();
+                (Vec::new(), Vec::new())
             }
-            (Vec::new(), Vec::new())
         }
         Ok(_) => {
+            site_counts.add_skipped();
             let info_log = LogMessage::new_info(
                 "File type not supported. Currently this check only supports ELF files.",
             )
@@ -63,9 +72,12 @@ pub fn check_cwe(
             (vec![info_log], Vec::new())
         }
         Err(err) => {
+            site_counts.add_skipped();
             let err_log = LogMessage::new_error(format!("Error while parsing binary: {err}"))
                 .source(CWE_MODULE.name);
             (vec![err_log], Vec::new())
         }
-    }
+    };
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    result
 }
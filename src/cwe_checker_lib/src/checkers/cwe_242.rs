@@ -0,0 +1,498 @@
+//! This module implements a check for CWE-242: Use of Inherently Dangerous Function
+//! in kernel and interrupt contexts.
+//!
+//! Some functions that are acceptable in user-space programs are dangerous in more restricted execution contexts.
+//! For example, copying data to user space with `copy_to_user` without validating the size leaks kernel memory,
+//! and calling non-reentrant functions like `strcpy` or allocating memory dynamically in interrupt service routines
+//! may corrupt shared state or deadlock the system.
+//!
+//! See <https://cwe.mitre.org/data/definitions/242.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! The check uses a table of rules. Each rule consists of a list of banned functions
+//! and the execution context in which they are banned:
+//! - The `kernel` context contains all functions of a Linux kernel module.
+//! - The `interrupt` context contains all functions reachable in the call graph from an interrupt handler.
+//!
+//! Interrupt handlers are detected through
+//! - their names (e.g. functions with the suffix `_IRQHandler` in CMSIS-based firmware),
+//! - the vector table at the start of the first executable memory segment of 32-bit ARM binaries,
+//! - function pointers given as handler arguments to interrupt registration functions like `request_irq`.
+//!
+//! Each call to a banned function in its corresponding context is flagged.
+//! If a rule specifies a size parameter, the call is only flagged
+//! if the Pointer Inference analysis could not determine an upper bound for the size argument,
+//! i.e. if the size was not validated before the call.
+//!
+//! ## False Positives
+//!
+//! - Functions reachable both from interrupt handlers and from normal code
+//!   may only call the banned function on paths that are not reachable from interrupt handlers.
+//! - The size may be validated in a way that the Pointer Inference analysis cannot track.
+//!
+//! ## False Negatives
+//!
+//! - Interrupt handlers that are not detected by any of the heuristics above are missed.
+//! - Indirect calls are not represented in the call graph,
+//!   so functions only reachable through indirect calls from an interrupt handler are missed.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The rule table, the interrupt registration functions together with the index of their handler parameter,
+//! the name suffixes of interrupt handlers and the number of vector table entries are configurable in config.json.
+
+use crate::abstract_domain::{TryToBitvec, TryToInterval};
+use crate::analysis::callgraph::get_program_callgraph;
+use crate::analysis::pointer_inference::PointerInference;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::parse_hex_string_to_u64;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::visit::Dfs;
+use std::collections::{BTreeMap, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE242",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The table of banned functions.
+    rules: Vec<Rule>,
+    /// Functions registering interrupt handlers,
+    /// mapped to the index of the parameter containing the handler function pointer.
+    irq_registration_symbols: BTreeMap<String, usize>,
+    /// Functions whose name ends with one of these suffixes are considered interrupt handlers.
+    interrupt_handler_suffixes: Vec<String>,
+    /// The number of vector table entries to search for interrupt handlers in 32-bit ARM binaries.
+    /// The first two entries (the initial stack pointer and the reset handler) are included in the count,
+    /// but are not considered interrupt handlers.
+    vector_table_entries: usize,
+}
+
+/// An entry of the table of banned functions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Rule {
+    /// The execution context in which the functions are banned.
+    context: ExecutionContext,
+    /// The names of the banned functions.
+    symbols: Vec<String>,
+    /// If set, calls are only banned if the parameter with this index is not bounded.
+    size_parameter: Option<usize>,
+    /// A short explanation why the functions are banned, presented to the user.
+    description: String,
+}
+
+/// The execution contexts that rules can apply to.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionContext {
+    /// All functions of a Linux kernel module.
+    Kernel,
+    /// All functions reachable from an interrupt handler.
+    Interrupt,
+}
+
+/// Run the check.
+///
+/// First the interrupt handlers of the binary are detected.
+/// Then each call to a banned function is checked against the context of its caller.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE242.");
+    let pi_result = analysis_results.pointer_inference;
+
+    let handlers = find_interrupt_handlers(project, pi_result, &config);
+    let interrupt_context = compute_interrupt_context(project, &handlers);
+    let mut site_counts = SiteCounts::default();
+    let cwe_warnings = check_rules(
+        project,
+        pi_result,
+        &config.rules,
+        &interrupt_context,
+        &mut site_counts,
+    );
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Find the TIDs of all interrupt handler functions.
+fn find_interrupt_handlers(
+    project: &Project,
+    pi_result: Option<&PointerInference>,
+    config: &Config,
+) -> Vec<Tid> {
+    let address_to_sub: HashMap<u64, &Tid> = project
+        .program
+        .term
+        .subs
+        .keys()
+        .filter_map(|tid| Some((parse_hex_string_to_u64(&tid.address).ok()?, tid)))
+        .collect();
+    let mut handlers: Vec<Tid> = project
+        .program
+        .term
+        .subs
+        .values()
+        .filter(|sub| {
+            config
+                .interrupt_handler_suffixes
+                .iter()
+                .any(|suffix| sub.term.name.ends_with(suffix.as_str()))
+        })
+        .map(|sub| sub.tid.clone())
+        .collect();
+
+    // Entries of the vector table of ARM Cortex-M binaries.
+    // The reset handler is skipped, since the whole program is reachable from it.
+    // The lowest bit of a handler address is set to indicate Thumb mode.
+    if project.cpu_architecture.to_uppercase().contains("ARM")
+        && project.get_pointer_bytesize() == ByteSize::new(4)
+    {
+        if let Some(segment) = project
+            .runtime_memory_image
+            .get_code_segments()
+            .into_iter()
+            .next()
+        {
+            for entry in segment
+                .bytes
+                .chunks_exact(4)
+                .take(config.vector_table_entries)
+                .skip(2)
+            {
+                let entry: [u8; 4] = entry.try_into().unwrap();
+                let address = if project.runtime_memory_image.is_little_endian_byte_order() {
+                    u32::from_le_bytes(entry)
+                } else {
+                    u32::from_be_bytes(entry)
+                };
+                if let Some(tid) = address_to_sub.get(&(u64::from(address) & !1)) {
+                    handlers.push((*tid).clone());
+                }
+            }
+        }
+    }
+
+    // Handlers passed to interrupt registration functions.
+    if let Some(pi_result) = pi_result {
+        let registration_names: Vec<String> =
+            config.irq_registration_symbols.keys().cloned().collect();
+        let registration_symbols = get_symbol_map(project, &registration_names);
+        for sub in project.program.term.subs.values() {
+            for blk in sub.term.blocks.iter() {
+                for jmp in blk.term.jmps.iter() {
+                    let Jmp::Call { target, .. } = &jmp.term else {
+                        continue;
+                    };
+                    let Some(symbol) = registration_symbols.get(target) else {
+                        continue;
+                    };
+                    let Some(param) = symbol
+                        .parameters
+                        .get(config.irq_registration_symbols[&symbol.name])
+                    else {
+                        continue;
+                    };
+                    let handler_address = pi_result
+                        .eval_parameter_arg_at_call(&jmp.tid, param)
+                        .and_then(|value| value.get_if_absolute_value()?.try_to_bitvec().ok())
+                        .and_then(|address| address.try_to_u64().ok());
+                    if let Some(tid) =
+                        handler_address.and_then(|address| address_to_sub.get(&address))
+                    {
+                        handlers.push((*tid).clone());
+                    }
+                }
+            }
+        }
+    }
+
+    handlers.sort();
+    handlers.dedup();
+    handlers
+}
+
+/// Compute the functions reachable from the given interrupt handlers.
+/// Each function is mapped to one of the handlers it is reachable from.
+fn compute_interrupt_context(project: &Project, handlers: &[Tid]) -> BTreeMap<Tid, Tid> {
+    let callgraph = get_program_callgraph(&project.program);
+    let mut interrupt_context = BTreeMap::new();
+    for handler in handlers {
+        let Some(start) = callgraph
+            .node_indices()
+            .find(|node| callgraph[*node] == *handler)
+        else {
+            continue;
+        };
+        let mut dfs = Dfs::new(&callgraph, start);
+        while let Some(node) = dfs.next(&callgraph) {
+            interrupt_context
+                .entry(callgraph[node].clone())
+                .or_insert_with(|| handler.clone());
+        }
+    }
+    interrupt_context
+}
+
+/// Check all calls to banned functions against the contexts of their callers.
+/// The checked calls are counted in `site_counts`.
+fn check_rules(
+    project: &Project,
+    pi_result: Option<&PointerInference>,
+    rules: &[Rule],
+    interrupt_context: &BTreeMap<Tid, Tid>,
+    site_counts: &mut SiteCounts,
+) -> Vec<CweWarning> {
+    let mut cwe_warnings = Vec::new();
+    for rule in rules {
+        let banned_symbols = get_symbol_map(project, &rule.symbols);
+        if banned_symbols.is_empty() {
+            continue;
+        }
+        for sub in project.program.term.subs.values() {
+            let handler = match rule.context {
+                ExecutionContext::Kernel if project.runtime_memory_image.is_lkm => None,
+                ExecutionContext::Kernel => continue,
+                ExecutionContext::Interrupt => match interrupt_context.get(&sub.tid) {
+                    Some(handler) => Some(&project.program.term.subs[handler]),
+                    None => continue,
+                },
+            };
+            for blk in sub.term.blocks.iter() {
+                for jmp in blk.term.jmps.iter() {
+                    let Jmp::Call { target, .. } = &jmp.term else {
+                        continue;
+                    };
+                    let Some(symbol) = banned_symbols.get(target) else {
+                        continue;
+                    };
+                    if let Some(size_index) = rule.size_parameter {
+                        if is_size_bounded(pi_result, &jmp.tid, symbol, size_index) {
+                            site_counts.add_safe();
+                            continue;
+                        }
+                    }
+                    site_counts.add_flagged();
+                    cwe_warnings.push(generate_cwe_warning(rule, sub, &jmp.tid, symbol, handler));
+                }
+            }
+        }
+    }
+    cwe_warnings
+}
+
+/// Check whether the Pointer Inference analysis found an upper bound for the size parameter of the call.
+fn is_size_bounded(
+    pi_result: Option<&PointerInference>,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    size_index: usize,
+) -> bool {
+    let Some(param) = symbol.parameters.get(size_index) else {
+        return false;
+    };
+    pi_result
+        .and_then(|pi_result| pi_result.eval_parameter_arg_at_call(call_tid, param))
+        .and_then(|size| size.get_if_absolute_value()?.try_to_offset_interval().ok())
+        .map(|(_, upper_bound)| upper_bound != i64::MAX)
+        .unwrap_or(false)
+}
+
+/// Generate the CWE warning for a call to a banned function.
+/// The interrupt handler is given if the call happens in interrupt context.
+fn generate_cwe_warning(
+    rule: &Rule,
+    sub: &Term<Sub>,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    handler: Option<&Term<Sub>>,
+) -> CweWarning {
+    let (context, mut tids, mut symbols) = match handler {
+        Some(handler) => (
+            format!("interrupt context (reachable from {})", handler.term.name),
+            vec![format!("{call_tid}"), format!("{}", handler.tid)],
+            vec![
+                symbol.name.clone(),
+                sub.term.name.clone(),
+                handler.term.name.clone(),
+            ],
+        ),
+        None => (
+            "kernel context".to_string(),
+            vec![format!("{call_tid}")],
+            vec![symbol.name.clone(), sub.term.name.clone()],
+        ),
+    };
+    tids.dedup();
+    symbols.dedup();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Use of Inherently Dangerous Function) Call to {} at {} ({}) in {context}: {}",
+            symbol.name, call_tid.address, sub.term.name, rule.description
+        ),
+    )
+    .tids(tids)
+    .addresses(vec![call_tid.address.clone()])
+    .symbols(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::mock_x64_project;
+    use crate::utils::binary::MemorySegment;
+
+    /// Get the TID of the mock function with the given name.
+    /// Unknown names are treated as extern symbols.
+    fn mock_tid(name: &str) -> Tid {
+        let mut tid = Tid::new(name);
+        tid.address = match name {
+            "timer_isr" => "0x8000100",
+            "helper" => "0x8000180",
+            "main" => "0x8000200",
+            "USART1_IRQHandler" => "0x8000300",
+            "format" => "0x8000380",
+            "ioctl" => "0x1000",
+            _ => return tid,
+        }
+        .to_string();
+        tid
+    }
+
+    fn mock_sub(name: &str, calls: &[&str]) -> Term<Sub> {
+        let mut sub = Sub::mock(name);
+        sub.tid = mock_tid(name);
+        let mut blk = Blk::mock_with_tid(&format!("{name}_blk"));
+        for (i, target) in calls.iter().enumerate() {
+            blk.term.jmps.push(Term {
+                tid: Tid::new(format!("{name}_call_{i}")),
+                term: Jmp::Call {
+                    target: mock_tid(target),
+                    return_: None,
+                },
+            });
+        }
+        sub.term.blocks.push(blk);
+        sub
+    }
+
+    fn mock_config() -> Config {
+        Config {
+            rules: vec![Rule {
+                context: ExecutionContext::Interrupt,
+                symbols: vec!["strcpy".to_string()],
+                size_parameter: None,
+                description: "strcpy is not reentrant".to_string(),
+            }],
+            irq_registration_symbols: BTreeMap::new(),
+            interrupt_handler_suffixes: vec!["_IRQHandler".to_string()],
+            vector_table_entries: 4,
+        }
+    }
+
+    #[test]
+    fn interrupt_context() {
+        let mut project = Project::mock_arm32();
+        let strcpy = ExternSymbol::mock_arm32("strcpy");
+        project
+            .program
+            .term
+            .extern_symbols
+            .insert(strcpy.tid.clone(), strcpy);
+        // `helper` is reachable from the vector table entry `timer_isr`,
+        // `format` is reachable from `USART1_IRQHandler`.
+        for sub in [
+            mock_sub("main", &["format"]),
+            mock_sub("timer_isr", &["helper"]),
+            mock_sub("helper", &["strcpy"]),
+            mock_sub("USART1_IRQHandler", &["format"]),
+            mock_sub("format", &["strcpy"]),
+        ] {
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        // Initial stack pointer, reset handler and one interrupt handler.
+        let mut vector_table = Vec::new();
+        for entry in [0x2000_1000u32, 0x0800_0201, 0x0800_0101] {
+            vector_table.extend(entry.to_le_bytes());
+        }
+        project.runtime_memory_image.memory_segments = vec![MemorySegment {
+            bytes: vector_table,
+            base_address: 0x0800_0000,
+            read_flag: true,
+            write_flag: false,
+            execute_flag: true,
+        }];
+        let config = mock_config();
+
+        let handlers = find_interrupt_handlers(&project, None, &config);
+        assert_eq!(
+            handlers,
+            vec![mock_tid("USART1_IRQHandler"), mock_tid("timer_isr")]
+        );
+        let interrupt_context = compute_interrupt_context(&project, &handlers);
+        assert_eq!(interrupt_context.len(), 4);
+        assert!(!interrupt_context.contains_key(&mock_tid("main")));
+
+        let mut site_counts = SiteCounts::default();
+        let warnings = check_rules(
+            &project,
+            None,
+            &config.rules,
+            &interrupt_context,
+            &mut site_counts,
+        );
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(site_counts.examined - site_counts.safe, 2);
+        assert_eq!(
+            warnings[0].symbols,
+            vec!["strcpy", "format", "USART1_IRQHandler"]
+        );
+        assert_eq!(warnings[1].tids, vec!["helper_call_0", "timer_isr"]);
+    }
+
+    #[test]
+    fn kernel_context() {
+        let mut project = mock_x64_project(
+            &["copy_to_user"],
+            vec![mock_sub("ioctl", &["copy_to_user"])],
+        );
+        let copy_to_user = project
+            .program
+            .term
+            .extern_symbols
+            .get_mut(&Tid::new("copy_to_user"))
+            .unwrap();
+        copy_to_user.parameters.push(Arg::mock_register("RSI", 8));
+        copy_to_user.parameters.push(Arg::mock_register("RDX", 8));
+        let rules = vec![Rule {
+            context: ExecutionContext::Kernel,
+            symbols: vec!["copy_to_user".to_string()],
+            size_parameter: Some(2),
+            description: "size is not validated".to_string(),
+        }];
+
+        let mut site_counts = SiteCounts::default();
+        assert!(check_rules(&project, None, &rules, &BTreeMap::new(), &mut site_counts).is_empty());
+        project.runtime_memory_image.is_lkm = true;
+        let warnings = check_rules(&project, None, &rules, &BTreeMap::new(), &mut site_counts);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].symbols, vec!["copy_to_user", "ioctl"]);
+    }
+}
@@ -30,6 +30,7 @@ use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::graph_utils::is_sink_call_reachable_from_source_call;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
 use crate::utils::symbol_utils::find_symbol;
 use crate::CweModule;
 
@@ -132,9 +133,14 @@ pub fn check_cwe(
         })
         .collect();
 
+    let mut site_counts = SiteCounts::default();
     let chroot_tid = match find_symbol(&project.program, "chroot") {
         Some((tid, _)) => tid.clone(),
-        None => return (Vec::new(), Vec::new()), // chroot is never called by the program
+        None => {
+            // chroot is never called by the program
+            analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+            return (Vec::new(), Vec::new());
+        }
     };
 
     let mut cwe_warnings = Vec::new();
@@ -164,16 +170,23 @@ pub fn check_cwe(
                             &chdir_tid,
                             &priviledge_dropping_tids[..],
                         ) {
+                            site_counts.add_flagged();
                             cwe_warnings.push(generate_cwe_warning(sub, &callsite_tid));
+                        } else {
+                            site_counts.add_safe();
                         }
+                    } else {
+                        site_counts.add_safe();
                     }
                 } else {
                     // There is no chdir symbol, so the chroot jail cannot be secured.
+                    site_counts.add_flagged();
                     cwe_warnings.push(generate_cwe_warning(sub, &callsite_tid));
                 }
             }
         }
     }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (Vec::new(), cwe_warnings)
 }
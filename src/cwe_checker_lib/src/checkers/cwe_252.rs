@@ -32,6 +32,9 @@
 //! - Taint reaches a return site of a function without any taint being returned
 //!   to the caller. Here, the caller of the function cannot know if the API
 //!   call was successful.
+//! - A tainted value is used as the address of a memory access. Here, the
+//!   program assumes that the API call was successful and the access may fault
+//!   if it was not, e.g., when `fgets` returned a NULL pointer.
 //!
 //! Taint propagation is stopped along paths as soon as a conditional control
 //! flow transfer depends on a tainted value.
@@ -66,7 +69,9 @@ use crate::analysis::pointer_inference::PointerInference;
 use crate::intermediate_representation::{ExternSymbol, Jmp, Project, Term};
 use crate::pipeline::AnalysisResults;
 use crate::prelude::*;
+use crate::utils::cancellation::CancellationToken;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
 use crate::utils::symbol_utils;
 use crate::CweModule;
 
@@ -84,7 +89,7 @@ use isolated_returns::*;
 /// CWE-252: Unchecked Return Value.
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE252",
-    version: "0.1",
+    version: "0.2",
     run: check_cwe,
 };
 
@@ -173,6 +178,8 @@ struct CweAnalysis<'a, 'b: 'a> {
     isolated_returns: Arc<IsolatedReturns<'a>>,
     project: &'a Project,
     pi_result: &'a PointerInference<'b>,
+    /// Stops the analysis once it is cancelled.
+    cancellation_token: Option<&'a CancellationToken>,
     /// Used to collect CWE warnings sent by the analyses for inidividual calls.
     cwe_collector: crossbeam_channel::Receiver<CweWarning>,
     /// Given to analyses for inidividual calls to send their CWE warnings.
@@ -190,6 +197,7 @@ impl<'a, 'b: 'a> CweAnalysis<'a, 'b> {
             isolated_returns: Arc::new(get_isolated_returns(cfg)),
             project: analysis_results.project,
             pi_result: analysis_results.pointer_inference.unwrap(),
+            cancellation_token: analysis_results.cancellation_token,
             cwe_collector: channel.1,
             cwe_sender_proto: channel.0,
         }
@@ -218,27 +226,52 @@ impl<'a, 'b: 'a> CweAnalysis<'a, 'b> {
         })
     }
 
-    /// Runs the CWE252 analysis and returns the generated warnings.
-    fn run(mut self) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    /// Runs the CWE252 analysis and returns the generated warnings
+    /// together with the counts of the analyzed calls.
+    ///
+    /// Calls without a warning are counted as safe,
+    /// since their return values were checked on all analyzed paths.
+    fn run(mut self) -> (Vec<LogMessage>, Vec<CweWarning>, SiteCounts) {
+        let cancellation_token = self.cancellation_token;
+        let examined_calls: Vec<String> = self
+            .worklist
+            .calls
+            .iter()
+            .map(|call| format!("{}", call.jmp.tid))
+            .collect();
         while let Some((isolated_returns, ta_comp_ctx)) = self.next_call_ctx() {
             let mut ta_comp = ta_comp_ctx.into_computation();
+            ta_comp.set_cancellation_token(cancellation_token);
 
             ta_comp.compute_with_max_steps(100);
 
             isolated_returns.analyze(&ta_comp);
         }
 
-        (
-            Vec::new(),
-            self.cwe_collector
-                .try_iter()
-                // FIXME: It would be nice to preerve all reasons during
-                // deduplication.
-                .map(|msg| (msg.tids.clone(), msg))
-                .collect::<BTreeMap<_, _>>()
-                .into_values()
-                .collect(),
-        )
+        let cwe_warnings: Vec<CweWarning> = self
+            .cwe_collector
+            .try_iter()
+            // FIXME: It would be nice to preerve all reasons during
+            // deduplication.
+            .map(|msg| (msg.tids.clone(), msg))
+            .collect::<BTreeMap<_, _>>()
+            .into_values()
+            .collect();
+        // The first TID of each warning is the TID of the call.
+        let flagged_calls: HashSet<&String> = cwe_warnings
+            .iter()
+            .filter_map(|warning| warning.tids.first())
+            .collect();
+        let mut site_counts = SiteCounts::default();
+        for call in examined_calls.iter() {
+            if flagged_calls.contains(call) {
+                site_counts.add_flagged();
+            } else {
+                site_counts.add_safe();
+            }
+        }
+
+        (Vec::new(), cwe_warnings, site_counts)
     }
 }
 
@@ -281,5 +314,8 @@ pub fn check_cwe(
     let config: Config =
         serde_json::from_value(cwe_params.clone()).expect("CWE252: invalid configuration");
 
-    CweAnalysis::new(analysis_results, config).run()
+    let (logs, cwe_warnings, site_counts) = CweAnalysis::new(analysis_results, config).run();
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (logs, cwe_warnings)
 }
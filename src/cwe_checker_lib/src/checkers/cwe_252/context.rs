@@ -15,7 +15,7 @@ use crate::analysis::pointer_inference::{Data as PiData, PointerInference};
 use crate::analysis::taint::state::State as TaState;
 use crate::analysis::taint::TaintAnalysis;
 use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
-use crate::intermediate_representation::{Blk, ExternSymbol, Jmp, Project, Term, Tid};
+use crate::intermediate_representation::{Blk, Def, ExternSymbol, Jmp, Project, Term, Tid};
 use crate::utils::debug::ToJsonCompact;
 use crate::utils::log::CweWarning;
 
@@ -153,6 +153,32 @@ impl<'a> TaintAnalysis<'a> for TaComputationContext<'a, '_> {
         None
     }
 
+    /// Generates a CWE warning when a tainted value is used as a memory address.
+    ///
+    /// If the return value of a fallible call is dereferenced before it was
+    /// checked, the program assumes that the call has succeeded. On failure
+    /// the dereference may fault, e.g., if `fgets` returned a NULL pointer.
+    /// Since the program does not check the return value before this point,
+    /// the taint propagation stops here.
+    fn update_def_post(
+        &self,
+        old_state: &TaState,
+        new_state: TaState,
+        def: &Term<Def>,
+    ) -> Option<TaState> {
+        match &def.term {
+            Def::Load { address, .. } | Def::Store { address, .. }
+                if old_state.eval(address).is_tainted() =>
+            {
+                self.generate_cwe_warning(&def.tid, "deref_before_check");
+
+                None
+            }
+            _ if new_state.is_empty() => self.handle_empty_state_out(&def.tid),
+            _ => Some(new_state),
+        }
+    }
+
     /// Update taint state on call to extern function.
     ///
     /// We almost always just want to remove the taint from non-callee-saved
@@ -0,0 +1,611 @@
+//! This module implements a check for CWE-266: Incorrect Privilege Assignment.
+//!
+//! Programs that need a single privileged operation often request far more privileges than necessary.
+//! On Linux this happens in two ways:
+//! Capability-aware programs grant themselves broad capabilities like `CAP_SYS_ADMIN`,
+//! which are nearly equivalent to full root privileges.
+//! Setuid-root programs keep their root privileges while processing untrusted input
+//! instead of dropping them after the privileged initialization.
+//! Any vulnerability in such a program then yields the unnecessary privileges to an attacker.
+//!
+//! See <https://cwe.mitre.org/data/definitions/266.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [Pointer Inference analysis](`crate::analysis::pointer_inference`)
+//! we check the following calls:
+//! - For calls to `prctl(PR_CAP_AMBIENT, PR_CAP_AMBIENT_RAISE, cap, ...)` a warning is generated
+//!   if the raised capability is contained in the policy table of broad capabilities.
+//! - For calls to `capset` the effective, permitted and inheritable capability sets
+//!   are read from the data structure passed to the function.
+//!   A warning is generated if the union of the sets contains a broad capability
+//!   or more than the configured maximal number of capabilities.
+//!
+//! If the binary calls a privilege dropping function (e.g. `setuid`) or is configured to be setuid-root,
+//! we also check whether the main loop of the `main` function can be reached without dropping privileges.
+//! The main loop is a loop in `main` that calls a function typically used for waiting for input, e.g. `accept` or `poll`,
+//! either directly or through a function called in the loop.
+//! Calls to privilege dropping functions with a parameter that is the constant zero (i.e. root) do not drop privileges.
+//! A call to a function that (transitively) calls a privilege dropping function counts as dropping privileges.
+//! A warning is generated if there is a path from the start of `main` to the main loop without such a call.
+//!
+//! ## False Positives
+//!
+//! - Broad capabilities may be needed by the program, e.g. for system management tools.
+//! - Privileges may be dropped by other means than the configured functions,
+//!   e.g. by a call to `capset` removing all capabilities or by executing another program.
+//! - The path without a privilege drop may be infeasible, e.g. if a command line option
+//!   that skips the drop can only be given by root.
+//!
+//! ## False Negatives
+//!
+//! - Capability sets that are not known constants at the call are not checked.
+//! - Capabilities granted by the libcap functions (e.g. `cap_set_proc`) or by file capabilities are not checked.
+//! - Only the function named `main` is checked for a main loop, so stripped binaries are not checked.
+//! - A function calling a privilege dropping function only on some of its paths counts as dropping privileges.
+//! - Privileges regained after dropping them (e.g. by `seteuid(0)`) are not considered.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! - `broad_capabilities`: The policy table of capabilities that should not be granted, with their capability numbers.
+//! - `max_capabilities`: The maximal number of capabilities that may be granted by a single call to `capset`.
+//! - `privilege_dropping_symbols`: Functions dropping root privileges if none of their parameters is zero.
+//! - `main_loop_symbols`: Functions whose calls identify the main loop of a program.
+//! - `assume_setuid_root`: Check the main loop also if the binary never calls a privilege dropping function.
+
+use crate::abstract_domain::{IntervalDomain, SizedDomain, TryToBitvec};
+use crate::analysis::pointer_inference::{Data, PointerInference};
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::graph_utils::get_successor_block;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::{get_callsites, get_constant_parameter, get_symbol_map};
+use crate::CweModule;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE266",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The capabilities that should not be granted, together with their capability numbers.
+    broad_capabilities: BTreeMap<String, u64>,
+    /// The maximal number of capabilities that may be granted by a single call to `capset`.
+    max_capabilities: u32,
+    /// Functions dropping root privileges if none of their parameters is zero.
+    privilege_dropping_symbols: Vec<String>,
+    /// Functions whose calls identify the main loop of a program.
+    main_loop_symbols: Vec<String>,
+    /// Check the main loop even if the binary never calls a privilege dropping function.
+    assume_setuid_root: bool,
+}
+
+/// The `prctl` option for changing the ambient capability set.
+const PR_CAP_AMBIENT: u64 = 47;
+/// The `PR_CAP_AMBIENT` sub-option for adding a capability to the ambient capability set.
+const PR_CAP_AMBIENT_RAISE: u64 = 2;
+/// The number of 32-bit capability sets (effective, permitted and inheritable)
+/// in each of the two data structures passed to `capset`.
+const CAPSET_SETS_PER_STRUCT: u64 = 3;
+
+/// Get the capability raised by a call to `prctl` if it adds a known capability to the ambient set.
+fn get_raised_ambient_capability(
+    project: &Project,
+    pi_result: &PointerInference,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+) -> Option<u64> {
+    let option = get_constant_parameter(project, pi_result, call_tid, symbol, 0)?;
+    let sub_option = get_constant_parameter(project, pi_result, call_tid, symbol, 1)?;
+    if option == PR_CAP_AMBIENT && sub_option == PR_CAP_AMBIENT_RAISE {
+        get_constant_parameter(project, pi_result, call_tid, symbol, 2)
+    } else {
+        None
+    }
+}
+
+/// Get the union of the capability sets passed to a call to `capset` as a bitmask.
+///
+/// The capability sets are read from the two data structures (for capabilities 0-31 and 32-63)
+/// that the second parameter points to.
+/// Sets that are not known constants are ignored.
+/// Returns `None` if none of the sets is known.
+fn get_capset_capabilities(
+    project: &Project,
+    pi_result: &PointerInference,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+) -> Option<u64> {
+    let arg = project.get_parameter_arg(symbol, 1)?;
+    let pointer: Data = pi_result.eval_parameter_arg_at_call(call_tid, &arg)?;
+    let pi_state = pi_result.get_state_at_jmp_tid(call_tid)?;
+    let mut capabilities: Option<u64> = None;
+    for index in 0..2 * CAPSET_SETS_PER_STRUCT {
+        let offset = Bitvector::from_u64(4 * index).into_resize_unsigned(pointer.bytesize());
+        let Ok(value) = pi_state.load_value_from_address(
+            &pointer.add_offset(&IntervalDomain::from(offset)),
+            ByteSize::new(4),
+            &project.runtime_memory_image,
+        ) else {
+            continue;
+        };
+        if let Some(set) = value
+            .try_to_bitvec()
+            .ok()
+            .and_then(|set| set.try_to_u64().ok())
+        {
+            let shift = 32 * (index / CAPSET_SETS_PER_STRUCT);
+            capabilities = Some(capabilities.unwrap_or(0) | (set << shift));
+        }
+    }
+    capabilities
+}
+
+/// Get the names of the broad capabilities contained in the given capability bitmask.
+fn get_broad_capabilities(config: &Config, capabilities: u64) -> Vec<String> {
+    config
+        .broad_capabilities
+        .iter()
+        .filter(|(_, number)| **number < 64 && capabilities & (1 << **number) != 0)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Generate the CWE warning for a call granting the given capabilities.
+fn generate_capability_warning(
+    sub: &Term<Sub>,
+    jmp: &Term<Jmp>,
+    symbol: &ExternSymbol,
+    capabilities: &[String],
+    num_capabilities: u32,
+) -> CweWarning {
+    let granted = if capabilities.is_empty() {
+        format!("{num_capabilities} capabilities")
+    } else {
+        format!("the capabilities {}", capabilities.join(", "))
+    };
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Incorrect Privilege Assignment) Function {} grants {} using {} at {}",
+            sub.term.name, granted, symbol.name, jmp.tid.address
+        ),
+    )
+    .tids(vec![format!("{}", jmp.tid)])
+    .addresses(vec![jmp.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![
+        std::iter::once("capabilities".to_string())
+            .chain(capabilities.iter().cloned())
+            .collect(),
+        vec!["num_capabilities".to_string(), num_capabilities.to_string()],
+    ])
+}
+
+/// Check all calls to `prctl` and `capset` for broad capabilities.
+fn check_capability_grants(
+    project: &Project,
+    pi_result: &PointerInference,
+    config: &Config,
+    site_counts: &mut SiteCounts,
+) -> Vec<CweWarning> {
+    let symbol_map = get_symbol_map(project, &["prctl".to_string(), "capset".to_string()]);
+    let mut cwe_warnings = Vec::new();
+    if symbol_map.is_empty() {
+        return cwe_warnings;
+    }
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, symbol) in get_callsites(sub, &symbol_map) {
+            if symbol.name == "prctl" {
+                let Some(capability) =
+                    get_raised_ambient_capability(project, pi_result, &jmp.tid, symbol)
+                else {
+                    // Calls with a known option do not raise an ambient capability.
+                    if get_constant_parameter(project, pi_result, &jmp.tid, symbol, 0).is_some() {
+                        site_counts.add_safe();
+                    } else {
+                        site_counts.add_skipped();
+                    }
+                    continue;
+                };
+                let capabilities = get_broad_capabilities(config, 1 << (capability % 64));
+                if !capabilities.is_empty() {
+                    site_counts.add_flagged();
+                    cwe_warnings.push(generate_capability_warning(
+                        sub,
+                        jmp,
+                        symbol,
+                        &capabilities,
+                        1,
+                    ));
+                } else {
+                    site_counts.add_safe();
+                }
+            } else if let Some(capability_set) =
+                get_capset_capabilities(project, pi_result, &jmp.tid, symbol)
+            {
+                let capabilities = get_broad_capabilities(config, capability_set);
+                let num_capabilities = capability_set.count_ones();
+                if !capabilities.is_empty() || num_capabilities > config.max_capabilities {
+                    site_counts.add_flagged();
+                    cwe_warnings.push(generate_capability_warning(
+                        sub,
+                        jmp,
+                        symbol,
+                        &capabilities,
+                        num_capabilities,
+                    ));
+                } else {
+                    site_counts.add_safe();
+                }
+            } else {
+                site_counts.add_skipped();
+            }
+        }
+    }
+    cwe_warnings
+}
+
+/// Return the TIDs of all calls in the program that drop privileges.
+///
+/// These are calls to privilege dropping functions without a zero parameter
+/// and calls to functions containing such calls.
+fn get_dropping_calls(
+    project: &Project,
+    pi_result: &PointerInference,
+    symbol_map: &HashMap<Tid, &ExternSymbol>,
+) -> HashSet<Tid> {
+    let mut dropping_calls = HashSet::new();
+    let mut dropping_subs = HashSet::new();
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, symbol) in get_callsites(sub, symbol_map) {
+            let num_parameters = usize::max(symbol.parameters.len(), 1);
+            let drops_privileges = (0..num_parameters).all(|index| {
+                get_constant_parameter(project, pi_result, &jmp.tid, symbol, index) != Some(0)
+            });
+            if drops_privileges {
+                dropping_calls.insert(jmp.tid.clone());
+                dropping_subs.insert(sub.tid.clone());
+            }
+        }
+    }
+    dropping_calls.extend(get_calls_to_subs_transitively(project, &mut dropping_subs));
+    dropping_calls
+}
+
+/// Extend the given set of functions by all functions (transitively) calling one of them
+/// and return the TIDs of all calls to functions in the set.
+fn get_calls_to_subs_transitively(project: &Project, subs: &mut HashSet<Tid>) -> HashSet<Tid> {
+    let mut calls = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for sub in project.program.term.subs.values() {
+            for jmp in sub.term.blocks.iter().flat_map(|blk| blk.term.jmps.iter()) {
+                if let Jmp::Call { target, .. } = &jmp.term {
+                    if subs.contains(target) && calls.insert(jmp.tid.clone()) {
+                        subs.insert(sub.tid.clone());
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+    calls
+}
+
+/// Return the TIDs of all calls to main loop functions
+/// and to functions (transitively) calling them.
+fn get_main_loop_calls(project: &Project, config: &Config) -> HashSet<Tid> {
+    let symbol_map = get_symbol_map(project, &config.main_loop_symbols);
+    let mut main_loop_calls = HashSet::new();
+    let mut main_loop_subs = HashSet::new();
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, _) in get_callsites(sub, &symbol_map) {
+            main_loop_calls.insert(jmp.tid.clone());
+            main_loop_subs.insert(sub.tid.clone());
+        }
+    }
+    main_loop_calls.extend(get_calls_to_subs_transitively(project, &mut main_loop_subs));
+    main_loop_calls
+}
+
+/// Get the indices of the successor blocks of each block of the function.
+fn get_block_successors(sub: &Term<Sub>) -> Vec<Vec<usize>> {
+    let block_indices: HashMap<&Tid, usize> = sub
+        .term
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(index, blk)| (&blk.tid, index))
+        .collect();
+    sub.term
+        .blocks
+        .iter()
+        .map(|blk| {
+            blk.term
+                .jmps
+                .iter()
+                .filter_map(get_successor_block)
+                .filter_map(|target| block_indices.get(target).copied())
+                .collect()
+        })
+        .collect()
+}
+
+/// Get the indices of all blocks reachable from the given start blocks,
+/// where the successors of blocks for which `is_barrier` returns true are not visited.
+fn get_reachable_blocks(
+    successors: &[Vec<usize>],
+    start: &[usize],
+    is_barrier: impl Fn(usize) -> bool,
+) -> HashSet<usize> {
+    let mut reachable: HashSet<usize> = start.iter().copied().collect();
+    let mut worklist: Vec<usize> = start.to_vec();
+    while let Some(index) = worklist.pop() {
+        if is_barrier(index) {
+            continue;
+        }
+        for successor in successors[index].iter() {
+            if reachable.insert(*successor) {
+                worklist.push(*successor);
+            }
+        }
+    }
+    reachable
+}
+
+/// Check whether the main loop of the function can be reached without dropping privileges.
+/// If yes, return the call identifying the main loop.
+fn find_main_loop_without_drop<'a>(
+    sub: &'a Term<Sub>,
+    dropping_calls: &HashSet<Tid>,
+    main_loop_calls: &HashSet<Tid>,
+) -> Option<&'a Term<Jmp>> {
+    let successors = get_block_successors(sub);
+    let reachable_without_drop = get_reachable_blocks(&successors, &[0], |index| {
+        sub.term.blocks[index]
+            .term
+            .jmps
+            .iter()
+            .any(|jmp| dropping_calls.contains(&jmp.tid))
+    });
+    sub.term
+        .blocks
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| reachable_without_drop.contains(index))
+        .filter(|(index, _)| {
+            // The block is part of a loop if it is reachable from its successors.
+            get_reachable_blocks(&successors, &successors[*index], |_| false).contains(index)
+        })
+        .find_map(|(_, blk)| {
+            blk.term
+                .jmps
+                .iter()
+                .find(|jmp| main_loop_calls.contains(&jmp.tid))
+        })
+}
+
+/// Generate the CWE warning for a main loop that can be reached without dropping privileges.
+fn generate_main_loop_warning(sub: &Term<Sub>, jmp: &Term<Jmp>) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Incorrect Privilege Assignment) The main loop of {} at {} may be reached without dropping root privileges",
+            sub.term.name, jmp.tid.address
+        ),
+    )
+    .tids(vec![format!("{}", jmp.tid)])
+    .addresses(vec![jmp.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+}
+
+/// Check whether the main loop of the `main` function can be reached without dropping privileges.
+fn check_privilege_dropping(
+    project: &Project,
+    pi_result: &PointerInference,
+    config: &Config,
+    site_counts: &mut SiteCounts,
+) -> Vec<CweWarning> {
+    let symbol_map = get_symbol_map(project, &config.privilege_dropping_symbols);
+    if symbol_map.is_empty() && !config.assume_setuid_root {
+        return Vec::new();
+    }
+    let dropping_calls = get_dropping_calls(project, pi_result, &symbol_map);
+    let main_loop_calls = get_main_loop_calls(project, config);
+    let mut cwe_warnings = Vec::new();
+    for sub in project
+        .program
+        .term
+        .subs
+        .values()
+        .filter(|sub| sub.term.name == "main" && !sub.term.blocks.is_empty())
+    {
+        match find_main_loop_without_drop(sub, &dropping_calls, &main_loop_calls) {
+            Some(jmp) => {
+                site_counts.add_flagged();
+                cwe_warnings.push(generate_main_loop_warning(sub, jmp));
+            }
+            None => site_counts.add_safe(),
+        }
+    }
+    cwe_warnings
+}
+
+/// Execute the CWE check.
+///
+/// Calls granting broad capabilities are reported,
+/// as well as main loops that can be reached without dropping root privileges.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let pi_result = analysis_results.pointer_inference.unwrap();
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE266.");
+
+    // The examined sites are the calls granting capabilities and the main functions.
+    let mut site_counts = SiteCounts::default();
+    let mut cwe_warnings = check_capability_grants(project, pi_result, &config, &mut site_counts);
+    cwe_warnings.append(&mut check_privilege_dropping(
+        project,
+        pi_result,
+        &config,
+        &mut site_counts,
+    ));
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::{def, defs, expr};
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "broad_capabilities": {"CAP_SETUID": 7, "CAP_SYS_ADMIN": 21},
+            "max_capabilities": 4,
+            "privilege_dropping_symbols": ["setuid"],
+            "main_loop_symbols": ["accept"],
+            "assume_setuid_root": false
+        })
+    }
+
+    /// Create a project containing the given extern symbols
+    /// and a function `main` consisting of the given blocks.
+    fn mock_project(symbols: &[&str], blocks: Vec<Term<Blk>>) -> Project {
+        let mut sub = Sub::mock("main");
+        sub.term.blocks = blocks;
+        mock_x64_project(symbols, vec![sub])
+    }
+
+    /// Create a block with the given definitions calling the given function.
+    fn mock_call_block(
+        name: &str,
+        defs: Vec<Term<Def>>,
+        callee: &str,
+        return_target: &str,
+    ) -> Term<Blk> {
+        let mut blk = Blk::mock_with_tid(name);
+        blk.term.defs = defs;
+        blk.term.jmps = vec![Jmp::call(
+            &format!("call_{callee}"),
+            callee,
+            Some(return_target),
+        )];
+        blk
+    }
+
+    #[test]
+    fn ambient_capabilities() {
+        for (capability, num_warnings) in [(21, 1), (10, 0)] {
+            let blk = mock_call_block(
+                "blk",
+                vec![
+                    def!["option: RDI:8 = 0x2f:8"],
+                    def!["raise: RSI:8 = 0x2:8"],
+                    def![format!("capability: RDX:8 = {capability:#x}:8")],
+                ],
+                "prctl",
+                "blk_end",
+            );
+            let project = mock_project(&["prctl"], vec![blk, Blk::mock_with_tid("blk_end")]);
+            let warnings = run_check(check_cwe, &project, &mock_config());
+            assert_eq!(warnings.len(), num_warnings);
+            if num_warnings > 0 {
+                assert_eq!(
+                    warnings[0].other[0],
+                    vec!["capabilities".to_string(), "CAP_SYS_ADMIN".to_string()]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn capset() {
+        let blk = mock_call_block(
+            "blk",
+            defs![
+                "effective: Store at RSP:8 + 0x8:8 := 0x200080:4",
+                "permitted: Store at RSP:8 + 0xc:8 := 0x200080:4",
+                "inheritable: Store at RSP:8 + 0x10:8 := 0x0:4",
+                "header: RDI:8 = RSP:8 + 0x20:8",
+                "data: RSI:8 = RSP:8 + 0x8:8"
+            ],
+            "capset",
+            "blk_end",
+        );
+        let project = mock_project(&["capset"], vec![blk, Blk::mock_with_tid("blk_end")]);
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].other[0],
+            vec![
+                "capabilities".to_string(),
+                "CAP_SETUID".to_string(),
+                "CAP_SYS_ADMIN".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn main_loop_without_privilege_drop() {
+        let mut blk_start = Blk::mock_with_tid("blk_start");
+        blk_start.term.jmps = vec![
+            Term {
+                tid: Tid::new("skip_drop"),
+                term: Jmp::CBranch {
+                    target: Tid::new("blk_loop"),
+                    condition: expr!("ZF:1"),
+                },
+            },
+            Jmp::branch("to_drop", "blk_drop"),
+        ];
+        let blk_drop = mock_call_block(
+            "blk_drop",
+            vec![def!["uid: RDI:8 = 0x3e8:8"]],
+            "setuid",
+            "blk_loop",
+        );
+        let blk_loop = mock_call_block("blk_loop", Vec::new(), "accept", "blk_loop");
+        let project = mock_project(
+            &["setuid", "accept"],
+            vec![blk_start.clone(), blk_drop.clone(), blk_loop.clone()],
+        );
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["call_accept".to_string()]);
+
+        // Without the branch skipping the privilege drop no warning is generated.
+        blk_start.term.jmps.remove(0);
+        let project = mock_project(
+            &["setuid", "accept"],
+            vec![blk_start.clone(), blk_drop, blk_loop.clone()],
+        );
+        assert!(run_check(check_cwe, &project, &mock_config()).is_empty());
+
+        // Setting the user ID to root does not drop privileges.
+        let blk_drop = mock_call_block(
+            "blk_drop",
+            vec![def!["uid: RDI:8 = 0x0:8"]],
+            "setuid",
+            "blk_loop",
+        );
+        let project = mock_project(&["setuid", "accept"], vec![blk_start, blk_drop, blk_loop]);
+        assert_eq!(run_check(check_cwe, &project, &mock_config()).len(), 1);
+    }
+}
@@ -0,0 +1,432 @@
+//! This module implements a check for CWE-277: Insecure Inherited Permissions
+//! and CWE-279: Incorrect Execution-Assigned Permissions on created IPC objects.
+//!
+//! Shared memory segments, semaphores and message queues are accessible to every process
+//! whose credentials match the permissions of the object.
+//! If a program creates such an object with permissive permissions like 0666,
+//! other users can read or manipulate the exchanged data (CWE-279).
+//! If a program opens a named object with the create flag but without the exclusive flag,
+//! it silently uses an existing object in case an attacker created it beforehand,
+//! together with the permissions chosen by the attacker (CWE-277).
+//!
+//! See <https://cwe.mitre.org/data/definitions/277.html>
+//! and <https://cwe.mitre.org/data/definitions/279.html> for detailed descriptions.
+//!
+//! ## How the check works
+//!
+//! For each call to an IPC object creation function (e.g. `shmget`, `semget`, `mq_open` or `shm_open`)
+//! we use the results of the pointer inference analysis to determine the values of the flags
+//! and the mode parameter. If the create flag is set, we generate a warning
+//! - if the mode grants one of the forbidden permissions,
+//! - or if the exclusive flag is not set and the object is not private.
+//!
+//! The name of the object is reported if it is a constant key or a constant string.
+//! System V objects created with the key `IPC_PRIVATE` (zero) cannot be opened by other processes
+//! through their key, so they are never reported for a missing exclusive flag.
+//!
+//! ### Symbols configurable in config.json
+//!
+//! - `ipc_functions`: The IPC object creation functions together with the parameter indices
+//!   of the object name, the flags and the mode and the values of the create and exclusive flags.
+//! - `forbidden_permissions`: The permission bits that must not be granted on created objects.
+//!
+//! ## False Positives
+//!
+//! - Objects that are intended to be shared with all users of the system are also flagged.
+//! - Creating a named object without the exclusive flag is not a problem
+//!   if the object resides in a directory that only trusted users can write to.
+//!
+//! ## False Negatives
+//!
+//! - Calls where the flags or the mode are not known constants are not checked.
+//! - Permissions changed later, e.g. with `shmctl` or `fchmod`, are not considered.
+//! - The process umask may further restrict the permissions of POSIX objects,
+//!   this is not taken into account.
+
+use crate::analysis::pointer_inference::PointerInference;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::{get_callsites, get_constant_parameter, get_symbol_map};
+use crate::CweModule;
+use std::collections::HashMap;
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE277",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// How the object name of an IPC object is passed to the creation function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NameKind {
+    /// A numeric System V IPC key.
+    Key,
+    /// A pointer to a string, e.g. the name of a POSIX IPC object.
+    String,
+}
+
+/// The description of an IPC object creation function in the configuration file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+struct IpcFunctionConfig {
+    /// The name of the function.
+    symbol: String,
+    /// The index of the parameter containing the object name.
+    name_parameter: usize,
+    /// How the object name is passed to the function.
+    name_kind: NameKind,
+    /// The index of the parameter containing the flags.
+    flags_parameter: usize,
+    /// The index of the parameter containing the mode.
+    /// The lowest nine bits of its value are the permission bits.
+    mode_parameter: usize,
+    /// The value of the create flag as an octal number.
+    create_flag: String,
+    /// The value of the exclusive flag as an octal number.
+    exclusive_flag: String,
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The IPC object creation functions.
+    ipc_functions: Vec<IpcFunctionConfig>,
+    /// The permission bits that must not be granted on created objects as an octal number.
+    forbidden_permissions: String,
+}
+
+/// An IPC object creation function with parsed flag values.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct IpcFunction {
+    name_parameter: usize,
+    name_kind: NameKind,
+    flags_parameter: usize,
+    mode_parameter: usize,
+    create_flag: u64,
+    exclusive_flag: u64,
+}
+
+impl IpcFunction {
+    /// Parse the flag values of the function from the configuration.
+    fn from_config(config: &IpcFunctionConfig) -> Result<IpcFunction, Error> {
+        Ok(IpcFunction {
+            name_parameter: config.name_parameter,
+            name_kind: config.name_kind,
+            flags_parameter: config.flags_parameter,
+            mode_parameter: config.mode_parameter,
+            create_flag: parse_octal_string(&config.create_flag)?,
+            exclusive_flag: parse_octal_string(&config.exclusive_flag)?,
+        })
+    }
+}
+
+/// Parse an octal number with an optional `0o` prefix.
+fn parse_octal_string(value: &str) -> Result<u64, Error> {
+    u64::from_str_radix(value.trim_start_matches("0o"), 8)
+        .map_err(|_| anyhow!("Invalid octal number: {value}"))
+}
+
+/// The permission bits of a mode value.
+const PERMISSION_BITS: u64 = 0o777;
+
+/// Get a textual representation of the name of the IPC object created by the call.
+///
+/// Returns `None` for System V objects created with the key `IPC_PRIVATE`.
+/// Returns `Some("unknown")` if the name is not a known constant.
+fn get_object_name(
+    project: &Project,
+    pi_result: &PointerInference,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    function: &IpcFunction,
+) -> Option<String> {
+    let value = get_constant_parameter(
+        project,
+        pi_result,
+        call_tid,
+        symbol,
+        function.name_parameter,
+    );
+    match (function.name_kind, value) {
+        (NameKind::Key, Some(0)) => None,
+        (NameKind::Key, Some(key)) => Some(format!("key {key:#x}")),
+        (NameKind::String, Some(address)) => Some(
+            project
+                .runtime_memory_image
+                .read_string_until_null_terminator(&Bitvector::from_u64(address))
+                .map(|name| format!("\"{name}\""))
+                .unwrap_or_else(|_| "unknown".to_string()),
+        ),
+        (_, None) => Some("unknown".to_string()),
+    }
+}
+
+/// Generate the CWE warning for an IPC object that is created with forbidden permissions.
+fn generate_permission_warning(
+    sub: &Term<Sub>,
+    jmp: &Term<Jmp>,
+    symbol: &ExternSymbol,
+    object_name: &str,
+    permissions: u64,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Incorrect Execution-Assigned Permissions) Function {} creates IPC object {} with permissions {:#o} using {} at {}",
+            sub.term.name, object_name, permissions, symbol.name, jmp.tid.address
+        ),
+    )
+    .tids(vec![format!("{}", jmp.tid)])
+    .addresses(vec![jmp.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![
+        vec!["object_name".to_string(), object_name.to_string()],
+        vec!["permissions".to_string(), format!("{permissions:#o}")],
+    ])
+}
+
+/// Generate the CWE warning for a named IPC object that is opened with the create flag
+/// but without the exclusive flag.
+fn generate_inherited_permission_warning(
+    sub: &Term<Sub>,
+    jmp: &Term<Jmp>,
+    symbol: &ExternSymbol,
+    object_name: &str,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Insecure Inherited Permissions) Function {} creates IPC object {} using {} at {} without the exclusive flag, so the permissions of an existing object are inherited",
+            sub.term.name, object_name, symbol.name, jmp.tid.address
+        ),
+    )
+    .tids(vec![format!("{}", jmp.tid)])
+    .addresses(vec![jmp.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec![
+        "object_name".to_string(),
+        object_name.to_string(),
+    ]])
+}
+
+/// Check a call to an IPC object creation function and generate the corresponding CWE warnings.
+fn check_call(
+    project: &Project,
+    pi_result: &PointerInference,
+    sub: &Term<Sub>,
+    jmp: &Term<Jmp>,
+    symbol: &ExternSymbol,
+    function: &IpcFunction,
+    forbidden_permissions: u64,
+    site_counts: &mut SiteCounts,
+) -> Vec<CweWarning> {
+    let mut warnings = Vec::new();
+    let Some(flags) = get_constant_parameter(
+        project,
+        pi_result,
+        &jmp.tid,
+        symbol,
+        function.flags_parameter,
+    ) else {
+        site_counts.add_skipped();
+        return warnings;
+    };
+    if flags & function.create_flag == 0 {
+        // Only existing objects are opened.
+        site_counts.add_safe();
+        return warnings;
+    }
+    let object_name = get_object_name(project, pi_result, &jmp.tid, symbol, function);
+    let display_name = object_name.as_deref().unwrap_or("IPC_PRIVATE");
+    let mode = get_constant_parameter(
+        project,
+        pi_result,
+        &jmp.tid,
+        symbol,
+        function.mode_parameter,
+    );
+    if let Some(mode) = mode {
+        let permissions = mode & PERMISSION_BITS;
+        if permissions & forbidden_permissions != 0 {
+            warnings.push(generate_permission_warning(
+                sub,
+                jmp,
+                symbol,
+                display_name,
+                permissions,
+            ));
+        }
+    }
+    if let Some(object_name) = object_name {
+        if flags & function.exclusive_flag == 0 {
+            warnings.push(generate_inherited_permission_warning(
+                sub,
+                jmp,
+                symbol,
+                &object_name,
+            ));
+        }
+    }
+    if !warnings.is_empty() {
+        site_counts.add_flagged();
+    } else if mode.is_none() {
+        site_counts.add_skipped();
+    } else {
+        site_counts.add_safe();
+    }
+    warnings
+}
+
+/// Execute the CWE check.
+///
+/// For each call to an IPC object creation function we check the constant flags and mode parameters
+/// and generate CWE warnings for forbidden permissions and for missing exclusive flags.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let pi_result = analysis_results.pointer_inference.unwrap();
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE277.");
+    let forbidden_permissions = parse_octal_string(&config.forbidden_permissions)
+        .expect("Invalid forbidden permissions inside config.json for CWE277.");
+    let functions: HashMap<String, IpcFunction> = config
+        .ipc_functions
+        .iter()
+        .map(|function_config| {
+            let function = IpcFunction::from_config(function_config)
+                .expect("Invalid IPC function inside config.json for CWE277.");
+            (function_config.symbol.clone(), function)
+        })
+        .collect();
+    let symbol_names: Vec<String> = functions.keys().cloned().collect();
+    let symbol_map = get_symbol_map(project, &symbol_names);
+    let mut cwe_warnings = Vec::new();
+    let mut site_counts = SiteCounts::default();
+    if symbol_map.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), cwe_warnings);
+    }
+
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, symbol) in get_callsites(sub, &symbol_map) {
+            cwe_warnings.append(&mut check_call(
+                project,
+                pi_result,
+                sub,
+                jmp,
+                symbol,
+                &functions[&symbol.name],
+                forbidden_permissions,
+                &mut site_counts,
+            ));
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::def;
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "ipc_functions": [
+                {
+                    "symbol": "shmget",
+                    "name_parameter": 0,
+                    "name_kind": "Key",
+                    "flags_parameter": 2,
+                    "mode_parameter": 2,
+                    "create_flag": "0o1000",
+                    "exclusive_flag": "0o2000"
+                },
+                {
+                    "symbol": "shm_open",
+                    "name_parameter": 0,
+                    "name_kind": "String",
+                    "flags_parameter": 1,
+                    "mode_parameter": 2,
+                    "create_flag": "0o100",
+                    "exclusive_flag": "0o200"
+                }
+            ],
+            "forbidden_permissions": "0o002"
+        })
+    }
+
+    /// Create a project with a function containing a call to the given symbol
+    /// after the given parameter assignments.
+    fn mock_project(symbol_name: &str, defs: Vec<Term<Def>>) -> Project {
+        let mut blk = Blk::mock_with_tid("blk");
+        blk.term.defs = defs;
+        blk.term
+            .jmps
+            .push(Jmp::call("call_ipc", symbol_name, Some("blk_end")));
+        let mut sub = Sub::mock("create_ipc_object");
+        sub.term.blocks = vec![blk, Blk::mock_with_tid("blk_end")];
+        mock_x64_project(&[symbol_name], vec![sub])
+    }
+
+    #[test]
+    fn permissive_shared_memory() {
+        // shmget(0x1234, 0x1000, IPC_CREAT | 0666)
+        let project = mock_project(
+            "shmget",
+            vec![
+                def!["key: RDI:8 = 0x1234:8"],
+                def!["size: RSI:8 = 0x1000:8"],
+                def!["flags: RDX:8 = 0x3b6:8"],
+            ],
+        );
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0]
+            .description
+            .starts_with("(Incorrect Execution-Assigned Permissions)"));
+        assert_eq!(warnings[0].other[1][1], "0o666");
+        assert!(warnings[1]
+            .description
+            .starts_with("(Insecure Inherited Permissions)"));
+        assert_eq!(warnings[1].other[0][1], "key 0x1234");
+
+        // shmget(IPC_PRIVATE, 0x1000, IPC_CREAT | 0600)
+        let project = mock_project(
+            "shmget",
+            vec![
+                def!["key: RDI:8 = 0x0:8"],
+                def!["size: RSI:8 = 0x1000:8"],
+                def!["flags: RDX:8 = 0x380:8"],
+            ],
+        );
+        assert!(run_check(check_cwe, &project, &mock_config()).is_empty());
+    }
+
+    #[test]
+    fn exclusive_posix_shared_memory() {
+        // shm_open(name, O_CREAT | O_EXCL | O_RDWR, 0600)
+        let project = mock_project(
+            "shm_open",
+            vec![def!["flags: RSI:8 = 0xc2:8"], def!["mode: RDX:8 = 0x180:8"]],
+        );
+        assert!(run_check(check_cwe, &project, &mock_config()).is_empty());
+
+        // shm_open(name, O_CREAT | O_RDWR, 0600)
+        let project = mock_project(
+            "shm_open",
+            vec![def!["flags: RSI:8 = 0x42:8"], def!["mode: RDX:8 = 0x180:8"]],
+        );
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].other[0][1], "unknown");
+    }
+}
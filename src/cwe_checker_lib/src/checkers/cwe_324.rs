@@ -0,0 +1,627 @@
+//! This module implements a check for CWE-324: Use of a Key Past its Expiration Date.
+//!
+//! Firmware often contains hard-coded X.509 certificates that are used to pin the certificate of a server
+//! or as trust anchors for the verification of other certificates.
+//! Once such a certificate expires, the device either can no longer establish connections
+//! or the expiration date has to be ignored during the verification,
+//! which means that the key is used past its expiration date.
+//! Since the certificates cannot be replaced without a firmware update,
+//! the expiration should be noticed before the firmware is shipped.
+//!
+//! See <https://cwe.mitre.org/data/definitions/324.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! The readable memory segments of the binary are searched for X.509 certificates,
+//! both in the PEM format (enclosed in `-----BEGIN CERTIFICATE-----` and `-----END CERTIFICATE-----`)
+//! and in the DER format.
+//! The validity period of each found certificate is parsed.
+//! A warning is generated for each certificate that is already expired
+//! or that expires within the configured number of days.
+//! The expiration is checked against the current system time
+//! unless a fixed reference time is configured.
+//!
+//! The warnings contain the instructions referencing the certificate
+//! and the calls to the configured certificate handling functions in the functions referencing the certificate,
+//! which are the places where the certificate is loaded for the verification.
+//!
+//! ## False Positives
+//!
+//! - Expired certificates that are not used (e.g. leftovers of test code) are flagged.
+//! - Expired certificates in a chain may be irrelevant if a certificate higher up the chain is trusted.
+//!
+//! ## False Negatives
+//!
+//! - Public keys without a certificate (e.g. `-----BEGIN PUBLIC KEY-----`) have no expiration date and are not checked.
+//! - Certificates that are compressed, encrypted or split into several parts in the binary are not found.
+//! - Certificates with a malformed or unusual encoding may not be parsed.
+//!
+//! ## Configuration
+//!
+//! The number of days before the expiration from which on certificates are reported,
+//! the certificate handling functions and an optional fixed reference time
+//! (as Unix timestamp) are configurable in config.json.
+
+use crate::analysis::xref::XrefIndex;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE324",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The first line of a certificate in the PEM format.
+const PEM_BEGIN: &[u8] = b"-----BEGIN CERTIFICATE-----";
+/// The last line of a certificate in the PEM format.
+const PEM_END: &[u8] = b"-----END CERTIFICATE-----";
+/// The DER encoding of the object identifier of the common name attribute (2.5.4.3).
+const COMMON_NAME_OID: &[u8] = &[0x06, 0x03, 0x55, 0x04, 0x03];
+/// The number of seconds of a day.
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Certificates expiring within this number of days after the reference time are reported.
+    expiry_warning_days: u64,
+    /// Functions that load or verify certificates.
+    certificate_symbols: Vec<String>,
+    /// The Unix timestamp against which the expiration is checked.
+    /// If not set, the current system time is used.
+    #[serde(default)]
+    reference_time: Option<i64>,
+}
+
+/// The encoding of a certificate in the binary.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Encoding {
+    Pem,
+    Der,
+}
+
+/// A certificate found in the memory image of the binary.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct FoundCertificate {
+    /// The address of the first byte of the certificate.
+    address: u64,
+    /// The length of the certificate in the binary in bytes.
+    length: u64,
+    /// The encoding of the certificate.
+    encoding: Encoding,
+    /// The common name of the subject of the certificate, if it has one.
+    common_name: Option<String>,
+    /// The start of the validity period as Unix timestamp.
+    not_before: i64,
+    /// The end of the validity period as Unix timestamp.
+    not_after: i64,
+}
+
+/// Read the DER encoded tag-length-value element at the start of the given bytes.
+///
+/// Returns the tag, the content and the remaining bytes after the element.
+fn read_tlv(bytes: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = bytes.split_first()?;
+    let (&first_length_byte, rest) = rest.split_first()?;
+    let (length, rest) = if first_length_byte < 0x80 {
+        (first_length_byte as usize, rest)
+    } else {
+        let num_length_bytes = (first_length_byte & 0x7f) as usize;
+        if num_length_bytes == 0 || num_length_bytes > 4 || rest.len() < num_length_bytes {
+            return None;
+        }
+        let length = rest[..num_length_bytes]
+            .iter()
+            .fold(0usize, |length, byte| (length << 8) | *byte as usize);
+        (length, &rest[num_length_bytes..])
+    };
+    if rest.len() < length {
+        return None;
+    }
+    Some((tag, &rest[..length], &rest[length..]))
+}
+
+/// Read the DER encoded element at the start of the given bytes if it has the given tag.
+///
+/// Returns the content and the remaining bytes after the element.
+fn read_element(bytes: &[u8], expected_tag: u8) -> Option<(&[u8], &[u8])> {
+    let (tag, content, rest) = read_tlv(bytes)?;
+    (tag == expected_tag).then_some((content, rest))
+}
+
+/// Get the number of days since the Unix epoch of the given date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Get the date of the proleptic Gregorian calendar for the given number of days since the Unix epoch.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Format the given Unix timestamp as date.
+fn format_date(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(SECONDS_PER_DAY));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Parse a DER encoded `UTCTime` or `GeneralizedTime` to a Unix timestamp.
+fn parse_time(tag: u8, content: &[u8]) -> Option<i64> {
+    let text = std::str::from_utf8(content).ok()?;
+    let text = text.strip_suffix('Z')?;
+    let (year, text) = match tag {
+        // UTCTime: two-digit years from 1950 to 2049
+        0x17 => {
+            let year: i64 = text.get(..2)?.parse().ok()?;
+            (
+                if year < 50 { 2000 + year } else { 1900 + year },
+                &text[2..],
+            )
+        }
+        // GeneralizedTime
+        0x18 => (text.get(..4)?.parse().ok()?, &text[4..]),
+        _ => return None,
+    };
+    if text.len() < 10 || !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let field = |index: usize| -> i64 { text[2 * index..2 * index + 2].parse().unwrap() };
+    let (month, day, hour, minute) = (field(0), field(1), field(2), field(3));
+    let second = if text.len() >= 12 { field(4) } else { 0 };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second)
+}
+
+/// Get the common name in the given DER encoded distinguished name.
+fn parse_common_name(name: &[u8]) -> Option<String> {
+    let position = name
+        .windows(COMMON_NAME_OID.len())
+        .position(|window| window == COMMON_NAME_OID)?;
+    let (_tag, value, _) = read_tlv(&name[position + COMMON_NAME_OID.len()..])?;
+    Some(String::from_utf8_lossy(value).into_owned())
+}
+
+/// A parsed DER encoded certificate.
+struct ParsedCertificate {
+    /// The length of the DER encoding in bytes.
+    length: usize,
+    /// The common name of the subject.
+    common_name: Option<String>,
+    /// The start of the validity period as Unix timestamp.
+    not_before: i64,
+    /// The end of the validity period as Unix timestamp.
+    not_after: i64,
+}
+
+/// Parse the DER encoded certificate at the start of the given bytes.
+///
+/// Returns `None` if the bytes do not start with a certificate.
+fn parse_der_certificate(bytes: &[u8]) -> Option<ParsedCertificate> {
+    let (certificate, rest) = read_element(bytes, 0x30)?;
+    let length = bytes.len() - rest.len();
+    let (tbs_certificate, remaining) = read_element(certificate, 0x30)?;
+    let (_signature_algorithm, remaining) = read_element(remaining, 0x30)?;
+    let (_signature, remaining) = read_element(remaining, 0x03)?;
+    if !remaining.is_empty() {
+        return None;
+    }
+    // The version is optional.
+    let tbs_certificate = match read_element(tbs_certificate, 0xa0) {
+        Some((_version, remaining)) => remaining,
+        None => tbs_certificate,
+    };
+    let (_serial_number, remaining) = read_element(tbs_certificate, 0x02)?;
+    let (_signature, remaining) = read_element(remaining, 0x30)?;
+    let (_issuer, remaining) = read_element(remaining, 0x30)?;
+    let (validity, remaining) = read_element(remaining, 0x30)?;
+    let (subject, _) = read_element(remaining, 0x30)?;
+    let (not_before_tag, not_before, validity) = read_tlv(validity)?;
+    let (not_after_tag, not_after, _) = read_tlv(validity)?;
+    Some(ParsedCertificate {
+        length,
+        common_name: parse_common_name(subject),
+        not_before: parse_time(not_before_tag, not_before)?,
+        not_after: parse_time(not_after_tag, not_after)?,
+    })
+}
+
+/// Decode the given Base64 text. Whitespace is ignored.
+fn decode_base64(text: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut num_bits = 0;
+    for byte in text.iter().filter(|byte| !byte.is_ascii_whitespace()) {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        num_bits += 6;
+        if num_bits >= 8 {
+            num_bits -= 8;
+            decoded.push((buffer >> num_bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+/// Find the position of the first occurrence of `needle` in `haystack`.
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Find all certificates in the PEM format in the given bytes.
+///
+/// Returns the offsets and lengths of the found certificates together with the parsed certificates.
+fn find_pem_certificates(bytes: &[u8]) -> Vec<(usize, usize, ParsedCertificate)> {
+    let mut certificates = Vec::new();
+    let mut offset = 0;
+    while let Some(begin) = find_subslice(&bytes[offset..], PEM_BEGIN) {
+        let start = offset + begin;
+        let content_start = start + PEM_BEGIN.len();
+        let Some(content_length) = find_subslice(&bytes[content_start..], PEM_END) else {
+            break;
+        };
+        let end = content_start + content_length + PEM_END.len();
+        if let Some(certificate) = decode_base64(&bytes[content_start..end - PEM_END.len()])
+            .as_deref()
+            .and_then(parse_der_certificate)
+        {
+            certificates.push((start, end - start, certificate));
+        }
+        offset = end;
+    }
+    certificates
+}
+
+/// Find all certificates in the DER format in the given bytes.
+///
+/// Returns the offsets of the found certificates together with the parsed certificates.
+fn find_der_certificates(bytes: &[u8]) -> Vec<(usize, ParsedCertificate)> {
+    let mut certificates = Vec::new();
+    let mut offset = 0;
+    while offset + 1 < bytes.len() {
+        // Certificates are longer than 127 bytes, so their length is given in the long form.
+        if bytes[offset] == 0x30 && (0x81..=0x83).contains(&bytes[offset + 1]) {
+            if let Some(certificate) = parse_der_certificate(&bytes[offset..]) {
+                let length = certificate.length;
+                certificates.push((offset, certificate));
+                offset += length;
+                continue;
+            }
+        }
+        offset += 1;
+    }
+    certificates
+}
+
+/// Find all PEM and DER encoded certificates in the readable memory segments of all address spaces.
+fn find_certificates(memory_image: &RuntimeMemoryImage) -> Vec<FoundCertificate> {
+    let mut certificates = Vec::new();
+    for (_, segment) in memory_image
+        .iter_segments()
+        .filter(|(_, segment)| segment.read_flag)
+    {
+        let pem_certificates = find_pem_certificates(&segment.bytes)
+            .into_iter()
+            .map(|(offset, length, certificate)| (offset, length, Encoding::Pem, certificate));
+        let der_certificates = find_der_certificates(&segment.bytes)
+            .into_iter()
+            .map(|(offset, certificate)| (offset, certificate.length, Encoding::Der, certificate));
+        for (offset, length, encoding, certificate) in pem_certificates.chain(der_certificates) {
+            certificates.push(FoundCertificate {
+                address: segment.base_address + offset as u64,
+                length: length as u64,
+                encoding,
+                common_name: certificate.common_name,
+                not_before: certificate.not_before,
+                not_after: certificate.not_after,
+            });
+        }
+    }
+    certificates
+}
+
+/// Get the current system time as Unix timestamp.
+fn current_time() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Run the check.
+///
+/// Search for hard-coded certificates and generate a warning for each certificate
+/// that is expired or expires soon.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE324.");
+    let reference_time = config.reference_time.unwrap_or_else(current_time);
+    let warning_time = reference_time + config.expiry_warning_days as i64 * SECONDS_PER_DAY;
+    let certificate_symbols = get_symbol_map(project, &config.certificate_symbols);
+    let xrefs = XrefIndex::new(project, analysis_results.pointer_inference);
+    let mut cwe_warnings = Vec::new();
+    // The examined sites are the certificates contained in the binary.
+    let mut site_counts = SiteCounts::default();
+    for certificate in find_certificates(&project.runtime_memory_image) {
+        if certificate.not_after >= warning_time {
+            site_counts.add_safe();
+            continue;
+        }
+        site_counts.add_flagged();
+        let certificate_calls =
+            get_certificate_calls(project, &certificate, &xrefs, &certificate_symbols);
+        cwe_warnings.push(generate_warning(
+            &certificate,
+            certificate.not_after < reference_time,
+            &xrefs,
+            &certificate_calls,
+        ));
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Get the calls to certificate handling functions in the functions referencing the given certificate.
+///
+/// Returns the TIDs of the call instructions together with the names of the called functions.
+fn get_certificate_calls(
+    project: &Project,
+    certificate: &FoundCertificate,
+    xrefs: &XrefIndex,
+    certificate_symbols: &HashMap<Tid, &ExternSymbol>,
+) -> BTreeMap<Tid, String> {
+    let referencing_subs: BTreeSet<&Tid> = xrefs
+        .get_xrefs_in_range(
+            certificate.address,
+            certificate.address + certificate.length,
+        )
+        .map(|(_, xref)| &xref.sub)
+        .collect();
+    let mut calls = BTreeMap::new();
+    for sub_tid in referencing_subs {
+        let Some(sub) = project.program.term.subs.get(sub_tid) else {
+            continue;
+        };
+        for jmp in sub.term.blocks.iter().flat_map(|blk| blk.term.jmps.iter()) {
+            if let Jmp::Call { target, .. } = &jmp.term {
+                if let Some(symbol) = certificate_symbols.get(target) {
+                    calls.insert(jmp.tid.clone(), symbol.name.clone());
+                }
+            }
+        }
+    }
+    calls
+}
+
+/// Generate the warning for an expired or soon expiring certificate.
+fn generate_warning(
+    certificate: &FoundCertificate,
+    is_expired: bool,
+    xrefs: &XrefIndex,
+    certificate_calls: &BTreeMap<Tid, String>,
+) -> CweWarning {
+    let address = format!("{:x}", certificate.address);
+    let mut referencing_tids: BTreeMap<String, String> = xrefs
+        .get_xrefs_in_range(
+            certificate.address,
+            certificate.address + certificate.length,
+        )
+        .map(|(_, xref)| (format!("{}", xref.tid), xref.tid.address.clone()))
+        .collect();
+    referencing_tids.extend(
+        certificate_calls
+            .keys()
+            .map(|tid| (format!("{tid}"), tid.address.clone())),
+    );
+    let mut addresses = vec![address.clone()];
+    addresses.extend(referencing_tids.values().cloned());
+    let description = match &certificate.common_name {
+        Some(common_name) => format!("Certificate \"{}\"", common_name.escape_debug()),
+        None => "Certificate".to_string(),
+    };
+    let encoding = match certificate.encoding {
+        Encoding::Pem => "PEM",
+        Encoding::Der => "DER",
+    };
+    let not_after = format_date(certificate.not_after);
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Expired Certificate) {description} at {address} ({encoding}) {} on {not_after}",
+            if is_expired { "expired" } else { "expires" }
+        ),
+    )
+    .addresses(addresses)
+    .tids(referencing_tids.into_keys().collect())
+    .symbols(
+        certificate_calls
+            .values()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    )
+    .other(vec![vec![
+        "validity".to_string(),
+        format_date(certificate.not_before),
+        not_after,
+    ]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::binary::MemorySegment;
+
+    /// Encode a DER tag-length-value element.
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![tag];
+        match content.len() {
+            length @ 0..=0x7f => encoded.push(length as u8),
+            length @ 0x80..=0xff => encoded.extend([0x81, length as u8]),
+            length => encoded.extend([0x82, (length >> 8) as u8, length as u8]),
+        }
+        encoded.extend(content);
+        encoded
+    }
+
+    /// Generate a DER encoded certificate with the given common name and validity period.
+    fn mock_certificate(common_name: &str, not_before: &str, not_after: &str) -> Vec<u8> {
+        let algorithm = tlv(
+            0x30,
+            &tlv(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 1, 1, 11]),
+        );
+        let mut name_attribute = COMMON_NAME_OID.to_vec();
+        name_attribute.extend(tlv(0x0c, common_name.as_bytes()));
+        let name = tlv(0x30, &tlv(0x31, &tlv(0x30, &name_attribute)));
+        let mut validity = tlv(0x17, not_before.as_bytes());
+        validity.extend(tlv(0x18, not_after.as_bytes()));
+        let public_key = tlv(0x30, &[algorithm.clone(), tlv(0x03, &[0; 140])].concat());
+        let tbs_certificate = tlv(
+            0x30,
+            &[
+                tlv(0xa0, &tlv(0x02, &[2])),
+                tlv(0x02, &[0x12, 0x34]),
+                algorithm.clone(),
+                name.clone(),
+                tlv(0x30, &validity),
+                name,
+                public_key,
+            ]
+            .concat(),
+        );
+        tlv(
+            0x30,
+            &[tbs_certificate, algorithm, tlv(0x03, &[0; 65])].concat(),
+        )
+    }
+
+    /// Encode the given bytes as Base64.
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = String::new();
+        for chunk in bytes.chunks(3) {
+            let buffer = chunk.iter().enumerate().fold(0u32, |buffer, (i, byte)| {
+                buffer | ((*byte as u32) << (16 - 8 * i))
+            });
+            for i in 0..=chunk.len() {
+                encoded.push(ALPHABET[((buffer >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            }
+        }
+        while encoded.len() % 4 != 0 {
+            encoded.push('=');
+        }
+        encoded
+    }
+
+    #[test]
+    fn date_conversion() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(civil_from_days(11017), (2000, 3, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(
+            parse_time(0x17, b"491231235959Z"),
+            Some(days_from_civil(2049, 12, 31) * SECONDS_PER_DAY + 86399)
+        );
+        assert_eq!(parse_time(0x17, b"500101000000Z"), Some(-631152000));
+        assert_eq!(
+            format_date(parse_time(0x18, b"20240229120000Z").unwrap()),
+            "2024-02-29"
+        );
+        assert_eq!(parse_time(0x18, b"20241301000000Z"), None);
+    }
+
+    #[test]
+    fn certificate_detection() {
+        let expired = mock_certificate("update.example.com", "200101000000Z", "20230101000000Z");
+        let valid = mock_certificate("Root CA", "200101000000Z", "20400101000000Z");
+        let pem = format!(
+            "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+            encode_base64(&valid)
+        );
+        let mut bytes = b"\x00\x30\x82\x00".to_vec();
+        let expired_offset = bytes.len() as u64;
+        bytes.extend(&expired);
+        let pem_offset = bytes.len() as u64;
+        bytes.extend(pem.as_bytes());
+        bytes.push(0);
+        let mut project = Project::mock_x64();
+        project.runtime_memory_image.memory_segments = vec![MemorySegment {
+            bytes,
+            base_address: 0x2000,
+            read_flag: true,
+            write_flag: false,
+            execute_flag: false,
+        }];
+        let certificates = find_certificates(&project.runtime_memory_image);
+        assert_eq!(
+            certificates,
+            vec![
+                FoundCertificate {
+                    address: 0x2000 + pem_offset,
+                    length: pem.len() as u64 - 1,
+                    encoding: Encoding::Pem,
+                    common_name: Some("Root CA".to_string()),
+                    not_before: days_from_civil(2020, 1, 1) * SECONDS_PER_DAY,
+                    not_after: days_from_civil(2040, 1, 1) * SECONDS_PER_DAY,
+                },
+                FoundCertificate {
+                    address: 0x2000 + expired_offset,
+                    length: expired.len() as u64,
+                    encoding: Encoding::Der,
+                    common_name: Some("update.example.com".to_string()),
+                    not_before: days_from_civil(2020, 1, 1) * SECONDS_PER_DAY,
+                    not_after: days_from_civil(2023, 1, 1) * SECONDS_PER_DAY,
+                },
+            ]
+        );
+        // Corrupted certificates are ignored.
+        let mut corrupted = expired.clone();
+        corrupted.pop();
+        assert!(find_der_certificates(&corrupted).is_empty());
+    }
+}
@@ -22,6 +22,7 @@
 
 use crate::prelude::*;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
 use crate::utils::symbol_utils::find_symbol;
 use crate::CweModule;
 
@@ -60,13 +61,20 @@ pub fn check_cwe(
     let project = analysis_results.project;
     let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
     let mut cwe_warnings = Vec::new();
+    // The examined sites are the used PRNG functions.
+    let mut site_counts = SiteCounts::default();
 
     for (secure_initializer_func, rand_func) in config.pairs.iter() {
-        if find_symbol(&project.program, rand_func).is_some()
-            && find_symbol(&project.program, secure_initializer_func).is_none()
-        {
+        if find_symbol(&project.program, rand_func).is_none() {
+            continue;
+        }
+        if find_symbol(&project.program, secure_initializer_func).is_none() {
+            site_counts.add_flagged();
             cwe_warnings.push(generate_cwe_warning(secure_initializer_func, rand_func));
+        } else {
+            site_counts.add_safe();
         }
     }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
     (Vec::new(), cwe_warnings)
 }
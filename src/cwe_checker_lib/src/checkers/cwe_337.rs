@@ -8,25 +8,52 @@
 //! ## How the check works
 //!
 //! Using dataflow analysis we search for an execution path where the result of a time source, like `time`,
-//! is used as an argument to a PRNG seeding function, like `srand`.
+//! or a device identifier, like the host ID or a MAC address, is used as an argument to a PRNG seeding function,
+//! like `srand`.
+//! Device identifiers are predictable, since they are often printed on the device or visible in the local network.
+//! Values derived from the sources by conversion functions like `strtoul` are also considered predictable.
+//! A separate dataflow analysis is run for each call to a source,
+//! so that the warnings can report which source calls the seed was derived from.
+//!
+//! Furthermore, we use the results of the pointer inference analysis to detect seeding functions
+//! that are called with a constant seed.
 //!
 //! ### Symbols configurable in config.json
 //!
-//! Both the sources of predictable seeds and the seeding functions can be configured using the `sources`
-//! and `seeding_functions` respectively.
+//! - `sources`: Functions whose return values are predictable seeds, e.g. time sources.
+//! - `device_id_sources`: Functions returning device identifiers.
+//!   For each function it is configured whether the identifier is returned (`ReturnValue`)
+//!   or written to the memory pointed to by the parameter with the given index (`{"ParameterTarget": index}`).
+//! - `conversion_functions`: Functions whose return value is derived from their first parameter,
+//!   e.g. functions parsing a serial number string.
+//! - `seeding_functions`: Random number seeding functions, including seeding functions of custom PRNG libraries.
+//!
+//! ## False Positives
+//!
+//! - The seeding function may be used for purposes where a predictable seed is acceptable,
+//!   e.g. for randomized backoff times.
+//!
+//! ## False Negatives
+//!
+//! - Seeding functions and PRNG implementations inside the binary are not recognized.
+//! - For device identifiers written to memory only the value at the exact address of the pointer is tracked.
+//! - The dataflow analysis is limited to a fixed number of steps per source call.
 
+use crate::abstract_domain::TryToBitvec;
 use crate::analysis::forward_interprocedural_fixpoint::create_computation;
 use crate::analysis::graph::{Edge, Graph, HasCfg};
 use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
 use crate::analysis::pointer_inference::{
     Data as PiData, PointerInference as PointerInferenceComputation,
 };
-use crate::analysis::taint::{state::State as TaState, TaintAnalysis};
+use crate::analysis::taint::summary::{TaintFlowTarget, TaintSummaries, TaintSummary};
+use crate::analysis::taint::{state::State as TaState, Taint, TaintAnalysis};
 use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::{
     log::{CweWarning, LogMessage},
+    metrics::SiteCounts,
     symbol_utils,
 };
 use crate::CweModule;
@@ -34,33 +61,68 @@ use crate::CweModule;
 use petgraph::visit::EdgeRef;
 
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::convert::AsRef;
+use std::fmt;
 
 /// The module name and version.
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE337",
-    version: "0.1",
+    version: "0.2",
     run: check_cwe,
 };
 
 /// The configuration struct.
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Config {
     /// Sources of predictable seeds.
     sources: Vec<String>,
+    /// Functions returning device identifiers like serial numbers or MAC addresses,
+    /// together with the location that the identifier is returned in.
+    #[serde(default)]
+    device_id_sources: BTreeMap<String, TaintFlowTarget>,
+    /// Functions whose return value is derived from their first parameter.
+    #[serde(default)]
+    conversion_functions: Vec<String>,
     /// Random number seeding functions.
     seeding_functions: Vec<String>,
 }
 
+/// The origin of a predictable seed.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+enum SeedOrigin {
+    /// The seed is a constant.
+    Constant(u64),
+    /// The seed is derived from the result of a call to the source function with the given name.
+    Source { name: String, call_tid: Tid },
+}
+
+impl fmt::Display for SeedOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeedOrigin::Constant(value) => write!(f, "constant {value:#x}"),
+            SeedOrigin::Source { name, call_tid } => write!(f, "{name} at {}", call_tid.address),
+        }
+    }
+}
+
+/// A call to a seeding function reached by a predictable seed.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+struct SeededCall {
+    /// The TID of the call to the seeding function.
+    call_tid: Tid,
+    /// The name of the seeding function.
+    seeding_function: String,
+}
+
 /// Run the CWE check.
 ///
 /// We check if a return value of any of the sources (as determined by the
 /// config file) is used as a direct parameter of any of the sinks (as
-/// determined by the config file).
+/// determined by the config file) or if a sink is called with a constant seed.
 ///
-/// Currently, this is only used to detect whether a call of `time` leads into a
-/// call of `srand`.
+/// The source calls that the seed may be derived from are reported in the warnings.
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
@@ -69,31 +131,38 @@ pub fn check_cwe(
     let config: Config = serde_json::from_value(cwe_params.clone())
         .expect("Invalid configuration inside config.json for CWE337.");
 
-    let source_map = symbol_utils::get_symbol_map(project, &config.sources[..]);
     let sink_map = symbol_utils::get_symbol_map(project, &config.seeding_functions[..]);
-    if source_map.is_empty() || sink_map.is_empty() {
+    // The examined sites are the calls to seeding functions.
+    let mut site_counts = SiteCounts::default();
+    if sink_map.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
         return (Vec::new(), Vec::new());
     }
-
     let pi_result = analysis_results.pointer_inference.unwrap();
+    let mut seed_origins: BTreeMap<SeededCall, BTreeSet<SeedOrigin>> = BTreeMap::new();
+    for (seeded_call, value) in find_constant_seeds(project, pi_result, &sink_map) {
+        seed_origins
+            .entry(seeded_call)
+            .or_default()
+            .insert(SeedOrigin::Constant(value));
+    }
+
+    let source_map = symbol_utils::get_symbol_map(project, &config.sources[..]);
+    let device_id_names: Vec<String> = config.device_id_sources.keys().cloned().collect();
+    let device_id_map = symbol_utils::get_symbol_map(project, &device_id_names);
+    let taint_summaries = get_taint_summaries(
+        &config.conversion_functions,
+        analysis_results.taint_summaries,
+    );
     let graph = analysis_results.control_flow_graph;
     let (cwe_sender, cwe_receiver) = crossbeam_channel::unbounded();
-
-    let context = Context {
-        project: analysis_results.project,
-        pi_result,
-        control_flow_graph: graph,
-        sink_map,
-        extern_symbol_map: project
-            .program
-            .term
-            .extern_symbols
-            .iter()
-            .map(|(tid, sym)| (tid.clone(), sym))
-            .collect(),
-        cwe_collector: cwe_sender,
-    };
-    let mut computation = create_computation(context, None);
+    let extern_symbol_map: HashMap<Tid, &ExternSymbol> = project
+        .program
+        .term
+        .extern_symbols
+        .iter()
+        .map(|(tid, sym)| (tid.clone(), sym))
+        .collect();
 
     for edge in graph.edge_references() {
         let Edge::ExternCallStub(jmp) = edge.weight() else {
@@ -102,29 +171,186 @@ pub fn check_cwe(
         let Jmp::Call { target, .. } = &jmp.term else {
             continue;
         };
-        let Some(symbol) = source_map.get(target) else {
+        let return_node = edge.target();
+        let start_state = if let Some(symbol) = source_map.get(target) {
+            TaState::new_return(symbol, pi_result, return_node)
+        } else if let Some(symbol) = device_id_map.get(target) {
+            get_device_id_state(
+                project,
+                pi_result,
+                &jmp.tid,
+                symbol,
+                &config.device_id_sources[&symbol.name],
+            )
+        } else {
             continue;
         };
-        let return_node = edge.target();
+        if start_state.is_empty() {
+            continue;
+        }
 
-        computation.set_node_value(
-            return_node,
-            NodeValue::Value(TaState::new_return(symbol, pi_result, return_node)),
-        );
-    }
+        let context = Context {
+            project,
+            pi_result,
+            control_flow_graph: graph,
+            sink_map: sink_map.clone(),
+            extern_symbol_map: extern_symbol_map.clone(),
+            taint_summaries: Some(&taint_summaries),
+            cwe_collector: cwe_sender.clone(),
+        };
+        let mut computation = create_computation(context, None);
+        computation.set_cancellation_token(analysis_results.cancellation_token);
+        computation.set_node_value(return_node, NodeValue::Value(start_state));
+        // FIXME: This number should be in the config.
+        computation.compute_with_max_steps(100);
 
-    // FIXME: This number should be in the config.
-    computation.compute_with_max_steps(100);
+        let origin = SeedOrigin::Source {
+            name: extern_symbol_map[target].name.clone(),
+            call_tid: jmp.tid.clone(),
+        };
+        for seeded_call in cwe_receiver.try_iter() {
+            seed_origins
+                .entry(seeded_call)
+                .or_default()
+                .insert(origin.clone());
+        }
+    }
 
     let mut cwe_warnings = BTreeMap::new();
-    for cwe in cwe_receiver.try_iter() {
+    for (seeded_call, origins) in seed_origins {
+        let cwe = generate_cwe_warning(&seeded_call, &origins);
         cwe_warnings.insert(cwe.addresses[0].clone(), cwe);
     }
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, _) in symbol_utils::get_callsites(sub, &sink_map) {
+            if cwe_warnings.contains_key(&jmp.tid.address) {
+                site_counts.add_flagged();
+            } else {
+                site_counts.add_safe();
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
     let cwe_warnings = cwe_warnings.into_values().collect();
 
     (Vec::new(), cwe_warnings)
 }
 
+/// Find all calls to seeding functions where the first parameter is a constant.
+fn find_constant_seeds(
+    project: &Project,
+    pi_result: &PointerInferenceComputation,
+    sink_map: &HashMap<Tid, &ExternSymbol>,
+) -> Vec<(SeededCall, u64)> {
+    let mut constant_seeds = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, symbol) in symbol_utils::get_callsites(sub, sink_map) {
+            let Some(param) = symbol.parameters.first() else {
+                continue;
+            };
+            let value = pi_result
+                .eval_parameter_arg_at_call(&jmp.tid, param)
+                .and_then(|value| value.get_if_absolute_value()?.try_to_bitvec().ok())
+                .and_then(|value| value.try_to_u64().ok());
+            if let Some(value) = value {
+                constant_seeds.push((
+                    SeededCall {
+                        call_tid: jmp.tid.clone(),
+                        seeding_function: symbol.name.clone(),
+                    },
+                    value,
+                ));
+            }
+        }
+    }
+    constant_seeds
+}
+
+/// Get the taint state after a call to a device identifier source,
+/// where the location containing the identifier is tainted.
+fn get_device_id_state(
+    project: &Project,
+    pi_result: &PointerInferenceComputation,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    target: &TaintFlowTarget,
+) -> TaState {
+    let calling_convention = project.get_calling_convention(symbol);
+    let mut state = TaState::new_empty();
+    match target {
+        TaintFlowTarget::ReturnValue => {
+            for register in calling_convention.integer_return_register.iter() {
+                state.set_register_taint(register, Taint::Tainted(register.size));
+            }
+        }
+        TaintFlowTarget::ParameterTarget(index) => {
+            if let Some(param) = project.get_parameter_arg(symbol, *index) {
+                if let Some(address) = pi_result.eval_parameter_arg_at_call(call_tid, &param) {
+                    state.save_taint_to_memory(&address, Taint::Tainted(param.bytesize()));
+                }
+            }
+        }
+    }
+    state
+}
+
+/// Get the taint summaries used by the analysis.
+///
+/// Conversion functions propagate taint from their first parameter (or the memory pointed to by it)
+/// to their return value. Summaries of imported functions take precedence over the conversion functions.
+fn get_taint_summaries(
+    conversion_functions: &[String],
+    imported_summaries: Option<&TaintSummaries>,
+) -> TaintSummaries {
+    let mut summaries = TaintSummaries::default();
+    for name in conversion_functions {
+        summaries.insert(
+            name.clone(),
+            TaintSummary {
+                flows: BTreeMap::from([(0, BTreeSet::from([TaintFlowTarget::ReturnValue]))]),
+            },
+        );
+    }
+    if let Some(imported_summaries) = imported_summaries {
+        summaries.extend(imported_summaries.clone());
+    }
+    summaries
+}
+
+/// Generate the CWE warning for a seeding function call with a predictable seed.
+fn generate_cwe_warning(seeded_call: &SeededCall, origins: &BTreeSet<SeedOrigin>) -> CweWarning {
+    let origin_descriptions: Vec<String> =
+        origins.iter().map(|origin| origin.to_string()).collect();
+    let mut tids = vec![format!("{}", seeded_call.call_tid)];
+    for origin in origins {
+        if let SeedOrigin::Source { call_tid, .. } = origin {
+            tids.push(format!("{call_tid}"));
+        }
+    }
+    let provenance_chain: Vec<String> = std::iter::once("seed_provenance".to_string())
+        .chain(origins.iter().map(|origin| {
+            format!(
+                "{origin} -> {} at {}",
+                seeded_call.seeding_function, seeded_call.call_tid.address
+            )
+        }))
+        .collect();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "RNG seed function {} at {} is seeded with predictable seed source: {}.",
+            seeded_call.seeding_function,
+            seeded_call.call_tid.address,
+            origin_descriptions.join(", ")
+        ),
+    )
+    .tids(tids)
+    .addresses(vec![seeded_call.call_tid.address.clone()])
+    .symbols(vec![seeded_call.seeding_function.clone()])
+    .other(vec![provenance_chain])
+}
+
 /// The Context struct for the taint analysis.
 pub struct Context<'a> {
     /// A pointer to the corresponding project struct.
@@ -140,8 +366,10 @@ pub struct Context<'a> {
     sink_map: HashMap<Tid, &'a ExternSymbol>,
     /// Maps the TID of an extern symbol to the extern symbol struct.
     extern_symbol_map: HashMap<Tid, &'a ExternSymbol>,
-    /// A channel where found CWE hits can be sent to.
-    cwe_collector: crossbeam_channel::Sender<CweWarning>,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where calls to seeding functions reached by taint can be sent to.
+    cwe_collector: crossbeam_channel::Sender<SeededCall>,
 }
 
 impl<'a> HasCfg<'a> for Context<'a> {
@@ -163,6 +391,10 @@ impl<'a> AsRef<Project> for Context<'a> {
 }
 
 impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
     /// Generate a CWE warning if taint may be contained in the arguments to a
     /// sink function.
     ///
@@ -187,7 +419,7 @@ impl<'a> TaintAnalysis<'a> for Context<'a> {
 
                         None
                     } else {
-                        Some(self.update_extern_symbol(state, sink_symbol))
+                        Some(self.update_extern_symbol(state, call, sink_symbol))
                     }
                 } else {
                     let extern_symbol = self
@@ -195,7 +427,7 @@ impl<'a> TaintAnalysis<'a> for Context<'a> {
                         .get(target)
                         .expect("Extern symbol not found.");
 
-                    Some(self.update_extern_symbol(state, extern_symbol))
+                    Some(self.update_extern_symbol(state, call, extern_symbol))
                 }
             }
             Jmp::CallInd { .. } => self.update_call_generic(state, &call.tid, &None),
@@ -208,26 +440,73 @@ impl<'a> Context<'a> {
     /// Transition function for calls to external functions that do not
     /// trigger a CWE warning, i.e., its not a sink function or no taint is in
     /// the arguments.
-    fn update_extern_symbol(&self, state: &TaState, extern_symbol: &ExternSymbol) -> TaState {
+    ///
+    /// Taint is propagated according to the taint summary of the function if one is known.
+    fn update_extern_symbol(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        extern_symbol: &ExternSymbol,
+    ) -> TaState {
+        let calling_conv = self.project.get_calling_convention(extern_symbol);
         let mut new_state = state.clone();
 
-        new_state.remove_non_callee_saved_taint(self.project.get_calling_convention(extern_symbol));
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        if let Some(summary) = self
+            .taint_summaries
+            .and_then(|summaries| summaries.get(&extern_symbol.name))
+        {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.pi_result,
+                &call.tid,
+                calling_conv,
+            );
+        }
 
         new_state
     }
 
+    /// Report that the given call to a seeding function is reached by taint.
     fn generate_cwe_warning(&self, sink_call: &Term<Jmp>, sink_symbol: &ExternSymbol) {
-        let cwe_warning = CweWarning::new(
-            CWE_MODULE.name,
-            CWE_MODULE.version,
-            format!(
-                "RNG seed function {} at {} is seeded with predictable seed source.",
-                sink_symbol.name, sink_call.tid.address,
-            ),
-        )
-        .tids(vec![format!("{}", sink_call.tid)])
-        .addresses(vec![sink_call.tid.address.clone()])
-        .symbols(vec![sink_symbol.name.clone()]);
-        let _ = self.cwe_collector.send(cwe_warning);
+        let _ = self.cwe_collector.send(SeededCall {
+            call_tid: sink_call.tid.clone(),
+            seeding_function: sink_symbol.name.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::run_check;
+    use crate::def;
+
+    #[test]
+    fn constant_seed() {
+        let mut project = Project::mock_x64();
+        project
+            .program
+            .term
+            .extern_symbols
+            .insert(Tid::new("srand"), ExternSymbol::mock_x64("srand"));
+        let mut blk = Blk::mock_with_tid("blk");
+        blk.term.defs = vec![def!["seed: RDI:8 = 0x2a:8"]];
+        blk.term
+            .jmps
+            .push(Jmp::call("call_srand", "srand", Some("blk_end")));
+        let mut sub = Sub::mock("main");
+        sub.term.blocks = vec![blk, Blk::mock_with_tid("blk_end")];
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+
+        let config = serde_json::json!({
+            "sources": ["time"],
+            "seeding_functions": ["srand"]
+        });
+        let warnings = run_check(check_cwe, &project, &config);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["call_srand".to_string()]);
+        assert!(warnings[0].description.contains("constant 0x2a"));
     }
 }
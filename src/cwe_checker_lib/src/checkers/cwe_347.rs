@@ -0,0 +1,605 @@
+//! This module implements a check for CWE-347: Improper Verification of Cryptographic Signature.
+//!
+//! Firmware updaters and boot loaders verify the signature of an image before installing or executing it.
+//! The verification functions of crypto libraries signal the result through their return value,
+//! but the libraries use different conventions for it:
+//! Some functions return 0 on success (e.g. `mbedtls_pk_verify`),
+//! others return 1 on success, 0 for invalid signatures and negative values on errors (e.g. `EVP_DigestVerifyFinal`).
+//! Checking the return value with the wrong convention (e.g. `if (EVP_DigestVerifyFinal(...))`)
+//! or not checking it at all lets unverified images pass.
+//!
+//! See <https://cwe.mitre.org/data/definitions/347.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! For each call to a signature verification function we follow the control flow inside the calling function,
+//! starting at the return site of the call.
+//! Instead of tracking abstract values we evaluate the return value (and all values computed from it)
+//! concretely for a few representative return values of the verification function,
+//! e.g. 1 (valid signature), 0 (invalid signature) and -1 (error) for functions returning 1 on success.
+//! The representative return values are given by the success convention of the function.
+//! - If a call to an action function (e.g. `execve` or `rename`) is reached
+//!   before a branch depending on the return value, the return value is not checked before the action.
+//! - At the first branch depending on the return value we evaluate the branch condition
+//!   for each representative return value.
+//!   If an action function is reachable from a branch target taken for a return value signalling a failure,
+//!   the return value is checked incorrectly.
+//!
+//! Paths on which the return value is stored to memory, passed to another function
+//! or returned to the caller are not followed further,
+//! since the return value may be checked elsewhere.
+//!
+//! ## False Positives
+//!
+//! - Actions reachable after a failed verification may be harmless, e.g. executing a recovery program.
+//! - The action may be reachable only after a second verification that is not recognized, e.g. in a called function.
+//!
+//! ## False Negatives
+//!
+//! - The return value is only tracked in registers inside the calling function.
+//! - Only actions inside the calling function are found.
+//!   If the action is executed by a caller, e.g. depending on a flag set by the calling function,
+//!   incorrect checks are not found.
+//! - Verification functions returning their result through a pointer parameter are not supported.
+//!
+//! ## Configuration
+//!
+//! The verification functions together with their success convention and the action functions
+//! are configurable in config.json.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::graph_utils::get_successor_block;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE347",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The convention of a verification function for signalling a successful verification through its return value.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum SuccessConvention {
+    /// The function returns 0 on success and a nonzero error code otherwise.
+    Zero,
+    /// The function returns 1 on success, 0 for invalid signatures and a negative value on errors.
+    One,
+    /// The function returns a nonzero value on success and 0 otherwise.
+    Nonzero,
+    /// The function returns a non-negative value on success and a negative error code otherwise.
+    NonNegative,
+}
+
+impl SuccessConvention {
+    /// Get representative return values of the function
+    /// together with whether they signal a successful verification.
+    fn get_sample_return_values(&self) -> &'static [(i64, bool)] {
+        match self {
+            SuccessConvention::Zero => &[(0, true), (1, false), (-1, false)],
+            SuccessConvention::One => &[(1, true), (0, false), (-1, false)],
+            SuccessConvention::Nonzero => &[(1, true), (0, false)],
+            SuccessConvention::NonNegative => &[(0, true), (1, true), (-1, false)],
+        }
+    }
+}
+
+impl std::fmt::Display for SuccessConvention {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SuccessConvention::Zero => write!(f, "returns 0 on success"),
+            SuccessConvention::One => write!(f, "returns 1 on success"),
+            SuccessConvention::Nonzero => write!(f, "returns a nonzero value on success"),
+            SuccessConvention::NonNegative => {
+                write!(f, "returns a non-negative value on success")
+            }
+        }
+    }
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Signature verification functions, mapped to their success convention.
+    verification_symbols: BTreeMap<String, SuccessConvention>,
+    /// Functions installing, executing or booting the verified data.
+    action_symbols: Vec<String>,
+}
+
+/// The (concrete) values of a register for each representative return value of the verification function.
+type SampleState = HashMap<Variable, Vec<Bitvector>>;
+
+/// A call to an action function.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct ActionCall {
+    /// The TID of the call.
+    tid: Tid,
+    /// The name of the called action function.
+    name: String,
+}
+
+/// The improper use of the return value of a verification function.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum ImproperCheck {
+    /// The call to the action function is reached without checking the return value.
+    Unchecked(ActionCall),
+    /// The branch with the given TID leads to the call to the action function
+    /// for the given return values signalling a failed verification.
+    AcceptsFailure {
+        /// The TID of the branch depending on the return value.
+        branch: Tid,
+        /// The call to the action function.
+        action: ActionCall,
+        /// The return values signalling a failed verification that lead to the action.
+        failure_values: Vec<i64>,
+    },
+}
+
+/// Evaluate the expression for each representative return value.
+///
+/// Returns `None` if the expression depends on values not contained in the state.
+fn evaluate(
+    expression: &Expression,
+    state: &SampleState,
+    num_samples: usize,
+) -> Option<Vec<Bitvector>> {
+    match expression {
+        Expression::Var(var) => state.get(var).cloned(),
+        Expression::Const(constant) => Some(vec![constant.clone(); num_samples]),
+        Expression::BinOp { op, lhs, rhs } => {
+            let lhs = evaluate(lhs, state, num_samples)?;
+            let rhs = evaluate(rhs, state, num_samples)?;
+            lhs.iter()
+                .zip(rhs.iter())
+                .map(|(lhs, rhs)| lhs.bin_op(*op, rhs).ok())
+                .collect()
+        }
+        Expression::UnOp { op, arg } => evaluate(arg, state, num_samples)?
+            .iter()
+            .map(|value| value.un_op(*op).ok())
+            .collect(),
+        Expression::Cast { op, size, arg } => evaluate(arg, state, num_samples)?
+            .iter()
+            .map(|value| value.cast(*op, *size).ok())
+            .collect(),
+        Expression::Subpiece {
+            low_byte,
+            size,
+            arg,
+        } => Some(
+            evaluate(arg, state, num_samples)?
+                .iter()
+                .map(|value| value.subpiece(*low_byte, *size))
+                .collect(),
+        ),
+        Expression::Unknown { .. } => None,
+    }
+}
+
+/// Check whether the expression depends on the return value of the verification function.
+fn depends_on_return_value(expression: &Expression, state: &SampleState) -> bool {
+    expression
+        .input_vars()
+        .iter()
+        .any(|var| state.contains_key(var))
+}
+
+/// The control flow inside a function calling a verification function.
+struct FunctionContext<'a> {
+    /// The blocks of the function.
+    blocks: HashMap<&'a Tid, &'a Term<Blk>>,
+    /// The action functions.
+    action_symbols: &'a HashMap<Tid, &'a ExternSymbol>,
+    /// The verification functions.
+    verification_symbols: &'a HashMap<Tid, &'a ExternSymbol>,
+}
+
+impl<'a> FunctionContext<'a> {
+    /// Return the call to an action function if the target of the call is an action function.
+    fn get_action_call(&self, call_tid: Tid, target: &Tid) -> Option<ActionCall> {
+        self.action_symbols.get(target).map(|symbol| ActionCall {
+            tid: call_tid,
+            name: symbol.name.clone(),
+        })
+    }
+
+    /// Return the first call to an action function reachable from the given block.
+    ///
+    /// Paths are not followed through calls to verification functions,
+    /// since the action may depend on the result of the second verification.
+    fn find_reachable_action(&self, start: &Tid) -> Option<ActionCall> {
+        let mut visited: HashSet<&Tid> = HashSet::new();
+        let mut worklist: VecDeque<&Tid> = VecDeque::from([start]);
+        while let Some(blk_tid) = worklist.pop_front() {
+            if !visited.insert(blk_tid) {
+                continue;
+            }
+            let Some(blk) = self.blocks.get(blk_tid) else {
+                continue;
+            };
+            for jmp in blk.term.jmps.iter() {
+                if let Jmp::Call { target, .. } = &jmp.term {
+                    if let Some(action) = self.get_action_call(jmp.tid.clone(), target) {
+                        return Some(action);
+                    }
+                    if self.verification_symbols.contains_key(target) {
+                        continue;
+                    }
+                }
+                worklist.extend(get_successor_block(jmp));
+            }
+        }
+        None
+    }
+
+    /// Follow the paths starting at the return site of a call to a verification function
+    /// and return the first improper check of its return value.
+    fn analyze_verification_call(
+        &self,
+        calling_convention: &CallingConvention,
+        call: &'a Term<Jmp>,
+        convention: SuccessConvention,
+    ) -> Option<ImproperCheck> {
+        let (
+            Jmp::Call {
+                return_: Some(return_site),
+                ..
+            },
+            Some(return_register),
+        ) = (
+            &call.term,
+            calling_convention.integer_return_register.first(),
+        )
+        else {
+            return None;
+        };
+        let samples = convention.get_sample_return_values();
+        let return_values = samples
+            .iter()
+            .map(|(value, _)| Bitvector::from_i64(*value).into_resize_signed(return_register.size))
+            .collect();
+        let initial_state = SampleState::from([(return_register.clone(), return_values)]);
+
+        let mut visited: HashSet<&Tid> = HashSet::new();
+        let mut worklist: VecDeque<(&Tid, SampleState)> =
+            VecDeque::from([(return_site, initial_state)]);
+        'paths: while let Some((blk_tid, mut state)) = worklist.pop_front() {
+            if !visited.insert(blk_tid) {
+                continue;
+            }
+            let Some(blk) = self.blocks.get(blk_tid) else {
+                continue;
+            };
+            for def in blk.term.defs.iter() {
+                match &def.term {
+                    Def::Assign { var, value } => {
+                        match evaluate(value, &state, samples.len()) {
+                            Some(values) if depends_on_return_value(value, &state) => {
+                                state.insert(var.clone(), values)
+                            }
+                            _ => state.remove(var),
+                        };
+                    }
+                    Def::Load { var, .. } => {
+                        state.remove(var);
+                    }
+                    Def::Store { value, .. } => {
+                        if depends_on_return_value(value, &state) {
+                            continue 'paths;
+                        }
+                    }
+                }
+            }
+            for jmp in blk.term.jmps.iter() {
+                match &jmp.term {
+                    Jmp::CBranch { target, condition }
+                        if depends_on_return_value(condition, &state) =>
+                    {
+                        let Some(condition) = evaluate(condition, &state, samples.len()) else {
+                            continue 'paths;
+                        };
+                        let fallthrough = blk.term.jmps.iter().find_map(|jmp| match &jmp.term {
+                            Jmp::Branch(target) => Some(target),
+                            _ => None,
+                        });
+                        let branches = [(Some(target), true), (fallthrough, false)];
+                        for (branch_target, is_taken) in branches {
+                            let Some(action) =
+                                branch_target.and_then(|target| self.find_reachable_action(target))
+                            else {
+                                continue;
+                            };
+                            let failure_values: Vec<i64> = samples
+                                .iter()
+                                .zip(condition.iter())
+                                .filter(|((_, is_success), condition)| {
+                                    !is_success && condition.is_zero() != is_taken
+                                })
+                                .map(|((value, _), _)| *value)
+                                .collect();
+                            if !failure_values.is_empty() {
+                                return Some(ImproperCheck::AcceptsFailure {
+                                    branch: jmp.tid.clone(),
+                                    action,
+                                    failure_values,
+                                });
+                            }
+                        }
+                        continue 'paths;
+                    }
+                    Jmp::Call { target, .. } if self.action_symbols.contains_key(target) => {
+                        return self
+                            .get_action_call(jmp.tid.clone(), target)
+                            .map(ImproperCheck::Unchecked);
+                    }
+                    Jmp::Call { .. } | Jmp::CallInd { .. } => {
+                        if calling_convention
+                            .integer_parameter_register
+                            .iter()
+                            .any(|register| state.contains_key(register))
+                        {
+                            // The return value may be checked by the called function.
+                            continue 'paths;
+                        }
+                        state.retain(|var, _| {
+                            calling_convention.callee_saved_register.contains(var)
+                        });
+                    }
+                    Jmp::Return(_) | Jmp::BranchInd(_) => continue 'paths,
+                    Jmp::Branch(_) | Jmp::CBranch { .. } | Jmp::CallOther { .. } => (),
+                }
+                if let Some(successor) = get_successor_block(jmp) {
+                    worklist.push_back((successor, state.clone()));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Run the check.
+///
+/// For each call to a verification function the paths following the call inside the calling function are analyzed.
+/// At most one warning is generated for each call.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE347.");
+
+    let verification_names: Vec<String> = config.verification_symbols.keys().cloned().collect();
+    let verification_symbols = get_symbol_map(project, &verification_names);
+    let action_symbols = get_symbol_map(project, &config.action_symbols);
+    // The examined sites are the calls to verification functions.
+    let mut site_counts = SiteCounts::default();
+    if verification_symbols.is_empty() || action_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        let context = FunctionContext {
+            blocks: sub.term.blocks.iter().map(|blk| (&blk.tid, blk)).collect(),
+            action_symbols: &action_symbols,
+            verification_symbols: &verification_symbols,
+        };
+        for jmp in sub.term.blocks.iter().flat_map(|blk| blk.term.jmps.iter()) {
+            let Jmp::Call { target, .. } = &jmp.term else {
+                continue;
+            };
+            let Some(symbol) = verification_symbols.get(target) else {
+                continue;
+            };
+            let convention = config.verification_symbols[&symbol.name];
+            if let Some(improper_check) = context.analyze_verification_call(
+                project.get_calling_convention(symbol),
+                jmp,
+                convention,
+            ) {
+                site_counts.add_flagged();
+                cwe_warnings.push(generate_cwe_warning(
+                    sub,
+                    &jmp.tid,
+                    symbol,
+                    convention,
+                    &improper_check,
+                ));
+            } else {
+                site_counts.add_safe();
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    (Vec::new(), cwe_warnings)
+}
+
+/// Generate the CWE warning for an improper check of the return value of a verification function.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    convention: SuccessConvention,
+    improper_check: &ImproperCheck,
+) -> CweWarning {
+    let (description, action, mut tids, mut addresses) = match improper_check {
+        ImproperCheck::Unchecked(action) => (
+            format!(
+                "(Improper Signature Verification) Result of {} at {} is not checked before calling {} at {} ({})",
+                symbol.name,
+                call_tid.address,
+                action.name,
+                action.tid.address,
+                sub.term.name
+            ),
+            action,
+            Vec::new(),
+            Vec::new(),
+        ),
+        ImproperCheck::AcceptsFailure {
+            branch,
+            action,
+            failure_values,
+        } => {
+            let failure_values: Vec<String> = failure_values
+                .iter()
+                .map(|value| value.to_string())
+                .collect();
+            (
+                format!(
+                    "(Improper Signature Verification) Result of {} at {} ({}) is checked incorrectly at {}: Return value {} leads to calling {} at {} ({})",
+                    symbol.name,
+                    call_tid.address,
+                    convention,
+                    branch.address,
+                    failure_values.join(" or "),
+                    action.name,
+                    action.tid.address,
+                    sub.term.name
+                ),
+                action,
+                vec![format!("{branch}")],
+                vec![branch.address.clone()],
+            )
+        }
+    };
+    tids.insert(0, format!("{call_tid}"));
+    tids.push(format!("{}", action.tid));
+    addresses.insert(0, call_tid.address.clone());
+    addresses.push(action.tid.address.clone());
+    CweWarning::new(CWE_MODULE.name, CWE_MODULE.version, description)
+        .tids(tids)
+        .addresses(addresses)
+        .symbols(vec![sub.term.name.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::{def, expr};
+
+    fn mock_project(subs: Vec<Term<Sub>>) -> Project {
+        mock_x64_project(
+            &["EVP_DigestVerifyFinal", "mbedtls_pk_verify", "execve"],
+            subs,
+        )
+    }
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "verification_symbols": {
+                "EVP_DigestVerifyFinal": "one",
+                "mbedtls_pk_verify": "zero"
+            },
+            "action_symbols": ["execve"]
+        })
+    }
+
+    /// Create a function calling the verification function
+    /// and branching on the given condition to a block calling `execve`.
+    /// The given defs compute the condition from the return value in `RAX`.
+    fn mock_sub(verification_function: &str, defs: Vec<Term<Def>>, condition: &str) -> Term<Sub> {
+        let mut blk_verify = Blk::mock_with_tid("blk_verify");
+        blk_verify.term.jmps.push(Jmp::call(
+            "call_verify",
+            verification_function,
+            Some("blk_check"),
+        ));
+        let mut blk_check = Blk::mock_with_tid("blk_check");
+        blk_check.term.defs = defs;
+        blk_check.term.jmps = vec![
+            Term {
+                tid: Tid::new("branch_check"),
+                term: Jmp::CBranch {
+                    target: Tid::new("blk_install"),
+                    condition: expr!(condition),
+                },
+            },
+            Jmp::branch("branch_fail", "blk_end"),
+        ];
+        let mut blk_install = Blk::mock_with_tid("blk_install");
+        blk_install
+            .term
+            .jmps
+            .push(Jmp::call("call_execve", "execve", Some("blk_end")));
+        let mut blk_end = Blk::mock_with_tid("blk_end");
+        blk_end.term.jmps.push(Term {
+            tid: Tid::new("return"),
+            term: Jmp::Return(expr!("0x0:8")),
+        });
+        let mut sub = Sub::mock("install_update");
+        sub.term.blocks = vec![blk_verify, blk_check, blk_install, blk_end];
+        sub
+    }
+
+    #[test]
+    fn nonzero_treated_as_success() {
+        // if (EVP_DigestVerifyFinal(...) != 0) execve(...);
+        let sub = mock_sub(
+            "EVP_DigestVerifyFinal",
+            vec![def!["ZF:1 = RAX:8 == 0x0:8"], def!["NZ:1 = ¬(ZF:1)"]],
+            "NZ:1",
+        );
+        let warnings = run_check(check_cwe, &mock_project(vec![sub]), &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec!["call_verify", "branch_check", "call_execve"]
+        );
+        assert!(warnings[0].description.contains("Return value -1 leads"));
+    }
+
+    #[test]
+    fn correct_checks() {
+        // if (EVP_DigestVerifyFinal(...) == 1) execve(...);
+        let sub = mock_sub(
+            "EVP_DigestVerifyFinal",
+            vec![def!["RAX:8 = RAX:8 - 0x1:8"], def!["ZF:1 = RAX:8 == 0x0:8"]],
+            "ZF:1",
+        );
+        assert!(run_check(check_cwe, &mock_project(vec![sub]), &mock_config()).is_empty());
+        // if (mbedtls_pk_verify(...) == 0) execve(...);
+        let sub = mock_sub(
+            "mbedtls_pk_verify",
+            vec![def!["ZF:1 = RAX:8 == 0x0:8"]],
+            "ZF:1",
+        );
+        assert!(run_check(check_cwe, &mock_project(vec![sub]), &mock_config()).is_empty());
+    }
+
+    #[test]
+    fn inverted_check() {
+        // if (mbedtls_pk_verify(...)) execve(...);
+        let sub = mock_sub(
+            "mbedtls_pk_verify",
+            vec![def!["ZF:1 = RAX:8 == 0x0:8"]],
+            "¬(ZF:1)",
+        );
+        let warnings = run_check(check_cwe, &mock_project(vec![sub]), &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0]
+            .description
+            .contains("Return value 1 or -1 leads"));
+    }
+
+    #[test]
+    fn unchecked_result() {
+        let sub = mock_sub(
+            "mbedtls_pk_verify",
+            vec![def!["ZF:1 = RBX:8 == 0x0:8"]],
+            "ZF:1",
+        );
+        let warnings = run_check(check_cwe, &mock_project(vec![sub]), &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["call_verify", "call_execve"]);
+    }
+}
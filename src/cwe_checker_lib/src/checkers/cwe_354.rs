@@ -0,0 +1,428 @@
+//! This module implements a check for CWE-354: Improper Validation of Integrity Check Value.
+//!
+//! Protocols transmitting commands over a network or a serial line often protect their messages
+//! with a checksum or a message authentication code.
+//! If a message handler acts on the command fields of a received message
+//! without verifying the integrity check value first,
+//! corrupted or forged messages are executed.
+//!
+//! See <https://cwe.mitre.org/data/definitions/354.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [Pointer Inference analysis](`crate::analysis::pointer_inference`)
+//! we find all functions that receive a message into a buffer through an input function (e.g. `recv`)
+//! and then branch on a value loaded from the buffer, i.e. dispatch on a command field of the message.
+//! Such a handler verifies the integrity of the message
+//! if it calls a verification function (e.g. `crc32` or `HMAC_Update`) with a pointer into the buffer.
+//!
+//! If at least one handler verifies its messages, the binary demonstrably implements integrity checks.
+//! Then we report all handlers that act on received messages without verifying them.
+//! Binaries without any verified handler are not reported to keep the number of false positives low.
+//!
+//! ## False Positives
+//!
+//! - The integrity check may happen in a caller of the handler or in a lower protocol layer.
+//! - Not all messages of a protocol need to be protected, e.g. unauthenticated discovery messages.
+//!
+//! ## False Negatives
+//!
+//! - Only branches on values loaded from the buffer in the same basic block are considered command dispatches.
+//! - A handler is considered verified if it passes the buffer to a verification function,
+//!   even if the result of the verification is ignored.
+//! - Internal verification functions are only recognized by their name
+//!   and only if they receive the buffer in a parameter register.
+//! - Binaries without any verified handler are not reported.
+//!
+//! ## Configuration
+//!
+//! The input functions together with the indices of their buffer and length parameters,
+//! the verification functions and keywords identifying internal verification functions by their name
+//! are configurable in config.json.
+
+use crate::analysis::pointer_inference::PointerInference;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::buffer_utils::{get_input_buffers, points_into, Buffer, BufferParameters};
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::{get_subs_by_keywords, get_symbol_map};
+use crate::CweModule;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE354",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions receiving messages, mapped to their buffer parameters.
+    input_symbols: BTreeMap<String, BufferParameters>,
+    /// Functions computing or verifying checksums, hashes or message authentication codes.
+    verification_symbols: Vec<String>,
+    /// Internal functions whose name contains one of these keywords (case-insensitive)
+    /// are considered to be verification functions.
+    verification_function_keywords: Vec<String>,
+}
+
+/// A function that receives messages and dispatches on their content.
+#[derive(Debug, Clone)]
+struct MessageHandler<'a> {
+    /// The function.
+    sub: &'a Term<Sub>,
+    /// The call to the input function receiving the message.
+    input_call: Tid,
+    /// The input function.
+    input_symbol: &'a ExternSymbol,
+    /// The jump instruction branching on a value loaded from the message.
+    dispatch: Tid,
+    /// Whether the message is passed to a verification function.
+    is_verified: bool,
+}
+
+/// Find the first jump of the function that branches on a value loaded from one of the buffers.
+///
+/// Values loaded from the buffers are tracked through assignments inside each basic block.
+fn find_dispatch_on_buffer(
+    pi_result: &PointerInference,
+    sub: &Term<Sub>,
+    buffers: &[Buffer],
+) -> Option<Tid> {
+    for blk in sub.term.blocks.iter() {
+        let mut loaded_vars: HashSet<&Variable> = HashSet::new();
+        for def in blk.term.defs.iter() {
+            match &def.term {
+                Def::Load { var, .. } => {
+                    let loads_from_buffer = pi_result
+                        .eval_address_at_def(&def.tid)
+                        .is_some_and(|address| points_into(buffers, &address));
+                    if loads_from_buffer {
+                        loaded_vars.insert(var);
+                    } else {
+                        loaded_vars.remove(var);
+                    }
+                }
+                Def::Assign { var, value } => {
+                    if value
+                        .input_vars()
+                        .iter()
+                        .any(|input| loaded_vars.contains(input))
+                    {
+                        loaded_vars.insert(var);
+                    } else {
+                        loaded_vars.remove(var);
+                    }
+                }
+                Def::Store { .. } => (),
+            }
+        }
+        for jmp in blk.term.jmps.iter() {
+            let (Jmp::CBranch {
+                condition: expression,
+                ..
+            }
+            | Jmp::BranchInd(expression)) = &jmp.term
+            else {
+                continue;
+            };
+            if expression
+                .input_vars()
+                .iter()
+                .any(|input| loaded_vars.contains(input))
+            {
+                return Some(jmp.tid.clone());
+            }
+        }
+    }
+    None
+}
+
+/// Returns `true` if the function passes a pointer into one of the buffers to a verification function.
+fn verifies_buffer(
+    project: &Project,
+    pi_result: &PointerInference,
+    sub: &Term<Sub>,
+    buffers: &[Buffer],
+    verification_symbols: &HashMap<Tid, &ExternSymbol>,
+    verification_subs: &HashSet<Tid>,
+) -> bool {
+    let standard_parameters: Vec<Arg> = project
+        .get_standard_calling_convention()
+        .map(|cconv| {
+            cconv
+                .integer_parameter_register
+                .iter()
+                .map(|register| Arg::from_var(register.clone(), None))
+                .collect()
+        })
+        .unwrap_or_default();
+    sub.term
+        .blocks
+        .iter()
+        .flat_map(|blk| blk.term.jmps.iter())
+        .any(|jmp| {
+            let Jmp::Call { target, .. } = &jmp.term else {
+                return false;
+            };
+            let parameters = if let Some(symbol) = verification_symbols.get(target) {
+                &symbol.parameters
+            } else if verification_subs.contains(target) {
+                &standard_parameters
+            } else {
+                return false;
+            };
+            parameters.iter().any(|param| {
+                pi_result
+                    .eval_parameter_arg_at_call(&jmp.tid, param)
+                    .is_some_and(|value| points_into(buffers, &value))
+            })
+        })
+}
+
+/// Run the check.
+///
+/// First all message handlers are collected.
+/// Then the handlers that do not verify their messages are reported
+/// if at least one other handler verifies its messages.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE354.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let input_names: Vec<String> = config.input_symbols.keys().cloned().collect();
+    let input_symbols = get_symbol_map(project, &input_names);
+    // The examined sites are the message handlers.
+    let mut site_counts = SiteCounts::default();
+    if input_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let verification_symbols = get_symbol_map(project, &config.verification_symbols);
+    let verification_subs = get_subs_by_keywords(project, &config.verification_function_keywords);
+
+    let mut handlers = Vec::new();
+    for sub in project.program.term.subs.values() {
+        let mut buffers = Vec::new();
+        let mut input_call = None;
+        for jmp in sub.term.blocks.iter().flat_map(|blk| blk.term.jmps.iter()) {
+            let Jmp::Call { target, .. } = &jmp.term else {
+                continue;
+            };
+            let Some(symbol) = input_symbols.get(target) else {
+                continue;
+            };
+            let parameters = config.input_symbols[&symbol.name];
+            buffers.append(&mut get_input_buffers(
+                project, pi_result, &jmp.tid, symbol, parameters,
+            ));
+            input_call.get_or_insert((jmp.tid.clone(), *symbol));
+        }
+        let Some((input_call, input_symbol)) = input_call else {
+            continue;
+        };
+        let Some(dispatch) = find_dispatch_on_buffer(pi_result, sub, &buffers) else {
+            continue;
+        };
+        handlers.push(MessageHandler {
+            sub,
+            input_call,
+            input_symbol,
+            dispatch,
+            is_verified: verifies_buffer(
+                project,
+                pi_result,
+                sub,
+                &buffers,
+                &verification_symbols,
+                &verification_subs,
+            ),
+        });
+    }
+
+    let num_verified = handlers
+        .iter()
+        .filter(|handler| handler.is_verified)
+        .count();
+    if num_verified == 0 {
+        // Without a verified handler there is no evidence that the messages carry integrity checks.
+        for _handler in handlers.iter() {
+            site_counts.add_skipped();
+        }
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let mut cwe_warnings = Vec::new();
+    for handler in handlers.iter() {
+        if handler.is_verified {
+            site_counts.add_safe();
+        } else {
+            site_counts.add_flagged();
+            cwe_warnings.push(generate_cwe_warning(handler, num_verified, handlers.len()));
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    (Vec::new(), cwe_warnings)
+}
+
+/// Generate the CWE warning for a message handler without integrity check.
+fn generate_cwe_warning(
+    handler: &MessageHandler,
+    num_verified: usize,
+    num_handlers: usize,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Missing Integrity Check) Function {} acts on a message received by {} at {} without verifying its integrity, while {} of {} message handlers verify their messages",
+            handler.sub.term.name,
+            handler.input_symbol.name,
+            handler.input_call.address,
+            num_verified,
+            num_handlers
+        ),
+    )
+    .tids(vec![
+        format!("{}", handler.input_call),
+        format!("{}", handler.dispatch),
+    ])
+    .addresses(vec![
+        handler.input_call.address.clone(),
+        handler.dispatch.address.clone(),
+    ])
+    .symbols(vec![handler.sub.term.name.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::{def, expr};
+
+    fn mock_project(subs: Vec<Term<Sub>>) -> Project {
+        mock_x64_project(&["recv", "crc32"], subs)
+    }
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "input_symbols": {"recv": {"buffer": 1, "length": 2}},
+            "verification_symbols": ["crc32"],
+            "verification_function_keywords": ["checksum"]
+        })
+    }
+
+    /// Create a handler function that receives a message into a stack buffer
+    /// and branches on its first byte.
+    /// If `verification` is given, the function passes the buffer to it before the branch.
+    fn mock_handler(name: &str, verification: Option<&str>) -> Term<Sub> {
+        let mut receive = Blk::mock_with_tid(&format!("{name}_receive"));
+        receive.term.defs = vec![
+            def![format!("{name}_buffer: RSI:8 = RSP:8 + 0x10:8")],
+            def![format!("{name}_length: RDX:8 = 0x100:8")],
+        ];
+        receive.term.jmps = vec![Jmp::call(
+            &format!("{name}_recv"),
+            "recv",
+            Some(&format!("{name}_verify")),
+        )];
+        let mut verify = Blk::mock_with_tid(&format!("{name}_verify"));
+        verify.term.defs = vec![def![format!("{name}_verify_arg: RDI:8 = RSP:8 + 0x10:8")]];
+        let dispatch_block = format!("{name}_dispatch");
+        verify.term.jmps = vec![match verification {
+            Some(verification) => Jmp::call(
+                &format!("{name}_call_verification"),
+                verification,
+                Some(&dispatch_block),
+            ),
+            None => Jmp::branch(&format!("{name}_no_verification"), &dispatch_block),
+        }];
+        let mut dispatch = Blk::mock_with_tid(&dispatch_block);
+        dispatch.term.defs = vec![
+            def![format!(
+                "{name}_load_command: RAX:1 := Load from RSP:8 + 0x10:8"
+            )],
+            def![format!("{name}_compare: ZF:1 = RAX:1 == 0x3:1")],
+        ];
+        dispatch.term.jmps = vec![
+            Term {
+                tid: Tid::new(format!("{name}_branch")),
+                term: Jmp::CBranch {
+                    target: Tid::new(format!("{name}_exit")),
+                    condition: expr!("ZF:1"),
+                },
+            },
+            Jmp::branch(&format!("{name}_fallthrough"), &format!("{name}_exit")),
+        ];
+        let mut exit = Blk::mock_with_tid(&format!("{name}_exit"));
+        exit.term.jmps = vec![Term {
+            tid: Tid::new(format!("{name}_return")),
+            term: Jmp::Return(expr!("0x0:8")),
+        }];
+        let mut sub = Sub::mock(name);
+        sub.term.blocks = vec![receive, verify, dispatch, exit];
+        sub
+    }
+
+    #[test]
+    fn unverified_handler() {
+        let warnings = run_check(
+            check_cwe,
+            &mock_project(vec![
+                mock_handler("verified", Some("crc32")),
+                mock_handler("unverified", None),
+            ]),
+            &mock_config(),
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].symbols, vec!["unverified".to_string()]);
+        assert_eq!(
+            warnings[0].tids,
+            vec![
+                "unverified_recv".to_string(),
+                "unverified_branch".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn no_verification_in_binary() {
+        assert!(run_check(
+            check_cwe,
+            &mock_project(vec![mock_handler("unverified", None)]),
+            &mock_config()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn internal_verification_function() {
+        let mut checksum = Sub::mock("compute_checksum");
+        let mut blk = Blk::mock_with_tid("checksum_blk");
+        blk.term.defs = vec![def!["checksum_pop: RSP:8 = RSP:8 + 0x8:8"]];
+        blk.term.jmps = vec![Term {
+            tid: Tid::new("checksum_return"),
+            term: Jmp::Return(expr!("0x0:8")),
+        }];
+        checksum.term.blocks = vec![blk];
+        let warnings = run_check(
+            check_cwe,
+            &mock_project(vec![
+                checksum,
+                mock_handler("verified", Some("compute_checksum")),
+                mock_handler("unverified", None),
+            ]),
+            &mock_config(),
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].symbols, vec!["unverified".to_string()]);
+    }
+}
@@ -0,0 +1,401 @@
+//! This module implements a check for CWE-362: Concurrent Execution using Shared Resource with Improper Synchronization ('Race Condition'),
+//! in the form of Time-of-check Time-of-use (TOCTOU) races on file paths.
+//!
+//! If a program checks a property of a file by its path (e.g. with `stat` or `access`)
+//! and later uses the file by the same path (e.g. with `chmod` or `open`),
+//! an attacker may replace the file in between, e.g. by a symbolic link to another file.
+//! The check is then applied to a different file than the use.
+//!
+//! See <https://cwe.mitre.org/data/definitions/362.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! The check generalizes the check for CWE-367, which reports every `access` call followed by an `open` call,
+//! to arbitrary configurable pairs of check and use functions.
+//! In contrast to the check for CWE-367 a pair is only reported
+//! if the path argument of the use-call is the same as the path argument of the check-call:
+//!
+//! - For paths in stack or heap memory the source-to-sink taint analysis of the [`flow`](crate::analysis::taint::flow) module
+//!   taints the path that the check-call was given
+//!   and reports use-calls whose path parameter points to the tainted path.
+//!   No propagation rules are configured,
+//!   so only the unchanged path is tracked, including across function calls.
+//! - For constant paths in global memory the use-calls reachable from the check-call in the same function
+//!   are reported if their path parameter points to the same string.
+//!
+//! ### Symbols configurable in config.json
+//!
+//! - `pairs`: The pairs of check and use functions. For each pair:
+//!   - `check`: The function checking a property of a file.
+//!   - `check_path_index`: The index of the path parameter of the check function.
+//!   - `use`: The function using a file.
+//!   - `use_path_index`: The index of the path parameter of the use function.
+//!
+//! ## False Positives
+//!
+//! - The file is in a directory that cannot be modified by an attacker.
+//! - The path buffer is overwritten with a different path between the calls,
+//!   e.g. by a function whose effects on memory are unknown to the taint analysis.
+//!
+//! ## False Negatives
+//!
+//! - Paths that are copied or rebuilt between the calls (e.g. with `strcpy` or `snprintf`) are not tracked.
+//! - Constant paths are only matched if both calls are contained in the same function.
+//! - The taint analysis is limited to a fixed number of steps per check-call,
+//!   so paths passing through long call chains may not reach the use-call.
+
+use crate::abstract_domain::TryToBitvec;
+use crate::analysis::graph::{Edge, Graph, NodeIndex};
+use crate::analysis::pointer_inference::PointerInference;
+use crate::analysis::taint::flow::{find_taint_flows, TaintFlowConfig};
+use crate::analysis::taint::summary::TaintFlowTarget;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
+use crate::CweModule;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE362",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// A pair of functions checking and using a file by its path.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CheckUsePair {
+    /// The function checking a property of a file.
+    check: String,
+    /// The index of the path parameter of the check function.
+    check_path_index: usize,
+    /// The function using a file.
+    #[serde(rename = "use")]
+    use_function: String,
+    /// The index of the path parameter of the use function.
+    use_path_index: usize,
+}
+
+/// The configuration struct
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The pairs of check and use functions.
+    pairs: Vec<CheckUsePair>,
+}
+
+/// A use-call reached by the path of a check-call.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+struct PathRace {
+    /// The TID of the check-call.
+    check_call: Tid,
+    /// The name of the check function.
+    check: String,
+    /// The TID of the use-call.
+    use_call: Tid,
+    /// The name of the use function.
+    use_function: String,
+    /// The path, if it is a constant string.
+    path: Option<String>,
+}
+
+/// Search for use-calls that are reached by the unchanged path argument of a check-call.
+/// See the module-level documentation for more information.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE362.");
+    // The examined sites are the calls to check functions.
+    let mut site_counts = SiteCounts::default();
+    let Some(pi_result) = analysis_results.pointer_inference else {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    };
+    let tainted_path_races = find_tainted_path_races(analysis_results, &config);
+    let constant_path_races = find_constant_path_races(
+        analysis_results.project,
+        analysis_results.control_flow_graph,
+        pi_result,
+        &config,
+    );
+
+    // Report each pair of calls only once, preferring the variant containing the constant path.
+    let mut races = BTreeMap::new();
+    for race in tainted_path_races.into_iter().chain(constant_path_races) {
+        races.insert((race.check_call.clone(), race.use_call.clone()), race);
+    }
+    let flagged_calls: BTreeSet<&Tid> = races.keys().map(|(check_call, _)| check_call).collect();
+    let check_names: Vec<String> = config.pairs.iter().map(|pair| pair.check.clone()).collect();
+    let check_symbols = get_symbol_map(analysis_results.project, &check_names);
+    for sub in analysis_results.project.program.term.subs.values() {
+        for (_, jmp, _) in get_callsites(sub, &check_symbols) {
+            if flagged_calls.contains(&jmp.tid) {
+                site_counts.add_flagged();
+            } else {
+                site_counts.add_safe();
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    let cwe_warnings = races.values().map(generate_cwe_warning).collect();
+    (Vec::new(), cwe_warnings)
+}
+
+/// Track the path arguments of the check-calls with the taint analysis
+/// and collect the use-calls whose path parameter points to a tracked path.
+fn find_tainted_path_races(
+    analysis_results: &AnalysisResults,
+    config: &Config,
+) -> BTreeSet<PathRace> {
+    let mut sinks_per_check: BTreeMap<(&str, usize), BTreeMap<String, Vec<usize>>> =
+        BTreeMap::new();
+    for pair in config.pairs.iter() {
+        sinks_per_check
+            .entry((pair.check.as_str(), pair.check_path_index))
+            .or_default()
+            .entry(pair.use_function.clone())
+            .or_default()
+            .push(pair.use_path_index);
+    }
+
+    let mut races = BTreeSet::new();
+    for ((check, check_path_index), sinks) in sinks_per_check {
+        let taint_config = TaintFlowConfig {
+            sources: BTreeMap::from([(
+                check.to_string(),
+                TaintFlowTarget::ParameterTarget(check_path_index),
+            )]),
+            sinks,
+            ..Default::default()
+        };
+        for flow in find_taint_flows(analysis_results, &taint_config) {
+            races.insert(PathRace {
+                check_call: flow.source.call_tid,
+                check: flow.source.function,
+                use_call: flow.sink.call_tid,
+                use_function: flow.sink.function,
+                path: None,
+            });
+        }
+    }
+    races
+}
+
+/// Collect the use-calls that are reachable from a check-call in the same function
+/// and that get the same constant path as the check-call.
+fn find_constant_path_races(
+    project: &Project,
+    graph: &Graph,
+    pi_result: &PointerInference,
+    config: &Config,
+) -> BTreeSet<PathRace> {
+    let mut races = BTreeSet::new();
+    for pair in config.pairs.iter() {
+        let symbol_map = get_symbol_map(project, &[pair.check.clone(), pair.use_function.clone()]);
+        let (Some(check_symbol), Some(use_symbol)) = (
+            symbol_map.values().find(|symbol| symbol.name == pair.check),
+            symbol_map
+                .values()
+                .find(|symbol| symbol.name == pair.use_function),
+        ) else {
+            continue;
+        };
+        for edge in graph.edge_references() {
+            let Edge::ExternCallStub(jmp) = edge.weight() else {
+                continue;
+            };
+            if !matches!(&jmp.term, Jmp::Call { target, .. } if *target == check_symbol.tid) {
+                continue;
+            }
+            let Some(path) = get_constant_path(
+                project,
+                pi_result,
+                &jmp.tid,
+                check_symbol,
+                pair.check_path_index,
+            ) else {
+                continue;
+            };
+            for use_call in get_reachable_calls(graph, edge.target(), &use_symbol.tid) {
+                let use_path = get_constant_path(
+                    project,
+                    pi_result,
+                    &use_call.tid,
+                    use_symbol,
+                    pair.use_path_index,
+                );
+                if use_path.as_ref() == Some(&path) {
+                    races.insert(PathRace {
+                        check_call: jmp.tid.clone(),
+                        check: pair.check.clone(),
+                        use_call: use_call.tid.clone(),
+                        use_function: pair.use_function.clone(),
+                        path: Some(path.clone()),
+                    });
+                }
+            }
+        }
+    }
+    races
+}
+
+/// Get the path parameter of a call if it points to a constant string in global memory.
+fn get_constant_path(
+    project: &Project,
+    pi_result: &PointerInference,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    path_index: usize,
+) -> Option<String> {
+    let param = project.get_parameter_arg(symbol, path_index)?;
+    let address = pi_result
+        .eval_parameter_arg_at_call(call_tid, &param)?
+        .get_if_absolute_value()?
+        .try_to_bitvec()
+        .ok()?;
+    project
+        .runtime_memory_image
+        .read_string_until_null_terminator(&address)
+        .ok()
+        .map(str::to_string)
+}
+
+/// Collect the calls to the given target that are reachable from the given node
+/// through a path of intraprocedural edges in the control flow graph.
+fn get_reachable_calls<'a>(
+    graph: &Graph<'a>,
+    start_node: NodeIndex,
+    target: &Tid,
+) -> Vec<&'a Term<Jmp>> {
+    let mut calls = Vec::new();
+    let mut visited_nodes = HashSet::from([start_node]);
+    let mut worklist = vec![start_node];
+    while let Some(node) = worklist.pop() {
+        for edge in graph.edges(node) {
+            match edge.weight() {
+                Edge::Call(_) | Edge::CrReturnStub => continue,
+                Edge::ExternCallStub(jmp) => {
+                    if matches!(&jmp.term, Jmp::Call { target: call_target, .. } if call_target == target)
+                    {
+                        calls.push(*jmp);
+                    }
+                }
+                _ => (),
+            }
+            if visited_nodes.insert(edge.target()) {
+                worklist.push(edge.target());
+            }
+        }
+    }
+    calls
+}
+
+/// Generate the CWE warning for a use-call reached by the path of a check-call.
+fn generate_cwe_warning(race: &PathRace) -> CweWarning {
+    let path = race
+        .path
+        .as_ref()
+        .map(|path| format!(" \"{path}\""))
+        .unwrap_or_default();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Time-of-check Time-of-use Race Condition) The path{} checked by {} at {} is used by {} at {}. The file may be replaced in between.",
+            path, race.check, race.check_call.address, race.use_function, race.use_call.address
+        ),
+    )
+    .tids(vec![format!("{}", race.check_call), format!("{}", race.use_call)])
+    .addresses(vec![
+        race.check_call.address.clone(),
+        race.use_call.address.clone(),
+    ])
+    .symbols(vec![race.check.clone(), race.use_function.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::defs;
+    use crate::utils::binary::MemorySegment;
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "pairs": [
+                {"check": "stat", "check_path_index": 0, "use": "chmod", "use_path_index": 0},
+                {"check": "stat", "check_path_index": 0, "use": "unlink", "use_path_index": 0}
+            ]
+        })
+    }
+
+    /// Create a project with a function `func` that calls `stat` and then `chmod` and `unlink`.
+    fn mock_project(stat_defs: Vec<Term<Def>>, chmod_defs: Vec<Term<Def>>) -> Project {
+        let mut blk_stat = Blk::mock_with_tid("blk_stat");
+        blk_stat.term.defs = stat_defs;
+        blk_stat
+            .term
+            .jmps
+            .push(Jmp::call("call_stat", "stat", Some("blk_chmod")));
+        let mut blk_chmod = Blk::mock_with_tid("blk_chmod");
+        blk_chmod.term.defs = chmod_defs;
+        blk_chmod
+            .term
+            .jmps
+            .push(Jmp::call("call_chmod", "chmod", Some("blk_unlink")));
+        let mut blk_unlink = Blk::mock_with_tid("blk_unlink");
+        blk_unlink.term.defs = defs!["other_path: RDI:8 = 0x9009:8"];
+        blk_unlink
+            .term
+            .jmps
+            .push(Jmp::call("call_unlink", "unlink", Some("blk_end")));
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![
+            blk_stat,
+            blk_chmod,
+            blk_unlink,
+            Blk::mock_with_tid("blk_end"),
+        ];
+        let mut project = mock_x64_project(&["stat", "chmod", "unlink"], vec![sub]);
+        project
+            .runtime_memory_image
+            .memory_segments
+            .push(MemorySegment {
+                bytes: b"/tmp/log\x00/tmp/other\x00".to_vec(),
+                base_address: 0x9000,
+                read_flag: true,
+                write_flag: false,
+                execute_flag: false,
+            });
+        project
+    }
+
+    #[test]
+    fn path_buffer_flows_to_use() {
+        let project = mock_project(
+            defs!["path: RDI:8 = RSP:8 + 16:8", "save_path: RBX:8 = RDI:8"],
+            defs!["same_path: RDI:8 = RBX:8"],
+        );
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["call_stat", "call_chmod"]);
+    }
+
+    #[test]
+    fn constant_path() {
+        let project = mock_project(
+            defs!["path: RDI:8 = 0x9000:8"],
+            defs!["same_path: RDI:8 = 0x9000:8"],
+        );
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["call_stat", "call_chmod"]);
+        assert!(warnings[0].description.contains("/tmp/log"));
+    }
+}
@@ -27,6 +27,7 @@ use crate::intermediate_representation::Jmp;
 use crate::prelude::*;
 use crate::utils::graph_utils::is_sink_call_reachable_from_source_call;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
 use crate::CweModule;
 use petgraph::visit::EdgeRef;
 use std::collections::HashMap;
@@ -75,6 +76,8 @@ pub fn check_cwe(
     let project = analysis_results.project;
     let graph = analysis_results.control_flow_graph;
     let mut cwe_warnings = Vec::new();
+    // The examined sites are the calls to the time-of-check functions.
+    let mut site_counts = SiteCounts::default();
 
     let symbol_map: HashMap<&str, Tid> = project
         .program
@@ -104,6 +107,7 @@ pub fn check_cwe(
                                     Node::BlkStart(_blk, sub) => sub.term.name.as_str(),
                                     _ => panic!("Malformed control flow graph."),
                                 };
+                                site_counts.add_flagged();
                                 cwe_warnings.push(generate_cwe_warning(
                                     source.as_str(),
                                     sink.as_str(),
@@ -111,6 +115,8 @@ pub fn check_cwe(
                                     sink_callsite,
                                     sub_name,
                                 ));
+                            } else {
+                                site_counts.add_safe();
                             }
                         }
                     }
@@ -118,6 +124,7 @@ pub fn check_cwe(
             }
         }
     }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (Vec::new(), cwe_warnings)
 }
@@ -0,0 +1,422 @@
+//! This module implements a check for CWE-369: Divide By Zero.
+//!
+//! An integer division or modulo operation with a divisor of zero is undefined behavior
+//! and usually crashes the program.
+//! If an attacker can choose the divisor, e.g. as the number of records or the block size
+//! in a parsed file or network message, this results in a denial of service.
+//!
+//! See <https://cwe.mitre.org/data/definitions/369.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! For each integer division or modulo operation (signed or unsigned) in the program
+//! we compute the value of the divisor using the results of the
+//! [Pointer Inference analysis](`crate::analysis::pointer_inference`).
+//! A warning is generated if the divisor is always zero.
+//!
+//! Additionally, we use a taint analysis starting at the return values of user input sources
+//! (e.g. `getenv` or `strtoul`) and the buffers filled by input functions (e.g. `read` or `recv`).
+//! If the divisor is tainted and its value interval contains zero, a warning is generated.
+//! In conservative mode the taint analysis is skipped and only divisors that are always zero are reported.
+//!
+//! ## False Positives
+//!
+//! - The value interval of the divisor may contain zero even though the divisor was checked against zero,
+//!   e.g. if the check is done in another function or the comparison is not recognized by the Pointer Inference analysis.
+//! - Divisions by a divisor that is always zero may be on infeasible paths.
+//!
+//! ## False Negatives
+//!
+//! - Untrusted divisors that are not derived from the configured sources are only reported if they are always zero.
+//! - For input buffers only the first pointer-sized value is tainted.
+//! - The taint analysis is intraprocedural.
+//! - Floating point divisions are not checked.
+//! - Divisions in library functions (e.g. `ldiv`) are not checked.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The user input sources and the input functions together with the index of their buffer parameter
+//! are configurable in config.json.
+//! The `conservative_mode` flag restricts the check to divisors that are always zero.
+
+use crate::abstract_domain::{SizedDomain, TryToBitvec, TryToInterval};
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, HasCfg, Node};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{
+    Data as PiData, PointerInference as PointerInferenceComputation, State as PiState,
+};
+use crate::analysis::taint::{
+    state::State as TaState, summary::TaintSummaries, Taint, TaintAnalysis,
+};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE369",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions whose return values are controlled by the user.
+    sources: Vec<String>,
+    /// Functions that write user input into a buffer,
+    /// mapped to the index of the parameter pointing to the buffer.
+    buffer_sources: BTreeMap<String, usize>,
+    /// Only report divisors that are always zero.
+    conservative_mode: bool,
+}
+
+/// Run the check.
+///
+/// We compute the divisors of all division and modulo operations with the Pointer Inference results
+/// and report divisors that are always zero
+/// or (if not in conservative mode) tainted divisors that may be zero.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE369.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let tainted_divisions = if config.conservative_mode {
+        BTreeSet::new()
+    } else {
+        compute_tainted_divisions(analysis_results, &config)
+    };
+
+    // Maps the TIDs of the reported `Def` terms to the corresponding warning.
+    let mut cwe_warnings: BTreeMap<Tid, CweWarning> = BTreeMap::new();
+    // The examined sites are the divisions.
+    let mut site_counts = SiteCounts::default();
+    let graph = pi_result.get_graph();
+    for node in graph.node_indices() {
+        let Node::BlkStart(blk, sub) = graph[node] else {
+            continue;
+        };
+        let Some(NodeValue::Value(state)) = pi_result.get_node_value(node) else {
+            continue;
+        };
+        let mut state = state.clone();
+        for def in blk.term.defs.iter() {
+            if let Def::Assign { value, .. } = &def.term {
+                let divisors = get_divisors(value);
+                let mut may_be_zero = false;
+                for divisor in divisors.iter() {
+                    let divisor_value = state.eval(divisor);
+                    let must_be_zero = divisor_must_be_zero(&divisor_value);
+                    may_be_zero |= divisor_may_be_zero(&divisor_value);
+                    if must_be_zero
+                        || (tainted_divisions.contains(&def.tid)
+                            && divisor_may_be_zero(&divisor_value))
+                    {
+                        cwe_warnings
+                            .entry(def.tid.clone())
+                            .or_insert_with(|| generate_cwe_warning(sub, &def.tid, must_be_zero));
+                    }
+                }
+                if !divisors.is_empty() {
+                    if cwe_warnings.contains_key(&def.tid) {
+                        site_counts.add_flagged();
+                    } else if may_be_zero {
+                        site_counts.add_skipped();
+                    } else {
+                        site_counts.add_safe();
+                    }
+                }
+            }
+            update_pi_state(&mut state, def, project);
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings.into_values().collect())
+}
+
+/// Return the divisors of all integer division and modulo operations contained in the expression.
+fn get_divisors(expression: &Expression) -> Vec<&Expression> {
+    use Expression::*;
+    match expression {
+        BinOp {
+            op: BinOpType::IntDiv | BinOpType::IntSDiv | BinOpType::IntRem | BinOpType::IntSRem,
+            lhs,
+            rhs,
+        } => [get_divisors(lhs), get_divisors(rhs), vec![rhs.as_ref()]].concat(),
+        BinOp { lhs, rhs, .. } => [get_divisors(lhs), get_divisors(rhs)].concat(),
+        UnOp { arg, .. } | Cast { arg, .. } | Subpiece { arg, .. } => get_divisors(arg),
+        Var(_) | Const(_) | Unknown { .. } => Vec::new(),
+    }
+}
+
+/// Check whether the divisor is always zero.
+fn divisor_must_be_zero(divisor: &PiData) -> bool {
+    divisor
+        .get_if_absolute_value()
+        .and_then(|value| value.try_to_bitvec().ok())
+        .is_some_and(|value| value.is_zero())
+}
+
+/// Check whether the divisor may be zero.
+///
+/// Pointers are assumed to be non-zero, so that only absolute and unknown values may be zero.
+fn divisor_may_be_zero(divisor: &PiData) -> bool {
+    if divisor.contains_top() {
+        return true;
+    }
+    match divisor.get_absolute_value() {
+        Some(value) => match value.try_to_interval() {
+            Ok(interval) => interval.contains(&Bitvector::zero(divisor.bytesize().into())),
+            Err(_) => true,
+        },
+        None => false,
+    }
+}
+
+/// Update the Pointer Inference state with the effects of the given `Def` term.
+fn update_pi_state(state: &mut PiState, def: &Term<Def>, project: &Project) {
+    match &def.term {
+        Def::Store { address, value } => {
+            let _ = state.handle_store(address, value, &project.runtime_memory_image);
+        }
+        Def::Assign { var, value } => {
+            state.handle_register_assign(var, value);
+        }
+        Def::Load { var, address } => {
+            let _ = state.handle_load(var, address, &project.runtime_memory_image);
+        }
+    }
+}
+
+/// Compute the TIDs of all `Def` terms containing a division or modulo operation with a tainted divisor.
+///
+/// The return values of the configured sources and the buffers filled by the configured buffer sources are tainted.
+fn compute_tainted_divisions(analysis_results: &AnalysisResults, config: &Config) -> BTreeSet<Tid> {
+    let project = analysis_results.project;
+    let pi_result = analysis_results.pointer_inference.unwrap();
+    let source_map = get_symbol_map(project, &config.sources);
+    let buffer_source_names: Vec<String> = config.buffer_sources.keys().cloned().collect();
+    let buffer_source_map = get_symbol_map(project, &buffer_source_names);
+    if source_map.is_empty() && buffer_source_map.is_empty() {
+        return BTreeSet::new();
+    }
+
+    let graph = analysis_results.control_flow_graph;
+    let (cwe_sender, cwe_receiver) = crossbeam_channel::unbounded();
+    let context = Context {
+        project,
+        pi_result,
+        control_flow_graph: graph,
+        taint_summaries: analysis_results.taint_summaries,
+        cwe_collector: cwe_sender,
+    };
+    let mut computation = create_computation(context, None);
+    computation.set_cancellation_token(analysis_results.cancellation_token);
+
+    for edge in graph.edge_references() {
+        let Edge::ExternCallStub(jmp) = edge.weight() else {
+            continue;
+        };
+        let Jmp::Call { target, .. } = &jmp.term else {
+            continue;
+        };
+        let return_node = edge.target();
+        let mut state = match source_map.get(target) {
+            Some(symbol) => TaState::new_return(symbol, pi_result, return_node),
+            None => TaState::new_empty(),
+        };
+        if let Some(symbol) = buffer_source_map.get(target) {
+            let buffer = config.buffer_sources[&symbol.name];
+            if let Some(param) = symbol.parameters.get(buffer) {
+                if let Some(address) = pi_result.eval_parameter_arg_at_call(&jmp.tid, param) {
+                    state.save_taint_to_memory(
+                        &address,
+                        Taint::Tainted(project.get_pointer_bytesize()),
+                    );
+                }
+            }
+        }
+        if !state.is_empty() {
+            computation.set_node_value(return_node, NodeValue::Value(state));
+        }
+    }
+
+    computation.compute_with_max_steps(100);
+
+    cwe_receiver.try_iter().collect()
+}
+
+/// The Context struct for the taint analysis.
+struct Context<'a> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInferenceComputation<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where the TIDs of `Def` terms with tainted divisors are sent to.
+    cwe_collector: crossbeam_channel::Sender<Tid>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
+    /// Report the assignment if it contains a division or modulo operation with a tainted divisor.
+    /// Then taint the destination register if the assigned value is tainted.
+    fn update_def_assign(
+        &self,
+        state: &TaState,
+        tid: &Tid,
+        var: &Variable,
+        value: &Expression,
+    ) -> TaState {
+        if get_divisors(value)
+            .into_iter()
+            .any(|divisor| state.eval(divisor).is_tainted())
+        {
+            let _ = self.cwe_collector.send(tid.clone());
+        }
+        let mut new_state = state.clone();
+        new_state.set_register_taint(var, state.eval(value));
+        new_state
+    }
+}
+
+/// Generate the CWE warning for a division at the given `Def` term.
+fn generate_cwe_warning(sub: &Term<Sub>, def_tid: &Tid, must_be_zero: bool) -> CweWarning {
+    let reason = if must_be_zero {
+        "is always zero"
+    } else {
+        "depends on user input and may be zero"
+    };
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Divide By Zero) The divisor of the division at {} ({}) {}",
+            def_tid.address, sub.term.name, reason
+        ),
+    )
+    .tids(vec![format!("{def_tid}")])
+    .addresses(vec![def_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec![
+        "must_be_zero".to_string(),
+        must_be_zero.to_string(),
+    ]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstract_domain::{IntervalDomain, RegisterDomain};
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::{def, expr, variable};
+
+    fn bin_op(op: BinOpType, lhs: Expression, rhs: Expression) -> Expression {
+        Expression::BinOp {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    #[test]
+    fn divisors() {
+        let modulo = bin_op(BinOpType::IntSRem, expr!("RCX:8"), expr!("0x10:8"));
+        let division = bin_op(BinOpType::IntDiv, expr!("RBX:8"), modulo.clone());
+        let expression = bin_op(BinOpType::IntAdd, expr!("RAX:8"), division);
+        assert_eq!(get_divisors(&expression), vec![&expr!("0x10:8"), &modulo]);
+        let multiplication = bin_op(BinOpType::IntMult, expr!("RAX:8"), expr!("RBX:8"));
+        assert!(get_divisors(&multiplication).is_empty());
+    }
+
+    #[test]
+    fn zero_divisors() {
+        let zero = PiData::from(Bitvector::from_u64(0));
+        assert!(divisor_must_be_zero(&zero));
+        assert!(divisor_may_be_zero(&zero));
+
+        let interval = PiData::from(IntervalDomain::mock(-2, 5));
+        assert!(!divisor_must_be_zero(&interval));
+        assert!(divisor_may_be_zero(&interval));
+
+        let positive = PiData::from(IntervalDomain::mock(1, 5));
+        assert!(!divisor_may_be_zero(&positive));
+
+        let top = PiData::new_top(ByteSize::new(8));
+        assert!(!divisor_must_be_zero(&top));
+        assert!(divisor_may_be_zero(&top));
+    }
+
+    #[test]
+    fn division_by_constant_zero() {
+        let mut blk = Blk::mock_with_tid("blk");
+        let assign = |tid: &str, var: &str, value: Expression| Term {
+            tid: Tid::new(tid),
+            term: Def::Assign {
+                var: variable!(var),
+                value,
+            },
+        };
+        blk.term.defs = vec![
+            def!["set_divisor: RCX:8 = 0x0:8"],
+            assign(
+                "division",
+                "RAX:8",
+                bin_op(BinOpType::IntDiv, expr!("RAX:8"), expr!("RCX:8")),
+            ),
+            assign(
+                "modulo",
+                "RDX:8",
+                bin_op(BinOpType::IntRem, expr!("RAX:8"), expr!("0x3:8")),
+            ),
+        ];
+        let mut sub = Sub::mock("func");
+        sub.term.blocks.push(blk);
+        let project = mock_x64_project(&[], vec![sub]);
+
+        let config = serde_json::json!({
+            "sources": [],
+            "buffer_sources": {},
+            "conservative_mode": true
+        });
+        let warnings = run_check(check_cwe, &project, &config);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["division".to_string()]);
+    }
+}
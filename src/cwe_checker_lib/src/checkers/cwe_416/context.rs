@@ -9,8 +9,8 @@ use crate::analysis::pointer_inference::PointerInference;
 use crate::analysis::vsa_results::VsaResult;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
-use crate::utils::log::CweWarning;
 use crate::utils::log::LogMessage;
+use crate::utils::log::{Confidence, CweWarning};
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 
@@ -138,16 +138,21 @@ impl<'a> Context<'a> {
 
     /// Handle a call to `free` by marking the corresponding memory object IDs as dangling and detecting possible double frees.
     fn handle_call_to_free(&self, state: &mut State, call_tid: &Tid, free_symbol: &ExternSymbol) {
-        if free_symbol.parameters.is_empty() {
+        let pointer_index = self
+            .pointer_inference
+            .get_allocator_function(&free_symbol.name)
+            .and_then(|function| function.pointer_parameter())
+            .unwrap_or(0);
+        let Some(pointer_param) = free_symbol.parameters.get(pointer_index) else {
             let error_msg = LogMessage::new_error("free symbol without parameter encountered.")
                 .location(call_tid.clone())
                 .source(CWE_MODULE.name);
             self.log_collector.send(error_msg).unwrap();
             return;
-        }
+        };
         if let Some(param) = self
             .pointer_inference
-            .eval_parameter_arg_at_call(call_tid, &free_symbol.parameters[0])
+            .eval_parameter_arg_at_call(call_tid, pointer_param)
         {
             if let Some(pi_state) = self.pointer_inference.get_state_at_jmp_tid(call_tid) {
                 if let Some(warning_causes) =
@@ -185,6 +190,9 @@ impl<'a> Context<'a> {
             symbols: Vec::new(),
             other: Vec::new(),
             description,
+            confidence: Confidence::Normal,
+            provenance: Vec::new(),
+            merged_cwes: Vec::new(),
         };
         self.cwe_warning_collector
             .send(WarningContext::new(
@@ -20,7 +20,10 @@
 //! ### Symbols configurable in config.json
 //!
 //! - The `deallocation_symbols` are the names of extern functions that deallocate memory.
-//! The check always assumes that the first parameter of such a function is the memory object to be freed.
+//! The index of the parameter pointing to the memory object to be freed is taken from the `allocator_functions`
+//! of the pointer inference configuration (the `Memory` section of config.json).
+//! Deallocation and reallocation functions configured there are also handled as deallocation symbols.
+//! For functions without a known parameter layout the first parameter is assumed to be the memory object to be freed.
 //! The check also assumes that memory is always freed by such a call,
 //! which can lead to false positive warnings for functions like `realloc`, where the memory object may not be freed by the call.
 //! - The `always_include_full_path_to_free_site` flag controls the amount of context information printed in the CWE warnings.
@@ -59,6 +62,7 @@ use crate::abstract_domain::AbstractIdentifier;
 use crate::prelude::*;
 use crate::utils::log::CweWarning;
 use crate::utils::log::LogMessage;
+use crate::utils::metrics::count_function_sites;
 use crate::CweModule;
 use std::collections::BTreeSet;
 use std::collections::HashSet;
@@ -74,7 +78,8 @@ pub static CWE_MODULE: CweModule = CweModule {
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Config {
     /// The names of symbols that free memory (e.g. the "free" function of C).
-    /// The analysis always assumes that the memory object to be freed is the first parameter of the function.
+    /// The parameter pointing to the memory object to be freed is configured in the pointer inference configuration.
+    /// If it is not configured there, then the first parameter is assumed.
     deallocation_symbols: Vec<String>,
     /// If this flag is set to `true`,
     /// then always include the full path to the actual `free`-site in the callgraph in the CWE warning context information.
@@ -97,7 +102,19 @@ pub fn check_cwe(
     config_json: &serde_json::Value,
 ) -> (Vec<LogMessage>, Vec<CweWarning>) {
     let config: Config = serde_json::from_value(config_json.clone()).unwrap();
-    let deallocation_symbols = config.deallocation_symbols.iter().cloned().collect();
+    let configured_deallocation_symbols = analysis_results
+        .pointer_inference
+        .unwrap()
+        .get_configured_allocator_functions()
+        .iter()
+        .filter(|(_, function)| function.pointer_parameter().is_some())
+        .map(|(name, _)| name);
+    let deallocation_symbols = config
+        .deallocation_symbols
+        .iter()
+        .chain(configured_deallocation_symbols)
+        .cloned()
+        .collect();
     let (cwe_warning_sender, cwe_warning_receiver) = crossbeam_channel::unbounded();
     let (log_sender, log_receiver) = crossbeam_channel::unbounded();
     let context = Context::new(
@@ -109,6 +126,7 @@ pub fn check_cwe(
 
     let mut fixpoint_computation =
         crate::analysis::forward_interprocedural_fixpoint::create_computation(context, None);
+    fixpoint_computation.set_cancellation_token(analysis_results.cancellation_token);
 
     for (sub_tid, entry_node_of_sub) in
         crate::analysis::graph::get_entry_nodes_of_subs(analysis_results.control_flow_graph)
@@ -126,17 +144,22 @@ pub fn check_cwe(
     while let Ok(warning) = cwe_warning_receiver.try_recv() {
         warnings.insert(warning);
     }
-    let cwes = generate_context_information_for_warnings(
+    let cwes: Vec<CweWarning> = generate_context_information_for_warnings(
         warnings,
         config.always_include_full_path_to_free_site,
-    );
+    )
+    .into_iter()
+    .collect();
+    // All functions are analyzed, since each function gets a start state.
+    let site_counts = count_function_sites(analysis_results.project, &cwes, |_| true);
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     let mut logs = BTreeSet::new();
     while let Ok(log_msg) = log_receiver.try_recv() {
         logs.insert(log_msg);
     }
 
-    (logs.into_iter().collect(), cwes.into_iter().collect())
+    (logs.into_iter().collect(), cwes)
 }
 
 /// A struct for collecting CWE warnings together with context information
@@ -34,6 +34,7 @@
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
 use crate::utils::symbol_utils::{find_symbol, get_calls_to_symbols};
 use crate::CweModule;
 use std::collections::HashMap;
@@ -87,14 +88,21 @@ pub fn check_cwe(
     if let Some((tid, name)) = find_symbol(&project.program, "system") {
         system_symbol.insert(tid, name);
     }
-    if !system_symbol.is_empty() && !privilege_changing_symbols.is_empty() {
+    // The examined sites are the functions calling `system`.
+    let mut site_counts = SiteCounts::default();
+    if !system_symbol.is_empty() {
         for sub in project.program.term.subs.values() {
-            if !get_calls_to_symbols(sub, &system_symbol).is_empty()
-                && !get_calls_to_symbols(sub, &privilege_changing_symbols).is_empty()
-            {
+            if get_calls_to_symbols(sub, &system_symbol).is_empty() {
+                continue;
+            }
+            if !get_calls_to_symbols(sub, &privilege_changing_symbols).is_empty() {
+                site_counts.add_flagged();
                 cwe_warnings.push(generate_cwe_warning(sub));
+            } else {
+                site_counts.add_safe();
             }
         }
     }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
     (Vec::new(), cwe_warnings)
 }
@@ -0,0 +1,455 @@
+//! This module implements a check for CWE-434: Unrestricted Upload of File with Dangerous Type.
+//!
+//! Embedded web servers often save uploaded files under a name chosen by the client
+//! or execute CGI programs chosen by request parameters.
+//! If the file name is not checked for its extension and for path traversal sequences,
+//! an attacker can upload scripts or executables and overwrite or run arbitrary files on the device.
+//!
+//! See <https://cwe.mitre.org/data/definitions/434.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! First we recognize embedded web server frameworks like GoAhead, boa or mongoose
+//! by the presence of characteristic symbols in the binary.
+//! The check only runs if at least one framework was recognized.
+//!
+//! Then a taint analysis tracks the request fields returned by the getter functions of the recognized frameworks.
+//! Path building functions like `sprintf` propagate the taint to their destination buffer.
+//! If a tainted value reaches a function that creates, renames or executes files (e.g. `fopen`),
+//! we generate a warning.
+//! If the tainted value is passed to a validation function (e.g. `strrchr` to check the file extension)
+//! before it reaches such a function, the value is assumed to be validated and no warning is generated.
+//!
+//! ### Symbols configurable in config.json
+//!
+//! - `frameworks`: The recognized web server frameworks. For each framework:
+//!   - `signatures`: Symbols whose presence in the binary indicate the framework.
+//!   - `return_sources`: Functions returning request fields.
+//!   - `output_sources`: Functions writing request fields into a buffer,
+//!     mapped to the parameter index of the buffer.
+//! - `path_builders`: Functions that write their arguments into a buffer,
+//!   mapped to the parameter index of the destination buffer.
+//! - `validators`: Functions that indicate a validation of the path or its extension.
+//! - `sinks`: Functions that create, rename or execute files.
+//!
+//! ## False Positives
+//!
+//! - Validation by custom code, e.g. byte-wise loops over the file name, is not recognized.
+//! - Request fields that are used as file contents instead of file names are also flagged
+//!   if the sink function accesses both, e.g. for `fwrite`-like sinks.
+//!
+//! ## False Negatives
+//!
+//! - Frameworks that are statically linked and stripped are not recognized.
+//! - Getter functions that are linked statically into the binary are not used as taint sources.
+//! - Calling a validation function is enough to suppress a warning,
+//!   even if its result is ignored afterwards.
+
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, HasCfg};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{
+    Data as PiData, PointerInference as PointerInferenceComputation,
+};
+use crate::analysis::taint::{
+    state::State as TaState, summary::TaintSummaries, Taint, TaintAnalysis,
+};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::{
+    log::{CweWarning, LogMessage},
+    metrics::SiteCounts,
+    symbol_utils,
+};
+use crate::CweModule;
+
+use petgraph::visit::EdgeRef;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::AsRef;
+
+/// The module name and version.
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE434",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The symbols belonging to an embedded web server framework.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Framework {
+    /// The name of the framework and the symbols indicating it.
+    #[serde(flatten)]
+    signature: symbol_utils::FrameworkSignature,
+    /// Functions returning request fields.
+    return_sources: Vec<String>,
+    /// Functions writing request fields into a buffer,
+    /// mapped to the parameter index of the buffer.
+    output_sources: BTreeMap<String, usize>,
+}
+
+impl AsRef<symbol_utils::FrameworkSignature> for Framework {
+    fn as_ref(&self) -> &symbol_utils::FrameworkSignature {
+        &self.signature
+    }
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The recognized web server frameworks.
+    frameworks: Vec<Framework>,
+    /// Functions assembling their arguments into a buffer,
+    /// mapped to the parameter index of the destination buffer.
+    path_builders: BTreeMap<String, usize>,
+    /// Functions that indicate a validation of their arguments.
+    validators: Vec<String>,
+    /// Functions that create, rename or execute files.
+    sinks: Vec<String>,
+}
+
+/// Run the CWE check.
+///
+/// We taint the request fields returned by the getter functions of all recognized frameworks
+/// and generate a warning for each call to a sink function
+/// where some parameter or some memory pointed to by a parameter may be tainted.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE434.");
+
+    let frameworks = symbol_utils::get_recognized_frameworks(project, &config.frameworks);
+    let sink_map = symbol_utils::get_symbol_map(project, &config.sinks[..]);
+    // The examined sites are the calls to sinks.
+    let mut site_counts = SiteCounts::default();
+    if frameworks.is_empty() || sink_map.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let sink_call_addresses: Vec<String> = project
+        .program
+        .term
+        .subs
+        .values()
+        .flat_map(|sub| symbol_utils::get_callsites(sub, &sink_map))
+        .map(|(_, jmp, _)| jmp.tid.address.clone())
+        .collect();
+    // Maps the TIDs of source functions to the symbol and the index of the output parameter (if any).
+    let mut source_map: HashMap<Tid, (&ExternSymbol, Option<usize>)> = HashMap::new();
+    for framework in frameworks.iter() {
+        for (tid, symbol) in symbol_utils::get_symbol_map(project, &framework.return_sources[..]) {
+            source_map.insert(tid, (symbol, None));
+        }
+        for (tid, (symbol, index)) in get_buffer_param_map(project, &framework.output_sources) {
+            source_map.insert(tid, (symbol, Some(index)));
+        }
+    }
+
+    let pi_result = analysis_results.pointer_inference.unwrap();
+    let graph = analysis_results.control_flow_graph;
+    let (cwe_sender, cwe_receiver) = crossbeam_channel::unbounded();
+
+    let context = Context {
+        project,
+        pi_result,
+        control_flow_graph: graph,
+        sink_map,
+        path_builder_map: get_buffer_param_map(project, &config.path_builders),
+        validator_tids: symbol_utils::get_symbol_map(project, &config.validators[..])
+            .into_keys()
+            .collect(),
+        extern_symbol_map: project
+            .program
+            .term
+            .extern_symbols
+            .iter()
+            .map(|(tid, sym)| (tid.clone(), sym))
+            .collect(),
+        taint_summaries: analysis_results.taint_summaries,
+        cwe_collector: cwe_sender,
+    };
+    let mut computation = create_computation(context, None);
+    computation.set_cancellation_token(analysis_results.cancellation_token);
+
+    for edge in graph.edge_references() {
+        let Edge::ExternCallStub(jmp) = edge.weight() else {
+            continue;
+        };
+        let Jmp::Call { target, .. } = &jmp.term else {
+            continue;
+        };
+        let Some((symbol, output_index)) = source_map.get(target) else {
+            continue;
+        };
+        let return_node = edge.target();
+        let state = match output_index {
+            None => TaState::new_return(symbol, pi_result, return_node),
+            Some(index) => {
+                let mut state = TaState::new_empty();
+                if let Some(buffer) =
+                    pi_result.eval_parameter_arg_at_call(&jmp.tid, &symbol.parameters[*index])
+                {
+                    state.save_taint_to_memory(&buffer, Taint::Tainted(ByteSize::new(1)));
+                }
+                state
+            }
+        };
+        if !state.is_empty() {
+            computation.set_node_value(return_node, NodeValue::Value(state));
+        }
+    }
+
+    computation.compute_with_max_steps(100);
+
+    let mut cwe_warnings = BTreeMap::new();
+    for cwe in cwe_receiver.try_iter() {
+        cwe_warnings.insert(cwe.addresses[0].clone(), cwe);
+    }
+    for address in sink_call_addresses.iter() {
+        if cwe_warnings.contains_key(address) {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    let mut framework_info = vec!["frameworks".to_string()];
+    framework_info.extend(
+        frameworks
+            .iter()
+            .map(|framework| framework.signature.name.clone()),
+    );
+    let cwe_warnings = cwe_warnings
+        .into_values()
+        .map(|cwe| cwe.other(vec![framework_info.clone()]))
+        .collect();
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Map the TIDs of the given extern functions contained in the project
+/// to the symbol and the index of their buffer parameter.
+fn get_buffer_param_map<'a>(
+    project: &'a Project,
+    functions: &BTreeMap<String, usize>,
+) -> HashMap<Tid, (&'a ExternSymbol, usize)> {
+    project
+        .program
+        .term
+        .extern_symbols
+        .iter()
+        .filter_map(|(tid, symbol)| {
+            let index = *functions.get(&symbol.name)?;
+            (index < symbol.parameters.len()).then(|| (tid.clone(), (symbol, index)))
+        })
+        .collect()
+}
+
+/// The Context struct for the taint analysis.
+pub struct Context<'a> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInferenceComputation<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// A map of symbols to use as sinks for the algorithm.
+    sink_map: HashMap<Tid, &'a ExternSymbol>,
+    /// Maps the TIDs of path building functions to the symbol
+    /// and the parameter index of the destination buffer.
+    path_builder_map: HashMap<Tid, (&'a ExternSymbol, usize)>,
+    /// The TIDs of validation functions.
+    validator_tids: BTreeSet<Tid>,
+    /// Maps the TID of an extern symbol to the extern symbol struct.
+    extern_symbol_map: HashMap<Tid, &'a ExternSymbol>,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where found CWE hits can be sent to.
+    cwe_collector: crossbeam_channel::Sender<CweWarning>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
+    /// Generate a CWE warning if taint may be contained in the arguments to a sink function,
+    /// propagate taint through path building functions
+    /// and stop the analysis if a tainted value is validated.
+    ///
+    /// After a warning was generated we return `None` to suppress
+    /// the generation of further warnings.
+    fn update_call_stub(&self, state: &TaState, call: &Term<Jmp>) -> Option<TaState> {
+        if state.is_empty() {
+            return None;
+        }
+
+        match &call.term {
+            Jmp::Call { target, .. } => {
+                let extern_symbol = self
+                    .extern_symbol_map
+                    .get(target)
+                    .expect("Extern symbol not found.");
+                if let Some(sink_symbol) = self.sink_map.get(target) {
+                    if state.check_extern_parameters_for_taint::<true>(
+                        self.vsa_result(),
+                        sink_symbol,
+                        &call.tid,
+                    ) {
+                        self.generate_cwe_warning(call, sink_symbol);
+
+                        None
+                    } else {
+                        Some(self.update_extern_symbol(state, call, sink_symbol))
+                    }
+                } else if self.validator_tids.contains(target) {
+                    if state.check_extern_parameters_for_taint::<true>(
+                        self.vsa_result(),
+                        extern_symbol,
+                        &call.tid,
+                    ) {
+                        None
+                    } else {
+                        Some(self.update_extern_symbol(state, call, extern_symbol))
+                    }
+                } else if let Some((builder_symbol, dest_index)) = self.path_builder_map.get(target)
+                {
+                    Some(self.update_path_builder(state, call, builder_symbol, *dest_index))
+                } else {
+                    Some(self.update_extern_symbol(state, call, extern_symbol))
+                }
+            }
+            Jmp::CallInd { .. } => self.update_call_generic(state, &call.tid, &None),
+            _ => panic!("Malformed control flow graph encountered."),
+        }
+    }
+}
+
+impl<'a> Context<'a> {
+    /// Transition function for calls to external functions that do not
+    /// trigger a CWE warning, i.e., its not a sink function or no taint is in
+    /// the arguments.
+    ///
+    /// Taint is propagated according to the taint summary of the function if one is known.
+    fn update_extern_symbol(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        extern_symbol: &ExternSymbol,
+    ) -> TaState {
+        let calling_conv = self.project.get_calling_convention(extern_symbol);
+        let mut new_state = state.clone();
+
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        if let Some(summary) = self
+            .taint_summaries
+            .and_then(|summaries| summaries.get(&extern_symbol.name))
+        {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.pi_result,
+                &call.tid,
+                calling_conv,
+            );
+        }
+
+        new_state
+    }
+
+    /// Transition function for calls to path building functions.
+    ///
+    /// If some argument of the call may be tainted or may point to taint,
+    /// the start of the destination buffer gets tainted.
+    fn update_path_builder(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        builder_symbol: &ExternSymbol,
+        dest_index: usize,
+    ) -> TaState {
+        let mut new_state = self.update_extern_symbol(state, call, builder_symbol);
+        if state.check_extern_parameters_for_taint::<true>(
+            self.vsa_result(),
+            builder_symbol,
+            &call.tid,
+        ) {
+            if let Some(dest) = self
+                .vsa_result()
+                .eval_parameter_arg_at_call(&call.tid, &builder_symbol.parameters[dest_index])
+            {
+                new_state.save_taint_to_memory(&dest, Taint::Tainted(ByteSize::new(1)));
+            }
+        }
+
+        new_state
+    }
+
+    fn generate_cwe_warning(&self, sink_call: &Term<Jmp>, sink_symbol: &ExternSymbol) {
+        let cwe_warning = CweWarning::new(
+            CWE_MODULE.name,
+            CWE_MODULE.version,
+            format!(
+                "(Unrestricted Upload) Unvalidated request field of an embedded web server is used by {} at {}.",
+                sink_symbol.name, sink_call.tid.address,
+            ),
+        )
+        .tids(vec![format!("{}", sink_call.tid)])
+        .addresses(vec![sink_call.tid.address.clone()])
+        .symbols(vec![sink_symbol.name.clone()]);
+        let _ = self.cwe_collector.send(cwe_warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::mock_x64_project;
+
+    #[test]
+    fn framework_recognition() {
+        let project = mock_x64_project(&["websGetVar"], Vec::new());
+        let frameworks: Vec<Framework> = serde_json::from_value(serde_json::json!([
+            {
+                "name": "GoAhead",
+                "signatures": ["websGetVar"],
+                "return_sources": ["websGetVar"],
+                "output_sources": {}
+            },
+            {
+                "name": "boa",
+                "signatures": ["add_cgi_env"],
+                "return_sources": [],
+                "output_sources": {}
+            }
+        ]))
+        .unwrap();
+
+        let recognized = symbol_utils::get_recognized_frameworks(&project, &frameworks);
+        assert_eq!(recognized.len(), 1);
+        assert_eq!(recognized[0].signature.name, "GoAhead");
+        assert_eq!(recognized[0].return_sources, vec!["websGetVar".to_string()]);
+    }
+}
@@ -0,0 +1,373 @@
+//! This module implements a check for CWE-440: Expected Behavior Violation,
+//! in the form of misaligned memory accesses on architectures with strict alignment requirements.
+//!
+//! Dereferencing a pointer that is not suitably aligned for the accessed type is undefined behavior in C.
+//! On architectures like x86 the access just works (maybe a bit slower),
+//! but architectures like SPARC or MIPS raise a bus error for misaligned loads and stores
+//! and some ARM configurations fault for certain instructions.
+//! A typical cause are pointer casts from byte buffers,
+//! e.g. reading a 32-bit integer from an odd offset inside a received network packet.
+//!
+//! See <https://cwe.mitre.org/data/definitions/440.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! The check is only executed for the architectures configured in config.json.
+//! For each load and store instruction the address is computed by the
+//! [Pointer Inference analysis](`crate::analysis::pointer_inference`).
+//! The required alignment of an access is its size (rounded down to a power of two),
+//! but at most the maximal alignment configured for the architecture.
+//! Assuming that each memory object is suitably aligned,
+//! the known bits of the offset into the object then tell us whether the access is
+//! - aligned, if the low bits of the offset are known to be zero,
+//! - misaligned, if the low bits are known and not all zero, or
+//! - possibly misaligned, if some of the low bits are unknown.
+//!
+//! Absolute addresses are checked in the same way.
+//! Possibly misaligned accesses are only reported if configured
+//! and if the offset is not completely unknown,
+//! e.g. for loops reading integers from a byte buffer with a step size of one byte.
+//!
+//! Each warning carries the severity configured for the architecture.
+//!
+//! ## False Positives
+//!
+//! - The analysis may not be able to prove that the alignment of an offset
+//!   is guaranteed by checks or computations that it does not track, e.g. explicit rounding in a loop.
+//! - Memory objects are not always as aligned as the check assumes,
+//!   which can also turn a misaligned offset into an aligned address.
+//!
+//! ## False Negatives
+//!
+//! - Accesses relative to the stack pointer are ignored,
+//!   since stack frame layouts are chosen by the compiler (and, e.g. on SPARC64, biased on purpose).
+//! - Misaligned pointers passed to other functions are not tracked back to their origin,
+//!   since the base of a parameter object is assumed to be aligned.
+//! - Accesses with completely unknown offsets are not reported.
+//!
+//! ## Configuration
+//!
+//! The checked architectures are given as prefixes of the architecture names (e.g. `sparc` or `MIPS`)
+//! together with the maximal required alignment and the severity of the warnings.
+
+use crate::abstract_domain::{
+    AbstractIdentifier, AbstractLocation, IntervalDomain, KnownBitsDomain, TryToInterval,
+};
+use crate::analysis::pointer_inference::Data;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::CweModule;
+use std::collections::BTreeMap;
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE440",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The severity of misaligned accesses on an architecture.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    /// Misaligned accesses are fixed up (slowly) by the hardware or the kernel.
+    Low,
+    /// Misaligned accesses fault for some instructions or system configurations.
+    Medium,
+    /// Misaligned accesses always fault.
+    High,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Low => write!(f, "low"),
+            Severity::Medium => write!(f, "medium"),
+            Severity::High => write!(f, "high"),
+        }
+    }
+}
+
+/// The alignment requirements of an architecture.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+struct ArchitectureConfig {
+    /// The maximal alignment (in bytes) required for any access.
+    max_alignment: u64,
+    /// The severity of warnings for the architecture.
+    severity: Severity,
+}
+
+/// The check-specific configuration.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The checked architectures, indexed by a prefix of the architecture name.
+    architectures: BTreeMap<String, ArchitectureConfig>,
+    /// Whether to report accesses whose alignment cannot be proven.
+    report_possible_misalignment: bool,
+}
+
+/// The result of checking the alignment of an offset or address.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Alignment {
+    /// The access is aligned.
+    Aligned,
+    /// The access is misaligned for all possible values.
+    Misaligned,
+    /// The access may be misaligned for some of the possible values.
+    PossiblyMisaligned,
+}
+
+/// Run the check.
+///
+/// Checks the address of every load and store instruction
+/// if the architecture of the binary is configured in config.json.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE440.");
+    // The examined sites are the memory accesses that require an alignment.
+    let mut site_counts = SiteCounts::default();
+    let Some(architecture) = get_architecture_config(&project.cpu_architecture, &config) else {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    };
+    let pointer_inference = analysis_results.pointer_inference.unwrap();
+
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for blk in &sub.term.blocks {
+            for def in &blk.term.defs {
+                let access_size = match &def.term {
+                    Def::Load { var, .. } => var.size,
+                    Def::Store { value, .. } => value.bytesize(),
+                    Def::Assign { .. } => continue,
+                };
+                let alignment = get_required_alignment(access_size, architecture.max_alignment);
+                if alignment <= 1 {
+                    continue;
+                }
+                let Some(address) = pointer_inference.eval_address_at_def(&def.tid) else {
+                    site_counts.add_skipped();
+                    continue;
+                };
+                match check_address_alignment(project, &address, alignment) {
+                    Alignment::Aligned => site_counts.add_safe(),
+                    Alignment::PossiblyMisaligned if !config.report_possible_misalignment => {
+                        site_counts.add_skipped()
+                    }
+                    result => {
+                        site_counts.add_flagged();
+                        cwe_warnings.push(generate_cwe_warning(
+                            sub,
+                            def,
+                            access_size,
+                            alignment,
+                            result,
+                            architecture.severity,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    (Vec::new(), cwe_warnings)
+}
+
+/// Return the configuration for the given architecture
+/// if the architecture name starts with one of the configured prefixes.
+fn get_architecture_config<'a>(
+    cpu_architecture: &str,
+    config: &'a Config,
+) -> Option<&'a ArchitectureConfig> {
+    let cpu_architecture = cpu_architecture.to_uppercase();
+    config
+        .architectures
+        .iter()
+        .find(|(prefix, _)| cpu_architecture.starts_with(&prefix.to_uppercase()))
+        .map(|(_, architecture)| architecture)
+}
+
+/// Return the alignment required for an access of the given size,
+/// i.e. the largest power of two not greater than the size, but at most `max_alignment`.
+fn get_required_alignment(access_size: ByteSize, max_alignment: u64) -> u64 {
+    let size = u64::from(access_size);
+    if size == 0 {
+        return 1;
+    }
+    u64::min(1 << (63 - size.leading_zeros()), max_alignment)
+}
+
+/// Check the alignment of all targets of the given address.
+///
+/// Targets relative to the stack pointer and `Top` values are ignored.
+/// If any of the other targets is misaligned or possibly misaligned, the worst result is returned.
+fn check_address_alignment(project: &Project, address: &Data, alignment: u64) -> Alignment {
+    let relative_offsets = address
+        .get_relative_values()
+        .iter()
+        .filter(|(id, _)| !is_stack_frame(project, id))
+        .map(|(_, offset)| offset);
+    relative_offsets
+        .chain(address.get_absolute_value())
+        .map(|offset| check_offset_alignment(offset, alignment))
+        .fold(Alignment::Aligned, |worst, result| match (worst, result) {
+            (Alignment::Misaligned, _) | (_, Alignment::Misaligned) => Alignment::Misaligned,
+            (Alignment::PossiblyMisaligned, _) | (_, Alignment::PossiblyMisaligned) => {
+                Alignment::PossiblyMisaligned
+            }
+            _ => Alignment::Aligned,
+        })
+}
+
+/// Returns `true` if the identifier represents the value of the stack pointer at the start of a function.
+fn is_stack_frame(project: &Project, id: &AbstractIdentifier) -> bool {
+    matches!(id.get_location(), AbstractLocation::Register(var) if *var == project.stack_pointer_register)
+}
+
+/// Check whether the low bits of the offset are known to be zero.
+///
+/// Offsets that are completely unknown are treated as aligned.
+fn check_offset_alignment(offset: &IntervalDomain, alignment: u64) -> Alignment {
+    let Ok(interval) = offset.try_to_interval() else {
+        return Alignment::Aligned;
+    };
+    let known_bits = KnownBitsDomain::from_interval(&interval);
+    let low_bits = alignment - 1;
+    let (Ok(value), Ok(unknown_bits)) = (
+        known_bits.value().try_to_u64(),
+        known_bits.unknown_bits().try_to_u64(),
+    ) else {
+        return Alignment::Aligned;
+    };
+    if unknown_bits & low_bits != 0 {
+        Alignment::PossiblyMisaligned
+    } else if value & low_bits != 0 {
+        Alignment::Misaligned
+    } else {
+        Alignment::Aligned
+    }
+}
+
+/// Generate the CWE warning for a misaligned access.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    def: &Term<Def>,
+    access_size: ByteSize,
+    alignment: u64,
+    result: Alignment,
+    severity: Severity,
+) -> CweWarning {
+    let access = match &def.term {
+        Def::Load { .. } => "Load",
+        _ => "Store",
+    };
+    let description = match result {
+        Alignment::PossiblyMisaligned => "may not be",
+        _ => "is not",
+    };
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Misaligned Memory Access) {} of {} bytes at {} ({}) {} aligned to {} bytes",
+            access,
+            u64::from(access_size),
+            def.tid.address,
+            sub.term.name,
+            description,
+            alignment
+        ),
+    )
+    .tids(vec![format!("{}", def.tid)])
+    .addresses(vec![def.tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec!["severity".to_string(), severity.to_string()]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstract_domain::Interval;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::def;
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "architectures": {
+                "sparc": {"max_alignment": 8, "severity": "high"}
+            },
+            "report_possible_misalignment": true
+        })
+    }
+
+    /// Create a project for the given architecture with a function executing the given defs.
+    fn mock_project(cpu_architecture: &str, defs: Vec<Term<Def>>) -> Project {
+        let mut blk = Blk::mock_with_tid("blk");
+        blk.term.defs = defs;
+        let mut sub = Sub::mock("parse_packet");
+        sub.term.blocks = vec![blk];
+        let mut project = mock_x64_project(&[], vec![sub]);
+        project.cpu_architecture = cpu_architecture.to_string();
+        project
+    }
+
+    #[test]
+    fn misaligned_access() {
+        let project = mock_project(
+            "sparc_64",
+            vec![
+                def!["read_length: RAX:8 := Load from RDI:8 + 0x4:8"],
+                def!["read_type: RBX:8 := Load from RDI:8 + 0x1:8"],
+                def!["read_flag: CF:1 := Load from RDI:8 + 0x1:8"],
+                def!["read_local: RCX:8 := Load from RSP:8 + 0x1:8"],
+            ],
+        );
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].tids, vec!["read_length".to_string()]);
+        assert!(warnings[0].description.contains("not aligned to 8 bytes"));
+        assert_eq!(warnings[1].tids, vec!["read_type".to_string()]);
+        assert_eq!(warnings[1].other, vec![vec!["severity", "high"]]);
+    }
+
+    #[test]
+    fn unchecked_architecture() {
+        let project = mock_project(
+            "x86_64",
+            vec![def!["read_type: RBX:8 := Load from RDI:8 + 0x1:8"]],
+        );
+        assert!(run_check(check_cwe, &project, &mock_config()).is_empty());
+    }
+
+    #[test]
+    fn offset_alignment() {
+        let interval = |start: i64, end: i64, stride: u64| {
+            IntervalDomain::from(Interval::new(
+                Bitvector::from_i64(start),
+                Bitvector::from_i64(end),
+                stride,
+            ))
+        };
+        assert_eq!(
+            check_offset_alignment(&interval(8, 32, 8), 8),
+            Alignment::Aligned
+        );
+        assert_eq!(
+            check_offset_alignment(&interval(1, 1, 0), 4),
+            Alignment::Misaligned
+        );
+        assert_eq!(
+            check_offset_alignment(&interval(0, 32, 1), 4),
+            Alignment::PossiblyMisaligned
+        );
+        assert_eq!(get_required_alignment(ByteSize::new(10), 8), 8);
+        assert_eq!(get_required_alignment(ByteSize::new(2), 8), 2);
+    }
+}
@@ -25,6 +25,7 @@ use crate::analysis::pointer_inference::State;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
 use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
 use crate::CweModule;
 use std::collections::BTreeSet;
@@ -113,12 +114,17 @@ pub fn check_cwe(
     let mut cwe_warnings = Vec::new();
 
     let symbol_map = get_symbol_map(project, &config.symbols);
+    let mut site_counts = SiteCounts::default();
     for sub in project.program.term.subs.values() {
         for (block, jmp, symbol) in get_callsites(sub, &symbol_map) {
             if check_for_pointer_sized_arg(project, block, symbol) {
+                site_counts.add_flagged();
                 cwe_warnings.push(generate_cwe_warning(jmp, symbol))
+            } else {
+                site_counts.add_safe();
             }
         }
     }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
     (Vec::new(), cwe_warnings)
 }
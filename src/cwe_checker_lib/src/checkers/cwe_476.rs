@@ -46,6 +46,7 @@ use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::{
     log::{CweWarning, LogMessage},
+    metrics::SiteCounts,
     symbol_utils,
 };
 use crate::CweModule;
@@ -91,6 +92,7 @@ pub fn check_cwe(
     let symbol_map = symbol_utils::get_symbol_map(project, &config.symbols[..]);
     let general_context = Context::new(project, pi_result, cwe_sender);
 
+    let mut examined_calls = 0;
     for edge in general_context.get_graph().edge_references() {
         let Edge::ExternCallStub(jmp) = edge.weight() else {
             continue;
@@ -106,10 +108,12 @@ pub fn check_cwe(
             panic!("Malformed control flow graph.");
         };
 
+        examined_calls += 1;
         let mut context = general_context.clone();
         context.set_taint_source(jmp, current_sub);
 
         let mut computation = create_computation(context, None);
+        computation.set_cancellation_token(analysis_results.cancellation_token);
         computation.set_node_value(
             return_node,
             NodeValue::Value(TaState::new_return(symbol, pi_result, return_node)),
@@ -124,7 +128,18 @@ pub fn check_cwe(
             _ => panic!(),
         };
     }
-    let cwe_warnings = cwe_warnings.into_values().collect();
+    let cwe_warnings: Vec<CweWarning> = cwe_warnings.into_values().collect();
+    // There is at most one warning per examined call.
+    // Calls without a warning are considered safe, since their return values were checked before use.
+    let mut site_counts = SiteCounts::default();
+    for call_index in 0..examined_calls {
+        if call_index < cwe_warnings.len() {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (Vec::new(), cwe_warnings)
 }
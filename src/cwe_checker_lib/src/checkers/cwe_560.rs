@@ -27,6 +27,7 @@ use crate::analysis::pointer_inference::State;
 use crate::intermediate_representation::*;
 use crate::prelude::*;
 use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
 use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
 use crate::CweModule;
 use std::collections::BTreeSet;
@@ -111,16 +112,21 @@ pub fn check_cwe(
     let mut cwes = Vec::new();
     let mut log_messages = Vec::new();
     let umask_symbol_map = get_symbol_map(project, &["umask".to_string()]);
+    let mut site_counts = SiteCounts::default();
     if !umask_symbol_map.is_empty() {
         for sub in project.program.term.subs.values() {
             for (block, jmp, umask_symbol) in get_callsites(sub, &umask_symbol_map) {
                 match get_umask_permission_arg(block, umask_symbol, project) {
                     Ok(permission_const) => {
                         if is_chmod_style_arg(permission_const) {
+                            site_counts.add_flagged();
                             cwes.push(generate_cwe_warning(sub, jmp, permission_const));
+                        } else {
+                            site_counts.add_safe();
                         }
                     }
                     Err(err) => {
+                        site_counts.add_skipped();
                         let log = LogMessage::new_info(format!(
                             "Could not determine umask argument: {err}"
                         ))
@@ -132,6 +138,7 @@ pub fn check_cwe(
             }
         }
     }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (log_messages, cwes)
 }
@@ -0,0 +1,440 @@
+//! This module implements a check for CWE-613: Insufficient Session Expiration.
+//!
+//! Embedded web servers often implement their own session handling on top of a minimal web server framework:
+//! After a successful login a session token is sent to the client in a cookie
+//! and later requests are authenticated by comparing the cookie against the stored token.
+//! If the validation never checks how old the session is,
+//! a stolen or leaked session token stays valid until the device reboots.
+//!
+//! See <https://cwe.mitre.org/data/definitions/613.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! First we recognize embedded web server frameworks like GoAhead, boa or mongoose
+//! by the presence of characteristic symbols in the binary (see also the check for CWE-434).
+//! The check only runs if at least one framework was recognized.
+//!
+//! Session-token generation sites are calls to response writing functions of the recognized frameworks
+//! (e.g. `websWrite` or `sprintf`) with a constant string argument containing one of the configured session markers,
+//! e.g. a `Set-Cookie` header.
+//! Session validation sites are calls to request field getters (e.g. `websGetVar` or `getenv`)
+//! with a constant string argument containing a session marker, e.g. `HTTP_COOKIE`.
+//! The validation path of a validation site consists of the function containing it
+//! and all functions (transitively) called by this function.
+//!
+//! If no validation path contains a call to a time source like `time` or `clock_gettime`,
+//! then the sessions apparently never expire and a warning is generated for each generation site.
+//! The warnings are informational, since the absence of time handling is only a heuristic indicator.
+//!
+//! ## False Positives
+//!
+//! - Sessions may be expired by other means, e.g. by a timer thread or by limiting the number of sessions.
+//! - Time sources that are not configured (e.g. reading the uptime from `/proc/uptime`) are not recognized.
+//!
+//! ## False Negatives
+//!
+//! - A time source call anywhere on a validation path is enough to suppress the warnings,
+//!   even if its result is not compared with the creation time of the session.
+//! - Session markers that are assembled at runtime are not recognized.
+//! - Frameworks that are statically linked and stripped are not recognized.
+//!
+//! ## Configuration
+//!
+//! - `frameworks`: The recognized web server frameworks. For each framework:
+//!   - `signatures`: Symbols whose presence in the binary indicate the framework.
+//!   - `session_generators`: Functions that may write a session token into a response.
+//!   - `session_validators`: Functions that may read a session token from a request.
+//! - `session_markers`: Strings indicating session tokens in the arguments of the functions above (case-insensitive).
+//! - `time_sources`: Functions returning the current time.
+
+use crate::abstract_domain::TryToBitvec;
+use crate::analysis::callgraph::{get_program_callgraph, CallGraph};
+use crate::analysis::pointer_inference::PointerInference;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::{
+    get_callsites, get_recognized_frameworks, get_symbol_map, FrameworkSignature,
+};
+use crate::CweModule;
+use petgraph::visit::Dfs;
+use std::collections::{BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE613",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The number of parameters of a call that are searched for session markers.
+const SEARCHED_PARAMETERS: usize = 4;
+
+/// The session handling functions of an embedded web server framework.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Framework {
+    /// The name of the framework and the symbols indicating it.
+    #[serde(flatten)]
+    signature: FrameworkSignature,
+    /// Functions that may write a session token into a response.
+    session_generators: Vec<String>,
+    /// Functions that may read a session token from a request.
+    session_validators: Vec<String>,
+}
+
+impl AsRef<FrameworkSignature> for Framework {
+    fn as_ref(&self) -> &FrameworkSignature {
+        &self.signature
+    }
+}
+
+/// The check-specific configuration.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The recognized web server frameworks.
+    frameworks: Vec<Framework>,
+    /// Strings indicating session tokens (case-insensitive).
+    session_markers: Vec<String>,
+    /// Functions returning the current time.
+    time_sources: Vec<String>,
+}
+
+/// A call generating or validating a session token.
+struct SessionSite<'a> {
+    /// The function containing the call.
+    sub: &'a Term<Sub>,
+    /// The call instruction.
+    call_tid: &'a Tid,
+    /// The name of the called function.
+    symbol: &'a str,
+    /// The string argument containing a session marker.
+    marker_string: String,
+}
+
+/// Run the check.
+///
+/// Generates a warning for each session-token generation site
+/// if none of the session validation paths handles time.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE613.");
+    let frameworks = get_recognized_frameworks(project, &config.frameworks);
+    // The examined sites are the session generation sites.
+    let mut site_counts = SiteCounts::default();
+    if frameworks.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let pointer_inference = analysis_results.pointer_inference.unwrap();
+    let markers: Vec<String> = config
+        .session_markers
+        .iter()
+        .map(|marker| marker.to_lowercase())
+        .collect();
+
+    let generators: Vec<String> = frameworks
+        .iter()
+        .flat_map(|framework| framework.session_generators.iter().cloned())
+        .collect();
+    let generation_sites = find_session_sites(
+        project,
+        pointer_inference,
+        &get_symbol_map(project, &generators),
+        &markers,
+    );
+    if generation_sites.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let validators: Vec<String> = frameworks
+        .iter()
+        .flat_map(|framework| framework.session_validators.iter().cloned())
+        .collect();
+    let validation_sites = find_session_sites(
+        project,
+        pointer_inference,
+        &get_symbol_map(project, &validators),
+        &markers,
+    );
+
+    let time_source_map = get_symbol_map(project, &config.time_sources);
+    let subs_calling_time_sources: BTreeSet<&Tid> = project
+        .program
+        .term
+        .subs
+        .values()
+        .filter(|sub| !get_callsites(sub, &time_source_map).is_empty())
+        .map(|sub| &sub.tid)
+        .collect();
+    let callgraph = get_program_callgraph(&project.program);
+    if validation_sites.iter().any(|site| {
+        validation_path_handles_time(&callgraph, &site.sub.tid, &subs_calling_time_sources)
+    }) {
+        for _ in generation_sites.iter() {
+            site_counts.add_safe();
+        }
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+
+    let framework_info: Vec<String> = std::iter::once("frameworks".to_string())
+        .chain(
+            frameworks
+                .iter()
+                .map(|framework| framework.signature.name.clone()),
+        )
+        .collect();
+    let cwe_warnings = generation_sites
+        .iter()
+        .map(|site| {
+            site_counts.add_flagged();
+            generate_cwe_warning(site, &validation_sites).other(vec![
+                vec!["severity".to_string(), "info".to_string()],
+                framework_info.clone(),
+            ])
+        })
+        .collect();
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    (Vec::new(), cwe_warnings)
+}
+
+/// Find all calls to the given functions where one of the first parameters
+/// is a constant string containing a session marker.
+fn find_session_sites<'a>(
+    project: &'a Project,
+    pointer_inference: &PointerInference,
+    symbol_map: &HashMap<Tid, &'a ExternSymbol>,
+    markers: &[String],
+) -> Vec<SessionSite<'a>> {
+    let mut sites = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, symbol) in get_callsites(sub, symbol_map) {
+            let marker_string = (0..SEARCHED_PARAMETERS).find_map(|index| {
+                let string = get_constant_string_parameter(
+                    project,
+                    pointer_inference,
+                    &jmp.tid,
+                    symbol,
+                    index,
+                )?;
+                let lowercase_string = string.to_lowercase();
+                markers
+                    .iter()
+                    .any(|marker| lowercase_string.contains(marker))
+                    .then_some(string)
+            });
+            if let Some(marker_string) = marker_string {
+                sites.push(SessionSite {
+                    sub,
+                    call_tid: &jmp.tid,
+                    symbol: &symbol.name,
+                    marker_string,
+                });
+            }
+        }
+    }
+    sites
+}
+
+/// Get the constant string that the parameter with the given index points to at the call.
+///
+/// Response writing functions like `websWrite` are variadic, so the parameter list of the symbol may be incomplete.
+/// In this case we fall back to the integer parameter registers of the calling convention.
+fn get_constant_string_parameter(
+    project: &Project,
+    pointer_inference: &PointerInference,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    index: usize,
+) -> Option<String> {
+    let arg = match symbol.parameters.get(index) {
+        Some(arg) => arg.clone(),
+        None => project
+            .get_calling_convention(symbol)
+            .integer_parameter_register
+            .get(index)
+            .map(|register| Arg::from_var(register.clone(), None))?,
+    };
+    let address = pointer_inference
+        .eval_parameter_arg_at_call(call_tid, &arg)?
+        .get_if_absolute_value()?
+        .try_to_bitvec()
+        .ok()?;
+    project
+        .runtime_memory_image
+        .read_string_until_null_terminator(&address)
+        .ok()
+        .map(|string| string.to_string())
+}
+
+/// Check whether the given function or one of the functions it (transitively) calls
+/// calls a time source.
+fn validation_path_handles_time(
+    callgraph: &CallGraph,
+    sub_tid: &Tid,
+    subs_calling_time_sources: &BTreeSet<&Tid>,
+) -> bool {
+    let Some(start_node) = callgraph
+        .node_indices()
+        .find(|node| callgraph[*node] == *sub_tid)
+    else {
+        return false;
+    };
+    let mut dfs = Dfs::new(callgraph, start_node);
+    while let Some(node) = dfs.next(callgraph) {
+        if subs_calling_time_sources.contains(&callgraph[node]) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Generate the CWE warning for a session-token generation site.
+fn generate_cwe_warning(site: &SessionSite, validation_sites: &[SessionSite]) -> CweWarning {
+    let validation_info = if validation_sites.is_empty() {
+        "no session validation was found".to_string()
+    } else {
+        let validating_functions: BTreeSet<&str> = validation_sites
+            .iter()
+            .map(|site| site.sub.term.name.as_str())
+            .collect();
+        format!(
+            "the session validation in {} never checks the time",
+            validating_functions
+                .into_iter()
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Insufficient Session Expiration) Session token written by {} at {} ({}) with \"{}\" may never expire: {}",
+            site.symbol,
+            site.call_tid.address,
+            site.sub.term.name,
+            site.marker_string,
+            validation_info
+        ),
+    )
+    .tids(
+        std::iter::once(site.call_tid)
+            .chain(validation_sites.iter().map(|site| site.call_tid))
+            .map(|tid| format!("{tid}"))
+            .collect(),
+    )
+    .addresses(vec![site.call_tid.address.clone()])
+    .symbols(vec![site.sub.term.name.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::def;
+
+    fn mock_config(signature: &str) -> serde_json::Value {
+        serde_json::json!({
+            "frameworks": [{
+                "name": "GoAhead",
+                "signatures": [signature],
+                "session_generators": ["websWrite"],
+                "session_validators": ["websGetVar"]
+            }],
+            "session_markers": ["cookie"],
+            "time_sources": ["time"]
+        })
+    }
+
+    /// Create a project with a login handler sending a session cookie
+    /// and a session check reading the cookie from the request.
+    /// If `check_expiry` is set, the session check calls a function calling `time`.
+    fn mock_project(check_expiry: bool) -> Project {
+        let mut blk_login = Blk::mock_with_tid("blk_login");
+        blk_login.term.defs = vec![def!["cookie_header: RSI:8 = 0x3000:8"]];
+        blk_login
+            .term
+            .jmps
+            .push(Jmp::call("call_write", "websWrite", Some("blk_login_end")));
+        let mut login = Sub::mock("login");
+        login.term.blocks = vec![blk_login, Blk::mock_with_tid("blk_login_end")];
+
+        let mut blk_check = Blk::mock_with_tid("blk_check");
+        blk_check.term.defs = vec![def!["cookie_name: RSI:8 = 0x3013:8"]];
+        blk_check
+            .term
+            .jmps
+            .push(Jmp::call("call_get_var", "websGetVar", Some("blk_expiry")));
+        let mut blk_expiry = Blk::mock_with_tid("blk_expiry");
+        if check_expiry {
+            blk_expiry.term.jmps.push(Jmp::call(
+                "call_expiry",
+                "is_expired",
+                Some("blk_check_end"),
+            ));
+        }
+        let mut check_session = Sub::mock("check_session");
+        check_session.term.blocks =
+            vec![blk_check, blk_expiry, Blk::mock_with_tid("blk_check_end")];
+
+        let mut blk_time = Blk::mock_with_tid("blk_time");
+        blk_time
+            .term
+            .jmps
+            .push(Jmp::call("call_time", "time", Some("blk_time_end")));
+        let mut is_expired = Sub::mock("is_expired");
+        is_expired.term.blocks = vec![blk_time, Blk::mock_with_tid("blk_time_end")];
+
+        let mut project = mock_x64_project(
+            &["websWrite", "websGetVar", "time"],
+            vec![login, check_session, is_expired],
+        );
+        for name in ["websWrite", "websGetVar", "time"] {
+            let symbol = project
+                .program
+                .term
+                .extern_symbols
+                .get_mut(&Tid::new(name))
+                .unwrap();
+            symbol.parameters = vec![Arg::mock_register("RDI", 8), Arg::mock_register("RSI", 8)];
+        }
+        project.runtime_memory_image.memory_segments = vec![MemorySegment {
+            bytes: b"Set-Cookie: sid=%s\x00HTTP_COOKIE\x00".to_vec(),
+            base_address: 0x3000,
+            read_flag: true,
+            write_flag: false,
+            execute_flag: false,
+        }];
+        project
+    }
+
+    #[test]
+    fn session_without_expiry() {
+        let warnings = run_check(check_cwe, &mock_project(false), &mock_config("websGetVar"));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec!["call_write".to_string(), "call_get_var".to_string()]
+        );
+        assert!(warnings[0].description.contains("Set-Cookie: sid=%s"));
+        assert!(warnings[0].description.contains("check_session"));
+        assert_eq!(warnings[0].other[0], vec!["severity", "info"]);
+    }
+
+    #[test]
+    fn session_with_expiry() {
+        assert!(run_check(check_cwe, &mock_project(true), &mock_config("websGetVar")).is_empty());
+    }
+
+    #[test]
+    fn unknown_framework() {
+        assert!(run_check(check_cwe, &mock_project(false), &mock_config("mg_start")).is_empty());
+    }
+}
@@ -0,0 +1,886 @@
+//! This module implements a check for CWE-649: Reliance on Obfuscation or Encryption of Security-Relevant Inputs without Integrity Checking.
+//!
+//! Web interfaces and network services of embedded devices often hand out session tokens or cookies
+//! that are merely encoded (e.g. with base64 or with a fixed XOR key)
+//! instead of being protected by a message authentication code or a signature.
+//! If the server decodes such a token received from a client
+//! and bases an authentication or authorization decision on its content,
+//! an attacker can forge tokens by encoding arbitrary content.
+//!
+//! See <https://cwe.mitre.org/data/definitions/649.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [Pointer Inference analysis](`crate::analysis::pointer_inference`)
+//! we find all functions that receive data into a buffer through an input function (e.g. `recv`)
+//! and decode the received data. Decoding is recognized
+//! - by calls to decoding functions (e.g. `EVP_DecodeBlock`) with a pointer into the received buffer,
+//! - by calls to internal functions whose name contains a keyword like `decode`
+//!   with a pointer into the received buffer,
+//! - by the idiom of an XOR decoding loop, i.e. a loop loading values from the received buffer,
+//!   combining them with a key through XOR and storing the results.
+//!
+//! Starting after the decoding we follow the control flow inside the function
+//! to the first branch depending on the decoded data,
+//! i.e. on a value loaded from the decoded buffer
+//! or on the return value of a comparison function (e.g. `strcmp`) called with a pointer into the decoded buffer.
+//! Paths are not followed through calls to verification functions.
+//! These are the signature verification functions also known to the check for CWE-347,
+//! functions computing or verifying message authentication codes
+//! and internal functions whose name contains a keyword like `verify`.
+//! A branch on the decoded data reachable without passing a verification function is reported.
+//!
+//! ## False Positives
+//!
+//! - Not every branch on decoded data is a security decision,
+//!   e.g. parsing the decoded data also branches on its content.
+//! - The integrity of the data may be verified elsewhere, e.g. by comparing the whole token with a stored session token.
+//! - The size of the decoded data is estimated from the size of the received buffer,
+//!   so branches on variables next to the decoded buffer may be attributed to the decoded data.
+//!
+//! ## False Negatives
+//!
+//! - Only data received and decoded in the same function is considered.
+//!   Tokens read from environment variables (e.g. `HTTP_COOKIE` in CGI programs) are not recognized.
+//! - Only decisions inside the decoding function are found.
+//! - Values loaded from the decoded buffer are only tracked inside the basic block of the load.
+//! - Custom decoding routines are only recognized by their name or by an XOR decoding loop inside the function.
+//! - Any call to a verification function counts as verification,
+//!   even if it verifies other data or its result is ignored.
+//!
+//! ## Configuration
+//!
+//! The input functions together with the indices of their buffer and length parameters,
+//! the decoding functions together with the indices of their input and output parameters,
+//! the comparison functions, the verification functions
+//! and keywords identifying internal decoding and verification functions by their name
+//! are configurable in config.json.
+
+use crate::analysis::pointer_inference::{Data, PointerInference};
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::buffer_utils::{get_input_buffers, points_into, Buffer, BufferParameters};
+use crate::utils::graph_utils::get_successor_block;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::{get_subs_by_keywords, get_symbol_map};
+use crate::CweModule;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE649",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The parameters of a decoding function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+struct DecodeParameters {
+    /// The index of the parameter containing the encoded data.
+    input: usize,
+    /// The index of the parameter containing the buffer the decoded data is written to.
+    output: usize,
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions receiving data from clients, mapped to their buffer parameters.
+    input_symbols: BTreeMap<String, BufferParameters>,
+    /// Functions decoding data, mapped to their input and output parameters.
+    decode_symbols: BTreeMap<String, DecodeParameters>,
+    /// Internal functions whose name contains one of these keywords (case-insensitive)
+    /// are considered to be decoding functions.
+    decode_function_keywords: Vec<String>,
+    /// Functions comparing the memory that their parameters point to.
+    comparison_symbols: Vec<String>,
+    /// Functions verifying signatures or computing or verifying message authentication codes.
+    verification_symbols: Vec<String>,
+    /// Internal functions whose name contains one of these keywords (case-insensitive)
+    /// are considered to be verification functions.
+    verification_function_keywords: Vec<String>,
+}
+
+/// A buffer that client data was received into.
+#[derive(Debug, Clone)]
+struct ReceivedBuffer<'a> {
+    /// The buffer.
+    buffer: Buffer,
+    /// The call to the input function receiving the data.
+    input_call: &'a Tid,
+    /// The input function.
+    input_symbol: &'a ExternSymbol,
+}
+
+/// The decoding of received data inside a function.
+#[derive(Debug, Clone)]
+struct DecodeSite<'a> {
+    /// The buffer containing the encoded data.
+    received: ReceivedBuffer<'a>,
+    /// The call to the decoding function or the store instruction of the decoding loop.
+    location: Tid,
+    /// The name of the decoding function or a description of the decoding idiom.
+    decoder: String,
+    /// The block containing the decoding.
+    block: &'a Tid,
+    /// The buffers containing the decoded data.
+    decoded: Vec<Buffer>,
+}
+
+/// Returns `true` if the expression combines a value depending on one of the given variables
+/// with a different value through XOR.
+fn contains_xor_with_key(expression: &Expression, vars: &HashSet<&Variable>) -> bool {
+    match expression {
+        Expression::BinOp {
+            op: BinOpType::IntXOr,
+            lhs,
+            rhs,
+        } if lhs != rhs => [lhs, rhs].iter().any(|operand| {
+            operand
+                .input_vars()
+                .iter()
+                .any(|input| vars.contains(input))
+        }),
+        Expression::BinOp { lhs, rhs, .. } => {
+            contains_xor_with_key(lhs, vars) || contains_xor_with_key(rhs, vars)
+        }
+        Expression::UnOp { arg, .. }
+        | Expression::Cast { arg, .. }
+        | Expression::Subpiece { arg, .. } => contains_xor_with_key(arg, vars),
+        Expression::Var(_) | Expression::Const(_) | Expression::Unknown { .. } => false,
+    }
+}
+
+/// The functions relevant for the check.
+struct FunctionTables<'a> {
+    /// The input functions.
+    input_symbols: HashMap<Tid, &'a ExternSymbol>,
+    /// The decoding functions.
+    decode_symbols: HashMap<Tid, &'a ExternSymbol>,
+    /// The internal decoding functions.
+    decode_subs: HashSet<Tid>,
+    /// The comparison functions.
+    comparison_symbols: HashMap<Tid, &'a ExternSymbol>,
+    /// The verification functions.
+    verification_symbols: HashMap<Tid, &'a ExternSymbol>,
+    /// The internal verification functions.
+    verification_subs: HashSet<Tid>,
+    /// The parameter registers of the standard calling convention,
+    /// which are used as parameters of internal functions.
+    standard_parameters: Vec<Arg>,
+}
+
+/// The control flow and the data received inside a function.
+struct FunctionContext<'a> {
+    /// The project.
+    project: &'a Project,
+    /// The results of the Pointer Inference analysis.
+    pi_result: &'a PointerInference<'a>,
+    /// The configuration of the check.
+    config: &'a Config,
+    /// The relevant functions.
+    tables: &'a FunctionTables<'a>,
+    /// The function.
+    sub: &'a Term<Sub>,
+    /// The blocks of the function.
+    blocks: HashMap<&'a Tid, &'a Term<Blk>>,
+}
+
+impl<'a> FunctionContext<'a> {
+    /// Get the buffers that client data is received into by calls to input functions.
+    fn get_received_buffers(&self) -> Vec<ReceivedBuffer<'a>> {
+        let mut received = Vec::new();
+        for jmp in self
+            .sub
+            .term
+            .blocks
+            .iter()
+            .flat_map(|blk| blk.term.jmps.iter())
+        {
+            let Jmp::Call { target, .. } = &jmp.term else {
+                continue;
+            };
+            let Some(&symbol) = self.tables.input_symbols.get(target) else {
+                continue;
+            };
+            let parameters = self.config.input_symbols[&symbol.name];
+            received.extend(
+                get_input_buffers(self.project, self.pi_result, &jmp.tid, symbol, parameters)
+                    .into_iter()
+                    .map(|buffer| ReceivedBuffer {
+                        buffer,
+                        input_call: &jmp.tid,
+                        input_symbol: symbol,
+                    }),
+            );
+        }
+        received
+    }
+
+    /// Evaluate the parameter with the given index of the extern function at the given call.
+    fn eval_parameter(&self, call_tid: &Tid, symbol: &ExternSymbol, index: usize) -> Option<Data> {
+        self.project
+            .get_parameter_arg(symbol, index)
+            .and_then(|arg| self.pi_result.eval_parameter_arg_at_call(call_tid, &arg))
+    }
+
+    /// Return the received buffer that the given value may point into.
+    fn find_received_buffer<'b>(
+        &self,
+        received: &'b [ReceivedBuffer<'a>],
+        value: &Data,
+    ) -> Option<&'b ReceivedBuffer<'a>> {
+        received
+            .iter()
+            .find(|received| received.buffer.contains_pointer(value))
+    }
+
+    /// Get the decodings of received data by calls to decoding functions.
+    ///
+    /// For internal decoding functions all pointer parameters not pointing into the received buffer
+    /// are considered to be output buffers.
+    /// If there are no such parameters, the data is assumed to be decoded in place.
+    fn get_decoding_calls(&self, received: &[ReceivedBuffer<'a>]) -> Vec<DecodeSite<'a>> {
+        let mut decode_sites = Vec::new();
+        for blk in self.sub.term.blocks.iter() {
+            for jmp in blk.term.jmps.iter() {
+                let Jmp::Call { target, .. } = &jmp.term else {
+                    continue;
+                };
+                if let Some(symbol) = self.tables.decode_symbols.get(target) {
+                    let parameters = self.config.decode_symbols[&symbol.name];
+                    let Some(input) = self.eval_parameter(&jmp.tid, symbol, parameters.input)
+                    else {
+                        continue;
+                    };
+                    let Some(source) = self.find_received_buffer(received, &input) else {
+                        continue;
+                    };
+                    let Some(output) = self.eval_parameter(&jmp.tid, symbol, parameters.output)
+                    else {
+                        continue;
+                    };
+                    decode_sites.push(DecodeSite {
+                        received: source.clone(),
+                        location: jmp.tid.clone(),
+                        decoder: symbol.name.clone(),
+                        block: &blk.tid,
+                        decoded: Buffer::from_pointer(&output, source.buffer.length()),
+                    });
+                } else if self.tables.decode_subs.contains(target) {
+                    let arguments: Vec<Data> = self
+                        .tables
+                        .standard_parameters
+                        .iter()
+                        .filter_map(|param| {
+                            self.pi_result.eval_parameter_arg_at_call(&jmp.tid, param)
+                        })
+                        .collect();
+                    let Some(source) = arguments
+                        .iter()
+                        .find_map(|argument| self.find_received_buffer(received, argument))
+                    else {
+                        continue;
+                    };
+                    let mut decoded: Vec<Buffer> = arguments
+                        .iter()
+                        .filter(|argument| self.find_received_buffer(received, argument).is_none())
+                        .flat_map(|argument| Buffer::from_pointer(argument, source.buffer.length()))
+                        .collect();
+                    if decoded.is_empty() {
+                        decoded.push(source.buffer.clone());
+                    }
+                    decode_sites.push(DecodeSite {
+                        received: source.clone(),
+                        location: jmp.tid.clone(),
+                        decoder: self.project.program.term.subs[target].term.name.clone(),
+                        block: &blk.tid,
+                        decoded,
+                    });
+                }
+            }
+        }
+        decode_sites
+    }
+
+    /// Returns `true` if the block is part of a loop inside the function.
+    fn is_in_loop(&self, blk: &Term<Blk>) -> bool {
+        let mut visited: HashSet<&Tid> = HashSet::new();
+        let mut worklist: Vec<&Tid> = blk
+            .term
+            .jmps
+            .iter()
+            .filter_map(get_successor_block)
+            .collect();
+        while let Some(blk_tid) = worklist.pop() {
+            if blk_tid == &blk.tid {
+                return true;
+            }
+            if !visited.insert(blk_tid) {
+                continue;
+            }
+            if let Some(successor) = self.blocks.get(blk_tid) {
+                worklist.extend(successor.term.jmps.iter().filter_map(get_successor_block));
+            }
+        }
+        false
+    }
+
+    /// Get the decodings of received data by XOR decoding loops.
+    ///
+    /// A decoding loop is a block inside a loop that loads a value from a received buffer,
+    /// combines it with a key through XOR and stores the result.
+    /// Values are tracked through assignments inside the block.
+    /// If the result is stored into a received buffer, the data is decoded in place.
+    fn get_decoding_loops(&self, received: &[ReceivedBuffer<'a>]) -> Vec<DecodeSite<'a>> {
+        let mut decode_sites = Vec::new();
+        for blk in self.sub.term.blocks.iter() {
+            // Variables containing values loaded from a received buffer or decoded values,
+            // mapped to the index of the received buffer.
+            let mut loaded_vars: HashMap<&Variable, usize> = HashMap::new();
+            let mut decoded_vars: HashMap<&Variable, usize> = HashMap::new();
+            for def in blk.term.defs.iter() {
+                match &def.term {
+                    Def::Load { var, .. } => {
+                        decoded_vars.remove(var);
+                        let source =
+                            self.pi_result
+                                .eval_address_at_def(&def.tid)
+                                .and_then(|address| {
+                                    received.iter().position(|received| {
+                                        received.buffer.contains_pointer(&address)
+                                    })
+                                });
+                        match source {
+                            Some(index) => loaded_vars.insert(var, index),
+                            None => loaded_vars.remove(var),
+                        };
+                    }
+                    Def::Assign { var, value } => {
+                        let find_source = |vars: &HashMap<&Variable, usize>| {
+                            value
+                                .input_vars()
+                                .iter()
+                                .find_map(|input| vars.get(input).copied())
+                        };
+                        let loaded_set: HashSet<&Variable> = loaded_vars.keys().copied().collect();
+                        let decoded_source = if contains_xor_with_key(value, &loaded_set) {
+                            find_source(&loaded_vars)
+                        } else {
+                            find_source(&decoded_vars)
+                        };
+                        let loaded_source = find_source(&loaded_vars);
+                        loaded_vars.remove(var);
+                        decoded_vars.remove(var);
+                        if let Some(index) = decoded_source {
+                            decoded_vars.insert(var, index);
+                        } else if let Some(index) = loaded_source {
+                            loaded_vars.insert(var, index);
+                        }
+                    }
+                    Def::Store { value, .. } => {
+                        let Some(index) = value
+                            .input_vars()
+                            .iter()
+                            .find_map(|input| decoded_vars.get(input).copied())
+                        else {
+                            continue;
+                        };
+                        let Some(address) = self.pi_result.eval_address_at_def(&def.tid) else {
+                            continue;
+                        };
+                        if !self.is_in_loop(blk) {
+                            break;
+                        }
+                        let source = &received[index];
+                        let decoded = match self.find_received_buffer(received, &address) {
+                            Some(target) => vec![target.buffer.clone()],
+                            None => Buffer::from_pointer(&address, source.buffer.length()),
+                        };
+                        decode_sites.push(DecodeSite {
+                            received: source.clone(),
+                            location: def.tid.clone(),
+                            decoder: "an XOR decoding loop".to_string(),
+                            block: &blk.tid,
+                            decoded,
+                        });
+                        break;
+                    }
+                }
+            }
+        }
+        decode_sites
+    }
+
+    /// Returns `true` if the call passes a pointer into the decoded buffers to a comparison function.
+    fn compares_decoded_data(&self, call: &Term<Jmp>, decoded: &[Buffer]) -> bool {
+        let Jmp::Call { target, .. } = &call.term else {
+            return false;
+        };
+        let Some(symbol) = self.tables.comparison_symbols.get(target) else {
+            return false;
+        };
+        symbol.parameters.iter().any(|param| {
+            self.pi_result
+                .eval_parameter_arg_at_call(&call.tid, param)
+                .is_some_and(|value| points_into(decoded, &value))
+        })
+    }
+
+    /// Returns `true` if the call is a call to a verification function.
+    fn is_verification_call(&self, call: &Term<Jmp>) -> bool {
+        let Jmp::Call { target, .. } = &call.term else {
+            return false;
+        };
+        self.tables.verification_symbols.contains_key(target)
+            || self.tables.verification_subs.contains(target)
+    }
+
+    /// Return the first jump of the block that branches on decoded data.
+    ///
+    /// Values loaded from the decoded buffers are tracked through assignments inside the block.
+    /// The given variables are considered to contain decoded data at the start of the block.
+    fn find_branch_on_decoded_data(
+        &self,
+        blk: &'a Term<Blk>,
+        decoded: &[Buffer],
+        mut decoded_vars: HashSet<&'a Variable>,
+    ) -> Option<&'a Tid> {
+        for def in blk.term.defs.iter() {
+            match &def.term {
+                Def::Load { var, .. } => {
+                    let loads_decoded_data = self
+                        .pi_result
+                        .eval_address_at_def(&def.tid)
+                        .is_some_and(|address| points_into(decoded, &address));
+                    if loads_decoded_data {
+                        decoded_vars.insert(var);
+                    } else {
+                        decoded_vars.remove(var);
+                    }
+                }
+                Def::Assign { var, value } => {
+                    if value
+                        .input_vars()
+                        .iter()
+                        .any(|input| decoded_vars.contains(input))
+                    {
+                        decoded_vars.insert(var);
+                    } else {
+                        decoded_vars.remove(var);
+                    }
+                }
+                Def::Store { .. } => (),
+            }
+        }
+        blk.term.jmps.iter().find_map(|jmp| {
+            let (Jmp::CBranch {
+                condition: expression,
+                ..
+            }
+            | Jmp::BranchInd(expression)) = &jmp.term
+            else {
+                return None;
+            };
+            expression
+                .input_vars()
+                .iter()
+                .any(|input| decoded_vars.contains(input))
+                .then_some(&jmp.tid)
+        })
+    }
+
+    /// Return the first branch on decoded data reachable after the decoding
+    /// without passing a call to a verification function.
+    ///
+    /// Branches inside the block containing the decoding are ignored,
+    /// since a decoding loop branches on the decoded data itself.
+    fn find_unverified_decision(&self, decode_site: &DecodeSite<'a>) -> Option<&'a Tid> {
+        let return_registers: HashSet<&Variable> = self
+            .project
+            .get_standard_calling_convention()
+            .map(|cconv| cconv.integer_return_register.iter().collect())
+            .unwrap_or_default();
+        let start = self.blocks.get(decode_site.block)?;
+        // The worklist contains blocks together with whether the block is reached
+        // through a comparison of decoded data.
+        let mut visited: HashSet<(&Tid, bool)> = HashSet::new();
+        let mut worklist: VecDeque<(&Tid, bool)> = start
+            .term
+            .jmps
+            .iter()
+            .filter_map(get_successor_block)
+            .map(|blk_tid| (blk_tid, false))
+            .collect();
+        while let Some((blk_tid, after_comparison)) = worklist.pop_front() {
+            if !visited.insert((blk_tid, after_comparison)) {
+                continue;
+            }
+            let Some(&blk) = self.blocks.get(blk_tid) else {
+                continue;
+            };
+            if blk.tid != *decode_site.block {
+                let decoded_vars = if after_comparison {
+                    return_registers.clone()
+                } else {
+                    HashSet::new()
+                };
+                if let Some(decision) =
+                    self.find_branch_on_decoded_data(blk, &decode_site.decoded, decoded_vars)
+                {
+                    return Some(decision);
+                }
+            }
+            for jmp in blk.term.jmps.iter() {
+                if self.is_verification_call(jmp) {
+                    continue;
+                }
+                if let Some(successor) = get_successor_block(jmp) {
+                    worklist.push_back((
+                        successor,
+                        self.compares_decoded_data(jmp, &decode_site.decoded),
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Run the check.
+///
+/// For each function we collect the buffers that client data is received into
+/// and the decodings of these buffers.
+/// For each decoding we then search for a branch on the decoded data
+/// that is reachable without passing a verification function.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE649.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let input_names: Vec<String> = config.input_symbols.keys().cloned().collect();
+    let input_symbols = get_symbol_map(project, &input_names);
+    // The examined sites are the decoding calls and loops operating on received data.
+    let mut site_counts = SiteCounts::default();
+    if input_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let decode_names: Vec<String> = config.decode_symbols.keys().cloned().collect();
+    let tables = FunctionTables {
+        input_symbols,
+        decode_symbols: get_symbol_map(project, &decode_names),
+        decode_subs: get_subs_by_keywords(project, &config.decode_function_keywords),
+        comparison_symbols: get_symbol_map(project, &config.comparison_symbols),
+        verification_symbols: get_symbol_map(project, &config.verification_symbols),
+        verification_subs: get_subs_by_keywords(project, &config.verification_function_keywords),
+        standard_parameters: project
+            .get_standard_calling_convention()
+            .map(|cconv| {
+                cconv
+                    .integer_parameter_register
+                    .iter()
+                    .map(|register| Arg::from_var(register.clone(), None))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        let context = FunctionContext {
+            project,
+            pi_result,
+            config: &config,
+            tables: &tables,
+            sub,
+            blocks: sub.term.blocks.iter().map(|blk| (&blk.tid, blk)).collect(),
+        };
+        let received = context.get_received_buffers();
+        if received.is_empty() {
+            continue;
+        }
+        let mut decode_sites = context.get_decoding_calls(&received);
+        decode_sites.append(&mut context.get_decoding_loops(&received));
+        for decode_site in decode_sites.iter() {
+            if let Some(decision) = context.find_unverified_decision(decode_site) {
+                site_counts.add_flagged();
+                cwe_warnings.push(generate_cwe_warning(sub, decode_site, decision));
+            } else {
+                site_counts.add_safe();
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    (Vec::new(), cwe_warnings)
+}
+
+/// Generate the CWE warning for a decision on decoded data without integrity check.
+fn generate_cwe_warning(sub: &Term<Sub>, decode_site: &DecodeSite, decision: &Tid) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Unverified Security Token) Function {} decodes data received by {} at {} with {} at {} and branches on the decoded data at {} without verifying its integrity",
+            sub.term.name,
+            decode_site.received.input_symbol.name,
+            decode_site.received.input_call.address,
+            decode_site.decoder,
+            decode_site.location.address,
+            decision.address
+        ),
+    )
+    .tids(vec![
+        format!("{}", decode_site.received.input_call),
+        format!("{}", decode_site.location),
+        format!("{decision}"),
+    ])
+    .addresses(vec![
+        decode_site.received.input_call.address.clone(),
+        decode_site.location.address.clone(),
+        decision.address.clone(),
+    ])
+    .symbols(vec![sub.term.name.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::{def, expr, variable};
+
+    fn mock_project(subs: Vec<Term<Sub>>) -> Project {
+        mock_x64_project(&["recv", "EVP_DecodeBlock", "strcmp", "HMAC"], subs)
+    }
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "input_symbols": {"recv": {"buffer": 1, "length": 2}},
+            "decode_symbols": {"EVP_DecodeBlock": {"input": 1, "output": 0}},
+            "decode_function_keywords": ["base64"],
+            "comparison_symbols": ["strcmp"],
+            "verification_symbols": ["HMAC"],
+            "verification_function_keywords": ["verify"]
+        })
+    }
+
+    /// Create a block receiving data into a stack buffer and continuing at the given block.
+    fn mock_receive_block(next_block: &str) -> Term<Blk> {
+        let mut receive = Blk::mock_with_tid("receive");
+        receive.term.defs = vec![
+            def!["buffer: RSI:8 = RSP:8 + 0x10:8"],
+            def!["length: RDX:8 = 0x100:8"],
+        ];
+        receive.term.jmps = vec![Jmp::call("recv_call", "recv", Some(next_block))];
+        receive
+    }
+
+    /// Create a block calling the given decoding function for the received data.
+    fn mock_decode_block(decoder: &str, next_block: &str) -> Term<Blk> {
+        let mut decode = Blk::mock_with_tid("decode");
+        decode.term.defs = vec![
+            def!["output: RDI:8 = RSP:8 + 0x200:8"],
+            def!["input: RSI:8 = RSP:8 + 0x10:8"],
+        ];
+        decode.term.jmps = vec![Jmp::call("decode_call", decoder, Some(next_block))];
+        decode
+    }
+
+    /// Create a block calling the given verification function if `verification` is given.
+    fn mock_verify_block(verification: Option<&str>, next_block: &str) -> Term<Blk> {
+        let mut verify = Blk::mock_with_tid("verify");
+        verify.term.jmps = vec![match verification {
+            Some(verification) => Jmp::call("verify_call", verification, Some(next_block)),
+            None => Jmp::branch("no_verification", next_block),
+        }];
+        verify
+    }
+
+    /// Create a block branching on a byte loaded from the given address.
+    fn mock_decision_block(address: &str) -> Term<Blk> {
+        let mut decision = Blk::mock_with_tid("decision");
+        decision.term.defs = vec![
+            def![format!("load_flag: RAX:1 := Load from {address}")],
+            def!["compare: ZF:1 = RAX:1 == 0x1:1"],
+        ];
+        decision.term.jmps = vec![
+            Term {
+                tid: Tid::new("decision_branch"),
+                term: Jmp::CBranch {
+                    target: Tid::new("exit"),
+                    condition: expr!("ZF:1"),
+                },
+            },
+            Jmp::branch("fallthrough", "exit"),
+        ];
+        decision
+    }
+
+    fn mock_exit_block() -> Term<Blk> {
+        let mut exit = Blk::mock_with_tid("exit");
+        exit.term.jmps = vec![Term {
+            tid: Tid::new("return"),
+            term: Jmp::Return(expr!("0x0:8")),
+        }];
+        exit
+    }
+
+    fn mock_decoding_sub(verification: Option<&str>) -> Term<Sub> {
+        let mut sub = Sub::mock("check_session");
+        sub.term.blocks = vec![
+            mock_receive_block("decode"),
+            mock_decode_block("EVP_DecodeBlock", "verify"),
+            mock_verify_block(verification, "decision"),
+            mock_decision_block("RSP:8 + 0x200:8"),
+            mock_exit_block(),
+        ];
+        sub
+    }
+
+    #[test]
+    fn decision_on_decoded_token() {
+        let warnings = run_check(
+            check_cwe,
+            &mock_project(vec![mock_decoding_sub(None)]),
+            &mock_config(),
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].symbols, vec!["check_session".to_string()]);
+        assert_eq!(
+            warnings[0].tids,
+            vec![
+                "recv_call".to_string(),
+                "decode_call".to_string(),
+                "decision_branch".to_string()
+            ]
+        );
+
+        assert!(run_check(
+            check_cwe,
+            &mock_project(vec![mock_decoding_sub(Some("HMAC"))]),
+            &mock_config()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn decision_on_undecoded_data() {
+        let mut sub = mock_decoding_sub(None);
+        sub.term.blocks[3] = mock_decision_block("RSP:8 + 0x400:8");
+        assert!(run_check(check_cwe, &mock_project(vec![sub]), &mock_config()).is_empty());
+    }
+
+    #[test]
+    fn internal_decoding_and_verification_functions() {
+        let internal_function = |name: &str| {
+            let mut sub = Sub::mock(name);
+            let mut blk = Blk::mock_with_tid(&format!("{name}_blk"));
+            blk.term.defs = vec![def![format!("{name}_pop: RSP:8 = RSP:8 + 0x8:8")]];
+            blk.term.jmps = vec![Term {
+                tid: Tid::new(format!("{name}_return")),
+                term: Jmp::Return(expr!("0x0:8")),
+            }];
+            sub.term.blocks = vec![blk];
+            sub
+        };
+        let decoder = internal_function("base64_decode");
+        let verifier = internal_function("verify_token");
+        let mut sub = Sub::mock("check_session");
+        sub.term.blocks = vec![
+            mock_receive_block("decode"),
+            mock_decode_block("base64_decode", "verify"),
+            mock_verify_block(None, "decision"),
+            mock_decision_block("RSP:8 + 0x200:8"),
+            mock_exit_block(),
+        ];
+        let warnings = run_check(
+            check_cwe,
+            &mock_project(vec![decoder.clone(), verifier.clone(), sub.clone()]),
+            &mock_config(),
+        );
+        assert_eq!(warnings.len(), 1);
+
+        sub.term.blocks[2] = mock_verify_block(Some("verify_token"), "decision");
+        assert!(run_check(
+            check_cwe,
+            &mock_project(vec![decoder, verifier, sub]),
+            &mock_config()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn comparison_of_decoded_token() {
+        let mut compare = Blk::mock_with_tid("verify");
+        compare.term.defs = vec![def!["compared: RDI:8 = RSP:8 + 0x200:8"]];
+        compare.term.jmps = vec![Jmp::call("strcmp_call", "strcmp", Some("decision"))];
+        let mut decision = Blk::mock_with_tid("decision");
+        decision.term.defs = vec![def!["compare: ZF:1 = RAX:8 == 0x0:8"]];
+        decision.term.jmps = vec![
+            Term {
+                tid: Tid::new("decision_branch"),
+                term: Jmp::CBranch {
+                    target: Tid::new("exit"),
+                    condition: expr!("ZF:1"),
+                },
+            },
+            Jmp::branch("fallthrough", "exit"),
+        ];
+        let mut sub = mock_decoding_sub(None);
+        sub.term.blocks[2] = compare;
+        sub.term.blocks[3] = decision;
+        let warnings = run_check(check_cwe, &mock_project(vec![sub]), &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids[2], "decision_branch".to_string());
+    }
+
+    #[test]
+    fn xor_decoding_loop() {
+        let mut decode_loop = Blk::mock_with_tid("decode");
+        decode_loop.term.defs = vec![
+            def!["load_encoded: RAX:1 := Load from RSP:8 + 0x10:8"],
+            Term {
+                tid: Tid::new("xor"),
+                term: Def::Assign {
+                    var: variable!("RAX:1"),
+                    value: Expression::BinOp {
+                        op: BinOpType::IntXOr,
+                        lhs: Box::new(expr!("RAX:1")),
+                        rhs: Box::new(expr!("0x5a:1")),
+                    },
+                },
+            },
+            def!["store_decoded: Store at RSP:8 + 0x10:8 := RAX:1"],
+            def!["loop_condition: ZF:1 = RCX:8 == 0x0:8"],
+        ];
+        decode_loop.term.jmps = vec![
+            Term {
+                tid: Tid::new("loop_branch"),
+                term: Jmp::CBranch {
+                    target: Tid::new("decode"),
+                    condition: expr!("ZF:1"),
+                },
+            },
+            Jmp::branch("loop_exit", "verify"),
+        ];
+        let mut sub = mock_decoding_sub(None);
+        sub.term.blocks[1] = decode_loop;
+        sub.term.blocks[3] = mock_decision_block("RSP:8 + 0x10:8");
+        let warnings = run_check(check_cwe, &mock_project(vec![sub]), &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec![
+                "recv_call".to_string(),
+                "store_decoded".to_string(),
+                "decision_branch".to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,377 @@
+//! This module implements a check for CWE-656: Reliance on Security Through Obscurity,
+//! in the form of predictable session tokens.
+//!
+//! Session identifiers and authentication tokens must not be guessable.
+//! Tokens that are derived from the current time, the process ID or the output of a
+//! non-cryptographic PRNG like `rand` can be predicted by an attacker,
+//! who is then able to take over the sessions of other users.
+//!
+//! See <https://cwe.mitre.org/data/definitions/656.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! Using a taint analysis we search for execution paths where the return value
+//! of a low-entropy source like `time`, `getpid` or `rand` reaches
+//! a function that sends data to clients (e.g. `send`) or compares data for authentication (e.g. `strcmp`).
+//! Token building functions like `sprintf` propagate the taint of their arguments
+//! to their destination buffer, so that tokens assembled from several low-entropy values are also tracked.
+//! Contrary to the check for CWE-337 we are not interested in the seeding of PRNGs,
+//! but in the use of the generated values as session tokens.
+//!
+//! ### Symbols configurable in config.json
+//!
+//! - `sources`: Functions returning low-entropy values.
+//! - `token_builders`: Functions that write their arguments into a buffer,
+//!   mapped to the parameter index of the destination buffer.
+//! - `sinks`: Functions that send tokens to clients or compare them for authentication.
+//!
+//! ## False Positives
+//!
+//! - Low-entropy values that are sent to clients for other purposes than authentication,
+//!   e.g. timestamps in protocol messages, are also flagged.
+//! - Tokens that mix a low-entropy value with enough entropy from a secure source are also flagged.
+//!
+//! ## False Negatives
+//!
+//! - Tokens derived from counters in global variables are not detected.
+//! - Taint is lost when token building functions write to buffers that the pointer inference cannot track exactly.
+
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, HasCfg};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{
+    Data as PiData, PointerInference as PointerInferenceComputation,
+};
+use crate::analysis::taint::{
+    state::State as TaState, summary::TaintSummaries, Taint, TaintAnalysis,
+};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::{
+    log::{CweWarning, LogMessage},
+    metrics::SiteCounts,
+    symbol_utils,
+};
+use crate::CweModule;
+
+use petgraph::visit::EdgeRef;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::convert::AsRef;
+
+/// The module name and version.
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE656",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions returning low-entropy values.
+    sources: Vec<String>,
+    /// Functions assembling their arguments into a buffer,
+    /// mapped to the parameter index of the destination buffer.
+    token_builders: BTreeMap<String, usize>,
+    /// Functions sending tokens to clients or comparing them for authentication.
+    sinks: Vec<String>,
+}
+
+/// Run the CWE check.
+///
+/// We taint the return values of all calls to source functions
+/// and generate a warning for each call to a sink function
+/// where some parameter or some memory pointed to by a parameter may be tainted.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE656.");
+
+    let source_map = symbol_utils::get_symbol_map(project, &config.sources[..]);
+    let sink_map = symbol_utils::get_symbol_map(project, &config.sinks[..]);
+    // The examined sites are the calls to sinks.
+    let mut site_counts = SiteCounts::default();
+    if source_map.is_empty() || sink_map.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let sink_call_addresses: Vec<String> = project
+        .program
+        .term
+        .subs
+        .values()
+        .flat_map(|sub| symbol_utils::get_callsites(sub, &sink_map))
+        .map(|(_, jmp, _)| jmp.tid.address.clone())
+        .collect();
+
+    let pi_result = analysis_results.pointer_inference.unwrap();
+    let graph = analysis_results.control_flow_graph;
+    let (cwe_sender, cwe_receiver) = crossbeam_channel::unbounded();
+
+    let context = Context {
+        project,
+        pi_result,
+        control_flow_graph: graph,
+        sink_map,
+        token_builder_map: get_token_builder_map(project, &config.token_builders),
+        extern_symbol_map: project
+            .program
+            .term
+            .extern_symbols
+            .iter()
+            .map(|(tid, sym)| (tid.clone(), sym))
+            .collect(),
+        taint_summaries: analysis_results.taint_summaries,
+        cwe_collector: cwe_sender,
+    };
+    let mut computation = create_computation(context, None);
+    computation.set_cancellation_token(analysis_results.cancellation_token);
+
+    for edge in graph.edge_references() {
+        let Edge::ExternCallStub(jmp) = edge.weight() else {
+            continue;
+        };
+        let Jmp::Call { target, .. } = &jmp.term else {
+            continue;
+        };
+        let Some(symbol) = source_map.get(target) else {
+            continue;
+        };
+        let return_node = edge.target();
+
+        computation.set_node_value(
+            return_node,
+            NodeValue::Value(TaState::new_return(symbol, pi_result, return_node)),
+        );
+    }
+
+    computation.compute_with_max_steps(100);
+
+    let mut cwe_warnings = BTreeMap::new();
+    for cwe in cwe_receiver.try_iter() {
+        cwe_warnings.insert(cwe.addresses[0].clone(), cwe);
+    }
+    for address in sink_call_addresses.iter() {
+        if cwe_warnings.contains_key(address) {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    let cwe_warnings = cwe_warnings.into_values().collect();
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Map the TIDs of the token building functions contained in the project
+/// to the symbol and the parameter index of the destination buffer.
+fn get_token_builder_map<'a>(
+    project: &'a Project,
+    token_builders: &BTreeMap<String, usize>,
+) -> HashMap<Tid, (&'a ExternSymbol, usize)> {
+    project
+        .program
+        .term
+        .extern_symbols
+        .iter()
+        .filter_map(|(tid, symbol)| {
+            let index = *token_builders.get(&symbol.name)?;
+            (index < symbol.parameters.len()).then(|| (tid.clone(), (symbol, index)))
+        })
+        .collect()
+}
+
+/// The Context struct for the taint analysis.
+pub struct Context<'a> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInferenceComputation<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// A map of symbols to use as sinks for the algorithm.
+    sink_map: HashMap<Tid, &'a ExternSymbol>,
+    /// Maps the TIDs of token building functions to the symbol
+    /// and the parameter index of the destination buffer.
+    token_builder_map: HashMap<Tid, (&'a ExternSymbol, usize)>,
+    /// Maps the TID of an extern symbol to the extern symbol struct.
+    extern_symbol_map: HashMap<Tid, &'a ExternSymbol>,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where found CWE hits can be sent to.
+    cwe_collector: crossbeam_channel::Sender<CweWarning>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
+    /// Generate a CWE warning if taint may be contained in the arguments to a
+    /// sink function and propagate taint through token building functions.
+    ///
+    /// After a warning was generated we return `None` to suppress
+    /// the generation of further warnings.
+    fn update_call_stub(&self, state: &TaState, call: &Term<Jmp>) -> Option<TaState> {
+        if state.is_empty() {
+            return None;
+        }
+
+        match &call.term {
+            Jmp::Call { target, .. } => {
+                if let Some(sink_symbol) = self.sink_map.get(target) {
+                    if state.check_extern_parameters_for_taint::<true>(
+                        self.vsa_result(),
+                        sink_symbol,
+                        &call.tid,
+                    ) {
+                        self.generate_cwe_warning(call, sink_symbol);
+
+                        None
+                    } else {
+                        Some(self.update_extern_symbol(state, call, sink_symbol))
+                    }
+                } else if let Some((builder_symbol, dest_index)) =
+                    self.token_builder_map.get(target)
+                {
+                    Some(self.update_token_builder(state, call, builder_symbol, *dest_index))
+                } else {
+                    let extern_symbol = self
+                        .extern_symbol_map
+                        .get(target)
+                        .expect("Extern symbol not found.");
+
+                    Some(self.update_extern_symbol(state, call, extern_symbol))
+                }
+            }
+            Jmp::CallInd { .. } => self.update_call_generic(state, &call.tid, &None),
+            _ => panic!("Malformed control flow graph encountered."),
+        }
+    }
+}
+
+impl<'a> Context<'a> {
+    /// Transition function for calls to external functions that do not
+    /// trigger a CWE warning, i.e., its not a sink function or no taint is in
+    /// the arguments.
+    ///
+    /// Taint is propagated according to the taint summary of the function if one is known.
+    fn update_extern_symbol(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        extern_symbol: &ExternSymbol,
+    ) -> TaState {
+        let calling_conv = self.project.get_calling_convention(extern_symbol);
+        let mut new_state = state.clone();
+
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        if let Some(summary) = self
+            .taint_summaries
+            .and_then(|summaries| summaries.get(&extern_symbol.name))
+        {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.pi_result,
+                &call.tid,
+                calling_conv,
+            );
+        }
+
+        new_state
+    }
+
+    /// Transition function for calls to token building functions.
+    ///
+    /// If some argument of the call may be tainted or may point to taint,
+    /// the start of the destination buffer gets tainted.
+    fn update_token_builder(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        builder_symbol: &ExternSymbol,
+        dest_index: usize,
+    ) -> TaState {
+        let mut new_state = self.update_extern_symbol(state, call, builder_symbol);
+        if state.check_extern_parameters_for_taint::<true>(
+            self.vsa_result(),
+            builder_symbol,
+            &call.tid,
+        ) {
+            if let Some(dest) = self
+                .vsa_result()
+                .eval_parameter_arg_at_call(&call.tid, &builder_symbol.parameters[dest_index])
+            {
+                new_state.save_taint_to_memory(&dest, Taint::Tainted(ByteSize::new(1)));
+            }
+        }
+
+        new_state
+    }
+
+    fn generate_cwe_warning(&self, sink_call: &Term<Jmp>, sink_symbol: &ExternSymbol) {
+        let cwe_warning = CweWarning::new(
+            CWE_MODULE.name,
+            CWE_MODULE.version,
+            format!(
+                "(Predictable Session Token) Value derived from a low-entropy source is used by {} at {}.",
+                sink_symbol.name, sink_call.tid.address,
+            ),
+        )
+        .tids(vec![format!("{}", sink_call.tid)])
+        .addresses(vec![sink_call.tid.address.clone()])
+        .symbols(vec![sink_symbol.name.clone()]);
+        let _ = self.cwe_collector.send(cwe_warning);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::mock_x64_project;
+
+    #[test]
+    fn token_builder_map() {
+        let mut project = mock_x64_project(&["sprintf", "strcpy"], Vec::new());
+        project
+            .program
+            .term
+            .extern_symbols
+            .get_mut(&Tid::new("strcpy"))
+            .unwrap()
+            .parameters = Vec::new();
+        let token_builders =
+            BTreeMap::from([("sprintf".to_string(), 0), ("strcpy".to_string(), 0)]);
+
+        let map = get_token_builder_map(&project, &token_builders);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map[&Tid::new("sprintf")].0.name, "sprintf");
+        assert_eq!(map[&Tid::new("sprintf")].1, 0);
+    }
+}
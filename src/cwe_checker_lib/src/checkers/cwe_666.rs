@@ -0,0 +1,699 @@
+//! This module implements a check for CWE-666: Operation on Resource in Wrong Phase of Lifetime,
+//! for the lifecycle of sockets.
+//!
+//! Sockets have to be used in the phases of their lifecycle that the operating system expects:
+//! A stream socket has to be connected before data can be sent over it,
+//! only listening sockets can accept connections
+//! and no operation is allowed after the socket was closed.
+//! Operations in the wrong phase fail at runtime,
+//! which often leads to unexpected behavior if the return values are not checked.
+//!
+//! See <https://cwe.mitre.org/data/definitions/666.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! We track the socket file descriptors returned by `socket` and `accept` through registers
+//! and (with the help of the pointer inference) through memory locations
+//! and compute an abstract typestate for each socket,
+//! i.e. the set of lifecycle phases that the socket may be in.
+//! Calls to `bind`, `listen`, `connect`, `shutdown` and `close` move the socket into the corresponding phase.
+//! For each operation on a socket we check whether it is allowed in any of the phases that the socket may be in.
+//! If not, a warning is generated.
+//!
+//! ### Symbols configurable in config.json
+//!
+//! - `send_symbols`: Functions that require a connected socket, e.g. `send`.
+//! - `receive_symbols`: Functions that require a bound or connected socket, e.g. `recv`.
+//! - `datagram_symbols`: Functions that specify the peer address themselves, e.g. `sendto`.
+//!
+//! ## False Positives
+//!
+//! - We assume that lifecycle functions always succeed.
+//!   E.g. if a call to `close` fails, the socket is still considered closed.
+//! - Receiving on a socket that was only created but never bound is flagged,
+//!   although the operating system implicitly binds datagram sockets on some occasions.
+//!
+//! ## False Negatives
+//!
+//! - The analysis is intraprocedural.
+//!   Sockets that are passed to or returned from other functions of the binary are not tracked.
+//! - Socket descriptors stored in memory are only tracked if the pointer inference knows the exact storage location.
+//! - Since we only report operations that are invalid in all phases that a socket may be in,
+//!   wrong operations on some execution paths may be missed.
+
+use crate::abstract_domain::{AbstractIdentifier, TryToBitvec};
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, Node};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{Data, PointerInference};
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE666",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions sending data over a connected socket.
+    send_symbols: Vec<String>,
+    /// Functions receiving data from a bound or connected socket.
+    receive_symbols: Vec<String>,
+    /// Functions sending or receiving data with an explicitly given peer address.
+    datagram_symbols: Vec<String>,
+}
+
+/// The phases in the lifecycle of a socket.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+enum Phase {
+    Created,
+    Bound,
+    Listening,
+    Connected,
+    ReadShutdown,
+    WriteShutdown,
+    Shutdown,
+    Closed,
+}
+
+impl Phase {
+    /// A description of the phase for CWE warnings.
+    fn description(&self) -> &'static str {
+        match self {
+            Phase::Created | Phase::Bound => "not connected",
+            Phase::Listening => "listening",
+            Phase::Connected => "connected",
+            Phase::ReadShutdown => "shut down for reading",
+            Phase::WriteShutdown => "shut down for writing",
+            Phase::Shutdown => "shut down",
+            Phase::Closed => "closed",
+        }
+    }
+}
+
+/// Operations on sockets.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Operation {
+    Bind,
+    Listen,
+    Accept,
+    Connect,
+    Shutdown,
+    Close,
+    Send,
+    Receive,
+    Datagram,
+}
+
+impl Operation {
+    /// Returns `true` if the operation is allowed on a socket in the given phase.
+    fn is_allowed_in(&self, phase: Phase) -> bool {
+        use Phase::*;
+        match self {
+            Operation::Bind => phase == Created,
+            Operation::Listen => matches!(phase, Created | Bound),
+            Operation::Accept => phase == Listening,
+            Operation::Connect => matches!(phase, Created | Bound | Connected),
+            Operation::Shutdown | Operation::Close => phase != Closed,
+            Operation::Send => matches!(phase, Connected | ReadShutdown),
+            Operation::Receive => matches!(phase, Bound | Connected | WriteShutdown),
+            Operation::Datagram => matches!(
+                phase,
+                Created | Bound | Connected | ReadShutdown | WriteShutdown
+            ),
+        }
+    }
+}
+
+/// Classification of the extern symbols relevant for the check.
+#[derive(Debug, Default)]
+struct SymbolClassification<'a> {
+    /// Functions returning new sockets.
+    creating_symbols: HashMap<Tid, &'a ExternSymbol>,
+    /// Functions operating on a socket given as their first parameter.
+    operations: HashMap<Tid, (&'a ExternSymbol, Operation)>,
+}
+
+impl<'a> SymbolClassification<'a> {
+    /// Classify the extern symbols of the project.
+    fn new(project: &'a Project, config: &Config) -> Self {
+        let mut classification = SymbolClassification {
+            creating_symbols: get_symbol_map(project, &["socket".to_string()]),
+            operations: HashMap::new(),
+        };
+        let mut add_operation = |names: &[String], operation: Operation| {
+            for (tid, symbol) in get_symbol_map(project, names) {
+                classification.operations.insert(tid, (symbol, operation));
+            }
+        };
+        for (name, operation) in [
+            ("bind", Operation::Bind),
+            ("listen", Operation::Listen),
+            ("accept", Operation::Accept),
+            ("accept4", Operation::Accept),
+            ("connect", Operation::Connect),
+            ("shutdown", Operation::Shutdown),
+            ("close", Operation::Close),
+        ] {
+            add_operation(&[name.to_string()], operation);
+        }
+        add_operation(&config.send_symbols, Operation::Send);
+        add_operation(&config.receive_symbols, Operation::Receive);
+        add_operation(&config.datagram_symbols, Operation::Datagram);
+        for (tid, (symbol, operation)) in classification.operations.iter() {
+            if *operation == Operation::Accept {
+                classification.creating_symbols.insert(tid.clone(), symbol);
+            }
+        }
+        classification
+    }
+}
+
+/// The abstract typestate of all sockets of a function.
+///
+/// Sockets are identified by the TID of the call that created them.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct State {
+    /// The sockets that a register may contain.
+    registers: BTreeMap<Variable, BTreeSet<Tid>>,
+    /// The sockets that an exactly known memory location may contain.
+    memory: BTreeMap<(AbstractIdentifier, i64), BTreeSet<Tid>>,
+    /// The phases that each socket may be in.
+    phases: BTreeMap<Tid, BTreeSet<Phase>>,
+}
+
+/// Merge two maps from keys to sets by taking the union of the sets.
+fn merge_set_maps<K: Ord + Clone, V: Ord + Clone>(
+    map: &BTreeMap<K, BTreeSet<V>>,
+    other: &BTreeMap<K, BTreeSet<V>>,
+) -> BTreeMap<K, BTreeSet<V>> {
+    let mut merged = map.clone();
+    for (key, values) in other {
+        merged
+            .entry(key.clone())
+            .or_default()
+            .extend(values.iter().cloned());
+    }
+    merged
+}
+
+impl State {
+    /// Merge two states.
+    fn merge(&self, other: &State) -> State {
+        State {
+            registers: merge_set_maps(&self.registers, &other.registers),
+            memory: merge_set_maps(&self.memory, &other.memory),
+            phases: merge_set_maps(&self.phases, &other.phases),
+        }
+    }
+
+    /// Return the sockets that the low bytes of the given expression may contain.
+    fn eval(&self, expression: &Expression) -> BTreeSet<Tid> {
+        match expression {
+            Expression::Var(var) => self.registers.get(var).cloned().unwrap_or_default(),
+            Expression::Cast {
+                op: CastOpType::IntZExt | CastOpType::IntSExt,
+                arg,
+                ..
+            }
+            | Expression::BinOp {
+                op: BinOpType::Piece,
+                rhs: arg,
+                ..
+            } => self.eval(arg),
+            Expression::Subpiece { low_byte, arg, .. } if u64::from(*low_byte) == 0 => {
+                self.eval(arg)
+            }
+            _ => BTreeSet::new(),
+        }
+    }
+
+    /// Set the sockets that a register may contain.
+    fn set_register(&mut self, var: &Variable, sockets: BTreeSet<Tid>) {
+        if sockets.is_empty() {
+            self.registers.remove(var);
+        } else {
+            self.registers.insert(var.clone(), sockets);
+        }
+    }
+
+    /// Return the exact memory location that the given address points to.
+    fn get_memory_location(address: &Data) -> Option<(AbstractIdentifier, i64)> {
+        let (id, offset) = address.get_if_unique_target()?;
+        if address.contains_top() {
+            return None;
+        }
+        Some((id.clone(), offset.try_to_offset().ok()?))
+    }
+
+    /// Load the sockets that the memory location at `address` may contain.
+    fn load(&self, address: Option<&Data>) -> BTreeSet<Tid> {
+        address
+            .and_then(State::get_memory_location)
+            .and_then(|location| self.memory.get(&location).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Store the given sockets at `address`.
+    ///
+    /// Stores to inexactly known locations are ignored.
+    fn store(&mut self, address: Option<&Data>, sockets: BTreeSet<Tid>) {
+        if let Some(location) = address.and_then(State::get_memory_location) {
+            if sockets.is_empty() {
+                self.memory.remove(&location);
+            } else {
+                self.memory.insert(location, sockets);
+            }
+        }
+    }
+
+    /// Update the state for a `Def`.
+    /// The `address` is the value of the address of loads and stores.
+    fn handle_def(&mut self, def: &Def, address: Option<&Data>) {
+        match def {
+            Def::Assign { var, value } => {
+                let sockets = self.eval(value);
+                self.set_register(var, sockets);
+            }
+            Def::Load { var, .. } => {
+                let sockets = self.load(address);
+                self.set_register(var, sockets);
+            }
+            Def::Store { value, .. } => {
+                let sockets = self.eval(value);
+                self.store(address, sockets);
+            }
+        }
+    }
+
+    /// Remove all registers that are not callee-saved in the given calling convention.
+    fn clear_non_callee_saved_registers(&mut self, calling_convention: Option<&CallingConvention>) {
+        match calling_convention {
+            Some(cconv) => self
+                .registers
+                .retain(|var, _| cconv.callee_saved_register.contains(var)),
+            None => self.registers.clear(),
+        }
+    }
+
+    /// Add a new socket created at the given call in the given phase.
+    fn create_socket(&mut self, call_tid: &Tid, return_register: Option<&Variable>, phase: Phase) {
+        if let Some(return_register) = return_register {
+            self.phases
+                .insert(call_tid.clone(), BTreeSet::from([phase]));
+            self.set_register(return_register, BTreeSet::from([call_tid.clone()]));
+        }
+    }
+
+    /// Move the given sockets into the new phases after an operation.
+    ///
+    /// If there is only one socket the update is a strong update,
+    /// otherwise the new phases are added to the old ones.
+    fn transition(&mut self, sockets: &BTreeSet<Tid>, new_phases: &BTreeSet<Phase>) {
+        for socket in sockets {
+            let phases = self.phases.entry(socket.clone()).or_default();
+            if sockets.len() == 1 {
+                *phases = new_phases.clone();
+            } else {
+                phases.extend(new_phases.iter().cloned());
+            }
+        }
+    }
+
+    /// Return the sockets on which the operation is not allowed in any of their possible phases,
+    /// together with the phases they may be in.
+    fn get_violations(
+        &self,
+        sockets: &BTreeSet<Tid>,
+        operation: Operation,
+    ) -> Vec<(&Tid, &BTreeSet<Phase>)> {
+        sockets
+            .iter()
+            .filter_map(|socket| {
+                let phases = self.phases.get_key_value(socket)?;
+                (!phases.1.is_empty()
+                    && !phases.1.iter().any(|phase| operation.is_allowed_in(*phase)))
+                .then_some(phases)
+            })
+            .collect()
+    }
+}
+
+/// The context of the socket typestate analysis.
+struct Context<'a> {
+    project: &'a Project,
+    graph: &'a Graph<'a>,
+    pointer_inference: &'a PointerInference<'a>,
+    symbols: SymbolClassification<'a>,
+}
+
+impl<'a> Context<'a> {
+    /// Return the sockets that the socket parameter of the call may contain.
+    fn get_socket_param(
+        &self,
+        state: &State,
+        call_tid: &Tid,
+        symbol: &ExternSymbol,
+    ) -> BTreeSet<Tid> {
+        match symbol.parameters.first() {
+            Some(Arg::Register { expr, .. }) => state.eval(expr),
+            Some(Arg::Stack { address, .. }) => {
+                let address = self.pointer_inference.eval_at_jmp(call_tid, address);
+                state.load(address.as_ref())
+            }
+            None => BTreeSet::new(),
+        }
+    }
+
+    /// Return the register that receives the return value of the extern symbol.
+    fn get_return_register(symbol: &ExternSymbol) -> Option<&Variable> {
+        match symbol.return_values.first() {
+            Some(Arg::Register {
+                expr: Expression::Var(var),
+                ..
+            }) => Some(var),
+            _ => None,
+        }
+    }
+
+    /// Return the phases that a socket may be in after the given operation.
+    fn get_phases_after_operation(
+        &self,
+        call_tid: &Tid,
+        symbol: &ExternSymbol,
+        operation: Operation,
+    ) -> Option<BTreeSet<Phase>> {
+        let phase = match operation {
+            Operation::Bind => Phase::Bound,
+            Operation::Listen => Phase::Listening,
+            Operation::Connect => Phase::Connected,
+            Operation::Close => Phase::Closed,
+            Operation::Shutdown => {
+                let how = symbol
+                    .parameters
+                    .get(1)
+                    .and_then(|param| {
+                        self.pointer_inference
+                            .eval_parameter_arg_at_call(call_tid, param)
+                    })
+                    .and_then(|how| how.get_if_absolute_value()?.try_to_offset().ok());
+                return Some(match how {
+                    Some(0) => BTreeSet::from([Phase::ReadShutdown]),
+                    Some(1) => BTreeSet::from([Phase::WriteShutdown]),
+                    Some(_) => BTreeSet::from([Phase::Shutdown]),
+                    None => {
+                        BTreeSet::from([Phase::ReadShutdown, Phase::WriteShutdown, Phase::Shutdown])
+                    }
+                });
+            }
+            Operation::Accept | Operation::Send | Operation::Receive | Operation::Datagram => {
+                return None
+            }
+        };
+        Some(BTreeSet::from([phase]))
+    }
+
+    /// Generate the CWE warnings for the call to an extern symbol with the given state before the call.
+    fn check_call(&self, state: &State, call: &Term<Jmp>, target: &Tid) -> Vec<CweWarning> {
+        let Some((symbol, operation)) = self.symbols.operations.get(target) else {
+            return Vec::new();
+        };
+        let sockets = self.get_socket_param(state, &call.tid, symbol);
+        state
+            .get_violations(&sockets, *operation)
+            .into_iter()
+            .map(|(socket, phases)| {
+                let descriptions: BTreeSet<&str> =
+                    phases.iter().map(|phase| phase.description()).collect();
+                CweWarning::new(
+                    CWE_MODULE.name,
+                    CWE_MODULE.version,
+                    format!(
+                        "(Operation on Resource in Wrong Phase of Lifetime) {} at {} is called on a socket that is {}",
+                        symbol.name,
+                        call.tid.address,
+                        descriptions.into_iter().collect::<Vec<_>>().join(" or ")
+                    ),
+                )
+                .tids(vec![format!("{}", call.tid)])
+                .addresses(vec![call.tid.address.clone()])
+                .symbols(vec![symbol.name.clone()])
+                .other(vec![vec![
+                    "socket_created_at".to_string(),
+                    socket.address.clone(),
+                ]])
+            })
+            .collect()
+    }
+}
+
+impl<'a> crate::analysis::forward_interprocedural_fixpoint::Context<'a> for Context<'a> {
+    type Value = State;
+
+    fn get_graph(&self) -> &Graph<'a> {
+        self.graph
+    }
+
+    fn merge(&self, value1: &State, value2: &State) -> State {
+        value1.merge(value2)
+    }
+
+    fn update_def(&self, value: &State, def: &Term<Def>) -> Option<State> {
+        let mut state = value.clone();
+        let address = match &def.term {
+            Def::Load { .. } | Def::Store { .. } => {
+                self.pointer_inference.eval_address_at_def(&def.tid)
+            }
+            Def::Assign { .. } => None,
+        };
+        state.handle_def(&def.term, address.as_ref());
+        Some(state)
+    }
+
+    fn update_jump(
+        &self,
+        value: &State,
+        _jump: &Term<Jmp>,
+        _untaken_conditional: Option<&Term<Jmp>>,
+        _target: &Term<Blk>,
+    ) -> Option<State> {
+        Some(value.clone())
+    }
+
+    /// The analysis is intraprocedural, so no state flows into callees.
+    fn update_call(
+        &self,
+        _value: &State,
+        _call: &Term<Jmp>,
+        _target: &Node,
+        _calling_convention: &Option<String>,
+    ) -> Option<State> {
+        None
+    }
+
+    /// Continue with the state before the call, where caller-saved registers are removed.
+    fn update_return(
+        &self,
+        _value: Option<&State>,
+        value_before_call: Option<&State>,
+        _call_term: &Term<Jmp>,
+        _return_term: &Term<Jmp>,
+        calling_convention: &Option<String>,
+    ) -> Option<State> {
+        let mut state = value_before_call?.clone();
+        state.clear_non_callee_saved_registers(
+            self.project
+                .get_specific_calling_convention(calling_convention),
+        );
+        Some(state)
+    }
+
+    fn update_call_stub(&self, value: &State, call: &Term<Jmp>) -> Option<State> {
+        let mut state = value.clone();
+        let Jmp::Call { target, .. } = &call.term else {
+            state.clear_non_callee_saved_registers(self.project.get_standard_calling_convention());
+            return Some(state);
+        };
+        let Some(symbol) = self.project.program.term.extern_symbols.get(target) else {
+            state.clear_non_callee_saved_registers(self.project.get_standard_calling_convention());
+            return Some(state);
+        };
+        if let Some((symbol, operation)) = self.symbols.operations.get(target) {
+            if let Some(new_phases) = self.get_phases_after_operation(&call.tid, symbol, *operation)
+            {
+                let sockets = self.get_socket_param(value, &call.tid, symbol);
+                state.transition(&sockets, &new_phases);
+            }
+        }
+        state.clear_non_callee_saved_registers(Some(self.project.get_calling_convention(symbol)));
+        if self.symbols.creating_symbols.contains_key(target) {
+            let phase = if symbol.name == "socket" {
+                Phase::Created
+            } else {
+                Phase::Connected
+            };
+            state.create_socket(&call.tid, Context::get_return_register(symbol), phase);
+        }
+        Some(state)
+    }
+
+    fn specialize_conditional(
+        &self,
+        value: &State,
+        _condition: &Expression,
+        _block_before_condition: &Term<Blk>,
+        _is_true: bool,
+    ) -> Option<State> {
+        Some(value.clone())
+    }
+}
+
+/// Run the check. See the module-level description for more information.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE666.");
+    let symbols = SymbolClassification::new(project, &config);
+    // The examined sites are the calls to socket operations.
+    let mut site_counts = SiteCounts::default();
+    if symbols.creating_symbols.is_empty() || symbols.operations.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let graph = analysis_results.control_flow_graph;
+    let context = Context {
+        project,
+        graph,
+        pointer_inference: analysis_results.pointer_inference.unwrap(),
+        symbols,
+    };
+
+    // Only functions that create sockets need to be analyzed.
+    let mut subs_creating_sockets = BTreeSet::new();
+    for edge in graph.edge_references() {
+        if let Edge::ExternCallStub(Term {
+            term: Jmp::Call { target, .. },
+            ..
+        }) = edge.weight()
+        {
+            if context.symbols.creating_symbols.contains_key(target) {
+                subs_creating_sockets.insert(graph[edge.source()].get_sub().tid.clone());
+            }
+        }
+    }
+    let mut computation = create_computation(context, None);
+    computation.set_cancellation_token(analysis_results.cancellation_token);
+    for (sub_tid, entry_node) in crate::analysis::graph::get_entry_nodes_of_subs(graph) {
+        if subs_creating_sockets.contains(&sub_tid) {
+            computation.set_node_value(entry_node, NodeValue::Value(State::default()));
+        }
+    }
+    computation.compute_with_max_steps(100);
+
+    let context = computation.get_context().get_context();
+    let mut cwe_warnings = BTreeMap::new();
+    for edge in graph.edge_references() {
+        let Edge::ExternCallStub(call) = edge.weight() else {
+            continue;
+        };
+        let Jmp::Call { target, .. } = &call.term else {
+            continue;
+        };
+        if !context.symbols.operations.contains_key(target) {
+            continue;
+        }
+        let Some(NodeValue::Value(state)) = computation.get_node_value(edge.source()) else {
+            site_counts.add_skipped();
+            continue;
+        };
+        let warnings = context.check_call(state, call, target);
+        if warnings.is_empty() {
+            site_counts.add_safe();
+        } else {
+            site_counts.add_flagged();
+        }
+        for warning in warnings {
+            cwe_warnings.insert((warning.addresses.clone(), warning.other.clone()), warning);
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{expr, variable};
+
+    #[test]
+    fn socket_typestate() {
+        let socket_tid = Tid::new("call_socket");
+        let mut state = State::default();
+        state.create_socket(&socket_tid, Some(&variable!("RAX:8")), Phase::Created);
+        state.handle_def(
+            &Def::Assign {
+                var: variable!("RBX:8"),
+                value: expr!("RAX:8"),
+            },
+            None,
+        );
+        let sockets = state.eval(&Expression::Subpiece {
+            low_byte: ByteSize::new(0),
+            size: ByteSize::new(4),
+            arg: Box::new(expr!("RBX:8")),
+        });
+        assert_eq!(sockets, BTreeSet::from([socket_tid.clone()]));
+
+        assert_eq!(state.get_violations(&sockets, Operation::Send).len(), 1);
+        assert!(state
+            .get_violations(&sockets, Operation::Datagram)
+            .is_empty());
+        state.transition(&sockets, &BTreeSet::from([Phase::Connected]));
+        assert!(state.get_violations(&sockets, Operation::Send).is_empty());
+        assert_eq!(state.get_violations(&sockets, Operation::Accept).len(), 1);
+
+        // The phases are merged on control flow joins.
+        let mut closed_state = state.clone();
+        closed_state.transition(&sockets, &BTreeSet::from([Phase::Closed]));
+        assert_eq!(
+            closed_state.get_violations(&sockets, Operation::Send).len(),
+            1
+        );
+        let merged_state = state.merge(&closed_state);
+        assert!(merged_state
+            .get_violations(&sockets, Operation::Send)
+            .is_empty());
+
+        // Shutting down a socket for writing still allows receiving.
+        state.transition(&sockets, &BTreeSet::from([Phase::WriteShutdown]));
+        assert_eq!(state.get_violations(&sockets, Operation::Send).len(), 1);
+        assert!(state
+            .get_violations(&sockets, Operation::Receive)
+            .is_empty());
+
+        // Overwriting the register removes the socket from it.
+        state.handle_def(
+            &Def::Assign {
+                var: variable!("RBX:8"),
+                value: expr!("0:8"),
+            },
+            None,
+        );
+        assert!(state.eval(&expr!("RBX:8")).is_empty());
+    }
+}
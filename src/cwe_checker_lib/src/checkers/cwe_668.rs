@@ -0,0 +1,750 @@
+//! This module implements a check for CWE-668: Exposure of Resource to Wrong Sphere,
+//! in the form of sensitive data written to files that may be readable by other users.
+//!
+//! Files created by `fopen` get the permissions 0666 and files created by `open`
+//! get the permissions given in their mode parameter, both restricted by the umask of the process.
+//! With the common default umask 022 such files are readable by every user of the system.
+//! If a program writes keys, passwords or other credentials to such a file,
+//! other users can read them.
+//!
+//! See <https://cwe.mitre.org/data/definitions/668.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! For each call to a file creation function we use the results of the pointer inference analysis
+//! to determine whether the call creates a file and with which permissions:
+//! - For `fopen`-like functions the file is created if the mode string is a constant starting with `w` or `a`.
+//!   The permissions of such files are always 0666.
+//! - For `open`-like functions the file is created if the create flag is set in the flags parameter
+//!   and the permissions are given by the mode parameter.
+//!
+//! If the permissions of a created file contain one of the forbidden permissions,
+//! the returned file handle is tracked by a taint analysis to find all calls to write functions
+//! that write to the file.
+//! A second taint analysis tracks the sensitive data returned by the configured sources
+//! to find all calls to write functions whose data parameters contain the sensitive data
+//! (or point to it).
+//! A warning is generated for each write call reached by both a file handle and sensitive data.
+//!
+//! If the program calls `umask` with a constant mask containing all forbidden permissions,
+//! the permissions of created files do not depend on the default umask and no warnings are generated.
+//!
+//! ### Symbols configurable in config.json
+//!
+//! - `sensitive_sources`: Functions returning sensitive data.
+//!   For each function it is configured whether the data is returned (`ReturnValue`)
+//!   or written to the memory pointed to by the parameter with the given index (`{"ParameterTarget": index}`).
+//! - `fopen_functions`: `fopen`-like functions together with the index of their mode string parameter.
+//! - `open_functions`: `open`-like functions together with the indices of their flags and mode parameters.
+//!   Functions without a flags parameter (like `creat`) always create the file.
+//! - `write_functions`: Functions writing to a file together with the indices of their
+//!   file handle parameter and of the parameters containing the written data.
+//! - `umask_functions`: Functions setting the umask of the process.
+//! - `create_flag`: The value of the create flag of `open` as an octal number.
+//! - `forbidden_permissions`: The permission bits that files containing sensitive data must not grant.
+//!
+//! ## False Positives
+//!
+//! - The umask of the process may be set to a restrictive value by the parent process
+//!   or by a `umask` call with a non-constant mask.
+//! - The file may be created in a directory that is not accessible to other users.
+//! - The permissions may be restricted later, e.g. with `fchmod`, before sensitive data is written.
+//!
+//! ## False Negatives
+//!
+//! - The taint analyses are intraprocedural and limited to a fixed number of steps per source call.
+//!   Sensitive data or file handles passed to other functions of the binary are not tracked.
+//! - Calls where the mode string, the flags or the mode are not known constants are not checked.
+//! - Sensitive data not returned by one of the configured sources is not found.
+//! - Files created with `open` and written to through a `FILE` stream obtained by `fdopen` are not recognized.
+
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, HasCfg};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{
+    Data as PiData, PointerInference as PointerInferenceComputation,
+};
+use crate::analysis::taint::summary::{TaintFlowTarget, TaintSummaries};
+use crate::analysis::taint::{state::State as TaState, Taint, TaintAnalysis};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::cancellation::CancellationToken;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::{get_callsites, get_constant_parameter, get_symbol_map};
+use crate::CweModule;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE668",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The description of an `open`-like function in the configuration file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+struct OpenFunctionConfig {
+    /// The name of the function.
+    symbol: String,
+    /// The index of the parameter containing the flags.
+    /// If the function has no flags parameter, it always creates the file.
+    #[serde(default)]
+    flags_parameter: Option<usize>,
+    /// The index of the parameter containing the mode.
+    mode_parameter: usize,
+}
+
+/// The parameters of a function writing to a file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+struct WriteFunctionConfig {
+    /// The index of the parameter containing the file handle.
+    handle_parameter: usize,
+    /// The indices of the parameters containing the written data.
+    data_parameters: Vec<usize>,
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions returning sensitive data, together with the location that the data is returned in.
+    sensitive_sources: BTreeMap<String, TaintFlowTarget>,
+    /// `fopen`-like functions together with the index of their mode string parameter.
+    fopen_functions: BTreeMap<String, usize>,
+    /// `open`-like functions.
+    open_functions: Vec<OpenFunctionConfig>,
+    /// Functions writing to files.
+    write_functions: BTreeMap<String, WriteFunctionConfig>,
+    /// Functions setting the umask of the process.
+    umask_functions: Vec<String>,
+    /// The value of the create flag of `open`-like functions as an octal number.
+    create_flag: String,
+    /// The permission bits that files containing sensitive data must not grant as an octal number.
+    forbidden_permissions: String,
+}
+
+/// Parse an octal number with an optional `0o` prefix.
+fn parse_octal_string(value: &str) -> Result<u64, Error> {
+    u64::from_str_radix(value.trim_start_matches("0o"), 8)
+        .map_err(|_| anyhow!("Invalid octal number: {value}"))
+}
+
+/// The permissions of files created by `fopen`-like functions before the umask is applied.
+const FOPEN_PERMISSIONS: u64 = 0o666;
+
+/// The permission bits of a mode value.
+const PERMISSION_BITS: u64 = 0o777;
+
+/// A call creating a file with permissions that grant some of the forbidden permissions.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct FileCreation {
+    /// The TID of the call.
+    call_tid: Tid,
+    /// The name of the called function.
+    function: String,
+    /// The permissions of the created file before the umask is applied.
+    permissions: u64,
+}
+
+/// A call to a source of sensitive data.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+struct SensitiveSource {
+    /// The TID of the call.
+    call_tid: Tid,
+    /// The name of the called function.
+    function: String,
+}
+
+/// The value tracked by a taint analysis.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum TrackedValue {
+    /// The file handle returned by a file creation function.
+    FileHandle,
+    /// Sensitive data returned by a source function.
+    SensitiveData,
+}
+
+/// Run the CWE check.
+///
+/// We search for calls to write functions that write sensitive data to a file
+/// that was created with permissions granting some of the forbidden permissions.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE668.");
+    let create_flag = parse_octal_string(&config.create_flag)
+        .expect("Invalid create flag inside config.json for CWE668.");
+    let forbidden_permissions = parse_octal_string(&config.forbidden_permissions)
+        .expect("Invalid forbidden permissions inside config.json for CWE668.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+    // The examined sites are the write calls that write sensitive data.
+    let mut site_counts = SiteCounts::default();
+
+    if sets_restrictive_umask(
+        project,
+        pi_result,
+        &config.umask_functions,
+        forbidden_permissions,
+    ) {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let file_creations = find_permissive_file_creations(
+        project,
+        pi_result,
+        &config,
+        create_flag,
+        forbidden_permissions,
+    );
+    if file_creations.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let write_function_names: Vec<String> = config.write_functions.keys().cloned().collect();
+    let write_functions: HashMap<Tid, &WriteFunctionConfig> =
+        get_symbol_map(project, &write_function_names)
+            .into_iter()
+            .map(|(tid, symbol)| (tid, &config.write_functions[&symbol.name]))
+            .collect();
+    if write_functions.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let source_names: Vec<String> = config.sensitive_sources.keys().cloned().collect();
+    let source_map = get_symbol_map(project, &source_names);
+
+    let graph = analysis_results.control_flow_graph;
+    let (write_sender, write_receiver) = crossbeam_channel::unbounded();
+    let mut context = Context {
+        project,
+        pi_result,
+        control_flow_graph: graph,
+        write_functions: &write_functions,
+        tracked_value: TrackedValue::FileHandle,
+        taint_summaries: analysis_results.taint_summaries,
+        write_collector: write_sender,
+    };
+    let mut written_files: HashMap<Tid, Vec<&FileCreation>> = HashMap::new();
+    let mut written_sensitive_data: HashMap<Tid, BTreeSet<SensitiveSource>> = HashMap::new();
+    for (jmp, return_node) in get_extern_calls(graph) {
+        if let Some(file_creation) = file_creations
+            .iter()
+            .find(|creation| creation.call_tid == jmp.tid)
+        {
+            let symbol = &project.program.term.extern_symbols[get_call_target(jmp)];
+            let start_state = TaState::new_return(symbol, pi_result, return_node);
+            context.tracked_value = TrackedValue::FileHandle;
+            run_taint_analysis(
+                &context,
+                return_node,
+                start_state,
+                analysis_results.cancellation_token,
+            );
+            for write_call in write_receiver.try_iter() {
+                written_files
+                    .entry(write_call)
+                    .or_default()
+                    .push(file_creation);
+            }
+        } else if let Some(symbol) = source_map.get(get_call_target(jmp)) {
+            let start_state = get_source_state(
+                project,
+                pi_result,
+                &jmp.tid,
+                symbol,
+                &config.sensitive_sources[&symbol.name],
+            );
+            context.tracked_value = TrackedValue::SensitiveData;
+            run_taint_analysis(
+                &context,
+                return_node,
+                start_state,
+                analysis_results.cancellation_token,
+            );
+            let source = SensitiveSource {
+                call_tid: jmp.tid.clone(),
+                function: symbol.name.clone(),
+            };
+            for write_call in write_receiver.try_iter() {
+                written_sensitive_data
+                    .entry(write_call)
+                    .or_default()
+                    .insert(source.clone());
+            }
+        }
+    }
+
+    let mut cwe_warnings = BTreeMap::new();
+    for (write_call, sources) in written_sensitive_data {
+        let Some(files) = written_files.get(&write_call) else {
+            site_counts.add_safe();
+            continue;
+        };
+        site_counts.add_flagged();
+        let write_function = get_write_function_name(project, &write_call);
+        let warning = generate_cwe_warning(&write_call, &write_function, files, &sources);
+        cwe_warnings.insert(write_call, warning);
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings.into_values().collect())
+}
+
+/// Returns `true` if the program calls a umask function with a constant mask containing all forbidden permissions.
+fn sets_restrictive_umask(
+    project: &Project,
+    pi_result: &PointerInferenceComputation,
+    umask_functions: &[String],
+    forbidden_permissions: u64,
+) -> bool {
+    let umask_map = get_symbol_map(project, umask_functions);
+    project.program.term.subs.values().any(|sub| {
+        get_callsites(sub, &umask_map)
+            .into_iter()
+            .any(|(_, jmp, symbol)| {
+                get_constant_parameter(project, pi_result, &jmp.tid, symbol, 0)
+                    .is_some_and(|mask| mask & forbidden_permissions == forbidden_permissions)
+            })
+    })
+}
+
+/// Find all calls that create a file with permissions granting some of the forbidden permissions.
+fn find_permissive_file_creations(
+    project: &Project,
+    pi_result: &PointerInferenceComputation,
+    config: &Config,
+    create_flag: u64,
+    forbidden_permissions: u64,
+) -> Vec<FileCreation> {
+    let fopen_names: Vec<String> = config.fopen_functions.keys().cloned().collect();
+    let fopen_map = get_symbol_map(project, &fopen_names);
+    let open_names: Vec<String> = config
+        .open_functions
+        .iter()
+        .map(|function| function.symbol.clone())
+        .collect();
+    let open_map = get_symbol_map(project, &open_names);
+
+    let mut file_creations = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, symbol) in get_callsites(sub, &fopen_map) {
+            let mode_parameter = config.fopen_functions[&symbol.name];
+            if creates_file_by_mode_string(project, pi_result, &jmp.tid, symbol, mode_parameter) {
+                file_creations.push(FileCreation {
+                    call_tid: jmp.tid.clone(),
+                    function: symbol.name.clone(),
+                    permissions: FOPEN_PERMISSIONS,
+                });
+            }
+        }
+        for (_, jmp, symbol) in get_callsites(sub, &open_map) {
+            let Some(function) = config
+                .open_functions
+                .iter()
+                .find(|function| function.symbol == symbol.name)
+            else {
+                continue;
+            };
+            if let Some(flags_parameter) = function.flags_parameter {
+                let Some(flags) =
+                    get_constant_parameter(project, pi_result, &jmp.tid, symbol, flags_parameter)
+                else {
+                    continue;
+                };
+                if flags & create_flag == 0 {
+                    continue;
+                }
+            }
+            let Some(mode) = get_constant_parameter(
+                project,
+                pi_result,
+                &jmp.tid,
+                symbol,
+                function.mode_parameter,
+            ) else {
+                continue;
+            };
+            file_creations.push(FileCreation {
+                call_tid: jmp.tid.clone(),
+                function: symbol.name.clone(),
+                permissions: mode & PERMISSION_BITS,
+            });
+        }
+    }
+    file_creations.retain(|creation| creation.permissions & forbidden_permissions != 0);
+    file_creations
+}
+
+/// Returns `true` if the mode string parameter of an `fopen`-like function is a constant string
+/// that creates the file if it does not exist, i.e. if it starts with `w` or `a`.
+fn creates_file_by_mode_string(
+    project: &Project,
+    pi_result: &PointerInferenceComputation,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    mode_parameter: usize,
+) -> bool {
+    let Some(address) =
+        get_constant_parameter(project, pi_result, call_tid, symbol, mode_parameter)
+    else {
+        return false;
+    };
+    project
+        .runtime_memory_image
+        .read_string_until_null_terminator(&Bitvector::from_u64(address))
+        .is_ok_and(|mode| mode.starts_with('w') || mode.starts_with('a'))
+}
+
+/// Get all calls to extern functions in the control flow graph together with the nodes that the calls return to.
+fn get_extern_calls<'a>(graph: &'a Graph) -> Vec<(&'a Term<Jmp>, NodeIndex)> {
+    graph
+        .edge_references()
+        .filter_map(|edge| match edge.weight() {
+            Edge::ExternCallStub(jmp) if matches!(jmp.term, Jmp::Call { .. }) => {
+                Some((*jmp, edge.target()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Get the target of a direct call.
+fn get_call_target(jmp: &Term<Jmp>) -> &Tid {
+    match &jmp.term {
+        Jmp::Call { target, .. } => target,
+        _ => panic!("Expected a direct call."),
+    }
+}
+
+/// Get the name of the function called by the call with the given TID.
+fn get_write_function_name(project: &Project, call_tid: &Tid) -> String {
+    project
+        .program
+        .term
+        .subs
+        .values()
+        .flat_map(|sub| sub.term.blocks.iter())
+        .flat_map(|blk| blk.term.jmps.iter())
+        .find(|jmp| jmp.tid == *call_tid)
+        .and_then(|jmp| {
+            project
+                .program
+                .term
+                .extern_symbols
+                .get(get_call_target(jmp))
+        })
+        .map(|symbol| symbol.name.clone())
+        .unwrap_or_default()
+}
+
+/// Run the taint analysis with the given start state at the given node.
+fn run_taint_analysis(
+    context: &Context,
+    start_node: NodeIndex,
+    start_state: TaState,
+    cancellation_token: Option<&CancellationToken>,
+) {
+    if start_state.is_empty() {
+        return;
+    }
+    let mut computation = create_computation(context.clone(), None);
+    computation.set_cancellation_token(cancellation_token);
+    computation.set_node_value(start_node, NodeValue::Value(start_state));
+    // FIXME: This number should be in the config.
+    computation.compute_with_max_steps(100);
+}
+
+/// Get the taint state after a call to a source of sensitive data,
+/// where the location containing the data is tainted.
+fn get_source_state(
+    project: &Project,
+    pi_result: &PointerInferenceComputation,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    target: &TaintFlowTarget,
+) -> TaState {
+    let calling_convention = project.get_calling_convention(symbol);
+    let mut state = TaState::new_empty();
+    match target {
+        TaintFlowTarget::ReturnValue => {
+            for register in calling_convention.integer_return_register.iter() {
+                state.set_register_taint(register, Taint::Tainted(register.size));
+            }
+        }
+        TaintFlowTarget::ParameterTarget(index) => {
+            if let Some(param) = project.get_parameter_arg(symbol, *index) {
+                if let Some(address) = pi_result.eval_parameter_arg_at_call(call_tid, &param) {
+                    state.save_taint_to_memory(&address, Taint::Tainted(param.bytesize()));
+                }
+            }
+        }
+    }
+    state
+}
+
+/// Generate the CWE warning for a write call writing sensitive data to a file with permissive permissions.
+fn generate_cwe_warning(
+    write_call: &Tid,
+    write_function: &str,
+    files: &[&FileCreation],
+    sources: &BTreeSet<SensitiveSource>,
+) -> CweWarning {
+    let file_descriptions: Vec<String> = files
+        .iter()
+        .map(|file| {
+            format!(
+                "{} at {} (mode {:#o})",
+                file.function, file.call_tid.address, file.permissions
+            )
+        })
+        .collect();
+    let source_descriptions: Vec<String> = sources
+        .iter()
+        .map(|source| format!("{} at {}", source.function, source.call_tid.address))
+        .collect();
+    let mut tids = vec![format!("{write_call}")];
+    tids.extend(files.iter().map(|file| format!("{}", file.call_tid)));
+    tids.extend(sources.iter().map(|source| format!("{}", source.call_tid)));
+    let mut addresses = vec![write_call.address.clone()];
+    addresses.extend(files.iter().map(|file| file.call_tid.address.clone()));
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Exposure of Resource to Wrong Sphere) Sensitive data from {} is written by {} at {} to a file created by {} \
+            whose permissions depend on the umask. Create the file with an explicit restrictive mode like 0600.",
+            source_descriptions.join(", "),
+            write_function,
+            write_call.address,
+            file_descriptions.join(", ")
+        ),
+    )
+    .tids(tids)
+    .addresses(addresses)
+    .symbols(vec![write_function.to_string()])
+}
+
+/// The Context struct for the taint analyses.
+#[derive(Clone)]
+struct Context<'a> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInferenceComputation<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// Maps the TIDs of the write functions to their parameters.
+    write_functions: &'a HashMap<Tid, &'a WriteFunctionConfig>,
+    /// The value tracked by the analysis.
+    tracked_value: TrackedValue,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where calls to write functions reached by the tracked value are sent to.
+    write_collector: crossbeam_channel::Sender<Tid>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
+    /// Report calls to write functions whose file handle or data parameters are tainted.
+    ///
+    /// In contrast to most other checks the analysis continues after the write call,
+    /// since the same file handle or the same data may be used by several write calls.
+    fn update_extern_call(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        project: &Project,
+        extern_symbol: &ExternSymbol,
+    ) -> Option<TaState> {
+        if let Some(write_function) = self.write_functions.get(&extern_symbol.tid) {
+            let parameters = match self.tracked_value {
+                TrackedValue::FileHandle => std::slice::from_ref(&write_function.handle_parameter),
+                TrackedValue::SensitiveData => &write_function.data_parameters[..],
+            };
+            if parameters
+                .iter()
+                .any(|index| self.is_parameter_tainted(state, call, extern_symbol, *index))
+            {
+                let _ = self.write_collector.send(call.tid.clone());
+            }
+        }
+
+        let calling_conv = project.get_calling_convention(extern_symbol);
+        let mut new_state = state.clone();
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        if let Some(summary) = self
+            .taint_summaries
+            .and_then(|summaries| summaries.get(&extern_symbol.name))
+        {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.pi_result,
+                &call.tid,
+                calling_conv,
+            );
+        }
+        Some(new_state)
+    }
+}
+
+impl<'a> Context<'a> {
+    /// Check whether the parameter with the given index of the call is tainted.
+    ///
+    /// For sensitive data the parameter is also considered tainted if it points to tainted memory.
+    fn is_parameter_tainted(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        extern_symbol: &ExternSymbol,
+        index: usize,
+    ) -> bool {
+        let Some(arg) = self.project.get_parameter_arg(extern_symbol, index) else {
+            return false;
+        };
+        let value_is_tainted = match &arg {
+            Arg::Register { expr, .. } => state.eval(expr).is_tainted(),
+            Arg::Stack { address, size, .. } => self
+                .pi_result
+                .eval_at_jmp(&call.tid, address)
+                .is_some_and(|address| state.load_taint_from_memory(&address, *size).is_tainted()),
+        };
+        value_is_tainted
+            || (self.tracked_value == TrackedValue::SensitiveData
+                && self
+                    .pi_result
+                    .eval_parameter_arg_at_call(&call.tid, &arg)
+                    .is_some_and(|value| state.check_if_address_points_to_taint(value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::def;
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "sensitive_sources": {"getpass": "ReturnValue"},
+            "fopen_functions": {"fopen": 1},
+            "open_functions": [{"symbol": "open", "flags_parameter": 1, "mode_parameter": 2}],
+            "write_functions": {"fputs": {"handle_parameter": 1, "data_parameters": [0]}},
+            "umask_functions": ["umask"],
+            "create_flag": "0o100",
+            "forbidden_permissions": "0o077"
+        })
+    }
+
+    /// Create a project with a function opening a file with the given mode string,
+    /// reading a password with `getpass` and writing it to the file with `fputs`.
+    /// The given block is executed first.
+    fn mock_project(mode: &str, blk_start: Option<Term<Blk>>) -> Project {
+        let mut blk_open = Blk::mock_with_tid("blk_open");
+        blk_open.term.defs = vec![
+            def!["path: RDI:8 = 0x9000:8"],
+            def!["mode: RSI:8 = 0x9010:8"],
+        ];
+        blk_open
+            .term
+            .jmps
+            .push(Jmp::call("call_fopen", "fopen", Some("blk_read")));
+        let mut blk_read = Blk::mock_with_tid("blk_read");
+        blk_read.term.defs = vec![def!["save_handle: RBX:8 = RAX:8"]];
+        blk_read
+            .term
+            .jmps
+            .push(Jmp::call("call_getpass", "getpass", Some("blk_write")));
+        let mut blk_write = Blk::mock_with_tid("blk_write");
+        blk_write.term.defs = vec![def!["data: RDI:8 = RAX:8"], def!["handle: RSI:8 = RBX:8"]];
+        blk_write
+            .term
+            .jmps
+            .push(Jmp::call("call_fputs", "fputs", Some("blk_end")));
+        let blk_end = Blk::mock_with_tid("blk_end");
+
+        let mut sub = Sub::mock("store_password");
+        sub.term.blocks = blk_start.into_iter().collect();
+        sub.term
+            .blocks
+            .extend([blk_open, blk_read, blk_write, blk_end]);
+        let mut project = mock_x64_project(&["fopen", "getpass", "fputs", "umask"], vec![sub]);
+        let mut bytes = b"/tmp/key\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        bytes.extend(mode.as_bytes());
+        bytes.push(0);
+        project
+            .runtime_memory_image
+            .memory_segments
+            .push(MemorySegment {
+                bytes,
+                base_address: 0x9000,
+                read_flag: true,
+                write_flag: false,
+                execute_flag: false,
+            });
+        project
+    }
+
+    #[test]
+    fn password_written_to_new_file() {
+        let warnings = run_check(check_cwe, &mock_project("w", None), &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec![
+                "call_fputs".to_string(),
+                "call_fopen".to_string(),
+                "call_getpass".to_string()
+            ]
+        );
+        assert!(warnings[0].description.contains("mode 0o666"));
+    }
+
+    #[test]
+    fn file_opened_for_reading() {
+        assert!(run_check(check_cwe, &mock_project("r+", None), &mock_config()).is_empty());
+    }
+
+    #[test]
+    fn restrictive_umask() {
+        let mut blk_umask = Blk::mock_with_tid("blk_umask");
+        blk_umask.term.defs = vec![def!["mask: RDI:8 = 0x3f:8"]];
+        blk_umask
+            .term
+            .jmps
+            .push(Jmp::call("call_umask", "umask", Some("blk_open")));
+        assert!(run_check(
+            check_cwe,
+            &mock_project("w", Some(blk_umask)),
+            &mock_config()
+        )
+        .is_empty());
+    }
+}
@@ -25,6 +25,7 @@ use crate::{
     intermediate_representation::{ExternSymbol, Program, Sub, Term, Tid},
     utils::{
         log::{CweWarning, LogMessage},
+        metrics::SiteCounts,
         symbol_utils::get_calls_to_symbols,
     },
 };
@@ -116,6 +117,12 @@ pub fn check_cwe(
     let external_symbols: &BTreeMap<Tid, ExternSymbol> = &prog.term.extern_symbols;
     let dangerous_symbols = resolve_symbols(external_symbols, &config.symbols);
     let dangerous_calls = get_calls(subfunctions, &dangerous_symbols);
+    // Every call to a dangerous function is flagged.
+    let mut site_counts = SiteCounts::default();
+    for _call in dangerous_calls.iter() {
+        site_counts.add_flagged();
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (vec![], generate_cwe_warnings(dangerous_calls))
 }
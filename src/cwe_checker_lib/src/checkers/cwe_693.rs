@@ -0,0 +1,403 @@
+//! This module implements a check for CWE-693: Protection Mechanism Failure.
+//!
+//! Compiler mitigations like stack canaries can be disabled for single source files
+//! or functions, e.g. through build system flags or function attributes.
+//! If this happens for code that parses untrusted input,
+//! the binary looks hardened as a whole while exactly the most exposed functions are unprotected.
+//!
+//! See <https://cwe.mitre.org/data/definitions/693.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! A function is considered to have a stack canary if it calls one of the canary failure functions
+//! (e.g. `__stack_chk_fail`) in its epilogue
+//! or if the stack canary normalization pass removed a canary check from it.
+//! Using the results of the [Pointer Inference analysis](`crate::analysis::pointer_inference`)
+//! we find all functions that write into buffers on their own stack frame
+//! through calls to input functions (e.g. `recv`) or copy functions (e.g. `memcpy`).
+//! Compilers protect such functions with stack canaries if canaries are enabled.
+//! If at least one of these functions has a stack canary, canaries were enabled for parts of the binary.
+//! Then we report the unprotected functions on the attack surface:
+//! - Functions that read input directly into a stack buffer are reported with high priority.
+//! - Functions that copy data into a stack buffer and are reachable in the call graph
+//!   from a function calling an input function are reported with medium priority.
+//!
+//! ## False Positives
+//!
+//! - Compilers may omit the canary for functions with small buffers,
+//!   e.g. if only `-fstack-protector` instead of `-fstack-protector-strong` was used.
+//! - Functions of statically linked libraries may be compiled with different flags than the rest of the binary.
+//! - Reachability in the call graph does not imply that the copied data is controlled by an attacker.
+//!
+//! ## False Negatives
+//!
+//! - Canaries checked by inlined code without a call to a canary failure function are not detected.
+//!   Such functions are considered protected only if they also call a canary failure function.
+//! - Functions only reachable through indirect calls from input handling functions
+//!   are not considered to be on the attack surface.
+//! - Binaries without any stack canaries are not reported.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The canary failure functions as well as the input and copy functions
+//! together with the index of their destination buffer parameter are configurable in config.json.
+
+use crate::abstract_domain::AbstractIdentifier;
+use crate::analysis::callgraph::get_program_callgraph;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::visit::Dfs;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE693",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions called if a stack canary check fails.
+    canary_failure_symbols: Vec<String>,
+    /// Functions reading untrusted input,
+    /// mapped to the index of the parameter containing the destination buffer.
+    input_symbols: BTreeMap<String, usize>,
+    /// Functions copying data,
+    /// mapped to the index of the parameter containing the destination buffer.
+    copy_symbols: BTreeMap<String, usize>,
+}
+
+/// The priority of a finding.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Priority {
+    /// The function reads input directly into a stack buffer.
+    High,
+    /// The function copies data into a stack buffer and is reachable from input handling code.
+    Medium,
+}
+
+/// A call writing into a buffer on the stack frame of the calling function.
+#[derive(Debug, Clone)]
+struct StackBufferWrite<'a> {
+    /// The TID of the call.
+    call_tid: Tid,
+    /// The called function.
+    symbol: &'a ExternSymbol,
+    /// Whether the called function reads untrusted input.
+    is_input: bool,
+}
+
+/// Run the check.
+///
+/// First all functions writing into their own stack frame through input or copy functions are collected.
+/// Then the functions among them without stack canaries on the attack surface are reported.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE693.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let canary_symbols = get_symbol_map(project, &config.canary_failure_symbols);
+    // The examined sites are the functions writing to stack buffers.
+    let mut site_counts = SiteCounts::default();
+    if canary_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let input_symbols = get_buffer_symbol_map(project, &config.input_symbols);
+    let copy_symbols = get_buffer_symbol_map(project, &config.copy_symbols);
+
+    let mut buffer_writes: BTreeMap<Tid, Vec<StackBufferWrite>> = BTreeMap::new();
+    let mut input_callers = BTreeSet::new();
+    for sub in project.program.term.subs.values() {
+        let stack_id =
+            AbstractIdentifier::from_var(sub.tid.clone(), &project.stack_pointer_register);
+        for blk in sub.term.blocks.iter() {
+            for jmp in blk.term.jmps.iter() {
+                let Jmp::Call { target, .. } = &jmp.term else {
+                    continue;
+                };
+                let (is_input, (symbol, param_index)) = match input_symbols.get(target) {
+                    Some(input_symbol) => {
+                        input_callers.insert(sub.tid.clone());
+                        (true, input_symbol)
+                    }
+                    None => match copy_symbols.get(target) {
+                        Some(copy_symbol) => (false, copy_symbol),
+                        None => continue,
+                    },
+                };
+                let Some(buffer) = symbol
+                    .parameters
+                    .get(*param_index)
+                    .and_then(|param| pi_result.eval_parameter_arg_at_call(&jmp.tid, param))
+                else {
+                    continue;
+                };
+                if buffer.get_relative_values().contains_key(&stack_id) {
+                    buffer_writes
+                        .entry(sub.tid.clone())
+                        .or_default()
+                        .push(StackBufferWrite {
+                            call_tid: jmp.tid.clone(),
+                            symbol,
+                            is_input,
+                        });
+                }
+            }
+        }
+    }
+
+    let cwe_warnings =
+        find_unprotected_functions(project, &buffer_writes, &canary_symbols, &input_callers);
+    let flagged_tids: BTreeSet<&String> = cwe_warnings
+        .iter()
+        .flat_map(|warning| warning.tids.iter())
+        .collect();
+    for writes in buffer_writes.values() {
+        if writes
+            .iter()
+            .any(|write| flagged_tids.contains(&format!("{}", write.call_tid)))
+        {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    (Vec::new(), cwe_warnings)
+}
+
+/// Map the TIDs of the given symbols to the symbols and the index of their destination buffer parameter.
+fn get_buffer_symbol_map<'a>(
+    project: &'a Project,
+    symbols: &BTreeMap<String, usize>,
+) -> HashMap<Tid, (&'a ExternSymbol, usize)> {
+    let names: Vec<String> = symbols.keys().cloned().collect();
+    get_symbol_map(project, &names)
+        .into_iter()
+        .map(|(tid, symbol)| (tid, (symbol, symbols[&symbol.name])))
+        .collect()
+}
+
+/// Check whether the given function calls one of the given symbols.
+fn sub_calls_any_symbol(sub: &Term<Sub>, symbols: &HashMap<Tid, &ExternSymbol>) -> bool {
+    sub.term.blocks.iter().any(|blk| {
+        blk.term.jmps.iter().any(|jmp| match &jmp.term {
+            Jmp::Call { target, .. } => symbols.contains_key(target),
+            _ => false,
+        })
+    })
+}
+
+/// Compute all functions reachable in the call graph from the given functions (including themselves).
+fn compute_reachable_subs(project: &Project, start_subs: &BTreeSet<Tid>) -> BTreeSet<Tid> {
+    let callgraph = get_program_callgraph(&project.program);
+    let mut reachable_subs = BTreeSet::new();
+    for start in callgraph
+        .node_indices()
+        .filter(|node| start_subs.contains(&callgraph[*node]))
+    {
+        let mut dfs = Dfs::new(&callgraph, start);
+        while let Some(node) = dfs.next(&callgraph) {
+            reachable_subs.insert(callgraph[node].clone());
+        }
+    }
+    reachable_subs
+}
+
+/// Generate warnings for all functions on the attack surface writing into stack buffers without a stack canary.
+///
+/// No warnings are generated if none of the functions writing into stack buffers has a stack canary.
+/// The warnings are ordered by priority.
+fn find_unprotected_functions(
+    project: &Project,
+    buffer_writes: &BTreeMap<Tid, Vec<StackBufferWrite>>,
+    canary_symbols: &HashMap<Tid, &ExternSymbol>,
+    input_callers: &BTreeSet<Tid>,
+) -> Vec<CweWarning> {
+    let subs = &project.program.term.subs;
+    let unprotected_subs: Vec<&Term<Sub>> = buffer_writes
+        .keys()
+        .map(|sub_tid| &subs[sub_tid])
+        .filter(|sub| {
+            !sub_calls_any_symbol(sub, canary_symbols)
+                && !project
+                    .program
+                    .term
+                    .stack_canary_checks
+                    .contains_key(&sub.tid)
+        })
+        .collect();
+    let num_protected = buffer_writes.len() - unprotected_subs.len();
+    if num_protected == 0 {
+        return Vec::new();
+    }
+    let attack_surface = compute_reachable_subs(project, input_callers);
+
+    let mut findings = Vec::new();
+    for sub in unprotected_subs {
+        let writes = &buffer_writes[&sub.tid];
+        let priority = if writes.iter().any(|write| write.is_input) {
+            Priority::High
+        } else if attack_surface.contains(&sub.tid) {
+            Priority::Medium
+        } else {
+            continue;
+        };
+        findings.push((priority, sub, writes));
+    }
+    findings.sort_by(|(priority1, sub1, _), (priority2, sub2, _)| {
+        (priority1, &sub1.tid).cmp(&(priority2, &sub2.tid))
+    });
+    findings
+        .into_iter()
+        .map(|(priority, sub, writes)| {
+            generate_cwe_warning(sub, writes, priority, num_protected, buffer_writes.len())
+        })
+        .collect()
+}
+
+/// Generate the CWE warning for a function without stack canary.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    writes: &[StackBufferWrite],
+    priority: Priority,
+    num_protected: usize,
+    num_functions: usize,
+) -> CweWarning {
+    let symbols: BTreeSet<&str> = writes
+        .iter()
+        .map(|write| write.symbol.name.as_str())
+        .collect();
+    let reason = match priority {
+        Priority::High => "reads input into a stack buffer",
+        Priority::Medium => {
+            "is reachable from input handling code and copies data into a stack buffer"
+        }
+    };
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Missing Stack Canary) Function {} at {} {} ({}) without stack canary, while {} of {} functions with stack buffers are protected",
+            sub.term.name,
+            sub.tid.address,
+            reason,
+            symbols.into_iter().collect::<Vec<_>>().join(", "),
+            num_protected,
+            num_functions
+        ),
+    )
+    .tids(writes.iter().map(|write| format!("{}", write.call_tid)).collect())
+    .addresses(
+        writes
+            .iter()
+            .map(|write| write.call_tid.address.clone())
+            .collect(),
+    )
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec![
+        "priority".to_string(),
+        format!("{priority:?}").to_lowercase(),
+    ]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::mock_x64_project;
+
+    /// Create a project with the functions
+    /// - `protected`: reads input into a stack buffer and has a stack canary,
+    /// - `reader`: reads input into a stack buffer and calls `parser`,
+    /// - `parser`: copies data into a stack buffer,
+    /// - `unrelated`: copies data into a stack buffer, but is not reachable from input handling code.
+    ///
+    /// Also returns the stack buffer writes of the functions.
+    fn mock_setup() -> (Project, BTreeMap<Tid, Vec<StackBufferWrite<'static>>>) {
+        let mut subs = Vec::new();
+        for (sub_name, calls) in [
+            ("protected", vec!["recv", "__stack_chk_fail"]),
+            ("reader", vec!["recv", "parser"]),
+            ("parser", vec!["memcpy"]),
+            ("unrelated", vec!["memcpy"]),
+        ] {
+            let mut sub = Sub::mock(sub_name);
+            for target in calls {
+                let mut blk = Blk::mock_with_tid(&format!("{sub_name}_{target}"));
+                blk.term.jmps.push(Jmp::call(
+                    &format!("{sub_name}_call_{target}"),
+                    target,
+                    None,
+                ));
+                sub.term.blocks.push(blk);
+            }
+            subs.push(sub);
+        }
+        let project = mock_x64_project(&["__stack_chk_fail", "recv", "memcpy"], subs);
+
+        let recv: &'static ExternSymbol = Box::leak(Box::new(ExternSymbol::mock_x64("recv")));
+        let memcpy: &'static ExternSymbol = Box::leak(Box::new(ExternSymbol::mock_x64("memcpy")));
+        let mut buffer_writes = BTreeMap::new();
+        for (sub_name, symbol, is_input) in [
+            ("protected", recv, true),
+            ("reader", recv, true),
+            ("parser", memcpy, false),
+            ("unrelated", memcpy, false),
+        ] {
+            buffer_writes.insert(
+                Tid::new(sub_name),
+                vec![StackBufferWrite {
+                    call_tid: Tid::new(format!("{sub_name}_call_{}", symbol.name)),
+                    symbol,
+                    is_input,
+                }],
+            );
+        }
+        (project, buffer_writes)
+    }
+
+    #[test]
+    fn unprotected_functions() {
+        let (project, buffer_writes) = mock_setup();
+        let canary_symbols = get_symbol_map(&project, &["__stack_chk_fail".to_string()]);
+        let input_callers = BTreeSet::from([Tid::new("protected"), Tid::new("reader")]);
+
+        let warnings =
+            find_unprotected_functions(&project, &buffer_writes, &canary_symbols, &input_callers);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].symbols, vec!["reader".to_string()]);
+        assert_eq!(warnings[0].tids, vec!["reader_call_recv".to_string()]);
+        assert_eq!(
+            warnings[0].other,
+            vec![vec!["priority".to_string(), "high".to_string()]]
+        );
+        assert_eq!(warnings[1].symbols, vec!["parser".to_string()]);
+        assert_eq!(
+            warnings[1].other,
+            vec![vec!["priority".to_string(), "medium".to_string()]]
+        );
+
+        // No warnings if no function is protected.
+        let mut buffer_writes = buffer_writes;
+        buffer_writes.remove(&Tid::new("protected"));
+        assert!(find_unprotected_functions(
+            &project,
+            &buffer_writes,
+            &canary_symbols,
+            &input_callers
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,511 @@
+//! This module implements a check for CWE-733: Compiler Optimization Removal or Modification of Security-critical Code,
+//! in the form of polling loops on memory-mapped registers that lack the `volatile` qualifier.
+//!
+//! Firmware often waits for a hardware event by polling a status register of a peripheral,
+//! e.g. `while (!(UART->SR & TX_READY));`.
+//! If the register is not accessed through a `volatile` pointer,
+//! the compiler may assume that its value cannot change inside of the loop
+//! and hoist the load out of the loop.
+//! The resulting loop either exits immediately or spins forever,
+//! which violates the expected behavior of the code (see also CWE-440)
+//! and can, e.g., skip waiting for a cryptographic accelerator to finish its computation.
+//!
+//! See <https://cwe.mitre.org/data/definitions/733.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! This check is only executed for bare metal binaries.
+//! For other binaries it has to be selected explicitly via the `--partial` command line option.
+//!
+//! Loops are the non-trivial strongly connected components of the control flow graph of a function.
+//! For each loop without calls and with a single entry block we check whether
+//! - all conditions of conditional jumps leaving the loop are loop-invariant,
+//!   i.e. they only depend on variables that are not changed inside the loop
+//!   (directly or through assignments that are loop-invariant themselves),
+//! - and one of these variables is set outside of the loop by a load from a memory-mapped address.
+//!
+//! The load is searched for in the blocks dominating the loop entry,
+//! i.e. in the chain of unique predecessors of the loop entry.
+//! Memory-mapped addresses are classified by the [Pointer Inference analysis](`crate::analysis::pointer_inference`):
+//! For bare metal binaries all addresses outside of the flash and RAM memory regions
+//! given in the bare metal configuration are memory-mapped peripherals.
+//!
+//! One warning is generated per loop.
+//!
+//! ## False Positives
+//!
+//! - Busy-wait loops on a value that is deliberately read only once are flagged.
+//!   Such loops are usually bugs anyway.
+//! - Addresses outside of the configured memory regions are not always peripherals,
+//!   e.g. if the bare metal configuration does not contain all RAM regions of the chip.
+//!
+//! ## False Negatives
+//!
+//! - If the compiler transforms the hoisted polling loop into a check before an unconditional endless loop,
+//!   the result is indistinguishable from an intentional halt loop and thus not flagged.
+//! - Loops with additional exit conditions that change inside the loop, e.g. timeouts, are not flagged.
+//! - Loops containing calls are ignored, since the callee may read the register again.
+//! - The load is only searched for in the blocks dominating the loop entry up to a fixed depth.
+//!   Loads on only some paths to the loop are not tracked.
+
+use crate::analysis::pointer_inference::{ObjectKind, PointerInference};
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::CweModule;
+use petgraph::graph::{DiGraph, NodeIndex};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE733",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The maximal number of dominating blocks searched for the hoisted load.
+const MAX_PREDECESSOR_DEPTH: usize = 8;
+
+/// A loop of the control flow graph of a function.
+struct Loop<'a> {
+    /// The blocks of the loop.
+    blocks: BTreeMap<&'a Tid, &'a Term<Blk>>,
+}
+
+impl<'a> Loop<'a> {
+    /// Returns `true` if the loop contains calls or return instructions.
+    fn contains_calls_or_returns(&self) -> bool {
+        self.blocks.values().any(|blk| {
+            blk.term.jmps.iter().any(|jmp| {
+                matches!(
+                    jmp.term,
+                    Jmp::Call { .. } | Jmp::CallInd { .. } | Jmp::CallOther { .. } | Jmp::Return(_)
+                )
+            })
+        })
+    }
+
+    /// Get the conditional jumps that (may) leave the loop.
+    ///
+    /// A conditional jump leaves the loop if its target or another successor of its block is outside of the loop.
+    fn get_exit_branches(&self) -> Vec<&'a Term<Jmp>> {
+        let mut exit_branches = Vec::new();
+        for blk in self.blocks.values() {
+            let leaves_loop = get_intraprocedural_successors(blk)
+                .into_iter()
+                .any(|successor| !self.blocks.contains_key(successor));
+            if !leaves_loop {
+                continue;
+            }
+            exit_branches.extend(
+                blk.term
+                    .jmps
+                    .iter()
+                    .filter(|jmp| matches!(jmp.term, Jmp::CBranch { .. })),
+            );
+        }
+        exit_branches
+    }
+
+    /// Get all variables assigned to inside of the loop
+    /// together with the expressions assigned to them.
+    ///
+    /// Loaded variables are mapped to `None`, since the loaded value may change on each iteration.
+    fn get_assignments(&self) -> HashMap<&'a Variable, Vec<Option<&'a Expression>>> {
+        let mut assignments: HashMap<&Variable, Vec<Option<&Expression>>> = HashMap::new();
+        for blk in self.blocks.values() {
+            for def in blk.term.defs.iter() {
+                match &def.term {
+                    Def::Assign { var, value } => {
+                        assignments.entry(var).or_default().push(Some(value))
+                    }
+                    Def::Load { var, .. } => assignments.entry(var).or_default().push(None),
+                    Def::Store { .. } => (),
+                }
+            }
+        }
+        assignments
+    }
+}
+
+/// Get the intraprocedural successors of a block.
+fn get_intraprocedural_successors(blk: &Term<Blk>) -> Vec<&Tid> {
+    let mut successors = Vec::new();
+    for jmp in blk.term.jmps.iter() {
+        match &jmp.term {
+            Jmp::Branch(target)
+            | Jmp::CBranch { target, .. }
+            | Jmp::Call {
+                return_: Some(target),
+                ..
+            }
+            | Jmp::CallInd {
+                return_: Some(target),
+                ..
+            }
+            | Jmp::CallOther {
+                return_: Some(target),
+                ..
+            } => successors.push(target),
+            Jmp::BranchInd(_) => successors.extend(blk.term.indirect_jmp_targets.iter()),
+            _ => (),
+        }
+    }
+    successors
+}
+
+/// Find all loops of the given function,
+/// i.e. the strongly connected components of its control flow graph that contain a cycle.
+fn find_loops(sub: &Term<Sub>) -> Vec<Loop> {
+    let mut graph: DiGraph<&Term<Blk>, ()> = DiGraph::new();
+    let nodes: HashMap<&Tid, NodeIndex> = sub
+        .term
+        .blocks
+        .iter()
+        .map(|blk| (&blk.tid, graph.add_node(blk)))
+        .collect();
+    for blk in sub.term.blocks.iter() {
+        for successor in get_intraprocedural_successors(blk) {
+            if let Some(successor_node) = nodes.get(successor) {
+                graph.add_edge(nodes[&blk.tid], *successor_node, ());
+            }
+        }
+    }
+    petgraph::algo::kosaraju_scc(&graph)
+        .into_iter()
+        .filter(|component| component.len() > 1 || graph.contains_edge(component[0], component[0]))
+        .map(|component| Loop {
+            blocks: component
+                .into_iter()
+                .map(|node| graph[node])
+                .map(|blk| (&blk.tid, blk))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Compute the variables that are assigned to inside of the loop but are loop-invariant nevertheless,
+/// i.e. all assignments to them only depend on variables that are not changed inside the loop
+/// or are loop-invariant themselves.
+fn compute_invariant_variables<'a>(
+    assignments: &HashMap<&'a Variable, Vec<Option<&'a Expression>>>,
+) -> BTreeSet<&'a Variable> {
+    let mut invariant = BTreeSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (var, values) in assignments.iter() {
+            if invariant.contains(var) {
+                continue;
+            }
+            let is_invariant = values.iter().all(|value| {
+                value.is_some_and(|value| {
+                    value
+                        .input_vars()
+                        .into_iter()
+                        .all(|input| !assignments.contains_key(input) || invariant.contains(input))
+                })
+            });
+            if is_invariant {
+                invariant.insert(*var);
+                changed = true;
+            }
+        }
+    }
+    invariant
+}
+
+/// Get the variables not changed inside of the loop that the given loop-invariant expression depends on.
+fn get_loop_input_variables<'a>(
+    expression: &'a Expression,
+    assignments: &HashMap<&'a Variable, Vec<Option<&'a Expression>>>,
+) -> BTreeSet<&'a Variable> {
+    let mut inputs = BTreeSet::new();
+    let mut visited = BTreeSet::new();
+    let mut stack = expression.input_vars();
+    while let Some(var) = stack.pop() {
+        if !visited.insert(var) {
+            continue;
+        }
+        match assignments.get(var) {
+            Some(values) => {
+                stack.extend(values.iter().flatten().flat_map(|value| value.input_vars()))
+            }
+            None => {
+                inputs.insert(var);
+            }
+        }
+    }
+    inputs
+}
+
+/// Get the unique predecessor outside of the loop of the unique entry block of the loop.
+///
+/// Returns `None` if the loop has several entry blocks, several predecessors outside of the loop
+/// or contains the entry block of the function.
+fn get_loop_preheader<'a>(
+    sub: &'a Term<Sub>,
+    loop_: &Loop,
+    predecessors: &BTreeMap<&Tid, Vec<&'a Term<Blk>>>,
+) -> Option<&'a Term<Blk>> {
+    let sub_entry = &sub.term.blocks.first()?.tid;
+    if loop_.blocks.contains_key(sub_entry) {
+        return None;
+    }
+    let mut outside_predecessors = loop_.blocks.keys().flat_map(|tid| {
+        predecessors
+            .get(tid)
+            .into_iter()
+            .flatten()
+            .filter(|pred| !loop_.blocks.contains_key(&pred.tid))
+    });
+    let preheader = *outside_predecessors.next()?;
+    if outside_predecessors.next().is_some() {
+        return None;
+    }
+    Some(preheader)
+}
+
+/// Search the blocks dominating the loop for the last assignment to the given variable
+/// and return the load instruction if the variable is loaded from a memory-mapped address.
+///
+/// Copies of variables (including casts and subpieces) are followed.
+/// The search stops at blocks containing calls, since the callee may overwrite the variable.
+fn find_dominating_mmio_load<'a>(
+    var: &Variable,
+    preheader: &'a Term<Blk>,
+    predecessors: &BTreeMap<&Tid, Vec<&'a Term<Blk>>>,
+    pointer_inference: &PointerInference,
+) -> Option<&'a Term<Def>> {
+    let mut var = var.clone();
+    let mut blk = preheader;
+    for _ in 0..MAX_PREDECESSOR_DEPTH {
+        if blk
+            .term
+            .jmps
+            .iter()
+            .any(|jmp| matches!(jmp.term, Jmp::Call { .. } | Jmp::CallInd { .. }))
+        {
+            return None;
+        }
+        for def in blk.term.defs.iter().rev() {
+            match &def.term {
+                Def::Load { var: loaded, .. } if *loaded == var => {
+                    let address = pointer_inference.eval_address_at_def(&def.tid)?;
+                    return pointer_inference
+                        .get_pointer_targets(&address)
+                        .iter()
+                        .any(|target| target.kind == ObjectKind::Mmio)
+                        .then_some(def);
+                }
+                Def::Assign {
+                    var: assigned,
+                    value,
+                } if *assigned == var => {
+                    let copied = match value {
+                        Expression::Cast { arg, .. } | Expression::Subpiece { arg, .. } => {
+                            arg.as_ref()
+                        }
+                        value => value,
+                    };
+                    let Expression::Var(input) = copied else {
+                        return None;
+                    };
+                    var = input.clone();
+                }
+                _ => (),
+            }
+        }
+        match predecessors.get(&blk.tid).map(Vec::as_slice) {
+            Some([predecessor]) => blk = *predecessor,
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Check whether the given loop polls a value that is loaded from a memory-mapped address outside of the loop.
+///
+/// Returns the conditional jump leaving the loop and the hoisted load instruction.
+fn find_hoisted_mmio_load<'a>(
+    sub: &'a Term<Sub>,
+    loop_: &Loop<'a>,
+    predecessors: &BTreeMap<&Tid, Vec<&'a Term<Blk>>>,
+    pointer_inference: &PointerInference,
+) -> Option<(&'a Term<Jmp>, &'a Term<Def>)> {
+    if loop_.contains_calls_or_returns() {
+        return None;
+    }
+    let exit_branches = loop_.get_exit_branches();
+    let assignments = loop_.get_assignments();
+    let invariant = compute_invariant_variables(&assignments);
+    let is_invariant = |jmp: &&Term<Jmp>| match &jmp.term {
+        Jmp::CBranch { condition, .. } => condition
+            .input_vars()
+            .into_iter()
+            .all(|var| !assignments.contains_key(var) || invariant.contains(var)),
+        _ => false,
+    };
+    if exit_branches.is_empty() || !exit_branches.iter().all(is_invariant) {
+        return None;
+    }
+    let preheader = get_loop_preheader(sub, loop_, predecessors)?;
+    for exit_branch in exit_branches {
+        let Jmp::CBranch { condition, .. } = &exit_branch.term else {
+            continue;
+        };
+        for var in get_loop_input_variables(condition, &assignments) {
+            if let Some(load) =
+                find_dominating_mmio_load(var, preheader, predecessors, pointer_inference)
+            {
+                return Some((exit_branch, load));
+            }
+        }
+    }
+    None
+}
+
+/// Run the check.
+///
+/// Checks all loops of all functions for polling loops on hoisted loads from memory-mapped addresses.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    _cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let pointer_inference = analysis_results.pointer_inference.unwrap();
+
+    let mut cwe_warnings = Vec::new();
+    // The examined sites are the loops.
+    let mut site_counts = SiteCounts::default();
+    for sub in project.program.term.subs.values() {
+        let loops = find_loops(sub);
+        if loops.is_empty() {
+            continue;
+        }
+        let mut predecessors: BTreeMap<&Tid, Vec<&Term<Blk>>> = BTreeMap::new();
+        for blk in sub.term.blocks.iter() {
+            for successor in get_intraprocedural_successors(blk) {
+                predecessors.entry(successor).or_default().push(blk);
+            }
+        }
+        for loop_ in loops.iter() {
+            if let Some((exit_branch, load)) =
+                find_hoisted_mmio_load(sub, loop_, &predecessors, pointer_inference)
+            {
+                site_counts.add_flagged();
+                cwe_warnings.push(generate_cwe_warning(sub, exit_branch, load));
+            } else {
+                site_counts.add_safe();
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    (Vec::new(), cwe_warnings)
+}
+
+/// Generate the CWE warning for a polling loop on a hoisted load.
+fn generate_cwe_warning(sub: &Term<Sub>, exit_branch: &Term<Jmp>, load: &Term<Def>) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Missing Volatile Access) Polling loop at {} ({}) depends on a memory-mapped register that is only read once before the loop at {}",
+            exit_branch.tid.address, sub.term.name, load.tid.address
+        ),
+    )
+    .tids(vec![format!("{}", exit_branch.tid), format!("{}", load.tid)])
+    .addresses(vec![
+        exit_branch.tid.address.clone(),
+        load.tid.address.clone(),
+    ])
+    .symbols(vec![sub.term.name.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::{defs, expr, variable};
+
+    /// Create a function that loads a register from the given address
+    /// and then executes a polling loop with the given defs.
+    fn mock_polling_sub(name: &str, address: &str, loop_defs: Vec<Term<Def>>) -> Term<Sub> {
+        let mut blk_load = Blk::mock_with_tid(&format!("{name}_load"));
+        blk_load.term.defs = defs![
+            format!("{name}_load_status: RAX:8 := Load from {address}"),
+            format!("{name}_mask: RCX:8 = 0x1:8"),
+            format!("{name}_copy_status: RDX:8 = RAX:8")
+        ];
+        blk_load.term.jmps = vec![Term {
+            tid: Tid::new(format!("{name}_load_jmp")),
+            term: Jmp::Branch(Tid::new(format!("{name}_loop"))),
+        }];
+        let mut blk_loop = Blk::mock_with_tid(&format!("{name}_loop"));
+        blk_loop.term.defs = loop_defs;
+        blk_loop.term.defs.push(Term {
+            tid: Tid::new(format!("{name}_test")),
+            term: Def::Assign {
+                var: variable!("ZF:1"),
+                value: Expression::Subpiece {
+                    low_byte: ByteSize::new(0),
+                    size: ByteSize::new(1),
+                    arg: Box::new(Expression::BinOp {
+                        op: BinOpType::IntAnd,
+                        lhs: Box::new(expr!("RDX:8")),
+                        rhs: Box::new(expr!("RCX:8")),
+                    }),
+                },
+            },
+        });
+        blk_loop.term.jmps = vec![
+            Term {
+                tid: Tid::new(format!("{name}_cbranch")),
+                term: Jmp::CBranch {
+                    target: Tid::new(format!("{name}_loop")),
+                    condition: expr!("ZF:1"),
+                },
+            },
+            Term {
+                tid: Tid::new(format!("{name}_exit")),
+                term: Jmp::Branch(Tid::new(format!("{name}_end"))),
+            },
+        ];
+        let mut sub = Sub::mock(name);
+        sub.term.blocks = vec![
+            blk_load,
+            blk_loop,
+            Blk::mock_with_tid(&format!("{name}_end")),
+        ];
+        sub
+    }
+
+    #[test]
+    fn hoisted_mmio_load() {
+        let subs = vec![
+            // The status register is only read once before the loop.
+            mock_polling_sub("hoisted", "0x40000000:8", Vec::new()),
+            // The status register is read in every iteration.
+            mock_polling_sub(
+                "volatile",
+                "0x40000000:8",
+                defs![
+                    "reload_status: RAX:8 := Load from 0x40000000:8",
+                    "recopy_status: RDX:8 = RAX:8"
+                ],
+            ),
+            // The loop polls a global variable in RAM.
+            mock_polling_sub("global", "0x2000:8", Vec::new()),
+        ];
+        let project = mock_x64_project(&[], subs);
+
+        let warnings = run_check(check_cwe, &project, &serde_json::json!({}));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec!["hoisted_cbranch", "hoisted_load_status"]
+        );
+    }
+}
@@ -0,0 +1,637 @@
+//! This module implements a check for CWE-759: Use of a One-Way Hash without a Salt
+//! and its variant CWE-760: Use of a One-Way Hash with a Predictable Salt.
+//!
+//! Passwords should be stored as the output of a slow key derivation function (e.g. PBKDF2, scrypt or Argon2)
+//! with a random salt that is unique for each password.
+//! Without a salt, or with the same salt for all passwords,
+//! identical passwords result in identical hashes and precomputed (rainbow) tables can be used to crack them.
+//! Fast hash functions like MD5 or the SHA family additionally allow brute-forcing billions of candidate passwords per second.
+//!
+//! See <https://cwe.mitre.org/data/definitions/759.html> and <https://cwe.mitre.org/data/definitions/760.html>
+//! for detailed descriptions.
+//!
+//! ## How the check works
+//!
+//! For each call to a function returning a password (e.g. `getpass` or `readpassphrase`)
+//! we taint the returned pointer or the buffer that the password is written to.
+//! A taint analysis then follows the password through the calling function
+//! and records calls to hash and key derivation functions that receive the password (or a pointer to it)
+//! as their data parameter.
+//! The hash functions are configured per crypto library in a table
+//! containing the indices of their data and salt parameters.
+//! For each call hashing a password a warning is generated if
+//! - the salt parameter is a null pointer,
+//! - the salt parameter points to a constant string in read-only memory of the binary
+//!   or (if the [String Abstraction analysis](`crate::analysis::string_abstraction`) was computed)
+//!   the salt is a string whose value is known at the call, or
+//! - the function is a fast hash function instead of a key derivation function.
+//!
+//! ## False Positives
+//!
+//! - The input of a password prompt is not always stored, e.g. it may be hashed for a one-time comparison.
+//! - The salt may be appended to the password before it is passed to a fast hash function.
+//!
+//! ## False Negatives
+//!
+//! - The taint analysis is intraprocedural.
+//!   Passwords hashed in other functions than the one reading them are not found.
+//! - Passwords not read by one of the configured source functions (e.g. read from a network connection) are not found.
+//! - Salts that are computed from predictable values at runtime (e.g. from the user name or the time) are not detected.
+//!
+//! ## Configuration
+//!
+//! The password sources (together with the indices of their buffer and size parameters, if any)
+//! and the hash and key derivation functions of each crypto library
+//! (together with the indices of their data and salt parameters) are configurable in config.json.
+
+use crate::abstract_domain::{BricksDomain, IntervalDomain, SizedDomain, TryToBitvec};
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, HasCfg};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{
+    Data as PiData, PointerInference as PointerInferenceComputation,
+};
+use crate::analysis::string_abstraction::context::Context as StringAbstractionContext;
+use crate::analysis::taint::{
+    state::State as TaState, summary::TaintSummaries, Taint, TaintAnalysis,
+};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::graph_utils::get_extern_call_nodes;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE759",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The parameters of a function returning a password.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+struct SourceParameters {
+    /// The index of the parameter pointing to the buffer that the password is written to.
+    /// If there is no such parameter, the return value points to the password.
+    #[serde(default)]
+    buffer: Option<usize>,
+    /// The index of the parameter containing the size of the buffer, if there is one.
+    #[serde(default)]
+    size: Option<usize>,
+}
+
+/// The parameters of a hash or key derivation function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+struct HashParameters {
+    /// The index of the parameter containing the hashed data.
+    data: usize,
+    /// The index of the salt parameter, if the function has one.
+    #[serde(default)]
+    salt: Option<usize>,
+    /// Whether the function is a key derivation function suitable for password storage.
+    #[serde(default)]
+    kdf: bool,
+}
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions returning passwords.
+    password_sources: BTreeMap<String, SourceParameters>,
+    /// The hash and key derivation functions of each crypto library.
+    hash_symbols: BTreeMap<String, BTreeMap<String, HashParameters>>,
+    /// The maximal number of bytes of a password buffer that get tainted.
+    /// Also used if the size of the buffer is not known.
+    max_tainted_bytes: u64,
+}
+
+/// A hash or key derivation function.
+#[derive(Debug, Clone)]
+struct HashFunction<'a> {
+    /// The extern symbol of the function.
+    symbol: &'a ExternSymbol,
+    /// The crypto library containing the function.
+    library: String,
+    /// The parameters of the function.
+    parameters: HashParameters,
+}
+
+/// The weakness of a call hashing a password.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Weakness {
+    /// The salt parameter is a null pointer.
+    NoSalt,
+    /// The salt is a constant, whose value is given if known.
+    ConstantSalt(Option<String>),
+    /// The function is a fast hash function instead of a key derivation function.
+    FastHash,
+}
+
+/// Run the check.
+///
+/// For each call to a password source, a separate taint analysis is started at the call,
+/// so that warnings can be attributed to the source of the password.
+/// For each call hashing a password only the first source reaching it is reported.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE759.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+    let graph = analysis_results.control_flow_graph;
+
+    let source_names: Vec<String> = config.password_sources.keys().cloned().collect();
+    let source_map = get_symbol_map(project, &source_names);
+    let hash_functions = get_hash_functions(project, &config);
+    // The examined sites are the calls to hash functions that hash passwords.
+    let mut site_counts = SiteCounts::default();
+    if source_map.is_empty() || hash_functions.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut hash_calls: BTreeMap<Tid, (Tid, String)> = BTreeMap::new();
+    for edge in graph.edge_references() {
+        let Edge::ExternCallStub(jmp) = edge.weight() else {
+            continue;
+        };
+        let Jmp::Call { target, .. } = &jmp.term else {
+            continue;
+        };
+        let Some(source) = source_map.get(target) else {
+            continue;
+        };
+        let Some(state) = generate_source_state(
+            project,
+            source,
+            &config.password_sources[&source.name],
+            &jmp.tid,
+            pi_result,
+            config.max_tainted_bytes,
+        ) else {
+            continue;
+        };
+
+        let (cwe_sender, cwe_receiver) = crossbeam_channel::unbounded();
+        let context = Context {
+            project,
+            pi_result,
+            control_flow_graph: graph,
+            hash_functions: &hash_functions,
+            taint_summaries: analysis_results.taint_summaries,
+            cwe_collector: cwe_sender,
+        };
+        let mut computation = create_computation(context, None);
+        computation.set_cancellation_token(analysis_results.cancellation_token);
+        computation.set_node_value(edge.target(), NodeValue::Value(state));
+        computation.compute_with_max_steps(100);
+
+        for hash_call in cwe_receiver.try_iter() {
+            hash_calls
+                .entry(hash_call)
+                .or_insert_with(|| (jmp.tid.clone(), source.name.clone()));
+        }
+    }
+
+    let call_nodes = get_extern_call_nodes(graph);
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for jmp in sub.term.blocks.iter().flat_map(|blk| blk.term.jmps.iter()) {
+            let (Some((source_tid, source_name)), Jmp::Call { target, .. }) =
+                (hash_calls.get(&jmp.tid), &jmp.term)
+            else {
+                continue;
+            };
+            let hash_function = &hash_functions[target];
+            let weakness = hash_function
+                .parameters
+                .salt
+                .and_then(|index| project.get_parameter_arg(hash_function.symbol, index))
+                .and_then(|salt| {
+                    get_salt_weakness(
+                        analysis_results,
+                        call_nodes.get(&jmp.tid).copied(),
+                        &jmp.tid,
+                        &salt,
+                    )
+                });
+            let weakness = match weakness {
+                None if !hash_function.parameters.kdf => Some(Weakness::FastHash),
+                weakness => weakness,
+            };
+            if let Some(weakness) = weakness {
+                site_counts.add_flagged();
+                cwe_warnings.push(generate_cwe_warning(
+                    sub,
+                    &jmp.tid,
+                    hash_function,
+                    source_tid,
+                    source_name,
+                    &weakness,
+                ));
+            } else {
+                site_counts.add_safe();
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Get the hash and key derivation functions of all crypto libraries that are called by the binary.
+fn get_hash_functions<'a>(project: &'a Project, config: &Config) -> HashMap<Tid, HashFunction<'a>> {
+    let mut hash_functions = HashMap::new();
+    for (library, functions) in config.hash_symbols.iter() {
+        let names: Vec<String> = functions.keys().cloned().collect();
+        for (tid, symbol) in get_symbol_map(project, &names) {
+            hash_functions.insert(
+                tid,
+                HashFunction {
+                    symbol,
+                    library: library.clone(),
+                    parameters: functions[&symbol.name],
+                },
+            );
+        }
+    }
+    hash_functions
+}
+
+/// Generate the taint state after a call to a password source.
+///
+/// If the password is written to a buffer, each byte of the buffer gets tainted separately.
+/// Otherwise the return registers containing the pointer to the password are tainted.
+/// Returns `None` if the buffer is not known.
+fn generate_source_state(
+    project: &Project,
+    source: &ExternSymbol,
+    parameters: &SourceParameters,
+    call_tid: &Tid,
+    pi_result: &PointerInferenceComputation,
+    max_tainted_bytes: u64,
+) -> Option<TaState> {
+    let mut state = TaState::new_empty();
+    let Some(buffer_index) = parameters.buffer else {
+        let return_registers: Vec<Variable> = match source.return_values.as_slice() {
+            [] => project
+                .get_calling_convention(source)
+                .integer_return_register
+                .iter()
+                .take(1)
+                .cloned()
+                .collect(),
+            return_values => return_values
+                .iter()
+                .filter_map(|arg| match arg {
+                    Arg::Register {
+                        expr: Expression::Var(var),
+                        ..
+                    } => Some(var.clone()),
+                    _ => None,
+                })
+                .collect(),
+        };
+        for register in return_registers.iter() {
+            state.set_register_taint(register, Taint::Tainted(register.size));
+        }
+        return (!state.is_empty()).then_some(state);
+    };
+    let buffer = project
+        .get_parameter_arg(source, buffer_index)
+        .and_then(|arg| pi_result.eval_parameter_arg_at_call(call_tid, &arg))?;
+    let size = parameters
+        .size
+        .and_then(|index| project.get_parameter_arg(source, index))
+        .and_then(|param| pi_result.eval_parameter_arg_at_call(call_tid, &param))
+        .and_then(|size| size.get_if_absolute_value()?.try_to_bitvec().ok())
+        .and_then(|size| size.try_to_u64().ok())
+        .map(|size| std::cmp::min(size, max_tainted_bytes))
+        .unwrap_or(max_tainted_bytes);
+    for offset in 0..size {
+        let offset = Bitvector::from_u64(offset).into_resize_unsigned(buffer.bytesize());
+        state.save_taint_to_memory(
+            &buffer.add_offset(&IntervalDomain::from(offset)),
+            Taint::Tainted(ByteSize::new(1)),
+        );
+    }
+    (!state.is_empty()).then_some(state)
+}
+
+/// Get the weakness of the salt parameter of a call hashing a password, if it has one.
+fn get_salt_weakness(
+    analysis_results: &AnalysisResults,
+    call_node: Option<NodeIndex>,
+    call_tid: &Tid,
+    salt: &Arg,
+) -> Option<Weakness> {
+    let runtime_memory_image = &analysis_results.project.runtime_memory_image;
+    let value = analysis_results
+        .pointer_inference?
+        .eval_parameter_arg_at_call(call_tid, salt)?;
+    if let Ok(address) = value.try_to_bitvec() {
+        if address.is_zero() {
+            return Some(Weakness::NoSalt);
+        }
+        if runtime_memory_image.is_global_memory_address(&address)
+            && !runtime_memory_image
+                .is_address_writeable(&address)
+                .unwrap_or(true)
+        {
+            let salt = runtime_memory_image
+                .read_string_until_null_terminator(&address)
+                .ok()
+                .map(|salt| salt.to_string());
+            return Some(Weakness::ConstantSalt(salt));
+        }
+        return None;
+    }
+    get_constant_string_at_call(analysis_results, call_node, salt)
+        .map(|salt| Weakness::ConstantSalt(Some(salt)))
+}
+
+/// Get the string that the given argument points to at the call
+/// if the string abstraction knows its exact value.
+fn get_constant_string_at_call(
+    analysis_results: &AnalysisResults,
+    call_node: Option<NodeIndex>,
+    arg: &Arg,
+) -> Option<String> {
+    let (
+        Some(string_abstraction),
+        Some(pointer_inference),
+        Some(call_node),
+        Arg::Register {
+            expr: Expression::Var(var),
+            ..
+        },
+    ) = (
+        analysis_results.string_abstraction,
+        analysis_results.pointer_inference,
+        call_node,
+        arg,
+    )
+    else {
+        return None;
+    };
+    let (Some(string_state), Some(pi_state)) = (
+        string_abstraction.get_node_value(call_node),
+        pointer_inference.get_node_value(call_node),
+    ) else {
+        return None;
+    };
+    let string_state = string_state.unwrap_value();
+    let pointer = string_state.get_variable_to_pointer_map().get(var)?;
+    if pointer.get_relative_values().is_empty() {
+        return None;
+    }
+    StringAbstractionContext::<BricksDomain>::merge_domains_from_multiple_pointer_targets(
+        string_state,
+        pi_state.unwrap_value(),
+        pointer.get_relative_values(),
+    )
+    .get_constant_string()
+}
+
+/// The Context struct for the taint analysis.
+struct Context<'a> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInferenceComputation<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// The hash and key derivation functions called by the binary.
+    hash_functions: &'a HashMap<Tid, HashFunction<'a>>,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where the TIDs of calls hashing a password are sent to.
+    cwe_collector: crossbeam_channel::Sender<Tid>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> Context<'a> {
+    /// Check whether the given parameter of the call is a password
+    /// or a pointer to a password.
+    fn is_password_parameter(&self, state: &TaState, call_tid: &Tid, parameter: &Arg) -> bool {
+        let is_tainted = match parameter {
+            Arg::Register { expr, .. } => state.eval(expr).is_tainted(),
+            Arg::Stack { address, size, .. } => self
+                .pi_result
+                .eval_at_jmp(call_tid, address)
+                .is_some_and(|address| state.load_taint_from_memory(&address, *size).is_tainted()),
+        };
+        is_tainted
+            || self
+                .pi_result
+                .eval_parameter_arg_at_call(call_tid, parameter)
+                .is_some_and(|value| state.check_if_address_points_to_taint(value))
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
+    /// Report calls to hash functions whose data parameter is a password.
+    fn update_extern_call(
+        &self,
+        state: &TaState,
+        call: &Term<Jmp>,
+        project: &Project,
+        extern_symbol: &ExternSymbol,
+    ) -> Option<TaState> {
+        if let Some(hash_function) = self.hash_functions.get(&extern_symbol.tid) {
+            if let Some(data) =
+                project.get_parameter_arg(extern_symbol, hash_function.parameters.data)
+            {
+                if self.is_password_parameter(state, &call.tid, &data) {
+                    let _ = self.cwe_collector.send(call.tid.clone());
+                }
+            }
+        }
+        let calling_conv = project.get_calling_convention(extern_symbol);
+        let mut new_state = state.clone();
+        new_state.remove_non_callee_saved_taint(calling_conv);
+        if let Some(summary) = self
+            .taint_summaries
+            .and_then(|summaries| summaries.get(&extern_symbol.name))
+        {
+            summary.apply(
+                state,
+                &mut new_state,
+                self.pi_result,
+                &call.tid,
+                calling_conv,
+            );
+        }
+        Some(new_state)
+    }
+}
+
+/// Generate the CWE warning for a call hashing a password read by the given source call.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    call_tid: &Tid,
+    hash_function: &HashFunction,
+    source_tid: &Tid,
+    source_name: &str,
+    weakness: &Weakness,
+) -> CweWarning {
+    let (cwe, description) = match weakness {
+        Weakness::NoSalt => ("CWE-759", "without a salt".to_string()),
+        Weakness::ConstantSalt(Some(salt)) => {
+            ("CWE-760", format!("with the constant salt \"{salt}\""))
+        }
+        Weakness::ConstantSalt(None) => ("CWE-760", "with a constant salt".to_string()),
+        Weakness::FastHash => (
+            "CWE-759",
+            "with a fast hash function instead of a key derivation function".to_string(),
+        ),
+    };
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Weak Password Hash) Password read by {} at {} is hashed by {} ({}) at {} {} ({})",
+            source_name,
+            source_tid.address,
+            hash_function.symbol.name,
+            hash_function.library,
+            call_tid.address,
+            description,
+            sub.term.name
+        ),
+    )
+    .tids(vec![format!("{call_tid}"), format!("{source_tid}")])
+    .addresses(vec![call_tid.address.clone(), source_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![
+        vec!["cwe".to_string(), cwe.to_string()],
+        vec![
+            "hash_function".to_string(),
+            hash_function.symbol.name.clone(),
+        ],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::def;
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "password_sources": {"getpass": {}},
+            "hash_symbols": {
+                "openssl": {
+                    "MD5": {"data": 0},
+                    "PKCS5_PBKDF2_HMAC": {"data": 0, "salt": 2, "kdf": true}
+                }
+            },
+            "max_tainted_bytes": 64
+        })
+    }
+
+    /// Create a project with a function reading a password with `getpass`
+    /// and passing it to the given hash function.
+    /// The given defs are executed before the call to the hash function.
+    fn mock_project(hash_function: &str, defs: Vec<Term<Def>>) -> Project {
+        let mut blk_read = Blk::mock_with_tid("blk_read");
+        blk_read
+            .term
+            .jmps
+            .push(Jmp::call("call_getpass", "getpass", Some("blk_hash")));
+        let mut blk_hash = Blk::mock_with_tid("blk_hash");
+        blk_hash.term.defs = vec![def!["password: RDI:8 = RAX:8"]];
+        blk_hash.term.defs.extend(defs);
+        blk_hash
+            .term
+            .jmps
+            .push(Jmp::call("call_hash", hash_function, Some("blk_end")));
+        let blk_end = Blk::mock_with_tid("blk_end");
+
+        let mut sub = Sub::mock("store_password");
+        sub.term.blocks = vec![blk_read, blk_hash, blk_end];
+        let mut project = mock_x64_project(&["getpass", hash_function], vec![sub]);
+        for name in ["getpass", hash_function] {
+            let symbol = project
+                .program
+                .term
+                .extern_symbols
+                .get_mut(&Tid::new(name))
+                .unwrap();
+            symbol.parameters = vec![
+                Arg::mock_register("RDI", 8),
+                Arg::mock_register("RSI", 8),
+                Arg::mock_register("RDX", 8),
+            ];
+        }
+        project
+    }
+
+    #[test]
+    fn fast_hash() {
+        let warnings = run_check(check_cwe, &mock_project("MD5", Vec::new()), &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec!["call_hash".to_string(), "call_getpass".to_string()]
+        );
+        assert_eq!(warnings[0].other[0], vec!["cwe", "CWE-759"]);
+    }
+
+    #[test]
+    fn kdf_without_salt() {
+        let warnings = run_check(
+            check_cwe,
+            &mock_project("PKCS5_PBKDF2_HMAC", vec![def!["salt: RDX:8 = 0x0:8"]]),
+            &mock_config(),
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].description.contains("without a salt"));
+    }
+
+    #[test]
+    fn kdf_with_unknown_salt() {
+        assert!(run_check(
+            check_cwe,
+            &mock_project("PKCS5_PBKDF2_HMAC", Vec::new()),
+            &mock_config()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn hashed_data_is_no_password() {
+        let project = mock_project("MD5", vec![def!["not_password: RDI:8 = RBX:8"]]);
+        assert!(run_check(check_cwe, &project, &mock_config()).is_empty());
+    }
+}
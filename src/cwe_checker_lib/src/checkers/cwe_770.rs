@@ -0,0 +1,375 @@
+//! This module implements a check for CWE-770: Allocation of Resources Without Limits or Throttling.
+//!
+//! Network daemons usually accept incoming connections in a loop
+//! and spawn a new thread or process or allocate some state for each accepted connection.
+//! If the number of simultaneously handled connections is not limited,
+//! an attacker can exhaust the memory, thread or process limits of the system
+//! simply by opening many connections, resulting in a denial of service.
+//!
+//! See <https://cwe.mitre.org/data/definitions/770.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! We search for loops in the control flow graph of each function that contain a call to `accept`.
+//! The per-iteration effects of each such loop are classified by the calls contained in the loop:
+//! Calls to e.g. `pthread_create` or `fork` spawn a new thread or process for each connection
+//! and calls to e.g. `malloc` allocate memory for each connection.
+//! If the loop has such effects, but contains neither a comparison that may limit the number of connections
+//! nor a call to a throttling function (e.g. `sem_wait`), a warning is emitted.
+//!
+//! Comparisons of the form `x < y` or `x <= y` (signed or unsigned) are considered as possible limits,
+//! unless one side is one of the constants -1, 0 or 1.
+//! The latter are usually error checks of return values, e.g. of the return value of `accept` itself.
+//!
+//! ## False Positives
+//!
+//! - The number of connections may be limited outside of the accept loop,
+//!   e.g. in a spawned thread that closes the connection if too many connections are open.
+//! - Connections may be limited by the system, e.g. by the listen backlog combined with a small `RLIMIT_NPROC`.
+//!
+//! ## False Negatives
+//!
+//! - Any comparison in the loop is accepted as a possible limit,
+//!   even if it does not involve a connection counter.
+//! - Per-connection effects in functions called from the loop are not detected.
+//! - Loops spanning several functions (e.g. an accept loop calling a function that accepts the connection) are not detected.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The accepting functions, the functions with per-connection effects
+//! and the throttling functions are configurable in config.json.
+
+use crate::analysis::graph::{Edge, Graph, Node};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::visit::EdgeFiltered;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE770",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions that accept a new connection.
+    accept_symbols: Vec<String>,
+    /// Functions that spawn a new thread or process.
+    spawn_symbols: Vec<String>,
+    /// Functions that allocate memory.
+    allocation_symbols: Vec<String>,
+    /// Functions that can be used to throttle the handling of connections.
+    throttling_symbols: Vec<String>,
+}
+
+/// The per-iteration effects of a loop that consume resources.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Effect {
+    /// A new thread or process is spawned.
+    Spawn,
+    /// Memory is allocated.
+    Allocation,
+}
+
+/// An accept loop of a function.
+#[derive(Debug, Default)]
+struct AcceptLoop<'a> {
+    /// The calls to accepting functions in the loop.
+    accept_calls: BTreeMap<Tid, &'a ExternSymbol>,
+    /// The calls with per-connection effects in the loop.
+    effect_calls: BTreeMap<Tid, (Effect, &'a ExternSymbol)>,
+    /// Whether the loop calls a throttling function or contains a comparison that may limit the number of connections.
+    is_limited: bool,
+}
+
+/// Check whether the given expression is one of the constants -1, 0 or 1.
+fn is_trivial_constant(expression: &Expression) -> bool {
+    match expression {
+        Expression::Const(constant) => matches!(constant.try_to_i64(), Ok(-1..=1)),
+        _ => false,
+    }
+}
+
+/// Check whether the given expression contains a comparison that may limit the number of connections.
+fn contains_limiting_comparison(expression: &Expression) -> bool {
+    use BinOpType::*;
+    match expression {
+        Expression::BinOp { op, lhs, rhs } => {
+            let is_limiting = matches!(op, IntLess | IntSLess | IntLessEqual | IntSLessEqual)
+                && !is_trivial_constant(lhs)
+                && !is_trivial_constant(rhs);
+            is_limiting || contains_limiting_comparison(lhs) || contains_limiting_comparison(rhs)
+        }
+        Expression::UnOp { arg, .. }
+        | Expression::Cast { arg, .. }
+        | Expression::Subpiece { arg, .. } => contains_limiting_comparison(arg),
+        Expression::Var(_) | Expression::Const(_) | Expression::Unknown { .. } => false,
+    }
+}
+
+/// Check whether the given block contains a comparison that may limit the number of connections.
+fn block_contains_limiting_comparison(block: &Term<Blk>) -> bool {
+    block.term.defs.iter().any(|def| match &def.term {
+        Def::Assign { value, .. } => contains_limiting_comparison(value),
+        Def::Load { .. } | Def::Store { .. } => false,
+    }) || block.term.jmps.iter().any(|jmp| match &jmp.term {
+        Jmp::CBranch { condition, .. } => contains_limiting_comparison(condition),
+        _ => false,
+    })
+}
+
+/// Find all loops of the control flow graph that contain a call to one of the accepting functions
+/// and classify their per-iteration effects.
+///
+/// The loops are the non-trivial strongly connected components of the intraprocedural control flow graph.
+/// Each loop is returned together with the function containing it.
+fn find_accept_loops<'a>(
+    graph: &Graph<'a>,
+    effect_symbols: &HashMap<Tid, (Effect, &'a ExternSymbol)>,
+    accept_symbols: &HashMap<Tid, &'a ExternSymbol>,
+    throttling_symbols: &HashMap<Tid, &'a ExternSymbol>,
+) -> Vec<(&'a Term<Sub>, AcceptLoop<'a>)> {
+    let intraprocedural_graph = EdgeFiltered::from_fn(graph, |edge| {
+        !matches!(edge.weight(), Edge::Call(_) | Edge::CrReturnStub)
+    });
+    let mut accept_loops = Vec::new();
+    for component in petgraph::algo::kosaraju_scc(&intraprocedural_graph) {
+        // Every loop contains at least the start and the end node of a block.
+        if component.len() < 2 {
+            continue;
+        }
+        let mut accept_loop = AcceptLoop::default();
+        let mut loop_sub = None;
+        for node in component {
+            let Node::BlkEnd(block, sub) = graph[node] else {
+                continue;
+            };
+            loop_sub = Some(sub);
+            accept_loop.is_limited |= block_contains_limiting_comparison(block);
+            for jmp in block.term.jmps.iter() {
+                let Jmp::Call { target, .. } = &jmp.term else {
+                    continue;
+                };
+                if let Some(symbol) = accept_symbols.get(target) {
+                    accept_loop.accept_calls.insert(jmp.tid.clone(), symbol);
+                }
+                if throttling_symbols.contains_key(target) {
+                    accept_loop.is_limited = true;
+                }
+                if let Some((effect, symbol)) = effect_symbols.get(target) {
+                    accept_loop
+                        .effect_calls
+                        .insert(jmp.tid.clone(), (*effect, symbol));
+                }
+            }
+        }
+        if let Some(sub) = loop_sub {
+            if !accept_loop.accept_calls.is_empty() {
+                accept_loops.push((sub, accept_loop));
+            }
+        }
+    }
+    accept_loops
+}
+
+/// Generate the CWE warning for an accept loop without connection limits.
+fn generate_cwe_warning(sub: &Term<Sub>, accept_loop: &AcceptLoop) -> CweWarning {
+    let (accept_tid, accept_symbol) = accept_loop.accept_calls.iter().next().unwrap();
+    let effects: BTreeSet<Effect> = accept_loop
+        .effect_calls
+        .values()
+        .map(|(effect, _)| *effect)
+        .collect();
+    let effect_description = effects
+        .iter()
+        .map(|effect| {
+            let symbols: BTreeSet<&str> = accept_loop
+                .effect_calls
+                .values()
+                .filter(|(call_effect, _)| call_effect == effect)
+                .map(|(_, symbol)| symbol.name.as_str())
+                .collect();
+            let symbols = symbols.into_iter().collect::<Vec<_>>().join(", ");
+            match effect {
+                Effect::Spawn => format!("spawns threads or processes ({symbols})"),
+                Effect::Allocation => format!("allocates memory ({symbols})"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" and ");
+    let mut tids = vec![format!("{accept_tid}")];
+    let mut addresses = vec![accept_tid.address.clone()];
+    for effect_tid in accept_loop.effect_calls.keys() {
+        tids.push(format!("{effect_tid}"));
+        addresses.push(effect_tid.address.clone());
+    }
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Unbounded Connection Handling) The loop accepting connections with {} at {} ({}) {} for each connection without limiting the number of connections",
+            accept_symbol.name, accept_tid.address, sub.term.name, effect_description
+        ),
+    )
+    .tids(tids)
+    .addresses(addresses)
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![effects
+        .iter()
+        .map(|effect| format!("{effect:?}"))
+        .collect()])
+}
+
+/// Run the check.
+///
+/// For each loop containing a call to an accepting function we check
+/// whether the loop spawns threads or processes or allocates memory in each iteration
+/// without limiting the number of connections.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let graph = analysis_results.control_flow_graph;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE770.");
+
+    let accept_symbols = get_symbol_map(project, &config.accept_symbols);
+    // The examined sites are the accept loops.
+    let mut site_counts = SiteCounts::default();
+    if accept_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let throttling_symbols = get_symbol_map(project, &config.throttling_symbols);
+    let mut effect_symbols = HashMap::new();
+    for (effect, symbol_names) in [
+        (Effect::Allocation, &config.allocation_symbols),
+        (Effect::Spawn, &config.spawn_symbols),
+    ] {
+        for (tid, symbol) in get_symbol_map(project, symbol_names) {
+            effect_symbols.insert(tid, (effect, symbol));
+        }
+    }
+
+    let mut cwe_warnings = Vec::new();
+    for (sub, accept_loop) in
+        find_accept_loops(graph, &effect_symbols, &accept_symbols, &throttling_symbols)
+    {
+        if accept_loop.is_limited || accept_loop.effect_calls.is_empty() {
+            site_counts.add_safe();
+        } else {
+            site_counts.add_flagged();
+            cwe_warnings.push(generate_cwe_warning(sub, &accept_loop));
+        }
+    }
+    cwe_warnings.sort();
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::{expr, variable};
+
+    /// Create a project with a function `server` containing an accept loop that spawns a thread for each connection.
+    /// If `with_limit` is set, the loop compares a connection counter with a constant limit.
+    fn mock_project(with_limit: bool) -> Project {
+        let mut blk_accept = Blk::mock_with_tid("blk_accept");
+        blk_accept
+            .term
+            .jmps
+            .push(Jmp::call("call_accept", "accept", Some("blk_check")));
+        let mut blk_check = Blk::mock_with_tid("blk_check");
+        blk_check.term.defs.push(Term {
+            tid: Tid::new("error_check"),
+            term: Def::Assign {
+                var: variable!("ZF:1"),
+                value: Expression::BinOp {
+                    op: BinOpType::IntSLess,
+                    lhs: Box::new(expr!("RAX:8")),
+                    rhs: Box::new(expr!("0:8")),
+                },
+            },
+        });
+        if with_limit {
+            blk_check.term.defs.push(Term {
+                tid: Tid::new("limit_check"),
+                term: Def::Assign {
+                    var: variable!("CF:1"),
+                    value: Expression::BinOp {
+                        op: BinOpType::IntLess,
+                        lhs: Box::new(expr!("RBX:8")),
+                        rhs: Box::new(expr!("0x40:8")),
+                    },
+                },
+            });
+        }
+        blk_check
+            .term
+            .jmps
+            .push(Jmp::call("call_malloc", "malloc", Some("blk_spawn")));
+        let mut blk_spawn = Blk::mock_with_tid("blk_spawn");
+        blk_spawn.term.jmps.push(Jmp::call(
+            "call_pthread_create",
+            "pthread_create",
+            Some("blk_loop"),
+        ));
+        let mut blk_loop = Blk::mock_with_tid("blk_loop");
+        blk_loop
+            .term
+            .jmps
+            .push(Jmp::branch("jump_loop", "blk_accept"));
+
+        let mut sub = Sub::mock("server");
+        sub.term
+            .blocks
+            .extend([blk_accept, blk_check, blk_spawn, blk_loop]);
+        mock_x64_project(&["accept", "pthread_create", "malloc"], vec![sub])
+    }
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "accept_symbols": ["accept"],
+            "spawn_symbols": ["pthread_create"],
+            "allocation_symbols": ["malloc"],
+            "throttling_symbols": ["sem_wait"],
+        })
+    }
+
+    #[test]
+    fn unbounded_accept_loop() {
+        let project = mock_project(false);
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec![
+                "call_accept".to_string(),
+                "call_malloc".to_string(),
+                "call_pthread_create".to_string()
+            ]
+        );
+        assert_eq!(
+            warnings[0].other,
+            vec![vec!["Spawn".to_string(), "Allocation".to_string()]]
+        );
+    }
+
+    #[test]
+    fn limited_accept_loop() {
+        let project = mock_project(true);
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert!(warnings.is_empty());
+    }
+}
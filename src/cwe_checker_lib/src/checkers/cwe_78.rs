@@ -8,56 +8,60 @@
 //!
 //! ## How the check works
 //!
-//! The check depends entirely on the string abstraction analysis that is run beforehand.
-//! The string abstraction uses a forward fixpoint analysis to determine potential strings at all
-//! nodes in the CFG. More detailed information about the string abstraction can be found in the
-//! corresponding files.
+//! The user-controlled input returned by the configured input sources (e.g. `recv` or `getenv`)
+//! is tracked by the source-to-sink taint analysis of the [`flow`](crate::analysis::taint::flow) module.
+//! Command building functions like `sprintf` or `strcat` propagate the taint of their arguments to their destination buffer.
+//! A warning is generated for each call to a system call symbol whose command parameter contains or points to tainted input.
+//! Calls to the configured sanitizers remove the taint from the buffers passed to them.
 //!
-//! The BricksDomain, a string abstract domain defining a string as a sequence of substring sets (bricks)
-//! is used for this check. As it considers the order of characters, it can be further used for a manual
-//! post analysis of the commands given to system calls.
+//! Before a warning is generated, the command is looked up in the string abstraction with the AutomatonDomain,
+//! a string abstract domain representing the possible strings by a finite automaton.
+//! If the automaton shows that the command is completely known, i.e. contains no unknown substring,
+//! the input cannot have reached the command and no warning is generated.
 //!
 //! ### Symbols configurable in config.json
 //!
-//! The system calls considered in this check can be configured in the config.json.
+//! - `system_symbols`: The system calls, whose first parameter is the command.
+//! - `input_sources`: Functions returning user-controlled input.
+//!   For each function it is configured whether the input is returned (`ReturnValue`)
+//!   or written to the memory pointed to by the parameter with the given index (`{"ParameterTarget": index}`).
+//! - `command_builders`: Functions that write their arguments into a buffer,
+//!   mapped to the parameter index of the destination buffer.
+//! - `sanitizers`: Functions that neutralize the special elements in the buffers passed to them.
 //!
 //! ## False Positives
 //!
 //! - The input comes from the user but proper sanitization was not detected by the analysis even though it exists.
-//! - The input comes from the user but the format string's input format could not be distinguished as non-string input.
+//! - The input only influences parts of the command that cannot change its meaning, e.g. a numeric argument.
 //!
 //! ## False Negatives
 //!
-//! - Missing substrings due to lost track of pointer targets
-//! - Non tracked function parameters cause incomplete strings that could miss possible dangerous inputs
+//! - The taint analysis is limited to a fixed number of steps per source call,
+//!   so input passing through long call chains may not reach the command.
+//! - Input copied by functions that are neither configured as command builders
+//!   nor covered by the taint summaries of imported libraries is lost.
 
-use petgraph::visit::EdgeRef;
-
-use crate::CweModule;
-
-use crate::abstract_domain::BricksDomain;
-use crate::abstract_domain::TryToBitvec;
+use crate::abstract_domain::{AbstractDomain, AutomatonDomain, TryToBitvec};
 use crate::analysis::graph::Edge;
-use crate::analysis::pointer_inference::State as PointerInferenceState;
 use crate::analysis::string_abstraction::context::Context;
-use crate::analysis::string_abstraction::state::State;
-use crate::intermediate_representation::Arg;
-use crate::intermediate_representation::Expression;
-use crate::intermediate_representation::ExternSymbol;
-use crate::intermediate_representation::Jmp;
-use crate::intermediate_representation::RuntimeMemoryImage;
-use crate::intermediate_representation::Sub;
+use crate::analysis::taint::flow::{
+    find_taint_flows, get_builder_summaries, CallSite, TaintFlowConfig,
+};
+use crate::analysis::taint::summary::TaintFlowTarget;
+use crate::intermediate_representation::{Arg, Expression, ExternSymbol, Jmp};
 use crate::prelude::*;
-use crate::utils::log::CweWarning;
-use crate::utils::log::LogMessage;
-
-use std::collections::BTreeMap;
-use std::fmt::Debug;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// The module name and version
 pub static CWE_MODULE: CweModule = CweModule {
     name: "CWE78",
-    version: "0.1",
+    version: "0.2",
     run: check_cwe,
 };
 
@@ -66,191 +70,296 @@ pub static CWE_MODULE: CweModule = CweModule {
 pub struct Config {
     /// The names of the system call symbols
     system_symbols: Vec<String>,
+    /// Functions returning user-controlled input, together with the location that the input is returned in.
+    input_sources: BTreeMap<String, TaintFlowTarget>,
+    /// Functions assembling their arguments into a buffer,
+    /// mapped to the parameter index of the destination buffer.
+    #[serde(default)]
+    command_builders: BTreeMap<String, usize>,
+    /// Functions neutralizing the special elements in the buffers passed to them.
+    #[serde(default)]
+    sanitizers: BTreeSet<String>,
 }
 
-/// This check checks the string parameter at system calls given by the string abstraction analysis
+/// This check tracks user-controlled input to the command parameter of system calls
 /// to find potential OS Command Injection vulnerabilities.
 pub fn check_cwe(
     analysis_results: &AnalysisResults,
     cwe_params: &serde_json::Value,
 ) -> (Vec<LogMessage>, Vec<CweWarning>) {
-    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
-    let (cwe_sender, cwe_receiver): (
-        crossbeam_channel::Sender<CweWarning>,
-        crossbeam_channel::Receiver<CweWarning>,
-    ) = crossbeam_channel::unbounded();
-    let (log_sender, log_receiver): (
-        crossbeam_channel::Sender<LogMessage>,
-        crossbeam_channel::Receiver<LogMessage>,
-    ) = crossbeam_channel::unbounded();
-    let string_abstraction = analysis_results.string_abstraction.unwrap();
-
-    let system_symbol: Option<(Tid, ExternSymbol)> = string_abstraction
-        .get_context()
-        .project
-        .program
-        .term
-        .extern_symbols
-        .clone()
-        .into_iter()
-        .find(|(_, symbol)| config.system_symbols.contains(&symbol.name));
-    let string_graph = string_abstraction.get_graph();
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE78.");
+    let system_symbols = get_symbol_map(project, &config.system_symbols);
+    // The examined sites are the calls to system functions.
+    let mut site_counts = SiteCounts::default();
+    if system_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
 
-    if let Some((_, system)) = system_symbol {
-        for edge in string_graph.edge_references() {
-            if let Edge::ExternCallStub(jmp) = edge.weight() {
-                if let Jmp::Call { target, .. } = &jmp.term {
-                    if system.tid == *target {
-                        if let Some(source_node) = string_abstraction.get_node_value(edge.source())
-                        {
-                            if let Some(pi_node) = analysis_results
-                                .pointer_inference
-                                .unwrap()
-                                .get_node_value(edge.source())
-                            {
-                                let pi_state = pi_node.unwrap_value();
-                                let source_state = source_node.unwrap_value();
-                                check_system_call_parameter(
-                                    source_state,
-                                    pi_state,
-                                    &system,
-                                    &jmp.tid,
-                                    &cwe_sender,
-                                    &log_sender,
-                                    &string_abstraction
-                                        .get_context()
-                                        .project
-                                        .runtime_memory_image,
-                                )
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    let taint_config = TaintFlowConfig {
+        sources: config.input_sources.clone(),
+        sinks: system_symbols
+            .values()
+            .map(|symbol| (symbol.name.clone(), vec![0]))
+            .collect(),
+        sanitizers: config.sanitizers.clone(),
+        propagators: get_builder_summaries(project, &config.command_builders),
+    };
+    let mut tainted_calls: BTreeMap<CallSite, BTreeSet<CallSite>> = BTreeMap::new();
+    for flow in find_taint_flows(analysis_results, &taint_config) {
+        tainted_calls
+            .entry(flow.sink)
+            .or_default()
+            .insert(flow.source);
     }
 
-    let mut cwe_warnings = BTreeMap::new();
-    for cwe in cwe_receiver.try_iter() {
-        match &cwe.addresses[..] {
-            [taint_source_address, ..] => cwe_warnings.insert(taint_source_address.clone(), cwe),
-            _ => panic!(),
+    let call_nodes = get_system_call_nodes(analysis_results, &system_symbols);
+    let mut log_messages = Vec::new();
+    let mut cwe_warnings = Vec::new();
+    let mut flagged_calls = BTreeSet::new();
+    for (sink, sources) in tainted_calls.iter() {
+        let Some((node, symbol)) = call_nodes.get(&sink.call_tid) else {
+            continue;
         };
+        if is_command_known(analysis_results, *node, symbol) {
+            log_messages.push(LogMessage::new_debug(format!(
+                "Tainted command at {} is a known string",
+                sink.call_tid.address
+            )));
+            continue;
+        }
+        let sub_name = project
+            .program
+            .term
+            .subs
+            .values()
+            .find(|sub| {
+                sub.term
+                    .blocks
+                    .iter()
+                    .any(|blk| blk.term.jmps.iter().any(|jmp| jmp.tid == sink.call_tid))
+            })
+            .map(|sub| sub.term.name.as_str())
+            .unwrap_or_default();
+        flagged_calls.insert(&sink.call_tid);
+        cwe_warnings.push(generate_cwe_warning(sub_name, sink, sources));
     }
-
-    let cwe_warnings = cwe_warnings.into_values().collect();
-    let log_messages = log_receiver.try_iter().collect();
+    for call_tid in call_nodes.keys() {
+        if flagged_calls.contains(call_tid) {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (log_messages, cwe_warnings)
 }
 
-/// Checks the system call parameter given by the Bricks Domain.
-pub fn check_system_call_parameter(
-    source_state: &State<BricksDomain>,
-    pi_state: &PointerInferenceState,
-    system_symbol: &ExternSymbol,
-    jmp_tid: &Tid,
-    cwe_collector: &crossbeam_channel::Sender<CweWarning>,
-    log_collector: &crossbeam_channel::Sender<LogMessage>,
-    runtime_memory_image: &RuntimeMemoryImage,
-) {
-    let sub = source_state.get_current_sub().unwrap();
-    if let Some(Arg::Register {
-        expr: Expression::Var(var),
-        ..
-    }) = system_symbol.parameters.first()
-    {
-        if let Some(value) = source_state.get_variable_to_pointer_map().get(var) {
-            let contains_string_constant = value.get_absolute_value().is_some();
-            let contains_relative_string_pointer = !value.get_relative_values().is_empty();
-            if contains_relative_string_pointer {
-                let mut parameter_domain =
-                    Context::<BricksDomain>::merge_domains_from_multiple_pointer_targets(
-                        source_state,
-                        pi_state,
-                        value.get_relative_values(),
-                    );
-                if contains_string_constant {
-                    if let Ok(global_string) = runtime_memory_image
-                        .read_string_until_null_terminator(
-                            &value.get_absolute_value().unwrap().try_to_bitvec().unwrap(),
-                        )
-                    {
-                        parameter_domain.widen(&BricksDomain::from(global_string.to_string()));
-                    } else {
-                        parameter_domain = BricksDomain::Top;
-                    }
+/// Map the TIDs of all calls to system call symbols to the CFG node before the call
+/// and the called symbol.
+fn get_system_call_nodes<'a>(
+    analysis_results: &AnalysisResults,
+    system_symbols: &HashMap<Tid, &'a ExternSymbol>,
+) -> HashMap<Tid, (NodeIndex, &'a ExternSymbol)> {
+    let graph = analysis_results.control_flow_graph;
+    let mut call_nodes = HashMap::new();
+    for edge in graph.edge_references() {
+        if let Edge::ExternCallStub(jmp) = edge.weight() {
+            if let Jmp::Call { target, .. } = &jmp.term {
+                if let Some(symbol) = system_symbols.get(target) {
+                    call_nodes.insert(jmp.tid.clone(), (edge.source(), *symbol));
                 }
-                check_if_string_domain_indicates_vulnerability(
-                    parameter_domain,
-                    jmp_tid,
-                    sub,
-                    system_symbol,
-                    cwe_collector,
-                );
-            } else if !contains_string_constant && !contains_relative_string_pointer {
-                let _ = cwe_collector.send(generate_cwe_warning(
-                    &sub.term.name,
-                    jmp_tid,
-                    &system_symbol.name,
-                ));
             }
-        } else {
-            let _ = log_collector.send(LogMessage::new_debug(format!(
-                "No Parameter tracked for system call at {}",
-                jmp_tid.address
-            )));
         }
     }
+    call_nodes
 }
 
-/// Checks if the Bricks Domain indicates a vulnerability at the system call.
-pub fn check_if_string_domain_indicates_vulnerability(
-    input_domain: BricksDomain,
-    jmp_tid: &Tid,
-    sub: &Term<Sub>,
+/// Returns `true` if the string abstraction shows that the command passed to the system call
+/// at the given node contains no unknown substring.
+///
+/// Returns `false` if the string abstraction was not computed or does not know the command.
+fn is_command_known(
+    analysis_results: &AnalysisResults,
+    node: NodeIndex,
     system_symbol: &ExternSymbol,
-    cwe_collector: &crossbeam_channel::Sender<CweWarning>,
-) {
-    match &input_domain {
-        BricksDomain::Top => {
-            let _ = cwe_collector.send(generate_cwe_warning(
-                &sub.term.name,
-                jmp_tid,
-                &system_symbol.name,
-            ));
-        }
-        BricksDomain::Value(bricks) => {
-            let partially_known = bricks
-                .iter()
-                .any(|brick| matches!(brick, crate::abstract_domain::BrickDomain::Top));
-            if partially_known {
-                let _ = cwe_collector.send(generate_cwe_warning(
-                    &sub.term.name,
-                    jmp_tid,
-                    &system_symbol.name,
-                ));
-            }
-        }
+) -> bool {
+    let (Some(string_abstraction), Some(pi_result)) = (
+        analysis_results.automaton_string_abstraction,
+        analysis_results.pointer_inference,
+    ) else {
+        return false;
+    };
+    let (Some(string_node), Some(pi_node)) = (
+        string_abstraction.get_node_value(node),
+        pi_result.get_node_value(node),
+    ) else {
+        return false;
+    };
+    let Some(Arg::Register {
+        expr: Expression::Var(var),
+        ..
+    }) = system_symbol.parameters.first()
+    else {
+        return false;
+    };
+    let string_state = string_node.unwrap_value();
+    let Some(value) = string_state.get_variable_to_pointer_map().get(var) else {
+        return false;
+    };
+    let mut command: Option<AutomatonDomain> = None;
+    if !value.get_relative_values().is_empty() {
+        command = Some(
+            Context::<AutomatonDomain>::merge_domains_from_multiple_pointer_targets(
+                string_state,
+                pi_node.unwrap_value(),
+                value.get_relative_values(),
+            ),
+        );
+    }
+    if let Some(address) = value.get_absolute_value() {
+        let Ok(global_string) = address.try_to_bitvec().and_then(|address| {
+            analysis_results
+                .project
+                .runtime_memory_image
+                .read_string_until_null_terminator(&address)
+        }) else {
+            return false;
+        };
+        let global_command = AutomatonDomain::from(global_string.to_string());
+        command = Some(match command {
+            Some(command) => command.merge(&global_command),
+            None => global_command,
+        });
     }
+    command.is_some_and(|command| !command.contains_unknown_substring())
 }
 
 /// Generates the CWE Warning for the CWE 78 check
-pub fn generate_cwe_warning(sub_name: &str, jmp_tid: &Tid, symbol_name: &str) -> CweWarning {
+fn generate_cwe_warning(
+    sub_name: &str,
+    sink: &CallSite,
+    sources: &BTreeSet<CallSite>,
+) -> CweWarning {
+    let source_descriptions: Vec<String> = sources
+        .iter()
+        .map(|source| format!("{} at {}", source.function, source.call_tid.address))
+        .collect();
     let description: String = format!(
-        "(OS Command Injection) Input for call to {} may not be properly sanitized in function {} ({})",
-        symbol_name, sub_name, jmp_tid.address,
+        "(OS Command Injection) Input for call to {} may not be properly sanitized in function {} ({}). The input comes from {}",
+        sink.function,
+        sub_name,
+        sink.call_tid.address,
+        source_descriptions.join(", "),
     );
     CweWarning::new(
         String::from(CWE_MODULE.name),
         String::from(CWE_MODULE.version),
         description,
     )
-    .addresses(vec![jmp_tid.address.clone()])
-    .tids(vec![format!("{jmp_tid}")])
+    .addresses(vec![sink.call_tid.address.clone()])
+    .tids(
+        std::iter::once(format!("{}", sink.call_tid))
+            .chain(sources.iter().map(|source| format!("{}", source.call_tid)))
+            .collect(),
+    )
     .symbols(vec![String::from(sub_name)])
     .other(vec![vec![
         String::from("OS Command Injection"),
-        symbol_name.to_string(),
+        sink.function.clone(),
     ]])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::run_check;
+    use crate::def;
+
+    /// Create a project where `main` reads input with `getenv`,
+    /// appends it to a command with `strcat`, optionally sanitizes the command
+    /// and passes the command to `system`.
+    fn mock_project(sanitize: bool) -> Project {
+        let mut project = Project::mock_x64();
+        for name in ["getenv", "strcat", "escape_shell_arg", "system"] {
+            let symbol = ExternSymbol::mock_x64(name);
+            project
+                .program
+                .term
+                .extern_symbols
+                .insert(symbol.tid.clone(), symbol);
+        }
+        let mut blk_input = Blk::mock_with_tid("blk_input");
+        blk_input
+            .term
+            .jmps
+            .push(Jmp::call("call_getenv", "getenv", Some("blk_build")));
+        let mut blk_build = Blk::mock_with_tid("blk_build");
+        blk_build.term.defs = vec![
+            def!["input: RSI:8 = RAX:8"],
+            def!["command: RDI:8 = RSP:8 + 0x10:8"],
+        ];
+        blk_build
+            .term
+            .jmps
+            .push(Jmp::call("call_strcat", "strcat", Some("blk_sanitize")));
+        let mut blk_sanitize = Blk::mock_with_tid("blk_sanitize");
+        if sanitize {
+            blk_sanitize.term.defs = vec![def!["sanitize_arg: RDI:8 = RSP:8 + 0x10:8"]];
+            blk_sanitize.term.jmps.push(Jmp::call(
+                "call_escape",
+                "escape_shell_arg",
+                Some("blk_system"),
+            ));
+        } else {
+            blk_sanitize.term.jmps.push(Term {
+                tid: Tid::new("jmp_system"),
+                term: Jmp::Branch(Tid::new("blk_system")),
+            });
+        }
+        let mut blk_system = Blk::mock_with_tid("blk_system");
+        blk_system.term.defs = vec![def!["command_arg: RDI:8 = RSP:8 + 0x10:8"]];
+        blk_system
+            .term
+            .jmps
+            .push(Jmp::call("call_system", "system", Some("blk_end")));
+        let mut main = Sub::mock("main");
+        main.term.blocks = vec![
+            blk_input,
+            blk_build,
+            blk_sanitize,
+            blk_system,
+            Blk::mock_with_tid("blk_end"),
+        ];
+        project.program.term.subs.insert(main.tid.clone(), main);
+        project.program.term.entry_points.insert(Tid::new("main"));
+        project
+    }
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "system_symbols": ["system"],
+            "input_sources": {"getenv": "ReturnValue"},
+            "command_builders": {"strcat": 0},
+            "sanitizers": ["escape_shell_arg"]
+        })
+    }
+
+    #[test]
+    fn input_reaching_command() {
+        let warnings = run_check(check_cwe, &mock_project(false), &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tids, vec!["call_system", "call_getenv"]);
+        assert_eq!(warnings[0].symbols, vec!["main"]);
+        assert!(warnings[0].description.contains("getenv"));
+    }
+
+    #[test]
+    fn sanitized_input() {
+        let warnings = run_check(check_cwe, &mock_project(true), &mock_config());
+        assert!(warnings.is_empty());
+    }
+}
@@ -22,6 +22,7 @@ use crate::{
     intermediate_representation::{Program, Sub, Term, Tid},
     utils::{
         log::{CweWarning, LogMessage},
+        metrics::SiteCounts,
         symbol_utils::{find_symbol, get_calls_to_symbols},
     },
 };
@@ -80,6 +81,12 @@ pub fn check_cwe(
             .values()
             .for_each(|sub| warnings.append(&mut handle_sub(sub, symbol)));
     }
+    // Every call to `ioctl` is flagged.
+    let mut site_counts = SiteCounts::default();
+    for _warning in warnings.iter() {
+        site_counts.add_flagged();
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (vec![], warnings)
 }
@@ -0,0 +1,232 @@
+//! This module implements a check for CWE-786: Access of Memory Location Before Start of Buffer.
+//!
+//! Buffer underflows happen if a program reads or writes memory located directly before a buffer.
+//! They are commonly caused by off-by-one errors in index computations,
+//! e.g. an access to `buf[i - 1]` where `i` may be zero.
+//! Since heap chunk metadata is usually stored directly before the chunk,
+//! buffer underwrites on the heap can often be used to corrupt the heap allocator state.
+//!
+//! See <https://cwe.mitre.org/data/definitions/786.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [Pointer Inference analysis](`crate::analysis::pointer_inference`)
+//! we evaluate the target address of each load and store instruction.
+//! If the address may point to a negative offset relative to the start of a memory object
+//! returned by one of the configured allocation functions, a warning is emitted.
+//! Accesses to the stack are not checked,
+//! since negative offsets relative to the stack frame base are the norm.
+//!
+//! ## False Positives
+//!
+//! - Any analysis imprecision of the Pointer Inference analysis regarding the access offset
+//!   may lead to false positives.
+//! - Allocator wrappers that store a header in front of the returned buffer
+//!   legitimately access negative offsets of the buffer.
+//!
+//! ## False Negatives
+//!
+//! - Only memory objects allocated in the same function as the access are checked.
+//!   Accesses through pointers that were passed as parameters are not checked.
+//! - Accesses whose offset is completely unknown are ignored.
+//! - Buffer underflows on the stack or in global memory are not detected.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The names of the allocation functions are configurable in config.json.
+
+use crate::abstract_domain::{AbstractIdentifier, TryToInterval};
+use crate::analysis::pointer_inference::Data;
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use std::collections::{BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE786",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The names of functions that return a pointer to the start of a newly allocated buffer.
+    allocation_symbols: Vec<String>,
+}
+
+/// Run the check.
+///
+/// Every load and store instruction is checked for whether its target address
+/// may lie before the start of a buffer returned by an allocation function.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE786.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let allocation_symbols = get_symbol_map(project, &config.allocation_symbols);
+    // The examined sites are the memory accesses.
+    let mut site_counts = SiteCounts::default();
+    if allocation_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let allocation_calls = collect_allocation_calls(project, &allocation_symbols);
+
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            for def in blk.term.defs.iter() {
+                let is_write = match &def.term {
+                    Def::Load { .. } => false,
+                    Def::Store { .. } => true,
+                    Def::Assign { .. } => continue,
+                };
+                let Some(address) = pi_result.eval_address_at_def(&def.tid) else {
+                    site_counts.add_skipped();
+                    continue;
+                };
+                let underflowing_targets = find_underflowing_targets(&address, &allocation_calls);
+                if underflowing_targets.is_empty() {
+                    site_counts.add_safe();
+                } else {
+                    site_counts.add_flagged();
+                }
+                for (object_id, (lower_offset, upper_offset)) in underflowing_targets {
+                    cwe_warnings.push(generate_cwe_warning(
+                        sub,
+                        &def.tid,
+                        is_write,
+                        &object_id,
+                        lower_offset,
+                        upper_offset,
+                    ));
+                }
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Collect the TIDs of all calls to the given allocation functions.
+fn collect_allocation_calls(
+    project: &Project,
+    allocation_symbols: &HashMap<Tid, &ExternSymbol>,
+) -> BTreeSet<Tid> {
+    let mut allocation_calls = BTreeSet::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            for jmp in blk.term.jmps.iter() {
+                if let Jmp::Call { target, .. } = &jmp.term {
+                    if allocation_symbols.contains_key(target) {
+                        allocation_calls.insert(jmp.tid.clone());
+                    }
+                }
+            }
+        }
+    }
+    allocation_calls
+}
+
+/// Return all heap objects that the given address may point before,
+/// together with the interval of possible offsets relative to the start of the object.
+///
+/// Targets with unbounded offsets are ignored, since they usually result from analysis imprecision.
+fn find_underflowing_targets(
+    address: &Data,
+    allocation_calls: &BTreeSet<Tid>,
+) -> Vec<(AbstractIdentifier, (i64, i64))> {
+    address
+        .get_relative_values()
+        .iter()
+        .filter(|(id, _)| allocation_calls.contains(id.get_tid()))
+        .filter_map(|(id, offset)| {
+            let (lower_offset, upper_offset) = offset.try_to_offset_interval().ok()?;
+            if lower_offset < 0 && lower_offset != i64::MIN {
+                Some((id.clone(), (lower_offset, upper_offset)))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Generate the CWE warning for an access before the start of a buffer.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    access_tid: &Tid,
+    is_write: bool,
+    object_id: &AbstractIdentifier,
+    lower_offset: i64,
+    upper_offset: i64,
+) -> CweWarning {
+    let (kind, access) = if is_write {
+        ("Buffer Underwrite", "write")
+    } else {
+        ("Buffer Under-read", "read")
+    };
+    let certainty = if upper_offset < 0 { "does" } else { "may" };
+    let allocation_tid = object_id.get_tid();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "({kind}) Memory {access} at {} ({}) {certainty} access memory before the start of the buffer allocated at {}",
+            access_tid.address, sub.term.name, allocation_tid.address
+        ),
+    )
+    .tids(vec![format!("{access_tid}"), format!("{allocation_tid}")])
+    .addresses(vec![
+        access_tid.address.clone(),
+        allocation_tid.address.clone(),
+    ])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec![
+        "offset_interval".to_string(),
+        format!("[{lower_offset}, {upper_offset}]"),
+    ]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abstract_domain::{IntervalDomain, SizedDomain};
+    use crate::variable;
+
+    #[test]
+    fn underflowing_targets() {
+        let heap_id = AbstractIdentifier::from_var(Tid::new("malloc_call"), &variable!("RAX:8"));
+        let stack_id = AbstractIdentifier::from_var(Tid::new("func"), &variable!("RSP:8"));
+        let allocation_calls = BTreeSet::from([Tid::new("malloc_call")]);
+        let offset = |start: i64, end: i64| {
+            IntervalDomain::new(Bitvector::from_i64(start), Bitvector::from_i64(end))
+        };
+
+        // Possible off-by-one access `buf[i - 1]` with `i` in `[0, 15]`.
+        let address = Data::from_target(heap_id.clone(), offset(-1, 14));
+        assert_eq!(
+            find_underflowing_targets(&address, &allocation_calls),
+            vec![(heap_id.clone(), (-1, 14))]
+        );
+        // In-bounds access
+        let address = Data::from_target(heap_id.clone(), offset(0, 14));
+        assert!(find_underflowing_targets(&address, &allocation_calls).is_empty());
+        // Negative stack offsets are not reported.
+        let address = Data::from_target(stack_id, offset(-16, -8));
+        assert!(find_underflowing_targets(&address, &allocation_calls).is_empty());
+        // Unknown offsets are not reported.
+        let address = Data::from_target(heap_id, IntervalDomain::new_top(ByteSize::new(8)));
+        assert!(find_underflowing_targets(&address, &allocation_calls).is_empty());
+    }
+}
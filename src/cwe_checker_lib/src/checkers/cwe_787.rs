@@ -0,0 +1,265 @@
+//! This module implements a check for CWE-787: Out-of-bounds Write.
+//!
+//! Writes outside of the bounds of a buffer corrupt the data adjacent to the buffer.
+//! For buffers on the stack this data includes other local variables, saved registers
+//! and the return address, so that out-of-bounds writes can often be used
+//! to hijack the control flow of the program.
+//!
+//! See <https://cwe.mitre.org/data/definitions/787.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! The [Pointer Inference analysis](`crate::analysis::pointer_inference`) treats the stack frame
+//! of a function as a single memory object.
+//! Thus the check for CWE-119 only detects writes reaching outside of the whole stack frame.
+//! This check reconstructs the layout of each stack frame to detect overflows of individual stack buffers:
+//!
+//! - Each access to the stack frame at a constant offset and each computation of a pointer
+//!   to a constant offset in the stack frame marks the start of a stack variable.
+//! - A stack variable extends up to the start of the next stack variable.
+//!   The last variable below the return address extends up to the stack pointer value at the function start.
+//!
+//! Then every store instruction whose target address is not a constant offset in the stack frame
+//! is checked against the bounds of the stack variable containing the smallest possible target address.
+//! A warning is generated if the write may reach beyond the end of that variable.
+//! At most one warning is generated per stack variable.
+//!
+//! ## False Positives
+//!
+//! - If fields of a struct or elements of an array on the stack are accessed directly,
+//!   the struct or array is split into several variables.
+//!   Indexed writes to it may then be reported as overflows of its first part.
+//! - Imprecise value intervals of the Pointer Inference analysis, e.g. for loop counters without known bounds,
+//!   may lead to false positives.
+//!
+//! ## False Negatives
+//!
+//! - Only buffers on the stack are checked.
+//!   Overflows of heap buffers are reported by the check for CWE-119.
+//! - Writes by called functions, e.g. by `memcpy` or `strcpy`, are not checked.
+//! - Stack variables whose start is never accessed directly are merged with the preceding variable,
+//!   so that overflows into them are missed.
+//! - The intermediate representation does not contain debug information,
+//!   so that the sizes of stack buffers are only reconstructed from the accesses to the stack frame.
+//!   Unused parts at the end of a buffer are counted as part of the buffer.
+//! - Writes with unknown or unbounded offsets are ignored,
+//!   since these usually result from analysis imprecision.
+
+use crate::abstract_domain::{AbstractIdentifier, IntervalDomain, TryToBitvec, TryToInterval};
+use crate::analysis::pointer_inference::{Data, ObjectKind, PointerInference};
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::CweModule;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE787",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// Run the check.
+///
+/// For each function we reconstruct the layout of its stack frame
+/// and check all store instructions with non-constant offsets against the bounds of the stack variables.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    _cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let mut cwe_warnings = Vec::new();
+    // The examined sites are the writes to the stack.
+    let mut site_counts = SiteCounts::default();
+    for sub in project.program.term.subs.values() {
+        let stack_id =
+            AbstractIdentifier::from_var(sub.tid.clone(), &project.stack_pointer_register);
+        let variable_starts = compute_stack_variable_starts(sub, pi_result, &stack_id, project);
+        // Maps the start offsets of overflowed stack variables to the corresponding warning.
+        let mut sub_warnings: BTreeMap<i64, CweWarning> = BTreeMap::new();
+        for blk in sub.term.blocks.iter() {
+            for def in blk.term.defs.iter() {
+                let Def::Store { value, .. } = &def.term else {
+                    continue;
+                };
+                let Some(address) = pi_result.eval_address_at_def(&def.tid) else {
+                    continue;
+                };
+                let Some(offset) = get_stack_offset(pi_result, &address, &stack_id) else {
+                    continue;
+                };
+                if offset.try_to_bitvec().is_ok() {
+                    // Writes to constant offsets are not considered.
+                    site_counts.add_safe();
+                    continue;
+                }
+                let Ok((lower_offset, upper_offset)) = offset.try_to_offset_interval() else {
+                    site_counts.add_skipped();
+                    continue;
+                };
+                if lower_offset == i64::MIN || upper_offset == i64::MAX {
+                    site_counts.add_skipped();
+                    continue;
+                }
+                let write_end = upper_offset.saturating_add(u64::from(value.bytesize()) as i64);
+                if let Some((start, end)) =
+                    find_overflowed_variable(&variable_starts, lower_offset, write_end)
+                {
+                    site_counts.add_flagged();
+                    sub_warnings.entry(start).or_insert_with(|| {
+                        generate_cwe_warning(sub, &def.tid, start, end, lower_offset, write_end)
+                    });
+                } else {
+                    site_counts.add_safe();
+                }
+            }
+        }
+        cwe_warnings.extend(sub_warnings.into_values());
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Get the offset of the given value relative to the stack frame given by `stack_id`
+/// if the value may point to the stack frame.
+fn get_stack_offset(
+    pi_result: &PointerInference,
+    value: &Data,
+    stack_id: &AbstractIdentifier,
+) -> Option<IntervalDomain> {
+    pi_result
+        .get_pointer_targets(value)
+        .into_iter()
+        .find(|target| target.kind == ObjectKind::Stack && target.id.as_ref() == Some(stack_id))
+        .map(|target| target.offset)
+}
+
+/// Compute the start offsets of all stack variables of the function.
+///
+/// Each access to the stack frame at a constant offset
+/// and each assignment of a pointer to a constant offset in the stack frame
+/// (except for assignments to the stack pointer register) marks the start of a stack variable.
+fn compute_stack_variable_starts(
+    sub: &Term<Sub>,
+    pi_result: &PointerInference,
+    stack_id: &AbstractIdentifier,
+    project: &Project,
+) -> BTreeSet<i64> {
+    let mut variable_starts = BTreeSet::new();
+    for blk in sub.term.blocks.iter() {
+        for def in blk.term.defs.iter() {
+            let value = match &def.term {
+                Def::Load { .. } | Def::Store { .. } => pi_result.eval_address_at_def(&def.tid),
+                Def::Assign { var, .. } if *var != project.stack_pointer_register => {
+                    pi_result.eval_value_at_def(&def.tid)
+                }
+                Def::Assign { .. } => None,
+            };
+            if let Some(offset) = value
+                .and_then(|value| get_stack_offset(pi_result, &value, stack_id))
+                .and_then(|offset| offset.try_to_offset().ok())
+            {
+                variable_starts.insert(offset);
+            }
+        }
+    }
+    variable_starts
+}
+
+/// Find the stack variable containing the offset `write_start`
+/// and return its start and end offset if the write may reach beyond its end.
+///
+/// Variables at non-negative offsets belong to the stack frame of the caller.
+/// Their end is only known if another variable follows them.
+fn find_overflowed_variable(
+    variable_starts: &BTreeSet<i64>,
+    write_start: i64,
+    write_end: i64,
+) -> Option<(i64, i64)> {
+    let start = *variable_starts.range(..=write_start).next_back()?;
+    let end = match variable_starts.range(start + 1..).next() {
+        Some(next_start) => *next_start,
+        None if start < 0 => 0,
+        None => return None,
+    };
+    if write_end > end {
+        Some((start, end))
+    } else {
+        None
+    }
+}
+
+/// Generate the CWE warning for a write beyond the end of a stack variable.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    store_tid: &Tid,
+    variable_start: i64,
+    variable_end: i64,
+    write_start: i64,
+    write_end: i64,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Out-of-bounds Write) Memory write at {} ({}) may write beyond the end of the stack buffer of size {} at stack offset {}",
+            store_tid.address,
+            sub.term.name,
+            variable_end - variable_start,
+            variable_start
+        ),
+    )
+    .tids(vec![format!("{store_tid}")])
+    .addresses(vec![store_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![
+        vec![
+            "buffer_interval".to_string(),
+            format!("[{variable_start}, {variable_end})"),
+        ],
+        vec![
+            "write_interval".to_string(),
+            format!("[{write_start}, {write_end})"),
+        ],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflowed_variables() {
+        // A buffer at [-0x40, -0x10), followed by a scalar variable and the saved frame pointer.
+        let variable_starts = BTreeSet::from([-0x40, -0x10, -0x8]);
+        assert_eq!(
+            find_overflowed_variable(&variable_starts, -0x40, -0x10),
+            None
+        );
+        assert_eq!(
+            find_overflowed_variable(&variable_starts, -0x40, -0xc),
+            Some((-0x40, -0x10))
+        );
+        assert_eq!(
+            find_overflowed_variable(&variable_starts, -0x20, -0x8),
+            Some((-0x40, -0x10))
+        );
+        // The last variable extends up to the return address.
+        assert_eq!(find_overflowed_variable(&variable_starts, -0x8, 0), None);
+        assert_eq!(
+            find_overflowed_variable(&variable_starts, -0x8, 0x8),
+            Some((-0x8, 0))
+        );
+        // Writes below all known variables are ignored.
+        assert_eq!(find_overflowed_variable(&variable_starts, -0x50, 0), None);
+        // Variables in the stack frame of the caller have no known end.
+        let variable_starts = BTreeSet::from([0x8]);
+        assert_eq!(find_overflowed_variable(&variable_starts, 0x8, 0x100), None);
+    }
+}
@@ -20,6 +20,8 @@
 //!
 //! - At most one warning for stack memory allocation is created for each Function. This means multiple weaknesses
 //! are not detected individually.
+//! - Allocations whose size is not known are not checked.
+//!   They are counted as skipped sites in the metrics of the check, see [`crate::utils::metrics`].
 
 use crate::abstract_domain::DataDomain;
 use crate::abstract_domain::IntervalDomain;
@@ -31,6 +33,7 @@ use crate::intermediate_representation::*;
 use crate::pipeline::AnalysisResults;
 use crate::utils::log::CweWarning;
 use crate::utils::log::LogMessage;
+use crate::utils::metrics::SiteCounts;
 use crate::utils::symbol_utils::get_callsites;
 use crate::utils::symbol_utils::get_symbol_map;
 use crate::CweModule;
@@ -63,6 +66,24 @@ fn is_assign_on_sp(def: &Def, sp: &Variable) -> bool {
     false
 }
 
+/// Returns `true` if the stack offsets contained in the interval are known,
+/// i.e. if it can be decided whether they exceed the threshold for stack allocations.
+fn contains_known_stack_offsets(interval: &DataDomain<IntervalDomain>) -> bool {
+    !interval.get_relative_values().is_empty()
+        && interval
+            .get_relative_values()
+            .values()
+            .all(|offset| offset.try_to_interval().is_ok())
+}
+
+/// Returns `true` if the absolute value contained in the interval is known,
+/// i.e. if it can be decided whether it exceeds the threshold for heap allocations.
+fn contains_known_absolute_value(interval: &DataDomain<IntervalDomain>) -> bool {
+    interval
+        .get_absolute_value()
+        .is_some_and(|value| value.try_to_interval().is_ok())
+}
+
 /// Determines if the interval holds values exceeding the threshold for stack allocations.
 fn exceeds_threshold_on_stack(interval: DataDomain<IntervalDomain>, threshold: u64) -> bool {
     for rel_interval in interval.get_relative_values().values() {
@@ -91,21 +112,6 @@ fn exceeds_threshold_on_call(interval: DataDomain<IntervalDomain>, threshold: u6
     false
 }
 
-/// Checks if the multiplication of element count and size parameters exceeds the threshold.
-fn multiply_args_for_calloc(
-    pir: &PointerInference,
-    jmp_tid: &Tid,
-    parms: Vec<&Arg>,
-) -> Option<DataDomain<IntervalDomain>> {
-    if let (Some(nmeb), Some(size)) = (
-        pir.eval_parameter_arg_at_call(jmp_tid, parms[0]),
-        pir.eval_parameter_arg_at_call(jmp_tid, parms[1]),
-    ) {
-        return Some(nmeb.bin_op(BinOpType::IntMult, &size));
-    }
-    None
-}
-
 /// Generate the CWE warning for a detected instance of the CWE.
 fn generate_cwe_warning(allocation: &Tid, is_stack_allocation: bool) -> CweWarning {
     CweWarning::new(
@@ -137,22 +143,26 @@ pub fn check_cwe(
     let mut cwe_warnings = Vec::new();
     let pir = analysis_results.pointer_inference.unwrap();
     let symbol_map = get_symbol_map(project, &config.symbols);
+    let mut site_counts = SiteCounts::default();
 
     'functions: for sub in project.program.term.subs.values() {
         // Function call allocation case
         for (_, jump, symbol) in get_callsites(sub, &symbol_map) {
-            if let Some(interval) = match symbol.name.as_str() {
-                "calloc" => multiply_args_for_calloc(
-                    pir,
-                    &jump.tid,
-                    vec![&symbol.parameters[0], &symbol.parameters[1]],
-                ),
-                "realloc" => pir.eval_parameter_arg_at_call(&jump.tid, &symbol.parameters[1]),
-                _ => pir.eval_parameter_arg_at_call(&jump.tid, &symbol.parameters[0]),
-            } {
-                if exceeds_threshold_on_call(interval, config.heap_threshold) {
-                    cwe_warnings.push(generate_cwe_warning(&jump.tid, false));
+            // Symbols without a known parameter layout are assumed to take the size as first parameter.
+            let size = pir.eval_allocation_size(&jump.tid, symbol).or_else(|| {
+                let parameter = symbol.parameters.first()?;
+                pir.eval_parameter_arg_at_call(&jump.tid, parameter)
+            });
+            match size {
+                Some(interval) if contains_known_absolute_value(&interval) => {
+                    if exceeds_threshold_on_call(interval, config.heap_threshold) {
+                        cwe_warnings.push(generate_cwe_warning(&jump.tid, false));
+                        site_counts.add_flagged();
+                    } else {
+                        site_counts.add_safe();
+                    }
                 }
+                _ => site_counts.add_skipped(),
             }
         }
         // Stack allocation case
@@ -164,16 +174,25 @@ pub fn check_cwe(
                 .filter(|x| is_assign_on_sp(&x.term, &project.stack_pointer_register))
                 .collect();
             for assign in assign_on_sp {
-                if let Some(interval) = pir.eval_value_at_def(&assign.tid) {
-                    if exceeds_threshold_on_stack(interval, config.stack_threshold) {
-                        cwe_warnings.push(generate_cwe_warning(&assign.tid, true));
-                        continue 'functions;
-                    }
+                let Some(interval) = pir.eval_value_at_def(&assign.tid) else {
+                    site_counts.add_skipped();
+                    continue;
+                };
+                let is_known = contains_known_stack_offsets(&interval);
+                if exceeds_threshold_on_stack(interval, config.stack_threshold) {
+                    cwe_warnings.push(generate_cwe_warning(&assign.tid, true));
+                    site_counts.add_flagged();
+                    continue 'functions;
+                } else if is_known {
+                    site_counts.add_safe();
+                } else {
+                    site_counts.add_skipped();
                 }
             }
         }
     }
     cwe_warnings.dedup();
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
 
     (Vec::new(), cwe_warnings)
 }
@@ -0,0 +1,309 @@
+//! This module implements a check for CWE-804: Guessable CAPTCHA,
+//! in the form of login challenges derived from low-entropy values.
+//!
+//! The web interfaces of embedded devices often protect their login with a CAPTCHA
+//! or a challenge-response scheme, where the client has to answer a random challenge generated by the device.
+//! If the challenge is derived from the current time, the process ID or a counter,
+//! an attacker can predict it and bypass the protection, e.g. to brute-force passwords.
+//!
+//! See <https://cwe.mitre.org/data/definitions/804.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! First we recognize embedded web server frameworks like GoAhead, boa or mongoose
+//! by the presence of characteristic symbols in the binary (see also the check for CWE-434).
+//! The check only runs if at least one framework was recognized.
+//!
+//! Then the source-to-sink taint analysis of the [`flow`](crate::analysis::taint::flow) module
+//! tracks the values returned by low-entropy sources like `time` or `rand`, as in the check for CWE-656.
+//! Challenge building functions like `sprintf` propagate the taint of their arguments to their destination buffer.
+//! In contrast to the check for CWE-656 only flows to the challenge sinks are reported,
+//! i.e. to functions sending the challenge to the client (e.g. `websWrite`) or validating the response (e.g. `strcmp`),
+//! and only if the function containing the source or the sink call references a string
+//! containing one of the configured challenge markers, e.g. `captcha`.
+//!
+//! The check is not run on a standard run of the cwe_checker,
+//! since it is only relevant for embedded web interfaces.
+//! Select it explicitly with the `--partial` command line option.
+//!
+//! ### Symbols configurable in config.json
+//!
+//! - `frameworks`: The recognized web server frameworks. For each framework:
+//!   - `name`: The name of the framework.
+//!   - `signatures`: Symbols whose presence in the binary indicate the framework.
+//! - `sources`: Functions returning low-entropy values, together with the location that the value is returned in.
+//! - `challenge_builders`: Functions that write their arguments into a buffer,
+//!   mapped to the parameter index of the destination buffer.
+//! - `sinks`: Functions that send challenges to clients or compare responses against them,
+//!   mapped to the indices of the parameters that must not contain predictable challenges.
+//! - `challenge_markers`: Strings indicating challenge handling code (case-insensitive).
+//!
+//! ## False Positives
+//!
+//! - Low-entropy values sent by challenge handling code for other purposes, e.g. timestamps, are also flagged.
+//! - Challenges that mix a low-entropy value with enough entropy from a secure source are also flagged.
+//!
+//! ## False Negatives
+//!
+//! - Challenges derived from counters in global variables are not detected.
+//! - Challenge handling code that does not reference one of the challenge markers is not checked.
+//! - Frameworks that are statically linked and stripped are not recognized.
+
+use crate::analysis::taint::flow::{
+    find_taint_flows, get_builder_summaries, CallSite, TaintFlowConfig,
+};
+use crate::analysis::taint::summary::TaintFlowTarget;
+use crate::analysis::xref::XrefIndex;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::{get_recognized_frameworks, FrameworkSignature};
+use crate::CweModule;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version.
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE804",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// The recognized web server frameworks.
+    frameworks: Vec<FrameworkSignature>,
+    /// Functions returning low-entropy values, together with the location that the value is returned in.
+    sources: BTreeMap<String, TaintFlowTarget>,
+    /// Functions assembling their arguments into a buffer,
+    /// mapped to the parameter index of the destination buffer.
+    challenge_builders: BTreeMap<String, usize>,
+    /// Functions sending challenges to clients or comparing responses against them,
+    /// mapped to the indices of the checked parameters.
+    sinks: BTreeMap<String, Vec<usize>>,
+    /// Strings indicating challenge handling code (case-insensitive).
+    challenge_markers: Vec<String>,
+}
+
+/// Run the CWE check.
+///
+/// We compute the taint flows from the low-entropy sources to the challenge sinks
+/// and generate a warning for each sink call in challenge handling code reached by such a flow.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE804.");
+    let frameworks = get_recognized_frameworks(project, &config.frameworks);
+    // The examined sites are the sinks reached by a taint flow.
+    let mut site_counts = SiteCounts::default();
+    if frameworks.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let challenge_subs = get_challenge_handling_subs(project, &config.challenge_markers);
+    if challenge_subs.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+
+    let taint_config = TaintFlowConfig {
+        sources: config.sources.clone(),
+        sinks: config.sinks.clone(),
+        propagators: get_builder_summaries(project, &config.challenge_builders),
+        ..Default::default()
+    };
+    let call_locations = get_call_locations(project);
+    let mut tainted_sinks: BTreeMap<CallSite, BTreeSet<CallSite>> = BTreeMap::new();
+    let mut reached_sinks = BTreeSet::new();
+    for flow in find_taint_flows(analysis_results, &taint_config) {
+        reached_sinks.insert(flow.sink.call_tid.clone());
+        let in_challenge_code = [&flow.source.call_tid, &flow.sink.call_tid]
+            .into_iter()
+            .filter_map(|call_tid| call_locations.get(call_tid))
+            .any(|sub_tid| challenge_subs.contains(*sub_tid));
+        if in_challenge_code {
+            tainted_sinks
+                .entry(flow.sink)
+                .or_default()
+                .insert(flow.source);
+        }
+    }
+
+    let framework_info: Vec<String> = std::iter::once("frameworks".to_string())
+        .chain(frameworks.iter().map(|framework| framework.name.clone()))
+        .collect();
+    for sink_tid in reached_sinks.iter() {
+        if tainted_sinks.keys().any(|sink| sink.call_tid == *sink_tid) {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    let cwe_warnings = tainted_sinks
+        .iter()
+        .map(|(sink, sources)| {
+            generate_cwe_warning(sink, sources).other(vec![framework_info.clone()])
+        })
+        .collect();
+    (Vec::new(), cwe_warnings)
+}
+
+/// Get the TIDs of all functions referencing a string that contains one of the given markers.
+fn get_challenge_handling_subs(project: &Project, markers: &[String]) -> BTreeSet<Tid> {
+    let markers: Vec<String> = markers.iter().map(|marker| marker.to_lowercase()).collect();
+    let xrefs = XrefIndex::new(project, None);
+    let mut subs = BTreeSet::new();
+    for address in xrefs.referenced_addresses() {
+        let Ok(string) = project
+            .runtime_memory_image
+            .read_string_until_null_terminator(&Bitvector::from_u64(address))
+        else {
+            continue;
+        };
+        let string = string.to_lowercase();
+        if markers.iter().any(|marker| string.contains(marker)) {
+            subs.extend(xrefs.get_xrefs(address).map(|xref| xref.sub.clone()));
+        }
+    }
+    subs
+}
+
+/// Map the TIDs of all call instructions to the TID of the function containing them.
+fn get_call_locations(project: &Project) -> HashMap<&Tid, &Tid> {
+    let mut call_locations = HashMap::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            for jmp in blk.term.jmps.iter() {
+                if let Jmp::Call { .. } = jmp.term {
+                    call_locations.insert(&jmp.tid, &sub.tid);
+                }
+            }
+        }
+    }
+    call_locations
+}
+
+/// Generate the CWE warning for a challenge sink reached by low-entropy values.
+fn generate_cwe_warning(sink: &CallSite, sources: &BTreeSet<CallSite>) -> CweWarning {
+    let source_descriptions: Vec<String> = sources
+        .iter()
+        .map(|source| format!("{} at {}", source.function, source.call_tid.address))
+        .collect();
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Guessable CAPTCHA) Challenge passed to {} at {} is derived from the low-entropy source {}",
+            sink.function,
+            sink.call_tid.address,
+            source_descriptions.join(", ")
+        ),
+    )
+    .tids(
+        std::iter::once(format!("{}", sink.call_tid))
+            .chain(sources.iter().map(|source| format!("{}", source.call_tid)))
+            .collect(),
+    )
+    .addresses(vec![sink.call_tid.address.clone()])
+    .symbols(vec![sink.function.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+    use crate::utils::binary::MemorySegment;
+    use crate::{def, expr};
+
+    fn mock_project() -> Project {
+        let mut subs = Vec::new();
+        // `check_captcha` compares the answer with a time-derived challenge,
+        // `check_update` does the same without referencing a challenge marker.
+        for (name, defs) in [
+            (
+                "check_captcha",
+                vec![
+                    def!["error_message: RDX:8 = 0x9000:8"],
+                    def!["challenge: RDI:8 = RAX:8"],
+                ],
+            ),
+            ("check_update", vec![def!["timestamp: RDI:8 = RAX:8"]]),
+        ] {
+            let mut blk_time = Blk::mock_with_tid(&format!("{name}_blk_time"));
+            blk_time.term.jmps.push(Jmp::call(
+                &format!("{name}_call_time"),
+                "time",
+                Some(&format!("{name}_blk_compare")),
+            ));
+            let mut blk_compare = Blk::mock_with_tid(&format!("{name}_blk_compare"));
+            blk_compare.term.defs = defs;
+            blk_compare.term.jmps.push(Jmp::call(
+                &format!("{name}_call_strcmp"),
+                "strcmp",
+                Some(&format!("{name}_blk_return")),
+            ));
+            let mut blk_return = Blk::mock_with_tid(&format!("{name}_blk_return"));
+            blk_return.term.jmps.push(Term {
+                tid: Tid::new(format!("{name}_return")),
+                term: Jmp::Return(expr!("0x0:8")),
+            });
+            let mut sub = Sub::mock(name);
+            sub.term.blocks = vec![blk_time, blk_compare, blk_return];
+            subs.push(sub);
+        }
+        let mut project = mock_x64_project(&["time", "strcmp", "websGetVar"], subs);
+        project
+            .runtime_memory_image
+            .memory_segments
+            .push(MemorySegment {
+                bytes: b"Invalid CAPTCHA\x00".to_vec(),
+                base_address: 0x9000,
+                read_flag: true,
+                write_flag: false,
+                execute_flag: false,
+            });
+        project
+    }
+
+    #[test]
+    fn time_derived_challenge() {
+        let project = mock_project();
+        let config = serde_json::json!({
+            "frameworks": [{"name": "GoAhead", "signatures": ["websGetVar"]}],
+            "sources": {"time": "ReturnValue"},
+            "challenge_builders": {"sprintf": 0},
+            "sinks": {"strcmp": [0, 1]},
+            "challenge_markers": ["captcha"]
+        });
+
+        let warnings = run_check(check_cwe, &project, &config);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec![
+                "check_captcha_call_strcmp".to_string(),
+                "check_captcha_call_time".to_string()
+            ]
+        );
+        assert_eq!(
+            warnings[0].other,
+            vec![vec!["frameworks".to_string(), "GoAhead".to_string()]]
+        );
+
+        let config = serde_json::json!({
+            "frameworks": [{"name": "mongoose", "signatures": ["mg_start"]}],
+            "sources": {"time": "ReturnValue"},
+            "challenge_builders": {},
+            "sinks": {"strcmp": [0, 1]},
+            "challenge_markers": ["captcha"]
+        });
+        let warnings = run_check(check_cwe, &project, &config);
+        assert!(warnings.is_empty());
+    }
+}
@@ -0,0 +1,382 @@
+//! This module implements a check for CWE-805: Buffer Access with Incorrect Length Value.
+//!
+//! A common source of this bug is a mismatch between two versions of a struct,
+//! e.g. when a `memcpy` copies `sizeof(struct new_version)` bytes between
+//! an object of the new version and an object of a smaller, older version of the struct.
+//! Depending on which of the two objects is smaller
+//! the copy either writes or reads past the end of it.
+//!
+//! See <https://cwe.mitre.org/data/definitions/805.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! Using the results of the [Pointer Inference analysis](`crate::analysis::pointer_inference`)
+//! we compute the destination, the source and the length parameter of each call to a copy function
+//! (e.g. `memcpy` or `memmove`).
+//! If both the destination and the source point to the start of a heap object
+//! whose size is a known constant at the call that allocated it,
+//! the sizes of the two objects differ
+//! and the length parameter equals the size of the larger object,
+//! then a warning is generated for the call.
+//!
+//! ## False Positives
+//!
+//! - The size of an object is only known at the allocation call.
+//!   If an object is reallocated to a different size later on, the check may generate false positives.
+//!
+//! ## False Negatives
+//!
+//! - Only heap objects with constant sizes are checked.
+//!   Stack and global objects are ignored, since their sizes are not known.
+//! - Copies not starting at the start of both objects are ignored.
+//! - Copy lengths that are not exactly equal to the size of the larger object are ignored.
+//!   Other out-of-bounds accesses are found by the check for [CWE-119](`crate::checkers::cwe_119`).
+//!
+//! ## Configuration
+//!
+//! The copy functions (together with the indices of their destination, source and length parameters)
+//! and the allocation functions are configurable in config.json.
+//! The size parameters of allocation functions are taken from the `allocator_functions`
+//! of the pointer inference configuration (the `Memory` section of config.json).
+//! For other allocation functions the size is assumed to be the first parameter.
+
+use crate::abstract_domain::{IntervalDomain, TryToBitvec};
+use crate::analysis::pointer_inference::{Data, PointerInference};
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
+use crate::CweModule;
+use std::collections::{BTreeMap, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE805",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The parameter indices of a function copying memory.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+struct CopyParameters {
+    /// The index of the destination parameter.
+    destination: usize,
+    /// The index of the source parameter.
+    source: usize,
+    /// The index of the length parameter.
+    length: usize,
+}
+
+/// The check-specific configuration.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions copying memory together with the indices of their parameters.
+    copy_symbols: BTreeMap<String, CopyParameters>,
+    /// Functions allocating heap memory.
+    allocation_symbols: Vec<String>,
+}
+
+/// A heap object whose size is known.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct SizedObject {
+    /// The TID of the call allocating the object.
+    allocation: Tid,
+    /// The size of the object in bytes.
+    size: u64,
+}
+
+/// Run the check.
+///
+/// First the sizes of all heap objects allocated with a constant size are computed.
+/// Then each call to a copy function is checked for a length parameter
+/// that equals the size of the larger one of two differently sized objects.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE805.");
+    let pointer_inference = analysis_results.pointer_inference.unwrap();
+
+    let copy_names: Vec<String> = config.copy_symbols.keys().cloned().collect();
+    let copy_map = get_symbol_map(project, &copy_names);
+    // The examined sites are the calls to copy functions.
+    let mut site_counts = SiteCounts::default();
+    if copy_map.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let allocation_map = get_symbol_map(project, &config.allocation_symbols);
+    let object_sizes = compute_object_sizes(project, &allocation_map, pointer_inference);
+
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, symbol) in get_callsites(sub, &copy_map) {
+            let parameters = &config.copy_symbols[&symbol.name];
+            if let Some((destination, source, length)) = check_copy_call(
+                &jmp.tid,
+                symbol,
+                parameters,
+                &object_sizes,
+                pointer_inference,
+            ) {
+                site_counts.add_flagged();
+                cwe_warnings.push(generate_cwe_warning(
+                    sub,
+                    &jmp.tid,
+                    symbol,
+                    &destination,
+                    &source,
+                    length,
+                ));
+            } else {
+                site_counts.add_safe();
+            }
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    (Vec::new(), cwe_warnings)
+}
+
+/// Compute a map from the TIDs of allocation calls
+/// to the size of the allocated object for all calls where the size is a known constant.
+fn compute_object_sizes(
+    project: &Project,
+    allocation_map: &HashMap<Tid, &ExternSymbol>,
+    pointer_inference: &PointerInference,
+) -> HashMap<Tid, u64> {
+    let mut object_sizes = HashMap::new();
+    for sub in project.program.term.subs.values() {
+        for (_, jmp, symbol) in get_callsites(sub, allocation_map) {
+            if let Some(size) = compute_allocation_size(&jmp.tid, symbol, pointer_inference) {
+                object_sizes.insert(jmp.tid.clone(), size);
+            }
+        }
+    }
+    object_sizes
+}
+
+/// Compute the size of the object allocated by the given call if it is a known constant.
+fn compute_allocation_size(
+    jmp_tid: &Tid,
+    symbol: &ExternSymbol,
+    pointer_inference: &PointerInference,
+) -> Option<u64> {
+    // Symbols without a known parameter layout are assumed to take the size as first parameter.
+    let size = match pointer_inference.eval_allocation_size(jmp_tid, symbol) {
+        Some(size) => size,
+        None => {
+            let parameter = symbol.parameters.first()?;
+            pointer_inference.eval_parameter_arg_at_call(jmp_tid, parameter)?
+        }
+    };
+    get_constant(&size)
+}
+
+/// Return the value if it is a known constant that is not a pointer.
+fn get_constant(value: &Data) -> Option<u64> {
+    value
+        .get_if_absolute_value()?
+        .try_to_bitvec()
+        .ok()?
+        .try_to_u64()
+        .ok()
+}
+
+/// Return the heap object that the value points to
+/// if it is a unique pointer to the start of a heap object with known size.
+fn get_sized_object(value: &Data, object_sizes: &HashMap<Tid, u64>) -> Option<SizedObject> {
+    let (object_id, offset) = value.get_if_unique_target()?;
+    if !is_zero(offset) {
+        return None;
+    }
+    let size = object_sizes.get(object_id.get_tid())?;
+    Some(SizedObject {
+        allocation: object_id.get_tid().clone(),
+        size: *size,
+    })
+}
+
+/// Check whether the offset is exactly zero.
+fn is_zero(offset: &IntervalDomain) -> bool {
+    matches!(offset.try_to_bitvec(), Ok(offset) if offset.is_zero())
+}
+
+/// Check whether the call copies the size of the larger object between two differently sized objects.
+/// If yes, return the destination and source objects and the length of the copy.
+fn check_copy_call(
+    jmp_tid: &Tid,
+    symbol: &ExternSymbol,
+    parameters: &CopyParameters,
+    object_sizes: &HashMap<Tid, u64>,
+    pointer_inference: &PointerInference,
+) -> Option<(SizedObject, SizedObject, u64)> {
+    let eval_parameter = |index: usize| {
+        let parameter = symbol.parameters.get(index)?;
+        pointer_inference.eval_parameter_arg_at_call(jmp_tid, parameter)
+    };
+    let destination = get_sized_object(&eval_parameter(parameters.destination)?, object_sizes)?;
+    let source = get_sized_object(&eval_parameter(parameters.source)?, object_sizes)?;
+    let length = get_constant(&eval_parameter(parameters.length)?)?;
+    if destination.size != source.size && length == u64::max(destination.size, source.size) {
+        Some((destination, source, length))
+    } else {
+        None
+    }
+}
+
+/// Generate the CWE warning for a copy between two differently sized objects.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    jmp_tid: &Tid,
+    symbol: &ExternSymbol,
+    destination: &SizedObject,
+    source: &SizedObject,
+    length: u64,
+) -> CweWarning {
+    let (access, smaller_object) = if destination.size < source.size {
+        ("writes past the end of the destination", destination)
+    } else {
+        ("reads past the end of the source", source)
+    };
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Buffer Access with Incorrect Length Value) Call to {} at {} ({}) copies {} bytes between objects of size {} and {} and {} (allocated at {})",
+            symbol.name,
+            jmp_tid.address,
+            sub.term.name,
+            length,
+            destination.size,
+            source.size,
+            access,
+            smaller_object.allocation.address
+        ),
+    )
+    .tids(vec![
+        format!("{jmp_tid}"),
+        format!("{}", destination.allocation),
+        format!("{}", source.allocation),
+    ])
+    .addresses(vec![
+        jmp_tid.address.clone(),
+        destination.allocation.address.clone(),
+        source.allocation.address.clone(),
+    ])
+    .symbols(vec![sub.term.name.clone()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check_with_allocation_symbols};
+    use crate::def;
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "copy_symbols": {"memcpy": {"destination": 0, "source": 1, "length": 2}},
+            "allocation_symbols": ["malloc"]
+        })
+    }
+
+    /// Create a project with a function allocating two objects of the given sizes
+    /// and copying `length` bytes from the second object to the first one.
+    fn mock_project(destination_size: u64, source_size: u64, length: u64) -> Project {
+        let mut blk_dst = Blk::mock_with_tid("blk_dst");
+        blk_dst.term.defs = vec![def![format!("dst_size: RDI:8 = {destination_size:#x}:8")]];
+        blk_dst
+            .term
+            .jmps
+            .push(Jmp::call("malloc_dst", "malloc", Some("blk_src")));
+        let mut blk_src = Blk::mock_with_tid("blk_src");
+        blk_src.term.defs = vec![
+            def!["save_dst: RBX:8 = RAX:8"],
+            def![format!("src_size: RDI:8 = {source_size:#x}:8")],
+        ];
+        blk_src
+            .term
+            .jmps
+            .push(Jmp::call("malloc_src", "malloc", Some("blk_copy")));
+        let mut blk_copy = Blk::mock_with_tid("blk_copy");
+        blk_copy.term.defs = vec![
+            def!["dst: RDI:8 = RBX:8"],
+            def!["src: RSI:8 = RAX:8"],
+            def![format!("length: RDX:8 = {length:#x}:8")],
+        ];
+        blk_copy
+            .term
+            .jmps
+            .push(Jmp::call("call_memcpy", "memcpy", Some("blk_end")));
+        let blk_end = Blk::mock_with_tid("blk_end");
+
+        let mut sub = Sub::mock("copy_struct");
+        sub.term.blocks = vec![blk_dst, blk_src, blk_copy, blk_end];
+        let mut project = mock_x64_project(&["malloc", "memcpy"], vec![sub]);
+        project
+            .program
+            .term
+            .extern_symbols
+            .get_mut(&Tid::new("memcpy"))
+            .unwrap()
+            .parameters = vec![
+            Arg::mock_register("RDI", 8),
+            Arg::mock_register("RSI", 8),
+            Arg::mock_register("RDX", 8),
+        ];
+        project
+    }
+
+    #[test]
+    fn copy_into_smaller_object() {
+        let warnings = run_check_with_allocation_symbols(
+            check_cwe,
+            &mock_project(0x10, 0x18, 0x18),
+            &mock_config(),
+            &["malloc"],
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec![
+                "call_memcpy".to_string(),
+                "malloc_dst".to_string(),
+                "malloc_src".to_string()
+            ]
+        );
+        assert!(warnings[0].description.contains("writes past the end"));
+    }
+
+    #[test]
+    fn copy_from_smaller_object() {
+        let warnings = run_check_with_allocation_symbols(
+            check_cwe,
+            &mock_project(0x18, 0x10, 0x18),
+            &mock_config(),
+            &["malloc"],
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].description.contains("reads past the end"));
+    }
+
+    #[test]
+    fn correct_lengths() {
+        assert!(run_check_with_allocation_symbols(
+            check_cwe,
+            &mock_project(0x18, 0x10, 0x10),
+            &mock_config(),
+            &["malloc"]
+        )
+        .is_empty());
+        assert!(run_check_with_allocation_symbols(
+            check_cwe,
+            &mock_project(0x18, 0x18, 0x18),
+            &mock_config(),
+            &["malloc"]
+        )
+        .is_empty());
+    }
+}
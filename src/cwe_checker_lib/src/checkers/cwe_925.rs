@@ -0,0 +1,264 @@
+//! This module implements a check for CWE-925: Improper Verification of Intent by Broadcast Receiver.
+//!
+//! On embedded Linux systems, privileged services often receive commands from other processes
+//! through UNIX domain sockets or netlink sockets.
+//! If such a service executes privileged operations on behalf of the sender
+//! without verifying who the sender is, any local process can abuse the service
+//! to escalate its privileges.
+//! The kernel provides the credentials of the peer process for this purpose,
+//! e.g. through the `SO_PEERCRED` socket option or `SCM_CREDENTIALS` control messages.
+//!
+//! See <https://cwe.mitre.org/data/definitions/925.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! For each call to a function receiving data from a socket (e.g. `recv` or `recvmsg`)
+//! we search for privileged operations (e.g. `system` or `setuid`)
+//! that are reachable from the call inside the same function.
+//! If such a privileged operation is reachable and the function does not call any of the functions
+//! that can be used to query the credentials of the peer (e.g. `getsockopt` or `getpeereid`),
+//! a warning is emitted.
+//!
+//! ## False Positives
+//!
+//! - The credentials of the peer may be checked in a caller or callee of the handler function.
+//! - We do not check whether the socket is a UNIX domain or netlink socket.
+//!   Handlers for network sockets are also checked,
+//!   but they usually cannot check peer credentials at all.
+//! - `SCM_CREDENTIALS` control messages are accessed through macros.
+//!   If they are received by another function than the one executing the privileged operation,
+//!   we will not find the check.
+//!
+//! ## False Negatives
+//!
+//! - We do not check whether a call to a credential checking function actually queries the
+//!   peer credentials (e.g. whether `getsockopt` is called with `SO_PEERCRED`),
+//!   nor whether the result is used to decide whether the privileged operation is executed.
+//! - Privileged operations in other functions than the one receiving the command are not found.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The functions receiving commands, the privileged operations
+//! and the functions for querying peer credentials are configurable in config.json.
+
+use crate::analysis::graph::{Edge, Graph, Node};
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::graph_utils::is_sink_call_reachable_from_source_call;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE925",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions that receive commands from a socket.
+    input_symbols: Vec<String>,
+    /// Privileged operations that should only be executed on behalf of verified peers.
+    privileged_symbols: Vec<String>,
+    /// Functions that can be used to verify the credentials of the peer.
+    credential_check_symbols: Vec<String>,
+}
+
+/// Check whether the given function calls one of the given symbols.
+fn sub_calls_any_symbol(sub: &Term<Sub>, symbols: &HashMap<Tid, &ExternSymbol>) -> bool {
+    sub.term.blocks.iter().any(|blk| {
+        blk.term.jmps.iter().any(|jmp| match &jmp.term {
+            Jmp::Call { target, .. } => symbols.contains_key(target),
+            _ => false,
+        })
+    })
+}
+
+/// Generate the CWE warning for a privileged operation
+/// that is reachable from a receiving call without verification of the peer credentials.
+fn generate_cwe_warning(
+    sub: &Term<Sub>,
+    input_callsite: &Tid,
+    input_symbol: &ExternSymbol,
+    sink_callsite: &Tid,
+    sink_symbol: &ExternSymbol,
+) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Missing Peer Credential Check) Data received by {} at {} reaches the privileged operation {} at {} ({}) without verification of the peer credentials",
+            input_symbol.name, input_callsite.address, sink_symbol.name, sink_callsite.address, sub.term.name
+        ),
+    )
+    .tids(vec![format!("{sink_callsite}"), format!("{input_callsite}")])
+    .addresses(vec![
+        sink_callsite.address.clone(),
+        input_callsite.address.clone(),
+    ])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec![
+        "privileged_operation".to_string(),
+        sink_symbol.name.clone(),
+    ]])
+}
+
+/// Find all privileged operations reachable from calls to input symbols in functions
+/// that do not call any of the credential checking functions.
+///
+/// Only the first input call reaching a privileged operation is reported for each operation.
+/// The calls to input symbols are counted as the examined sites.
+fn find_unverified_privileged_operations<'a>(
+    graph: &Graph<'a>,
+    input_symbols: &HashMap<Tid, &'a ExternSymbol>,
+    privileged_symbols: &HashMap<Tid, &'a ExternSymbol>,
+    credential_check_symbols: &HashMap<Tid, &'a ExternSymbol>,
+    site_counts: &mut SiteCounts,
+) -> BTreeMap<Tid, CweWarning> {
+    let mut warnings = BTreeMap::new();
+    for node in graph.node_indices() {
+        let Node::BlkEnd(_, sub) = graph[node] else {
+            continue;
+        };
+        for edge in graph.edges(node) {
+            let Edge::ExternCallStub(jmp) = edge.weight() else {
+                continue;
+            };
+            let Jmp::Call { target, .. } = &jmp.term else {
+                continue;
+            };
+            let Some(input_symbol) = input_symbols.get(target) else {
+                continue;
+            };
+            if sub_calls_any_symbol(sub, credential_check_symbols) {
+                site_counts.add_safe();
+                continue;
+            }
+            let mut is_flagged = false;
+            for (sink_tid, sink_symbol) in privileged_symbols.iter() {
+                if let Some(sink_callsite) =
+                    is_sink_call_reachable_from_source_call(graph, edge.target(), target, sink_tid)
+                {
+                    is_flagged = true;
+                    warnings.entry(sink_callsite.clone()).or_insert_with(|| {
+                        generate_cwe_warning(
+                            sub,
+                            &jmp.tid,
+                            input_symbol,
+                            &sink_callsite,
+                            sink_symbol,
+                        )
+                    });
+                }
+            }
+            if is_flagged {
+                site_counts.add_flagged();
+            } else {
+                site_counts.add_safe();
+            }
+        }
+    }
+    warnings
+}
+
+/// Run the check.
+///
+/// For each call to an input symbol we search for privileged operations
+/// reachable from it in the same function.
+/// If the function does not call a function that can be used to verify the peer credentials,
+/// a warning is generated for each reachable privileged operation.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let graph = analysis_results.control_flow_graph;
+    let config: Config = serde_json::from_value(cwe_params.clone()).unwrap();
+
+    let input_symbols = get_symbol_map(project, &config.input_symbols);
+    let privileged_symbols = get_symbol_map(project, &config.privileged_symbols);
+    // The examined sites are the calls to input functions.
+    let mut site_counts = SiteCounts::default();
+    if input_symbols.is_empty() || privileged_symbols.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let credential_check_symbols = get_symbol_map(project, &config.credential_check_symbols);
+
+    let warnings = find_unverified_privileged_operations(
+        graph,
+        &input_symbols,
+        &privileged_symbols,
+        &credential_check_symbols,
+        &mut site_counts,
+    );
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+
+    (Vec::new(), warnings.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::{mock_x64_project, run_check};
+
+    /// Create a project with a function `handler` calling `recv` and then `system`.
+    /// If `with_credential_check` is set, the handler additionally calls `getsockopt` before `recv`.
+    fn mock_project(with_credential_check: bool) -> Project {
+        let mut blk_getsockopt = Blk::mock_with_tid("blk_getsockopt");
+        blk_getsockopt
+            .term
+            .jmps
+            .push(Jmp::call("call_getsockopt", "getsockopt", Some("blk_recv")));
+        let mut blk_recv = Blk::mock_with_tid("blk_recv");
+        blk_recv
+            .term
+            .jmps
+            .push(Jmp::call("call_recv", "recv", Some("blk_system")));
+        let mut blk_system = Blk::mock_with_tid("blk_system");
+        blk_system
+            .term
+            .jmps
+            .push(Jmp::call("call_system", "system", Some("blk_end")));
+        let blk_end = Blk::mock_with_tid("blk_end");
+
+        let mut sub = Sub::mock("handler");
+        if with_credential_check {
+            sub.term.blocks.push(blk_getsockopt);
+        }
+        sub.term.blocks.extend([blk_recv, blk_system, blk_end]);
+        mock_x64_project(&["recv", "system", "getsockopt"], vec![sub])
+    }
+
+    fn mock_config() -> serde_json::Value {
+        serde_json::json!({
+            "input_symbols": ["recv"],
+            "privileged_symbols": ["system"],
+            "credential_check_symbols": ["getsockopt"],
+        })
+    }
+
+    #[test]
+    fn unverified_privileged_operation() {
+        let project = mock_project(false);
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].tids,
+            vec!["call_system".to_string(), "call_recv".to_string()]
+        );
+    }
+
+    #[test]
+    fn verified_privileged_operation() {
+        let project = mock_project(true);
+        let warnings = run_check(check_cwe, &project, &mock_config());
+        assert!(warnings.is_empty());
+    }
+}
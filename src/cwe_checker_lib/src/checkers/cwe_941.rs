@@ -0,0 +1,359 @@
+//! This module implements a check for CWE-941: Incorrectly Specified Destination in a Communication Channel.
+//!
+//! Global function pointers that are located in writable memory determine
+//! the destination of indirect calls at runtime.
+//! If such a function pointer is overwritten with a value derived from user input,
+//! the attacker controls the target of the indirect call.
+//! Even if the input is not used as a function pointer directly,
+//! the write turns any memory corruption bug into a direct control flow hijack.
+//!
+//! See <https://cwe.mitre.org/data/definitions/941.html> for a detailed description.
+//!
+//! ## How the check works
+//!
+//! First we collect the function pointer globals of the binary.
+//! These are global variables that are loaded and then used as the target of an indirect call
+//! in the same basic block, or that get the address of a function assigned to them.
+//!
+//! Then we use a taint analysis starting at the return values of user input sources
+//! (e.g. `getenv` or `strtoul`) and the buffers filled by input functions (e.g. `read` or `recv`).
+//! If a tainted value is stored to a function pointer global, a warning is emitted.
+//!
+//! ## False Positives
+//!
+//! - The tainted value may be validated (e.g. compared against a list of allowed functions)
+//!   before it is stored to the function pointer global.
+//!
+//! ## False Negatives
+//!
+//! - Function pointer globals that are only used as call targets in other basic blocks
+//!   than the one loading them are not found.
+//! - Function pointer tables, where the accessed index is not exactly known, are not found.
+//! - For input buffers only the first pointer-sized value is tainted.
+//! - The taint analysis is intraprocedural.
+//!
+//! ## Symbols configurable in config.json
+//!
+//! The user input sources and the input functions together with the index of their buffer parameter
+//! are configurable in config.json.
+
+use crate::analysis::forward_interprocedural_fixpoint::create_computation;
+use crate::analysis::graph::{Edge, Graph, HasCfg};
+use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+use crate::analysis::pointer_inference::{
+    Data as PiData, PointerInference as PointerInferenceComputation,
+};
+use crate::analysis::taint::{
+    state::State as TaState, summary::TaintSummaries, Taint, TaintAnalysis,
+};
+use crate::analysis::vsa_results::{HasVsaResult, VsaResult};
+use crate::analysis::xref::get_accessed_global_addresses;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::binary::parse_hex_string_to_u64;
+use crate::utils::log::{CweWarning, LogMessage};
+use crate::utils::metrics::SiteCounts;
+use crate::utils::symbol_utils::get_symbol_map;
+use crate::CweModule;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The module name and version
+pub static CWE_MODULE: CweModule = CweModule {
+    name: "CWE941",
+    version: "0.1",
+    run: check_cwe,
+};
+
+/// The configuration struct.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Config {
+    /// Functions whose return values are controlled by the user.
+    sources: Vec<String>,
+    /// Functions that write user input into a buffer,
+    /// mapped to the index of the parameter pointing to the buffer.
+    buffer_sources: BTreeMap<String, usize>,
+}
+
+/// Run the check.
+///
+/// We collect all function pointer globals of the binary
+/// and then search with a taint analysis for stores of user input to them.
+pub fn check_cwe(
+    analysis_results: &AnalysisResults,
+    cwe_params: &serde_json::Value,
+) -> (Vec<LogMessage>, Vec<CweWarning>) {
+    let project = analysis_results.project;
+    let config: Config = serde_json::from_value(cwe_params.clone())
+        .expect("Invalid configuration inside config.json for CWE941.");
+    let pi_result = analysis_results.pointer_inference.unwrap();
+
+    let function_pointer_globals = find_function_pointer_globals(project, Some(pi_result));
+    // The examined sites are the global variables holding function pointers.
+    let mut site_counts = SiteCounts::default();
+    if function_pointer_globals.is_empty() {
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+    let source_map = get_symbol_map(project, &config.sources);
+    let buffer_source_names: Vec<String> = config.buffer_sources.keys().cloned().collect();
+    let buffer_source_map = get_symbol_map(project, &buffer_source_names);
+    if source_map.is_empty() && buffer_source_map.is_empty() {
+        for _ in function_pointer_globals.iter() {
+            site_counts.add_safe();
+        }
+        analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+        return (Vec::new(), Vec::new());
+    }
+
+    let graph = analysis_results.control_flow_graph;
+    let (cwe_sender, cwe_receiver) = crossbeam_channel::unbounded();
+    let context = Context {
+        project,
+        pi_result,
+        control_flow_graph: graph,
+        function_pointer_globals: function_pointer_globals.clone(),
+        taint_summaries: analysis_results.taint_summaries,
+        cwe_collector: cwe_sender,
+    };
+    let mut computation = create_computation(context, None);
+    computation.set_cancellation_token(analysis_results.cancellation_token);
+
+    for edge in graph.edge_references() {
+        let Edge::ExternCallStub(jmp) = edge.weight() else {
+            continue;
+        };
+        let Jmp::Call { target, .. } = &jmp.term else {
+            continue;
+        };
+        let return_node = edge.target();
+        let mut state = match source_map.get(target) {
+            Some(symbol) => TaState::new_return(symbol, pi_result, return_node),
+            None => TaState::new_empty(),
+        };
+        if let Some(symbol) = buffer_source_map.get(target) {
+            let buffer = config.buffer_sources[&symbol.name];
+            if let Some(param) = symbol.parameters.get(buffer) {
+                if let Some(address) = pi_result.eval_parameter_arg_at_call(&jmp.tid, param) {
+                    state.save_taint_to_memory(
+                        &address,
+                        Taint::Tainted(project.get_pointer_bytesize()),
+                    );
+                }
+            }
+        }
+        if !state.is_empty() {
+            computation.set_node_value(return_node, NodeValue::Value(state));
+        }
+    }
+
+    computation.compute_with_max_steps(100);
+
+    let tainted_stores: BTreeMap<Tid, u64> = cwe_receiver.try_iter().collect();
+    for global in function_pointer_globals.iter() {
+        if tainted_stores.values().any(|address| address == global) {
+            site_counts.add_flagged();
+        } else {
+            site_counts.add_safe();
+        }
+    }
+    analysis_results.report_site_counts(CWE_MODULE.name, site_counts);
+    let mut cwe_warnings = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            for def in blk.term.defs.iter() {
+                if let Some(global) = tainted_stores.get(&def.tid) {
+                    cwe_warnings.push(generate_cwe_warning(sub, &def.tid, *global));
+                }
+            }
+        }
+    }
+
+    (Vec::new(), cwe_warnings)
+}
+
+/// Collect the addresses of all global variables that are used as function pointers.
+///
+/// A global variable is considered a function pointer if its value is loaded into a register
+/// that is used as the target of an indirect call in the same block,
+/// or if the address of a function of the program is stored to it.
+fn find_function_pointer_globals(
+    project: &Project,
+    pointer_inference: Option<&PointerInferenceComputation>,
+) -> BTreeSet<u64> {
+    let function_addresses: BTreeSet<u64> = project
+        .program
+        .term
+        .subs
+        .keys()
+        .filter_map(|sub_tid| parse_hex_string_to_u64(&sub_tid.address).ok())
+        .collect();
+    let mut function_pointer_globals = BTreeSet::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            // Maps registers to the global addresses that their current value was loaded from.
+            let mut loaded_from: HashMap<&Variable, BTreeSet<u64>> = HashMap::new();
+            for def in blk.term.defs.iter() {
+                match &def.term {
+                    Def::Load { var, .. } => {
+                        let addresses =
+                            get_accessed_global_addresses(def, project, pointer_inference);
+                        loaded_from.insert(var, addresses);
+                    }
+                    Def::Assign {
+                        var,
+                        value: Expression::Var(input),
+                    } => {
+                        let addresses = loaded_from.get(input).cloned().unwrap_or_default();
+                        loaded_from.insert(var, addresses);
+                    }
+                    Def::Assign { var, .. } => {
+                        loaded_from.remove(var);
+                    }
+                    Def::Store {
+                        value: Expression::Const(value),
+                        ..
+                    } => {
+                        if matches!(value.try_to_u64(), Ok(value) if function_addresses.contains(&value))
+                        {
+                            function_pointer_globals.extend(get_accessed_global_addresses(
+                                def,
+                                project,
+                                pointer_inference,
+                            ));
+                        }
+                    }
+                    Def::Store { .. } => (),
+                }
+            }
+            for jmp in blk.term.jmps.iter() {
+                if let Jmp::CallInd {
+                    target: Expression::Var(target),
+                    ..
+                } = &jmp.term
+                {
+                    if let Some(addresses) = loaded_from.get(target) {
+                        function_pointer_globals.extend(addresses.iter().copied());
+                    }
+                }
+            }
+        }
+    }
+    function_pointer_globals
+}
+
+/// The Context struct for the taint analysis.
+struct Context<'a> {
+    /// A pointer to the corresponding project struct.
+    project: &'a Project,
+    /// A pointer to the results of the pointer inference analysis.
+    pi_result: &'a PointerInferenceComputation<'a>,
+    /// The underlying control flow graph for the algorithm.
+    control_flow_graph: &'a Graph<'a>,
+    /// The addresses of all function pointer globals.
+    function_pointer_globals: BTreeSet<u64>,
+    /// Taint summaries of functions imported from other binaries.
+    taint_summaries: Option<&'a TaintSummaries>,
+    /// A channel where the TIDs of tainted stores to function pointer globals
+    /// are sent to together with the address of the global.
+    cwe_collector: crossbeam_channel::Sender<(Tid, u64)>,
+}
+
+impl<'a> HasCfg<'a> for Context<'a> {
+    fn get_cfg(&self) -> &Graph<'a> {
+        self.control_flow_graph
+    }
+}
+
+impl<'a> HasVsaResult<PiData> for Context<'a> {
+    fn vsa_result(&self) -> &impl VsaResult<ValueDomain = PiData> {
+        self.pi_result
+    }
+}
+
+impl<'a> AsRef<Project> for Context<'a> {
+    fn as_ref(&self) -> &Project {
+        self.project
+    }
+}
+
+impl<'a> TaintAnalysis<'a> for Context<'a> {
+    fn taint_summaries(&self) -> Option<&TaintSummaries> {
+        self.taint_summaries
+    }
+
+    /// Generate a CWE warning if a tainted value is stored to a function pointer global.
+    fn update_def_post(
+        &self,
+        old_state: &TaState,
+        new_state: TaState,
+        def: &Term<Def>,
+    ) -> Option<TaState> {
+        if let Def::Store { value, .. } = &def.term {
+            if old_state.eval(value).is_tainted() {
+                let targets =
+                    get_accessed_global_addresses(def, self.project, Some(self.pi_result));
+                if let Some(global) = targets
+                    .iter()
+                    .find(|address| self.function_pointer_globals.contains(address))
+                {
+                    let _ = self.cwe_collector.send((def.tid.clone(), *global));
+                }
+            }
+        }
+        if new_state.is_empty() {
+            self.handle_empty_state_out(&def.tid)
+        } else {
+            Some(new_state)
+        }
+    }
+}
+
+/// Generate the CWE warning for a tainted store to the function pointer global at the given address.
+fn generate_cwe_warning(sub: &Term<Sub>, store_tid: &Tid, global: u64) -> CweWarning {
+    CweWarning::new(
+        CWE_MODULE.name,
+        CWE_MODULE.version,
+        format!(
+            "(Tainted Function Pointer) User input is written to the function pointer global at 0x{:x} at {} ({})",
+            global, store_tid.address, sub.term.name
+        ),
+    )
+    .tids(vec![format!("{store_tid}")])
+    .addresses(vec![store_tid.address.clone()])
+    .symbols(vec![sub.term.name.clone()])
+    .other(vec![vec![
+        "function_pointer_global".to_string(),
+        format!("0x{global:x}"),
+    ]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkers::test_utils::mock_x64_project;
+    use crate::{def, variable};
+
+    #[test]
+    fn function_pointer_globals() {
+        let mut blk = Blk::mock();
+        blk.term.defs = vec![
+            def!["load_fp: RAX:8 := Load from 0x2000:8"],
+            def!["copy_fp: RCX:8 = RAX:8"],
+            def!["load_data: RDX:8 := Load from 0x2004:8"],
+        ];
+        blk.term.jmps.push(Term {
+            tid: Tid::new("call_fp"),
+            term: Jmp::CallInd {
+                target: Expression::Var(variable!("RCX:8")),
+                return_: None,
+            },
+        });
+        let mut sub = Sub::mock("func");
+        sub.term.blocks.push(blk);
+        let project = mock_x64_project(&[], vec![sub]);
+
+        let globals = find_function_pointer_globals(&project, None);
+        assert_eq!(globals, BTreeSet::from([0x2000]));
+    }
+}
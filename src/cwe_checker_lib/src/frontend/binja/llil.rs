@@ -0,0 +1,893 @@
+//! LLIL expression trees and their translation into basic blocks of the internally used IR.
+
+use super::{format_address, Function, Instruction};
+use crate::intermediate_representation::{
+    BinOpType, Bitvector, BitvectorExtended, Blk, CastOpType, Def, Expression, Jmp, UnOpType,
+    Variable,
+};
+use crate::pcode::RegisterProperties;
+use crate::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// An LLIL expression tree as contained in Binary Ninja dumps.
+///
+/// The variants correspond to the LLIL operations of the same name in the Binary Ninja API.
+/// Operations that are not supported by the translation are deserialized as [`Llil::Unsupported`].
+#[allow(missing_docs)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(tag = "operation", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Llil {
+    Nop,
+    SetReg {
+        size: u64,
+        dest: String,
+        src: Box<Llil>,
+    },
+    SetRegSplit {
+        size: u64,
+        hi: String,
+        lo: String,
+        src: Box<Llil>,
+    },
+    SetFlag {
+        dest: String,
+        src: Box<Llil>,
+    },
+    Store {
+        size: u64,
+        dest: Box<Llil>,
+        src: Box<Llil>,
+    },
+    Push {
+        size: u64,
+        src: Box<Llil>,
+    },
+    Pop {
+        size: u64,
+    },
+    Reg {
+        size: u64,
+        src: String,
+    },
+    RegSplit {
+        size: u64,
+        hi: String,
+        lo: String,
+    },
+    Flag {
+        src: String,
+    },
+    Const {
+        size: u64,
+        constant: u64,
+    },
+    ConstPtr {
+        size: u64,
+        constant: u64,
+    },
+    Load {
+        size: u64,
+        src: Box<Llil>,
+    },
+    Add {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Sub {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    And {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Or {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Xor {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Lsl {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Lsr {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Asr {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Mul {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Divu {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Divs {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Modu {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Mods {
+        size: u64,
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpE {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpNe {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpSlt {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpUlt {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpSle {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpUle {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpSge {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpUge {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpSgt {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    CmpUgt {
+        left: Box<Llil>,
+        right: Box<Llil>,
+    },
+    Neg {
+        size: u64,
+        src: Box<Llil>,
+    },
+    Not {
+        size: u64,
+        src: Box<Llil>,
+    },
+    Sx {
+        size: u64,
+        src: Box<Llil>,
+    },
+    Zx {
+        size: u64,
+        src: Box<Llil>,
+    },
+    LowPart {
+        size: u64,
+        src: Box<Llil>,
+    },
+    Jump {
+        dest: Box<Llil>,
+    },
+    JumpTo {
+        dest: Box<Llil>,
+        /// The possible target addresses of the jump and the LLIL instruction indices of the targets.
+        targets: BTreeMap<u64, usize>,
+    },
+    Call {
+        dest: Box<Llil>,
+    },
+    Tailcall {
+        dest: Box<Llil>,
+    },
+    Ret {
+        dest: Box<Llil>,
+    },
+    #[serde(rename = "NORET")]
+    NoRet,
+    Goto {
+        dest: usize,
+    },
+    If {
+        condition: Box<Llil>,
+        #[serde(rename = "true")]
+        true_target: usize,
+        #[serde(rename = "false")]
+        false_target: usize,
+    },
+    Syscall,
+    Bp,
+    Trap,
+    Undef,
+    Unimpl,
+    /// An operation that is not supported by the translation.
+    #[serde(other)]
+    Unsupported,
+}
+
+impl Llil {
+    /// Return the size of the result of the expression if it is given in the dump.
+    fn size(&self) -> Option<ByteSize> {
+        use Llil::*;
+        let size = match self {
+            Reg { size, .. }
+            | RegSplit { size, .. }
+            | Const { size, .. }
+            | ConstPtr { size, .. }
+            | Load { size, .. }
+            | Pop { size }
+            | Add { size, .. }
+            | Sub { size, .. }
+            | And { size, .. }
+            | Or { size, .. }
+            | Xor { size, .. }
+            | Lsl { size, .. }
+            | Lsr { size, .. }
+            | Asr { size, .. }
+            | Mul { size, .. }
+            | Divu { size, .. }
+            | Divs { size, .. }
+            | Modu { size, .. }
+            | Mods { size, .. }
+            | Neg { size, .. }
+            | Not { size, .. }
+            | Sx { size, .. }
+            | Zx { size, .. }
+            | LowPart { size, .. } => *size,
+            Flag { .. }
+            | CmpE { .. }
+            | CmpNe { .. }
+            | CmpSlt { .. }
+            | CmpUlt { .. }
+            | CmpSle { .. }
+            | CmpUle { .. }
+            | CmpSge { .. }
+            | CmpUge { .. }
+            | CmpSgt { .. }
+            | CmpUgt { .. } => 1,
+            _ => 0,
+        };
+        (size != 0).then(|| ByteSize::new(size))
+    }
+
+    /// If the expression is a binary operation, return the corresponding IR operation,
+    /// whether the operands have to be swapped, and the operands.
+    fn binary_operation(&self) -> Option<(BinOpType, bool, &Llil, &Llil)> {
+        use BinOpType::*;
+        use Llil::*;
+        let (op, swap, left, right) = match self {
+            Add { left, right, .. } => (IntAdd, false, left, right),
+            Sub { left, right, .. } => (IntSub, false, left, right),
+            And { left, right, .. } => (IntAnd, false, left, right),
+            Or { left, right, .. } => (IntOr, false, left, right),
+            Xor { left, right, .. } => (IntXOr, false, left, right),
+            Lsl { left, right, .. } => (IntLeft, false, left, right),
+            Lsr { left, right, .. } => (IntRight, false, left, right),
+            Asr { left, right, .. } => (IntSRight, false, left, right),
+            Mul { left, right, .. } => (IntMult, false, left, right),
+            Divu { left, right, .. } => (IntDiv, false, left, right),
+            Divs { left, right, .. } => (IntSDiv, false, left, right),
+            Modu { left, right, .. } => (IntRem, false, left, right),
+            Mods { left, right, .. } => (IntSRem, false, left, right),
+            CmpE { left, right } => (IntEqual, false, left, right),
+            CmpNe { left, right } => (IntNotEqual, false, left, right),
+            CmpSlt { left, right } => (IntSLess, false, left, right),
+            CmpUlt { left, right } => (IntLess, false, left, right),
+            CmpSle { left, right } => (IntSLessEqual, false, left, right),
+            CmpUle { left, right } => (IntLessEqual, false, left, right),
+            CmpSge { left, right } => (IntSLessEqual, true, left, right),
+            CmpUge { left, right } => (IntLessEqual, true, left, right),
+            CmpSgt { left, right } => (IntSLess, true, left, right),
+            CmpUgt { left, right } => (IntLess, true, left, right),
+            _ => return None,
+        };
+        Some((op, swap, left, right))
+    }
+}
+
+/// Return the given size if it is not zero and the fallback size otherwise.
+fn size_or(size: u64, fallback: ByteSize) -> ByteSize {
+    if size == 0 {
+        fallback
+    } else {
+        ByteSize::new(size)
+    }
+}
+
+/// Translates the LLIL of a function into IR basic blocks.
+pub(super) struct FunctionTranslator<'a> {
+    function: &'a Function,
+    register_map: &'a HashMap<&'a String, &'a RegisterProperties>,
+    stack_pointer: &'a Variable,
+    /// The term identifiers of the functions and imports, indexed by their addresses.
+    call_targets: &'a HashMap<u64, Tid>,
+    /// The term identifiers of the LLIL basic blocks, indexed by the index of their first instruction.
+    block_tids: HashMap<usize, Tid>,
+    /// The addresses of the LLIL instructions, indexed by their instruction index.
+    instruction_addresses: HashMap<usize, u64>,
+    /// The number of terms already generated for each address.
+    term_counts: HashMap<u64, u64>,
+    /// The number of temporary variables already generated.
+    temp_count: u64,
+}
+
+impl<'a> FunctionTranslator<'a> {
+    /// Create a translator for the given function.
+    pub fn new(
+        function: &'a Function,
+        register_map: &'a HashMap<&'a String, &'a RegisterProperties>,
+        stack_pointer: &'a Variable,
+        call_targets: &'a HashMap<u64, Tid>,
+    ) -> Self {
+        let mut block_tids = HashMap::new();
+        let mut block_addresses = HashSet::new();
+        for block in function.blocks.iter() {
+            if let Some(first) = block.instructions.first() {
+                let tid = if block_addresses.insert(first.address) {
+                    Tid::blk_id_at_address(&format_address(first.address))
+                } else {
+                    continuation_block_tid(first.address, first.index)
+                };
+                block_tids.insert(first.index, tid);
+            }
+        }
+        let instruction_addresses = function
+            .blocks
+            .iter()
+            .flat_map(|block| block.instructions.iter())
+            .map(|instruction| (instruction.index, instruction.address))
+            .collect();
+        FunctionTranslator {
+            function,
+            register_map,
+            stack_pointer,
+            call_targets,
+            block_tids,
+            instruction_addresses,
+            term_counts: HashMap::new(),
+            temp_count: 0,
+        }
+    }
+
+    /// Translate the function into IR basic blocks.
+    ///
+    /// LLIL basic blocks are split after calls,
+    /// since calls are the last instructions of IR basic blocks.
+    pub fn translate(mut self) -> Vec<Term<Blk>> {
+        let mut blocks = Vec::new();
+        for block in self.function.blocks.iter() {
+            let mut current: Option<Term<Blk>> = None;
+            for instruction in block.instructions.iter() {
+                let blk = current
+                    .get_or_insert_with(|| new_block(self.block_tid_at_index(instruction.index)));
+                if self.translate_instruction(instruction, blk) {
+                    blocks.extend(current.take());
+                }
+            }
+            if let (Some(mut blk), Some(last)) = (current, block.instructions.last()) {
+                // The block falls through to the next block.
+                if let Some(target) = self.block_tids.get(&(last.index + 1)).cloned() {
+                    self.push_jmp(&mut blk, last.address, Jmp::Branch(target));
+                }
+                blocks.push(blk);
+            }
+        }
+        blocks
+    }
+
+    /// Translate the given instruction and add the resulting terms to the given block.
+    ///
+    /// Returns `true` if the instruction ends the block.
+    fn translate_instruction(&mut self, instruction: &Instruction, blk: &mut Term<Blk>) -> bool {
+        let address = instruction.address;
+        let pointer_size = self.stack_pointer.size;
+        match &instruction.il {
+            Llil::SetReg { size, dest, src } => {
+                let var = self.variable(dest, ByteSize::new(*size));
+                let def = if let Llil::Load { src: location, .. } = src.as_ref() {
+                    Def::Load {
+                        address: self.expression(location, pointer_size, blk, address),
+                        var,
+                    }
+                } else {
+                    Def::Assign {
+                        value: self.expression(src, var.size, blk, address),
+                        var,
+                    }
+                };
+                self.push_def(blk, address, def);
+            }
+            Llil::SetRegSplit { size, hi, lo, src } => {
+                let value = self.expression(src, ByteSize::new(*size), blk, address);
+                let half_size = ByteSize::new(u64::from(value.bytesize()) / 2);
+                let temp = self.temp(value.bytesize());
+                let lo = self.variable(lo, half_size);
+                let hi = self.variable(hi, half_size);
+                self.push_def(
+                    blk,
+                    address,
+                    Def::Assign {
+                        var: temp.clone(),
+                        value,
+                    },
+                );
+                let lo_value = Expression::Subpiece {
+                    low_byte: ByteSize::new(0),
+                    size: lo.size,
+                    arg: Box::new(Expression::Var(temp.clone())),
+                };
+                let hi_value = Expression::Subpiece {
+                    low_byte: lo.size,
+                    size: hi.size,
+                    arg: Box::new(Expression::Var(temp)),
+                };
+                self.push_def(
+                    blk,
+                    address,
+                    Def::Assign {
+                        var: lo,
+                        value: lo_value,
+                    },
+                );
+                self.push_def(
+                    blk,
+                    address,
+                    Def::Assign {
+                        var: hi,
+                        value: hi_value,
+                    },
+                );
+            }
+            Llil::SetFlag { dest, src } => {
+                let var = flag(dest);
+                let value = self.expression(src, var.size, blk, address);
+                self.push_def(blk, address, Def::Assign { var, value });
+            }
+            Llil::Store { size, dest, src } => {
+                let location = self.expression(dest, pointer_size, blk, address);
+                let value = self.expression(src, ByteSize::new(*size), blk, address);
+                self.push_def(
+                    blk,
+                    address,
+                    Def::Store {
+                        address: location,
+                        value,
+                    },
+                );
+            }
+            Llil::Push { size, src } => {
+                let mut value = self.expression(src, ByteSize::new(*size), blk, address);
+                if value.input_vars().contains(&self.stack_pointer) {
+                    let temp = self.temp(value.bytesize());
+                    self.push_def(
+                        blk,
+                        address,
+                        Def::Assign {
+                            var: temp.clone(),
+                            value,
+                        },
+                    );
+                    value = Expression::Var(temp);
+                }
+                let stack_pointer = self.stack_pointer.clone();
+                let new_stack_pointer =
+                    Expression::Var(stack_pointer.clone()).plus_const(-(*size as i64));
+                self.push_def(
+                    blk,
+                    address,
+                    Def::Assign {
+                        var: stack_pointer.clone(),
+                        value: new_stack_pointer,
+                    },
+                );
+                self.push_def(
+                    blk,
+                    address,
+                    Def::Store {
+                        address: Expression::Var(stack_pointer),
+                        value,
+                    },
+                );
+            }
+            Llil::Jump { dest } => {
+                let jmp = match self.call_target(dest) {
+                    Some(target) => Jmp::Call {
+                        target,
+                        return_: None,
+                    },
+                    None => Jmp::BranchInd(self.expression(dest, pointer_size, blk, address)),
+                };
+                self.push_jmp(blk, address, jmp);
+                return true;
+            }
+            Llil::JumpTo { dest, targets } => {
+                let target = self.expression(dest, pointer_size, blk, address);
+                let mut indirect_jmp_targets: Vec<Tid> = targets
+                    .values()
+                    .map(|index| self.block_tid_at_index(*index))
+                    .collect();
+                indirect_jmp_targets.sort();
+                indirect_jmp_targets.dedup();
+                blk.term.indirect_jmp_targets = indirect_jmp_targets;
+                self.push_jmp(blk, address, Jmp::BranchInd(target));
+                return true;
+            }
+            Llil::Call { dest } | Llil::Tailcall { dest } => {
+                let return_ = match &instruction.il {
+                    Llil::Call { .. } => self.successor_block_tid(instruction.index),
+                    _ => None,
+                };
+                let jmp = match self.call_target(dest) {
+                    Some(target) => Jmp::Call { target, return_ },
+                    None => Jmp::CallInd {
+                        target: self.expression(dest, pointer_size, blk, address),
+                        return_,
+                    },
+                };
+                self.push_jmp(blk, address, jmp);
+                return true;
+            }
+            Llil::Ret { dest } => {
+                let target = self.expression(dest, pointer_size, blk, address);
+                self.push_jmp(blk, address, Jmp::Return(target));
+                return true;
+            }
+            Llil::Goto { dest } => {
+                let target = self.block_tid_at_index(*dest);
+                self.push_jmp(blk, address, Jmp::Branch(target));
+                return true;
+            }
+            Llil::If {
+                condition,
+                true_target,
+                false_target,
+            } => {
+                let condition = self.expression(condition, ByteSize::new(1), blk, address);
+                let true_target = self.block_tid_at_index(*true_target);
+                let false_target = self.block_tid_at_index(*false_target);
+                self.push_jmp(
+                    blk,
+                    address,
+                    Jmp::CBranch {
+                        target: true_target,
+                        condition,
+                    },
+                );
+                self.push_jmp(blk, address, Jmp::Branch(false_target));
+                return true;
+            }
+            Llil::Syscall => {
+                let jmp = Jmp::CallOther {
+                    description: "syscall".to_string(),
+                    return_: self.successor_block_tid(instruction.index),
+                };
+                self.push_jmp(blk, address, jmp);
+                return true;
+            }
+            Llil::NoRet | Llil::Bp | Llil::Trap | Llil::Undef => {
+                let jmp = Jmp::CallOther {
+                    description: format!("{:?}", instruction.il),
+                    return_: None,
+                };
+                self.push_jmp(blk, address, jmp);
+                return true;
+            }
+            Llil::Nop | Llil::Unimpl | Llil::Unsupported => (),
+            expression => {
+                // Expressions used as statements, e.g. `POP` for discarding stack values.
+                let size = expression.size().unwrap_or(pointer_size);
+                self.expression(expression, size, blk, address);
+            }
+        }
+        false
+    }
+
+    /// Translate the given LLIL expression into an IR expression.
+    ///
+    /// Loads from memory are translated into loads into temporary variables,
+    /// which are added to the given block.
+    /// The given size is used if the size of the expression is not given in the dump.
+    fn expression(
+        &mut self,
+        llil: &Llil,
+        size: ByteSize,
+        blk: &mut Term<Blk>,
+        address: u64,
+    ) -> Expression {
+        let pointer_size = self.stack_pointer.size;
+        if let Some((op, swap, left, right)) = llil.binary_operation() {
+            use BinOpType::*;
+            let operand_size = match op {
+                IntEqual | IntNotEqual | IntLess | IntSLess | IntLessEqual | IntSLessEqual => {
+                    left.size().or_else(|| right.size()).unwrap_or(pointer_size)
+                }
+                _ => llil.size().unwrap_or(size),
+            };
+            let right_size = match op {
+                IntLeft | IntRight | IntSRight => right.size().unwrap_or(operand_size),
+                _ => operand_size,
+            };
+            let mut lhs = self.expression(left, operand_size, blk, address);
+            let mut rhs = self.expression(right, right_size, blk, address);
+            if swap {
+                std::mem::swap(&mut lhs, &mut rhs);
+            }
+            return Expression::BinOp {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+        match llil {
+            Llil::Reg {
+                size: reg_size,
+                src,
+            } => Expression::Var(self.variable(src, size_or(*reg_size, size))),
+            Llil::RegSplit { size: _, hi, lo } => {
+                let half_size = ByteSize::new(u64::from(size) / 2);
+                let hi = Expression::Var(self.variable(hi, half_size));
+                let lo = Expression::Var(self.variable(lo, half_size));
+                Expression::BinOp {
+                    op: BinOpType::Piece,
+                    lhs: Box::new(hi),
+                    rhs: Box::new(lo),
+                }
+            }
+            Llil::Flag { src } => Expression::Var(flag(src)),
+            Llil::Const {
+                size: const_size,
+                constant,
+            }
+            | Llil::ConstPtr {
+                size: const_size,
+                constant,
+            } => Expression::Const(
+                Bitvector::from_u64(*constant).into_resize_unsigned(size_or(*const_size, size)),
+            ),
+            Llil::Load {
+                size: load_size,
+                src,
+            } => {
+                let location = self.expression(src, pointer_size, blk, address);
+                let temp = self.temp(size_or(*load_size, size));
+                self.push_def(
+                    blk,
+                    address,
+                    Def::Load {
+                        var: temp.clone(),
+                        address: location,
+                    },
+                );
+                Expression::Var(temp)
+            }
+            Llil::Pop { size: pop_size } => {
+                let temp = self.temp(size_or(*pop_size, size));
+                let stack_pointer = self.stack_pointer.clone();
+                self.push_def(
+                    blk,
+                    address,
+                    Def::Load {
+                        var: temp.clone(),
+                        address: Expression::Var(stack_pointer.clone()),
+                    },
+                );
+                let new_stack_pointer =
+                    Expression::Var(stack_pointer.clone()).plus_const(u64::from(temp.size) as i64);
+                self.push_def(
+                    blk,
+                    address,
+                    Def::Assign {
+                        var: stack_pointer,
+                        value: new_stack_pointer,
+                    },
+                );
+                Expression::Var(temp)
+            }
+            Llil::Neg {
+                size: neg_size,
+                src,
+            } => Expression::UnOp {
+                op: UnOpType::Int2Comp,
+                arg: Box::new(self.expression(src, size_or(*neg_size, size), blk, address)),
+            },
+            Llil::Not {
+                size: not_size,
+                src,
+            } => {
+                let arg = self.expression(src, size_or(*not_size, size), blk, address);
+                let op = if arg.bytesize() == ByteSize::new(1) && src.size().is_none() {
+                    UnOpType::BoolNegate
+                } else {
+                    UnOpType::IntNegate
+                };
+                Expression::UnOp {
+                    op,
+                    arg: Box::new(arg),
+                }
+            }
+            Llil::Sx {
+                size: cast_size,
+                src,
+            }
+            | Llil::Zx {
+                size: cast_size,
+                src,
+            } => {
+                let cast_size = size_or(*cast_size, size);
+                let arg = self.expression(src, src.size().unwrap_or(cast_size), blk, address);
+                if arg.bytesize() == cast_size {
+                    return arg;
+                }
+                let op = match llil {
+                    Llil::Sx { .. } => CastOpType::IntSExt,
+                    _ => CastOpType::IntZExt,
+                };
+                Expression::Cast {
+                    op,
+                    size: cast_size,
+                    arg: Box::new(arg),
+                }
+            }
+            Llil::LowPart {
+                size: part_size,
+                src,
+            } => {
+                let part_size = size_or(*part_size, size);
+                let arg = self.expression(src, src.size().unwrap_or(part_size), blk, address);
+                if arg.bytesize() == part_size {
+                    arg
+                } else {
+                    Expression::Subpiece {
+                        low_byte: ByteSize::new(0),
+                        size: part_size,
+                        arg: Box::new(arg),
+                    }
+                }
+            }
+            _ => Expression::Unknown {
+                description: format!("Unsupported LLIL operation at {}", format_address(address)),
+                size,
+            },
+        }
+    }
+
+    /// Return the term identifier of the block starting at the given instruction index.
+    fn block_tid_at_index(&self, index: usize) -> Tid {
+        if let Some(tid) = self.block_tids.get(&index) {
+            return tid.clone();
+        }
+        let address = self
+            .instruction_addresses
+            .get(&index)
+            .copied()
+            .unwrap_or_default();
+        continuation_block_tid(address, index)
+    }
+
+    /// Return the term identifier of the block that execution continues with
+    /// after the instruction with the given index, if the instruction is not the last instruction of the function.
+    fn successor_block_tid(&self, index: usize) -> Option<Tid> {
+        self.instruction_addresses
+            .contains_key(&(index + 1))
+            .then(|| self.block_tid_at_index(index + 1))
+    }
+
+    /// Return the term identifier of the function or import called through the given target expression.
+    fn call_target(&self, dest: &Llil) -> Option<Tid> {
+        let address = match dest {
+            Llil::Const { constant, .. } | Llil::ConstPtr { constant, .. } => *constant,
+            Llil::Load { src, .. } => match src.as_ref() {
+                Llil::Const { constant, .. } | Llil::ConstPtr { constant, .. } => *constant,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        self.call_targets.get(&address).cloned()
+    }
+
+    /// Return the variable for the register with the given name.
+    ///
+    /// Registers that are not registers of the architecture are LLIL temporary registers.
+    fn variable(&self, name: &String, size: ByteSize) -> Variable {
+        match self.register_map.get(name) {
+            Some(register) => Variable::from(*register),
+            None => Variable {
+                name: name.clone(),
+                size,
+                is_temp: true,
+            },
+        }
+    }
+
+    /// Create a new temporary variable of the given size.
+    fn temp(&mut self, size: ByteSize) -> Variable {
+        self.temp_count += 1;
+        Variable {
+            name: format!("$Ubinja{}", self.temp_count),
+            size,
+            is_temp: true,
+        }
+    }
+
+    /// Return a new term identifier for a term generated for the instruction at the given address.
+    fn term_tid(&mut self, address: u64) -> Tid {
+        let count = self.term_counts.entry(address).or_insert(0);
+        let address = format_address(address);
+        let mut tid = Tid::new(format!("instr_{address}_{count}"));
+        tid.address = address;
+        *count += 1;
+        tid
+    }
+
+    /// Add a `Def` term for the instruction at the given address to the block.
+    fn push_def(&mut self, blk: &mut Term<Blk>, address: u64, def: Def) {
+        let tid = self.term_tid(address);
+        blk.term.defs.push(Term { tid, term: def });
+    }
+
+    /// Add a `Jmp` term for the instruction at the given address to the block.
+    fn push_jmp(&mut self, blk: &mut Term<Blk>, address: u64, jmp: Jmp) {
+        let tid = self.term_tid(address);
+        blk.term.jmps.push(Term { tid, term: jmp });
+    }
+}
+
+/// Return the variable for the flag with the given name.
+///
+/// Flags are handled as independent 1-byte registers.
+fn flag(name: &str) -> Variable {
+    Variable {
+        name: name.to_string(),
+        size: ByteSize::new(1),
+        is_temp: false,
+    }
+}
+
+/// Return the term identifier of a block starting at the LLIL instruction with the given address and index
+/// that is not the first block starting at the address.
+fn continuation_block_tid(address: u64, index: usize) -> Tid {
+    let address = format_address(address);
+    let mut tid = Tid::new(format!("blk_{address}_{index}"));
+    tid.address = address;
+    tid
+}
+
+/// Create an empty block with the given term identifier.
+fn new_block(tid: Tid) -> Term<Blk> {
+    Term {
+        tid,
+        term: Blk {
+            defs: Vec::new(),
+            jmps: Vec::new(),
+            indirect_jmp_targets: Vec::new(),
+        },
+    }
+}
@@ -0,0 +1,397 @@
+//! Translation of Binary Ninja low level IL (LLIL) dumps into the internally used IR.
+//!
+//! The dumps are generated by the `binja_export/export_llil.py` script,
+//! which uses the Binary Ninja Python API and thus requires a Binary Ninja license with API access:
+//! ```sh
+//! python3 binja_export/export_llil.py BINARY DUMP.json
+//! ```
+//! The dump can then be analyzed with `cwe_checker BINARY --binja-dump DUMP.json`,
+//! which does not execute Ghidra.
+//!
+//! A dump is a JSON object corresponding to the [`BinjaDump`] struct.
+//! The `format_version` field is incremented whenever the format changes incompatibly.
+//! Architecture information is given in the same form as in the P-Code exported from Ghidra:
+//! - `cpu_architecture` has the form `<processor>_<bitness>` of the Ghidra language IDs, e.g. `x86_64` or `ARM_32`.
+//! - `registers` lists all registers of the architecture with their base registers
+//!   (see [`RegisterProperties`]).
+//!   Sub-registers are replaced by their base registers during the translation.
+//! - `datatype_properties` contains the sizes of C data types (see [`DatatypeProperties`]).
+//!
+//! The LLIL instructions of each basic block are serialized as expression trees (see [`Llil`]).
+//! Each expression is a JSON object with the name of the LLIL operation without the `LLIL_` prefix
+//! in the `operation` field, its size in bytes in the `size` field
+//! and its operands in fields named like the operands in the Binary Ninja API,
+//! e.g. `{"operation": "SET_REG", "size": 8, "dest": "rax", "src": {...}}`.
+//! Registers and flags are given by name, constants as unsigned integers
+//! and the targets of `GOTO` and `IF` instructions by their LLIL instruction index.
+//! Operations that are not supported by the translation are translated to unknown values.
+//!
+//! Calls are translated to direct calls if their target is the address of a function or import of the dump,
+//! or a load from one of the addresses of an import (e.g. its GOT entry).
+//! Functions at the address of an import (e.g. PLT stubs) are not translated.
+
+use crate::intermediate_representation::{
+    Arg, CallingConvention as IrCallingConvention, DatatypeProperties, Expression, ExternSymbol,
+    Program, Project, ProvenanceTable, RuntimeMemoryImage, Sub, Variable,
+};
+use crate::pcode::{replace_input_subregister, replace_subregister_in_block, RegisterProperties};
+use crate::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+mod llil;
+use llil::FunctionTranslator;
+pub use llil::Llil;
+
+/// The version of the dump format.
+pub const BINJA_DUMP_FORMAT_VERSION: u64 = 1;
+
+/// The top-level object of a Binary Ninja LLIL dump.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct BinjaDump {
+    /// The version of the dump format.
+    pub format_version: u64,
+    /// The CPU architecture in the form `<processor>_<bitness>`, e.g. `x86_64`.
+    pub cpu_architecture: String,
+    /// The address at which Binary Ninja loaded the binary.
+    pub image_base: u64,
+    /// The name of the stack pointer register.
+    pub stack_pointer_register: String,
+    /// All registers of the architecture.
+    pub registers: Vec<RegisterProperties>,
+    /// The calling conventions of the architecture.
+    pub calling_conventions: Vec<CallingConvention>,
+    /// The name of the default calling convention of the binary.
+    pub default_calling_convention: String,
+    /// The sizes of C data types.
+    pub datatype_properties: DatatypeProperties,
+    /// The functions of the binary.
+    pub functions: Vec<Function>,
+    /// The functions imported from shared libraries.
+    #[serde(default)]
+    pub imports: Vec<Import>,
+    /// The addresses of the functions that may be called from outside of the binary.
+    #[serde(default)]
+    pub entry_points: Vec<u64>,
+    /// The lengths of the assembly instructions in bytes, indexed by their addresses.
+    #[serde(default)]
+    pub instruction_lengths: BTreeMap<u64, u64>,
+}
+
+/// A calling convention with registers given by name.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CallingConvention {
+    /// The name of the calling convention.
+    pub name: String,
+    /// The integer parameter registers in the order of the parameters.
+    pub integer_parameter_registers: Vec<String>,
+    /// The floating point parameter registers in the order of the parameters.
+    #[serde(default)]
+    pub float_parameter_registers: Vec<String>,
+    /// The integer return registers.
+    pub integer_return_registers: Vec<String>,
+    /// The floating point return registers.
+    #[serde(default)]
+    pub float_return_registers: Vec<String>,
+    /// The callee-saved registers.
+    pub callee_saved_registers: Vec<String>,
+}
+
+/// A function of the binary.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Function {
+    /// The name of the function.
+    pub name: String,
+    /// The entry address of the function.
+    pub address: u64,
+    /// The name of the calling convention of the function, if known.
+    #[serde(default)]
+    pub calling_convention: Option<String>,
+    /// The LLIL basic blocks of the function.
+    /// The block containing the first LLIL instruction is the entry block of the function.
+    pub blocks: Vec<BasicBlock>,
+}
+
+/// An LLIL basic block.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct BasicBlock {
+    /// The instructions of the block in the order of their LLIL instruction index.
+    pub instructions: Vec<Instruction>,
+}
+
+/// An LLIL instruction.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Instruction {
+    /// The LLIL instruction index in the function.
+    pub index: usize,
+    /// The address of the assembly instruction that the LLIL instruction belongs to.
+    pub address: u64,
+    /// The LLIL expression tree of the instruction.
+    pub il: Llil,
+}
+
+/// A function imported from a shared library.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Import {
+    /// The name of the imported function.
+    pub name: String,
+    /// The address of the import symbol, e.g. of the PLT stub for ELF files.
+    pub address: u64,
+    /// Further addresses through which the function is called, e.g. its GOT entry.
+    #[serde(default)]
+    pub addresses: Vec<u64>,
+    /// The name of the calling convention of the function, if known.
+    #[serde(default)]
+    pub calling_convention: Option<String>,
+    /// The locations of the parameters of the function.
+    #[serde(default)]
+    pub parameters: Vec<ArgLocation>,
+    /// The locations of the return values of the function.
+    #[serde(default)]
+    pub return_values: Vec<ArgLocation>,
+    /// Whether the function does not return to the caller.
+    #[serde(default)]
+    pub no_return: bool,
+    /// Whether the function has a variable number of parameters.
+    #[serde(default)]
+    pub has_var_args: bool,
+}
+
+/// The location of a parameter or return value.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgLocation {
+    /// The value is contained in the register with the given name.
+    Register(String),
+    /// The value is contained on the stack.
+    Stack {
+        /// The offset of the value relative to the stack pointer at the call instruction.
+        offset: i64,
+        /// The size of the value in bytes.
+        size: u64,
+    },
+}
+
+impl BinjaDump {
+    /// Translate the dump into a project of the internally used IR.
+    ///
+    /// The `binary_base_address` denotes the base address of the memory image of the binary
+    /// according to the program headers of the binary.
+    /// It is needed to detect whether Binary Ninja loaded the binary at a different address.
+    ///
+    /// The runtime memory image of the returned project is empty.
+    pub fn into_ir_project(self, binary_base_address: u64) -> Result<Project, Error> {
+        if self.format_version != BINJA_DUMP_FORMAT_VERSION {
+            return Err(anyhow!(
+                "The Binary Ninja dump has format version {}, but only version {} is supported",
+                self.format_version,
+                BINJA_DUMP_FORMAT_VERSION
+            ));
+        }
+        let register_map: HashMap<&String, &RegisterProperties> = self
+            .registers
+            .iter()
+            .map(|register| (&register.register, register))
+            .collect();
+        let base_register = |name: &String| -> Result<Variable, Error> {
+            let register = register_map
+                .get(name)
+                .ok_or_else(|| anyhow!("Unknown register {name}"))?;
+            let base_register = register_map
+                .get(&register.base_register)
+                .ok_or_else(|| anyhow!("Unknown register {}", register.base_register))?;
+            Ok((*base_register).into())
+        };
+        let register_expression = |name: &String| -> Result<Expression, Error> {
+            let register = register_map
+                .get(name)
+                .ok_or_else(|| anyhow!("Unknown register {name}"))?;
+            Ok(replace_input_subregister(
+                Expression::Var((*register).into()),
+                &register_map,
+            ))
+        };
+        let stack_pointer = base_register(&self.stack_pointer_register)?;
+        // The IR recognizes the standard calling convention by its Ghidra name.
+        let calling_convention_name = |name: &str| {
+            if name == self.default_calling_convention {
+                "__stdcall".to_string()
+            } else {
+                name.to_string()
+            }
+        };
+
+        let mut calling_conventions = BTreeMap::new();
+        for cconv in self.calling_conventions.iter() {
+            let name = calling_convention_name(cconv.name.as_str());
+            let ir_cconv = IrCallingConvention {
+                name: name.clone(),
+                integer_parameter_register: cconv
+                    .integer_parameter_registers
+                    .iter()
+                    .map(base_register)
+                    .collect::<Result<_, _>>()?,
+                float_parameter_register: cconv
+                    .float_parameter_registers
+                    .iter()
+                    .map(register_expression)
+                    .collect::<Result<_, _>>()?,
+                integer_return_register: cconv
+                    .integer_return_registers
+                    .iter()
+                    .map(base_register)
+                    .collect::<Result<_, _>>()?,
+                float_return_register: cconv
+                    .float_return_registers
+                    .iter()
+                    .map(register_expression)
+                    .collect::<Result<_, _>>()?,
+                callee_saved_register: cconv
+                    .callee_saved_registers
+                    .iter()
+                    .map(base_register)
+                    .collect::<Result<BTreeSet<_>, _>>()?
+                    .into_iter()
+                    .collect(),
+            };
+            calling_conventions.insert(name, ir_cconv);
+        }
+
+        // The call targets of the program, indexed by their addresses.
+        let mut call_targets = HashMap::new();
+        let mut extern_symbols = BTreeMap::new();
+        for import in self.imports.iter() {
+            let tid = sub_tid(import.address);
+            call_targets.insert(import.address, tid.clone());
+            for address in import.addresses.iter() {
+                call_targets.insert(*address, tid.clone());
+            }
+            let to_ir_arg = |location: &ArgLocation| -> Result<Arg, Error> {
+                Ok(match location {
+                    ArgLocation::Register(name) => Arg::Register {
+                        expr: register_expression(name)?,
+                        data_type: None,
+                    },
+                    ArgLocation::Stack { offset, size } => Arg::Stack {
+                        address: Expression::Var(stack_pointer.clone()).plus_const(*offset),
+                        size: ByteSize::new(*size),
+                        data_type: None,
+                    },
+                })
+            };
+            let symbol = ExternSymbol {
+                tid: tid.clone(),
+                addresses: std::iter::once(&import.address)
+                    .chain(import.addresses.iter())
+                    .map(|address| format_address(*address))
+                    .collect(),
+                name: import.name.clone(),
+                calling_convention: import
+                    .calling_convention
+                    .as_deref()
+                    .map(calling_convention_name),
+                parameters: import
+                    .parameters
+                    .iter()
+                    .map(to_ir_arg)
+                    .collect::<Result<_, _>>()?,
+                return_values: import
+                    .return_values
+                    .iter()
+                    .map(to_ir_arg)
+                    .collect::<Result<_, _>>()?,
+                no_return: import.no_return,
+                has_var_args: import.has_var_args,
+            };
+            extern_symbols.insert(tid, symbol);
+        }
+        let functions: Vec<&Function> = self
+            .functions
+            .iter()
+            .filter(|function| !call_targets.contains_key(&function.address))
+            .collect();
+        for function in functions.iter() {
+            call_targets.insert(function.address, sub_tid(function.address));
+        }
+
+        let mut subs = BTreeMap::new();
+        for function in functions {
+            let tid = sub_tid(function.address);
+            let mut blocks =
+                FunctionTranslator::new(function, &register_map, &stack_pointer, &call_targets)
+                    .translate();
+            for block in blocks.iter_mut() {
+                replace_subregister_in_block(block, &register_map);
+            }
+            let sub = Sub {
+                name: function.name.clone(),
+                blocks,
+                calling_convention: function
+                    .calling_convention
+                    .as_deref()
+                    .map(calling_convention_name),
+            };
+            subs.insert(tid.clone(), Term { tid, term: sub });
+        }
+
+        let entry_points = self
+            .entry_points
+            .iter()
+            .filter_map(|address| call_targets.get(address).cloned())
+            .collect();
+        let instruction_lengths = self
+            .instruction_lengths
+            .iter()
+            .map(|(address, length)| (format_address(*address), *length))
+            .collect();
+        let program = Program {
+            subs,
+            extern_symbols,
+            entry_points,
+            address_base_offset: self.image_base.saturating_sub(binary_base_address),
+            stack_canary_checks: BTreeMap::new(),
+            fortified_symbols: BTreeMap::new(),
+            provenance: ProvenanceTable::new(instruction_lengths),
+        };
+        let register_set = self
+            .registers
+            .iter()
+            .filter(|register| register.register == register.base_register)
+            .map(Variable::from)
+            .collect();
+
+        Ok(Project {
+            program: Term {
+                tid: tid_at_address("prog", self.image_base),
+                term: program,
+            },
+            cpu_architecture: self.cpu_architecture,
+            stack_pointer_register: stack_pointer,
+            calling_conventions,
+            register_set,
+            datatype_properties: self.datatype_properties,
+            runtime_memory_image: RuntimeMemoryImage::empty(true),
+            annotations: Default::default(),
+        })
+    }
+}
+
+/// Format an address in the format used in term identifiers.
+fn format_address(address: u64) -> String {
+    format!("{address:08x}")
+}
+
+/// Create a term identifier with the given prefix for the given address.
+fn tid_at_address(prefix: &str, address: u64) -> Tid {
+    let address = format_address(address);
+    let mut tid = Tid::new(format!("{prefix}_{address}"));
+    tid.address = address;
+    tid
+}
+
+/// The term identifier of the function or import at the given address.
+fn sub_tid(address: u64) -> Tid {
+    tid_at_address("sub", address)
+}
+
+#[cfg(test)]
+mod tests;
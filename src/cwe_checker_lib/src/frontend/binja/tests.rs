@@ -0,0 +1,193 @@
+use super::*;
+use crate::intermediate_representation::{Def, Jmp};
+use serde_json::json;
+
+fn reg(name: &str, size: u64) -> serde_json::Value {
+    json!({"operation": "REG", "size": size, "src": name})
+}
+
+fn constant(value: u64, size: u64) -> serde_json::Value {
+    json!({"operation": "CONST", "size": size, "constant": value})
+}
+
+fn mock_dump() -> serde_json::Value {
+    json!({
+        "format_version": BINJA_DUMP_FORMAT_VERSION,
+        "cpu_architecture": "x86_64",
+        "image_base": 0,
+        "stack_pointer_register": "rsp",
+        "registers": [
+            {"register": "rax", "base_register": "rax", "lsb": 0, "size": 8},
+            {"register": "eax", "base_register": "rax", "lsb": 0, "size": 4},
+            {"register": "rdi", "base_register": "rdi", "lsb": 0, "size": 8},
+            {"register": "rsp", "base_register": "rsp", "lsb": 0, "size": 8}
+        ],
+        "calling_conventions": [{
+            "name": "sysv",
+            "integer_parameter_registers": ["rdi"],
+            "integer_return_registers": ["rax"],
+            "callee_saved_registers": []
+        }],
+        "default_calling_convention": "sysv",
+        "datatype_properties": {
+            "char_size": 1,
+            "double_size": 8,
+            "float_size": 4,
+            "integer_size": 4,
+            "long_double_size": 16,
+            "long_long_size": 8,
+            "long_size": 8,
+            "pointer_size": 8,
+            "short_size": 2
+        },
+        "functions": [
+            {
+                "name": "main",
+                "address": 0x1000,
+                "blocks": [
+                    {"instructions": [
+                        {"index": 0, "address": 0x1000, "il": {
+                            "operation": "PUSH", "size": 8, "src": reg("rdi", 8)
+                        }},
+                        {"index": 1, "address": 0x1004, "il": {
+                            "operation": "SET_REG", "size": 4, "dest": "eax", "src": {
+                                "operation": "LOAD", "size": 4, "src": {
+                                    "operation": "ADD", "size": 8,
+                                    "left": reg("rsp", 8), "right": constant(8, 8)
+                                }
+                            }
+                        }},
+                        {"index": 2, "address": 0x1008, "il": {
+                            "operation": "CALL", "dest": {
+                                "operation": "LOAD", "size": 8, "src": {
+                                    "operation": "CONST_PTR", "size": 8, "constant": 0x3000
+                                }
+                            }
+                        }},
+                        {"index": 3, "address": 0x100c, "il": {
+                            "operation": "IF",
+                            "condition": {
+                                "operation": "CMP_E", "size": 0,
+                                "left": reg("eax", 4), "right": constant(0, 4)
+                            },
+                            "true": 4,
+                            "false": 5
+                        }}
+                    ]},
+                    {"instructions": [
+                        {"index": 4, "address": 0x1010, "il": {
+                            "operation": "SET_REG", "size": 8, "dest": "rax", "src": {
+                                "operation": "ROL", "size": 8,
+                                "left": reg("rax", 8), "right": constant(1, 1)
+                            }
+                        }}
+                    ]},
+                    {"instructions": [
+                        {"index": 5, "address": 0x1014, "il": {
+                            "operation": "RET", "dest": {"operation": "POP", "size": 8}
+                        }}
+                    ]}
+                ]
+            },
+            {
+                "name": "puts",
+                "address": 0x2000,
+                "blocks": []
+            }
+        ],
+        "imports": [{
+            "name": "puts",
+            "address": 0x2000,
+            "addresses": [0x3000],
+            "parameters": [{"register": "rdi"}],
+            "return_values": [{"register": "eax"}]
+        }],
+        "entry_points": [0x1000]
+    })
+}
+
+#[test]
+fn translate_dump() {
+    let dump: BinjaDump = serde_json::from_value(mock_dump()).unwrap();
+    let project = dump.into_ir_project(0).unwrap();
+    let program = &project.program.term;
+    assert_eq!(program.subs.len(), 1);
+    assert_eq!(program.entry_points.len(), 1);
+    assert!(project.calling_conventions.contains_key("__stdcall"));
+    assert_eq!(project.stack_pointer_register.name, "rsp");
+
+    let puts = &program.extern_symbols[&sub_tid(0x2000)];
+    assert_eq!(puts.name, "puts");
+    assert_eq!(puts.addresses, vec!["00002000", "00003000"]);
+    assert_eq!(
+        puts.return_values[0],
+        Arg::Register {
+            expr: Expression::Subpiece {
+                low_byte: ByteSize::new(0),
+                size: ByteSize::new(4),
+                arg: Box::new(Expression::Var(Variable {
+                    name: "rax".to_string(),
+                    size: ByteSize::new(8),
+                    is_temp: false
+                }))
+            },
+            data_type: None
+        }
+    );
+
+    let blocks = &program.subs[&sub_tid(0x1000)].term.blocks;
+    let block_ids: Vec<String> = blocks.iter().map(|blk| format!("{}", blk.tid)).collect();
+    assert_eq!(
+        block_ids,
+        vec![
+            "blk_00001000",
+            "blk_0000100c_3",
+            "blk_00001010",
+            "blk_00001014"
+        ]
+    );
+    // The call ends the first block and returns to a new block.
+    assert_eq!(
+        blocks[0].term.jmps[0].term,
+        Jmp::Call {
+            target: sub_tid(0x2000),
+            return_: Some(blocks[1].tid.clone())
+        }
+    );
+    // The sub-register `eax` is replaced by its base register.
+    assert!(blocks[0]
+        .term
+        .defs
+        .iter()
+        .all(|def| !format!("{}", def.term).contains("eax")));
+    assert!(matches!(
+        &blocks[1].term.jmps[..],
+        [
+            Term { term: Jmp::CBranch { target, .. }, .. },
+            Term { term: Jmp::Branch(fallthrough), .. }
+        ] if *target == blocks[2].tid && *fallthrough == blocks[3].tid
+    ));
+    // The unsupported rotation is translated to an unknown value.
+    assert!(matches!(
+        &blocks[2].term.defs[0].term,
+        Def::Assign {
+            value: Expression::Unknown { .. },
+            ..
+        }
+    ));
+    assert_eq!(
+        blocks[2].term.jmps[0].term,
+        Jmp::Branch(blocks[3].tid.clone())
+    );
+    // The return address is popped from the stack.
+    assert_eq!(blocks[3].term.defs.len(), 2);
+    assert!(matches!(blocks[3].term.jmps[0].term, Jmp::Return(_)));
+}
+
+#[test]
+fn reject_unknown_format_version() {
+    let mut dump = mock_dump();
+    dump["format_version"] = json!(BINJA_DUMP_FORMAT_VERSION + 1);
+    let dump: BinjaDump = serde_json::from_value(dump).unwrap();
+    assert!(dump.into_ir_project(0).is_err());
+}
@@ -0,0 +1,6 @@
+//! Frontends that translate the output of disassemblers other than Ghidra
+//! into the internally used [intermediate representation](crate::intermediate_representation).
+//!
+//! The resulting projects are normalized and analyzed exactly like projects generated from Ghidra P-Code.
+
+pub mod binja;
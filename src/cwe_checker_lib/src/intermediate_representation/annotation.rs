@@ -0,0 +1,194 @@
+use super::Project;
+use crate::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A label that an analysis can attach to a term.
+///
+/// For `Def` terms the label describes the value computed by the expression of the `Def`.
+/// For `Jmp` terms the label describes the condition or the target expression of the jump.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub enum Label {
+    /// The value may be controlled by an attacker,
+    /// e.g. because it was derived from the return value of `recv` or `getenv`.
+    Tainted,
+    /// The value was derived from tainted input, but was sanitized or bounds checked afterwards.
+    Sanitized,
+    /// The value only depends on constants of the binary.
+    ConstantDerived,
+    /// A label specific to the analysis that attached it.
+    Custom(String),
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Label::Tainted => write!(f, "tainted"),
+            Label::Sanitized => write!(f, "sanitized"),
+            Label::ConstantDerived => write!(f, "constant-derived"),
+            Label::Custom(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// A label attached to a term together with the name of the analysis that attached it.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct Annotation {
+    /// The label.
+    pub label: Label,
+    /// The name of the analysis or check that attached the label, e.g. `Pointer Inference` or `CWE134`.
+    pub source: String,
+}
+
+impl Annotation {
+    /// Create a new annotation with the given label attached by the given analysis.
+    pub fn new(label: Label, source: impl ToString) -> Annotation {
+        Annotation {
+            label,
+            source: source.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.label, self.source)
+    }
+}
+
+/// A side table of annotations attached to `Def` and `Jmp` terms, indexed by their term identifiers.
+///
+/// Analyses can use the table to publish facts about the values computed by terms,
+/// so that later normalization passes and checks can consult them without recomputing them.
+/// Since the analyses only get immutable access to the project,
+/// annotations have to be added to the project before the analyses depending on them run.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct AnnotationMap {
+    #[serde(with = "crate::utils::map_as_list")]
+    annotations: BTreeMap<Tid, Vec<Annotation>>,
+}
+
+impl AnnotationMap {
+    /// Attach the given annotation to the term with the given TID.
+    /// Annotations already attached to the term are not added a second time.
+    pub fn add(&mut self, tid: Tid, annotation: Annotation) {
+        let annotations = self.annotations.entry(tid).or_default();
+        if !annotations.contains(&annotation) {
+            annotations.push(annotation);
+        }
+    }
+
+    /// Get the annotations of the term with the given TID in the order in which they were added.
+    pub fn get(&self, tid: &Tid) -> &[Annotation] {
+        self.annotations
+            .get(tid)
+            .map(|annotations| annotations.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns `true` if some analysis attached the given label to the term with the given TID.
+    pub fn has_label(&self, tid: &Tid, label: &Label) -> bool {
+        self.get(tid)
+            .iter()
+            .any(|annotation| annotation.label == *label)
+    }
+
+    /// Remove all annotations attached by the given analysis,
+    /// e.g. before the analysis is rerun on a modified program.
+    pub fn remove_source(&mut self, source: &str) {
+        self.annotations.retain(|_, annotations| {
+            annotations.retain(|annotation| annotation.source != source);
+            !annotations.is_empty()
+        });
+    }
+
+    /// Iterate over all annotated terms and their annotations.
+    pub fn iter(&self) -> impl Iterator<Item = (&Tid, &[Annotation])> {
+        self.annotations
+            .iter()
+            .map(|(tid, annotations)| (tid, annotations.as_slice()))
+    }
+
+    /// Returns `true` if no term is annotated.
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+}
+
+impl Project {
+    /// Remove the annotations of terms that are no longer contained in the program,
+    /// e.g. because a normalization pass removed them.
+    pub fn remove_dangling_annotations(&mut self) {
+        if self.annotations.is_empty() {
+            return;
+        }
+        let mut remaining = AnnotationMap::default();
+        for sub in self.program.term.subs.values() {
+            for blk in sub.term.blocks.iter() {
+                let tids = blk
+                    .term
+                    .defs
+                    .iter()
+                    .map(|def| &def.tid)
+                    .chain(blk.term.jmps.iter().map(|jmp| &jmp.tid));
+                for tid in tids {
+                    if let Some(annotations) = self.annotations.annotations.remove(tid) {
+                        remaining.annotations.insert(tid.clone(), annotations);
+                    }
+                }
+            }
+        }
+        self.annotations = remaining;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::def;
+    use crate::intermediate_representation::{Blk, Sub};
+
+    #[test]
+    fn add_and_query_annotations() {
+        let mut map = AnnotationMap::default();
+        let tid = Tid::new("def");
+        map.add(tid.clone(), Annotation::new(Label::Tainted, "taint"));
+        map.add(tid.clone(), Annotation::new(Label::Tainted, "taint"));
+        map.add(
+            tid.clone(),
+            Annotation::new(Label::Custom("loop counter".to_string()), "CWE190"),
+        );
+        assert_eq!(map.get(&tid).len(), 2);
+        assert!(map.has_label(&tid, &Label::Tainted));
+        assert!(!map.has_label(&tid, &Label::Sanitized));
+        assert!(map.get(&Tid::new("other")).is_empty());
+        assert_eq!(map.get(&tid)[1].to_string(), "loop counter (CWE190)");
+
+        map.remove_source("taint");
+        assert!(!map.has_label(&tid, &Label::Tainted));
+        map.remove_source("CWE190");
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn remove_dangling_annotations() {
+        let mut project = Project::mock_x64();
+        let mut blk = Blk::mock_with_tid("blk");
+        blk.term.defs = vec![def!["def: RAX:8 = 0x0:8"]];
+        let mut sub = Sub::mock("sub");
+        sub.term.blocks = vec![blk];
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+        for name in ["def", "removed_def"] {
+            project.annotations.add(
+                Tid::new(name),
+                Annotation::new(Label::ConstantDerived, "test"),
+            );
+        }
+
+        project.remove_dangling_annotations();
+        assert!(project
+            .annotations
+            .has_label(&Tid::new("def"), &Label::ConstantDerived));
+        assert!(project.annotations.get(&Tid::new("removed_def")).is_empty());
+    }
+}
@@ -30,6 +30,10 @@ mod program;
 pub use program::*;
 mod project;
 pub use project::*;
+mod provenance;
+pub use provenance::*;
+mod annotation;
+pub use annotation::*;
 mod runtime_memory_image;
 pub use runtime_memory_image::*;
 #[cfg(test)]
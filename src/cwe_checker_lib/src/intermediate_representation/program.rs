@@ -1,4 +1,6 @@
-use super::{Blk, ExternSymbol, Sub};
+use super::stack_canary_normalization::StackCanaryCheck;
+use super::symbol_normalization::FortifiedSymbol;
+use super::{Blk, ExternSymbol, ProvenanceTable, Sub};
 use crate::prelude::*;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
@@ -7,8 +9,10 @@ use std::fmt;
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Program {
     /// The known functions contained in the binary
+    #[serde(with = "crate::utils::map_as_list")]
     pub subs: BTreeMap<Tid, Term<Sub>>,
     /// Extern symbols linked to the binary by the linker.
+    #[serde(with = "crate::utils::map_as_list")]
     pub extern_symbols: BTreeMap<Tid, ExternSymbol>,
     /// Entry points into to binary,
     /// i.e. the term identifiers of functions that may be called from outside of the binary.
@@ -21,6 +25,20 @@ pub struct Program {
     /// Thus addresses as specified by the binary and addresses as reported by Ghidra may differ by a constant offset,
     /// which is stored in this value.
     pub address_base_offset: u64,
+    /// The stack canary checks removed by the
+    /// [stack canary normalization pass](crate::intermediate_representation::stack_canary_normalization),
+    /// indexed by the term identifiers of the functions containing them.
+    #[serde(default, with = "crate::utils::map_as_list")]
+    pub stack_canary_checks: BTreeMap<Tid, Vec<StackCanaryCheck>>,
+    /// The fortified variants of functions renamed by the
+    /// [extern symbol normalization pass](crate::intermediate_representation::symbol_normalization),
+    /// indexed by the term identifiers of their extern symbols.
+    #[serde(default, with = "crate::utils::map_as_list")]
+    pub fortified_symbols: BTreeMap<Tid, FortifiedSymbol>,
+    /// The provenance of the terms of the program,
+    /// linking them back to the instructions of the binary.
+    #[serde(default)]
+    pub provenance: ProvenanceTable,
 }
 
 impl Program {
@@ -49,6 +67,18 @@ impl Program {
         }
         None
     }
+
+    /// Get a comment describing the provenance of the given term for IR dumps.
+    ///
+    /// The comment is empty if nothing is known about the provenance of the term.
+    fn provenance_comment(&self, tid: &Tid) -> String {
+        let provenance = self.provenance.get(tid);
+        if provenance.is_empty() {
+            String::new()
+        } else {
+            format!(" ; {provenance}")
+        }
+    }
 }
 
 impl fmt::Display for Program {
@@ -66,12 +96,24 @@ impl fmt::Display for Program {
                 }
             )?;
             for Term { tid, term: blk } in sub.blocks.iter() {
-                writeln!(f, "  BLK [{}]", tid)?;
+                writeln!(f, "  BLK [{}]{}", tid, self.provenance_comment(tid))?;
                 for Term { tid, term: def } in blk.defs.iter() {
-                    writeln!(f, "    DEF [{}] {}", tid, def)?;
+                    writeln!(
+                        f,
+                        "    DEF [{}] {}{}",
+                        tid,
+                        def,
+                        self.provenance_comment(tid)
+                    )?;
                 }
                 for Term { tid, term: jmp } in blk.jmps.iter() {
-                    writeln!(f, "    JMP [{}] {}", tid, jmp)?;
+                    writeln!(
+                        f,
+                        "    JMP [{}] {}{}",
+                        tid,
+                        jmp,
+                        self.provenance_comment(tid)
+                    )?;
                 }
             }
         }
@@ -6,8 +6,18 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 /// Contains implementation of the block duplication normalization pass.
 mod block_duplication_normalization;
 use block_duplication_normalization::*;
+pub mod canonicalization;
+use canonicalization::*;
 pub mod propagate_control_flow;
 use propagate_control_flow::*;
+pub mod riscv_normalization;
+use riscv_normalization::*;
+pub mod simd_normalization;
+use simd_normalization::*;
+pub mod stack_canary_normalization;
+use stack_canary_normalization::*;
+pub mod symbol_normalization;
+use symbol_normalization::*;
 
 /// The `Project` struct is the main data structure representing a binary.
 ///
@@ -30,6 +40,9 @@ pub struct Project {
     pub datatype_properties: DatatypeProperties,
     /// Represents the memory after loading the binary.
     pub runtime_memory_image: RuntimeMemoryImage,
+    /// Labels attached to terms by analyses, e.g. to mark tainted or constant values.
+    #[serde(default)]
+    pub annotations: AnnotationMap,
 }
 
 impl Project {
@@ -75,6 +88,106 @@ impl Project {
             self.get_standard_calling_convention().unwrap()
         }
     }
+
+    /// Return the offset of the first stack parameter of a call
+    /// relative to the value of the stack pointer at the call instruction.
+    ///
+    /// On x86 the return address is already pushed onto the stack at the call instruction.
+    /// On 32-bit MIPS (o32 ABI) the caller reserves stack space for the four parameter registers
+    /// and on 32-bit PowerPC for the back chain and the link register.
+    pub fn get_stack_parameter_start_offset(&self) -> u64 {
+        match self.cpu_architecture.as_str() {
+            "x86" | "x86_32" | "x86_64" => u64::from(self.get_pointer_bytesize()),
+            "MIPS_32" => 16,
+            "PowerPC_32" => 8,
+            _ => 0,
+        }
+    }
+
+    /// Return the offset of a stack parameter of the given size
+    /// if the preceding stack parameters end at the given offset.
+    ///
+    /// On 32-bit ARM and MIPS parameters of twice the pointer size (e.g. `double` or `long long`)
+    /// are aligned to their size.
+    /// The same holds on RISC-V for both 32-bit and 64-bit binaries,
+    /// e.g. for `long double` on RV64.
+    /// All other parameters are aligned to the pointer size.
+    pub fn align_stack_parameter_offset(&self, offset: u64, size: ByteSize) -> u64 {
+        let pointer_size = u64::from(self.get_pointer_bytesize());
+        let architecture = self.cpu_architecture.to_uppercase();
+        let alignment = if u64::from(size) == 2 * pointer_size
+            && (architecture.starts_with("RISCV")
+                || (pointer_size == 4
+                    && (architecture.starts_with("ARM") || architecture.starts_with("MIPS"))))
+        {
+            2 * pointer_size
+        } else {
+            pointer_size
+        };
+        offset.next_multiple_of(alignment)
+    }
+
+    /// Return the number of bytes that a stack parameter of the given size occupies on the stack.
+    /// Stack parameters are padded to a multiple of the pointer size.
+    pub fn get_stack_parameter_slot_size(&self, size: ByteSize) -> u64 {
+        u64::from(size).next_multiple_of(u64::from(self.get_pointer_bytesize()))
+    }
+
+    /// Return the argument for the parameter with the given index of a call to the given extern symbol.
+    ///
+    /// The parameter list of variadic functions like `fprintf` may be incomplete.
+    /// The locations of missing parameters are computed from the calling convention
+    /// under the assumption that they are integers or pointers.
+    /// They are passed in the remaining integer parameter registers
+    /// and then on the stack after the known stack parameters.
+    pub fn get_parameter_arg(&self, extern_symbol: &ExternSymbol, index: usize) -> Option<Arg> {
+        if let Some(arg) = extern_symbol.parameters.get(index) {
+            return Some(arg.clone());
+        }
+        let calling_convention = self.get_calling_convention(extern_symbol);
+        let mut register_index = extern_symbol
+            .parameters
+            .iter()
+            .filter(|arg| match arg {
+                Arg::Register {
+                    expr: Expression::Var(var),
+                    ..
+                } => calling_convention.integer_parameter_register.contains(var),
+                _ => false,
+            })
+            .count();
+        let mut stack_offset = self.get_stack_parameter_start_offset();
+        for arg in extern_symbol.parameters.iter() {
+            if let Some(offset) = arg
+                .eval_stack_offset()
+                .ok()
+                .and_then(|offset| offset.try_to_u64().ok())
+            {
+                stack_offset = stack_offset.max(offset + u64::from(arg.bytesize()));
+            }
+        }
+        let pointer_size = self.get_pointer_bytesize();
+        let mut arg = None;
+        for _ in extern_symbol.parameters.len()..=index {
+            if let Some(register) = calling_convention
+                .integer_parameter_register
+                .get(register_index)
+            {
+                arg = Some(Arg::from_var(register.clone(), None));
+                register_index += 1;
+            } else {
+                let offset = self.align_stack_parameter_offset(stack_offset, pointer_size);
+                arg = Some(Arg::Stack {
+                    address: Expression::Var(self.stack_pointer_register.clone())
+                        .plus_const(offset as i64),
+                    size: pointer_size,
+                    data_type: None,
+                });
+                stack_offset = offset + self.get_stack_parameter_slot_size(pointer_size);
+            }
+        }
+        arg
+    }
 }
 
 impl Project {
@@ -361,26 +474,53 @@ impl Project {
     ///
     /// - Removal of duplicate TIDs. (This is a workaround for a bug in the
     ///   P-Code-Extractor and should be removed once the bug is fixed.)
+    /// - Canonicalization of the order of independent `Def`s and of the names
+    ///   of temporary variables.
+    /// - Renaming of extern symbols that alias other functions, e.g. versioned,
+    ///   libc-internal or fortified variants, to the names of the aliased functions.
     /// - Replacement of references to nonexisting TIDs with jumps to artificial
     ///   sink targets in the CFG.
     /// - Duplication of blocks so that if a block is contained in several
     ///   functions, each function gets its own unique copy.
     /// - Replacement of return addresses for calls to non-returning functions
     ///   with artificial sink targets.
+    /// - Replacement of SIMD user operations with fall-through branches that
+    ///   overwrite the vector registers.
+    /// - Substitution of the RISC-V `zero` register and of a constant RISC-V
+    ///   global pointer with their values.
     ///
     /// After those passes all of the later analyses can be computed. However,
     /// they are expected to run faster if you also run
     /// [`Project::normalize_optimize`] beforehand.
+    ///
+    /// Terms created or moved by the passes are recorded in [`Program::provenance`].
     #[must_use]
     pub fn normalize_basic(&mut self) -> Vec<LogMessage> {
         let mut logs = self.remove_duplicate_tids();
-        self.add_artifical_sink();
-        logs.append(self.remove_references_to_nonexisting_tids().as_mut());
-        make_block_to_sub_mapping_unique(self);
+        canonicalize(self);
+        logs.append(normalize_extern_symbol_names(self).as_mut());
         logs.append(
-            self.retarget_non_returning_calls_to_artificial_sink()
-                .as_mut(),
+            self.run_recorded_pass(NormalizationPass::ArtificialSinks, |project| {
+                project.add_artifical_sink();
+                project.remove_references_to_nonexisting_tids()
+            })
+            .as_mut(),
+        );
+        self.run_recorded_pass(
+            NormalizationPass::BlockDuplication,
+            make_block_to_sub_mapping_unique,
+        );
+        logs.append(
+            self.run_recorded_pass(NormalizationPass::ArtificialSinks, |project| {
+                project.retarget_non_returning_calls_to_artificial_sink()
+            })
+            .as_mut(),
+        );
+        self.run_recorded_pass(
+            NormalizationPass::SimdSubstitution,
+            substitute_simd_call_others,
         );
+        logs.append(normalize_riscv_registers(self).as_mut());
 
         logs
     }
@@ -395,20 +535,29 @@ impl Project {
     ///
     /// Currently, the following optimizations are performed:
     ///
+    /// - Split copies through vector registers into pointer-sized copies.
     /// - Propagate input expressions along variable assignments.
     /// - Replace trivial expressions like `a XOR a` with their result.
+    /// - Remove stack canary checks and record them in [`Program::stack_canary_checks`].
     /// - Remove dead register assignments.
     /// - Propagate the control flow along chains of conditionals with the same condition.
     /// - Substitute bitwise `AND` and `OR` operations with the stack pointer
     ///   in cases where the result is known due to known stack pointer alignment.
+    ///
+    /// Terms created by splitting vector copies are recorded in [`Program::provenance`].
+    /// Annotations of removed terms are removed from [`Project::annotations`].
     #[must_use]
     pub fn normalize_optimize(&mut self) -> Vec<LogMessage> {
+        self.run_recorded_pass(NormalizationPass::VectorCopySplitting, split_vector_copies);
         analysis::expression_propagation::propagate_input_expression(self);
         self.substitute_trivial_expressions();
+        remove_stack_canary_checks(self);
         analysis::dead_variable_elimination::remove_dead_var_assignments(self);
         propagate_control_flow(self);
-        analysis::stack_alignment_substitution::substitute_and_on_stackpointer(self)
-            .unwrap_or_default()
+        let logs = analysis::stack_alignment_substitution::substitute_and_on_stackpointer(self)
+            .unwrap_or_default();
+        self.remove_dangling_annotations();
+        logs
     }
 
     /// Run all normalization passes over the project.
@@ -479,6 +628,73 @@ impl Term<Jmp> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{def, expr, variable};
+
+    /// Run the computation several times in separate threads and assert that all runs yield the same result.
+    ///
+    /// Each thread uses its own random seeds for hash-based containers,
+    /// so results depending on the iteration order of such containers are detected with high probability.
+    fn assert_deterministic<T: PartialEq + std::fmt::Debug + Send>(
+        computation: impl Fn() -> T + Sync,
+    ) {
+        let results: Vec<T> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4).map(|_| scope.spawn(&computation)).collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        for result in results[1..].iter() {
+            assert_eq!(result, &results[0]);
+        }
+    }
+
+    /// Create a project where the function `other` jumps into the middle of a chain of conditionals in `main`,
+    /// so that the normalization has to duplicate blocks and retarget jumps.
+    fn mock_project_with_shared_blocks() -> Project {
+        let mut project = Project::mock_x64();
+        let num_blocks = 8;
+        let mut main = Sub::mock("main");
+        for index in 0..num_blocks {
+            let mut blk = Blk::mock_with_tid(&format!("blk_{index}"));
+            blk.term.defs = vec![def![format!("def_{index}: RAX:8 = RAX:8 + 0x1:8")]];
+            blk.term.jmps = if index + 2 < num_blocks {
+                vec![
+                    Term {
+                        tid: Tid::new(format!("jmp_if_{index}")),
+                        term: Jmp::CBranch {
+                            target: Tid::new(format!("blk_{}", index + 1)),
+                            condition: expr!("ZF:1"),
+                        },
+                    },
+                    Jmp::branch(&format!("jmp_else_{index}"), &format!("blk_{}", index + 2)),
+                ]
+            } else {
+                vec![Term {
+                    tid: Tid::new(format!("return_{index}")),
+                    term: Jmp::Return(expr!("RAX:8")),
+                }]
+            };
+            main.term.blocks.push(blk);
+        }
+        let mut other_blk = Blk::mock_with_tid("other_blk");
+        other_blk.term.jmps = vec![Jmp::branch("other_jmp", "blk_2")];
+        let mut other = Sub::mock("other");
+        other.term.blocks.push(other_blk);
+        for sub in [main, other] {
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        project
+    }
+
+    #[test]
+    fn normalization_is_deterministic() {
+        assert_deterministic(|| {
+            let mut project = mock_project_with_shared_blocks();
+            let logs = project.normalize();
+            (project, logs)
+        });
+    }
 
     #[test]
     fn retarget_nonexisting_jumps() {
@@ -492,4 +708,71 @@ mod tests {
             .is_err());
         assert_eq!(jmp_term.term, Jmp::Branch(Tid::artificial_sink_block("")));
     }
+
+    #[test]
+    fn parameters_beyond_parameter_registers() {
+        let project = Project::mock_arm32();
+        let symbol = ExternSymbol::mock_arm32("variadic");
+        assert_eq!(
+            project.get_parameter_arg(&symbol, 3),
+            Some(Arg::from_var(variable!("r3:4"), None))
+        );
+        let stack_arg = |address| Arg::Stack {
+            address,
+            size: ByteSize::new(4),
+            data_type: None,
+        };
+        assert_eq!(
+            project.get_parameter_arg(&symbol, 4),
+            Some(stack_arg(expr!("sp:4")))
+        );
+        assert_eq!(
+            project.get_parameter_arg(&symbol, 5),
+            Some(stack_arg(expr!("sp:4 + 4:4")))
+        );
+        assert_eq!(project.align_stack_parameter_offset(4, ByteSize::new(8)), 8);
+        assert_eq!(project.get_stack_parameter_slot_size(ByteSize::new(1)), 4);
+
+        // On x86 with the cdecl calling convention all parameters are passed on the stack.
+        let mut project = project;
+        project.cpu_architecture = "x86_32".to_string();
+        project.stack_pointer_register = variable!("ESP:4");
+        for calling_convention in project.calling_conventions.values_mut() {
+            calling_convention.integer_parameter_register.clear();
+        }
+        let mut symbol = symbol;
+        symbol.parameters = vec![stack_arg(expr!("ESP:4 + 4:4"))];
+        assert_eq!(
+            project.get_parameter_arg(&symbol, 2),
+            Some(stack_arg(expr!("ESP:4 + 12:4")))
+        );
+        assert_eq!(project.align_stack_parameter_offset(4, ByteSize::new(8)), 4);
+    }
+
+    #[test]
+    fn riscv64_stack_parameter_alignment() {
+        let project = Project::mock_riscv64();
+        assert_eq!(
+            project.align_stack_parameter_offset(8, ByteSize::new(16)),
+            16
+        );
+        assert_eq!(project.align_stack_parameter_offset(8, ByteSize::new(8)), 8);
+        assert_eq!(project.align_stack_parameter_offset(4, ByteSize::new(4)), 8);
+        assert_eq!(project.get_stack_parameter_slot_size(ByteSize::new(16)), 16);
+        assert_eq!(project.get_stack_parameter_start_offset(), 0);
+
+        let symbol = ExternSymbol::mock_riscv64("variadic");
+        assert_eq!(
+            project.get_parameter_arg(&symbol, 7),
+            Some(Arg::from_var(variable!("a7:8"), None))
+        );
+        assert_eq!(
+            project.get_parameter_arg(&symbol, 8),
+            Some(Arg::Stack {
+                address: expr!("sp:8"),
+                size: ByteSize::new(8),
+                data_type: None,
+            })
+        );
+    }
 }
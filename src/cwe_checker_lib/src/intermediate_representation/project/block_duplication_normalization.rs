@@ -67,15 +67,18 @@ impl Project {
     /// Generate a map from all `Sub` TIDs to the set TIDs of all contained blocks in the `Sub`.
     /// Used for the [`make_block_to_sub_mapping_unique`] normalization pass,
     /// as this function assumes that there may exist blocks contained in more than one `Sub`.
+    ///
+    /// The block sets are ordered, so that the duplicated blocks are appended to a `Sub`
+    /// in a deterministic order.
     fn generate_sub_tid_to_contained_block_tids_map(
         &self,
         block_tid_to_block_map: &HashMap<Tid, &Term<Blk>>,
-    ) -> HashMap<Tid, HashSet<Tid>> {
+    ) -> HashMap<Tid, BTreeSet<Tid>> {
         let mut sub_to_blocks_map = HashMap::new();
         for sub in self.program.term.subs.values() {
             let mut worklist: Vec<Tid> =
                 sub.term.blocks.iter().map(|blk| blk.tid.clone()).collect();
-            let mut block_set = HashSet::new();
+            let mut block_set = BTreeSet::new();
             while let Some(block_tid) = worklist.pop() {
                 if !block_set.contains(&block_tid) {
                     block_set.insert(block_tid.clone());
@@ -108,13 +111,14 @@ impl Project {
     /// with the TID of the sub they are contained in
     /// (to ensure that the newly created terms have unique TIDs).
     /// The TIDs of jump and return targets are not adjusted in this function.
+    /// The duplicated blocks are ordered by the TIDs of the original blocks.
     /// The returned map maps the TID of a `Sub` to the newly created blocks for that `Sub`.
     ///
     /// This function is part of the [`make_block_to_sub_mapping_unique`] normalization pass
     /// and should not be used for other purposes.
     fn duplicate_blocks_contained_in_several_subs(
         &self,
-        sub_to_blocks_map: &HashMap<Tid, HashSet<Tid>>,
+        sub_to_blocks_map: &HashMap<Tid, BTreeSet<Tid>>,
         tid_to_sub_map: &HashMap<Tid, Tid>,
         block_tid_to_block_map: &HashMap<Tid, &Term<Blk>>,
     ) -> HashMap<Tid, Vec<Term<Blk>>> {
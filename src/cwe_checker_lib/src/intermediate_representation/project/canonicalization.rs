@@ -0,0 +1,386 @@
+//! IR Canonicalization Pass
+//!
+//! Ghidra does not always lift semantically identical code to identical P-Code.
+//! The names of temporary variables are derived from offsets into the unique space,
+//! which depend on the lifting history of the Ghidra session,
+//! and independent P-Code operations of an instruction may be emitted in different orders.
+//! Features that compare the IR of different runs (caching, diffing, fingerprinting)
+//! need a representation that is independent of these artifacts.
+//!
+//! The canonicalization pass therefore
+//! - reorders independent `Def`s of the same instruction into a deterministic order
+//!   that only depends on the content of the `Def`s,
+//! - renames all temporary variables of a function to `$T0`, `$T1`, ...
+//!   in the order of their first occurrence.
+//!
+//! The pass does not change the semantics of the program
+//! and does not move `Def`s across instruction boundaries.
+
+use super::*;
+
+/// Canonicalize the order of `Def`s and the names of temporary variables in all functions of the project.
+///
+/// See the module-level documentation for more information.
+pub fn canonicalize(project: &mut Project) {
+    for sub in project.program.term.subs.values_mut() {
+        for block in sub.term.blocks.iter_mut() {
+            schedule_defs(block);
+        }
+        rename_temporary_variables(sub);
+    }
+}
+
+/// Reorder the `Def`s of each instruction in the block deterministically.
+///
+/// The `Def`s of an instruction are scheduled in topological order of their dependencies,
+/// where among all `Def`s whose dependencies are already scheduled
+/// the one with the smallest canonical representation is chosen first.
+fn schedule_defs(block: &mut Term<Blk>) {
+    let mut scheduled_defs = Vec::with_capacity(block.term.defs.len());
+    let mut defs = std::mem::take(&mut block.term.defs).into_iter().peekable();
+    while let Some(first_def) = defs.next() {
+        let mut instruction_defs = vec![first_def];
+        while let Some(def) = defs.next_if(|def| def.tid.address == instruction_defs[0].tid.address)
+        {
+            instruction_defs.push(def);
+        }
+        scheduled_defs.append(&mut schedule_instruction_defs(instruction_defs));
+    }
+    block.term.defs = scheduled_defs;
+}
+
+/// Schedule the `Def`s of a single instruction.
+/// See [`schedule_defs`] for more information.
+fn schedule_instruction_defs(defs: Vec<Term<Def>>) -> Vec<Term<Def>> {
+    if defs.len() < 2 {
+        return defs;
+    }
+    let keys: Vec<String> = defs
+        .iter()
+        .map(|def| {
+            let mut anonymized_def = def.term.clone();
+            rename_variables_in_def(&mut anonymized_def, &mut |var| {
+                var.is_temp.then(|| Variable {
+                    name: "$T".to_string(),
+                    ..var.clone()
+                })
+            });
+            anonymized_def.to_string()
+        })
+        .collect();
+    let mut remaining_dependencies: Vec<usize> = (0..defs.len())
+        .map(|index| {
+            (0..index)
+                .filter(|&earlier| are_dependent(&defs[earlier].term, &defs[index].term))
+                .count()
+        })
+        .collect();
+    let mut is_scheduled = vec![false; defs.len()];
+    let mut order = Vec::with_capacity(defs.len());
+    while order.len() < defs.len() {
+        let next = (0..defs.len())
+            .filter(|&index| !is_scheduled[index] && remaining_dependencies[index] == 0)
+            .min_by(|&a, &b| keys[a].cmp(&keys[b]).then(a.cmp(&b)))
+            .expect("Dependencies between Defs of an instruction are acyclic.");
+        is_scheduled[next] = true;
+        order.push(next);
+        for later in (next + 1)..defs.len() {
+            if are_dependent(&defs[next].term, &defs[later].term) {
+                remaining_dependencies[later] -= 1;
+            }
+        }
+    }
+    let mut defs: Vec<Option<Term<Def>>> = defs.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|index| defs[index].take().unwrap())
+        .collect()
+}
+
+/// Returns `true` if the relative order of the two `Def`s matters for the semantics of the program,
+/// i.e. if one of them writes a variable that the other one reads or writes,
+/// or if both access memory and at least one of them is a store.
+fn are_dependent(first: &Def, second: &Def) -> bool {
+    let (first_reads, first_write) = get_read_and_written_variables(first);
+    let (second_reads, second_write) = get_read_and_written_variables(second);
+    if let Some(written) = first_write {
+        if second_reads.contains(&written) || second_write == Some(written) {
+            return true;
+        }
+    }
+    if let Some(written) = second_write {
+        if first_reads.contains(&written) {
+            return true;
+        }
+    }
+    matches!(
+        (first, second),
+        (Def::Store { .. }, Def::Store { .. } | Def::Load { .. })
+            | (Def::Load { .. }, Def::Store { .. })
+    )
+}
+
+/// Return the variables read by the given `Def` and the variable written by it (if any).
+fn get_read_and_written_variables(def: &Def) -> (Vec<&Variable>, Option<&Variable>) {
+    match def {
+        Def::Assign { var, value } => (value.input_vars(), Some(var)),
+        Def::Load { var, address } => (address.input_vars(), Some(var)),
+        Def::Store { address, value } => {
+            let mut reads = address.input_vars();
+            reads.append(&mut value.input_vars());
+            (reads, None)
+        }
+    }
+}
+
+/// Rename the temporary variables of the function to `$T0`, `$T1`, ...
+/// in the order of their first occurrence.
+///
+/// Blocks are visited in the order of their TIDs,
+/// so that the new names do not depend on the order of the blocks inside the function.
+fn rename_temporary_variables(sub: &mut Term<Sub>) {
+    let mut block_order: Vec<usize> = (0..sub.term.blocks.len()).collect();
+    block_order.sort_by(|&a, &b| sub.term.blocks[a].tid.cmp(&sub.term.blocks[b].tid));
+
+    let mut renaming: HashMap<(String, ByteSize), Variable> = HashMap::new();
+    let mut collect_names = |var: &Variable| {
+        if var.is_temp {
+            let new_name = format!("$T{}", renaming.len());
+            renaming
+                .entry((var.name.clone(), var.size))
+                .or_insert_with(|| Variable {
+                    name: new_name,
+                    ..var.clone()
+                });
+        }
+        None
+    };
+    for &index in block_order.iter() {
+        let block = &mut sub.term.blocks[index];
+        for def in block.term.defs.iter_mut() {
+            rename_variables_in_def(&mut def.term, &mut collect_names);
+        }
+        for jmp in block.term.jmps.iter_mut() {
+            rename_variables_in_jmp(&mut jmp.term, &mut collect_names);
+        }
+    }
+
+    let mut apply_renaming = |var: &Variable| renaming.get(&(var.name.clone(), var.size)).cloned();
+    for block in sub.term.blocks.iter_mut() {
+        for def in block.term.defs.iter_mut() {
+            rename_variables_in_def(&mut def.term, &mut apply_renaming);
+        }
+        for jmp in block.term.jmps.iter_mut() {
+            rename_variables_in_jmp(&mut jmp.term, &mut apply_renaming);
+        }
+    }
+}
+
+/// Replace all variables in the `Def` for which `renaming` returns a new variable.
+///
+/// The variables are visited in the order in which they appear in the textual representation of the `Def`.
+fn rename_variables_in_def(
+    def: &mut Def,
+    renaming: &mut impl FnMut(&Variable) -> Option<Variable>,
+) {
+    match def {
+        Def::Assign { var, value } => {
+            rename_variable(var, renaming);
+            rename_variables_in_expression(value, renaming);
+        }
+        Def::Load { var, address } => {
+            rename_variable(var, renaming);
+            rename_variables_in_expression(address, renaming);
+        }
+        Def::Store { address, value } => {
+            rename_variables_in_expression(address, renaming);
+            rename_variables_in_expression(value, renaming);
+        }
+    }
+}
+
+/// Replace all variables in the `Jmp` for which `renaming` returns a new variable.
+fn rename_variables_in_jmp(
+    jmp: &mut Jmp,
+    renaming: &mut impl FnMut(&Variable) -> Option<Variable>,
+) {
+    match jmp {
+        Jmp::Branch(_) | Jmp::Call { .. } | Jmp::CallOther { .. } => (),
+        Jmp::BranchInd(expr)
+        | Jmp::CBranch {
+            condition: expr, ..
+        }
+        | Jmp::CallInd { target: expr, .. }
+        | Jmp::Return(expr) => rename_variables_in_expression(expr, renaming),
+    }
+}
+
+/// Replace all variables in the expression for which `renaming` returns a new variable.
+fn rename_variables_in_expression(
+    expression: &mut Expression,
+    renaming: &mut impl FnMut(&Variable) -> Option<Variable>,
+) {
+    match expression {
+        Expression::Var(var) => rename_variable(var, renaming),
+        Expression::Const(_) | Expression::Unknown { .. } => (),
+        Expression::BinOp { lhs, rhs, .. } => {
+            rename_variables_in_expression(lhs, renaming);
+            rename_variables_in_expression(rhs, renaming);
+        }
+        Expression::UnOp { arg, .. }
+        | Expression::Cast { arg, .. }
+        | Expression::Subpiece { arg, .. } => rename_variables_in_expression(arg, renaming),
+    }
+}
+
+/// Replace the variable if `renaming` returns a new variable for it.
+fn rename_variable(var: &mut Variable, renaming: &mut impl FnMut(&Variable) -> Option<Variable>) {
+    if let Some(new_var) = renaming(var) {
+        *var = new_var;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{expr, variable};
+
+    fn temp(name: &str) -> Variable {
+        Variable {
+            name: name.to_string(),
+            size: ByteSize::new(8),
+            is_temp: true,
+        }
+    }
+
+    fn def_at(id: &str, address: &str, def: Def) -> Term<Def> {
+        let mut tid = Tid::new(id);
+        tid.address = address.to_string();
+        Term { tid, term: def }
+    }
+
+    /// Lift a small instruction sequence with the given temporary variable names and `Def` order.
+    fn mock_sub(temp_names: [&str; 2], swap_independent_defs: bool) -> Term<Sub> {
+        let mut defs = vec![
+            def_at(
+                "def_0",
+                "0x1000",
+                Def::Assign {
+                    var: temp(temp_names[0]),
+                    value: expr!("RAX:8 + 1:8"),
+                },
+            ),
+            def_at(
+                "def_1",
+                "0x1000",
+                Def::Assign {
+                    var: temp(temp_names[1]),
+                    value: expr!("RBX:8"),
+                },
+            ),
+            def_at(
+                "def_2",
+                "0x1000",
+                Def::Store {
+                    address: Expression::Var(temp(temp_names[1])),
+                    value: Expression::Var(temp(temp_names[0])),
+                },
+            ),
+            def_at(
+                "def_3",
+                "0x1004",
+                Def::Assign {
+                    var: variable!("RAX:8"),
+                    value: Expression::Var(temp(temp_names[0])),
+                },
+            ),
+        ];
+        if swap_independent_defs {
+            defs.swap(0, 1);
+        }
+        let mut block = Blk::mock();
+        block.term.defs = defs;
+        block.term.jmps.push(Term {
+            tid: Tid::new("jmp"),
+            term: Jmp::BranchInd(Expression::Var(temp(temp_names[1]))),
+        });
+        let mut sub = Sub::mock("func");
+        sub.term.blocks.push(block);
+        sub
+    }
+
+    #[test]
+    fn canonicalization_of_equivalent_code() {
+        let mut project = Project::mock_x64();
+        let sub = mock_sub(["$U100", "$U200"], false);
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+        let mut other_project = Project::mock_x64();
+        let other_sub = mock_sub(["$U3f0", "$U80"], true);
+        other_project
+            .program
+            .term
+            .subs
+            .insert(other_sub.tid.clone(), other_sub);
+
+        canonicalize(&mut project);
+        canonicalize(&mut other_project);
+        let sub = &project.program.term.subs[&Tid::new("func")];
+        let other_sub = &other_project.program.term.subs[&Tid::new("func")];
+        let defs = &sub.term.blocks[0].term.defs;
+        let other_defs = &other_sub.term.blocks[0].term.defs;
+        for (def, other_def) in defs.iter().zip(other_defs.iter()) {
+            assert_eq!(def.term, other_def.term);
+        }
+        assert_eq!(
+            sub.term.blocks[0].term.jmps,
+            other_sub.term.blocks[0].term.jmps
+        );
+        // The store depends on both assignments and the last def belongs to another instruction.
+        assert!(matches!(defs[2].term, Def::Store { .. }));
+        assert!(defs[3].tid.has_id_suffix("def_3"));
+        assert_eq!(
+            defs[0].term,
+            Def::Assign {
+                var: temp("$T0"),
+                value: expr!("RAX:8 + 1:8"),
+            }
+        );
+    }
+
+    #[test]
+    fn dependent_defs_keep_order() {
+        let read_after_write = Def::Assign {
+            var: variable!("RBX:8"),
+            value: expr!("RAX:8"),
+        };
+        let write = Def::Assign {
+            var: variable!("RAX:8"),
+            value: expr!("0:8"),
+        };
+        let load = Def::Load {
+            var: variable!("RCX:8"),
+            address: expr!("RSP:8"),
+        };
+        let store = Def::Store {
+            address: expr!("RDI:8"),
+            value: expr!("RDX:8"),
+        };
+        assert!(are_dependent(&write, &read_after_write));
+        assert!(are_dependent(&read_after_write, &write));
+        assert!(are_dependent(&load, &store));
+        assert!(!are_dependent(&load, &write));
+        assert!(are_dependent(&load, &load));
+        let other_load = Def::Load {
+            var: variable!("RSI:8"),
+            address: expr!("RSP:8"),
+        };
+        assert!(!are_dependent(&load, &other_load));
+
+        let defs = vec![
+            def_at("write", "0x1000", write),
+            def_at("read", "0x1000", read_after_write),
+        ];
+        let scheduled = schedule_instruction_defs(defs);
+        assert!(scheduled[0].tid.has_id_suffix("write"));
+    }
+}
@@ -14,16 +14,19 @@
 //! equivalent to the old target but skips zero or more intermediate blocks.
 //! Knowledge about conditions that are always true when a particular branch is
 //! executed are used to resolve the target of intermediate conditional jumps.
+//! Conditions are also propagated across calls to functions without side effects,
+//! as long as the callee does not overwrite the inputs of the condition
+//! (see the [function effects classification](crate::analysis::function_effects)).
 //!
 //! Lastly, the newly bypassed blocks are considered dead code and are removed.
 
-use crate::analysis::graph::{self, Edge, Graph, Node};
+use crate::analysis::function_effects::{compute_function_effects, FunctionEffects};
+use crate::analysis::graph::{self, BlockInDegrees, Edge, Graph, Node};
 use crate::intermediate_representation::*;
 
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use petgraph::graph::NodeIndex;
-use petgraph::Direction::Incoming;
 
 /// Performs the Control Flow Propagation normalization pass.
 ///
@@ -31,10 +34,13 @@ use petgraph::Direction::Incoming;
 /// does.
 pub fn propagate_control_flow(project: &mut Project) {
     let cfg_before_normalization = graph::get_program_cfg(&project.program);
+    let mut block_in_degrees = BlockInDegrees::new(&cfg_before_normalization);
     let nodes_without_incoming_edges_at_beginning =
-        get_nodes_without_incoming_edge(&cfg_before_normalization);
+        block_in_degrees.get_blocks_without_incoming_edges();
+    let function_effects = compute_function_effects(project);
 
-    let mut jmps_to_retarget = HashMap::new();
+    // An ordered map, so that the effects of the pass do not depend on hash seeds.
+    let mut jmps_to_retarget = BTreeMap::new();
     for node in cfg_before_normalization.node_indices() {
         let Node::BlkStart(block, sub) = cfg_before_normalization[node] else {
             continue;
@@ -54,11 +60,19 @@ pub fn propagate_control_flow(project: &mut Project) {
                 tid: call_tid,
                 term:
                     Jmp::Call {
-                        target: _,
+                        target,
                         return_: Some(return_target),
                     },
-            }]
-            | [Term {
+            }] => {
+                let true_conditions =
+                    get_conditions_preserved_by_call(target, &function_effects, true_conditions);
+                if let Some(new_target) =
+                    find_target_for_retargetable_jump(return_target, &sub.term, &true_conditions)
+                {
+                    jmps_to_retarget.insert(call_tid.clone(), new_target);
+                }
+            }
+            [Term {
                 tid: call_tid,
                 term:
                     Jmp::CallInd {
@@ -124,11 +138,11 @@ pub fn propagate_control_flow(project: &mut Project) {
             _ => (),
         }
     }
+    // Instead of rebuilding the control flow graph after retargeting,
+    // only the in-degrees of the old and new jump targets are updated.
+    block_in_degrees.retarget_jumps(&cfg_before_normalization, &jmps_to_retarget);
     retarget_jumps(project, jmps_to_retarget);
-
-    let cfg_after_normalization = graph::get_program_cfg(&project.program);
-    let nodes_without_incoming_edges_at_end =
-        get_nodes_without_incoming_edge(&cfg_after_normalization);
+    let nodes_without_incoming_edges_at_end = block_in_degrees.get_blocks_without_incoming_edges();
 
     remove_new_orphaned_blocks(
         project,
@@ -137,9 +151,36 @@ pub fn propagate_control_flow(project: &mut Project) {
     );
 }
 
+/// Returns the conditions that still hold after the callee returns.
+///
+/// If the callee has side effects, we do not know anything about the state
+/// after the call. Otherwise, all conditions whose inputs are not overwritten
+/// by the callee remain true.
+fn get_conditions_preserved_by_call(
+    callee: &Tid,
+    function_effects: &BTreeMap<Tid, FunctionEffects>,
+    true_conditions: Vec<Expression>,
+) -> Vec<Expression> {
+    let Some(callee_effects) = function_effects
+        .get(callee)
+        .filter(|effects| effects.is_side_effect_free())
+    else {
+        return Vec::new();
+    };
+    true_conditions
+        .into_iter()
+        .filter(|condition| {
+            condition
+                .input_vars()
+                .into_iter()
+                .all(|var| !callee_effects.may_write_register(var))
+        })
+        .collect()
+}
+
 /// Inserts the new target TIDs into jump instructions for which a new target
 /// was computed.
-fn retarget_jumps(project: &mut Project, mut jmps_to_retarget: HashMap<Tid, Tid>) {
+fn retarget_jumps(project: &mut Project, mut jmps_to_retarget: BTreeMap<Tid, Tid>) {
     for sub in project.program.term.subs.values_mut() {
         for blk in sub.term.blocks.iter_mut() {
             for jmp in blk.term.jmps.iter_mut() {
@@ -351,19 +392,6 @@ fn negate_condition(expr: Expression) -> Expression {
     }
 }
 
-/// Iterates the CFG and returns all nodes that do not have an incoming edge.
-fn get_nodes_without_incoming_edge(cfg: &Graph) -> HashSet<Tid> {
-    cfg.node_indices()
-        .filter_map(|node| {
-            if cfg.neighbors_directed(node, Incoming).next().is_none() {
-                Some(cfg[node].get_block().tid.clone())
-            } else {
-                None
-            }
-        })
-        .collect()
-}
-
 /// Calculates the difference of the orphaned blocks and removes them from the
 /// project.
 fn remove_new_orphaned_blocks(
@@ -384,7 +412,7 @@ fn remove_new_orphaned_blocks(
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::{def, expr};
+    use crate::{def, defs, expr};
     use std::collections::BTreeMap;
 
     fn mock_condition_block_custom(
@@ -473,6 +501,12 @@ pub mod tests {
         }
     }
 
+    fn mock_ret_block_with_defs(name: &str, defs: Vec<Term<Def>>) -> Term<Blk> {
+        let mut blk = mock_ret_only_block(name);
+        blk.term.defs = defs;
+        blk
+    }
+
     fn mock_block_with_defs_and_call(
         name: &str,
         call_target: &str,
@@ -596,7 +630,10 @@ pub mod tests {
         let sub_2 = Sub {
             name: "sub_2".to_string(),
             calling_convention: None,
-            blocks: vec![mock_ret_only_block("ret_blk")],
+            blocks: vec![mock_ret_block_with_defs(
+                "ret_blk",
+                defs!["ZF:1 = r0:4 == 0x0:4"],
+            )],
         };
         let sub_2 = Term {
             tid: Tid::new("sub_2"),
@@ -610,7 +647,7 @@ pub mod tests {
         let expected_blocks = vec![
             mock_condition_block("cond_blk_1", "call_blk", "end_blk_1"),
             mock_block_with_defs_and_call("call_blk", "sub_2", "cond_blk_2"),
-            // cond_blk_2 can not be skipped as call may modify the inputs to
+            // cond_blk_2 can not be skipped as the call modifies the inputs to
             // the conditional expresion.
             mock_condition_block("cond_blk_2", "end_blk_2", "end_blk_1"),
             mock_block_with_defs("end_blk_1", "end_blk_1"),
@@ -622,6 +659,55 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn call_return_to_cond_jump_across_pure_call() {
+        let sub_1 = Sub {
+            name: "sub_1".to_string(),
+            calling_convention: None,
+            blocks: vec![
+                mock_condition_block("cond_blk_1", "call_blk", "end_blk_1"),
+                mock_block_with_defs_and_call("call_blk", "sub_2", "cond_blk_2"),
+                mock_condition_block("cond_blk_2", "end_blk_2", "end_blk_1"),
+                mock_block_with_defs("end_blk_1", "end_blk_1"),
+                mock_block_with_defs("end_blk_2", "end_blk_2"),
+            ],
+        };
+        let sub_1 = Term {
+            tid: Tid::new("sub_1"),
+            term: sub_1,
+        };
+        let sub_2 = Sub {
+            name: "sub_2".to_string(),
+            calling_convention: None,
+            blocks: vec![mock_ret_block_with_defs(
+                "ret_blk",
+                defs!["r0:4 = r1:4 + 0x1:4"],
+            )],
+        };
+        let sub_2 = Term {
+            tid: Tid::new("sub_2"),
+            term: sub_2,
+        };
+        let mut project = Project::mock_arm32();
+        project.program.term.subs =
+            BTreeMap::from([(Tid::new("sub_1"), sub_1), (Tid::new("sub_2"), sub_2)]);
+
+        propagate_control_flow(&mut project);
+        let expected_blocks = [
+            mock_condition_block("cond_blk_1", "call_blk", "end_blk_1"),
+            // The callee has no side effects and does not modify the inputs
+            // to the conditional expression.
+            mock_block_with_defs_and_call("call_blk", "sub_2", "end_blk_2"),
+            // cond_blk_2 removed, since no incoming edge anymore
+            mock_block_with_defs("end_blk_1", "end_blk_1"),
+            mock_block_with_defs("end_blk_2", "end_blk_2"),
+        ];
+        assert_eq!(
+            &project.program.term.subs[&Tid::new("sub_1")].term.blocks[..],
+            &expected_blocks[..]
+        );
+    }
+
     #[test]
     fn call_return_to_cond_jump_removed() {
         let sub_1 = Sub {
@@ -0,0 +1,261 @@
+//! RISC-V Normalization Pass
+//!
+//! Ghidra models two RISC-V registers with a special meaning as ordinary registers:
+//!
+//! - The register `zero` (`x0`) always contains the value zero and writes to it are discarded.
+//!   Ghidra still generates reads of the register, e.g. for the `mv` or `beqz` pseudo-instructions,
+//!   so that the later analyses would treat it as an unknown value.
+//! - The global pointer `gp` (`x3`) is set once at program start to the address `__global_pointer$`
+//!   and then used as base address for accesses to global variables.
+//!   Since the analyses are mostly intraprocedural, accesses relative to `gp` would be
+//!   accesses relative to an unknown parameter value in all functions except the entry point.
+//!
+//! The [`normalize_riscv_registers`] pass replaces reads of `zero` with the constant zero
+//! and removes assignments to it.
+//! If `gp` is only ever set to a single constant value,
+//! the pass also replaces reads of `gp` with this constant in all functions that do not write to `gp`.
+
+use super::*;
+
+/// The name of the RISC-V register that is hardwired to zero.
+const ZERO_REGISTER: &str = "zero";
+
+/// The name of the RISC-V global pointer register.
+const GLOBAL_POINTER_REGISTER: &str = "gp";
+
+/// Performs the RISC-V normalization pass.
+///
+/// See the module-level documentation for more information on what this pass does.
+/// Does nothing for other CPU architectures.
+pub fn normalize_riscv_registers(project: &mut Project) -> Vec<LogMessage> {
+    if !project.cpu_architecture.to_uppercase().starts_with("RISCV") {
+        return Vec::new();
+    }
+    let mut logs = Vec::new();
+    if let Some(zero) = find_register(project, ZERO_REGISTER) {
+        substitute_zero_register(project, &zero);
+    }
+    if let Some(gp) = find_register(project, GLOBAL_POINTER_REGISTER) {
+        if let Some(value) = get_constant_register_value(project, &gp) {
+            logs.push(LogMessage::new_info(format!(
+                "Substituted the RISC-V global pointer with its constant value 0x{value:x}."
+            )));
+            substitute_constant_register(project, &gp, &Expression::Const(value));
+        }
+    }
+    logs
+}
+
+/// Return the base register with the given name.
+fn find_register(project: &Project, name: &str) -> Option<Variable> {
+    project
+        .register_set
+        .iter()
+        .find(|register| register.name == name)
+        .cloned()
+}
+
+/// Replace all reads of the `zero` register with the constant zero and remove all assignments to it.
+fn substitute_zero_register(project: &mut Project, zero: &Variable) {
+    let zero_value = Expression::Const(Bitvector::zero(zero.size.into()));
+    for sub in project.program.term.subs.values_mut() {
+        for blk in sub.term.blocks.iter_mut() {
+            blk.term
+                .defs
+                .retain(|def| !matches!(&def.term, Def::Assign { var, .. } if var == zero));
+            substitute_register_in_block(&mut blk.term, zero, &zero_value);
+        }
+    }
+}
+
+/// Return the value of the given register if all assignments to it in the program
+/// assign the same constant value.
+///
+/// Constant offsets added to the register in the same block are taken into account,
+/// so that the usual `auipc gp, ...; addi gp, gp, ...` sequence yields the final value.
+fn get_constant_register_value(project: &Project, register: &Variable) -> Option<Bitvector> {
+    let mut values: Vec<Bitvector> = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for blk in sub.term.blocks.iter() {
+            let mut current_value: Option<Bitvector> = None;
+            let mut is_written = false;
+            for def in blk.term.defs.iter() {
+                match &def.term {
+                    Def::Assign { var, value } if var == register => {
+                        let mut value = value.clone();
+                        if let Some(known_value) = &current_value {
+                            value.substitute_input_var(
+                                register,
+                                &Expression::Const(known_value.clone()),
+                            );
+                        }
+                        value.substitute_trivial_operations();
+                        let Expression::Const(constant) = value else {
+                            return None;
+                        };
+                        current_value = Some(constant);
+                        is_written = true;
+                    }
+                    Def::Load { var, .. } if var == register => return None,
+                    _ => (),
+                }
+            }
+            if is_written {
+                let value = current_value?;
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+        }
+    }
+    if values.len() == 1 {
+        values.pop()
+    } else {
+        None
+    }
+}
+
+/// Replace reads of the given register with the given value in all functions that do not write to the register.
+fn substitute_constant_register(project: &mut Project, register: &Variable, value: &Expression) {
+    for sub in project.program.term.subs.values_mut() {
+        let writes_register = sub.term.blocks.iter().any(|blk| {
+            blk.term.defs.iter().any(|def| match &def.term {
+                Def::Assign { var, .. } | Def::Load { var, .. } => var == register,
+                Def::Store { .. } => false,
+            })
+        });
+        if !writes_register {
+            for blk in sub.term.blocks.iter_mut() {
+                substitute_register_in_block(&mut blk.term, register, value);
+            }
+        }
+    }
+}
+
+/// Replace all reads of the given register in the block with the given value.
+fn substitute_register_in_block(blk: &mut Blk, register: &Variable, value: &Expression) {
+    for def in blk.defs.iter_mut() {
+        match &mut def.term {
+            Def::Assign { value: expr, .. } | Def::Load { address: expr, .. } => {
+                expr.substitute_input_var(register, value);
+            }
+            Def::Store {
+                address,
+                value: stored_value,
+            } => {
+                address.substitute_input_var(register, value);
+                stored_value.substitute_input_var(register, value);
+            }
+        }
+    }
+    for jmp in blk.jmps.iter_mut() {
+        match &mut jmp.term {
+            Jmp::Branch(_) | Jmp::Call { .. } | Jmp::CallOther { .. } => (),
+            Jmp::BranchInd(expr)
+            | Jmp::CBranch {
+                condition: expr, ..
+            }
+            | Jmp::CallInd { target: expr, .. }
+            | Jmp::Return(expr) => expr.substitute_input_var(register, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{def, defs, expr};
+
+    /// Create a RISC-V project with the functions `_start`, setting `gp`,
+    /// and `func`, using `gp` and `zero`.
+    fn mock_project() -> Project {
+        let mut project = Project::mock_riscv64();
+
+        let mut start_blk = Blk::mock_with_tid("start_blk");
+        start_blk.term.defs = defs![
+            "auipc_gp: gp:8 = 0x2000:8",
+            "addi_gp: gp:8 = gp:8 + 0x800:8",
+            "set_zero: zero:8 = 0x1:8"
+        ];
+        let mut start = Sub::mock("_start");
+        start.term.blocks.push(start_blk);
+
+        let mut func_blk = Blk::mock_with_tid("func_blk");
+        func_blk.term.defs = defs![
+            "load_global: a0:8 := Load from gp:8 + 0x10:8",
+            "mv: a1:8 = zero:8"
+        ];
+        func_blk.term.jmps = vec![Term {
+            tid: Tid::new("beqz"),
+            term: Jmp::CBranch {
+                target: Tid::new("func_blk"),
+                condition: expr!("zero:8"),
+            },
+        }];
+        let mut func = Sub::mock("func");
+        func.term.blocks.push(func_blk);
+
+        for sub in [start, func] {
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        project
+    }
+
+    #[test]
+    fn register_substitution() {
+        let mut project = mock_project();
+        let logs = normalize_riscv_registers(&mut project);
+        assert_eq!(logs.len(), 1);
+
+        let start = &project.program.term.subs[&Tid::new("_start")];
+        assert_eq!(
+            start.term.blocks[0].term.defs,
+            defs![
+                "auipc_gp: gp:8 = 0x2000:8",
+                "addi_gp: gp:8 = gp:8 + 0x800:8"
+            ]
+        );
+        let func = &project.program.term.subs[&Tid::new("func")];
+        assert_eq!(
+            func.term.blocks[0].term.defs,
+            defs![
+                "load_global: a0:8 := Load from 0x2800:8 + 0x10:8",
+                "mv: a1:8 = 0x0:8"
+            ]
+        );
+        assert_eq!(
+            func.term.blocks[0].term.jmps[0].term,
+            Jmp::CBranch {
+                target: Tid::new("func_blk"),
+                condition: expr!("0x0:8"),
+            }
+        );
+    }
+
+    #[test]
+    fn conflicting_global_pointer_values() {
+        let mut project = mock_project();
+        let mut other_blk = Blk::mock_with_tid("other_blk");
+        other_blk.term.defs = vec![def!["other_gp: gp:8 = 0x4000:8"]];
+        let mut other = Sub::mock("other");
+        other.term.blocks.push(other_blk);
+        project.program.term.subs.insert(other.tid.clone(), other);
+
+        let logs = normalize_riscv_registers(&mut project);
+        assert!(logs.is_empty());
+        let func = &project.program.term.subs[&Tid::new("func")];
+        assert_eq!(
+            func.term.blocks[0].term.defs[0],
+            def!["load_global: a0:8 := Load from gp:8 + 0x10:8"]
+        );
+    }
+
+    #[test]
+    fn other_architectures_unchanged() {
+        let mut project = mock_project();
+        project.cpu_architecture = "x86_64".to_string();
+        let expected_project = project.clone();
+        assert!(normalize_riscv_registers(&mut project).is_empty());
+        assert_eq!(project, expected_project);
+    }
+}
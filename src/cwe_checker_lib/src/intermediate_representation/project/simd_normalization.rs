@@ -0,0 +1,399 @@
+//! SIMD Normalization Passes
+//!
+//! Ghidra represents many SIMD instructions either as `CALLOTHER` user
+//! operations or as loads and stores of whole vector registers. Both forms
+//! are hard on the later analyses: A `CALLOTHER` is a dead end in the control
+//! flow graph, so that everything after e.g. a `pshufb` in the same function
+//! is unreachable for the analyses. And since the abstract domains cannot
+//! represent the contents of a vector register, copying a buffer through a
+//! vector register replaces the tracked buffer contents with *Top*.
+//!
+//! The passes in this module address both problems:
+//!
+//! - [`substitute_simd_call_others`] replaces `CALLOTHER` instructions of known
+//!   SIMD user operations with a fall-through branch. Since Ghidra does not
+//!   tell us the output of a user operation, all vector registers are
+//!   overwritten with unknown values before the branch. SIMD user operations
+//!   do not write to memory or to general purpose registers.
+//! - [`split_vector_copies`] treats vector registers as byte arrays for the
+//!   compiler-vectorized `memcpy` idiom, i.e. a vector-sized load whose result
+//!   is stored unmodified in the same block. The store is replaced by a
+//!   sequence of pointer-sized stores of the bytes that were loaded.
+
+use super::*;
+
+/// The minimal size in bytes of a value to be considered a SIMD vector.
+const MIN_VECTOR_SIZE: u64 = 16;
+
+/// Prefixes of the names of user operations that Ghidra uses to model SIMD
+/// instructions on ARM and AArch64.
+const SIMD_USEROP_PREFIXES: [&str; 3] = ["NEON_", "SIMD_", "Vector"];
+
+/// Names of user operations that Ghidra uses to model SSE/AVX instructions
+/// with vector register outputs on x86.
+const X86_SIMD_USEROPS: [&str; 20] = [
+    "aesdec",
+    "aesdeclast",
+    "aesenc",
+    "aesenclast",
+    "aesimc",
+    "aeskeygenassist",
+    "packssdw",
+    "packsswb",
+    "packusdw",
+    "packuswb",
+    "palignr",
+    "pavgb",
+    "pavgw",
+    "pclmulqdq",
+    "phaddd",
+    "phaddw",
+    "pmaddubsw",
+    "pmaddwd",
+    "psadbw",
+    "pshufb",
+];
+
+/// Returns `true` if values of the given size are SIMD vectors,
+/// i.e. do not fit into general purpose registers.
+pub fn is_vector_size(size: ByteSize) -> bool {
+    u64::from(size) >= MIN_VECTOR_SIZE
+}
+
+/// Returns `true` if the given `CALLOTHER` description names a SIMD user
+/// operation whose only side effect is writing to vector registers.
+fn is_simd_userop(description: &str) -> bool {
+    if let Some(name) = SIMD_USEROP_PREFIXES
+        .iter()
+        .find_map(|prefix| description.strip_prefix(prefix))
+    {
+        // Vector loads and stores may access memory.
+        let name = name.to_lowercase();
+        !name.starts_with("ld") && !name.starts_with("st")
+    } else {
+        let name = description.strip_prefix('v').unwrap_or(description);
+        X86_SIMD_USEROPS.contains(&name)
+    }
+}
+
+/// Replace `CALLOTHER` instructions of SIMD user operations with a branch to
+/// the return target of the instruction.
+///
+/// All vector registers of the project get overwritten with unknown values
+/// before the branch to soundly model the unknown output of the operation.
+pub fn substitute_simd_call_others(project: &mut Project) {
+    let vector_registers: Vec<Variable> = project
+        .register_set
+        .iter()
+        .filter(|reg| is_vector_size(reg.size))
+        .cloned()
+        .collect();
+    for sub in project.program.term.subs.values_mut() {
+        for block in sub.term.blocks.iter_mut() {
+            let [jmp] = &mut block.term.jmps[..] else {
+                continue;
+            };
+            let Jmp::CallOther {
+                description,
+                return_: Some(return_target),
+            } = &jmp.term
+            else {
+                continue;
+            };
+            if !is_simd_userop(description) {
+                continue;
+            }
+            for register in vector_registers.iter() {
+                block.term.defs.push(Term {
+                    tid: jmp
+                        .tid
+                        .clone()
+                        .with_id_suffix(&format!("_{}", register.name)),
+                    term: Def::Assign {
+                        var: register.clone(),
+                        value: Expression::Unknown {
+                            description: description.clone(),
+                            size: register.size,
+                        },
+                    },
+                });
+            }
+            jmp.term = Jmp::Branch(return_target.clone());
+        }
+    }
+}
+
+/// Replace stores of vector registers that hold an unmodified copy of a
+/// vector-sized load in the same block with pointer-sized stores.
+///
+/// The bytes of the vector are loaded into temporary registers right before
+/// the original load, so that the replacement stores write exactly the bytes
+/// that the original store would have written.
+pub fn split_vector_copies(project: &mut Project) {
+    let pointer_size = project.get_pointer_bytesize();
+    for sub in project.program.term.subs.values_mut() {
+        for block in sub.term.blocks.iter_mut() {
+            split_vector_copies_in_block(block, pointer_size);
+        }
+    }
+}
+
+/// Split vector copies inside a single block.
+/// See [`split_vector_copies`] for more information.
+fn split_vector_copies_in_block(block: &mut Term<Blk>, pointer_size: ByteSize) {
+    // Maps variables to the temporary registers holding their low bytes in order.
+    let mut vector_contents: HashMap<Variable, Vec<Variable>> = HashMap::new();
+    let mut new_defs = Vec::with_capacity(block.term.defs.len());
+    for (index, def) in block.term.defs.iter().enumerate() {
+        match &def.term {
+            Def::Load { var, address } => {
+                vector_contents.remove(var);
+                if is_vector_size(var.size) && u64::from(var.size) % u64::from(pointer_size) == 0 {
+                    let mut parts = Vec::new();
+                    for part_index in 0..(u64::from(var.size) / u64::from(pointer_size)) {
+                        let part = Variable {
+                            name: format!("$vector_part_{index}_{part_index}"),
+                            size: pointer_size,
+                            is_temp: true,
+                        };
+                        let offset = (part_index * u64::from(pointer_size)) as i64;
+                        new_defs.push(Term {
+                            tid: def
+                                .tid
+                                .clone()
+                                .with_id_suffix(&format!("_vector_part_{part_index}")),
+                            term: Def::Load {
+                                var: part.clone(),
+                                address: address.clone().plus_const(offset),
+                            },
+                        });
+                        parts.push(part);
+                    }
+                    new_defs.push(def.clone());
+                    vector_contents.insert(var.clone(), parts);
+                    continue;
+                }
+            }
+            Def::Assign { var, value } => {
+                match get_vector_source(value, &vector_contents) {
+                    Some(parts) => vector_contents.insert(var.clone(), parts),
+                    None => vector_contents.remove(var),
+                };
+            }
+            Def::Store { address, value } => {
+                if let Some(parts) = get_vector_source(value, &vector_contents) {
+                    let num_parts = u64::from(value.bytesize()) / u64::from(pointer_size);
+                    if u64::from(value.bytesize()) % u64::from(pointer_size) == 0
+                        && num_parts <= parts.len() as u64
+                    {
+                        for (part_index, part) in
+                            parts.into_iter().take(num_parts as usize).enumerate()
+                        {
+                            let offset = (part_index as u64 * u64::from(pointer_size)) as i64;
+                            new_defs.push(Term {
+                                tid: def
+                                    .tid
+                                    .clone()
+                                    .with_id_suffix(&format!("_vector_part_{part_index}")),
+                                term: Def::Store {
+                                    address: address.clone().plus_const(offset),
+                                    value: Expression::Var(part),
+                                },
+                            });
+                        }
+                        continue;
+                    }
+                }
+            }
+        }
+        new_defs.push(def.clone());
+    }
+    block.term.defs = new_defs;
+}
+
+/// If the low bytes of the given expression are the unmodified contents of a
+/// variable in `vector_contents`, return the temporary registers holding them.
+///
+/// Recognized are the variable itself, zero-extensions of it, subpieces
+/// starting at its lowest byte and pieces with it as the low part.
+fn get_vector_source(
+    expression: &Expression,
+    vector_contents: &HashMap<Variable, Vec<Variable>>,
+) -> Option<Vec<Variable>> {
+    match expression {
+        Expression::Var(var) => vector_contents.get(var).cloned(),
+        Expression::Cast {
+            op: CastOpType::IntZExt,
+            arg,
+            ..
+        }
+        | Expression::BinOp {
+            op: BinOpType::Piece,
+            rhs: arg,
+            ..
+        } => get_vector_source(arg, vector_contents),
+        Expression::Subpiece { low_byte, arg, .. } if u64::from(*low_byte) == 0 => {
+            get_vector_source(arg, vector_contents)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{defs, expr, variable};
+
+    #[test]
+    fn simd_call_other_substitution() {
+        let mut project = Project::mock_x64();
+        project.register_set.insert(variable!("ZMM0:64"));
+        let call_other = |description: &str| Term {
+            tid: Tid::new("call_other"),
+            term: Jmp::CallOther {
+                description: description.to_string(),
+                return_: Some(Tid::new("return_target")),
+            },
+        };
+        let mut simd_block = Blk::mock_with_tid("simd_block");
+        simd_block.term.jmps.push(call_other("pshufb"));
+        let mut other_block = Blk::mock_with_tid("other_block");
+        other_block.term.jmps.push(call_other("syscall"));
+        let mut sub = Sub::mock("func");
+        sub.term.blocks = vec![simd_block, other_block];
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+
+        substitute_simd_call_others(&mut project);
+        let blocks = &project.program.term.subs[&Tid::new("func")].term.blocks;
+        assert_eq!(
+            blocks[0].term.jmps[0].term,
+            Jmp::Branch(Tid::new("return_target"))
+        );
+        assert_eq!(
+            blocks[0].term.defs,
+            vec![Term {
+                tid: Tid::new("call_other_ZMM0"),
+                term: Def::Assign {
+                    var: variable!("ZMM0:64"),
+                    value: Expression::Unknown {
+                        description: "pshufb".to_string(),
+                        size: ByteSize::new(64),
+                    },
+                },
+            }]
+        );
+        assert_eq!(blocks[1].term.jmps[0], call_other("syscall"));
+        assert!(blocks[1].term.defs.is_empty());
+
+        assert!(is_simd_userop("NEON_cmeq"));
+        assert!(is_simd_userop("vpshufb"));
+        assert!(!is_simd_userop("NEON_st1"));
+    }
+
+    #[test]
+    fn vector_copy_splitting() {
+        let mut block = Blk::mock_with_tid("block");
+        block.term.defs = defs![
+            "load: XMM0:16 := Load from RSI:8",
+            "store: Store at RDI:8 := XMM0:16",
+            "unrelated: Store at RDI:8 := RAX:8"
+        ];
+        split_vector_copies_in_block(&mut block, ByteSize::new(8));
+
+        let part = |index: u64| Variable {
+            name: format!("$vector_part_0_{index}"),
+            size: ByteSize::new(8),
+            is_temp: true,
+        };
+        let defs = &block.term.defs;
+        assert_eq!(defs.len(), 6);
+        assert_eq!(
+            defs[0].term,
+            Def::Load {
+                var: part(0),
+                address: expr!("RSI:8"),
+            }
+        );
+        assert_eq!(
+            defs[1].term,
+            Def::Load {
+                var: part(1),
+                address: expr!("RSI:8 + 8:8"),
+            }
+        );
+        assert_eq!(defs[2].tid, Tid::new("load"));
+        assert_eq!(
+            defs[4].term,
+            Def::Store {
+                address: expr!("RDI:8 + 8:8"),
+                value: Expression::Var(part(1)),
+            }
+        );
+        assert_eq!(defs[5].tid, Tid::new("unrelated"));
+    }
+
+    #[test]
+    fn vector_copy_through_base_register() {
+        let mut block = Blk::mock_with_tid("block");
+        let loaded_value = Variable {
+            name: "loaded_value".to_string(),
+            size: ByteSize::new(16),
+            is_temp: true,
+        };
+        let zmm0 = variable!("ZMM0:64");
+        block.term.defs = vec![
+            Term {
+                tid: Tid::new("load"),
+                term: Def::Load {
+                    var: loaded_value.clone(),
+                    address: expr!("RSI:8"),
+                },
+            },
+            Term {
+                tid: Tid::new("cast_to_base"),
+                term: Def::Assign {
+                    var: zmm0.clone(),
+                    value: Expression::Cast {
+                        op: CastOpType::IntZExt,
+                        size: ByteSize::new(64),
+                        arg: Box::new(Expression::Var(loaded_value)),
+                    },
+                },
+            },
+            Term {
+                tid: Tid::new("store"),
+                term: Def::Store {
+                    address: expr!("RDI:8"),
+                    value: Expression::Subpiece {
+                        low_byte: ByteSize::new(0),
+                        size: ByteSize::new(16),
+                        arg: Box::new(Expression::Var(zmm0.clone())),
+                    },
+                },
+            },
+        ];
+        let mut overwritten_block = block.clone();
+        overwritten_block.term.defs.insert(
+            2,
+            Term {
+                tid: Tid::new("overwrite"),
+                term: Def::Assign {
+                    var: zmm0,
+                    value: Expression::Unknown {
+                        description: "pshufb".to_string(),
+                        size: ByteSize::new(64),
+                    },
+                },
+            },
+        );
+
+        split_vector_copies_in_block(&mut block, ByteSize::new(8));
+        assert_eq!(block.term.defs.len(), 6);
+        assert!(matches!(block.term.defs[5].term, Def::Store { .. }));
+        assert!(block.term.defs[5].tid.has_id_suffix("_vector_part_1"));
+
+        split_vector_copies_in_block(&mut overwritten_block, ByteSize::new(8));
+        assert_eq!(overwritten_block.term.defs.len(), 6);
+        assert_eq!(overwritten_block.term.defs[5].tid, Tid::new("store"));
+    }
+}
@@ -0,0 +1,345 @@
+//! Stack Canary Normalization Pass
+//!
+//! Functions protected by a stack canary store a secret value on the stack in
+//! their prologue and compare it with the original value in their epilogue.
+//! If the values differ, a failure function like `__stack_chk_fail` is called,
+//! which aborts the program. The check is just noise for the later analyses:
+//! Its condition depends on a thread-local value that the analyses cannot
+//! know, so that they have to consider both outcomes of the comparison. It
+//! also prevents the [control flow propagation pass] from propagating
+//! conditions across the epilogue of the function.
+//!
+//! The [`remove_stack_canary_checks`] pass removes these checks:
+//!
+//! - Conditional jumps to blocks that call a stack check failure function are
+//!   replaced by unconditional jumps to the block that is executed if the
+//!   canary is intact.
+//! - Blocks calling a stack check failure function that are no longer the
+//!   target of any jump are removed.
+//! - Loads of the canary from thread-local storage whose value is only stored
+//!   on the stack are removed together with the store.
+//!
+//! The computations of the comparison become dead code and are removed by the
+//! dead variable elimination afterwards. The removed checks are recorded in
+//! [`Program::stack_canary_checks`], so that checks can still find out which
+//! functions are protected by a stack canary.
+//!
+//! The frame setup and teardown code of functions is not removed, since the
+//! pointer inference relies on it to track the stack frame and the values of
+//! callee-saved registers.
+//!
+//! [control flow propagation pass]: mod@super::propagate_control_flow
+
+use super::*;
+
+/// Names of the functions that are called if a stack canary check fails.
+pub const STACK_CHECK_FAILURE_SYMBOLS: [&str; 2] = ["__stack_chk_fail", "__stack_chk_fail_local"];
+
+/// Names of the registers containing the thread pointer on x86,
+/// relative to which the stack canary is stored.
+const THREAD_POINTER_REGISTERS: [&str; 2] = ["FS_OFFSET", "GS_OFFSET"];
+
+/// A stack canary check that was removed by the normalization pass.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct StackCanaryCheck {
+    /// The term identifier of the conditional jump that checked the canary.
+    pub check: Tid,
+    /// The term identifier of the call to the stack check failure function.
+    pub failure_call: Tid,
+}
+
+/// Performs the Stack Canary normalization pass.
+///
+/// See the module-level documentation for more information on what this pass
+/// does.
+pub fn remove_stack_canary_checks(project: &mut Project) {
+    let failure_symbols: HashSet<Tid> = project
+        .program
+        .term
+        .extern_symbols
+        .values()
+        .filter(|symbol| STACK_CHECK_FAILURE_SYMBOLS.contains(&symbol.name.as_str()))
+        .map(|symbol| symbol.tid.clone())
+        .collect();
+    if failure_symbols.is_empty() {
+        return;
+    }
+    let mut removed_checks = BTreeMap::new();
+    for sub in project.program.term.subs.values_mut() {
+        let checks = remove_stack_canary_checks_in_sub(sub, &failure_symbols);
+        if !checks.is_empty() {
+            removed_checks.insert(sub.tid.clone(), checks);
+        }
+    }
+    project
+        .program
+        .term
+        .stack_canary_checks
+        .extend(removed_checks);
+}
+
+/// Remove the stack canary checks of the given function and return them.
+fn remove_stack_canary_checks_in_sub(
+    sub: &mut Term<Sub>,
+    failure_symbols: &HashSet<Tid>,
+) -> Vec<StackCanaryCheck> {
+    // Maps the TIDs of blocks calling a failure function to the TID of the call.
+    let failure_blocks: HashMap<Tid, Tid> = sub
+        .term
+        .blocks
+        .iter()
+        .filter_map(|blk| {
+            blk.term
+                .jmps
+                .iter()
+                .find(|jmp| {
+                    matches!(&jmp.term, Jmp::Call { target, .. } if failure_symbols.contains(target))
+                })
+                .map(|jmp| (blk.tid.clone(), jmp.tid.clone()))
+        })
+        .collect();
+    if failure_blocks.is_empty() {
+        return Vec::new();
+    }
+
+    let mut checks = Vec::new();
+    let mut canary_addresses = HashSet::new();
+    for blk in sub.term.blocks.iter_mut() {
+        let [Term {
+            tid: check_tid,
+            term: Jmp::CBranch { target, .. },
+        }, Term {
+            tid: branch_tid,
+            term: Jmp::Branch(fallthrough),
+        }] = &blk.term.jmps[..]
+        else {
+            continue;
+        };
+        let (failure_block, intact_target) = match (
+            failure_blocks.contains_key(target),
+            failure_blocks.contains_key(fallthrough),
+        ) {
+            (true, false) => (target, fallthrough),
+            (false, true) => (fallthrough, target),
+            _ => continue,
+        };
+        checks.push(StackCanaryCheck {
+            check: check_tid.clone(),
+            failure_call: failure_blocks[failure_block].clone(),
+        });
+        blk.term.jmps = vec![Term {
+            tid: branch_tid.clone(),
+            term: Jmp::Branch(intact_target.clone()),
+        }];
+        canary_addresses.extend(blk.term.defs.iter().filter_map(|def| match &def.term {
+            Def::Load { address, .. } if is_thread_local_address(address) => Some(address.clone()),
+            _ => None,
+        }));
+    }
+    if checks.is_empty() {
+        return checks;
+    }
+
+    let jump_targets = get_jump_targets(sub);
+    sub.term
+        .blocks
+        .retain(|blk| !failure_blocks.contains_key(&blk.tid) || jump_targets.contains(&blk.tid));
+    for blk in sub.term.blocks.iter_mut() {
+        remove_canary_stores(blk, &canary_addresses);
+    }
+    checks
+}
+
+/// Returns `true` if the address is an offset to the thread pointer,
+/// i.e. the address of a thread-local variable.
+fn is_thread_local_address(address: &Expression) -> bool {
+    let input_vars = address.input_vars();
+    !input_vars.is_empty()
+        && input_vars
+            .iter()
+            .all(|var| THREAD_POINTER_REGISTERS.contains(&var.name.as_str()))
+}
+
+/// Get the term identifiers of all blocks that are targets of intraprocedural
+/// jumps or call returns in the given function.
+fn get_jump_targets(sub: &Term<Sub>) -> HashSet<Tid> {
+    sub.term
+        .blocks
+        .iter()
+        .flat_map(|blk| blk.term.jmps.iter())
+        .filter_map(|jmp| match &jmp.term {
+            Jmp::Branch(target) | Jmp::CBranch { target, .. } => Some(target.clone()),
+            Jmp::Call { return_, .. }
+            | Jmp::CallInd { return_, .. }
+            | Jmp::CallOther { return_, .. } => return_.clone(),
+            Jmp::BranchInd(_) | Jmp::Return(_) => None,
+        })
+        .collect()
+}
+
+/// Remove loads of the stack canary from one of the given addresses together
+/// with the store of the loaded value on the stack.
+///
+/// The load and the store are only removed if the loaded value is overwritten
+/// in the same block without being used anywhere else.
+fn remove_canary_stores(blk: &mut Term<Blk>, canary_addresses: &HashSet<Expression>) {
+    let mut defs_to_remove = HashSet::new();
+    for (load_index, def) in blk.term.defs.iter().enumerate() {
+        let Def::Load { var, address } = &def.term else {
+            continue;
+        };
+        if !canary_addresses.contains(address) {
+            continue;
+        }
+        let mut uses = blk.term.defs[load_index + 1..]
+            .iter()
+            .enumerate()
+            .filter(|(_, def)| def_uses_or_assigns(def, var));
+        let Some((store_offset, store)) = uses.next() else {
+            continue;
+        };
+        let is_canary_store = matches!(
+            &store.term,
+            Def::Store { address, value: Expression::Var(value) }
+                if value == var && !address.input_vars().contains(&var)
+        );
+        let is_overwritten = uses.next().is_some_and(|(_, def)| match &def.term {
+            Def::Assign { var: output, value } => {
+                output == var && !value.input_vars().contains(&var)
+            }
+            Def::Load {
+                var: output,
+                address,
+            } => output == var && !address.input_vars().contains(&var),
+            Def::Store { .. } => false,
+        });
+        if is_canary_store && is_overwritten {
+            defs_to_remove.insert(def.tid.clone());
+            defs_to_remove.insert(blk.term.defs[load_index + 1 + store_offset].tid.clone());
+        }
+    }
+    blk.term
+        .defs
+        .retain(|def| !defs_to_remove.contains(&def.tid));
+}
+
+/// Returns `true` if the definition reads or writes the given variable.
+fn def_uses_or_assigns(def: &Term<Def>, var: &Variable) -> bool {
+    match &def.term {
+        Def::Assign { var: output, value } => output == var || value.input_vars().contains(&var),
+        Def::Load {
+            var: output,
+            address,
+        } => output == var || address.input_vars().contains(&var),
+        Def::Store { address, value } => {
+            address.input_vars().contains(&var) || value.input_vars().contains(&var)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{def, defs, expr};
+
+    /// Create a project with a function protected by a stack canary:
+    ///
+    /// - `entry`: Stores the canary on the stack and calls `other_function`.
+    /// - `epilogue`: Compares the canary and jumps to `fail` if it was changed.
+    /// - `fail`: Calls `__stack_chk_fail`.
+    /// - `exit`: Returns.
+    fn mock_project() -> Project {
+        let mut project = Project::mock_x64();
+        let failure_symbol = ExternSymbol::mock_x64("__stack_chk_fail");
+        project
+            .program
+            .term
+            .extern_symbols
+            .insert(failure_symbol.tid.clone(), failure_symbol);
+        let mut entry = Blk::mock_with_tid("entry");
+        entry.term.defs = defs![
+            "canary_load: RAX:8 := Load from FS_OFFSET:8 + 0x28:8",
+            "canary_store: Store at RSP:8 + 0x8:8 := RAX:8",
+            "clear: RAX:8 = 0x0:8"
+        ];
+        entry.term.jmps = vec![Jmp::call("call", "other_function", Some("epilogue"))];
+        let mut epilogue = Blk::mock_with_tid("epilogue");
+        epilogue.term.defs = defs![
+            "load_stack: RDX:8 := Load from RSP:8 + 0x8:8",
+            "load_canary: RCX:8 := Load from FS_OFFSET:8 + 0x28:8",
+            "compare: ZF:1 = RDX:8 == RCX:8"
+        ];
+        epilogue.term.jmps = vec![
+            Term {
+                tid: Tid::new("check"),
+                term: Jmp::CBranch {
+                    target: Tid::new("fail"),
+                    condition: expr!("ZF:1"),
+                },
+            },
+            Jmp::branch("fallthrough", "exit"),
+        ];
+        let mut fail = Blk::mock_with_tid("fail");
+        fail.term.jmps = vec![Jmp::call("call_fail", "__stack_chk_fail", None)];
+        let mut exit = Blk::mock_with_tid("exit");
+        exit.term.jmps = vec![Term {
+            tid: Tid::new("return"),
+            term: Jmp::Return(expr!("0x0:8")),
+        }];
+        let mut sub = Sub::mock("protected");
+        sub.term.blocks = vec![entry, epilogue, fail, exit];
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+        project
+    }
+
+    #[test]
+    fn canary_check_removal() {
+        let mut project = mock_project();
+        remove_stack_canary_checks(&mut project);
+        let sub = &project.program.term.subs[&Tid::new("protected")];
+        let block_tids: Vec<Tid> = sub.term.blocks.iter().map(|blk| blk.tid.clone()).collect();
+        assert_eq!(
+            block_tids,
+            vec![Tid::new("entry"), Tid::new("epilogue"), Tid::new("exit")]
+        );
+        assert_eq!(
+            sub.term.blocks[0].term.defs,
+            vec![def!["clear: RAX:8 = 0x0:8"]]
+        );
+        assert_eq!(
+            sub.term.blocks[1].term.jmps,
+            vec![Jmp::branch("fallthrough", "exit")]
+        );
+        assert_eq!(
+            project.program.term.stack_canary_checks,
+            BTreeMap::from([(
+                Tid::new("protected"),
+                vec![StackCanaryCheck {
+                    check: Tid::new("check"),
+                    failure_call: Tid::new("call_fail"),
+                }]
+            )])
+        );
+    }
+
+    #[test]
+    fn canary_load_still_used() {
+        let mut project = mock_project();
+        let sub = project
+            .program
+            .term
+            .subs
+            .get_mut(&Tid::new("protected"))
+            .unwrap();
+        // The canary value is used after the store, so the load must not be removed.
+        sub.term.blocks[0].term.defs = defs![
+            "canary_load: RAX:8 := Load from FS_OFFSET:8 + 0x28:8",
+            "canary_store: Store at RSP:8 + 0x8:8 := RAX:8",
+            "use: RBX:8 = RAX:8"
+        ];
+        remove_stack_canary_checks(&mut project);
+        let sub = &project.program.term.subs[&Tid::new("protected")];
+        assert_eq!(sub.term.blocks[0].term.defs.len(), 3);
+        assert_eq!(project.program.term.stack_canary_checks.len(), 1);
+    }
+}
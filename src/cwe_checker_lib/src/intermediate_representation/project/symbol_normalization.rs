@@ -0,0 +1,243 @@
+//! Extern Symbol Normalization Pass
+//!
+//! The checks identify extern functions by their names as configured in `config.json`.
+//! But the same function may be imported under different names,
+//! depending on the C standard library and the compiler flags used to build the binary:
+//!
+//! - GNU symbol versioning may add a version suffix to the name, e.g. `memcpy@GLIBC_2.14`.
+//! - Internal aliases like `__GI_memcpy` (glibc, uClibc) or `__libc_malloc`
+//!   may be referenced directly, especially in statically linked firmware.
+//! - musl and glibc redirect functions using a 64-bit `time_t` on 32-bit targets
+//!   to names like `__stat_time64` or `__clock_gettime64`.
+//! - Source fortification (`_FORTIFY_SOURCE`) replaces calls to copy functions
+//!   with calls to checked variants like `__memcpy_chk`,
+//!   that take the size of the destination object as additional last parameter.
+//!   Bionic uses additional variants like `__open_2` or `__strlen_chk`.
+//!   Unresolved compiler builtins may also appear as `__builtin___memcpy_chk` or `__builtin_memcpy`.
+//!
+//! The [`normalize_extern_symbol_names`] pass renames such extern symbols to the name of the
+//! function whose semantics they share, so that the checks do not miss them.
+//! Only variants whose parameters start with the parameters of the base function are renamed,
+//! so that the parameter indices configured for the base function stay valid.
+//! Fortified variants with additional parameters in between, like `__sprintf_chk`,
+//! keep their names and have to be configured separately.
+//!
+//! Renamed fortified variants are recorded in [`Program::fortified_symbols`]
+//! together with the index of their object size parameter,
+//! so that checks can take the runtime bounds check into account.
+
+use super::*;
+
+/// Fortified variants of functions together with the name of the base function,
+/// the index of the checked destination parameter and the index of the object size parameter.
+const FORTIFIED_SYMBOLS: [(&str, &str, usize, usize); 14] = [
+    ("__memcpy_chk", "memcpy", 0, 3),
+    ("__memmove_chk", "memmove", 0, 3),
+    ("__mempcpy_chk", "mempcpy", 0, 3),
+    ("__memset_chk", "memset", 0, 3),
+    ("__strcpy_chk", "strcpy", 0, 2),
+    ("__stpcpy_chk", "stpcpy", 0, 2),
+    ("__strcat_chk", "strcat", 0, 2),
+    ("__strncpy_chk", "strncpy", 0, 3),
+    ("__stpncpy_chk", "stpncpy", 0, 3),
+    ("__strncat_chk", "strncat", 0, 3),
+    ("__wcscpy_chk", "wcscpy", 0, 2),
+    ("__gets_chk", "gets", 0, 1),
+    ("__read_chk", "read", 1, 3),
+    ("__pread_chk", "pread", 1, 4),
+];
+
+/// Alternative names of functions together with the name of the function they alias.
+const SYMBOL_ALIASES: [(&str, &str); 28] = [
+    // Internal aliases of glibc and uClibc
+    ("__libc_malloc", "malloc"),
+    ("__libc_calloc", "calloc"),
+    ("__libc_realloc", "realloc"),
+    ("__libc_free", "free"),
+    ("__libc_memalign", "memalign"),
+    ("__uClibc_main", "__libc_start_main"),
+    // Functions with 64-bit time_t on 32-bit targets (musl and glibc)
+    ("__time64", "time"),
+    ("__stat_time64", "stat"),
+    ("__fstat_time64", "fstat"),
+    ("__lstat_time64", "lstat"),
+    ("__fstatat_time64", "fstatat"),
+    ("__gettimeofday_time64", "gettimeofday"),
+    ("__settimeofday_time64", "settimeofday"),
+    ("__clock_gettime64", "clock_gettime"),
+    ("__clock_settime64", "clock_settime"),
+    ("__nanosleep_time64", "nanosleep"),
+    ("__localtime64", "localtime"),
+    ("__localtime64_r", "localtime_r"),
+    ("__gmtime64", "gmtime"),
+    ("__gmtime64_r", "gmtime_r"),
+    ("__mktime64", "mktime"),
+    ("__select_time64", "select"),
+    // Fortified variants of Bionic without a destination object
+    ("__open_2", "open"),
+    ("__openat_2", "openat"),
+    ("__strlen_chk", "strlen"),
+    ("__strchr_chk", "strchr"),
+    ("__strrchr_chk", "strrchr"),
+    ("__umask_chk", "umask"),
+];
+
+/// Prefixes of the ISO C conforming variants of the `scanf` family of functions in glibc.
+const ISO_C_PREFIXES: [&str; 2] = ["__isoc99_", "__isoc23_"];
+
+/// A fortified variant of a function, whose extern symbol was renamed to the name of the base function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct FortifiedSymbol {
+    /// The original name of the extern symbol.
+    pub original_name: String,
+    /// The index of the parameter pointing to the destination object.
+    pub destination_index: usize,
+    /// The index of the parameter containing the size of the destination object
+    /// as known to the compiler.
+    pub object_size_index: usize,
+}
+
+/// Performs the extern symbol normalization pass.
+///
+/// See the module-level documentation for more information on what this pass does.
+pub fn normalize_extern_symbol_names(project: &mut Project) -> Vec<LogMessage> {
+    let mut num_renamed_symbols = 0;
+    for symbol in project.program.term.extern_symbols.values_mut() {
+        let (name, fortified) = get_canonical_name(&symbol.name);
+        if name == symbol.name {
+            continue;
+        }
+        if let Some((destination_index, object_size_index)) = fortified {
+            project.program.term.fortified_symbols.insert(
+                symbol.tid.clone(),
+                FortifiedSymbol {
+                    original_name: symbol.name.clone(),
+                    destination_index,
+                    object_size_index,
+                },
+            );
+        }
+        symbol.name = name;
+        num_renamed_symbols += 1;
+    }
+    if num_renamed_symbols > 0 {
+        vec![LogMessage::new_info(format!(
+            "Renamed {num_renamed_symbols} extern symbols to the names of the functions they alias."
+        ))]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Return the canonical name of a symbol.
+///
+/// If the symbol is a fortified variant of a function,
+/// also return the indices of the destination and the object size parameter.
+pub fn get_canonical_name(name: &str) -> (String, Option<(usize, usize)>) {
+    let name = strip_version_suffix(name);
+    let name = name.strip_prefix("__GI_").unwrap_or(name);
+    let name = name.strip_prefix("__builtin_").unwrap_or(name);
+    if let Some((_, base_name, destination_index, object_size_index)) = FORTIFIED_SYMBOLS
+        .iter()
+        .find(|(fortified_name, ..)| *fortified_name == name)
+    {
+        return (
+            base_name.to_string(),
+            Some((*destination_index, *object_size_index)),
+        );
+    }
+    if let Some((_, base_name)) = SYMBOL_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return (base_name.to_string(), None);
+    }
+    for prefix in ISO_C_PREFIXES {
+        if let Some(base_name) = name.strip_prefix(prefix) {
+            return (base_name.to_string(), None);
+        }
+    }
+    (name.to_string(), None)
+}
+
+/// Remove a GNU symbol version suffix like `@GLIBC_2.14` or `@@GLIBC_2.2.5` from the name.
+///
+/// Suffixes starting with a digit are kept,
+/// since they denote the parameter size of `stdcall` functions on Windows.
+fn strip_version_suffix(name: &str) -> &str {
+    match name.split_once('@') {
+        Some((base_name, version))
+            if !base_name.is_empty()
+                && !version
+                    .trim_start_matches('@')
+                    .starts_with(|c: char| c.is_ascii_digit()) =>
+        {
+            base_name
+        }
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_names() {
+        for (name, expected_name) in [
+            ("memcpy", "memcpy"),
+            ("memcpy@GLIBC_2.14", "memcpy"),
+            ("fopen@@GLIBC_2.2.5", "fopen"),
+            ("_Foo@12", "_Foo@12"),
+            ("__GI_strlen", "strlen"),
+            ("__libc_malloc", "malloc"),
+            ("__stat_time64", "stat"),
+            ("__isoc99_sscanf", "sscanf"),
+            ("__open_2", "open"),
+            ("__stack_chk_fail", "__stack_chk_fail"),
+            ("__sprintf_chk", "__sprintf_chk"),
+        ] {
+            assert_eq!(get_canonical_name(name), (expected_name.to_string(), None));
+        }
+        assert_eq!(
+            get_canonical_name("__builtin___memcpy_chk"),
+            ("memcpy".to_string(), Some((0, 3)))
+        );
+        assert_eq!(
+            get_canonical_name("__read_chk@GLIBC_2.4"),
+            ("read".to_string(), Some((1, 3)))
+        );
+    }
+
+    #[test]
+    fn symbol_renaming() {
+        let mut project = Project::mock_x64();
+        for name in ["__strcpy_chk", "free@GLIBC_2.2.5", "puts"] {
+            let symbol = ExternSymbol::mock_x64(name);
+            project
+                .program
+                .term
+                .extern_symbols
+                .insert(symbol.tid.clone(), symbol);
+        }
+        let logs = normalize_extern_symbol_names(&mut project);
+        assert_eq!(logs.len(), 1);
+
+        let names: Vec<&str> = project
+            .program
+            .term
+            .extern_symbols
+            .values()
+            .map(|symbol| symbol.name.as_str())
+            .collect();
+        assert!(names.contains(&"strcpy"));
+        assert!(names.contains(&"free"));
+        assert!(names.contains(&"puts"));
+        assert_eq!(
+            project.program.term.fortified_symbols[&Tid::new("__strcpy_chk")],
+            FortifiedSymbol {
+                original_name: "__strcpy_chk".to_string(),
+                destination_index: 0,
+                object_size_index: 2,
+            }
+        );
+        assert_eq!(project.program.term.fortified_symbols.len(), 1);
+    }
+}
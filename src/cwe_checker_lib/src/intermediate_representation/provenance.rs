@@ -0,0 +1,249 @@
+use super::{Program, Project};
+use crate::prelude::*;
+use crate::utils::log::CweWarning;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// A normalization pass that may create new terms or move terms to other blocks or functions.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum NormalizationPass {
+    /// The insertion of artificial sink blocks and functions as targets for
+    /// jumps to nonexisting targets and returns from non-returning calls.
+    ArtificialSinks,
+    /// The duplication of blocks contained in more than one function.
+    BlockDuplication,
+    /// The replacement of SIMD user operations with assignments to the vector registers.
+    SimdSubstitution,
+    /// The splitting of copies through vector registers into pointer-sized copies.
+    VectorCopySplitting,
+}
+
+impl fmt::Display for NormalizationPass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NormalizationPass::ArtificialSinks => write!(f, "artificial sinks"),
+            NormalizationPass::BlockDuplication => write!(f, "block duplication"),
+            NormalizationPass::SimdSubstitution => write!(f, "SIMD substitution"),
+            NormalizationPass::VectorCopySplitting => write!(f, "vector copy splitting"),
+        }
+    }
+}
+
+/// The provenance of a term, linking it back to the instruction in the binary that it was lifted from.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Default)]
+pub struct Provenance {
+    /// The address of the instruction that the term was lifted from.
+    /// Is `None` for terms that do not correspond to an instruction, e.g. artificial sinks.
+    pub address: Option<String>,
+    /// The length of the instruction in bytes, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instruction_length: Option<u64>,
+    /// The normalization passes that created or moved the term, in the order in which they ran.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub passes: Vec<NormalizationPass>,
+}
+
+impl Provenance {
+    /// Returns `true` if nothing is known about the provenance of the term.
+    pub fn is_empty(&self) -> bool {
+        self.address.is_none() && self.passes.is_empty()
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.address, self.instruction_length) {
+            (Some(address), Some(length)) => write!(f, "@{address} ({length} bytes)")?,
+            (Some(address), None) => write!(f, "@{address}")?,
+            (None, _) => write!(f, "@UNKNOWN")?,
+        }
+        if !self.passes.is_empty() {
+            let passes: Vec<String> = self.passes.iter().map(|pass| pass.to_string()).collect();
+            write!(f, " via {}", passes.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A side table recording the provenance of the terms of a program.
+///
+/// The addresses of the terms are already contained in their TIDs.
+/// The table adds the lengths of the instructions at these addresses
+/// (as reported by the disassembler) and the normalization passes that created or moved a term.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct ProvenanceTable {
+    /// Maps instruction addresses to the length of the instruction in bytes.
+    instruction_lengths: BTreeMap<String, u64>,
+    /// Maps term identifiers to the normalization passes that created or moved the term.
+    #[serde(with = "crate::utils::map_as_list")]
+    passes: BTreeMap<Tid, Vec<NormalizationPass>>,
+}
+
+impl ProvenanceTable {
+    /// Create a new table from the instruction lengths reported by the disassembler.
+    pub fn new(instruction_lengths: BTreeMap<String, u64>) -> ProvenanceTable {
+        ProvenanceTable {
+            instruction_lengths,
+            passes: BTreeMap::new(),
+        }
+    }
+
+    /// Record that the given normalization pass created or moved the term with the given TID.
+    pub fn record_pass(&mut self, tid: Tid, pass: NormalizationPass) {
+        let passes = self.passes.entry(tid).or_default();
+        if passes.last() != Some(&pass) {
+            passes.push(pass);
+        }
+    }
+
+    /// Get the provenance of the term with the given TID.
+    pub fn get(&self, tid: &Tid) -> Provenance {
+        let address = (!tid.has_unknown_address()).then(|| tid.address.clone());
+        Provenance {
+            instruction_length: address
+                .as_ref()
+                .and_then(|address| self.instruction_lengths.get(address).copied()),
+            address,
+            passes: self.passes.get(tid).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+impl Program {
+    /// Generate a map from all `Sub`, `Blk`, `Def` and `Jmp` TIDs of the program
+    /// to the TID of the term directly containing them.
+    /// `Sub` TIDs are mapped to themselves.
+    fn get_term_locations(&self) -> HashMap<Tid, Tid> {
+        let mut locations = HashMap::new();
+        for sub in self.subs.values() {
+            locations.insert(sub.tid.clone(), sub.tid.clone());
+            for blk in sub.term.blocks.iter() {
+                locations.insert(blk.tid.clone(), sub.tid.clone());
+                for def in blk.term.defs.iter() {
+                    locations.insert(def.tid.clone(), blk.tid.clone());
+                }
+                for jmp in blk.term.jmps.iter() {
+                    locations.insert(jmp.tid.clone(), blk.tid.clone());
+                }
+            }
+        }
+        locations
+    }
+
+    /// Add the provenance of the terms referenced by the given CWE warnings to the warnings.
+    ///
+    /// The provenance is only added if at least one term of a warning was created or moved
+    /// by a normalization pass or if the length of an instruction of the warning is known.
+    pub fn add_provenance_to_warnings(&self, warnings: &mut [CweWarning]) {
+        let tids: HashMap<String, &Tid> = self
+            .subs
+            .values()
+            .flat_map(|sub| {
+                std::iter::once(&sub.tid).chain(sub.term.blocks.iter().flat_map(|blk| {
+                    std::iter::once(&blk.tid)
+                        .chain(blk.term.defs.iter().map(|def| &def.tid))
+                        .chain(blk.term.jmps.iter().map(|jmp| &jmp.tid))
+                }))
+            })
+            .map(|tid| (tid.to_string(), tid))
+            .collect();
+        for warning in warnings.iter_mut() {
+            let provenance: Vec<Provenance> = warning
+                .tids
+                .iter()
+                .map(|id| {
+                    tids.get(id)
+                        .map(|tid| self.provenance.get(tid))
+                        .unwrap_or_default()
+                })
+                .collect();
+            if provenance.iter().any(|provenance| {
+                provenance.instruction_length.is_some() || !provenance.passes.is_empty()
+            }) {
+                warning.provenance = provenance;
+            }
+        }
+    }
+}
+
+impl Project {
+    /// Run the given normalization pass and record the terms that it created
+    /// or moved to another block or function in the provenance table of the program.
+    pub(super) fn run_recorded_pass<T>(
+        &mut self,
+        pass: NormalizationPass,
+        run: impl FnOnce(&mut Project) -> T,
+    ) -> T {
+        let locations_before = self.program.term.get_term_locations();
+        let result = run(self);
+        for (tid, location) in self.program.term.get_term_locations() {
+            if locations_before.get(&tid) != Some(&location) {
+                self.program.term.provenance.record_pass(tid, pass);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intermediate_representation::{Blk, Def, Expression, Sub};
+    use crate::{def, variable};
+
+    #[test]
+    fn provenance_of_terms() {
+        let mut table = ProvenanceTable::new(BTreeMap::from([("0x1000".to_string(), 4)]));
+        let mut tid = Tid::new("instr_0x1000_0");
+        tid.address = "0x1000".to_string();
+        table.record_pass(tid.clone(), NormalizationPass::BlockDuplication);
+        table.record_pass(tid.clone(), NormalizationPass::BlockDuplication);
+        let provenance = table.get(&tid);
+        assert_eq!(provenance.address, Some("0x1000".to_string()));
+        assert_eq!(provenance.instruction_length, Some(4));
+        assert_eq!(provenance.passes, vec![NormalizationPass::BlockDuplication]);
+        assert_eq!(
+            provenance.to_string(),
+            "@0x1000 (4 bytes) via block duplication"
+        );
+
+        let sink = table.get(&Tid::artificial_sink_sub());
+        assert!(sink.is_empty());
+        assert_eq!(sink.to_string(), "@UNKNOWN");
+    }
+
+    #[test]
+    fn record_created_and_moved_terms() {
+        let mut project = Project::mock_x64();
+        let mut blk = Blk::mock_with_tid("blk");
+        blk.term.defs = vec![def!["def: RAX:8 = 0x0:8"]];
+        let mut sub = Sub::mock("sub");
+        sub.term.blocks = vec![blk];
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+
+        project.run_recorded_pass(NormalizationPass::SimdSubstitution, |project| {
+            let blk = &mut project
+                .program
+                .term
+                .subs
+                .get_mut(&Tid::new("sub"))
+                .unwrap()
+                .term
+                .blocks[0];
+            blk.term.defs.push(Term {
+                tid: Tid::new("new_def"),
+                term: Def::Assign {
+                    var: variable!("RBX:8"),
+                    value: Expression::Var(variable!("RAX:8")),
+                },
+            });
+        });
+        let provenance = &project.program.term.provenance;
+        assert_eq!(
+            provenance.get(&Tid::new("new_def")).passes,
+            vec![NormalizationPass::SimdSubstitution]
+        );
+        assert!(provenance.get(&Tid::new("def")).passes.is_empty());
+        assert!(provenance.get(&Tid::new("blk")).passes.is_empty());
+    }
+}
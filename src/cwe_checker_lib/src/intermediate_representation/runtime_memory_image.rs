@@ -1,16 +1,47 @@
 use super::*;
-use crate::utils::binary::{parse_hex_string_to_u64, BareMetalConfig, MemorySegment};
+use crate::utils::binary::{
+    parse_hex_string_to_u64, BareMetalConfig, BootSection, MemorySegment, OverlayConfig,
+};
 use goblin::{elf, Object};
+use std::collections::BTreeMap;
+
+/// An address space of the memory image.
+///
+/// Most architectures use a single address space for code and data.
+/// Harvard architectures (e.g. AVR microcontrollers) have a separate address space for code,
+/// so that the same address may denote a location in program memory and a different location in RAM.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Default)]
+pub enum AddressSpace {
+    /// The address space accessed by load and store instructions.
+    /// On architectures with a single address space it also contains the code.
+    #[default]
+    Data,
+    /// The program memory of Harvard architectures.
+    Code,
+    /// An overlay with the given name, i.e. alternative contents mapped to addresses of the data address space,
+    /// e.g. a bank of flash memory.
+    Overlay(String),
+}
+
+/// The data address space, used for returning references to it.
+static DATA_ADDRESS_SPACE: AddressSpace = AddressSpace::Data;
 
 /// A representation of the runtime image of a binary after being loaded into memory by the loader.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct RuntimeMemoryImage {
-    /// Sequence of memory segments.
+    /// Sequence of memory segments of the data address space.
+    /// Segments found earlier take precedence on lookup.
     pub memory_segments: Vec<MemorySegment>,
+    /// The memory segments of all address spaces other than the data address space.
+    #[serde(default, with = "crate::utils::map_as_list")]
+    pub other_address_spaces: BTreeMap<AddressSpace, Vec<MemorySegment>>,
     /// Endianness
     pub is_little_endian: bool,
     /// True iff we are analyzing a Linux loadable kernel module.
     pub is_lkm: bool,
+    /// True iff the memory image was reconstructed from a snapshot of a running process, e.g. a core dump.
+    /// The contents of writeable memory are then the values at the time the snapshot was taken.
+    pub is_snapshot: bool,
 }
 
 impl RuntimeMemoryImage {
@@ -21,6 +52,8 @@ impl RuntimeMemoryImage {
             memory_segments: Vec::new(),
             is_little_endian,
             is_lkm: false,
+            is_snapshot: false,
+            other_address_spaces: BTreeMap::new(),
         }
     }
 
@@ -51,6 +84,8 @@ impl RuntimeMemoryImage {
                     memory_segments,
                     is_little_endian: true,
                     is_lkm: false,
+                    is_snapshot: false,
+                    other_address_spaces: BTreeMap::new(),
                 };
                 memory_image.add_global_memory_offset(pe_file.image_base as u64);
                 Ok(memory_image)
@@ -77,6 +112,8 @@ impl RuntimeMemoryImage {
             memory_segments,
             is_little_endian: elf_file.header.endianness().unwrap().is_little(),
             is_lkm: false,
+            is_snapshot: false,
+            other_address_spaces: BTreeMap::new(),
         })
     }
 
@@ -115,14 +152,20 @@ impl RuntimeMemoryImage {
             is_little_endian: elf_file.header.endianness().unwrap().is_little(),
             is_lkm: get_section(".modinfo", &elf_file).is_some()
                 && get_section(".gnu.linkonce.this_module", &elf_file).is_some(),
+            is_snapshot: false,
+            other_address_spaces: BTreeMap::new(),
         })
     }
 
     /// Generate a runtime memory image for a bare metal binary.
     ///
     /// The generated runtime memory image contains:
-    /// * one memory region corresponding to non-volatile memory
+    /// * one memory region corresponding to non-volatile memory,
+    ///   which is part of the code address space on Harvard architectures
     /// * one memory region corresponding to volatile memory (RAM)
+    /// * one memory region for each boot section, containing its contents after the startup code copied
+    ///   or decompressed it
+    /// * one overlay address space for each overlay
     ///
     /// See [`BareMetalConfig`] for more information about the assumed memory layout for bare metal binaries.
     pub fn new_from_bare_metal(
@@ -152,13 +195,96 @@ impl RuntimeMemoryImage {
             None => return Err(anyhow!("Binary too large for given base address")),
         }
 
+        let binary_segment = MemorySegment::from_bare_metal_file(binary, flash_base_address);
+        // Segments found earlier take precedence on lookup,
+        // so the contents of boot sections shadow the zero-initialized RAM.
+        let mut memory_segments = bare_metal_config
+            .boot_sections
+            .iter()
+            .map(|section| get_boot_section_segment(binary, section))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut other_address_spaces = BTreeMap::new();
+        if bare_metal_config.harvard_architecture {
+            // Program memory cannot be written by store instructions.
+            let code_segment = MemorySegment {
+                write_flag: false,
+                ..binary_segment
+            };
+            other_address_spaces.insert(AddressSpace::Code, vec![code_segment]);
+        } else {
+            memory_segments.push(binary_segment);
+        }
+        memory_segments.push(MemorySegment::new_bare_metal_ram_segment(
+            ram_base_address,
+            ram_size,
+        ));
+        for overlay in bare_metal_config.overlays.iter() {
+            other_address_spaces.insert(
+                AddressSpace::Overlay(overlay.name.clone()),
+                vec![get_overlay_segment(binary, overlay)?],
+            );
+        }
+
         Ok(RuntimeMemoryImage {
-            memory_segments: vec![
-                MemorySegment::from_bare_metal_file(binary, flash_base_address),
-                MemorySegment::new_bare_metal_ram_segment(ram_base_address, ram_size),
-            ],
+            memory_segments,
             is_little_endian,
             is_lkm: false,
+            is_snapshot: false,
+            other_address_spaces,
+        })
+    }
+
+    /// Generate a runtime memory image from an ELF core dump of a running process of the given binary.
+    ///
+    /// The memory segments contained in the core dump (including those of loaded libraries)
+    /// are shifted by the load bias of the binary, so that the addresses of the binary
+    /// match the addresses used by the analyzed program.
+    /// The load bias is computed from the `AT_ENTRY` entry of the auxiliary vector in the core dump.
+    /// Memory regions missing from the core dump (e.g. because the kernel does not dump read-only file mappings)
+    /// are filled with the memory segments of the binary itself.
+    ///
+    /// Note that pointers stored in the snapshot are runtime addresses,
+    /// i.e. they are not adjusted by the load bias.
+    pub fn new_from_core_dump(binary: &[u8], core_dump: &[u8]) -> Result<Self, Error> {
+        let binary_image = Self::new(binary)?;
+        let Object::Elf(binary_file) = Object::parse(binary)? else {
+            return Err(anyhow!("Core dumps are only supported for ELF binaries."));
+        };
+        let Object::Elf(core_file) = Object::parse(core_dump)? else {
+            return Err(anyhow!("The core dump is not an ELF file."));
+        };
+        if core_file.header.e_type != elf::header::ET_CORE {
+            return Err(anyhow!(
+                "Unexpected ELF type for core dump: e_type {}",
+                core_file.header.e_type
+            ));
+        }
+        let load_bias = get_auxv_entry(&core_file, core_dump, AT_ENTRY)
+            .map(|runtime_entry| runtime_entry.wrapping_sub(binary_file.entry))
+            .unwrap_or(0);
+
+        let mut memory_segments: Vec<MemorySegment> = core_file
+            .program_headers
+            .iter()
+            .filter(|header| header.p_type == elf::program_header::PT_LOAD && header.p_filesz > 0)
+            .map(|header| {
+                let mut segment = MemorySegment::from_elf_segment(core_dump, header);
+                segment.base_address = segment.base_address.wrapping_sub(load_bias);
+                segment
+            })
+            .collect();
+        if memory_segments.is_empty() {
+            return Err(anyhow!("No memory segments found in the core dump"));
+        }
+        // Segments found earlier take precedence on lookup, so the binary only fills the gaps.
+        memory_segments.extend(binary_image.memory_segments);
+
+        Ok(RuntimeMemoryImage {
+            memory_segments,
+            is_little_endian: binary_image.is_little_endian,
+            is_lkm: false,
+            is_snapshot: true,
+            other_address_spaces: binary_image.other_address_spaces,
         })
     }
 
@@ -197,15 +323,76 @@ impl RuntimeMemoryImage {
         self.is_little_endian
     }
 
-    /// Add a global offset to the base addresses of all memory segments.
+    /// Add a global offset to the base addresses of all memory segments of all address spaces.
     /// Useful to align the addresses with those reported by Ghidra
     /// if the Ghidra backend added such an offset to all addresses.
     pub fn add_global_memory_offset(&mut self, offset: u64) {
-        for segment in self.memory_segments.iter_mut() {
+        for segment in self
+            .memory_segments
+            .iter_mut()
+            .chain(self.other_address_spaces.values_mut().flatten())
+        {
             segment.base_address += offset;
         }
     }
 
+    /// Get the memory segments of the given address space.
+    pub fn get_segments(&self, address_space: &AddressSpace) -> &[MemorySegment] {
+        match address_space {
+            AddressSpace::Data => &self.memory_segments,
+            _ => self
+                .other_address_spaces
+                .get(address_space)
+                .map_or(&[], Vec::as_slice),
+        }
+    }
+
+    /// Iterate over the memory segments of all address spaces together with their address space.
+    pub fn iter_segments(&self) -> impl Iterator<Item = (&AddressSpace, &MemorySegment)> {
+        self.memory_segments
+            .iter()
+            .map(|segment| (&DATA_ADDRESS_SPACE, segment))
+            .chain(
+                self.other_address_spaces
+                    .iter()
+                    .flat_map(|(address_space, segments)| {
+                        segments.iter().map(move |segment| (address_space, segment))
+                    }),
+            )
+    }
+
+    /// Returns `true` if the code of the program is contained in a separate code address space.
+    pub fn is_harvard_architecture(&self) -> bool {
+        self.other_address_spaces.contains_key(&AddressSpace::Code)
+    }
+
+    /// Get the memory segments containing the code of the program.
+    ///
+    /// These are the segments of the code address space on Harvard architectures
+    /// and the executable segments of the data address space otherwise.
+    pub fn get_code_segments(&self) -> Vec<&MemorySegment> {
+        if self.is_harvard_architecture() {
+            self.get_segments(&AddressSpace::Code).iter().collect()
+        } else {
+            self.memory_segments
+                .iter()
+                .filter(|segment| segment.execute_flag)
+                .collect()
+        }
+    }
+
+    /// Returns `true` if an overlay is mapped to at least one of the `size` bytes starting at the given address.
+    fn is_covered_by_overlay(&self, address: u64, size: u64) -> bool {
+        self.other_address_spaces
+            .iter()
+            .filter(|(address_space, _)| matches!(address_space, AddressSpace::Overlay(_)))
+            .flat_map(|(_, segments)| segments.iter())
+            .any(|segment| {
+                address < segment.base_address + segment.bytes.len() as u64
+                    && segment.base_address < address + size
+            })
+    }
+
     /// Read the contents of the memory image at the given address
     /// to emulate a read instruction to global data at runtime.
     ///
@@ -213,42 +400,116 @@ impl RuntimeMemoryImage {
     /// i.e. values are interpreted with the endianness of the CPU architecture.
     /// If the address points to a writeable segment, the returned value is a `Ok(None)` value,
     /// since the data may change during program execution.
+    /// The only exception are memory images reconstructed from snapshots,
+    /// where the value at the time of the snapshot is returned.
+    /// If an overlay is mapped to the address, the returned value is also `Ok(None)`,
+    /// since the contents depend on the overlay mapped at runtime.
     ///
     /// Returns an error if the address is not contained in the global data address range.
     pub fn read(&self, address: &Bitvector, size: ByteSize) -> Result<Option<Bitvector>, Error> {
+        self.read_from_address_space(&AddressSpace::Data, address, size)
+    }
+
+    /// Read the contents of the given address space at the given address.
+    ///
+    /// See [`read`](Self::read) for the handling of writeable memory.
+    /// Overlays are only considered for reads from the data address space.
+    ///
+    /// Returns an error if the address is not contained in the address space.
+    pub fn read_from_address_space(
+        &self,
+        address_space: &AddressSpace,
+        address: &Bitvector,
+        size: ByteSize,
+    ) -> Result<Option<Bitvector>, Error> {
+        let segment = self.get_segment_containing(address_space, address, size)?;
+        if segment.write_flag && !self.is_snapshot {
+            // The segment is writeable, thus we do not know the content at runtime.
+            return Ok(None);
+        }
+        if *address_space == AddressSpace::Data
+            && self.is_covered_by_overlay(address.try_to_u64().unwrap(), u64::from(size))
+        {
+            return Ok(None);
+        }
+        self.read_from_segment(segment, address, size).map(Some)
+    }
+
+    /// Read the contents of the memory image at the given address as contained in the binary,
+    /// even if the address points to a writeable segment.
+    ///
+    /// This is only sound for data that is not changed at runtime,
+    /// e.g. for vtables or other data in the `.data.rel.ro` section,
+    /// which is writeable during relocation but not afterwards.
+    ///
+    /// Returns an error if the address is not contained in the global data address range.
+    pub fn read_initial_value(
+        &self,
+        address: &Bitvector,
+        size: ByteSize,
+    ) -> Result<Bitvector, Error> {
+        let segment = self.get_segment_containing(&AddressSpace::Data, address, size)?;
+        self.read_from_segment(segment, address, size)
+    }
+
+    /// Get the memory segment of the address space fully containing the `size` bytes starting at the given address.
+    fn get_segment_containing(
+        &self,
+        address_space: &AddressSpace,
+        address: &Bitvector,
+        size: ByteSize,
+    ) -> Result<&MemorySegment, Error> {
         let address = address.try_to_u64().unwrap();
-        for segment in self.memory_segments.iter() {
-            if address >= segment.base_address
-                && u64::from(size) <= segment.base_address + segment.bytes.len() as u64
-                && address <= segment.base_address + segment.bytes.len() as u64 - u64::from(size)
-            {
-                if segment.write_flag {
-                    // The segment is writeable, thus we do not know the content at runtime.
-                    return Ok(None);
-                }
-                let index = (address - segment.base_address) as usize;
-                let mut bytes = segment.bytes[index..index + u64::from(size) as usize].to_vec();
-                if self.is_little_endian {
-                    bytes = bytes.into_iter().rev().collect();
-                }
-                let mut bytes = bytes.into_iter();
-                let mut bitvector = Bitvector::from_u8(bytes.next().unwrap());
-                for byte in bytes {
-                    let new_byte = Bitvector::from_u8(byte);
-                    bitvector = bitvector.bin_op(BinOpType::Piece, &new_byte)?;
-                }
-                return Ok(Some(bitvector));
-            }
+        self.get_segments(address_space)
+            .iter()
+            .find(|segment| {
+                address >= segment.base_address
+                    && u64::from(size) <= segment.base_address + segment.bytes.len() as u64
+                    && address
+                        <= segment.base_address + segment.bytes.len() as u64 - u64::from(size)
+            })
+            // No segment fully contains the read.
+            .ok_or_else(|| anyhow!("Address is not a valid global memory address."))
+    }
+
+    /// Read `size` bytes at the given address from the segment, which must contain the bytes.
+    ///
+    /// The read is endian-aware.
+    fn read_from_segment(
+        &self,
+        segment: &MemorySegment,
+        address: &Bitvector,
+        size: ByteSize,
+    ) -> Result<Bitvector, Error> {
+        let index = (address.try_to_u64().unwrap() - segment.base_address) as usize;
+        let mut bytes = segment.bytes[index..index + u64::from(size) as usize].to_vec();
+        if self.is_little_endian {
+            bytes = bytes.into_iter().rev().collect();
         }
-        // No segment fully contains the read.
-        Err(anyhow!("Address is not a valid global memory address."))
+        let mut bytes = bytes.into_iter();
+        let mut bitvector = Bitvector::from_u8(bytes.next().unwrap());
+        for byte in bytes {
+            let new_byte = Bitvector::from_u8(byte);
+            bitvector = bitvector.bin_op(BinOpType::Piece, &new_byte)?;
+        }
+        Ok(bitvector)
     }
 
     /// Read the contents of memory from a given address onwards until a null byte is reached and checks whether the
     /// content is a valid UTF8 string.
     pub fn read_string_until_null_terminator(&self, address: &Bitvector) -> Result<&str, Error> {
+        self.read_string_from_address_space(&AddressSpace::Data, address)
+    }
+
+    /// Read a null-terminated UTF8 string at the given address of the given address space,
+    /// e.g. a string constant in the program memory of a Harvard architecture.
+    pub fn read_string_from_address_space(
+        &self,
+        address_space: &AddressSpace,
+        address: &Bitvector,
+    ) -> Result<&str, Error> {
         let address = address.try_to_u64().unwrap();
-        for segment in self.memory_segments.iter() {
+        for segment in self.get_segments(address_space).iter() {
             if address >= segment.base_address
                 && address <= segment.base_address + segment.bytes.len() as u64
             {
@@ -361,6 +622,72 @@ impl RuntimeMemoryImage {
     }
 }
 
+/// The note type of the auxiliary vector in ELF core dumps.
+const NT_AUXV: u32 = 6;
+/// The key of the entry point of the program in the auxiliary vector.
+const AT_ENTRY: u64 = 9;
+
+/// Returns the value of the entry with the given key in the auxiliary vector of an ELF core dump.
+///
+/// The auxiliary vector is a sequence of key-value pairs of the word size of the architecture.
+fn get_auxv_entry(core_file: &elf::Elf, core_dump: &[u8], key: u64) -> Option<u64> {
+    let word_size = if core_file.is_64 { 8 } else { 4 };
+    let is_little_endian = core_file.little_endian;
+    let read_word = |bytes: &[u8]| -> u64 {
+        let mut word = [0u8; 8];
+        if is_little_endian {
+            word[..word_size].copy_from_slice(bytes);
+            u64::from_le_bytes(word)
+        } else {
+            word[8 - word_size..].copy_from_slice(bytes);
+            u64::from_be_bytes(word)
+        }
+    };
+    core_file
+        .iter_note_headers(core_dump)?
+        .filter_map(|note| note.ok())
+        .filter(|note| note.n_type == NT_AUXV)
+        .flat_map(|note| note.desc.chunks_exact(2 * word_size))
+        .find(|entry| read_word(&entry[..word_size]) == key)
+        .map(|entry| read_word(&entry[word_size..]))
+}
+
+/// Get the part of the binary at the given file offset with the given size,
+/// both given as hexadecimal strings.
+fn get_binary_part<'a>(binary: &'a [u8], file_offset: &str, size: &str) -> Result<&'a [u8], Error> {
+    let start = parse_hex_string_to_u64(file_offset)? as usize;
+    let size = parse_hex_string_to_u64(size)? as usize;
+    start
+        .checked_add(size)
+        .and_then(|end| binary.get(start..end))
+        .ok_or_else(|| anyhow!("Section at file offset {file_offset} not contained in the binary"))
+}
+
+/// Generate the memory segment containing the contents of a boot section
+/// after the startup code copied or decompressed it to its runtime address.
+fn get_boot_section_segment(binary: &[u8], section: &BootSection) -> Result<MemorySegment, Error> {
+    let contents = get_binary_part(binary, &section.file_offset, &section.size)?;
+    Ok(MemorySegment {
+        bytes: section
+            .compression
+            .decompress(contents)
+            .context("Could not decompress boot section")?,
+        base_address: parse_hex_string_to_u64(&section.runtime_address)?,
+        read_flag: true,
+        write_flag: !section.read_only,
+        execute_flag: false,
+    })
+}
+
+/// Generate the memory segment of an overlay.
+fn get_overlay_segment(binary: &[u8], overlay: &OverlayConfig) -> Result<MemorySegment, Error> {
+    let contents = get_binary_part(binary, &overlay.file_offset, &overlay.size)?;
+    Ok(MemorySegment::from_bare_metal_file(
+        contents,
+        parse_hex_string_to_u64(&overlay.base_address)?,
+    ))
+}
+
 /// Returns the section header of the first section with this name.
 fn get_section<'a>(name: &str, elf_file: &'a elf::Elf<'a>) -> Option<&'a elf::SectionHeader> {
     let sh_strtab = &elf_file.shdr_strtab;
@@ -397,6 +724,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_initial_value() {
+        let mem_image = RuntimeMemoryImage::mock();
+        let address = bitvec!("0x2000:8");
+        assert_eq!(mem_image.read(&address, ByteSize::new(8)).unwrap(), None);
+        assert_eq!(
+            mem_image
+                .read_initial_value(&address, ByteSize::new(8))
+                .unwrap(),
+            bitvec!("0x0:8")
+        );
+        assert!(mem_image
+            .read_initial_value(&bitvec!("0x2004:8"), ByteSize::new(8))
+            .is_err());
+    }
+
     #[test]
     fn ro_data_pointer() {
         let mem_image = RuntimeMemoryImage::mock();
@@ -420,4 +763,143 @@ mod tests {
                 .unwrap(),
         );
     }
+
+    /// Build a minimal little-endian x86-64 ELF file with the given type, entry point
+    /// and program headers, given as tuples of segment type, flags, virtual address and contents.
+    fn mock_elf(e_type: u16, entry: u64, segments: &[(u32, u32, u64, Vec<u8>)]) -> Vec<u8> {
+        let mut elf = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+        elf.resize(16, 0);
+        elf.extend(e_type.to_le_bytes());
+        elf.extend(0x3eu16.to_le_bytes());
+        elf.extend(1u32.to_le_bytes());
+        elf.extend(entry.to_le_bytes());
+        elf.extend(64u64.to_le_bytes());
+        elf.extend(0u64.to_le_bytes());
+        elf.extend(0u32.to_le_bytes());
+        elf.extend(64u16.to_le_bytes());
+        elf.extend(56u16.to_le_bytes());
+        elf.extend((segments.len() as u16).to_le_bytes());
+        elf.extend([64, 0, 0, 0, 0, 0]);
+        let mut offset = 64 + 56 * segments.len() as u64;
+        for (p_type, p_flags, vaddr, contents) in segments {
+            elf.extend(p_type.to_le_bytes());
+            elf.extend(p_flags.to_le_bytes());
+            elf.extend(offset.to_le_bytes());
+            elf.extend(vaddr.to_le_bytes());
+            elf.extend(vaddr.to_le_bytes());
+            elf.extend((contents.len() as u64).to_le_bytes());
+            elf.extend((contents.len() as u64).to_le_bytes());
+            elf.extend(4u64.to_le_bytes());
+            offset += contents.len() as u64;
+        }
+        for (_, _, _, contents) in segments {
+            elf.extend(contents);
+        }
+        elf
+    }
+
+    #[test]
+    fn memory_image_from_core_dump() {
+        use goblin::elf::program_header::{PF_R, PF_W, PT_LOAD, PT_NOTE};
+        let binary = mock_elf(
+            goblin::elf::header::ET_EXEC,
+            0x1000,
+            &[
+                (PT_LOAD, PF_R, 0x1000, vec![1, 2, 3, 4]),
+                (PT_LOAD, PF_R | PF_W, 0x2000, vec![0; 8]),
+            ],
+        );
+        let load_bias: u64 = 0x5555_0000_0000;
+        let mut auxv_note = Vec::new();
+        auxv_note.extend(5u32.to_le_bytes());
+        auxv_note.extend(32u32.to_le_bytes());
+        auxv_note.extend(6u32.to_le_bytes());
+        auxv_note.extend(b"CORE\0\0\0\0");
+        for word in [9, load_bias + 0x1000, 0, 0] {
+            auxv_note.extend(word.to_le_bytes());
+        }
+        let core_dump = mock_elf(
+            goblin::elf::header::ET_CORE,
+            0,
+            &[
+                (PT_NOTE, 0, 0, auxv_note),
+                (
+                    PT_LOAD,
+                    PF_R | PF_W,
+                    load_bias + 0x2000,
+                    vec![0x42, 0, 0, 0, 0, 0, 0, 0],
+                ),
+            ],
+        );
+
+        let mem_image = RuntimeMemoryImage::new_from_core_dump(&binary, &core_dump).unwrap();
+        assert!(mem_image.is_snapshot);
+        // Writeable memory contains the values from the time of the snapshot.
+        assert_eq!(
+            mem_image
+                .read(&bitvec!("0x2000:8"), ByteSize::new(8))
+                .unwrap(),
+            Some(bitvec!("0x42:8"))
+        );
+        // Memory missing in the core dump is taken from the binary.
+        assert_eq!(
+            mem_image
+                .read(&bitvec!("0x1000:8"), ByteSize::new(4))
+                .unwrap(),
+            Some(bitvec!("0x04030201:4"))
+        );
+        assert!(RuntimeMemoryImage::new_from_core_dump(&binary, &binary).is_err());
+    }
+
+    #[test]
+    fn bare_metal_address_spaces() {
+        let config: crate::utils::binary::BareMetalConfig =
+            serde_json::from_value(serde_json::json!({
+                "processor_id": "AVR8:LE:16:default",
+                "flash_base_address": "0x0",
+                "ram_base_address": "0x100",
+                "ram_size": "0x100",
+                "harvard_architecture": true,
+                "overlays": [
+                    {"name": "bank1", "base_address": "0x100", "file_offset": "0x0", "size": "0x4"}
+                ],
+                "boot_sections": [
+                    {
+                        "file_offset": "0x10",
+                        "size": "0x8",
+                        "runtime_address": "0x100",
+                        "compression": "lz4",
+                        "read_only": true
+                    }
+                ]
+            }))
+            .unwrap();
+        // The LZ4 block decompresses to "abcabcabc!".
+        let mut binary = vec![0x0c; 16];
+        binary.extend([0x32, b'a', b'b', b'c', 0x03, 0x00, 0x10, b'!']);
+        let mem_image = RuntimeMemoryImage::new_from_bare_metal(&binary, &config).unwrap();
+
+        assert!(mem_image.is_harvard_architecture());
+        assert_eq!(mem_image.get_code_segments().len(), 1);
+        assert!(mem_image.read(&bitvec!("0x0:2"), ByteSize::new(2)).is_err());
+        assert_eq!(
+            mem_image
+                .read_from_address_space(&AddressSpace::Code, &bitvec!("0x0:2"), ByteSize::new(2))
+                .unwrap(),
+            Some(bitvec!("0x0c0c:2"))
+        );
+        // The overlay is mapped to the first bytes of the boot section.
+        assert_eq!(
+            mem_image
+                .read(&bitvec!("0x100:2"), ByteSize::new(4))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            mem_image
+                .read(&bitvec!("0x104:2"), ByteSize::new(4))
+                .unwrap(),
+            Some(bitvec!("0x62616362:4"))
+        );
+    }
 }
@@ -85,6 +85,12 @@ impl Tid {
             && self.address == Self::UNKNOWN_ADDRESS
     }
 
+    /// Returns true if the address of the term is unknown,
+    /// e.g. because the term does not correspond to an instruction in the binary.
+    pub fn has_unknown_address(&self) -> bool {
+        self.address == Self::UNKNOWN_ADDRESS
+    }
+
     /// Returns true iff the ID is for the artificial sink sub.
     pub fn is_artificial_sink_sub(&self) -> bool {
         self.id == Self::ARTIFICIAL_SINK_SUB_ID && self.address == Self::UNKNOWN_ADDRESS
@@ -88,6 +88,8 @@ impl RuntimeMemoryImage {
             ],
             is_little_endian: true,
             is_lkm: false,
+            is_snapshot: false,
+            other_address_spaces: BTreeMap::new(),
         }
     }
 }
@@ -100,6 +102,9 @@ impl Program {
             extern_symbols: BTreeMap::from_iter(a),
             entry_points: BTreeSet::new(),
             address_base_offset: 0x1000u64,
+            stack_canary_checks: BTreeMap::new(),
+            fortified_symbols: BTreeMap::new(),
+            provenance: ProvenanceTable::default(),
         }
     }
 
@@ -158,6 +163,34 @@ impl Program {
             (other_function.tid.clone(), other_function),
         ])
     }
+
+    /// Returns Program with malloc, free and other_function
+    pub fn mock_riscv64() -> Program {
+        let malloc = ExternSymbol::create_extern_symbol(
+            "malloc",
+            CallingConvention::mock_riscv64(),
+            Some(Datatype::Integer),
+            Some(Datatype::Pointer),
+        );
+        let free = ExternSymbol::create_extern_symbol(
+            "free",
+            CallingConvention::mock_riscv64(),
+            Some(Datatype::Pointer),
+            None,
+        );
+        let other_function = ExternSymbol::create_extern_symbol(
+            "other_function",
+            CallingConvention::mock_riscv64(),
+            None,
+            None,
+        );
+
+        Program::add_extern_symbols_to_program(vec![
+            (malloc.tid.clone(), malloc),
+            (free.tid.clone(), free),
+            (other_function.tid.clone(), other_function),
+        ])
+    }
 }
 
 #[cfg(test)]
@@ -187,6 +220,7 @@ impl Project {
             register_set: integer_register.iter().cloned().collect(),
             datatype_properties: DatatypeProperties::mock_x64(),
             runtime_memory_image: RuntimeMemoryImage::mock(),
+            annotations: AnnotationMap::default(),
         }
     }
 
@@ -226,6 +260,40 @@ impl Project {
             register_set: integer_register.collect(),
             datatype_properties: DatatypeProperties::mock_arm32(),
             runtime_memory_image: RuntimeMemoryImage::mock(),
+            annotations: AnnotationMap::default(),
+        }
+    }
+
+    /// Returns project with RV64GC calling convention and mocked program.
+    pub fn mock_riscv64() -> Project {
+        let none_cconv_register: Vec<Variable> = vec![
+            "zero", "ra", "gp", "tp", "t0", "t1", "t2", "t3", "t4", "t5", "t6",
+        ]
+        .into_iter()
+        .map(|name| variable!(format!("{name}:8")))
+        .collect();
+
+        let calling_convention = CallingConvention::mock_riscv64();
+        let register_set = calling_convention
+            .integer_parameter_register
+            .iter()
+            .chain(calling_convention.callee_saved_register.iter())
+            .chain(none_cconv_register.iter())
+            .cloned()
+            .collect();
+
+        Project {
+            program: Term {
+                tid: Tid::new("program_tid"),
+                term: Program::mock_riscv64(),
+            },
+            cpu_architecture: "RISCV_64".to_string(),
+            stack_pointer_register: variable!("sp:8"),
+            calling_conventions: BTreeMap::from([("__stdcall".to_string(), calling_convention)]),
+            register_set,
+            datatype_properties: DatatypeProperties::mock_riscv64(),
+            runtime_memory_image: RuntimeMemoryImage::mock(),
+            annotations: AnnotationMap::default(),
         }
     }
 }
@@ -178,6 +178,21 @@ impl DatatypeProperties {
             short_size: ByteSize::new(2),
         }
     }
+
+    /// Datatype sizes according to the RISC-V LP64D ABI
+    pub fn mock_riscv64() -> DatatypeProperties {
+        DatatypeProperties {
+            char_size: ByteSize::new(1),
+            double_size: ByteSize::new(8),
+            float_size: ByteSize::new(4),
+            integer_size: ByteSize::new(4),
+            long_double_size: ByteSize::new(16),
+            long_long_size: ByteSize::new(8),
+            long_size: ByteSize::new(8),
+            pointer_size: ByteSize::new(8),
+            short_size: ByteSize::new(2),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -321,6 +336,33 @@ impl CallingConvention {
             ],
         }
     }
+
+    /// Following the RISC-V LP64D ABI of RV64GC
+    pub fn mock_riscv64() -> CallingConvention {
+        let registers = |names: &[&str]| -> Vec<Variable> {
+            names
+                .iter()
+                .map(|name| variable!(format!("{name}:8")))
+                .collect()
+        };
+        CallingConvention {
+            name: "__stdcall".to_string(), // so that the mock is useable as standard calling convention in tests
+            integer_parameter_register: registers(&[
+                "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7",
+            ]),
+            float_parameter_register: ["fa0", "fa1", "fa2", "fa3", "fa4", "fa5", "fa6", "fa7"]
+                .iter()
+                .map(|name| expr!(format!("{name}:8")))
+                .collect(),
+            integer_return_register: registers(&["a0", "a1"]),
+            float_return_register: vec![expr!("fa0:8"), expr!("fa1:8")],
+            callee_saved_register: registers(&[
+                "sp", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11",
+                "fs0", "fs1", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9", "fs10",
+                "fs11",
+            ]),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -379,6 +421,19 @@ impl ExternSymbol {
         }
     }
 
+    pub fn mock_riscv64(name: impl ToString) -> ExternSymbol {
+        ExternSymbol {
+            tid: Tid::new(name.to_string()),
+            addresses: vec!["UNKNOWN".to_string()],
+            name: name.to_string(),
+            calling_convention: Some("__stdcall".to_string()),
+            parameters: vec![Arg::mock_register("a0", 8)],
+            return_values: vec![Arg::mock_register("a0", 8)],
+            no_return: false,
+            has_var_args: false,
+        }
+    }
+
     pub fn mock_sprintf_x64() -> Self {
         ExternSymbol {
             tid: Tid::new("sprintf"),
@@ -59,6 +59,19 @@ Detailed usage instructions are contained in the file.
 [FACT](https://github.com/fkie-cad/FACT_core) already contains a ready-to-use cwe_checker plugin,
 which lets you run the cwe_checker and view its result through the FACT user interface.
 
+### Out-of-tree checks
+
+Additional CWE checks can be shipped as plugins, i.e. as dynamic libraries that are loaded at runtime.
+This requires building the cwe_checker with the `plugins` feature.
+See the [`plugins`] module for how to write a plugin.
+
+### Minimal builds
+
+Tools that only need some of the analyses can compile the library without its default features.
+The `string-abstraction` feature contains the string abstraction analysis
+and the `taint` feature the taint analysis framework.
+Checks that depend on a disabled analysis are not compiled and not returned by [`get_modules`].
+
 # Further documentation
 
 You can find out more information about each check, including known false positives and false negatives,
@@ -68,12 +81,17 @@ by reading the check-specific module documentation in the [`checkers`] module.
 pub mod abstract_domain;
 pub mod analysis;
 pub mod checkers;
+pub mod frontend;
 pub mod intermediate_representation;
 pub mod pcode;
 pub mod pipeline;
+pub mod plugins;
+pub mod query;
 pub mod utils;
 
-use utils::log::{CweWarning, LogMessage};
+use rayon::prelude::*;
+use utils::cancellation::CancellationToken;
+use utils::log::{timed_logging, CweWarning, LogCollector, LogMessage};
 
 mod prelude {
     pub use apint::Width;
@@ -113,24 +131,189 @@ impl std::fmt::Display for CweModule {
 /// Get a list of all known analysis modules.
 pub fn get_modules() -> Vec<&'static CweModule> {
     vec![
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_15::CWE_MODULE,
+        #[cfg(all(feature = "string-abstraction", feature = "taint"))]
         &crate::checkers::cwe_78::CWE_MODULE,
         &crate::checkers::cwe_119::CWE_MODULE,
+        #[cfg(feature = "taint")]
         &crate::checkers::cwe_134::CWE_MODULE,
         &crate::checkers::cwe_190::CWE_MODULE,
+        &crate::checkers::cwe_204::CWE_MODULE,
         &crate::checkers::cwe_215::CWE_MODULE,
+        &crate::checkers::cwe_242::CWE_MODULE,
         &crate::checkers::cwe_243::CWE_MODULE,
+        #[cfg(feature = "taint")]
         &crate::checkers::cwe_252::CWE_MODULE,
+        &crate::checkers::cwe_266::CWE_MODULE,
+        &crate::checkers::cwe_277::CWE_MODULE,
+        &crate::checkers::cwe_324::CWE_MODULE,
         &crate::checkers::cwe_332::CWE_MODULE,
+        #[cfg(feature = "taint")]
         &crate::checkers::cwe_337::CWE_MODULE,
+        &crate::checkers::cwe_347::CWE_MODULE,
+        &crate::checkers::cwe_354::CWE_MODULE,
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_362::CWE_MODULE,
         &crate::checkers::cwe_367::CWE_MODULE,
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_369::CWE_MODULE,
         &crate::checkers::cwe_416::CWE_MODULE,
         &crate::checkers::cwe_426::CWE_MODULE,
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_434::CWE_MODULE,
+        &crate::checkers::cwe_440::CWE_MODULE,
         &crate::checkers::cwe_467::CWE_MODULE,
+        #[cfg(feature = "taint")]
         &crate::checkers::cwe_476::CWE_MODULE,
+        #[cfg(feature = "string-abstraction")]
+        &crate::checkers::cwe_522::CWE_MODULE,
         &crate::checkers::cwe_560::CWE_MODULE,
+        &crate::checkers::cwe_613::CWE_MODULE,
+        &crate::checkers::cwe_649::CWE_MODULE,
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_656::CWE_MODULE,
+        &crate::checkers::cwe_666::CWE_MODULE,
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_668::CWE_MODULE,
         &crate::checkers::cwe_676::CWE_MODULE,
+        &crate::checkers::cwe_693::CWE_MODULE,
+        &crate::checkers::cwe_733::CWE_MODULE,
+        #[cfg(all(feature = "string-abstraction", feature = "taint"))]
+        &crate::checkers::cwe_759::CWE_MODULE,
+        &crate::checkers::cwe_770::CWE_MODULE,
         &crate::checkers::cwe_782::CWE_MODULE,
+        &crate::checkers::cwe_786::CWE_MODULE,
+        &crate::checkers::cwe_787::CWE_MODULE,
         &crate::checkers::cwe_789::CWE_MODULE,
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_804::CWE_MODULE,
+        &crate::checkers::cwe_805::CWE_MODULE,
+        &crate::checkers::cwe_925::CWE_MODULE,
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_941::CWE_MODULE,
+        &crate::checkers::cwe_1007::CWE_MODULE,
+        &crate::checkers::cwe_1177::CWE_MODULE,
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_1236::CWE_MODULE,
+        &crate::checkers::cwe_1269::CWE_MODULE,
+        &crate::checkers::cwe_1277::CWE_MODULE,
+        &crate::checkers::cwe_1287::CWE_MODULE,
+        #[cfg(feature = "taint")]
+        &crate::checkers::cwe_1292::CWE_MODULE,
+        #[cfg(feature = "string-abstraction")]
+        &crate::checkers::cwe_1325::CWE_MODULE,
+        &crate::checkers::cwe_1332::CWE_MODULE,
+        &crate::checkers::cwe_1419::CWE_MODULE,
         &crate::analysis::pointer_inference::CWE_MODULE,
     ]
 }
+
+/// Execute the given modules and send their logs and CWE warnings to the log collector.
+///
+/// The modules only get shared access to the project and the analysis results,
+/// so they are executed in parallel on a thread pool with the given number of threads.
+/// If `num_threads` is `None`, the number of threads is the number of available CPU cores.
+/// The order of the collected messages does not depend on the number of threads,
+/// since the log collector sorts them.
+///
+/// If the cancellation token of the analysis results is cancelled,
+/// the modules that have not started yet are skipped.
+/// Returns the names of the skipped modules.
+pub fn run_modules(
+    modules: &[&CweModule],
+    analysis_results: &AnalysisResults,
+    config: &serde_json::Value,
+    log_collector: &LogCollector,
+    num_threads: Option<usize>,
+) -> Result<Vec<&'static str>, Error> {
+    let mut thread_pool = rayon::ThreadPoolBuilder::new();
+    if let Some(num_threads) = num_threads {
+        thread_pool = thread_pool.num_threads(num_threads);
+    }
+    let thread_pool = thread_pool
+        .build()
+        .context("Could not create the thread pool for the CWE checks")?;
+    let skipped_modules = thread_pool.install(|| {
+        modules
+            .par_iter()
+            .filter_map(|module| {
+                if analysis_results
+                    .cancellation_token
+                    .is_some_and(CancellationToken::is_cancelled)
+                {
+                    return Some(module.name);
+                }
+                timed_logging(format!("Executing the modules - {}", module.name));
+                log_collector
+                    .logger(module.name)
+                    .send_results((module.run)(analysis_results, &config[module.name]));
+                None
+            })
+            .collect()
+    });
+    Ok(skipped_modules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intermediate_representation::Project;
+    use crate::utils::log::LogFilter;
+    use crate::utils::metrics::MetricsCollector;
+
+    #[test]
+    fn all_checks_report_site_counts() {
+        let config: serde_json::Value =
+            serde_json::from_str(include_str!("../../config.json")).unwrap();
+        let project = Project::mock_x64();
+        let metrics_collector = MetricsCollector::new();
+        let analysis_results = AnalysisResults::mock_from_project(&project)
+            .with_metrics_collector(Some(&metrics_collector));
+        let function_effects = analysis_results.compute_function_effects();
+        let analysis_results = analysis_results.with_function_effects(Some(&function_effects));
+        let pi_results = analysis_results.compute_pointer_inference(&config["Memory"], false);
+        let analysis_results = analysis_results.with_pointer_inference(Some(&pi_results));
+        #[cfg(feature = "string-abstraction")]
+        let string_abstraction = analysis_results
+            .compute_string_abstraction(&config["StringAbstraction"], Some(&pi_results));
+        #[cfg(feature = "string-abstraction")]
+        let analysis_results = analysis_results.with_string_abstraction(Some(&string_abstraction));
+        #[cfg(feature = "string-abstraction")]
+        let automaton_string_abstraction = analysis_results
+            .compute_string_abstraction(&config["StringAbstraction"], Some(&pi_results));
+        #[cfg(feature = "string-abstraction")]
+        let analysis_results =
+            analysis_results.with_automaton_string_abstraction(Some(&automaton_string_abstraction));
+        let escape_analysis = analysis_results.compute_escape_analysis(&pi_results);
+        let analysis_results = analysis_results.with_escape_analysis(Some(&escape_analysis));
+
+        let modules = get_modules();
+        let log_collector = LogCollector::spawn(LogFilter::default());
+        let skipped_modules = run_modules(
+            &modules,
+            &analysis_results,
+            &config,
+            &log_collector,
+            Some(1),
+        )
+        .unwrap();
+        assert!(skipped_modules.is_empty());
+        let collected_logs = log_collector.collect();
+
+        // The pointer inference module only forwards the logs of the pointer inference analysis
+        // and does not examine any sites itself.
+        let check_names: Vec<&str> = modules
+            .iter()
+            .map(|module| module.name)
+            .filter(|name| *name != crate::analysis::pointer_inference::CWE_MODULE.name)
+            .collect();
+        let metrics = metrics_collector.get_check_metrics(&check_names, &collected_logs.cwes);
+        for name in check_names {
+            assert!(
+                metrics[name].sites.is_some(),
+                "The check {name} does not report site counts"
+            );
+        }
+    }
+}
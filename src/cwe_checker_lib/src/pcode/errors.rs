@@ -0,0 +1,80 @@
+use super::{ExpressionType, JmpType};
+use crate::prelude::*;
+
+/// The reason why a P-Code instruction or function could not be translated into the internally used IR.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub enum LiftingError {
+    /// An input or output varnode required by the instruction is missing.
+    MissingOperand {
+        /// The mnemonic of the instruction.
+        mnemonic: String,
+        /// The name of the missing operand.
+        operand: String,
+    },
+    /// A varnode is neither a register nor a constant.
+    InvalidVarnode {
+        /// A textual representation of the varnode.
+        varnode: String,
+    },
+    /// A constant varnode contains a value that cannot be parsed.
+    InvalidConstant {
+        /// The value of the varnode.
+        value: String,
+    },
+    /// A direct jump has an indirect target or vice versa.
+    UnexpectedLabel {
+        /// The mnemonic of the jump.
+        mnemonic: JmpType,
+    },
+    /// The expression cannot be translated on its own.
+    UnsupportedExpression {
+        /// The mnemonic of the expression.
+        mnemonic: ExpressionType,
+    },
+    /// The function is not empty, but contains no block starting at the function address.
+    MissingEntryBlock,
+}
+
+impl LiftingError {
+    /// Create an error for a missing operand of an instruction with the given mnemonic.
+    pub fn missing_operand(mnemonic: impl std::fmt::Debug, operand: &str) -> LiftingError {
+        LiftingError::MissingOperand {
+            mnemonic: format!("{mnemonic:?}"),
+            operand: operand.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for LiftingError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LiftingError::MissingOperand { mnemonic, operand } => {
+                write!(
+                    formatter,
+                    "Operand {operand} of {mnemonic} instruction is missing"
+                )
+            }
+            LiftingError::InvalidVarnode { varnode } => {
+                write!(
+                    formatter,
+                    "Varnode {varnode} is neither a register nor a constant"
+                )
+            }
+            LiftingError::InvalidConstant { value } => {
+                write!(formatter, "Constant {value} cannot be parsed")
+            }
+            LiftingError::UnexpectedLabel { mnemonic } => {
+                write!(
+                    formatter,
+                    "Unexpected jump target type for {mnemonic:?} instruction"
+                )
+            }
+            LiftingError::UnsupportedExpression { mnemonic } => {
+                write!(formatter, "Expression {mnemonic:?} cannot be translated")
+            }
+            LiftingError::MissingEntryBlock => write!(formatter, "Starting block not found"),
+        }
+    }
+}
+
+impl std::error::Error for LiftingError {}
@@ -1,4 +1,4 @@
-use super::Def;
+use super::{Def, LiftingError};
 use crate::intermediate_representation::BinOpType as IrBinOpType;
 use crate::intermediate_representation::ByteSize;
 use crate::intermediate_representation::CastOpType as IrCastOpType;
@@ -35,52 +35,56 @@ impl From<Variable> for IrVariable {
     }
 }
 
-impl From<Variable> for IrExpression {
+impl TryFrom<Variable> for IrExpression {
+    type Error = LiftingError;
+
     /// Translate a P-Code variable into a `Var`or `Const` expression of the internally used IR.
-    /// Panics if the translation fails.
-    fn from(pcode_var: Variable) -> IrExpression {
+    /// Returns an error if the variable is neither a register nor a constant.
+    fn try_from(pcode_var: Variable) -> Result<IrExpression, LiftingError> {
         match (&pcode_var.name, &pcode_var.value) {
-            (Some(_name), None) => IrExpression::Var(pcode_var.into()),
-            (None, Some(_hex_value)) => IrExpression::Const(pcode_var.parse_const_to_bitvector()),
-            _ => panic!("Conversion failed:\n{pcode_var:?}"),
+            (Some(_name), None) => Ok(IrExpression::Var(pcode_var.into())),
+            (None, Some(_hex_value)) => {
+                Ok(IrExpression::Const(pcode_var.parse_const_to_bitvector()?))
+            }
+            _ => Err(pcode_var.invalid_varnode_error()),
         }
     }
 }
 
 impl Variable {
+    /// Translate a P-Code variable into a register variable of the internally used IR.
+    /// Returns an error if the variable does not represent a register.
+    pub fn try_into_register(self) -> Result<IrVariable, LiftingError> {
+        if self.name.is_some() {
+            Ok(self.into())
+        } else {
+            Err(self.invalid_varnode_error())
+        }
+    }
+
     /// Parses a variable representing a concrete value to a bitvector containing the value.
-    pub fn parse_const_to_bitvector(&self) -> Bitvector {
+    pub fn parse_const_to_bitvector(&self) -> Result<Bitvector, LiftingError> {
         match &self.value {
-            Some(hex_value) => {
-                let mut bitvector = Bitvector::from_str_radix(16, hex_value).unwrap();
-                match bitvector.width().cmp(&self.size.into()) {
-                    std::cmp::Ordering::Greater => bitvector.truncate(self.size).unwrap(),
-                    std::cmp::Ordering::Less => bitvector.zero_extend(self.size).unwrap(),
-                    std::cmp::Ordering::Equal => (),
-                }
-                bitvector
-            }
-            _ => panic!(),
+            Some(hex_value) => parse_hex_to_bitvector(hex_value, self.size),
+            None => Err(self.invalid_varnode_error()),
         }
     }
 
     /// Parses a variable representing an address to a pointer-sized bitvector containing the address.
-    pub fn parse_address_to_bitvector(&self, generic_pointer_size: ByteSize) -> Bitvector {
+    pub fn parse_address_to_bitvector(
+        &self,
+        generic_pointer_size: ByteSize,
+    ) -> Result<Bitvector, LiftingError> {
         match &self.address {
-            Some(hex_value) => {
-                let mut bitvector = Bitvector::from_str_radix(16, hex_value).unwrap();
-                match bitvector.width().cmp(&generic_pointer_size.into()) {
-                    std::cmp::Ordering::Greater => {
-                        bitvector.truncate(generic_pointer_size).unwrap()
-                    }
-                    std::cmp::Ordering::Less => {
-                        bitvector.zero_extend(generic_pointer_size).unwrap()
-                    }
-                    std::cmp::Ordering::Equal => (),
-                }
-                bitvector
-            }
-            _ => panic!(),
+            Some(hex_value) => parse_hex_to_bitvector(hex_value, generic_pointer_size),
+            None => Err(self.invalid_varnode_error()),
+        }
+    }
+
+    /// Generate the error for varnodes that do not have the expected type.
+    fn invalid_varnode_error(&self) -> LiftingError {
+        LiftingError::InvalidVarnode {
+            varnode: format!("{self:?}"),
         }
     }
 
@@ -126,19 +130,42 @@ impl Variable {
         }
     }
 
-    /// Translates a variable into the byte size that it represents. Panics on error.
-    pub fn parse_to_bytesize(self) -> ByteSize {
+    /// Translates a variable into the byte size that it represents.
+    pub fn parse_to_bytesize(self) -> Result<ByteSize, LiftingError> {
         match (&self.name, &self.value) {
-            (None, Some(hex_value)) => {
-                assert!(u64::from(self.size) <= 8);
-                let val: u64 = u64::from_str_radix(hex_value, 16).unwrap();
-                val.into()
+            (None, Some(hex_value)) if u64::from(self.size) <= 8 => {
+                let val: u64 = u64::from_str_radix(hex_value, 16).map_err(|_| {
+                    LiftingError::InvalidConstant {
+                        value: hex_value.clone(),
+                    }
+                })?;
+                Ok(val.into())
             }
-            _ => panic!(),
+            _ => Err(self.invalid_varnode_error()),
         }
     }
 }
 
+/// Parse a hexadecimal number to a bitvector of the given size.
+/// The number is truncated or zero-extended to the given size if necessary.
+fn parse_hex_to_bitvector(hex_value: &str, size: ByteSize) -> Result<Bitvector, LiftingError> {
+    let invalid_constant_error = || LiftingError::InvalidConstant {
+        value: hex_value.to_string(),
+    };
+    let mut bitvector =
+        Bitvector::from_str_radix(16, hex_value).map_err(|_| invalid_constant_error())?;
+    match bitvector.width().cmp(&size.into()) {
+        std::cmp::Ordering::Greater => bitvector
+            .truncate(size)
+            .map_err(|_| invalid_constant_error())?,
+        std::cmp::Ordering::Less => bitvector
+            .zero_extend(size)
+            .map_err(|_| invalid_constant_error())?,
+        std::cmp::Ordering::Equal => (),
+    }
+    Ok(bitvector)
+}
+
 /// A P-Code expression.
 ///
 /// P-Code itself does not divide instructions into expressions, definitions and jumps,
@@ -157,20 +184,26 @@ pub struct Expression {
     pub input2: Option<Variable>,
 }
 
-impl From<Expression> for IrExpression {
+impl TryFrom<Expression> for IrExpression {
+    type Error = LiftingError;
+
     /// Translates a P-Code expression into an expression of the internally used IR if possible.
-    /// Panics if translation is not possible.
+    /// Returns an error if translation is not possible.
     ///
     /// Cases where translation is not possible:
     /// - `LOAD` and `STORE`, since these are not expressions (they have side effects).
     /// - Expressions which store the size of their output in the output variable (to which we do not have access here).
     /// These include `SUBPIECE`, `INT_ZEXT`, `INT_SEXT`, `INT2FLOAT`, `FLOAT2FLOAT`, `TRUNC`, `LZCOUNT` and `POPCOUNT`.
     /// Translation of these expressions is handled explicitly during translation of `Def`.
-    fn from(expr: Expression) -> IrExpression {
+    fn try_from(expr: Expression) -> Result<IrExpression, LiftingError> {
         use ExpressionType::*;
-        match expr.mnemonic {
-            COPY => expr.input0.unwrap().into(),
-            LOAD | STORE | SUBPIECE => panic!(),
+        let expression = match expr.mnemonic {
+            COPY => expr.get_input0()?.try_into()?,
+            LOAD | STORE | SUBPIECE => {
+                return Err(LiftingError::UnsupportedExpression {
+                    mnemonic: expr.mnemonic,
+                })
+            }
             PIECE | INT_EQUAL | INT_NOTEQUAL | INT_LESS | INT_SLESS | INT_LESSEQUAL
             | INT_SLESSEQUAL | INT_ADD | INT_SUB | INT_CARRY | INT_SCARRY | INT_SBORROW
             | INT_XOR | INT_AND | INT_OR | INT_LEFT | INT_RIGHT | INT_SRIGHT | INT_MULT
@@ -178,16 +211,44 @@ impl From<Expression> for IrExpression {
             | FLOAT_EQUAL | FLOAT_NOTEQUAL | FLOAT_LESS | FLOAT_LESSEQUAL | FLOAT_ADD
             | FLOAT_SUB | FLOAT_MULT | FLOAT_DIV => IrExpression::BinOp {
                 op: expr.mnemonic.into(),
-                lhs: Box::new(expr.input0.unwrap().into()),
-                rhs: Box::new(expr.input1.unwrap().into()),
+                lhs: Box::new(expr.get_input0()?.try_into()?),
+                rhs: Box::new(expr.get_input1()?.try_into()?),
             },
             INT_NEGATE | INT_2COMP | BOOL_NEGATE | FLOAT_NEG | FLOAT_ABS | FLOAT_SQRT
             | FLOAT_CEIL | FLOAT_FLOOR | FLOAT_ROUND | FLOAT_NAN => IrExpression::UnOp {
                 op: expr.mnemonic.into(),
-                arg: Box::new(expr.input0.unwrap().into()),
+                arg: Box::new(expr.get_input0()?.try_into()?),
             },
-            INT_ZEXT | INT_SEXT | INT2FLOAT | FLOAT2FLOAT | TRUNC | POPCOUNT | LZCOUNT => panic!(),
-        }
+            INT_ZEXT | INT_SEXT | INT2FLOAT | FLOAT2FLOAT | TRUNC | POPCOUNT | LZCOUNT => {
+                return Err(LiftingError::UnsupportedExpression {
+                    mnemonic: expr.mnemonic,
+                })
+            }
+        };
+        Ok(expression)
+    }
+}
+
+impl Expression {
+    /// Get the first input varnode or an error if it does not exist.
+    pub fn get_input0(&self) -> Result<Variable, LiftingError> {
+        self.input0
+            .clone()
+            .ok_or_else(|| LiftingError::missing_operand(self.mnemonic, "input0"))
+    }
+
+    /// Get the second input varnode or an error if it does not exist.
+    pub fn get_input1(&self) -> Result<Variable, LiftingError> {
+        self.input1
+            .clone()
+            .ok_or_else(|| LiftingError::missing_operand(self.mnemonic, "input1"))
+    }
+
+    /// Get the third input varnode or an error if it does not exist.
+    pub fn get_input2(&self) -> Result<Variable, LiftingError> {
+        self.input2
+            .clone()
+            .ok_or_else(|| LiftingError::missing_operand(self.mnemonic, "input2"))
     }
 }
 
@@ -448,21 +509,36 @@ mod tests {
             size: ByteSize::new(8),
             is_virtual: false,
         };
-        assert_eq!(var.parse_const_to_bitvector(), Bitvector::from_u64(0));
+        assert_eq!(
+            var.parse_const_to_bitvector().unwrap(),
+            Bitvector::from_u64(0)
+        );
         var.value = Some("0010f".to_string());
-        assert_eq!(var.parse_const_to_bitvector(), Bitvector::from_u64(271));
+        assert_eq!(
+            var.parse_const_to_bitvector().unwrap(),
+            Bitvector::from_u64(271)
+        );
         var.value = Some("1ff".to_string());
         var.size = ByteSize::new(1);
-        assert_eq!(var.parse_const_to_bitvector(), Bitvector::from_u8(255));
+        assert_eq!(
+            var.parse_const_to_bitvector().unwrap(),
+            Bitvector::from_u8(255)
+        );
         var.size = ByteSize::new(16);
-        assert_eq!(var.parse_const_to_bitvector(), Bitvector::from_u128(511));
+        assert_eq!(
+            var.parse_const_to_bitvector().unwrap(),
+            Bitvector::from_u128(511)
+        );
 
         var.value = Some("00_ffffffffffffffff_ffffffffffffffff".to_string());
         var.size = ByteSize::new(16);
-        assert_eq!(var.parse_const_to_bitvector(), Bitvector::from_i128(-1));
+        assert_eq!(
+            var.parse_const_to_bitvector().unwrap(),
+            Bitvector::from_i128(-1)
+        );
         var.size = ByteSize::new(10);
         assert_eq!(
-            var.parse_const_to_bitvector(),
+            var.parse_const_to_bitvector().unwrap(),
             Bitvector::from_i128(-1)
                 .into_truncate(ByteSize::new(10))
                 .unwrap()
@@ -476,8 +552,12 @@ mod tests {
             is_virtual: false,
         };
         assert_eq!(
-            var.parse_address_to_bitvector(ByteSize::new(8)),
+            var.parse_address_to_bitvector(ByteSize::new(8)).unwrap(),
             Bitvector::from_u64(271)
         );
+        assert!(matches!(
+            IrExpression::try_from(var),
+            Err(LiftingError::InvalidVarnode { .. })
+        ));
     }
 }
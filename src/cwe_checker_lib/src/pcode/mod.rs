@@ -7,8 +7,13 @@
 //! The contents of this module are only used for the initial translation of P-Code into the internally used IR.
 //! For everything else the [`intermediate_representation`](crate::intermediate_representation) should be used directly.
 
+mod errors;
+pub use errors::*;
 mod expressions;
 pub use expressions::*;
 mod term;
 pub use term::*;
 mod subregister_substitution;
+pub(crate) use subregister_substitution::{
+    replace_input_subregister, replace_subregister_in_block,
+};
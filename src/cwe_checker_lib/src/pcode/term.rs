@@ -1,7 +1,8 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use super::subregister_substitution::replace_input_subregister;
-use super::{Expression, ExpressionType, RegisterProperties, Variable};
+use super::{Expression, ExpressionType, LiftingError, RegisterProperties, Variable};
+use crate::intermediate_representation::AnnotationMap;
 use crate::intermediate_representation::Arg as IrArg;
 use crate::intermediate_representation::Blk as IrBlk;
 use crate::intermediate_representation::ByteSize;
@@ -13,11 +14,12 @@ use crate::intermediate_representation::ExternSymbol as IrExternSymbol;
 use crate::intermediate_representation::Jmp as IrJmp;
 use crate::intermediate_representation::Program as IrProgram;
 use crate::intermediate_representation::Project as IrProject;
+use crate::intermediate_representation::ProvenanceTable;
 use crate::intermediate_representation::RuntimeMemoryImage;
 use crate::intermediate_representation::Sub as IrSub;
 use crate::intermediate_representation::Variable as IrVariable;
+use crate::pipeline::{PipelineError, PipelineStage};
 use crate::prelude::*;
-use crate::utils::log::LogMessage;
 
 // TODO: Handle the case where an indirect tail call is represented by CALLIND plus RETURN
 
@@ -66,57 +68,79 @@ pub enum JmpType {
     RETURN,
 }
 
-impl From<Jmp> for IrJmp {
+impl TryFrom<Jmp> for IrJmp {
+    type Error = LiftingError;
+
     /// Convert a P-Code jump to the internally used IR.
-    fn from(jmp: Jmp) -> IrJmp {
+    fn try_from(jmp: Jmp) -> Result<IrJmp, LiftingError> {
         use JmpType::*;
+        let mnemonic = jmp.mnemonic;
         let unwrap_label_direct = |label| {
             if let Label::Direct(tid) = label {
-                tid
+                Ok(tid)
             } else {
-                panic!()
+                Err(LiftingError::UnexpectedLabel { mnemonic })
             }
         };
-        let unwrap_label_indirect = |label| {
+        let unwrap_label_indirect = |label| -> Result<IrExpression, LiftingError> {
             if let Label::Indirect(expr) = label {
-                expr
+                expr.try_into()
             } else {
-                panic!()
+                Err(LiftingError::UnexpectedLabel { mnemonic })
             }
         };
-        match jmp.mnemonic {
-            BRANCH => IrJmp::Branch(unwrap_label_direct(jmp.goto.unwrap())),
+        let goto = || {
+            jmp.goto
+                .clone()
+                .ok_or_else(|| LiftingError::missing_operand(mnemonic, "goto"))
+        };
+        let call = || {
+            jmp.call
+                .clone()
+                .ok_or_else(|| LiftingError::missing_operand(mnemonic, "call"))
+        };
+        let call_target = |call: &Call| {
+            call.target
+                .clone()
+                .ok_or_else(|| LiftingError::missing_operand(mnemonic, "call target"))
+        };
+        let ir_jmp = match mnemonic {
+            BRANCH => IrJmp::Branch(unwrap_label_direct(goto()?)?),
             CBRANCH => IrJmp::CBranch {
-                target: unwrap_label_direct(jmp.goto.unwrap()),
-                condition: jmp.condition.unwrap().into(),
+                target: unwrap_label_direct(goto()?)?,
+                condition: jmp
+                    .condition
+                    .clone()
+                    .ok_or_else(|| LiftingError::missing_operand(mnemonic, "condition"))?
+                    .try_into()?,
             },
-            BRANCHIND => {
-                let target = unwrap_label_indirect(jmp.goto.unwrap());
-                IrJmp::BranchInd(target.into())
-            }
+            BRANCHIND => IrJmp::BranchInd(unwrap_label_indirect(goto()?)?),
             CALL => {
-                let call = jmp.call.unwrap();
+                let call = call()?;
                 IrJmp::Call {
-                    target: unwrap_label_direct(call.target.unwrap()),
-                    return_: call.return_.map(unwrap_label_direct),
+                    target: unwrap_label_direct(call_target(&call)?)?,
+                    return_: call.return_.map(unwrap_label_direct).transpose()?,
                 }
             }
             CALLIND => {
-                let call = jmp.call.unwrap();
+                let call = call()?;
                 IrJmp::CallInd {
-                    target: unwrap_label_indirect(call.target.unwrap()).into(),
-                    return_: call.return_.map(unwrap_label_direct),
+                    target: unwrap_label_indirect(call_target(&call)?)?,
+                    return_: call.return_.map(unwrap_label_direct).transpose()?,
                 }
             }
             CALLOTHER => {
-                let call = jmp.call.unwrap();
+                let call = call()?;
                 IrJmp::CallOther {
-                    description: call.call_string.unwrap(),
-                    return_: call.return_.map(unwrap_label_direct),
+                    description: call
+                        .call_string
+                        .ok_or_else(|| LiftingError::missing_operand(mnemonic, "call string"))?,
+                    return_: call.return_.map(unwrap_label_direct).transpose()?,
                 }
             }
-            RETURN => IrJmp::Return(unwrap_label_indirect(jmp.goto.unwrap()).into()),
-        }
+            RETURN => IrJmp::Return(unwrap_label_indirect(goto()?)?),
+        };
+        Ok(ir_jmp)
     }
 }
 
@@ -140,52 +164,57 @@ pub struct Def {
 
 impl Def {
     /// Convert a P-Code instruction to the internally used IR.
-    pub fn into_ir_def(self, generic_pointer_size: ByteSize) -> IrDef {
+    pub fn into_ir_def(self, generic_pointer_size: ByteSize) -> Result<IrDef, LiftingError> {
         use super::ExpressionType::*;
+        let get_lhs = || {
+            self.lhs
+                .clone()
+                .ok_or_else(|| LiftingError::missing_operand(self.rhs.mnemonic, "output"))
+        };
         match self.rhs.mnemonic {
             LOAD => {
-                return IrDef::Load {
-                    var: self.lhs.unwrap().into(),
-                    address: self.rhs.input1.unwrap().into(),
-                }
+                return Ok(IrDef::Load {
+                    var: get_lhs()?.try_into_register()?,
+                    address: self.rhs.get_input1()?.try_into()?,
+                })
             }
             STORE => {
-                return IrDef::Store {
-                    address: self.rhs.input1.unwrap().into(),
-                    value: self.rhs.input2.unwrap().into(),
-                }
+                return Ok(IrDef::Store {
+                    address: self.rhs.get_input1()?.try_into()?,
+                    value: self.rhs.get_input2()?.try_into()?,
+                })
             }
             _ => (),
         }
-        let target_var = self.lhs.unwrap();
+        let target_var = get_lhs()?;
         let value = match self.rhs.mnemonic {
             LOAD | STORE => unreachable!(),
             SUBPIECE => IrExpression::Subpiece {
-                low_byte: self.rhs.input1.unwrap().parse_to_bytesize(),
+                low_byte: self.rhs.get_input1()?.parse_to_bytesize()?,
                 size: target_var.size,
-                arg: Box::new(self.rhs.input0.unwrap().into()),
+                arg: Box::new(self.rhs.get_input0()?.try_into()?),
             },
             INT_ZEXT | INT_SEXT | INT2FLOAT | FLOAT2FLOAT | TRUNC | POPCOUNT | LZCOUNT => {
                 IrExpression::Cast {
                     op: self.rhs.mnemonic.into(),
                     size: target_var.size,
-                    arg: Box::new(self.rhs.input0.unwrap().into()),
+                    arg: Box::new(self.rhs.get_input0()?.try_into()?),
                 }
             }
-            _ => self.rhs.into(),
+            _ => self.rhs.try_into()?,
         };
         if target_var.address.is_some() {
-            IrDef::Store {
+            Ok(IrDef::Store {
                 address: IrExpression::Const(
-                    target_var.parse_address_to_bitvector(generic_pointer_size),
+                    target_var.parse_address_to_bitvector(generic_pointer_size)?,
                 ),
                 value,
-            }
+            })
         } else {
-            IrDef::Assign {
-                var: target_var.into(),
+            Ok(IrDef::Assign {
+                var: target_var.try_into_register()?,
                 value,
-            }
+            })
         }
     }
 }
@@ -201,15 +230,22 @@ pub struct Blk {
 
 impl Blk {
     /// Convert a P-Code block to the internally used IR.
-    pub fn into_ir_blk(self, generic_pointer_size: ByteSize) -> IrBlk {
+    ///
+    /// If the conversion fails, the error is returned together with the TID of the failing instruction.
+    pub fn into_ir_blk(self, generic_pointer_size: ByteSize) -> Result<IrBlk, (Tid, LiftingError)> {
         let defs: Vec<Term<IrDef>> = self
             .defs
             .into_iter()
-            .map(|def_term| Term {
-                tid: def_term.tid,
-                term: def_term.term.into_ir_def(generic_pointer_size),
+            .map(|def_term| {
+                Ok(Term {
+                    term: def_term
+                        .term
+                        .into_ir_def(generic_pointer_size)
+                        .map_err(|err| (def_term.tid.clone(), err))?,
+                    tid: def_term.tid,
+                })
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
         let indirect_jmp_targets = self
             .jmps
             .iter()
@@ -218,20 +254,25 @@ impl Blk {
         let jmps: Vec<Term<IrJmp>> = self
             .jmps
             .into_iter()
-            .map(|jmp_term| Term {
-                tid: jmp_term.tid,
-                term: jmp_term.term.into(),
+            .map(|jmp_term| {
+                Ok(Term {
+                    term: jmp_term
+                        .term
+                        .try_into()
+                        .map_err(|err| (jmp_term.tid.clone(), err))?,
+                    tid: jmp_term.tid,
+                })
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
         let indirect_jmp_targets = indirect_jmp_targets
             .into_iter()
             .map(|address| Tid::blk_id_at_address(&address))
             .collect();
-        IrBlk {
+        Ok(IrBlk {
             defs,
             jmps,
             indirect_jmp_targets,
-        }
+        })
     }
 }
 
@@ -278,18 +319,13 @@ impl Blk {
         for (index, jmp) in self.jmps.iter_mut().enumerate() {
             match jmp.term.mnemonic {
                 JmpType::BRANCHIND | JmpType::CALLIND => {
-                    let input = match jmp.term.mnemonic {
-                        JmpType::BRANCHIND => match jmp.term.goto.as_mut().unwrap() {
-                            Label::Indirect(expr) => expr,
-                            Label::Direct(_) => panic!(),
-                        },
-                        JmpType::CALLIND => {
-                            match jmp.term.call.as_mut().unwrap().target.as_mut().unwrap() {
-                                Label::Indirect(expr) => expr,
-                                Label::Direct(_) => panic!(),
-                            }
-                        }
-                        _ => panic!(),
+                    let label = match jmp.term.mnemonic {
+                        JmpType::BRANCHIND => jmp.term.goto.as_mut(),
+                        _ => jmp.term.call.as_mut().and_then(|call| call.target.as_mut()),
+                    };
+                    // Malformed jumps are reported during the translation to the internally used IR.
+                    let Some(Label::Indirect(input)) = label else {
+                        continue;
                     };
                     if input.address.is_some() {
                         let temp_register_name = format!("$load_temp{index}");
@@ -348,7 +384,13 @@ impl Term<Sub> {
     /// The conversion also repairs the order of the basic blocks in the `blocks` array of the `Sub`
     /// in the sense that the first block of the array is required to also be the function entry point
     /// after the conversion.
-    pub fn into_ir_sub_term(mut self, generic_pointer_size: ByteSize) -> Term<IrSub> {
+    ///
+    /// Returns an error if an instruction of the function cannot be translated
+    /// or if the function has no block starting at the function address.
+    pub fn into_ir_sub_term(
+        mut self,
+        generic_pointer_size: ByteSize,
+    ) -> Result<Term<IrSub>, Box<PipelineError>> {
         // Since the intermediate representation expects that the first block of a function is its entry point,
         // we have to make sure that this actually holds.
         if !self.term.blocks.is_empty() && self.tid.address != self.term.blocks[0].tid.address {
@@ -362,27 +404,38 @@ impl Term<Sub> {
             if let Some(start_block_index) = start_block_index {
                 self.term.blocks.swap(0, start_block_index);
             } else {
-                panic!("Non-empty function without correct starting block encountered. Name: {}, TID: {}", self.term.name, self.tid);
+                return Err(Box::new(PipelineError::new(
+                    PipelineStage::Lifting,
+                    self.tid,
+                    self.term.name,
+                    LiftingError::MissingEntryBlock,
+                )));
             }
         }
 
-        let blocks = self
-            .term
-            .blocks
-            .into_iter()
-            .map(|block_term| Term {
-                tid: block_term.tid,
-                term: block_term.term.into_ir_blk(generic_pointer_size),
-            })
-            .collect();
-        Term {
+        let mut blocks = Vec::new();
+        for block_term in self.term.blocks {
+            match block_term.term.into_ir_blk(generic_pointer_size) {
+                Ok(block) => blocks.push(Term {
+                    tid: block_term.tid,
+                    term: block,
+                }),
+                Err((location, err)) => {
+                    return Err(Box::new(
+                        PipelineError::new(PipelineStage::Lifting, self.tid, self.term.name, err)
+                            .location(location),
+                    ))
+                }
+            }
+        }
+        Ok(Term {
             tid: self.tid,
             term: IrSub {
                 name: self.term.name,
                 blocks,
                 calling_convention: self.term.calling_convention,
             },
-        }
+        })
     }
 }
 
@@ -595,6 +648,9 @@ pub struct Program {
     ///
     /// Note that Ghidra may add an offset to the image base address as reported by the binary itself.
     pub image_base: String,
+    /// The lengths of the exported assembly instructions in bytes, indexed by their addresses.
+    #[serde(default)]
+    pub instruction_lengths: BTreeMap<String, u64>,
 }
 
 impl Program {
@@ -605,17 +661,39 @@ impl Program {
     /// It is needed to detect whether Ghidra added a constant offset to all addresses of the memory address.
     /// E.g. if the `binary_base_address` is 0 for shared object files,
     /// Ghidra adds an offset so that the memory image does not actually start at address 0.
+    ///
+    /// Functions that cannot be translated are handled as functions without code,
+    /// i.e. as dead ends in the control flow graph.
+    /// The corresponding errors are returned alongside the program.
     pub fn into_ir_program(
         self,
         binary_base_address: u64,
         conventions: &[CallingConvention],
         stack_pointer: &Variable,
         cpu_arch: &str,
-    ) -> IrProgram {
+    ) -> (IrProgram, Vec<PipelineError>) {
+        let mut errors = Vec::new();
         let subs = self
             .subs
             .into_iter()
-            .map(|sub| (sub.tid.clone(), sub.into_ir_sub_term(stack_pointer.size)))
+            .map(|sub| {
+                let tid = sub.tid.clone();
+                let empty_sub = Term {
+                    tid: sub.tid.clone(),
+                    term: IrSub {
+                        name: sub.term.name.clone(),
+                        blocks: Vec::new(),
+                        calling_convention: sub.term.calling_convention.clone(),
+                    },
+                };
+                match sub.into_ir_sub_term(stack_pointer.size) {
+                    Ok(ir_sub) => (tid, ir_sub),
+                    Err(err) => {
+                        errors.push(*err);
+                        (tid, empty_sub)
+                    }
+                }
+            })
             .collect();
         let extern_symbols = self
             .extern_symbols
@@ -629,12 +707,16 @@ impl Program {
             .collect();
         let address_base_offset =
             u64::from_str_radix(&self.image_base, 16).unwrap() - binary_base_address;
-        IrProgram {
+        let program = IrProgram {
             subs,
             extern_symbols,
             entry_points: self.entry_points.into_iter().collect(),
             address_base_offset,
-        }
+            stack_canary_checks: BTreeMap::new(),
+            fortified_symbols: BTreeMap::new(),
+            provenance: ProvenanceTable::new(self.instruction_lengths),
+        };
+        (program, errors)
     }
 }
 
@@ -730,20 +812,23 @@ impl Project {
     ///
     /// The `binary_base_address` denotes the base address of the memory image of the binary
     /// according to the program headers of the binary.
-    pub fn into_ir_project(self, binary_base_address: u64) -> IrProject {
+    ///
+    /// Errors during the translation of individual functions are returned alongside the project.
+    pub fn into_ir_project(self, binary_base_address: u64) -> (IrProject, Vec<PipelineError>) {
         let register_map: HashMap<&String, &RegisterProperties> = self
             .register_properties
             .iter()
             .map(|p| (&p.register, p))
             .collect();
+        let (program_term, errors) = self.program.term.into_ir_program(
+            binary_base_address,
+            &self.register_calling_convention,
+            &self.stack_pointer_register,
+            &self.cpu_architecture,
+        );
         let mut program: Term<IrProgram> = Term {
             tid: self.program.tid,
-            term: self.program.term.into_ir_program(
-                binary_base_address,
-                &self.register_calling_convention,
-                &self.stack_pointer_register,
-                &self.cpu_architecture,
-            ),
+            term: program_term,
         };
         // iterates over definitions and checks whether sub registers are used
         // if so, they are swapped with subpieces of base registers
@@ -783,7 +868,7 @@ impl Project {
             .into_iter()
             .map(|cconv| (cconv.name.clone(), cconv.into_ir_cconv(&register_map)))
             .collect();
-        IrProject {
+        let project = IrProject {
             program,
             cpu_architecture: self.cpu_architecture,
             stack_pointer_register: self.stack_pointer_register.into(),
@@ -791,7 +876,9 @@ impl Project {
             register_set,
             datatype_properties: self.datatype_properties.clone(),
             runtime_memory_image: RuntimeMemoryImage::empty(true),
-        }
+            annotations: AnnotationMap::default(),
+        };
+        (project, errors)
     }
 }
 
@@ -811,11 +898,11 @@ impl Project {
     /// Sometimes Ghidra generates a (correct) function start inside another function.
     /// But if the function start is not also the start of a basic block,
     /// we cannot handle it correctly (yet) as this would need splitting of basic blocks.
-    /// So instead we generate a log message and handle the function as a function without code,
+    /// So instead we generate an error and handle the function as a function without code,
     /// i.e. a dead end in the control flow graph.
     #[must_use]
-    pub fn normalize(&mut self) -> Vec<LogMessage> {
-        let mut log_messages = Vec::new();
+    pub fn normalize(&mut self) -> Vec<PipelineError> {
+        let mut errors = Vec::new();
 
         // Insert explicit `LOAD` instructions for implicit memory loads in P-Code.
         let generic_pointer_size = self.stack_pointer_register.size;
@@ -837,15 +924,17 @@ impl Project {
                     .iter()
                     .any(|block| block.tid.address == sub.tid.address)
             {
-                log_messages.push(LogMessage::new_error(format!(
-                    "Starting block of function {} ({}) not found.",
-                    sub.term.name, sub.tid
-                )));
+                errors.push(PipelineError::new(
+                    PipelineStage::Normalization,
+                    sub.tid.clone(),
+                    sub.term.name.clone(),
+                    LiftingError::MissingEntryBlock,
+                ));
                 sub.term.blocks = Vec::new();
             }
         }
 
-        log_messages
+        errors
     }
 }
 
@@ -409,7 +409,7 @@ fn def_deserialization() {
       "#,
     )
     .unwrap();
-    let _: IrDef = def.into_ir_def(ByteSize::new(8));
+    let _: IrDef = def.into_ir_def(ByteSize::new(8)).unwrap();
     let def: Def = serde_json::from_str(
         r#"
             {
@@ -435,7 +435,7 @@ fn def_deserialization() {
             "#,
     )
     .unwrap();
-    let _: IrDef = def.into_ir_def(ByteSize::new(8));
+    let _: IrDef = def.into_ir_def(ByteSize::new(8)).unwrap();
 }
 
 #[test]
@@ -458,7 +458,7 @@ fn def_deserialization_issue_247_regression_test() {
       }"#,
     )
     .unwrap();
-    let _: IrDef = def.into_ir_def(ByteSize::new(8));
+    let _: IrDef = def.into_ir_def(ByteSize::new(8)).unwrap();
 }
 
 #[test]
@@ -492,14 +492,14 @@ fn label_deserialization() {
 fn jmp_deserialization() {
     let setup = Setup::new();
     let jmp_term: Term<Jmp> = setup.jmp_t.clone();
-    let _: IrJmp = jmp_term.term.into();
+    let _: IrJmp = jmp_term.term.try_into().unwrap();
 }
 
 #[test]
 fn blk_deserialization() {
     let setup = Setup::new();
     let block_term: Term<Blk> = setup.blk_t.clone();
-    let _: IrBlk = block_term.term.into_ir_blk(ByteSize::new(8));
+    let _: IrBlk = block_term.term.into_ir_blk(ByteSize::new(8)).unwrap();
 }
 
 #[test]
@@ -539,7 +539,7 @@ fn arg_deserialization() {
 fn sub_deserialization() {
     let setup = Setup::new();
     let sub_term: Term<Sub> = setup.sub_t.clone();
-    let _: Term<IrSub> = sub_term.into_ir_sub_term(ByteSize::new(8));
+    let _: Term<IrSub> = sub_term.into_ir_sub_term(ByteSize::new(8)).unwrap();
     let sub_term: Term<Sub> = serde_json::from_str(
         r#"
           {
@@ -578,10 +578,93 @@ fn sub_deserialization() {
     .unwrap();
     // Example has special case where the starting block has to be corrected
     assert!(sub_term.tid.address != sub_term.term.blocks[0].tid.address);
-    let ir_sub: Term<IrSub> = sub_term.into_ir_sub_term(ByteSize::new(8));
+    let ir_sub: Term<IrSub> = sub_term.into_ir_sub_term(ByteSize::new(8)).unwrap();
     assert_eq!(ir_sub.tid.address, ir_sub.term.blocks[0].tid.address);
 }
 
+#[test]
+fn sub_lifting_error() {
+    let sub_term: Term<Sub> = serde_json::from_str(
+        r#"
+          {
+          "tid": {
+              "id": "sub_00101000",
+              "address": "00101000"
+          },
+          "term": {
+              "name": "sub_name",
+              "blocks": [
+                {
+                  "tid": {
+                      "id": "blk_00101000",
+                      "address": "00101000"
+                  },
+                  "term": {
+                      "defs": [
+                        {
+                          "tid": {
+                            "id": "instr_00101000_0",
+                            "address": "00101000"
+                          },
+                          "term": {
+                            "lhs": null,
+                            "rhs": {
+                              "mnemonic": "STORE",
+                              "input0": null,
+                              "input1": {
+                                "name": "RDI",
+                                "size": 8,
+                                "is_virtual": false
+                              }
+                            }
+                          }
+                        }
+                      ],
+                      "jmps": []
+                  }
+                }
+              ]
+          }
+          }
+          "#,
+    )
+    .unwrap();
+    let err = sub_term
+        .clone()
+        .into_ir_sub_term(ByteSize::new(8))
+        .unwrap_err();
+    assert_eq!(err.stage, PipelineStage::Lifting);
+    assert_eq!(err.function, sub_term.tid);
+    assert_eq!(
+        err.location.as_ref(),
+        Some(&sub_term.term.blocks[0].term.defs[0].tid)
+    );
+    assert_eq!(
+        err.diagnostic,
+        LiftingError::MissingOperand {
+            mnemonic: "STORE".to_string(),
+            operand: "input2".to_string()
+        }
+    );
+
+    // The function is replaced by a function without code, other functions are still translated.
+    let setup = Setup::new();
+    let mut program = setup.project.program.term.clone();
+    program.subs.push(sub_term);
+    let (ir_program, errors) = program.into_ir_program(
+        10000,
+        &setup.project.register_calling_convention,
+        &setup.project.stack_pointer_register,
+        &setup.project.cpu_architecture,
+    );
+    assert_eq!(errors, vec![*err.clone()]);
+    assert!(ir_program.subs[&err.function].term.blocks.is_empty());
+    assert_eq!(
+        ir_program.subs.len(),
+        setup.project.program.term.subs.len() + 1
+    );
+}
+
 #[test]
 fn extern_symbol_deserialization() {
     let setup = Setup::new();
@@ -651,19 +734,20 @@ fn program_deserialization() {
             "#,
     )
     .unwrap();
-    let _: IrProgram = program_term.term.into_ir_program(
+    let (_, errors): (IrProgram, _) = program_term.term.into_ir_program(
         10000,
         &setup.project.register_calling_convention,
         &setup.project.stack_pointer_register,
         &setup.project.cpu_architecture,
     );
+    assert!(errors.is_empty());
 }
 
 #[test]
 fn project_deserialization() {
     let setup = Setup::new();
     let project: Project = setup.project.clone();
-    let _: IrProject = project.into_ir_project(10000);
+    let _: IrProject = project.into_ir_project(10000).0;
 }
 
 #[test]
@@ -751,7 +835,7 @@ fn from_project_to_ir_project() {
     sub.term.blocks.push(blk);
     mock_project.program.term.subs.push(sub.clone());
 
-    let ir_program = mock_project.into_ir_project(10000).program.term;
+    let ir_program = mock_project.into_ir_project(10000).0.program.term;
     let ir_rax_var = IrVariable {
         name: String::from("RAX"),
         size: ByteSize::new(8),
@@ -0,0 +1,483 @@
+//! Dump of the recovered arguments of all call sites in a function.
+//!
+//! When a check like CWE-78 or CWE-134 does not report a call site as expected,
+//! the reason is usually that an argument could not be recovered precisely enough.
+//! The [`dump_callsites`] function prints for each call site of a function
+//! - the resolved callee (for indirect calls the possible target addresses),
+//! - the abstract value of each argument as computed by the Pointer Inference analysis,
+//! - the source labels of each argument, i.e. the origins of the memory objects and values it depends on,
+//!   like parameters of the function or return values of other calls,
+//! - and the string content that an argument points to if it is known.
+//!
+//! The arguments of extern symbols are taken from their calling convention,
+//! the arguments of functions of the binary from their function signatures
+//! and the arguments of indirect calls from the parameter registers of the standard calling convention.
+//! The string content is read from the global memory of the binary
+//! and, if the string abstraction was computed, from the string abstraction of stack and heap buffers.
+//! Taint of memory contents, e.g. of a buffer filled by `recv`, is not shown.
+//!
+//! If the cwe_checker is built with the `dwarf` feature and the binary contains debug information,
+//! the dump additionally shows the source lines of the call sites,
+//! the source-level names and types of the parameters of called functions
+//! and the names of the stack variables that arguments point to.
+
+use super::AnalysisResults;
+use crate::abstract_domain::{AbstractIdentifier, AbstractLocation, TryToBitvec};
+use crate::analysis::pointer_inference::{Data, ObjectKind, PointerInference, PointerTarget};
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+#[cfg(feature = "dwarf")]
+use crate::utils::binary::parse_hex_string_to_u64;
+#[cfg(feature = "dwarf")]
+use crate::utils::debug_info::DebugInfo;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Generate the textual dump of all call sites in the function with the given name.
+///
+/// The `debug_info` of the binary is used to annotate the dump with source-level information if it is provided.
+/// The parameter only exists if the cwe_checker is built with the `dwarf` feature.
+///
+/// Returns an error if the binary contains no function with the given name
+/// or if the Pointer Inference analysis was not computed.
+pub fn dump_callsites(
+    analysis_results: &AnalysisResults,
+    function_name: &str,
+    #[cfg(feature = "dwarf")] debug_info: Option<&DebugInfo>,
+) -> Result<String, Error> {
+    let project = analysis_results.project;
+    let pi_result = analysis_results
+        .pointer_inference
+        .ok_or_else(|| anyhow!("Dumping call sites requires the Pointer Inference analysis."))?;
+    let sub = project
+        .program
+        .term
+        .subs
+        .values()
+        .find(|sub| sub.term.name == function_name)
+        .ok_or_else(|| anyhow!("No function with name {function_name} found."))?;
+    let dumper = CallsiteDumper {
+        #[cfg(feature = "dwarf")]
+        debug_info,
+        ..CallsiteDumper::new(analysis_results, pi_result, sub)
+    };
+
+    let mut output = match dumper.get_declaration(sub) {
+        Some(declaration) => format!(
+            "Call sites of {} ({}, declared at {declaration}):\n",
+            sub.term.name, sub.tid.address
+        ),
+        None => format!("Call sites of {} ({}):\n", sub.term.name, sub.tid.address),
+    };
+    for blk in sub.term.blocks.iter() {
+        for jmp in blk.term.jmps.iter() {
+            dumper.dump_call(&mut output, blk, jmp);
+        }
+    }
+    Ok(output)
+}
+
+/// Helper struct for generating the dump of the call sites of a function.
+struct CallsiteDumper<'a, 'b> {
+    /// The analysis results used for the dump.
+    analysis_results: &'a AnalysisResults<'b>,
+    /// The results of the Pointer Inference analysis.
+    pi_result: &'b PointerInference<'b>,
+    /// The function whose call sites are dumped.
+    sub: &'b Term<Sub>,
+    /// Maps the TIDs of all calls in the program to the name of the called function.
+    callee_names: HashMap<&'b Tid, &'b str>,
+    /// The debug information of the binary if available.
+    #[cfg(feature = "dwarf")]
+    debug_info: Option<&'b DebugInfo>,
+}
+
+impl<'a, 'b> CallsiteDumper<'a, 'b> {
+    /// Create a new dumper for the call sites of the given function.
+    fn new(
+        analysis_results: &'a AnalysisResults<'b>,
+        pi_result: &'b PointerInference<'b>,
+        sub: &'b Term<Sub>,
+    ) -> CallsiteDumper<'a, 'b> {
+        let project: &'b Project = analysis_results.project;
+        let program = &project.program.term;
+        let mut callee_names = HashMap::new();
+        for blk in program.subs.values().flat_map(|sub| sub.term.blocks.iter()) {
+            for jmp in blk.term.jmps.iter() {
+                if let Jmp::Call { target, .. } = &jmp.term {
+                    if let Some(name) = get_function_name(program, target) {
+                        callee_names.insert(&jmp.tid, name);
+                    }
+                }
+            }
+        }
+        CallsiteDumper {
+            analysis_results,
+            pi_result,
+            sub,
+            callee_names,
+            #[cfg(feature = "dwarf")]
+            debug_info: None,
+        }
+    }
+
+    /// Append the dump of the given jump to the output if it is a call.
+    fn dump_call(&self, output: &mut String, blk: &Term<Blk>, jmp: &Term<Jmp>) {
+        let project = self.analysis_results.project;
+        let arguments: Vec<(String, Option<Data>)> = match &jmp.term {
+            Jmp::Call { target, .. } => {
+                if let Some(symbol) = project.program.term.extern_symbols.get(target) {
+                    writeln!(
+                        output,
+                        "{}: call {} (extern)",
+                        self.format_address(&jmp.tid.address),
+                        symbol.name
+                    )
+                    .unwrap();
+                    symbol
+                        .parameters
+                        .iter()
+                        .map(|arg| {
+                            (
+                                format_arg(arg),
+                                self.pi_result.eval_parameter_arg_at_call(&jmp.tid, arg),
+                            )
+                        })
+                        .collect()
+                } else {
+                    let name = get_function_name(&project.program.term, target).unwrap_or("?");
+                    writeln!(
+                        output,
+                        "{}: call {name}",
+                        self.format_address(&jmp.tid.address)
+                    )
+                    .unwrap();
+                    self.get_parameter_locations(target)
+                        .into_iter()
+                        .map(|location| {
+                            let value = self
+                                .pi_result
+                                .eval_parameter_location_at_call(&jmp.tid, &location);
+                            match self.get_parameter_name(target, &location) {
+                                Some(parameter) => (format!("{location} ({parameter})"), value),
+                                None => (location.to_string(), value),
+                            }
+                        })
+                        .collect()
+                }
+            }
+            Jmp::CallInd { target, .. } => {
+                let target_value = self.pi_result.eval_at_jmp(&jmp.tid, target);
+                writeln!(
+                    output,
+                    "{}: indirect call to {target} = {}",
+                    self.format_address(&jmp.tid.address),
+                    format_value(target_value.as_ref())
+                )
+                .unwrap();
+                project
+                    .get_standard_calling_convention()
+                    .map(|cconv| cconv.integer_parameter_register.clone())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|register| {
+                        let value = self
+                            .pi_result
+                            .eval_at_jmp(&jmp.tid, &Expression::Var(register.clone()));
+                        (register.to_string(), value)
+                    })
+                    .collect()
+            }
+            _ => return,
+        };
+        for (name, value) in arguments {
+            writeln!(output, "  {name} = {}", format_value(value.as_ref())).unwrap();
+            let Some(value) = value else {
+                continue;
+            };
+            let targets = self.pi_result.get_pointer_targets(&value);
+            let labels: Vec<String> = targets
+                .iter()
+                .map(|target| self.get_source_label(target))
+                .collect();
+            if !labels.is_empty() {
+                writeln!(output, "    sources: {}", labels.join(", ")).unwrap();
+            }
+            for target in targets.iter() {
+                if let Some(string) = self.get_string_content(blk, target) {
+                    writeln!(output, "    string: {string}").unwrap();
+                }
+            }
+        }
+    }
+
+    /// Get the parameter locations of the function with the given TID from its function signature.
+    fn get_parameter_locations(&self, sub_tid: &Tid) -> Vec<AbstractLocation> {
+        self.analysis_results
+            .function_signatures
+            .and_then(|signatures| signatures.get(sub_tid))
+            .map(|signature| signature.parameters.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get a label describing the origin of the memory object or value that the pointer target refers to.
+    fn get_source_label(&self, target: &PointerTarget) -> String {
+        let origin = target.get_allocation_site();
+        match target.kind {
+            ObjectKind::Stack => match self.get_stack_variable_name(target) {
+                Some(variable) => format!(
+                    "stack frame of {} (variable {variable})",
+                    self.get_function_name(origin)
+                ),
+                None => format!("stack frame of {}", self.get_function_name(origin)),
+            },
+            ObjectKind::Heap => format!("heap object allocated by {}", self.get_call_name(origin)),
+            ObjectKind::GlobalReadOnly => "read-only global memory".to_string(),
+            ObjectKind::GlobalWritable => "writeable global memory".to_string(),
+            ObjectKind::Mmio => "memory-mapped address".to_string(),
+            ObjectKind::Extern => {
+                let id = target.id.as_ref().unwrap();
+                if id.get_tid() == &self.sub.tid {
+                    format!("parameter {}", id.get_location())
+                } else if self.callee_names.contains_key(id.get_tid()) {
+                    format!("return value of {}", self.get_call_name(origin))
+                } else {
+                    format!("unknown object {id}")
+                }
+            }
+        }
+    }
+
+    /// Get the source location of the declaration of the given function.
+    #[cfg(feature = "dwarf")]
+    fn get_declaration(&self, sub: &Term<Sub>) -> Option<String> {
+        let function = self.debug_info?.get_function_at(&sub.tid.address)?;
+        function.declaration.as_ref().map(ToString::to_string)
+    }
+
+    /// Source locations are only known with the `dwarf` feature.
+    #[cfg(not(feature = "dwarf"))]
+    fn get_declaration(&self, _sub: &Term<Sub>) -> Option<String> {
+        None
+    }
+
+    /// Get the source-level name and type of the parameter of the called function
+    /// at the given parameter location.
+    #[cfg(feature = "dwarf")]
+    fn get_parameter_name(&self, callee: &Tid, location: &AbstractLocation) -> Option<String> {
+        let project = self.analysis_results.project;
+        let callee = project.program.term.subs.get(callee)?;
+        let function = self.debug_info?.get_function_at(&callee.tid.address)?;
+        function
+            .get_parameter(location, project)
+            .map(ToString::to_string)
+    }
+
+    /// Parameter names are only known with the `dwarf` feature.
+    #[cfg(not(feature = "dwarf"))]
+    fn get_parameter_name(&self, _callee: &Tid, _location: &AbstractLocation) -> Option<String> {
+        None
+    }
+
+    /// Get the source-level name of the stack variable that the pointer target points to.
+    #[cfg(feature = "dwarf")]
+    fn get_stack_variable_name(&self, target: &PointerTarget) -> Option<&str> {
+        let offset = target.offset.try_to_offset().ok()?;
+        let function = self
+            .debug_info?
+            .get_function_at(&target.get_allocation_site()?.address)?;
+        function
+            .get_stack_variable(offset)
+            .map(|variable| variable.name.as_str())
+    }
+
+    /// Variable names are only known with the `dwarf` feature.
+    #[cfg(not(feature = "dwarf"))]
+    fn get_stack_variable_name(&self, _target: &PointerTarget) -> Option<&str> {
+        None
+    }
+
+    /// Append the source location of the given address if it is known from the debug information.
+    fn format_address(&self, address: &str) -> String {
+        #[cfg(feature = "dwarf")]
+        if let Some(location) = self.debug_info.and_then(|debug_info| {
+            debug_info.get_source_location(parse_hex_string_to_u64(address).ok()?)
+        }) {
+            return format!("{address} ({location})");
+        }
+        address.to_string()
+    }
+
+    /// Get the name of the function with the given TID.
+    fn get_function_name(&self, tid: Option<&Tid>) -> String {
+        tid.and_then(|tid| get_function_name(&self.analysis_results.project.program.term, tid))
+            .unwrap_or("?")
+            .to_string()
+    }
+
+    /// Get the name of the function called by the call with the given TID together with the call address.
+    fn get_call_name(&self, call_tid: Option<&Tid>) -> String {
+        match call_tid {
+            Some(call_tid) => format!(
+                "{} at {}",
+                self.callee_names.get(call_tid).unwrap_or(&"?"),
+                call_tid.address
+            ),
+            None => "?".to_string(),
+        }
+    }
+
+    /// Get the string content that the pointer target points to, if it is known.
+    fn get_string_content(&self, blk: &Term<Blk>, target: &PointerTarget) -> Option<String> {
+        match target.kind {
+            ObjectKind::GlobalReadOnly | ObjectKind::GlobalWritable => {
+                let address = target.offset.try_to_bitvec().ok()?;
+                let string = self
+                    .analysis_results
+                    .project
+                    .runtime_memory_image
+                    .read_string_until_null_terminator(&address)
+                    .ok()?;
+                Some(format!("{string:?}"))
+            }
+            ObjectKind::Stack | ObjectKind::Heap => {
+                self.get_abstract_string_content(blk, target.id.as_ref()?, target)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the content of a stack or heap buffer from the string abstraction at the end of the given block.
+    #[cfg(feature = "string-abstraction")]
+    fn get_abstract_string_content(
+        &self,
+        blk: &Term<Blk>,
+        id: &AbstractIdentifier,
+        target: &PointerTarget,
+    ) -> Option<String> {
+        use crate::analysis::graph::Node;
+        use crate::analysis::interprocedural_fixpoint_generic::NodeValue;
+
+        let string_abstraction = self.analysis_results.string_abstraction?;
+        let graph = string_abstraction.get_graph();
+        let node = graph.node_indices().find(
+            |node| matches!(graph[*node], Node::BlkEnd(node_blk, _) if node_blk.tid == blk.tid),
+        )?;
+        let Some(NodeValue::Value(state)) = string_abstraction.get_node_value(node) else {
+            return None;
+        };
+        let string = match target.kind {
+            ObjectKind::Stack => {
+                let offset = target.offset.try_to_offset().ok()?;
+                state.get_stack_offset_to_string_map().get(&offset)?
+            }
+            _ => state.get_heap_to_string_map().get(id)?,
+        };
+        Some(string.to_string())
+    }
+
+    /// Without the string abstraction the content of stack and heap buffers is unknown.
+    #[cfg(not(feature = "string-abstraction"))]
+    fn get_abstract_string_content(
+        &self,
+        _blk: &Term<Blk>,
+        _id: &AbstractIdentifier,
+        _target: &PointerTarget,
+    ) -> Option<String> {
+        None
+    }
+}
+
+/// Get the name of the function or extern symbol with the given TID.
+fn get_function_name<'a>(program: &'a Program, tid: &Tid) -> Option<&'a str> {
+    if let Some(symbol) = program.extern_symbols.get(tid) {
+        Some(&symbol.name)
+    } else {
+        program.subs.get(tid).map(|sub| sub.term.name.as_str())
+    }
+}
+
+/// Get a short description of the location of an argument.
+fn format_arg(arg: &Arg) -> String {
+    match arg {
+        Arg::Register { expr, .. } => expr.to_string(),
+        Arg::Stack { address, size, .. } => format!("[{address}]:{}", u64::from(*size)),
+    }
+}
+
+/// Format an abstract value as a list of its relative and absolute parts.
+fn format_value(value: Option<&Data>) -> String {
+    let Some(value) = value else {
+        return "unknown".to_string();
+    };
+    let mut parts: Vec<String> = value
+        .get_relative_values()
+        .iter()
+        .map(|(id, offset)| format!("{id} + {offset}"))
+        .collect();
+    if let Some(absolute) = value.get_absolute_value() {
+        parts.push(absolute.to_string());
+    }
+    if value.contains_top() {
+        parts.push("Top".to_string());
+    }
+    if parts.is_empty() {
+        "Bottom".to_string()
+    } else {
+        parts.join(" | ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::pointer_inference::State;
+    use crate::{bitvec, variable};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn callsites_of_function() {
+        let mut project = Project::mock_x64();
+        let mut blk = Blk::mock_with_tid("main_blk");
+        blk.term.jmps = vec![Jmp::call("free_call", "free", None)];
+        let mut sub = Sub::mock("main");
+        sub.term.blocks.push(blk);
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+
+        let mut pi_result = PointerInference::mock(&project);
+        let mut state = State::new(&variable!("RSP:8"), Tid::new("main"), BTreeSet::new());
+        // The mock memory image contains the string "Hello World" at address 0x3002.
+        state.set_register(&variable!("RDI:8"), bitvec!("0x3002:8").into());
+        pi_result
+            .get_mut_states_at_tids()
+            .insert(Tid::new("free_call"), state);
+        let mut analysis_results = AnalysisResults::mock_from_project(&project);
+        analysis_results.pointer_inference = Some(&pi_result);
+
+        let output = dump_callsites(
+            &analysis_results,
+            "main",
+            #[cfg(feature = "dwarf")]
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            vec![
+                "Call sites of main (UNKNOWN):",
+                "UNKNOWN: call free (extern)",
+                "  RDI:8 = 0x0000000000003002:i64",
+                "    sources: read-only global memory",
+                "    string: \"Hello World\"",
+            ]
+        );
+        assert!(dump_callsites(
+            &analysis_results,
+            "unknown_function",
+            #[cfg(feature = "dwarf")]
+            None
+        )
+        .is_err());
+    }
+}
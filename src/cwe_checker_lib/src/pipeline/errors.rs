@@ -0,0 +1,87 @@
+//! Structured errors of the analysis pipeline.
+//!
+//! Errors during the translation of a function into the internally used IR do not abort the analysis.
+//! Instead, the function is treated as a function without code
+//! and a [`PipelineError`] describing the failure is returned alongside the analysis results.
+
+use crate::pcode::LiftingError;
+use crate::prelude::*;
+use crate::utils::log::LogMessage;
+
+/// The stage of the analysis pipeline in which an error occurred.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PipelineStage {
+    /// Normalization of the P-Code generated by Ghidra.
+    Normalization,
+    /// Translation of the P-Code into the internally used IR.
+    Lifting,
+}
+
+impl std::fmt::Display for PipelineStage {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PipelineStage::Normalization => write!(formatter, "Normalization"),
+            PipelineStage::Lifting => write!(formatter, "Lifting"),
+        }
+    }
+}
+
+/// An error that occurred while processing a function in the analysis pipeline.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct PipelineError {
+    /// The stage in which the error occurred.
+    pub stage: PipelineStage,
+    /// The TID of the affected function.
+    pub function: Tid,
+    /// The name of the affected function.
+    pub function_name: String,
+    /// The TID of the instruction that caused the error, if known.
+    pub location: Option<Tid>,
+    /// The reason for the error.
+    pub diagnostic: LiftingError,
+}
+
+impl PipelineError {
+    /// Create a new error for the given function.
+    pub fn new(
+        stage: PipelineStage,
+        function: Tid,
+        function_name: impl Into<String>,
+        diagnostic: LiftingError,
+    ) -> PipelineError {
+        PipelineError {
+            stage,
+            function,
+            function_name: function_name.into(),
+            location: None,
+            diagnostic,
+        }
+    }
+
+    /// Set the TID of the instruction that caused the error.
+    pub fn location(mut self, location: Tid) -> PipelineError {
+        self.location = Some(location);
+        self
+    }
+
+    /// Generate an error log message for the error.
+    pub fn to_log_message(&self) -> LogMessage {
+        let log_msg = LogMessage::new_error(self.to_string());
+        match &self.location {
+            Some(location) => log_msg.location(location.clone()),
+            None => log_msg.location(self.function.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for PipelineError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{} of function {} ({}) failed: {}",
+            self.stage, self.function_name, self.function, self.diagnostic
+        )
+    }
+}
+
+impl std::error::Error for PipelineError {}
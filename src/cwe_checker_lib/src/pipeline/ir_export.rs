@@ -0,0 +1,82 @@
+//! Export of the normalized intermediate representation as JSON.
+//!
+//! The exported file contains a single JSON object with the following fields:
+//!
+//! - `format_version`: The version of the export format, currently [`IR_EXPORT_FORMAT_VERSION`].
+//!   It is incremented whenever the serialized representation of the IR types changes incompatibly,
+//!   so that consumers can reject files that they do not understand.
+//! - `cwe_checker_version`: The version of the `cwe_checker_lib` crate that generated the file.
+//! - `project`: The normalized [`Project`].
+//!
+//! All IR types are serialized with their `serde` representation:
+//! - Structs are JSON objects with one field per struct field, e.g. `Tid` as `{"id": ..., "address": ...}`
+//!   and `Term<T>` as `{"tid": ..., "term": ...}`.
+//! - Enum variants are externally tagged, e.g. a `Def::Assign` is serialized as `{"Assign": {"var": ..., "value": ...}}`
+//!   and a variant without fields as its name.
+//! - Maps with `Tid` keys (e.g. the functions of the program) are serialized as lists of key-value pairs.
+//! - Constants (`Bitvector`) are serialized as by the `apint` crate.
+//!
+//! See the documentation of the types in the [`intermediate_representation`](crate::intermediate_representation) module
+//! for the meaning of the fields.
+
+use crate::intermediate_representation::Project;
+use crate::prelude::*;
+use std::borrow::Cow;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// The version of the JSON format of exported intermediate representations.
+pub const IR_EXPORT_FORMAT_VERSION: u64 = 1;
+
+/// The top-level object of an exported intermediate representation.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct IrExport<'a> {
+    /// The version of the export format.
+    pub format_version: u64,
+    /// The version of the `cwe_checker_lib` crate that generated the export.
+    pub cwe_checker_version: String,
+    /// The normalized project.
+    pub project: Cow<'a, Project>,
+}
+
+impl<'a> IrExport<'a> {
+    /// Wrap the given project for export with the current format version.
+    pub fn new(project: &'a Project) -> IrExport<'a> {
+        IrExport {
+            format_version: IR_EXPORT_FORMAT_VERSION,
+            cwe_checker_version: env!("CARGO_PKG_VERSION").to_string(),
+            project: Cow::Borrowed(project),
+        }
+    }
+}
+
+/// Write the given (normalized) project as JSON to the file at the given path.
+///
+/// See the [module documentation](self) for the format of the file.
+pub fn export_ir(project: &Project, path: &Path) -> Result<(), Error> {
+    let file = std::fs::File::create(path).context(format!(
+        "Could not create IR export file {}",
+        path.display()
+    ))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer(&mut writer, &IrExport::new(project))
+        .context("Could not serialize the intermediate representation")?;
+    writer
+        .flush()
+        .context(format!("Could not write IR export file {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_roundtrip() {
+        let project = Project::mock_x64();
+        let json = serde_json::to_string(&IrExport::new(&project)).unwrap();
+        let exported: IrExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(exported.format_version, IR_EXPORT_FORMAT_VERSION);
+        assert_eq!(*exported.project, project);
+    }
+}
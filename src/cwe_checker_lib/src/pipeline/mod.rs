@@ -1,34 +1,131 @@
 //! This module contains functions and structs helpful for building a complete analysis pipeline
 //! starting from the binary file path.
 
+pub mod callsite_dump;
+pub use callsite_dump::dump_callsites;
+mod errors;
+pub use errors::{PipelineError, PipelineStage};
+pub mod ir_export;
+pub use ir_export::export_ir;
 mod results;
 pub use results::AnalysisResults;
 
+use crate::frontend::binja::BinjaDump;
 use crate::intermediate_representation::{Project, RuntimeMemoryImage};
 use crate::prelude::*;
+use crate::utils::binary::BareMetalConfig;
 use crate::utils::debug;
+use crate::utils::emulation::{emulate_loader, EmulationSettings};
+use crate::utils::ghidra::{
+    get_project_from_ghidra, parse_pcode_project_to_ir_project, ExportSettings,
+};
 use crate::utils::log::LogMessage;
-use crate::utils::{binary::BareMetalConfig, ghidra::get_project_from_ghidra};
+use crate::utils::recording::load_recording;
 use std::path::Path;
 
+/// The binary file (as a byte vector), the parsed project struct, the log messages
+/// and the errors for functions that could not be translated.
+type DisassembledBinary = (Vec<u8>, Project, Vec<LogMessage>, Vec<PipelineError>);
+
 /// Disassemble the given binary and parse it to a [`Project`] struct.
 ///
 /// If successful, returns the binary file (as a byte vector), the parsed project struct,
-/// and a vector of log messages generated during the process.
+/// a vector of log messages generated during the process
+/// and the errors for functions that could not be translated.
+/// Such functions are contained in the project as functions without code.
+///
+/// The export settings control from where Ghidra loads the binary and which functions are exported.
 pub fn disassemble_binary(
     binary_file_path: &Path,
     bare_metal_config_opt: Option<BareMetalConfig>,
+    export_settings: &ExportSettings,
     debug_settings: &debug::Settings,
-) -> Result<(Vec<u8>, Project, Vec<LogMessage>), Error> {
+) -> Result<DisassembledBinary, Error> {
     let binary: Vec<u8> =
         std::fs::read(binary_file_path).context("Could not read from binary file path {}")?;
-    let (mut project, mut all_logs) = get_project_from_ghidra(
+    let (project, all_logs, pipeline_errors) = get_project_from_ghidra(
         binary_file_path,
         &binary[..],
         bare_metal_config_opt.clone(),
+        export_settings,
+        debug_settings,
+    )?;
+    finish_disassembly(
+        binary,
+        project,
+        all_logs,
+        pipeline_errors,
+        bare_metal_config_opt.as_ref(),
         debug_settings,
+    )
+}
+
+/// Parse the P-Code of the recording in the given directory to a [`Project`] struct
+/// without executing Ghidra.
+///
+/// The binary is read from the recording,
+/// so that the analysis does not need access to the original binary.
+/// Returns the same results as [`disassemble_binary`] returned for the recorded run.
+/// See the [`recording`](crate::utils::recording) module for the format of recordings.
+pub fn replay_recording(
+    recording_directory: &Path,
+    debug_settings: &debug::Settings,
+) -> Result<DisassembledBinary, Error> {
+    let recording = load_recording(recording_directory)?;
+    let bare_metal_config_opt = recording.manifest.bare_metal_config;
+    let (project, all_logs, pipeline_errors) = parse_pcode_project_to_ir_project(
+        recording.pcode_project,
+        &recording.binary[..],
+        &bare_metal_config_opt,
     )?;
+    finish_disassembly(
+        recording.binary,
+        project,
+        all_logs,
+        pipeline_errors,
+        bare_metal_config_opt.as_ref(),
+        debug_settings,
+    )
+}
 
+/// Parse the given Binary Ninja LLIL dump of the binary to a [`Project`] struct
+/// without executing Ghidra.
+///
+/// Returns the same results as [`disassemble_binary`].
+/// See the [`binja`](crate::frontend::binja) module for how to generate the dump.
+pub fn load_binja_dump(
+    binary_file_path: &Path,
+    dump_path: &Path,
+    debug_settings: &debug::Settings,
+) -> Result<DisassembledBinary, Error> {
+    let binary: Vec<u8> =
+        std::fs::read(binary_file_path).context("Could not read from binary file path")?;
+    let file = std::fs::File::open(dump_path).context("Could not open the Binary Ninja dump")?;
+    let dump: BinjaDump = serde_json::from_reader(std::io::BufReader::new(file))
+        .context("Invalid Binary Ninja dump")?;
+    // If the base address of the binary is unknown, we assume that Binary Ninja did not rebase the binary.
+    let binary_base_address =
+        RuntimeMemoryImage::get_base_address(&binary).unwrap_or(dump.image_base);
+    let project = dump.into_ir_project(binary_base_address)?;
+    finish_disassembly(
+        binary,
+        project,
+        Vec::new(),
+        Vec::new(),
+        None,
+        debug_settings,
+    )
+}
+
+/// Normalize the freshly lifted project and add the runtime memory image of the binary to it.
+fn finish_disassembly(
+    binary: Vec<u8>,
+    mut project: Project,
+    mut all_logs: Vec<LogMessage>,
+    pipeline_errors: Vec<PipelineError>,
+    bare_metal_config_opt: Option<&BareMetalConfig>,
+    debug_settings: &debug::Settings,
+) -> Result<DisassembledBinary, Error> {
     // Normalize the project and gather log messages generated from it.
     debug_settings.print(&project.program.term, debug::Stage::Ir(debug::IrForm::Raw));
     all_logs.append(&mut project.normalize_basic());
@@ -43,7 +140,7 @@ pub fn disassemble_binary(
     );
 
     // Generate the representation of the runtime memory image of the binary
-    let mut runtime_memory_image = if let Some(bare_metal_config) = bare_metal_config_opt.as_ref() {
+    let mut runtime_memory_image = if let Some(bare_metal_config) = bare_metal_config_opt {
         RuntimeMemoryImage::new_from_bare_metal(&binary, bare_metal_config)
             .context("Error while generating runtime memory image.")?
     } else {
@@ -56,5 +153,48 @@ pub fn disassemble_binary(
     }
     project.runtime_memory_image = runtime_memory_image;
 
-    Ok((binary, project, all_logs))
+    Ok((binary, project, all_logs, pipeline_errors))
+}
+
+/// Replace the runtime memory image of the project with the memory image
+/// reconstructed from the given ELF core dump of a running process of the binary.
+///
+/// See [`RuntimeMemoryImage::new_from_core_dump`] for details.
+pub fn load_core_dump(
+    project: &mut Project,
+    binary: &[u8],
+    core_dump_path: &Path,
+) -> Result<(), Error> {
+    let core_dump: Vec<u8> =
+        std::fs::read(core_dump_path).context("Could not read from core dump file path")?;
+    let runtime_memory_image = RuntimeMemoryImage::new_from_core_dump(binary, &core_dump)
+        .context("Error while generating runtime memory image from core dump.")?;
+    set_snapshot_memory_image(project, runtime_memory_image);
+    Ok(())
+}
+
+/// Replace the runtime memory image of the project with the memory image
+/// obtained by loading the binary with QEMU user mode emulation.
+///
+/// See [`emulate_loader`] for details.
+pub fn load_emulated_memory(
+    project: &mut Project,
+    binary: &[u8],
+    binary_file_path: &Path,
+    emulation_settings: &EmulationSettings,
+) -> Result<(), Error> {
+    let runtime_memory_image =
+        emulate_loader(binary_file_path, binary, project, emulation_settings)
+            .context("Error while generating runtime memory image by loader emulation.")?;
+    set_snapshot_memory_image(project, runtime_memory_image);
+    Ok(())
+}
+
+/// Replace the runtime memory image of the project with a memory image
+/// reconstructed from a snapshot of a running process of the binary.
+fn set_snapshot_memory_image(project: &mut Project, mut runtime_memory_image: RuntimeMemoryImage) {
+    if project.program.term.address_base_offset != 0 {
+        runtime_memory_image.add_global_memory_offset(project.program.term.address_base_offset);
+    }
+    project.runtime_memory_image = runtime_memory_image;
 }
@@ -1,11 +1,21 @@
-use crate::abstract_domain::BricksDomain;
+#[cfg(feature = "string-abstraction")]
+use crate::abstract_domain::{
+    AbstractDomain, AutomatonDomain, BricksDomain, DomainInsertion, HasTop,
+};
+use crate::analysis::escape_analysis::EscapeAnalysis;
+use crate::analysis::function_effects::FunctionEffects;
 use crate::analysis::function_signature::FunctionSignature;
 use crate::analysis::graph::Graph;
 use crate::analysis::pointer_inference::PointerInference;
+#[cfg(feature = "string-abstraction")]
 use crate::analysis::string_abstraction::StringAbstraction;
+#[cfg(feature = "taint")]
+use crate::analysis::taint::summary::TaintSummaries;
 use crate::intermediate_representation::Project;
 use crate::prelude::*;
+use crate::utils::cancellation::CancellationToken;
 use crate::utils::log::LogMessage;
+use crate::utils::metrics::{MetricsCollector, SiteCounts};
 use std::collections::BTreeMap;
 
 /// A struct containing pointers to all known analysis results
@@ -18,12 +28,27 @@ pub struct AnalysisResults<'a> {
     pub control_flow_graph: &'a Graph<'a>,
     /// A pointer to the project struct
     pub project: &'a Project,
+    /// The side effects of all functions if already computed.
+    pub function_effects: Option<&'a BTreeMap<Tid, FunctionEffects>>,
     /// The results of the function signature analysis if already computed.
     pub function_signatures: Option<&'a BTreeMap<Tid, FunctionSignature>>,
     /// The result of the pointer inference analysis if already computed.
     pub pointer_inference: Option<&'a PointerInference<'a>>,
     /// The result of the string abstraction if already computed.
+    #[cfg(feature = "string-abstraction")]
     pub string_abstraction: Option<&'a StringAbstraction<'a, BricksDomain>>,
+    /// The result of the string abstraction with the automaton domain if already computed.
+    #[cfg(feature = "string-abstraction")]
+    pub automaton_string_abstraction: Option<&'a StringAbstraction<'a, AutomatonDomain>>,
+    /// The result of the escape analysis for stack objects if already computed.
+    pub escape_analysis: Option<&'a EscapeAnalysis>,
+    /// Taint summaries of functions exported by libraries that the binary links against.
+    #[cfg(feature = "taint")]
+    pub taint_summaries: Option<&'a TaintSummaries>,
+    /// The token that stops the fixpoint computations of the analyses and checks if cancelled.
+    pub cancellation_token: Option<&'a CancellationToken>,
+    /// The collector for the site counts reported by the CWE checks.
+    pub metrics_collector: Option<&'a MetricsCollector>,
 }
 
 impl<'a> AnalysisResults<'a> {
@@ -37,9 +62,65 @@ impl<'a> AnalysisResults<'a> {
             binary,
             control_flow_graph,
             project,
+            function_effects: None,
             function_signatures: None,
             pointer_inference: None,
+            #[cfg(feature = "string-abstraction")]
             string_abstraction: None,
+            #[cfg(feature = "string-abstraction")]
+            automaton_string_abstraction: None,
+            escape_analysis: None,
+            #[cfg(feature = "taint")]
+            taint_summaries: None,
+            cancellation_token: None,
+            metrics_collector: None,
+        }
+    }
+
+    /// Create a new `AnalysisResults` struct containing the given cancellation token.
+    pub fn with_cancellation_token(
+        self,
+        cancellation_token: Option<&'a CancellationToken>,
+    ) -> AnalysisResults<'a> {
+        AnalysisResults {
+            cancellation_token,
+            ..self
+        }
+    }
+
+    /// Create a new `AnalysisResults` struct containing the given collector for site counts.
+    pub fn with_metrics_collector(
+        self,
+        metrics_collector: Option<&'a MetricsCollector>,
+    ) -> AnalysisResults<'a> {
+        AnalysisResults {
+            metrics_collector,
+            ..self
+        }
+    }
+
+    /// Report the candidate sites examined by the CWE check with the given name,
+    /// see the [`metrics`](crate::utils::metrics) module.
+    /// Does nothing if no metrics collector is set.
+    pub fn report_site_counts(&self, check: &str, counts: SiteCounts) {
+        if let Some(metrics_collector) = self.metrics_collector {
+            metrics_collector.report(check, counts);
+        }
+    }
+
+    /// Compute the side effects of all functions.
+    pub fn compute_function_effects(&self) -> BTreeMap<Tid, FunctionEffects> {
+        crate::analysis::function_effects::compute_function_effects(self.project)
+    }
+
+    /// Create a new `AnalysisResults` struct containing the given function effects.
+    pub fn with_function_effects(
+        self,
+        function_effects: Option<&'a BTreeMap<Tid, FunctionEffects>>,
+    ) -> AnalysisResults<'a> {
+        AnalysisResults {
+            function_effects,
+            ..self
         }
     }
 
@@ -90,24 +171,31 @@ impl<'a> AnalysisResults<'a> {
         }
     }
 
-    /// Compute the string abstraction.
+    /// Compute the string abstraction with the given string domain.
     /// As the string abstraction depends on the pointer inference, the
     /// pointer inference is also computed and put into the `AnalysisResults` struct.
     /// The result gets returned, but not saved to the `AnalysisResults` struct itself.
-    pub fn compute_string_abstraction(
+    #[cfg(feature = "string-abstraction")]
+    pub fn compute_string_abstraction<
+        T: AbstractDomain + DomainInsertion + HasTop + Eq + From<String> + Send + Sync,
+    >(
         &'a self,
         config: &serde_json::Value,
         pi_results: Option<&'a PointerInference<'a>>,
-    ) -> StringAbstraction<BricksDomain> {
-        crate::analysis::string_abstraction::run(
+    ) -> StringAbstraction<T> {
+        let mut string_abstraction = StringAbstraction::new(
             self.project,
             self.control_flow_graph,
             pi_results.unwrap(),
             serde_json::from_value(config.clone()).unwrap(),
-        )
+        );
+        string_abstraction.set_cancellation_token(self.cancellation_token);
+        string_abstraction.compute();
+        string_abstraction
     }
 
     /// Create a new `AnalysisResults` struct containing the given string abstraction results.
+    #[cfg(feature = "string-abstraction")]
     pub fn with_string_abstraction<'b: 'a>(
         self,
         string_abstraction: Option<&'b StringAbstraction<'a, BricksDomain>>,
@@ -117,6 +205,60 @@ impl<'a> AnalysisResults<'a> {
             ..self
         }
     }
+
+    /// Create a new `AnalysisResults` struct containing the given string abstraction results
+    /// computed with the automaton domain.
+    #[cfg(feature = "string-abstraction")]
+    pub fn with_automaton_string_abstraction<'b: 'a>(
+        self,
+        automaton_string_abstraction: Option<&'b StringAbstraction<'a, AutomatonDomain>>,
+    ) -> AnalysisResults<'b> {
+        AnalysisResults {
+            automaton_string_abstraction,
+            ..self
+        }
+    }
+
+    /// Compute the escape analysis for stack objects.
+    /// The escape analysis depends on the given pointer inference results.
+    /// The result gets returned, but not saved to the `AnalysisResults` struct itself.
+    pub fn compute_escape_analysis(&self, pi_results: &PointerInference) -> EscapeAnalysis {
+        EscapeAnalysis::new(self.project, pi_results)
+    }
+
+    /// Create a new `AnalysisResults` struct containing the given escape analysis results.
+    pub fn with_escape_analysis(
+        self,
+        escape_analysis: Option<&'a EscapeAnalysis>,
+    ) -> AnalysisResults<'a> {
+        AnalysisResults {
+            escape_analysis,
+            ..self
+        }
+    }
+
+    /// Compute the taint summaries of the functions exported by the binary.
+    /// The pointer inference results have to be contained in the `AnalysisResults` struct.
+    /// The given summaries of imported functions are used for calls to extern symbols.
+    #[cfg(feature = "taint")]
+    pub fn compute_taint_summaries(
+        &self,
+        imported_summaries: Option<&TaintSummaries>,
+    ) -> TaintSummaries {
+        crate::analysis::taint::summary::compute_taint_summaries(self, imported_summaries)
+    }
+
+    /// Create a new `AnalysisResults` struct containing the given taint summaries of imported functions.
+    #[cfg(feature = "taint")]
+    pub fn with_taint_summaries(
+        self,
+        taint_summaries: Option<&'a TaintSummaries>,
+    ) -> AnalysisResults<'a> {
+        AnalysisResults {
+            taint_summaries,
+            ..self
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,263 @@
+//! Support for CWE checks that are shipped separately from the cwe_checker.
+//!
+//! A plugin is a dynamic library (a crate with `crate-type = ["cdylib"]`)
+//! that depends on `cwe_checker_lib` and defines one or more [`CweModule`]s.
+//! The plugin exports its modules with the [`export_plugin!`](crate::export_plugin) macro:
+//!
+//! ```ignore
+//! pub static CWE_MODULE: CweModule = CweModule {
+//!     name: "CWE9999",
+//!     version: "0.1",
+//!     run: check_cwe,
+//! };
+//!
+//! cwe_checker_lib::export_plugin!(CWE_MODULE);
+//! ```
+//!
+//! If the cwe_checker is built with the `plugins` feature,
+//! it loads all dynamic libraries in the plugin directory at startup
+//! and runs the checks contained in them together with the built-in checks.
+//! The configuration of a plugin check is read from the entry with the name of the check in the configuration file.
+//! The results of the Pointer Inference analysis are always computed if a plugin check is run.
+//!
+//! Since Rust has no stable ABI, a plugin has to be compiled with the same compiler version
+//! and against the same version of `cwe_checker_lib` as the cwe_checker loading it.
+//! The plugin declaration contains the ABI version of the plugin interface
+//! and the version of `cwe_checker_lib` that the plugin was built against,
+//! so that incompatible plugins are rejected instead of causing undefined behavior.
+//!
+//! Loading plugins is only supported on Unix systems.
+
+use crate::prelude::*;
+use crate::CweModule;
+#[cfg(feature = "plugins")]
+use std::collections::BTreeSet;
+use std::ffi::{c_char, CStr};
+#[cfg(feature = "plugins")]
+use std::path::Path;
+
+/// The version of the plugin interface.
+/// Incremented whenever the layout of [`PluginDeclaration`] changes.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// The version of `cwe_checker_lib`.
+pub const LIB_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The version of `cwe_checker_lib` as a NUL-terminated C string.
+const LIB_VERSION_C_STRING: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+/// The name of the symbol under which a plugin exports its [`PluginDeclaration`].
+pub const PLUGIN_DECLARATION_SYMBOL: &[u8] = b"CWE_CHECKER_PLUGIN_DECLARATION\0";
+
+/// The declaration of the CWE checks contained in a plugin.
+///
+/// The declaration only contains types with a C representation,
+/// so that its layout does not depend on the compiler version of the plugin.
+/// Use the [`export_plugin!`](crate::export_plugin) macro to generate it.
+#[repr(C)]
+pub struct PluginDeclaration {
+    /// The version of the plugin interface the plugin was built for.
+    /// This is the first field so that it can be checked before any other field is accessed.
+    pub abi_version: u32,
+    /// The version of `cwe_checker_lib` that the plugin was built against as a NUL-terminated C string.
+    pub lib_version: *const c_char,
+    /// Pointer to the first element of the array of CWE checks of the plugin.
+    pub modules: *const &'static CweModule,
+    /// The number of CWE checks of the plugin.
+    pub num_modules: usize,
+}
+
+// SAFETY: The pointers of a declaration created with `PluginDeclaration::new` point to immutable static data.
+unsafe impl Sync for PluginDeclaration {}
+
+impl PluginDeclaration {
+    /// Create the declaration of a plugin containing the given CWE checks.
+    pub const fn new(modules: &'static [&'static CweModule]) -> PluginDeclaration {
+        PluginDeclaration {
+            abi_version: PLUGIN_ABI_VERSION,
+            lib_version: LIB_VERSION_C_STRING.as_ptr().cast(),
+            modules: modules.as_ptr(),
+            num_modules: modules.len(),
+        }
+    }
+
+    /// Get the version of `cwe_checker_lib` that the plugin was built against.
+    pub fn lib_version(&self) -> &str {
+        // SAFETY: The field points to a NUL-terminated string in static memory.
+        unsafe { CStr::from_ptr(self.lib_version) }
+            .to_str()
+            .unwrap_or_default()
+    }
+
+    /// Get the CWE checks of the plugin.
+    pub fn modules(&self) -> &'static [&'static CweModule] {
+        // SAFETY: The fields point to an array with `num_modules` elements in static memory.
+        unsafe { std::slice::from_raw_parts(self.modules, self.num_modules) }
+    }
+
+    /// Check whether the plugin was built for this version of the cwe_checker.
+    ///
+    /// The other fields of the declaration are only accessed if the ABI version matches.
+    pub fn check_compatibility(&self) -> Result<(), Error> {
+        if self.abi_version != PLUGIN_ABI_VERSION {
+            return Err(anyhow!(
+                "Plugin ABI version {} does not match the expected version {}",
+                self.abi_version,
+                PLUGIN_ABI_VERSION
+            ));
+        }
+        if self.lib_version() != LIB_VERSION {
+            return Err(anyhow!(
+                "Plugin was built against cwe_checker_lib {} instead of {}",
+                self.lib_version(),
+                LIB_VERSION
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Export the given [`CweModule`]s as the checks of a plugin.
+#[macro_export]
+macro_rules! export_plugin {
+    ($($module:path),* $(,)?) => {
+        #[no_mangle]
+        pub static CWE_CHECKER_PLUGIN_DECLARATION: $crate::plugins::PluginDeclaration =
+            $crate::plugins::PluginDeclaration::new(&[$(&$module),*]);
+    };
+}
+
+/// Load the CWE checks of all plugins in the given directory.
+///
+/// All files with the platform-specific extension for dynamic libraries are loaded in alphabetical order.
+/// Loaded libraries are never unloaded, since the returned modules point into them.
+/// Returns an error if a plugin cannot be loaded, is incompatible
+/// or contains a check whose name is already taken by another check.
+#[cfg(feature = "plugins")]
+pub fn load_plugins(plugin_dir: &Path) -> Result<Vec<&'static CweModule>, Error> {
+    let mut plugin_paths = Vec::new();
+    for entry in std::fs::read_dir(plugin_dir)
+        .with_context(|| format!("Could not read plugin directory {}", plugin_dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_file()
+            && path.extension().and_then(|ext| ext.to_str())
+                == Some(std::env::consts::DLL_EXTENSION)
+        {
+            plugin_paths.push(path);
+        }
+    }
+    plugin_paths.sort();
+
+    let mut module_names: BTreeSet<&str> = crate::get_modules()
+        .into_iter()
+        .map(|module| module.name)
+        .collect();
+    let mut modules = Vec::new();
+    for path in plugin_paths {
+        let declaration = load_plugin(&path)
+            .with_context(|| format!("Could not load plugin {}", path.display()))?;
+        for module in declaration.modules() {
+            if !module_names.insert(module.name) {
+                return Err(anyhow!(
+                    "Plugin {} contains the check {}, which already exists",
+                    path.display(),
+                    module.name
+                ));
+            }
+            modules.push(*module);
+        }
+    }
+    Ok(modules)
+}
+
+/// Load the plugin at the given path and return its declaration.
+#[cfg(all(feature = "plugins", unix))]
+fn load_plugin(path: &Path) -> Result<&'static PluginDeclaration, Error> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: Loading a library executes its initialization routines.
+    // We have to trust the plugins in the plugin directory anyway, since we execute their code.
+    // The library is never unloaded, so references into it stay valid for the rest of the program.
+    let declaration = unsafe {
+        let handle = dl::dlopen(path.as_ptr(), dl::RTLD_NOW | dl::RTLD_LOCAL);
+        if handle.is_null() {
+            return Err(anyhow!(
+                "{}",
+                CStr::from_ptr(dl::dlerror()).to_string_lossy()
+            ));
+        }
+        let symbol = dl::dlsym(handle, PLUGIN_DECLARATION_SYMBOL.as_ptr().cast());
+        if symbol.is_null() {
+            return Err(anyhow!(
+                "{}",
+                CStr::from_ptr(dl::dlerror()).to_string_lossy()
+            ));
+        }
+        // The declaration is only used after checking its ABI version.
+        &*(symbol as *const PluginDeclaration)
+    };
+    declaration.check_compatibility()?;
+    Ok(declaration)
+}
+
+/// Plugins can only be loaded with the dynamic linker of Unix systems.
+#[cfg(all(feature = "plugins", not(unix)))]
+fn load_plugin(_path: &Path) -> Result<&'static PluginDeclaration, Error> {
+    Err(anyhow!("Loading plugins is only supported on Unix systems"))
+}
+
+/// Bindings to the dynamic linker of the C library.
+#[cfg(all(feature = "plugins", unix))]
+mod dl {
+    use std::ffi::{c_char, c_int, c_void};
+
+    pub const RTLD_NOW: c_int = 2;
+    #[cfg(target_os = "macos")]
+    pub const RTLD_LOCAL: c_int = 4;
+    #[cfg(not(target_os = "macos"))]
+    pub const RTLD_LOCAL: c_int = 0;
+
+    #[cfg_attr(target_os = "linux", link(name = "dl"))]
+    extern "C" {
+        pub fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+        pub fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+        pub fn dlerror() -> *const c_char;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::export_plugin!(crate::checkers::cwe_676::CWE_MODULE);
+
+    #[test]
+    fn compatibility() {
+        assert!(CWE_CHECKER_PLUGIN_DECLARATION.check_compatibility().is_ok());
+        assert_eq!(CWE_CHECKER_PLUGIN_DECLARATION.lib_version(), LIB_VERSION);
+        assert_eq!(CWE_CHECKER_PLUGIN_DECLARATION.modules()[0].name, "CWE676");
+
+        let declaration = PluginDeclaration {
+            abi_version: PLUGIN_ABI_VERSION + 1,
+            ..CWE_CHECKER_PLUGIN_DECLARATION
+        };
+        assert!(declaration.check_compatibility().is_err());
+        let declaration = PluginDeclaration {
+            lib_version: "0.0.0\0".as_ptr().cast(),
+            ..CWE_CHECKER_PLUGIN_DECLARATION
+        };
+        assert!(declaration.check_compatibility().is_err());
+    }
+
+    #[cfg(feature = "plugins")]
+    #[test]
+    fn empty_plugin_dir() {
+        let plugin_dir = std::env::temp_dir().join("cwe_checker_empty_plugin_dir");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        assert!(load_plugins(&plugin_dir).unwrap().is_empty());
+        assert!(load_plugins(&plugin_dir.join("does_not_exist")).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! Queries for interactive audits of a binary.
+//!
+//! In contrast to the [CWE checks](crate::checkers), queries do not search for fixed bug patterns.
+//! Instead they answer questions about the analyzed binary that come up during manual audits,
+//! e.g. where a certain function is called with non-constant arguments
+//! or which functions reference a certain string.
+//!
+//! Queries are evaluated against the [cross reference index](crate::analysis::xref::XrefIndex)
+//! and, if available, the results of the [Pointer Inference analysis](crate::analysis::pointer_inference).
+
+use crate::abstract_domain::TryToBitvec;
+use crate::analysis::vsa_results::VsaResult;
+use crate::analysis::xref::{XrefIndex, XrefKind};
+use crate::intermediate_representation::*;
+use crate::pipeline::AnalysisResults;
+use crate::prelude::*;
+use crate::utils::symbol_utils::{get_callsites, get_symbol_map};
+
+/// A query that can be evaluated on the analysis results of a binary.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum Query {
+    /// Find all call sites of an extern symbol.
+    CallSites {
+        /// The name of the extern symbol.
+        symbol: String,
+        /// If set, only report call sites where the parameter with this index
+        /// is not known to be a constant.
+        non_constant_param: Option<usize>,
+    },
+    /// Find all instructions referencing a string in global memory containing the given text.
+    StringReferences(String),
+    /// Find all writes to a global variable, given either by its address or by its symbol name.
+    GlobalWrites(String),
+}
+
+/// A single result of a query.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct QueryMatch {
+    /// The name of the function containing the match.
+    pub function: String,
+    /// The TID of the matching instruction.
+    pub tid: String,
+    /// The address of the matching instruction.
+    pub address: String,
+    /// A short description of the match.
+    pub description: String,
+}
+
+impl std::fmt::Display for QueryMatch {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{} ({}): {}",
+            self.address, self.function, self.description
+        )
+    }
+}
+
+impl QueryMatch {
+    /// Generate a new match for the given instruction in the given function.
+    fn new(sub: &Term<Sub>, tid: &Tid, description: String) -> QueryMatch {
+        QueryMatch {
+            function: sub.term.name.clone(),
+            tid: format!("{tid}"),
+            address: tid.address.clone(),
+            description,
+        }
+    }
+}
+
+/// Evaluate the query on the given analysis results and return the sorted list of matches.
+///
+/// Returns an error if the query references symbols or parameters that do not exist in the binary.
+pub fn evaluate(
+    analysis_results: &AnalysisResults,
+    index: &XrefIndex,
+    query: &Query,
+) -> Result<Vec<QueryMatch>, Error> {
+    let mut matches = match query {
+        Query::CallSites {
+            symbol,
+            non_constant_param,
+        } => find_call_sites(analysis_results, symbol, *non_constant_param)?,
+        Query::StringReferences(text) => find_string_references(analysis_results, index, text),
+        Query::GlobalWrites(global) => find_global_writes(analysis_results, index, global)?,
+    };
+    matches.sort();
+    matches.dedup();
+    Ok(matches)
+}
+
+/// Find all call sites of the given extern symbol.
+///
+/// If `non_constant_param` is set, only those call sites are returned
+/// where the Pointer Inference analysis could not determine a constant value for the given parameter.
+/// Without Pointer Inference results no parameter value is known to be constant.
+fn find_call_sites(
+    analysis_results: &AnalysisResults,
+    symbol_name: &str,
+    non_constant_param: Option<usize>,
+) -> Result<Vec<QueryMatch>, Error> {
+    let project = analysis_results.project;
+    let symbol_map = get_symbol_map(project, &[symbol_name.to_string()]);
+    if symbol_map.is_empty() {
+        return Err(anyhow!(
+            "{} is not an extern symbol of the binary",
+            symbol_name
+        ));
+    }
+    let mut matches = Vec::new();
+    for sub in project.program.term.subs.values() {
+        for (_blk, jmp, symbol) in get_callsites(sub, &symbol_map) {
+            let Some(param_index) = non_constant_param else {
+                matches.push(QueryMatch::new(
+                    sub,
+                    &jmp.tid,
+                    format!("Call to {}", symbol.name),
+                ));
+                continue;
+            };
+            let param = symbol.parameters.get(param_index).ok_or_else(|| {
+                anyhow!(
+                    "{} has no parameter with index {}",
+                    symbol.name,
+                    param_index
+                )
+            })?;
+            let param_value = analysis_results
+                .pointer_inference
+                .and_then(|pi| pi.eval_parameter_arg_at_call(&jmp.tid, param));
+            let constant = param_value
+                .as_ref()
+                .and_then(|value| value.get_if_absolute_value())
+                .and_then(|value| value.try_to_bitvec().ok());
+            if constant.is_none() {
+                let value_description = match param_value {
+                    Some(value) => format!("{}", value.to_json_compact()),
+                    None => "unknown".to_string(),
+                };
+                matches.push(QueryMatch::new(
+                    sub,
+                    &jmp.tid,
+                    format!(
+                        "Call to {} with non-constant parameter {}: {}",
+                        symbol.name, param_index, value_description
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// Find all instructions referencing a string in global memory that contains the given text.
+fn find_string_references(
+    analysis_results: &AnalysisResults,
+    index: &XrefIndex,
+    text: &str,
+) -> Vec<QueryMatch> {
+    let project = analysis_results.project;
+    let mut matches = Vec::new();
+    for address in index.referenced_addresses() {
+        let Ok(string) = project
+            .runtime_memory_image
+            .read_string_until_null_terminator(&Bitvector::from_u64(address))
+        else {
+            continue;
+        };
+        if !string.contains(text) {
+            continue;
+        }
+        for xref in index.get_xrefs(address) {
+            if let Some(sub) = project.program.term.subs.get(&xref.sub) {
+                matches.push(QueryMatch::new(
+                    sub,
+                    &xref.tid,
+                    format!("Reference to string \"{}\" at 0x{:x}", string, address),
+                ));
+            }
+        }
+    }
+    matches
+}
+
+/// Find all instructions writing to the given global variable.
+///
+/// The global variable is either given as a hexadecimal address or as the name of a symbol in the binary.
+fn find_global_writes(
+    analysis_results: &AnalysisResults,
+    index: &XrefIndex,
+    global: &str,
+) -> Result<Vec<QueryMatch>, Error> {
+    let project = analysis_results.project;
+    let (start, size) = match global.strip_prefix("0x") {
+        Some(hex_address) => (u64::from_str_radix(hex_address, 16)?, 1),
+        None => {
+            let (address, size) = find_global_symbol(analysis_results.binary, global)
+                .ok_or_else(|| anyhow!("No global symbol with name {} found", global))?;
+            (address + project.program.term.address_base_offset, size)
+        }
+    };
+    let mut matches = Vec::new();
+    for (address, xref) in index.get_xrefs_in_range(start, start + std::cmp::max(size, 1)) {
+        if xref.kind != XrefKind::Write {
+            continue;
+        }
+        if let Some(sub) = project.program.term.subs.get(&xref.sub) {
+            matches.push(QueryMatch::new(
+                sub,
+                &xref.tid,
+                format!("Write to {} at 0x{:x}", global, address),
+            ));
+        }
+    }
+    Ok(matches)
+}
+
+/// Find the address and size of the data object with the given name in the symbol table of an ELF binary.
+fn find_global_symbol(binary: &[u8], name: &str) -> Option<(u64, u64)> {
+    let goblin::Object::Elf(elf_file) = goblin::Object::parse(binary).ok()? else {
+        return None;
+    };
+    elf_file
+        .syms
+        .iter()
+        .chain(elf_file.dynsyms.iter())
+        .find(|sym| {
+            sym.st_type() == goblin::elf::sym::STT_OBJECT
+                && (elf_file.strtab.get_at(sym.st_name) == Some(name)
+                    || elf_file.dynstrtab.get_at(sym.st_name) == Some(name))
+        })
+        .map(|sym| (sym.st_value, sym.st_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::def;
+
+    fn mock_project() -> Project {
+        let mut project = Project::mock_x64();
+        let memcpy = ExternSymbol::mock_x64("memcpy");
+        project
+            .program
+            .term
+            .extern_symbols
+            .insert(memcpy.tid.clone(), memcpy);
+        let mut blk = Blk::mock();
+        blk.term.defs = vec![
+            def!["string_ref: RDI:8 = 0x3002:8"],
+            def!["global_write: Store at 0x2004:8 := RAX:8"],
+        ];
+        blk.term
+            .jmps
+            .push(Jmp::call("call_memcpy", "memcpy", Some("block")));
+        let mut sub = Sub::mock("func");
+        sub.term.blocks.push(blk);
+        project.program.term.subs.insert(sub.tid.clone(), sub);
+        project
+    }
+
+    #[test]
+    fn query_evaluation() {
+        let project = mock_project();
+        let analysis_results = AnalysisResults::mock_from_project(&project);
+        let index = XrefIndex::new(&project, None);
+
+        let query = Query::StringReferences("World".to_string());
+        let matches = evaluate(&analysis_results, &index, &query).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].tid, "string_ref");
+        assert_eq!(matches[0].function, "func");
+
+        let query = Query::GlobalWrites("0x2004".to_string());
+        let matches = evaluate(&analysis_results, &index, &query).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].tid, "global_write");
+        let query = Query::GlobalWrites("0x2000".to_string());
+        assert!(evaluate(&analysis_results, &index, &query)
+            .unwrap()
+            .is_empty());
+
+        let query = Query::CallSites {
+            symbol: "memcpy".to_string(),
+            non_constant_param: Some(0),
+        };
+        let matches = evaluate(&analysis_results, &index, &query).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].tid, "call_memcpy");
+        let query = Query::CallSites {
+            symbol: "memcpy".to_string(),
+            non_constant_param: Some(2),
+        };
+        assert!(evaluate(&analysis_results, &index, &query).is_err());
+        let query = Query::CallSites {
+            symbol: "strcpy".to_string(),
+            non_constant_param: None,
+        };
+        assert!(evaluate(&analysis_results, &index, &query).is_err());
+    }
+}
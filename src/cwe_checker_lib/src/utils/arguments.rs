@@ -143,6 +143,9 @@ pub fn get_variable_parameters(
 
 /// Calculates the register and stack positions of format string parameters.
 /// The parameters are then returned as an argument vector for later tainting.
+///
+/// Stack parameters are padded and aligned according to the stack parameter layout of the architecture,
+/// see [`Project::align_stack_parameter_offset`] and [`Project::get_stack_parameter_slot_size`].
 pub fn calculate_parameter_locations(
     variadic_parameters: Vec<(Datatype, ByteSize)>,
     extern_symbol: &ExternSymbol,
@@ -151,10 +154,7 @@ pub fn calculate_parameter_locations(
     let calling_convention = project.get_calling_convention(extern_symbol);
     let mut var_args: Vec<Arg> = Vec::new();
     let mut float_arg_register_count = calling_convention.float_parameter_register.len();
-    let mut stack_offset: i64 = match project.cpu_architecture.as_str() {
-        "x86" | "x86_32" | "x86_64" => u64::from(project.stack_pointer_register.size) as i64,
-        _ => 0,
-    };
+    let mut stack_offset = project.get_stack_parameter_start_offset();
     let mut integer_arg_register_count =
         if calling_convention.integer_parameter_register.len() >= extern_symbol.parameters.len() {
             calling_convention.integer_parameter_register.len() - extern_symbol.parameters.len()
@@ -162,7 +162,7 @@ pub fn calculate_parameter_locations(
             for param in extern_symbol.parameters.iter() {
                 if let Ok(offset) = param.eval_stack_offset() {
                     let offset_after = offset.try_to_u64().unwrap() + u64::from(param.bytesize());
-                    stack_offset = std::cmp::max(stack_offset, offset_after as i64);
+                    stack_offset = std::cmp::max(stack_offset, offset_after);
                 }
             }
             0
@@ -185,13 +185,14 @@ pub fn calculate_parameter_locations(
 
                     integer_arg_register_count -= 1;
                 } else {
+                    let offset = project.align_stack_parameter_offset(stack_offset, *size);
                     var_args.push(create_stack_arg(
                         *size,
-                        stack_offset,
+                        offset as i64,
                         data_type.clone(),
                         &project.stack_pointer_register,
                     ));
-                    stack_offset += u64::from(*size) as i64
+                    stack_offset = offset + project.get_stack_parameter_slot_size(*size);
                 }
             }
             Datatype::Double => {
@@ -206,13 +207,14 @@ pub fn calculate_parameter_locations(
 
                     float_arg_register_count -= 1;
                 } else {
+                    let offset = project.align_stack_parameter_offset(stack_offset, *size);
                     var_args.push(create_stack_arg(
                         *size,
-                        stack_offset,
+                        offset as i64,
                         data_type.clone(),
                         &project.stack_pointer_register,
                     ));
-                    stack_offset += u64::from(*size) as i64
+                    stack_offset = offset + project.get_stack_parameter_slot_size(*size);
                 }
             }
             _ => panic!("Invalid data type specifier from format string."),
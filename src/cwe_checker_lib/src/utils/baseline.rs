@@ -0,0 +1,331 @@
+//! Baseline files of known CWE warnings.
+//!
+//! When the cwe_checker is introduced for an existing code base,
+//! a baseline file can record all warnings of the current version of the binary,
+//! so that later runs only report warnings that are not contained in the baseline.
+//!
+//! Warnings are identified by a fingerprint that does not depend on addresses,
+//! so that it stays the same if the code is shifted by a recompilation.
+//! The fingerprint is a hash of
+//! - the name of the check,
+//! - the name of the function containing the warning,
+//! - the `Def` and `Jmp` terms of the block containing the first term referenced by the warning,
+//!   with all addresses removed,
+//! - the position of the term in its block and the number of blocks with the same terms
+//!   before the block in the function.
+//!
+//! # Limitations
+//!
+//! - Hexadecimal numbers with at least five digits are assumed to be addresses
+//!   and are ignored for the fingerprint.
+//!   Changes to such constants do not change the fingerprint.
+//! - Automatically generated function names like `FUN_00101234` are ignored,
+//!   so that warnings in different functions without a symbol name may share a fingerprint.
+//! - The fingerprint of a warning without a term in the program
+//!   is computed from its description with all addresses removed.
+
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::cache::fnv1a_hash;
+use crate::utils::log::CweWarning;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// A warning recorded in a baseline file.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct BaselineEntry {
+    /// The fingerprint of the warning.
+    pub fingerprint: u64,
+    /// The name of the check that generated the warning.
+    pub name: String,
+    /// The description of the warning at the time it was recorded.
+    /// Only meant for human readers of the baseline file.
+    pub description: String,
+}
+
+/// A set of known warnings that should not be reported again.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct Baseline {
+    /// The recorded warnings.
+    pub warnings: BTreeSet<BaselineEntry>,
+}
+
+impl Baseline {
+    /// Create a baseline containing the given warnings.
+    pub fn new(program: &Program, warnings: &[CweWarning]) -> Baseline {
+        let fingerprints = Fingerprints::new(program);
+        Baseline {
+            warnings: warnings
+                .iter()
+                .map(|warning| BaselineEntry {
+                    fingerprint: fingerprints.get(warning),
+                    name: warning.name.clone(),
+                    description: warning.description.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Load the baseline from the given file.
+    ///
+    /// Returns `None` if the file does not exist.
+    pub fn load(path: &Path) -> Result<Option<Baseline>, Error> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let baseline = serde_json::from_reader(file)
+            .with_context(|| format!("Could not parse the baseline file {}", path.display()))?;
+        Ok(Some(baseline))
+    }
+
+    /// Write the baseline to the given file.
+    pub fn store(&self, path: &Path) -> Result<(), Error> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Remove all warnings contained in the baseline from the given warnings.
+    /// Returns the number of removed warnings.
+    pub fn filter_warnings(&self, program: &Program, warnings: &mut Vec<CweWarning>) -> usize {
+        let known_fingerprints: BTreeSet<u64> = self
+            .warnings
+            .iter()
+            .map(|entry| entry.fingerprint)
+            .collect();
+        let fingerprints = Fingerprints::new(program);
+        let num_warnings = warnings.len();
+        warnings.retain(|warning| !known_fingerprints.contains(&fingerprints.get(warning)));
+        num_warnings - warnings.len()
+    }
+}
+
+/// Computation of the fingerprints of warnings for a program.
+struct Fingerprints<'a> {
+    /// The program.
+    program: &'a Program,
+    /// A map from the textual representation of the TIDs of all `Def` and `Jmp` terms of the program
+    /// to the function and the index of the block containing the term and the index of the term in the block.
+    locations: HashMap<String, (&'a Term<Sub>, usize, usize)>,
+}
+
+impl<'a> Fingerprints<'a> {
+    /// Index the terms of the given program.
+    fn new(program: &'a Program) -> Fingerprints<'a> {
+        let mut locations = HashMap::new();
+        for sub in program.subs.values() {
+            for (blk_index, blk) in sub.term.blocks.iter().enumerate() {
+                let tids = blk
+                    .term
+                    .defs
+                    .iter()
+                    .map(|def| &def.tid)
+                    .chain(blk.term.jmps.iter().map(|jmp| &jmp.tid));
+                for (term_index, tid) in tids.enumerate() {
+                    locations.insert(tid.to_string(), (sub, blk_index, term_index));
+                }
+            }
+        }
+        Fingerprints { program, locations }
+    }
+
+    /// Compute the fingerprint of the given warning.
+    fn get(&self, warning: &CweWarning) -> u64 {
+        let location = warning
+            .tids
+            .iter()
+            .find_map(|tid| self.locations.get(tid.as_str()));
+        let text = match location {
+            Some((sub, blk_index, term_index)) => {
+                let block_text = normalize_block(self.program, &sub.term.blocks[*blk_index]);
+                let num_equal_blocks = sub.term.blocks[..*blk_index]
+                    .iter()
+                    .filter(|blk| normalize_block(self.program, blk) == block_text)
+                    .count();
+                format!(
+                    "{}\n{}\n{num_equal_blocks} {term_index}\n{block_text}",
+                    warning.name,
+                    normalize_function_name(&sub.term.name),
+                )
+            }
+            None => format!(
+                "{}\n{}",
+                warning.name,
+                remove_addresses(&warning.description)
+            ),
+        };
+        fnv1a_hash(text.as_bytes())
+    }
+}
+
+/// Get a textual representation of the function without addresses.
+///
+/// The [analysis cache](crate::utils::cache) uses it to recognize functions
+/// that were only moved to another address.
+pub(crate) fn normalize_function(program: &Program, sub: &Term<Sub>) -> String {
+    let mut text = normalize_function_name(&sub.term.name);
+    text += "\n";
+    for blk in sub.term.blocks.iter() {
+        text += &normalize_block(program, blk);
+        text += "\n";
+    }
+    text
+}
+
+/// Get a textual representation of the terms of the block without addresses.
+fn normalize_block(program: &Program, blk: &Term<Blk>) -> String {
+    let mut text = String::new();
+    for def in blk.term.defs.iter() {
+        text += &remove_addresses(&def.term.to_string());
+        text += "\n";
+    }
+    for jmp in blk.term.jmps.iter() {
+        let jmp_text = match &jmp.term {
+            Jmp::Branch(_) => "BRANCH".to_string(),
+            Jmp::BranchInd(target) => format!("BRANCH {target}"),
+            Jmp::CBranch { condition, .. } => format!("IF {condition} BRANCH"),
+            Jmp::Call { target, .. } => format!("CALL {}", get_callee_name(program, target)),
+            Jmp::CallInd { target, .. } => format!("CALL {target}"),
+            Jmp::CallOther { description, .. } => format!("CALL {description}"),
+            Jmp::Return(expr) => format!("RETURN {expr}"),
+        };
+        text += &remove_addresses(&jmp_text);
+        text += "\n";
+    }
+    text
+}
+
+/// Get the normalized name of the function or extern symbol with the given TID.
+fn get_callee_name(program: &Program, target: &Tid) -> String {
+    if let Some(symbol) = program.extern_symbols.get(target) {
+        symbol.name.clone()
+    } else if let Some(sub) = program.subs.get(target) {
+        normalize_function_name(&sub.term.name)
+    } else {
+        String::new()
+    }
+}
+
+/// Remove the address from automatically generated function names like `FUN_00101234`.
+fn normalize_function_name(name: &str) -> String {
+    match name.rsplit_once('_') {
+        Some((prefix, suffix)) if suffix.len() >= 5 && is_hex(suffix) => prefix.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Replace all hexadecimal numbers with at least five digits in the text by `?`.
+fn remove_addresses(text: &str) -> String {
+    replace_addresses(text, |_| Some("?".to_string()))
+}
+
+/// Replace all hexadecimal numbers with at least five digits in the text,
+/// i.e. all numbers that may be addresses, by the result of `replace`.
+///
+/// The function `replace` gets the number including a `0x` prefix
+/// and returns `None` if the number should be kept.
+pub(crate) fn replace_addresses(text: &str, replace: impl Fn(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut word = String::new();
+    for character in text.chars().chain(std::iter::once(' ')) {
+        if character.is_ascii_alphanumeric() || character == '_' {
+            word.push(character);
+            continue;
+        }
+        let digits = word.strip_prefix("0x").unwrap_or(&word);
+        match (digits.len() >= 5 && is_hex(digits))
+            .then(|| replace(&word))
+            .flatten()
+        {
+            Some(replacement) => result += &replacement,
+            None => result += &word,
+        }
+        word.clear();
+        result.push(character);
+    }
+    result.pop();
+    result
+}
+
+/// Check whether the text consists only of hexadecimal digits.
+fn is_hex(text: &str) -> bool {
+    text.chars().all(|character| character.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::def;
+
+    /// Create a program with the functions `main` and `FUN_<address>`,
+    /// where all addresses are shifted by the given offset.
+    fn mock_program(offset: u64) -> Program {
+        let mut program = Project::mock_x64().program.term;
+        let helper_name = format!("FUN_{:08x}", 0x101000 + offset);
+        let helper = Sub::mock(&helper_name);
+        let mut blk = Blk::mock_with_tid(&format!("blk_{:x}", 0x101234 + offset));
+        blk.term.defs = vec![
+            def![format!(
+                "instr_{:x}_0: RAX:8 = 0x{:x}:8",
+                0x101234 + offset,
+                0x104000 + offset
+            )],
+            def![format!(
+                "instr_{:x}_1: RBX:8 = RAX:8 + 0x8:8",
+                0x101238 + offset
+            )],
+        ];
+        blk.term.jmps = vec![Jmp::call(
+            &format!("instr_{:x}_2", 0x10123c + offset),
+            &helper_name,
+            None,
+        )];
+        let mut main = Sub::mock("main");
+        main.term.blocks.push(blk);
+        for sub in [main, helper] {
+            program.subs.insert(sub.tid.clone(), sub);
+        }
+        program
+    }
+
+    fn mock_warning(name: &str, tid: String) -> CweWarning {
+        CweWarning::new(name, "0.1", "warning").tids(vec![tid])
+    }
+
+    #[test]
+    fn fingerprints_survive_address_shifts() {
+        let program = mock_program(0);
+        let warnings = vec![
+            mock_warning("CWE476", "instr_101238_1".to_string()),
+            mock_warning("CWE119", "instr_10123c_2".to_string()),
+        ];
+        let baseline = Baseline::new(&program, &warnings);
+        assert_eq!(baseline.warnings.len(), 2);
+
+        let shifted_program = mock_program(0x40);
+        let mut new_warnings = vec![
+            mock_warning("CWE476", "instr_101278_1".to_string()),
+            mock_warning("CWE476", "instr_101274_0".to_string()),
+            mock_warning("CWE119", "instr_10127c_2".to_string()),
+        ];
+        assert_eq!(
+            baseline.filter_warnings(&shifted_program, &mut new_warnings),
+            2
+        );
+        assert_eq!(
+            new_warnings,
+            vec![mock_warning("CWE476", "instr_101274_0".to_string())]
+        );
+    }
+
+    #[test]
+    fn normalization() {
+        assert_eq!(normalize_function_name("FUN_00101234"), "FUN");
+        assert_eq!(normalize_function_name("read_config"), "read_config");
+        assert_eq!(
+            remove_addresses("Call at 00101234 to 0x104000:8 with 0x10:8 (FUN_0010abcd)"),
+            "Call at ? to ?:8 with 0x10:8 (FUN_0010abcd)"
+        );
+    }
+}
@@ -10,10 +10,15 @@ use goblin::pe;
 /// and that the cwe_checker cannot automatically deduce from the binary itself.
 ///
 /// When handling bare metal binaries
-/// we assume that the corresponding MCU uses a very simple memory layout
+/// we assume that the corresponding MCU uses a simple memory layout
 /// consisting of exactly one region of non-volatile (flash) memory
 /// and exactly one region of volatile memory (RAM).
 /// Furthermore, we assume that the binary itself is just a dump of the non-volatile memory region.
+///
+/// The optional fields describe deviations from this layout:
+/// separate address spaces for code and data on Harvard architectures,
+/// overlays mapping parts of the binary to the same addresses as other memory,
+/// and sections that the startup code copies or decompresses from the binary to RAM.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct BareMetalConfig {
     /// The CPU type.
@@ -36,6 +41,123 @@ pub struct BareMetalConfig {
     ///
     /// If the exact size is unknown, then one can try to use an upper approximation instead.
     pub ram_size: String,
+    /// Set to `true` for chips with a Harvard architecture (e.g. AVR or 8051 microcontrollers),
+    /// which have separate address spaces for code and data.
+    /// The binary is then mapped into the code address space,
+    /// so that it is not visible to load and store instructions.
+    #[serde(default)]
+    pub harvard_architecture: bool,
+    /// Parts of the binary that are mapped to the same addresses as other memory, e.g. banked flash memory.
+    #[serde(default)]
+    pub overlays: Vec<OverlayConfig>,
+    /// Sections of the binary that the startup code copies or decompresses to their runtime address in RAM.
+    #[serde(default)]
+    pub boot_sections: Vec<BootSection>,
+}
+
+/// A part of a bare metal binary that is mapped to the same addresses as other memory.
+///
+/// Which overlay is mapped at runtime is usually switched by writing to a bank register,
+/// so reads from addresses covered by an overlay have no statically known value.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct OverlayConfig {
+    /// The name of the overlay.
+    pub name: String,
+    /// The address that the overlay is mapped to, as a hexadecimal number.
+    pub base_address: String,
+    /// The offset of the contents of the overlay in the binary, as a hexadecimal number.
+    pub file_offset: String,
+    /// The size of the overlay in bytes, as a hexadecimal number.
+    pub size: String,
+}
+
+/// A section of a bare metal binary that the startup code copies or decompresses to RAM,
+/// e.g. the initial values of the `.data` section.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct BootSection {
+    /// The offset of the (compressed) contents of the section in the binary, as a hexadecimal number.
+    pub file_offset: String,
+    /// The size of the (compressed) contents in the binary in bytes, as a hexadecimal number.
+    pub size: String,
+    /// The address that the contents are copied or decompressed to, as a hexadecimal number.
+    pub runtime_address: String,
+    /// The compression of the contents.
+    #[serde(default)]
+    pub compression: Compression,
+    /// Set to `true` if the section is not modified after boot,
+    /// e.g. for constant data copied to RAM for faster access.
+    /// The contents of the section can then be read by the analyses.
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// The compression formats of boot sections known to the cwe_checker.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    /// The contents are copied without decompression.
+    #[default]
+    None,
+    /// The contents are compressed in the LZ4 block format.
+    Lz4,
+}
+
+impl Compression {
+    /// Decompress the given bytes.
+    pub fn decompress(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Compression::None => Ok(bytes.to_vec()),
+            Compression::Lz4 => decompress_lz4_block(bytes),
+        }
+    }
+}
+
+/// Decompress data in the LZ4 block format.
+///
+/// A block is a sequence of tokens, each followed by literal bytes
+/// and a back-reference into the already decompressed data.
+/// The last token of a block contains only literals.
+fn decompress_lz4_block(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let error = || anyhow!("Invalid LZ4 block");
+    let mut output = Vec::new();
+    let mut index = 0;
+    // LZ4 encodes lengths of at least 15 as a sequence of additional bytes, ending with a byte less than 255.
+    let read_length = |index: &mut usize, mut length: usize| -> Result<usize, Error> {
+        if length == 15 {
+            loop {
+                let byte = *bytes.get(*index).ok_or_else(error)?;
+                *index += 1;
+                length += byte as usize;
+                if byte != 255 {
+                    break;
+                }
+            }
+        }
+        Ok(length)
+    };
+    while index < bytes.len() {
+        let token = bytes[index];
+        index += 1;
+        let literal_length = read_length(&mut index, (token >> 4) as usize)?;
+        let literals = bytes.get(index..index + literal_length).ok_or_else(error)?;
+        output.extend_from_slice(literals);
+        index += literal_length;
+        if index == bytes.len() {
+            break;
+        }
+        let offset_bytes = bytes.get(index..index + 2).ok_or_else(error)?;
+        let offset = u16::from_le_bytes([offset_bytes[0], offset_bytes[1]]) as usize;
+        index += 2;
+        if offset == 0 || offset > output.len() {
+            return Err(error());
+        }
+        let match_length = read_length(&mut index, (token & 0xf) as usize)? + 4;
+        // The match may overlap the bytes it produces, so it has to be copied byte by byte.
+        for _ in 0..match_length {
+            output.push(output[output.len() - offset]);
+        }
+    }
+    Ok(output)
 }
 
 impl BareMetalConfig {
@@ -0,0 +1,96 @@
+//! Helper functions for tracking the buffers that input functions write to,
+//! e.g. the buffer that `recv` writes a received message to.
+
+use crate::abstract_domain::{AbstractIdentifier, TryToBitvec, TryToInterval};
+use crate::analysis::pointer_inference::{Data, PointerInference};
+use crate::analysis::vsa_results::VsaResult;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+
+/// The parameters of an input function describing the buffer the input is written to.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BufferParameters {
+    /// The index of the parameter containing the destination buffer.
+    pub buffer: usize,
+    /// The index of the parameter containing the maximal number of bytes written to the buffer.
+    pub length: usize,
+}
+
+/// A buffer inside an abstract object.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Buffer {
+    /// The abstract object containing the buffer.
+    pub object: AbstractIdentifier,
+    /// The offset of the buffer inside the object, if known.
+    pub start: Option<i64>,
+    /// The offset of the end of the buffer inside the object, if known.
+    pub end: Option<i64>,
+}
+
+impl Buffer {
+    /// Get the buffers of the given length that the given pointer may point to.
+    ///
+    /// If the offset of the pointer is not exactly known, the buffer starts at the smallest possible offset.
+    pub fn from_pointer(pointer: &Data, length: Option<i64>) -> Vec<Buffer> {
+        pointer
+            .get_relative_values()
+            .iter()
+            .map(|(object, offset)| {
+                let start = offset
+                    .try_to_offset()
+                    .or_else(|_| offset.try_to_offset_interval().map(|(start, _)| start))
+                    .ok();
+                Buffer {
+                    object: object.clone(),
+                    start,
+                    end: start.zip(length).map(|(start, length)| start + length),
+                }
+            })
+            .collect()
+    }
+
+    /// Get the length of the buffer, if known.
+    pub fn length(&self) -> Option<i64> {
+        self.start.zip(self.end).map(|(start, end)| end - start)
+    }
+
+    /// Returns `true` if the given value may point into the buffer.
+    pub fn contains_pointer(&self, value: &Data) -> bool {
+        value.get_relative_values().iter().any(|(object, offset)| {
+            if *object != self.object {
+                return false;
+            }
+            let Ok(offset) = offset.try_to_offset() else {
+                return true;
+            };
+            self.start.map_or(true, |start| offset >= start)
+                && self.end.map_or(true, |end| offset < end)
+        })
+    }
+}
+
+/// Returns `true` if the given value may point into one of the buffers.
+pub fn points_into(buffers: &[Buffer], value: &Data) -> bool {
+    buffers.iter().any(|buffer| buffer.contains_pointer(value))
+}
+
+/// Get the buffers that input may be written to by the given call to an input function.
+pub fn get_input_buffers(
+    project: &Project,
+    pi_result: &PointerInference,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    parameters: BufferParameters,
+) -> Vec<Buffer> {
+    let Some(buffer) = project
+        .get_parameter_arg(symbol, parameters.buffer)
+        .and_then(|arg| pi_result.eval_parameter_arg_at_call(call_tid, &arg))
+    else {
+        return Vec::new();
+    };
+    let length = project
+        .get_parameter_arg(symbol, parameters.length)
+        .and_then(|arg| pi_result.eval_parameter_arg_at_call(call_tid, &arg))
+        .and_then(|length| length.try_to_offset().ok());
+    Buffer::from_pointer(&buffer, length)
+}
@@ -0,0 +1,515 @@
+//! A persistent cache of per-function analysis results for the incremental analysis of modified binaries.
+//!
+//! Each function of the program is identified by an address-independent fingerprint.
+//! It is computed from the same normalization of functions that the [baseline](crate::utils::baseline) uses,
+//! together with the control flow between the blocks of the function.
+//! The cache stores the fingerprint of each function together with the parameters from its function signature
+//! and the CWE warnings generated for it.
+//! When a modified version of the binary is analyzed,
+//! only the functions whose fingerprints changed and the functions depending on them are analyzed again.
+//! All other functions are replaced by extern symbols with the cached parameters,
+//! i.e. calls to them are handled like calls to library functions,
+//! and their warnings are taken from the cache.
+//! The term IDs and addresses of cached warnings are translated to the terms of the current binary,
+//! so that functions that were only moved to another address do not have to be analyzed again.
+//!
+//! # Limitations
+//!
+//! - Hexadecimal numbers with at least five digits are assumed to be addresses
+//!   and are ignored for the fingerprint.
+//!   Functions in which only such constants changed are not analyzed again.
+//! - A function is analyzed again if it (transitively) calls or is (transitively) called by a changed function.
+//!   Functions only affected through shared callers (e.g. by a changed return value of a sibling function)
+//!   or through global variables written by changed functions are not analyzed again.
+//! - Warnings that cannot be attributed to a function are not cached.
+//!   They are only generated for the re-analyzed part of the program.
+//! - Only the parameters of the function signatures are cached, not the accessed global variables.
+//! - Pointer Inference results are not cached.
+//!   Calls from re-analyzed functions to cached functions are handled by the Pointer Inference
+//!   like calls to extern symbols with the cached parameters.
+
+use crate::abstract_domain::{AbstractLocation, AbstractMemoryLocation};
+use crate::analysis::callgraph::get_program_callgraph;
+use crate::analysis::function_signature::FunctionSignature;
+use crate::intermediate_representation::*;
+use crate::prelude::*;
+use crate::utils::baseline::{normalize_function, replace_addresses};
+use crate::utils::log::CweWarning;
+use petgraph::visit::{Dfs, Reversed};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::Path;
+
+/// Hash the given bytes with the 64-bit FNV-1a hash function.
+///
+/// In contrast to the hashers of the standard library the result is stable
+/// across program runs and compiler versions, so that it can be stored on disk.
+pub fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Compute the address-independent fingerprint of a function.
+///
+/// The fingerprint is a hash of the normalized function (see [`normalize_function`])
+/// and of the indices of the blocks targeted by the jumps of each block.
+pub fn fingerprint_function(program: &Program, sub: &Term<Sub>) -> u64 {
+    let block_indices: HashMap<&Tid, usize> = sub
+        .term
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(index, blk)| (&blk.tid, index))
+        .collect();
+    let mut text = normalize_function(program, sub);
+    for blk in sub.term.blocks.iter() {
+        for jmp in blk.term.jmps.iter() {
+            let target = match &jmp.term {
+                Jmp::Branch(target) | Jmp::CBranch { target, .. } => Some(target),
+                Jmp::Call { return_, .. }
+                | Jmp::CallInd { return_, .. }
+                | Jmp::CallOther { return_, .. } => return_.as_ref(),
+                Jmp::BranchInd(_) | Jmp::Return(_) => None,
+            };
+            let target_index = target.and_then(|target| block_indices.get(target));
+            text += &format!("{target_index:?} ");
+        }
+        text += "\n";
+    }
+    fnv1a_hash(text.as_bytes())
+}
+
+/// The cached results for one function.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct CachedFunction {
+    /// The fingerprint of the function, see [`fingerprint_function`].
+    pub fingerprint: u64,
+    /// The TIDs of the function and of all its blocks, `Def` and `Jmp` terms in program order.
+    /// Used to translate the cached warnings to the terms of the function in a modified binary.
+    terms: Vec<Tid>,
+    /// The parameters of the function according to its function signature.
+    pub parameters: Vec<Arg>,
+    /// The CWE warnings generated for the function.
+    pub warnings: Vec<CweWarning>,
+}
+
+impl CachedFunction {
+    /// Get the cached warnings with all term IDs and addresses translated to the terms of the given function.
+    ///
+    /// The function must have the same fingerprint as the cached function,
+    /// so that both functions have the same number of terms in the same order.
+    pub fn get_relocated_warnings(&self, sub: &Term<Sub>) -> Vec<CweWarning> {
+        let terms = get_function_terms(sub);
+        let tids: HashMap<String, &Tid> = self
+            .terms
+            .iter()
+            .map(|tid| tid.to_string())
+            .zip(terms.iter().copied())
+            .collect();
+        let addresses: HashMap<&str, &str> = self
+            .terms
+            .iter()
+            .zip(terms.iter())
+            .filter(|(old_tid, _)| !old_tid.has_unknown_address())
+            .map(|(old_tid, new_tid)| (old_tid.address.as_str(), new_tid.address.as_str()))
+            .collect();
+        let address_values: HashMap<u64, u64> = addresses
+            .iter()
+            .filter_map(|(old_address, new_address)| {
+                Some((
+                    u64::from_str_radix(old_address, 16).ok()?,
+                    u64::from_str_radix(new_address, 16).ok()?,
+                ))
+            })
+            .collect();
+        let relocate_address = |address: &String| {
+            addresses
+                .get(address.as_str())
+                .map_or_else(|| address.clone(), |new_address| new_address.to_string())
+        };
+        let relocate_text = |text: &str| {
+            replace_addresses(text, |number| {
+                let digits = number.strip_prefix("0x").unwrap_or(number);
+                let new_value = address_values.get(&u64::from_str_radix(digits, 16).ok()?)?;
+                let prefix = &number[..number.len() - digits.len()];
+                Some(format!(
+                    "{prefix}{new_value:0width$x}",
+                    width = digits.len()
+                ))
+            })
+        };
+        self.warnings
+            .iter()
+            .map(|warning| {
+                let mut warning = warning.clone();
+                for tid in warning.tids.iter_mut() {
+                    if let Some(new_tid) = tids.get(tid.as_str()) {
+                        *tid = new_tid.to_string();
+                    }
+                }
+                warning.addresses = warning.addresses.iter().map(relocate_address).collect();
+                for provenance in warning.provenance.iter_mut() {
+                    provenance.address = provenance.address.as_ref().map(relocate_address);
+                }
+                warning.description = relocate_text(&warning.description);
+                for text in warning.other.iter_mut().flatten() {
+                    *text = relocate_text(text);
+                }
+                warning
+            })
+            .collect()
+    }
+}
+
+/// The per-function analysis results of a binary.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct AnalysisCache {
+    /// A hash of the analysis configuration and the executed checks.
+    /// Cached results are only reused if the configuration is the same.
+    settings_hash: u64,
+    /// The cached results for each function.
+    functions: Vec<CachedFunction>,
+}
+
+impl AnalysisCache {
+    /// Create a cache from the parameters and warnings generated for the given program.
+    ///
+    /// Each warning is attributed to the function containing the first term referenced by the warning.
+    /// Warnings that cannot be attributed to a function are not cached.
+    pub fn new(
+        program: &Program,
+        warnings: &[CweWarning],
+        parameters: &BTreeMap<Tid, Vec<Arg>>,
+        settings_hash: u64,
+    ) -> AnalysisCache {
+        let mut functions: BTreeMap<&Tid, CachedFunction> = program
+            .subs
+            .values()
+            .map(|sub| {
+                (
+                    &sub.tid,
+                    CachedFunction {
+                        fingerprint: fingerprint_function(program, sub),
+                        terms: get_function_terms(sub).into_iter().cloned().collect(),
+                        parameters: parameters.get(&sub.tid).cloned().unwrap_or_default(),
+                        warnings: Vec::new(),
+                    },
+                )
+            })
+            .collect();
+        let term_locations = get_term_to_function_map(program);
+        for warning in warnings {
+            let function = warning
+                .tids
+                .iter()
+                .find_map(|tid| term_locations.get(tid.as_str()));
+            if let Some(cached_function) = function.and_then(|tid| functions.get_mut(*tid)) {
+                cached_function.warnings.push(warning.clone());
+            }
+        }
+        AnalysisCache {
+            settings_hash,
+            functions: functions.into_values().collect(),
+        }
+    }
+
+    /// Load the cache from the given file.
+    ///
+    /// Returns `None` if the file does not exist or was created with different analysis settings.
+    pub fn load(path: &Path, settings_hash: u64) -> Result<Option<AnalysisCache>, Error> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let cache: AnalysisCache = serde_json::from_reader(file)
+            .with_context(|| format!("Could not parse the analysis cache {}", path.display()))?;
+        Ok((cache.settings_hash == settings_hash).then_some(cache))
+    }
+
+    /// Write the cache to the given file.
+    pub fn store(&self, path: &Path) -> Result<(), Error> {
+        std::fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("Could not write the analysis cache {}", path.display()))
+    }
+
+    /// Get the functions of the program whose cached results can be reused,
+    /// together with their cached results.
+    ///
+    /// These are the functions with the fingerprint of a cached function,
+    /// that do not (transitively) call a changed function
+    /// and that are not (transitively) called by a changed function.
+    /// If several functions have the same fingerprint,
+    /// they are matched to the cached functions with this fingerprint in program order.
+    pub fn get_reusable_functions(
+        &self,
+        program: &Term<Program>,
+    ) -> BTreeMap<Tid, &CachedFunction> {
+        let mut cached_functions: HashMap<u64, VecDeque<&CachedFunction>> = HashMap::new();
+        for cached in self.functions.iter() {
+            cached_functions
+                .entry(cached.fingerprint)
+                .or_default()
+                .push_back(cached);
+        }
+        let mut unchanged_functions = BTreeMap::new();
+        for sub in program.term.subs.values() {
+            let fingerprint = fingerprint_function(&program.term, sub);
+            if let Some(cached) = cached_functions
+                .get_mut(&fingerprint)
+                .and_then(VecDeque::pop_front)
+            {
+                unchanged_functions.insert(sub.tid.clone(), cached);
+            }
+        }
+        let callgraph = get_program_callgraph(program);
+        let mut affected_functions = BTreeSet::new();
+        for node in callgraph.node_indices() {
+            if unchanged_functions.contains_key(&callgraph[node]) {
+                continue;
+            }
+            let mut callees = Dfs::new(&callgraph, node);
+            while let Some(callee) = callees.next(&callgraph) {
+                affected_functions.insert(callgraph[callee].clone());
+            }
+            let reversed_callgraph = Reversed(&callgraph);
+            let mut callers = Dfs::new(reversed_callgraph, node);
+            while let Some(caller) = callers.next(reversed_callgraph) {
+                affected_functions.insert(callgraph[caller].clone());
+            }
+        }
+        unchanged_functions.retain(|tid, _| !affected_functions.contains(tid));
+        unchanged_functions
+    }
+
+    /// Get the cached warnings of the given functions,
+    /// translated to the terms of the functions in the given program.
+    pub fn get_warnings(
+        program: &Program,
+        functions: &BTreeMap<Tid, &CachedFunction>,
+    ) -> Vec<CweWarning> {
+        functions
+            .iter()
+            .filter_map(|(tid, cached)| Some(cached.get_relocated_warnings(program.subs.get(tid)?)))
+            .flatten()
+            .collect()
+    }
+}
+
+/// Compute the hash of the analysis settings, i.e. of the configuration and the names and versions of the executed checks.
+pub fn hash_settings(config: &serde_json::Value, modules: &[&crate::CweModule]) -> u64 {
+    let mut text = config.to_string();
+    for module in modules {
+        text += &format!("\n{module}");
+    }
+    text += &format!("\n{}", env!("CARGO_PKG_VERSION"));
+    fnv1a_hash(text.as_bytes())
+}
+
+/// Get the TIDs of the function and of all its blocks, `Def` and `Jmp` terms in program order.
+fn get_function_terms(sub: &Term<Sub>) -> Vec<&Tid> {
+    let mut terms = vec![&sub.tid];
+    for blk in sub.term.blocks.iter() {
+        terms.push(&blk.tid);
+        terms.extend(blk.term.defs.iter().map(|def| &def.tid));
+        terms.extend(blk.term.jmps.iter().map(|jmp| &jmp.tid));
+    }
+    terms
+}
+
+/// Generate a map from the string representations of all block, `Def` and `Jmp` TIDs of the program
+/// to the TID of the function containing them.
+fn get_term_to_function_map(program: &Program) -> HashMap<String, &Tid> {
+    let mut locations = HashMap::new();
+    for sub in program.subs.values() {
+        for tid in get_function_terms(sub) {
+            locations.insert(tid.to_string(), &sub.tid);
+        }
+    }
+    locations
+}
+
+impl Project {
+    /// Replace the given functions with extern symbols with the cached parameters,
+    /// so that calls to them are handled like calls to library functions.
+    ///
+    /// Used to exclude functions with cached analysis results from the analysis.
+    pub fn replace_functions_with_stubs(&mut self, functions: &BTreeMap<Tid, &CachedFunction>) {
+        let Some(calling_convention) = self.get_standard_calling_convention().cloned() else {
+            return;
+        };
+        let program = &mut self.program.term;
+        for (tid, cached) in functions {
+            let Some(sub) = program.subs.remove(tid) else {
+                continue;
+            };
+            program.entry_points.remove(tid);
+            let symbol = ExternSymbol {
+                tid: tid.clone(),
+                addresses: vec![tid.address.clone()],
+                name: sub.term.name,
+                calling_convention: Some(calling_convention.name.clone()),
+                parameters: cached.parameters.clone(),
+                return_values: calling_convention
+                    .integer_return_register
+                    .iter()
+                    .take(1)
+                    .map(|register| Arg::from_var(register.clone(), None))
+                    .collect(),
+                no_return: false,
+                has_var_args: false,
+            };
+            program.extern_symbols.insert(tid.clone(), symbol);
+        }
+    }
+
+    /// Get the parameters of the functions from their function signatures,
+    /// so that they can be stored in the analysis cache.
+    ///
+    /// Only register parameters and parameters on the stack are returned.
+    pub fn get_function_parameters(
+        &self,
+        signatures: &BTreeMap<Tid, FunctionSignature>,
+    ) -> BTreeMap<Tid, Vec<Arg>> {
+        signatures
+            .iter()
+            .map(|(tid, signature)| {
+                let args = signature
+                    .parameters
+                    .keys()
+                    .filter_map(|param| match param {
+                        AbstractLocation::Register(var) => Some(Arg::from_var(var.clone(), None)),
+                        AbstractLocation::Pointer(
+                            var,
+                            AbstractMemoryLocation::Location { offset, size },
+                        ) if *var == self.stack_pointer_register => Some(Arg::Stack {
+                            address: Expression::Var(var.clone()).plus_const(*offset),
+                            size: *size,
+                            data_type: None,
+                        }),
+                        _ => None,
+                    })
+                    .collect();
+                (tid.clone(), args)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::def;
+    use crate::variable;
+
+    /// Create a TID with the given address.
+    fn mock_tid(name: &str, address: u64) -> Tid {
+        let mut tid = Tid::new(format!("{name}_{address:x}"));
+        tid.address = format!("{address:08x}");
+        tid
+    }
+
+    /// Create a program with the functions `main`, `helper` and `other`,
+    /// where `main` calls `helper` and all addresses are shifted by the given offset.
+    fn mock_program(offset: u64) -> Term<Program> {
+        let mut project = Project::mock_x64();
+        let functions = [("main", Some("helper")), ("helper", None), ("other", None)];
+        for (index, (name, callee)) in functions.into_iter().enumerate() {
+            let address = 0x101000 + 0x100 * index as u64 + offset;
+            let mut blk = Blk::mock_with_tid(&format!("blk_{name}"));
+            blk.tid = mock_tid("blk", address);
+            let mut def = def![format!("RAX:8 = 0x{:x}:8", 0x104000 + offset)];
+            def.tid = mock_tid("instr", address);
+            blk.term.defs = vec![def];
+            if let Some(callee) = callee {
+                let mut call = Jmp::call("call", callee, None);
+                call.tid = mock_tid("instr", address + 4);
+                blk.term.jmps.push(call);
+            }
+            let mut sub = Sub::mock(name);
+            sub.term.blocks = vec![blk];
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        project.program
+    }
+
+    #[test]
+    fn reuse_unchanged_functions() {
+        let program = mock_program(0);
+        let warnings = vec![
+            CweWarning::new("CWE476", "0.3", "in main").tids(vec!["instr_101000".to_string()]),
+            CweWarning::new("CWE476", "0.3", "in other").tids(vec!["instr_101200".to_string()]),
+            CweWarning::new("CWE476", "0.3", "unknown").tids(vec!["unknown".to_string()]),
+        ];
+        let cache = AnalysisCache::new(&program.term, &warnings, &BTreeMap::new(), 0);
+        assert_eq!(
+            cache
+                .get_reusable_functions(&program)
+                .into_keys()
+                .collect::<Vec<_>>(),
+            vec![Tid::new("helper"), Tid::new("main"), Tid::new("other")]
+        );
+
+        let mut modified_program = program.clone();
+        let helper = modified_program
+            .term
+            .subs
+            .get_mut(&Tid::new("helper"))
+            .unwrap();
+        helper.term.blocks[0].term.defs = vec![def!["def_helper: RAX:8 = 0x1:8"]];
+        let reusable = cache.get_reusable_functions(&modified_program);
+        assert_eq!(
+            reusable.keys().collect::<Vec<_>>(),
+            vec![&Tid::new("other")]
+        );
+        let cached_warnings = AnalysisCache::get_warnings(&modified_program.term, &reusable);
+        assert_eq!(cached_warnings.len(), 1);
+        assert_eq!(cached_warnings[0].description, "in other");
+    }
+
+    #[test]
+    fn reuse_moved_functions() {
+        let program = mock_program(0);
+        let warnings = vec![CweWarning::new("CWE476", "0.3", "at 00101200 (0x104000)")
+            .addresses(vec!["00101200".to_string()])
+            .tids(vec!["instr_101200".to_string()])];
+        let cache = AnalysisCache::new(&program.term, &warnings, &BTreeMap::new(), 0);
+
+        let moved_program = mock_program(0x40);
+        let reusable = cache.get_reusable_functions(&moved_program);
+        assert_eq!(reusable.len(), 3);
+        assert_eq!(
+            AnalysisCache::get_warnings(&moved_program.term, &reusable),
+            vec![CweWarning::new("CWE476", "0.3", "at 00101240 (0x104000)")
+                .addresses(vec!["00101240".to_string()])
+                .tids(vec!["instr_101240".to_string()])]
+        );
+    }
+
+    #[test]
+    fn replace_functions_with_stubs() {
+        let mut project = Project::mock_x64();
+        project.program = mock_program(0);
+        let signatures = BTreeMap::from([(Tid::new("helper"), FunctionSignature::mock_x64())]);
+        let parameters = project.get_function_parameters(&signatures);
+        assert_eq!(
+            parameters[&Tid::new("helper")],
+            vec![
+                Arg::from_var(variable!("RDI:8"), None),
+                Arg::from_var(variable!("RSI:8"), None)
+            ]
+        );
+        let cache = AnalysisCache::new(&project.program.term, &[], &parameters, 0);
+        let reusable: BTreeMap<Tid, &CachedFunction> = cache
+            .get_reusable_functions(&project.program)
+            .into_iter()
+            .filter(|(tid, _)| *tid == Tid::new("helper"))
+            .collect();
+        project.replace_functions_with_stubs(&reusable);
+        let program = &project.program.term;
+        assert!(!program.subs.contains_key(&Tid::new("helper")));
+        let stub = &program.extern_symbols[&Tid::new("helper")];
+        assert_eq!(stub.name, "helper");
+        assert_eq!(stub.parameters, parameters[&Tid::new("helper")]);
+        assert!(program.subs.contains_key(&Tid::new("main")));
+    }
+}
@@ -0,0 +1,50 @@
+//! Cooperative cancellation of long-running analyses.
+//!
+//! A [`CancellationToken`] is shared between the analyses and the code that may cancel them,
+//! e.g. a signal handler for Ctrl-C.
+//! The fixpoint computations check the token before each node update
+//! and stop with their intermediate results once it is cancelled,
+//! see [`Computation::set_cancellation_token`](crate::analysis::fixpoint::Computation::set_cancellation_token).
+//! The CWE checks that have not started yet are skipped, see [`crate::run_modules`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A token signaling that the running analysis should stop at the next safe point.
+///
+/// Clones of the token share their state, i.e. cancelling one clone cancels all of them.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new token that is not cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancel the token and all of its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+}
@@ -0,0 +1,326 @@
+//! Import of runtime coverage data.
+//!
+//! Coverage data recorded by emulation or fuzzing harnesses tells which parts of the binary
+//! were actually executed. CWE warnings at executed program points are more likely
+//! to be reachable in practice, so they can be marked with a higher [`Confidence`].
+//!
+//! The following file formats are supported:
+//! - drcov files (text or binary basic block table), as generated by DynamoRIO
+//!   or by the drcov plugin of QEMU. Only basic blocks of the module
+//!   with the same file name as the analyzed binary are imported.
+//! - Lists of addresses, one hexadecimal address per line.
+//!   The addresses are virtual addresses as specified in the binary file.
+//!   Empty lines and lines starting with `#` are ignored.
+//!
+//! Line-based coverage formats like lcov cannot be mapped to addresses without debug information
+//! and are rejected.
+
+use super::binary::parse_hex_string_to_u64;
+use super::log::{Confidence, CweWarning};
+use crate::intermediate_representation::{Blk, Project, Term};
+use crate::prelude::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The address ranges of the binary that were executed according to imported coverage data.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct Coverage {
+    /// Maps the start address of a covered range to its (exclusive) end address.
+    ranges: BTreeMap<u64, u64>,
+}
+
+impl Coverage {
+    /// Read the coverage file at the given path and map its contents to addresses of the project.
+    ///
+    /// The `binary_name` is the file name of the analyzed binary,
+    /// which is needed to find the binary in the module table of drcov files.
+    ///
+    /// Basic blocks of the project containing a covered address are marked as covered as a whole.
+    pub fn from_file(path: &Path, binary_name: &str, project: &Project) -> Result<Coverage, Error> {
+        let content = std::fs::read(path)
+            .with_context(|| format!("Could not read coverage file {}", path.display()))?;
+        let mut coverage = if content.starts_with(b"DRCOV VERSION") {
+            Coverage::from_drcov(&content, binary_name, get_image_base(project))?
+        } else {
+            let text = std::str::from_utf8(&content).context("Unknown coverage file format")?;
+            if text
+                .lines()
+                .any(|line| line.starts_with("SF:") || line.starts_with("DA:"))
+            {
+                return Err(anyhow!(
+                    "lcov coverage files are line-based and cannot be mapped to addresses"
+                ));
+            }
+            Coverage::from_address_list(text, project.program.term.address_base_offset)?
+        };
+        coverage.extend_to_blocks(project);
+        Ok(coverage)
+    }
+
+    /// Parse a drcov file.
+    ///
+    /// The offsets of basic blocks in drcov files are relative to the base address of their module.
+    /// They are added to the given image base of the binary.
+    fn from_drcov(content: &[u8], binary_name: &str, image_base: u64) -> Result<Coverage, Error> {
+        let mut coverage = Coverage::default();
+        let mut module_ids = Vec::new();
+        let mut path_column = None;
+        let mut position = 0;
+        while position < content.len() {
+            let line_end = content[position..]
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .map_or(content.len(), |end| position + end);
+            let line = String::from_utf8_lossy(&content[position..line_end]);
+            let line = line.trim();
+            position = line_end + 1;
+            if let Some(columns) = line.strip_prefix("Columns:") {
+                path_column = columns
+                    .split(',')
+                    .position(|column| column.trim() == "path");
+            } else if let Some(bb_count) = line.strip_prefix("BB Table:") {
+                let bb_count: usize = bb_count
+                    .split_whitespace()
+                    .next()
+                    .and_then(|count| count.parse().ok())
+                    .context("Invalid basic block table header in drcov file")?;
+                let rest = content.get(position..).unwrap_or_default();
+                if rest.starts_with(b"module[") {
+                    coverage.add_drcov_text_blocks(rest, &module_ids, image_base)?;
+                } else {
+                    coverage.add_drcov_binary_blocks(rest, bb_count, &module_ids, image_base)?;
+                }
+                break;
+            } else if let (Some(path_column), Some(first_char)) = (path_column, line.chars().next())
+            {
+                if !first_char.is_ascii_digit() {
+                    continue;
+                }
+                let columns: Vec<&str> = line.splitn(path_column + 1, ',').collect();
+                let (Some(id), Some(path)) = (columns.first(), columns.get(path_column)) else {
+                    continue;
+                };
+                let module_name = Path::new(path.trim())
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string());
+                if module_name.as_deref() == Some(binary_name) {
+                    module_ids.push(id.trim().parse::<u64>()?);
+                }
+            }
+        }
+        if module_ids.is_empty() {
+            return Err(anyhow!(
+                "Module {binary_name} not found in the module table of the drcov file"
+            ));
+        }
+        Ok(coverage)
+    }
+
+    /// Add the basic blocks of a binary drcov basic block table.
+    ///
+    /// Each entry consists of a 32-bit offset, a 16-bit size and a 16-bit module ID in little endian.
+    fn add_drcov_binary_blocks(
+        &mut self,
+        table: &[u8],
+        bb_count: usize,
+        module_ids: &[u64],
+        image_base: u64,
+    ) -> Result<(), Error> {
+        if table.len() < bb_count * 8 {
+            return Err(anyhow!("Truncated basic block table in drcov file"));
+        }
+        for entry in table.chunks_exact(8).take(bb_count) {
+            let offset = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+            let size = u16::from_le_bytes([entry[4], entry[5]]);
+            let module_id = u16::from_le_bytes([entry[6], entry[7]]);
+            if module_ids.contains(&u64::from(module_id)) {
+                self.add_range(image_base + u64::from(offset), u64::from(size));
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the basic blocks of a textual drcov basic block table.
+    ///
+    /// Each line has the form `module[  ID]: 0xOFFSET, SIZE`.
+    fn add_drcov_text_blocks(
+        &mut self,
+        table: &[u8],
+        module_ids: &[u64],
+        image_base: u64,
+    ) -> Result<(), Error> {
+        let table = String::from_utf8_lossy(table);
+        for line in table.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let parse_error = || anyhow!("Invalid basic block entry in drcov file: {line}");
+            let (module, block) = line
+                .strip_prefix("module[")
+                .and_then(|line| line.split_once("]:"))
+                .ok_or_else(parse_error)?;
+            let (offset, size) = block.split_once(',').ok_or_else(parse_error)?;
+            let module_id: u64 = module.trim().parse()?;
+            if module_ids.contains(&module_id) {
+                self.add_range(
+                    image_base + parse_hex_string_to_u64(offset.trim())?,
+                    size.trim().parse()?,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a list of hexadecimal addresses.
+    ///
+    /// The given address base offset of the project is added to each address.
+    fn from_address_list(text: &str, address_base_offset: u64) -> Result<Coverage, Error> {
+        let mut coverage = Coverage::default();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let address = parse_hex_string_to_u64(line)
+                .with_context(|| format!("Invalid address in coverage file: {line}"))?;
+            coverage.add_range(address + address_base_offset, 1);
+        }
+        Ok(coverage)
+    }
+
+    /// Add the range of the given size starting at the given address.
+    ///
+    /// Overlapping and adjacent ranges are merged, so that the stored ranges are always disjoint.
+    fn add_range(&mut self, start: u64, size: u64) {
+        let mut start = start;
+        let mut end = start + size.max(1);
+        if let Some((&previous_start, &previous_end)) = self.ranges.range(..=start).next_back() {
+            if previous_end >= start {
+                start = previous_start;
+                end = end.max(previous_end);
+            }
+        }
+        let overlapping: Vec<u64> = self
+            .ranges
+            .range(start..=end)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in overlapping {
+            end = end.max(self.ranges.remove(&key).unwrap());
+        }
+        self.ranges.insert(start, end);
+    }
+
+    /// Mark all instructions of basic blocks of the project as covered
+    /// if one of the instructions of the block is covered.
+    fn extend_to_blocks(&mut self, project: &Project) {
+        let covered_blocks: Vec<Vec<u64>> = project
+            .program
+            .term
+            .subs
+            .values()
+            .flat_map(|sub| sub.term.blocks.iter())
+            .map(get_instruction_addresses)
+            .filter(|addresses| addresses.iter().any(|address| self.is_covered(*address)))
+            .collect();
+        for address in covered_blocks.into_iter().flatten() {
+            self.add_range(address, 1);
+        }
+    }
+
+    /// Returns true if the instruction at the given address was executed.
+    pub fn is_covered(&self, address: u64) -> bool {
+        self.ranges.range(..=address).any(|(_, end)| address < *end)
+    }
+
+    /// Returns true if no address is covered.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Set the confidence of all CWE warnings whose program point was executed to [`Confidence::High`].
+    ///
+    /// The program point of a warning is the first address of the warning.
+    pub fn mark_covered_warnings(&self, warnings: &mut [CweWarning]) {
+        for warning in warnings.iter_mut() {
+            if let Some(Ok(address)) = warning
+                .addresses
+                .first()
+                .map(|address| parse_hex_string_to_u64(address))
+            {
+                if self.is_covered(address) {
+                    warning.confidence = Confidence::High;
+                }
+            }
+        }
+    }
+}
+
+/// Get the lowest address of the memory segments of the binary,
+/// which corresponds to the base address that module offsets in coverage files are relative to.
+fn get_image_base(project: &Project) -> u64 {
+    project
+        .runtime_memory_image
+        .memory_segments
+        .iter()
+        .map(|segment| segment.base_address)
+        .min()
+        .unwrap_or(project.program.term.address_base_offset)
+}
+
+/// Get the addresses of the instructions contained in the block.
+fn get_instruction_addresses(block: &Term<Blk>) -> Vec<u64> {
+    let def_addresses = block.term.defs.iter().map(|def| &def.tid.address);
+    let jmp_addresses = block.term.jmps.iter().map(|jmp| &jmp.tid.address);
+    def_addresses
+        .chain(jmp_addresses)
+        .filter_map(|address| parse_hex_string_to_u64(address).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drcov_parsing() {
+        let header = "DRCOV VERSION: 2\n\
+            DRCOV FLAVOR: drcov\n\
+            Module Table: version 2, count 2\n\
+            Columns: id, base, end, entry, checksum, timestamp, path\n  \
+            0, 0x7f0000000000, 0x7f0000100000, 0x0, 0x0, 0x0, /usr/lib/libc.so.6\n  \
+            1, 0x555555554000, 0x555555560000, 0x0, 0x0, 0x0, /tmp/my, binary\n\
+            BB Table: 3 bbs\n";
+        let mut content = header.as_bytes().to_vec();
+        for (offset, size, module_id) in [(0x1000u32, 8u16, 1u16), (0x2000, 4, 0), (0x3000, 2, 1)] {
+            content.extend(offset.to_le_bytes());
+            content.extend(size.to_le_bytes());
+            content.extend(module_id.to_le_bytes());
+        }
+        let coverage = Coverage::from_drcov(&content, "my, binary", 0x100000).unwrap();
+        assert!(coverage.is_covered(0x101000));
+        assert!(coverage.is_covered(0x101007));
+        assert!(!coverage.is_covered(0x101008));
+        assert!(!coverage.is_covered(0x102000));
+        assert!(coverage.is_covered(0x103001));
+
+        let text_content = header.replace("3 bbs", "2 bbs")
+            + "module[  1]: 0x0000000000001000,   8\nmodule[  0]: 0x0000000000002000,   4\n";
+        let coverage = Coverage::from_drcov(text_content.as_bytes(), "my, binary", 0).unwrap();
+        assert!(coverage.is_covered(0x1004));
+        assert!(!coverage.is_covered(0x2000));
+
+        assert!(Coverage::from_drcov(&content, "other", 0).is_err());
+    }
+
+    #[test]
+    fn mark_warnings() {
+        let coverage = Coverage::from_address_list("# covered\n0x1000\n\n2000\n", 0x100).unwrap();
+        let mut warnings = vec![
+            CweWarning::new("CWE476", "0.1", "covered").addresses(vec!["00001100".to_string()]),
+            CweWarning::new("CWE476", "0.1", "uncovered").addresses(vec!["00001104".to_string()]),
+            CweWarning::new("CWE476", "0.1", "no address"),
+        ];
+        coverage.mark_covered_warnings(&mut warnings);
+        assert_eq!(warnings[0].confidence, Confidence::High);
+        assert_eq!(warnings[1].confidence, Confidence::Normal);
+        assert_eq!(warnings[2].confidence, Confidence::Normal);
+        assert!(Coverage::from_address_list("not an address", 0).is_err());
+    }
+}
@@ -0,0 +1,968 @@
+//! Source-level information from the DWARF debug information of ELF binaries.
+//!
+//! Binaries compiled with debug information contain the names and types
+//! of the parameters and local variables of each function
+//! and a line table that maps instruction addresses to lines in the source code.
+//! [`DebugInfo::new`] parses this information with the `gimli` crate
+//! and maps the locations of parameters and local variables onto the intermediate representation:
+//! - Register locations are mapped to the corresponding register of the project.
+//! - Stack locations relative to the frame base of a function are converted into offsets
+//!   relative to the stack pointer value at the start of the function,
+//!   i.e. into the offsets used by the Pointer Inference analysis.
+//!   This is only possible if the frame base is the canonical frame address
+//!   or, on x86, the frame pointer register after the standard function prologue.
+//!
+//! The source locations are used to annotate CWE warnings (see [`DebugInfo::add_source_info_to_warnings`])
+//! and the names of parameters and variables are used for debugging output.
+//!
+//! Location lists are only evaluated at the start address of a function,
+//! so that the locations of many variables in optimized code are unknown.
+//! Compressed debug sections and separate debug files are not supported.
+
+use super::binary::parse_hex_string_to_u64;
+use super::log::CweWarning;
+use crate::abstract_domain::{AbstractLocation, AbstractMemoryLocation};
+use crate::intermediate_representation::{Project, Variable};
+use crate::prelude::*;
+use gimli::{AttributeValue, EndianSlice, Operation, RunTimeEndian, UnitOffset};
+use goblin::elf::{header, section_header, Elf};
+use std::collections::BTreeMap;
+
+/// The reader type used for parsing the DWARF sections.
+type Reader<'a> = EndianSlice<'a, RunTimeEndian>;
+
+/// The maximal nesting depth of type definitions that is followed when computing type names.
+const MAX_TYPE_DEPTH: usize = 16;
+
+/// A position in the source code.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub struct SourceLocation {
+    /// The path of the source file as given in the debug information.
+    pub file: String,
+    /// The line number in the source file.
+    pub line: u64,
+}
+
+impl std::fmt::Display for SourceLocation {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{}:{}", self.file, self.line)
+    }
+}
+
+/// The location of a parameter or local variable at the start of its function.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum VariableLocation {
+    /// The variable is contained in a register.
+    Register(Variable),
+    /// The variable is located on the stack.
+    /// The offset is relative to the stack pointer value at the start of the function.
+    Stack(i64),
+}
+
+/// A parameter or local variable of a function.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SourceVariable {
+    /// The name of the variable in the source code.
+    pub name: String,
+    /// The C-like name of the type of the variable, e.g. `char *` or `int[16]`.
+    pub type_name: String,
+    /// The byte size of the variable if known.
+    pub size: Option<u64>,
+    /// True if the variable has a floating point type.
+    pub is_float: bool,
+    /// The location of the variable if it is known.
+    pub location: Option<VariableLocation>,
+}
+
+impl std::fmt::Display for SourceVariable {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{} {}", self.type_name, self.name)
+    }
+}
+
+/// The debug information of a function.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FunctionDebugInfo {
+    /// The name of the function in the source code.
+    pub name: String,
+    /// The start address of the function in the intermediate representation.
+    pub address: u64,
+    /// The (exclusive) end address of the function in the intermediate representation.
+    pub end_address: u64,
+    /// The position of the function declaration in the source code.
+    pub declaration: Option<SourceLocation>,
+    /// The parameters of the function in the order of their declaration.
+    pub parameters: Vec<SourceVariable>,
+    /// The local variables of the function, including variables of nested scopes.
+    pub variables: Vec<SourceVariable>,
+}
+
+impl FunctionDebugInfo {
+    /// Get the parameter of the function corresponding to the given parameter location
+    /// of a function signature.
+    ///
+    /// If no parameter with the given location exists,
+    /// parameter registers of the standard calling convention are matched with the parameters
+    /// by their position, ignoring floating point parameters.
+    /// This is necessary for unoptimized code, where the debug information contains
+    /// the locations of the copies of the parameters in the stack frame.
+    pub fn get_parameter(
+        &self,
+        location: &AbstractLocation,
+        project: &Project,
+    ) -> Option<&SourceVariable> {
+        let variable_location = match location {
+            AbstractLocation::Register(var) => VariableLocation::Register(var.clone()),
+            AbstractLocation::Pointer(var, AbstractMemoryLocation::Location { offset, .. })
+                if *var == project.stack_pointer_register =>
+            {
+                VariableLocation::Stack(*offset)
+            }
+            _ => return None,
+        };
+        if let Some(parameter) = self
+            .parameters
+            .iter()
+            .find(|param| param.location.as_ref() == Some(&variable_location))
+        {
+            return Some(parameter);
+        }
+        let VariableLocation::Register(var) = variable_location else {
+            return None;
+        };
+        let index = project
+            .get_standard_calling_convention()?
+            .integer_parameter_register
+            .iter()
+            .position(|register| *register == var)?;
+        self.parameters
+            .iter()
+            .filter(|param| !param.is_float)
+            .nth(index)
+    }
+
+    /// Get the parameter or local variable located at the given offset in the stack frame of the function.
+    ///
+    /// The offset is relative to the stack pointer value at the start of the function
+    /// and may point to the inside of the variable.
+    pub fn get_stack_variable(&self, offset: i64) -> Option<&SourceVariable> {
+        self.parameters
+            .iter()
+            .chain(self.variables.iter())
+            .find(|variable| match (&variable.location, variable.size) {
+                (Some(VariableLocation::Stack(start)), Some(size)) => {
+                    *start <= offset && offset < start.saturating_add(size as i64)
+                }
+                (Some(VariableLocation::Stack(start)), None) => *start == offset,
+                _ => false,
+            })
+    }
+}
+
+/// The source-level information contained in the debug information of a binary.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DebugInfo {
+    /// Maps the start addresses of functions to their debug information.
+    functions: BTreeMap<u64, FunctionDebugInfo>,
+    /// The line table of the binary.
+    /// Maps addresses to the source location of the instructions starting at the address.
+    /// The value is `None` for the end of an instruction sequence
+    /// or if the source location of the instructions is unknown.
+    lines: BTreeMap<u64, Option<SourceLocation>>,
+}
+
+impl DebugInfo {
+    /// Parse the DWARF debug information of the given binary.
+    ///
+    /// Returns `None` if the binary is not an ELF file or does not contain debug information.
+    /// All addresses are translated to the addresses used in the intermediate representation of the project.
+    pub fn new(binary: &[u8], project: &Project) -> Result<Option<DebugInfo>, Error> {
+        let Ok(goblin::Object::Elf(elf)) = goblin::Object::parse(binary) else {
+            return Ok(None);
+        };
+        if get_section(binary, &elf, ".debug_info")?.is_none() {
+            return Ok(None);
+        }
+        let endian = if elf.little_endian {
+            RunTimeEndian::Little
+        } else {
+            RunTimeEndian::Big
+        };
+        let dwarf = gimli::Dwarf::load(|section| -> Result<Reader, Error> {
+            let data = get_section(binary, &elf, section.name())?.unwrap_or(&[]);
+            Ok(EndianSlice::new(data, endian))
+        })?;
+        let address_size: u64 = if elf.is_64 { 8 } else { 4 };
+        let mut parser = DwarfParser {
+            dwarf: &dwarf,
+            project,
+            architecture: Architecture::new(elf.header.e_machine, address_size, project),
+            address_size,
+            debug_info: DebugInfo::default(),
+        };
+        let mut units = dwarf.units();
+        while let Some(unit_header) = units.next()? {
+            let unit = dwarf.unit(unit_header)?;
+            parser.parse_line_program(&unit)?;
+            let mut tree = unit.entries_tree(None)?;
+            parser.parse_children(&unit, tree.root()?)?;
+        }
+        Ok(Some(parser.debug_info))
+    }
+
+    /// Get the debug information of the function containing the given address.
+    pub fn get_function(&self, address: u64) -> Option<&FunctionDebugInfo> {
+        let (_, function) = self.functions.range(..=address).next_back()?;
+        if address < function.end_address {
+            Some(function)
+        } else {
+            None
+        }
+    }
+
+    /// Get the debug information of the function starting at the given address string,
+    /// e.g. the address of the TID of a function.
+    pub fn get_function_at(&self, address: &str) -> Option<&FunctionDebugInfo> {
+        let address = parse_hex_string_to_u64(address).ok()?;
+        self.functions.get(&address)
+    }
+
+    /// Get the source location of the instruction at the given address.
+    pub fn get_source_location(&self, address: u64) -> Option<&SourceLocation> {
+        let (_, location) = self.lines.range(..=address).next_back()?;
+        location.as_ref()
+    }
+
+    /// Add the source location of the first address of each CWE warning to the warning.
+    ///
+    /// The source location is added as a `source_location` entry to the `other` field of the warning.
+    /// If the source-level name of the containing function is not one of the symbols of the warning,
+    /// it is added as a `source_function` entry.
+    pub fn add_source_info_to_warnings(&self, warnings: &mut [CweWarning]) {
+        for warning in warnings.iter_mut() {
+            let Some(address) = warning
+                .addresses
+                .first()
+                .and_then(|address| parse_hex_string_to_u64(address).ok())
+            else {
+                continue;
+            };
+            if let Some(location) = self.get_source_location(address) {
+                warning
+                    .other
+                    .push(vec!["source_location".to_string(), location.to_string()]);
+            }
+            if let Some(function) = self.get_function(address) {
+                if !warning.symbols.contains(&function.name) {
+                    warning
+                        .other
+                        .push(vec!["source_function".to_string(), function.name.clone()]);
+                }
+            }
+        }
+    }
+}
+
+/// Get the contents of the ELF section with the given name.
+fn get_section<'a>(binary: &'a [u8], elf: &Elf, name: &str) -> Result<Option<&'a [u8]>, Error> {
+    let Some(section) = elf
+        .section_headers
+        .iter()
+        .find(|section| elf.shdr_strtab.get_at(section.sh_name) == Some(name))
+    else {
+        return Ok(None);
+    };
+    if section.sh_type == section_header::SHT_NOBITS {
+        return Ok(Some(&[]));
+    }
+    if section.sh_flags & u64::from(section_header::SHF_COMPRESSED) != 0 {
+        return Err(anyhow!("Compressed debug section {name} is not supported"));
+    }
+    let range = section
+        .file_range()
+        .ok_or_else(|| anyhow!("Invalid section {name}"))?;
+    Ok(Some(binary.get(range).ok_or_else(|| {
+        anyhow!("Section {name} exceeds the file size")
+    })?))
+}
+
+/// The properties of an architecture needed to interpret DWARF locations.
+struct Architecture {
+    /// Maps DWARF register numbers to the corresponding registers of the project.
+    registers: Vec<Option<Variable>>,
+    /// The offset of the canonical frame address relative to the stack pointer value at the function start.
+    cfa_offset: i64,
+    /// The DWARF register number of the frame pointer register
+    /// if its value after the standard function prologue is known.
+    frame_pointer: Option<u16>,
+}
+
+impl Architecture {
+    /// Get the properties of the architecture with the given ELF machine type.
+    ///
+    /// For unknown architectures no locations can be mapped onto the intermediate representation.
+    fn new(machine: u16, address_size: u64, project: &Project) -> Architecture {
+        let register_names: &[&str] = match machine {
+            header::EM_X86_64 => &[
+                "RAX", "RDX", "RCX", "RBX", "RSI", "RDI", "RBP", "RSP", "R8", "R9", "R10", "R11",
+                "R12", "R13", "R14", "R15",
+            ],
+            header::EM_386 => &["EAX", "ECX", "EDX", "EBX", "ESP", "EBP", "ESI", "EDI"],
+            header::EM_ARM => &[
+                "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12",
+                "sp", "lr", "pc",
+            ],
+            header::EM_AARCH64 => &[
+                "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12",
+                "x13", "x14", "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24",
+                "x25", "x26", "x27", "x28", "x29", "x30", "sp",
+            ],
+            header::EM_MIPS => &[
+                "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3", "t0", "t1", "t2", "t3", "t4",
+                "t5", "t6", "t7", "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "t8", "t9", "k0",
+                "k1", "gp", "sp", "s8", "ra",
+            ],
+            header::EM_PPC | header::EM_PPC64 => &[
+                "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12",
+                "r13", "r14", "r15", "r16", "r17", "r18", "r19", "r20", "r21", "r22", "r23", "r24",
+                "r25", "r26", "r27", "r28", "r29", "r30", "r31",
+            ],
+            header::EM_RISCV => &[
+                "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2",
+                "a3", "a4", "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9",
+                "s10", "s11", "t3", "t4", "t5", "t6",
+            ],
+            _ => &[],
+        };
+        let registers = register_names
+            .iter()
+            .map(|name| {
+                project
+                    .register_set
+                    .iter()
+                    .find(|register| register.name.eq_ignore_ascii_case(name))
+                    .cloned()
+            })
+            .collect();
+        match machine {
+            // The call instruction pushes the return address onto the stack
+            // and the function prologue pushes the frame pointer below it.
+            header::EM_X86_64 => Architecture {
+                registers,
+                cfa_offset: address_size as i64,
+                frame_pointer: Some(6),
+            },
+            header::EM_386 => Architecture {
+                registers,
+                cfa_offset: address_size as i64,
+                frame_pointer: Some(5),
+            },
+            _ => Architecture {
+                registers,
+                cfa_offset: 0,
+                frame_pointer: None,
+            },
+        }
+    }
+
+    /// Get the register of the project corresponding to the given DWARF register.
+    fn get_register(&self, register: gimli::Register) -> Option<Variable> {
+        self.registers
+            .get(usize::from(register.0))
+            .cloned()
+            .flatten()
+    }
+}
+
+/// Information about a type needed for source-level variables.
+struct TypeInfo {
+    /// The C-like name of the type.
+    name: String,
+    /// The byte size of the type if known.
+    size: Option<u64>,
+    /// True for floating point types.
+    is_float: bool,
+}
+
+impl TypeInfo {
+    /// The type info for unknown types or the `void` type.
+    fn unknown(name: &str) -> TypeInfo {
+        TypeInfo {
+            name: name.to_string(),
+            size: None,
+            is_float: false,
+        }
+    }
+}
+
+/// Helper struct for collecting the debug information of all compilation units.
+struct DwarfParser<'a> {
+    /// The DWARF sections of the binary.
+    dwarf: &'a gimli::Dwarf<Reader<'a>>,
+    /// The project that addresses and registers are mapped to.
+    project: &'a Project,
+    /// The architecture-specific properties of the binary.
+    architecture: Architecture,
+    /// The size of pointers in bytes.
+    address_size: u64,
+    /// The debug information collected so far.
+    debug_info: DebugInfo,
+}
+
+impl<'a> DwarfParser<'a> {
+    /// Translate an address in the binary to the corresponding address in the intermediate representation.
+    fn translate_address(&self, address: u64) -> u64 {
+        address.wrapping_add(self.project.program.term.address_base_offset)
+    }
+
+    /// Add the rows of the line program of the unit to the line table.
+    fn parse_line_program(&mut self, unit: &gimli::Unit<Reader<'a>>) -> Result<(), Error> {
+        let Some(program) = unit.line_program.clone() else {
+            return Ok(());
+        };
+        let mut rows = program.rows();
+        while let Some((line_header, row)) = rows.next_row()? {
+            let address = self.translate_address(row.address());
+            if row.end_sequence() {
+                self.debug_info.lines.entry(address).or_insert(None);
+                continue;
+            }
+            let location = match (row.file(line_header), row.line()) {
+                (Some(file), Some(line)) => Some(SourceLocation {
+                    file: self.get_string(unit, file.path_name())?,
+                    line: line.get(),
+                }),
+                _ => None,
+            };
+            self.debug_info.lines.insert(address, location);
+        }
+        Ok(())
+    }
+
+    /// Collect the functions defined by the children of the given node.
+    ///
+    /// Namespaces and classes are searched recursively for member functions.
+    fn parse_children(
+        &mut self,
+        unit: &gimli::Unit<Reader<'a>>,
+        node: gimli::EntriesTreeNode<Reader<'a>>,
+    ) -> Result<(), Error> {
+        let mut children = node.children();
+        while let Some(child) = children.next()? {
+            match child.entry().tag() {
+                gimli::DW_TAG_subprogram => {
+                    if let Some(function) = self.parse_function(unit, child)? {
+                        self.debug_info.functions.insert(function.address, function);
+                    }
+                }
+                gimli::DW_TAG_namespace
+                | gimli::DW_TAG_class_type
+                | gimli::DW_TAG_structure_type => self.parse_children(unit, child)?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the debug information of a function.
+    ///
+    /// Returns `None` for declarations of functions without code.
+    fn parse_function(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        node: gimli::EntriesTreeNode<Reader<'a>>,
+    ) -> Result<Option<FunctionDebugInfo>, Error> {
+        let entry = node.entry();
+        let Some(low_pc) = self.get_address(unit, entry.attr_value(gimli::DW_AT_low_pc)?)? else {
+            return Ok(None);
+        };
+        let high_pc = match entry.attr_value(gimli::DW_AT_high_pc)? {
+            Some(AttributeValue::Udata(size)) => low_pc.wrapping_add(size),
+            value => self.get_address(unit, value)?.unwrap_or(low_pc),
+        };
+        let frame_base = self.get_frame_base(unit, entry)?;
+        let mut function = FunctionDebugInfo {
+            name: self.get_function_name(unit, entry)?.unwrap_or_default(),
+            address: self.translate_address(low_pc),
+            end_address: self.translate_address(high_pc),
+            declaration: self.get_declaration(unit, entry)?,
+            parameters: Vec::new(),
+            variables: Vec::new(),
+        };
+        let mut children = node.children();
+        while let Some(child) = children.next()? {
+            match child.entry().tag() {
+                gimli::DW_TAG_formal_parameter => {
+                    let parameter = self.parse_variable(unit, child.entry(), low_pc, frame_base)?;
+                    function.parameters.push(parameter);
+                }
+                gimli::DW_TAG_variable => {
+                    let variable = self.parse_variable(unit, child.entry(), low_pc, frame_base)?;
+                    function.variables.push(variable);
+                }
+                gimli::DW_TAG_lexical_block => {
+                    self.parse_lexical_block(unit, child, low_pc, frame_base, &mut function)?
+                }
+                _ => (),
+            }
+        }
+        Ok(Some(function))
+    }
+
+    /// Add the local variables of a lexical block and its nested blocks to the function.
+    fn parse_lexical_block(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        node: gimli::EntriesTreeNode<Reader<'a>>,
+        function_start: u64,
+        frame_base: Option<i64>,
+        function: &mut FunctionDebugInfo,
+    ) -> Result<(), Error> {
+        let mut children = node.children();
+        while let Some(child) = children.next()? {
+            match child.entry().tag() {
+                gimli::DW_TAG_variable => {
+                    let variable =
+                        self.parse_variable(unit, child.entry(), function_start, frame_base)?;
+                    function.variables.push(variable);
+                }
+                gimli::DW_TAG_lexical_block => {
+                    self.parse_lexical_block(unit, child, function_start, frame_base, function)?
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a parameter or local variable of the function starting at `function_start`.
+    fn parse_variable(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        entry: &gimli::DebuggingInformationEntry<Reader<'a>>,
+        function_start: u64,
+        frame_base: Option<i64>,
+    ) -> Result<SourceVariable, Error> {
+        let type_info = match entry.attr_value(gimli::DW_AT_type)? {
+            Some(AttributeValue::UnitRef(offset)) => self.get_type(unit, offset, 0)?,
+            _ => TypeInfo::unknown("?"),
+        };
+        Ok(SourceVariable {
+            name: self.get_name(unit, entry)?.unwrap_or_default(),
+            type_name: type_info.name,
+            size: type_info.size,
+            is_float: type_info.is_float,
+            location: self.get_location(unit, entry, function_start, frame_base)?,
+        })
+    }
+
+    /// Get the location of a variable at the start of its function.
+    fn get_location(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        entry: &gimli::DebuggingInformationEntry<Reader<'a>>,
+        function_start: u64,
+        frame_base: Option<i64>,
+    ) -> Result<Option<VariableLocation>, Error> {
+        let expression = match entry.attr_value(gimli::DW_AT_location)? {
+            Some(AttributeValue::Exprloc(expression)) => expression,
+            Some(value) => {
+                let Some(mut locations) = self.dwarf.attr_locations(unit, value)? else {
+                    return Ok(None);
+                };
+                let mut expression = None;
+                while let Some(location) = locations.next()? {
+                    if location.range.begin <= function_start && function_start < location.range.end
+                    {
+                        expression = Some(location.data);
+                        break;
+                    }
+                }
+                let Some(expression) = expression else {
+                    return Ok(None);
+                };
+                expression
+            }
+            None => return Ok(None),
+        };
+        let mut operations = expression.operations(unit.encoding());
+        let operation = operations.next()?;
+        if operations.next()?.is_some() {
+            // Composite locations are not supported.
+            return Ok(None);
+        }
+        Ok(match operation {
+            Some(Operation::Register { register }) => self
+                .architecture
+                .get_register(register)
+                .map(VariableLocation::Register),
+            Some(Operation::FrameOffset { offset }) => {
+                frame_base.map(|base| VariableLocation::Stack(base + offset))
+            }
+            _ => None,
+        })
+    }
+
+    /// Get the frame base of a function as offset relative to the stack pointer value at the function start.
+    fn get_frame_base(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        entry: &gimli::DebuggingInformationEntry<Reader<'a>>,
+    ) -> Result<Option<i64>, Error> {
+        let Some(AttributeValue::Exprloc(expression)) =
+            entry.attr_value(gimli::DW_AT_frame_base)?
+        else {
+            return Ok(None);
+        };
+        let mut operations = expression.operations(unit.encoding());
+        Ok(match operations.next()? {
+            Some(Operation::CallFrameCFA) => Some(self.architecture.cfa_offset),
+            Some(Operation::Register { register })
+                if Some(register.0) == self.architecture.frame_pointer =>
+            {
+                Some(self.architecture.cfa_offset - 2 * self.address_size as i64)
+            }
+            _ => None,
+        })
+    }
+
+    /// Get the type information of the type defined at the given offset.
+    fn get_type(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        offset: UnitOffset,
+        depth: usize,
+    ) -> Result<TypeInfo, Error> {
+        if depth > MAX_TYPE_DEPTH {
+            return Ok(TypeInfo::unknown("?"));
+        }
+        let entry = unit.entry(offset)?;
+        let name = self.get_name(unit, &entry)?;
+        let size = entry
+            .attr(gimli::DW_AT_byte_size)?
+            .and_then(|attr| attr.udata_value());
+        let inner_type = match entry.attr_value(gimli::DW_AT_type)? {
+            Some(AttributeValue::UnitRef(inner_offset)) => {
+                self.get_type(unit, inner_offset, depth + 1)?
+            }
+            _ => TypeInfo::unknown("void"),
+        };
+        let type_info = match entry.tag() {
+            gimli::DW_TAG_base_type => TypeInfo {
+                name: name.unwrap_or_else(|| "?".to_string()),
+                size,
+                is_float: matches!(
+                    entry.attr_value(gimli::DW_AT_encoding)?,
+                    Some(AttributeValue::Encoding(gimli::DW_ATE_float))
+                ),
+            },
+            gimli::DW_TAG_pointer_type | gimli::DW_TAG_reference_type => TypeInfo {
+                name: if inner_type.name.ends_with('*') {
+                    format!("{}*", inner_type.name)
+                } else {
+                    format!("{} *", inner_type.name)
+                },
+                size: Some(size.unwrap_or(self.address_size)),
+                is_float: false,
+            },
+            gimli::DW_TAG_const_type => TypeInfo {
+                name: format!("const {}", inner_type.name),
+                ..inner_type
+            },
+            gimli::DW_TAG_volatile_type => TypeInfo {
+                name: format!("volatile {}", inner_type.name),
+                ..inner_type
+            },
+            gimli::DW_TAG_typedef => TypeInfo {
+                name: name.unwrap_or(inner_type.name),
+                ..inner_type
+            },
+            gimli::DW_TAG_array_type => {
+                let dimensions = self.get_array_dimensions(unit, offset)?;
+                let mut name = inner_type.name;
+                let mut computed_size = inner_type.size;
+                for dimension in dimensions.iter() {
+                    match dimension {
+                        Some(count) => name.push_str(&format!("[{count}]")),
+                        None => name.push_str("[]"),
+                    }
+                    computed_size = computed_size
+                        .zip(*dimension)
+                        .map(|(size, count)| size * count);
+                }
+                TypeInfo {
+                    name,
+                    size: size.or(computed_size),
+                    is_float: false,
+                }
+            }
+            gimli::DW_TAG_structure_type
+            | gimli::DW_TAG_union_type
+            | gimli::DW_TAG_enumeration_type => {
+                let keyword = match entry.tag() {
+                    gimli::DW_TAG_structure_type => "struct",
+                    gimli::DW_TAG_union_type => "union",
+                    _ => "enum",
+                };
+                TypeInfo {
+                    name: format!(
+                        "{keyword} {}",
+                        name.unwrap_or_else(|| "<anonymous>".to_string())
+                    ),
+                    size,
+                    is_float: false,
+                }
+            }
+            gimli::DW_TAG_subroutine_type => TypeInfo::unknown("<function>"),
+            _ => TypeInfo {
+                name: name.unwrap_or_else(|| "?".to_string()),
+                size,
+                is_float: false,
+            },
+        };
+        Ok(type_info)
+    }
+
+    /// Get the number of elements in each dimension of the array type at the given offset.
+    fn get_array_dimensions(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        offset: UnitOffset,
+    ) -> Result<Vec<Option<u64>>, Error> {
+        let mut dimensions = Vec::new();
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let root = tree.root()?;
+        let mut children = root.children();
+        while let Some(child) = children.next()? {
+            let entry = child.entry();
+            if entry.tag() != gimli::DW_TAG_subrange_type {
+                continue;
+            }
+            let count = match entry.attr(gimli::DW_AT_count)? {
+                Some(count) => count.udata_value(),
+                None => entry
+                    .attr(gimli::DW_AT_upper_bound)?
+                    .and_then(|bound| bound.udata_value())
+                    .map(|bound| bound + 1),
+            };
+            dimensions.push(count);
+        }
+        Ok(dimensions)
+    }
+
+    /// Get the name of a function.
+    ///
+    /// The names of out-of-line definitions of member functions and of concrete instances
+    /// of inline functions are taken from the referenced declaration.
+    fn get_function_name(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        entry: &gimli::DebuggingInformationEntry<Reader<'a>>,
+    ) -> Result<Option<String>, Error> {
+        if let Some(name) = self.get_name(unit, entry)? {
+            return Ok(Some(name));
+        }
+        for attribute in [gimli::DW_AT_specification, gimli::DW_AT_abstract_origin] {
+            if let Some(AttributeValue::UnitRef(offset)) = entry.attr_value(attribute)? {
+                return self.get_name(unit, &unit.entry(offset)?);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the position of the declaration of an entry in the source code.
+    fn get_declaration(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        entry: &gimli::DebuggingInformationEntry<Reader<'a>>,
+    ) -> Result<Option<SourceLocation>, Error> {
+        let (Some(file_index), Some(line)) = (
+            entry
+                .attr(gimli::DW_AT_decl_file)?
+                .and_then(|attr| attr.udata_value()),
+            entry
+                .attr(gimli::DW_AT_decl_line)?
+                .and_then(|attr| attr.udata_value()),
+        ) else {
+            return Ok(None);
+        };
+        let Some(line_program) = unit.line_program.as_ref() else {
+            return Ok(None);
+        };
+        let Some(file) = line_program.header().file(file_index) else {
+            return Ok(None);
+        };
+        Ok(Some(SourceLocation {
+            file: self.get_string(unit, file.path_name())?,
+            line,
+        }))
+    }
+
+    /// Get the name attribute of an entry.
+    fn get_name(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        entry: &gimli::DebuggingInformationEntry<Reader<'a>>,
+    ) -> Result<Option<String>, Error> {
+        match entry.attr_value(gimli::DW_AT_name)? {
+            Some(name) => Ok(Some(self.get_string(unit, name)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the string that the given attribute value refers to.
+    fn get_string(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        value: AttributeValue<Reader<'a>>,
+    ) -> Result<String, Error> {
+        Ok(self
+            .dwarf
+            .attr_string(unit, value)?
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    /// Get the address that the given attribute value refers to.
+    fn get_address(
+        &self,
+        unit: &gimli::Unit<Reader<'a>>,
+        value: Option<AttributeValue<Reader<'a>>>,
+    ) -> Result<Option<u64>, Error> {
+        match value {
+            Some(value) => Ok(self.dwarf.attr_address(unit, value)?),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variable;
+
+    fn mock_variable(
+        name: &str,
+        size: u64,
+        is_float: bool,
+        location: VariableLocation,
+    ) -> SourceVariable {
+        SourceVariable {
+            name: name.to_string(),
+            type_name: if is_float { "double" } else { "char *" }.to_string(),
+            size: Some(size),
+            is_float,
+            location: Some(location),
+        }
+    }
+
+    fn mock_debug_info() -> DebugInfo {
+        let function = FunctionDebugInfo {
+            name: "copy".to_string(),
+            address: 0x1000,
+            end_address: 0x1080,
+            declaration: Some(SourceLocation {
+                file: "main.c".to_string(),
+                line: 4,
+            }),
+            parameters: vec![
+                mock_variable("dst", 8, false, VariableLocation::Stack(-0x50)),
+                mock_variable("scale", 8, true, VariableLocation::Stack(-0x58)),
+                mock_variable(
+                    "src",
+                    8,
+                    false,
+                    VariableLocation::Register(variable!("RSI:8")),
+                ),
+            ],
+            variables: vec![mock_variable(
+                "buf",
+                32,
+                false,
+                VariableLocation::Stack(-0x38),
+            )],
+        };
+        let mut debug_info = DebugInfo::default();
+        debug_info.functions.insert(function.address, function);
+        for (address, line) in [(0x1000, Some(4)), (0x1010, Some(7)), (0x1080, None)] {
+            let location = line.map(|line| SourceLocation {
+                file: "main.c".to_string(),
+                line,
+            });
+            debug_info.lines.insert(address, location);
+        }
+        debug_info
+    }
+
+    #[test]
+    fn source_locations() {
+        let debug_info = mock_debug_info();
+        assert_eq!(debug_info.get_function(0x1040).unwrap().name, "copy");
+        assert!(debug_info.get_function(0x1080).is_none());
+        assert!(debug_info.get_function_at("00001000").is_some());
+        assert!(debug_info.get_function_at("00001010").is_none());
+        assert_eq!(
+            debug_info.get_source_location(0x1014).unwrap().to_string(),
+            "main.c:7"
+        );
+        assert!(debug_info.get_source_location(0x1084).is_none());
+
+        let mut warnings = vec![
+            CweWarning::new("CWE787", "0.1", "mock").addresses(vec!["00001014".to_string()]),
+            CweWarning::new("CWE787", "0.1", "mock")
+                .addresses(vec!["00001000".to_string()])
+                .symbols(vec!["copy".to_string()]),
+        ];
+        debug_info.add_source_info_to_warnings(&mut warnings);
+        assert_eq!(
+            warnings[0].other,
+            vec![
+                vec!["source_location".to_string(), "main.c:7".to_string()],
+                vec!["source_function".to_string(), "copy".to_string()],
+            ]
+        );
+        assert_eq!(
+            warnings[1].other,
+            vec![vec!["source_location".to_string(), "main.c:4".to_string()]]
+        );
+    }
+
+    #[test]
+    fn variables() {
+        let project = Project::mock_x64();
+        let debug_info = mock_debug_info();
+        let function = debug_info.get_function(0x1000).unwrap();
+        // Parameters are matched by location first and by their position otherwise.
+        let param = |location| {
+            function
+                .get_parameter(&location, &project)
+                .map(|p| p.name.as_str())
+        };
+        assert_eq!(
+            param(AbstractLocation::Register(variable!("RSI:8"))),
+            Some("src")
+        );
+        assert_eq!(
+            param(AbstractLocation::Register(variable!("RDI:8"))),
+            Some("dst")
+        );
+        assert_eq!(param(AbstractLocation::Register(variable!("RDX:8"))), None);
+        assert_eq!(param(AbstractLocation::Register(variable!("RAX:8"))), None);
+        assert_eq!(
+            param(AbstractLocation::Pointer(
+                variable!("RSP:8"),
+                AbstractMemoryLocation::Location {
+                    offset: -0x58,
+                    size: ByteSize::new(8)
+                }
+            )),
+            Some("scale")
+        );
+
+        assert_eq!(function.get_stack_variable(-0x30).unwrap().name, "buf");
+        assert_eq!(function.get_stack_variable(-0x50).unwrap().name, "dst");
+        assert!(function.get_stack_variable(-0x18).is_none());
+
+        assert_eq!(DebugInfo::new(b"no ELF file", &project).unwrap(), None);
+    }
+}
@@ -0,0 +1,380 @@
+//! Utility functions for obtaining the initial memory contents of a binary
+//! by loading it with QEMU user mode emulation.
+//!
+//! The binary is patched with a trap instruction at the point where the emulation should stop,
+//! i.e. at its entry point or (if constructors should be executed) at its `main` function.
+//! When the emulated process reaches the trap, QEMU writes a core dump of the process.
+//! At that point the loader has mapped the binary and its libraries, applied all relocations
+//! (including GOT entries, since lazy binding is disabled) and set up thread-local storage.
+//! The core dump is then imported like a core dump given on the command line,
+//! see [`RuntimeMemoryImage::new_from_core_dump`].
+//!
+//! The binary is executed (at least partially) on the machine running the cwe_checker,
+//! so this mode should only be used for trusted binaries or inside a sandbox.
+
+use crate::intermediate_representation::{Project, RuntimeMemoryImage};
+use crate::prelude::*;
+use crate::utils::binary::parse_hex_string_to_u64;
+use crate::utils::ghidra::get_tmp_folder;
+use goblin::elf::{self, Elf};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Settings for loading a binary with QEMU user mode emulation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmulationSettings {
+    /// If set, the emulation stops at the `main` function of the binary
+    /// instead of its entry point, so that constructors of the binary and its libraries are executed.
+    pub run_constructors: bool,
+    /// The directory containing the libraries and the dynamic loader for the architecture of the binary.
+    /// Needed for dynamically linked binaries of architectures other than the host architecture.
+    pub sysroot: Option<PathBuf>,
+    /// The maximal time the emulation may take until the core dump is written.
+    pub timeout: Duration,
+}
+
+impl Default for EmulationSettings {
+    fn default() -> Self {
+        EmulationSettings {
+            run_constructors: false,
+            sysroot: None,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// The location where the emulation is stopped by a trap instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TrapLocation {
+    /// The virtual address of the trap instruction.
+    address: u64,
+    /// The original bytes of the binary overwritten by the trap instruction.
+    original_bytes: Vec<u8>,
+}
+
+/// Generate the runtime memory image of the given ELF binary by loading it with QEMU user mode emulation.
+///
+/// The QEMU user mode emulator for the architecture of the binary (e.g. `qemu-x86_64`)
+/// has to be contained in the `PATH`.
+/// The trap instruction used to stop the emulation is removed from the returned memory image.
+pub fn emulate_loader(
+    binary_path: &Path,
+    binary: &[u8],
+    project: &Project,
+    settings: &EmulationSettings,
+) -> Result<RuntimeMemoryImage, Error> {
+    let elf_file = Elf::parse(binary)?;
+    if !matches!(
+        elf_file.header.e_type,
+        elf::header::ET_EXEC | elf::header::ET_DYN
+    ) {
+        return Err(anyhow!(
+            "Loader emulation is only supported for executable ELF files."
+        ));
+    }
+    let is_little_endian = elf_file.header.endianness()?.is_little();
+    let qemu_name = get_qemu_name(elf_file.header.e_machine, elf_file.is_64, is_little_endian)?;
+    let (address, is_thumb) = if settings.run_constructors {
+        get_main_address(project, &elf_file)?
+    } else if elf_file.header.e_machine == elf::header::EM_ARM {
+        (elf_file.entry & !1, elf_file.entry & 1 == 1)
+    } else {
+        (elf_file.entry, false)
+    };
+    let trap = get_trap_instruction(elf_file.header.e_machine, is_thumb, is_little_endian)?;
+
+    let mut patched_binary = binary.to_vec();
+    let trap_location = patch_binary(&elf_file, &mut patched_binary, address, &trap)?;
+
+    let work_dir = get_tmp_folder()?.join(format!(
+        "emulation_{:?}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    ));
+    std::fs::create_dir(&work_dir).context("Unable to create folder for the emulation")?;
+    let core_dump = run_until_trap(
+        binary_path,
+        &patched_binary,
+        &work_dir,
+        &qemu_name,
+        settings,
+    );
+    std::fs::remove_dir_all(&work_dir).context("Could not clean up folder of the emulation")?;
+
+    let mut memory_image = RuntimeMemoryImage::new_from_core_dump(binary, &core_dump?)?;
+    restore_original_bytes(&mut memory_image, &trap_location);
+    Ok(memory_image)
+}
+
+/// Get the name of the QEMU user mode emulator for the given ELF machine type.
+fn get_qemu_name(machine: u16, is_64: bool, is_little_endian: bool) -> Result<String, Error> {
+    let name = match (machine, is_64, is_little_endian) {
+        (elf::header::EM_386, _, _) => "i386",
+        (elf::header::EM_X86_64, _, _) => "x86_64",
+        (elf::header::EM_ARM, _, true) => "arm",
+        (elf::header::EM_ARM, _, false) => "armeb",
+        (elf::header::EM_AARCH64, _, true) => "aarch64",
+        (elf::header::EM_AARCH64, _, false) => "aarch64_be",
+        (elf::header::EM_MIPS, false, true) => "mipsel",
+        (elf::header::EM_MIPS, false, false) => "mips",
+        (elf::header::EM_MIPS, true, true) => "mips64el",
+        (elf::header::EM_MIPS, true, false) => "mips64",
+        (elf::header::EM_PPC, _, _) => "ppc",
+        (elf::header::EM_PPC64, _, true) => "ppc64le",
+        (elf::header::EM_PPC64, _, false) => "ppc64",
+        (elf::header::EM_RISCV, false, _) => "riscv32",
+        (elf::header::EM_RISCV, true, _) => "riscv64",
+        _ => {
+            return Err(anyhow!(
+                "Loader emulation is not supported for the architecture of the binary."
+            ))
+        }
+    };
+    Ok(format!("qemu-{name}"))
+}
+
+/// Get the bytes of an instruction that raises a signal when executed.
+fn get_trap_instruction(
+    machine: u16,
+    is_thumb: bool,
+    is_little_endian: bool,
+) -> Result<Vec<u8>, Error> {
+    let to_bytes = |instruction: u32| {
+        if is_little_endian {
+            instruction.to_le_bytes().to_vec()
+        } else {
+            instruction.to_be_bytes().to_vec()
+        }
+    };
+    let trap = match machine {
+        // ud2
+        elf::header::EM_386 | elf::header::EM_X86_64 => vec![0x0f, 0x0b],
+        // udf #0 (Thumb)
+        elf::header::EM_ARM if is_thumb => {
+            if is_little_endian {
+                0xde00u16.to_le_bytes().to_vec()
+            } else {
+                0xde00u16.to_be_bytes().to_vec()
+            }
+        }
+        // udf #0
+        elf::header::EM_ARM => to_bytes(0xe7f000f0),
+        // udf #0 (AArch64 instructions are always little-endian)
+        elf::header::EM_AARCH64 => 0u32.to_le_bytes().to_vec(),
+        // break
+        elf::header::EM_MIPS => to_bytes(0x0000000d),
+        // trap
+        elf::header::EM_PPC | elf::header::EM_PPC64 => to_bytes(0x7fe00008),
+        // unimp
+        elf::header::EM_RISCV => vec![0; 4],
+        _ => {
+            return Err(anyhow!(
+                "Loader emulation is not supported for the architecture of the binary."
+            ))
+        }
+    };
+    Ok(trap)
+}
+
+/// Get the virtual address of the `main` function of the binary
+/// and whether it is a Thumb function (for ARM binaries).
+///
+/// The address of `main` is taken from the project, since Ghidra also finds `main` in stripped binaries.
+/// Whether `main` is a Thumb function is determined from the symbol table of the binary, if available.
+fn get_main_address(project: &Project, elf_file: &Elf) -> Result<(u64, bool), Error> {
+    let main = project
+        .program
+        .term
+        .subs
+        .values()
+        .find(|sub| sub.term.name == "main")
+        .ok_or_else(|| anyhow!("No main function found to stop the loader emulation at."))?;
+    let address = parse_hex_string_to_u64(&main.tid.address)?
+        .wrapping_sub(project.program.term.address_base_offset);
+    let is_thumb = elf_file.header.e_machine == elf::header::EM_ARM
+        && elf_file
+            .syms
+            .iter()
+            .chain(elf_file.dynsyms.iter())
+            .any(|sym| sym.st_value == address | 1 && sym.is_function());
+    Ok((address, is_thumb))
+}
+
+/// Overwrite the bytes of the binary at the given virtual address with the given trap instruction.
+///
+/// Returns the location of the trap together with the overwritten bytes.
+fn patch_binary(
+    elf_file: &Elf,
+    binary: &mut [u8],
+    address: u64,
+    trap: &[u8],
+) -> Result<TrapLocation, Error> {
+    let file_offset = elf_file
+        .program_headers
+        .iter()
+        .find(|header| {
+            header.p_type == elf::program_header::PT_LOAD
+                && header.p_vaddr <= address
+                && address + trap.len() as u64 <= header.p_vaddr + header.p_filesz
+        })
+        .map(|header| (header.p_offset + address - header.p_vaddr) as usize)
+        .ok_or_else(|| {
+            anyhow!(
+                "Address {:#x} is not contained in the binary file.",
+                address
+            )
+        })?;
+    let bytes = &mut binary[file_offset..file_offset + trap.len()];
+    let original_bytes = bytes.to_vec();
+    bytes.copy_from_slice(trap);
+    Ok(TrapLocation {
+        address,
+        original_bytes,
+    })
+}
+
+/// Execute the patched binary with QEMU inside the given directory until it reaches the trap instruction
+/// and return the core dump written by QEMU.
+///
+/// The original file name of the binary is kept, since it may be used by the binary itself.
+fn run_until_trap(
+    binary_path: &Path,
+    patched_binary: &[u8],
+    work_dir: &Path,
+    qemu_name: &str,
+    settings: &EmulationSettings,
+) -> Result<Vec<u8>, Error> {
+    let file_name = binary_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid file name"))?;
+    let patched_path = work_dir.join(file_name);
+    std::fs::write(&patched_path, patched_binary)
+        .context("Could not write patched binary for the emulation")?;
+    std::fs::set_permissions(&patched_path, std::fs::Permissions::from_mode(0o700))?;
+
+    // QEMU only writes core dumps if the core file size limit of its process allows it.
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg("ulimit -c unlimited && exec \"$@\"")
+        .arg("sh")
+        .arg(qemu_name);
+    if let Some(sysroot) = &settings.sysroot {
+        command.arg("-L").arg(sysroot);
+    }
+    command
+        .arg("-E") // Resolve all GOT entries when loading instead of on the first call
+        .arg("LD_BIND_NOW=1")
+        .arg(&patched_path)
+        .current_dir(work_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let mut child = command
+        .spawn()
+        .context(format!("Could not execute {qemu_name}"))?;
+
+    let start = Instant::now();
+    while child.try_wait()?.is_none() {
+        if start.elapsed() > settings.timeout {
+            child.kill()?;
+            child.wait()?;
+            return Err(anyhow!(
+                "The emulation did not reach the trap instruction before the timeout."
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    // QEMU names core dumps `qemu_<program>_<timestamp>_<pid>.core`.
+    for entry in std::fs::read_dir(work_dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "core")
+        {
+            return std::fs::read(path).context("Could not read core dump written by QEMU");
+        }
+    }
+    Err(anyhow!(
+        "QEMU did not write a core dump. The binary may have exited before reaching the trap instruction."
+    ))
+}
+
+/// Restore the original bytes of the binary overwritten by the trap instruction in the memory image.
+fn restore_original_bytes(memory_image: &mut RuntimeMemoryImage, trap_location: &TrapLocation) {
+    let end_address = trap_location.address + trap_location.original_bytes.len() as u64;
+    for segment in memory_image.memory_segments.iter_mut() {
+        if segment.base_address <= trap_location.address
+            && end_address <= segment.base_address + segment.bytes.len() as u64
+        {
+            let offset = (trap_location.address - segment.base_address) as usize;
+            segment.bytes[offset..offset + trap_location.original_bytes.len()]
+                .copy_from_slice(&trap_location.original_bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::binary::MemorySegment;
+
+    #[test]
+    fn qemu_names() {
+        assert_eq!(
+            get_qemu_name(elf::header::EM_X86_64, true, true).unwrap(),
+            "qemu-x86_64"
+        );
+        assert_eq!(
+            get_qemu_name(elf::header::EM_MIPS, false, true).unwrap(),
+            "qemu-mipsel"
+        );
+        assert_eq!(
+            get_qemu_name(elf::header::EM_PPC64, true, false).unwrap(),
+            "qemu-ppc64"
+        );
+        assert!(get_qemu_name(elf::header::EM_SPARC, false, false).is_err());
+    }
+
+    #[test]
+    fn trap_instructions() {
+        assert_eq!(
+            get_trap_instruction(elf::header::EM_ARM, false, true).unwrap(),
+            vec![0xf0, 0x00, 0xf0, 0xe7]
+        );
+        assert_eq!(
+            get_trap_instruction(elf::header::EM_ARM, true, true).unwrap(),
+            vec![0x00, 0xde]
+        );
+        assert_eq!(
+            get_trap_instruction(elf::header::EM_MIPS, false, false).unwrap(),
+            vec![0x00, 0x00, 0x00, 0x0d]
+        );
+    }
+
+    #[test]
+    fn restore_trapped_bytes() {
+        let mut memory_image = RuntimeMemoryImage::empty(true);
+        memory_image.memory_segments = vec![MemorySegment {
+            bytes: vec![0x0f, 0x0b, 0x90, 0x90],
+            base_address: 0x1000,
+            read_flag: true,
+            write_flag: false,
+            execute_flag: true,
+        }];
+        let trap_location = TrapLocation {
+            address: 0x1000,
+            original_bytes: vec![0x55, 0x48],
+        };
+        restore_original_bytes(&mut memory_image, &trap_location);
+        assert_eq!(
+            memory_image.memory_segments[0].bytes,
+            vec![0x55, 0x48, 0x90, 0x90]
+        );
+    }
+}
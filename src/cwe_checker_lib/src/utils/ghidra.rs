@@ -1,8 +1,9 @@
 //! Utility functions for executing Ghidra and extracting P-Code from the output.
 
+use crate::pipeline::PipelineError;
 use crate::prelude::*;
 use crate::utils::binary::BareMetalConfig;
-use crate::utils::{get_ghidra_plugin_path, read_config_file};
+use crate::utils::{get_ghidra_plugin_path, pcode_cache, read_config_file, recording};
 use crate::{
     intermediate_representation::{Project, RuntimeMemoryImage},
     utils::debug,
@@ -17,19 +18,90 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::thread;
 
+/// A program in an existing Ghidra project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhidraProgram {
+    /// The path to the Ghidra project.
+    /// Either the path to the `.gpr` file of the project
+    /// or the path of the project directory without the `.gpr` extension.
+    pub project_path: PathBuf,
+    /// The name of the program inside the Ghidra project.
+    pub program_name: String,
+}
+
+impl GhidraProgram {
+    /// Return the directory containing the Ghidra project and the name of the project.
+    pub fn get_project_location_and_name(&self) -> Result<(PathBuf, String), Error> {
+        let project_name = self
+            .project_path
+            .file_stem()
+            .ok_or_else(|| anyhow!("Invalid Ghidra project path"))?
+            .to_string_lossy()
+            .to_string();
+        let project_location = match self.project_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        Ok((project_location, project_name))
+    }
+}
+
+/// Settings for the export of the P-Code of a binary by the `p_code_extractor` plugin.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ExportSettings {
+    /// If set, the program is exported from the given Ghidra project.
+    /// The program is opened read-only and is not analyzed again,
+    /// so that manual fixes of the analysts in the project are preserved.
+    pub ghidra_program: Option<GhidraProgram>,
+    /// If not empty, only the code of functions with the given names
+    /// or entry addresses (in hexadecimal with a `0x` prefix) is exported.
+    /// All other functions of the binary are exported as extern symbols,
+    /// i.e. calls to them are handled like calls to library functions.
+    pub function_filter: Vec<String>,
+    /// If set, the P-Code received from Ghidra is saved as a recording to the given directory,
+    /// so that the analysis can later be replayed without Ghidra.
+    /// See the [`recording`](crate::utils::recording) module for the format of recordings.
+    pub recording: Option<PathBuf>,
+    /// If set, the P-Code received from Ghidra is cached in the given directory
+    /// and reused by later exports of the same binary with the same settings.
+    /// The cache is not used for exports from existing Ghidra projects,
+    /// since changes to the project cannot be detected.
+    /// See the [`pcode_cache`](crate::utils::pcode_cache) module for the format of the cache.
+    pub pcode_cache: Option<PathBuf>,
+}
+
 /// Execute the `p_code_extractor` plugin in Ghidra and parse its output into the `Project` data structure.
 ///
 /// Return an error if the creation of the project failed.
+/// Functions that could not be translated are contained in the project as functions without code.
+/// The corresponding errors are returned alongside the project.
 pub fn get_project_from_ghidra(
     file_path: &Path,
     binary: &[u8],
     bare_metal_config_opt: Option<BareMetalConfig>,
+    export_settings: &ExportSettings,
     debug_settings: &debug::Settings,
-) -> Result<(Project, Vec<LogMessage>), Error> {
-    let pcode_project = if let Some(saved_pcode_raw) = debug_settings.get_saved_pcode_raw() {
-        let file = std::fs::File::open(saved_pcode_raw)
-            .expect("Failed to open saved output of Pcode Extractor plugin.");
-        serde_json::from_reader(std::io::BufReader::new(file))?
+) -> Result<(Project, Vec<LogMessage>, Vec<PipelineError>), Error> {
+    let cache_directory_and_key = export_settings
+        .pcode_cache
+        .as_ref()
+        .filter(|_| export_settings.ghidra_program.is_none())
+        .map(|directory| {
+            let key = pcode_cache::get_cache_key(
+                binary,
+                bare_metal_config_opt.as_ref(),
+                &export_settings.function_filter,
+            );
+            (directory, key)
+        });
+    let cached_pcode_json = cache_directory_and_key
+        .as_ref()
+        .and_then(|(directory, key)| pcode_cache::load_cached_pcode(directory, key));
+    let pcode_json = if let Some(saved_pcode_raw) = debug_settings.get_saved_pcode_raw() {
+        std::fs::read_to_string(saved_pcode_raw)
+            .context("Failed to open saved output of Pcode Extractor plugin.")?
+    } else if let Some(cached_pcode_json) = cached_pcode_json {
+        cached_pcode_json
     } else {
         let tmp_folder = get_tmp_folder()?;
         // We add a timestamp suffix to file names
@@ -49,53 +121,82 @@ pub fn get_project_from_ghidra(
             &fifo_path,
             &timestamp_suffix,
             &bare_metal_config_opt,
+            export_settings,
         )?;
-        execute_ghidra(ghidra_command, &fifo_path, debug_settings)?
+        let pcode_json = execute_ghidra(ghidra_command, &fifo_path, debug_settings)?;
+        if let Some((directory, key)) = &cache_directory_and_key {
+            pcode_cache::store_pcode(directory, key, &pcode_json)
+                .context("Could not add the Ghidra export to the P-Code cache")?;
+        }
+        pcode_json
     };
 
+    if let Some(recording_directory) = &export_settings.recording {
+        let binary_name = file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        recording::save_recording(
+            recording_directory,
+            &binary_name,
+            binary,
+            &pcode_json,
+            bare_metal_config_opt.as_ref(),
+        )
+        .context("Could not save the recording of the Ghidra export")?;
+    }
+    let pcode_project = serde_json::from_str(&pcode_json)?;
+
     parse_pcode_project_to_ir_project(pcode_project, binary, &bare_metal_config_opt)
 }
 
 /// Normalize the given P-Code project
 /// and then parse it into a project struct of the internally used intermediate representation.
+///
+/// Errors for functions that could not be normalized or translated are returned alongside the project.
+/// They are also added to the returned log messages.
 pub fn parse_pcode_project_to_ir_project(
     mut pcode_project: crate::pcode::Project,
     binary: &[u8],
     bare_metal_config_opt: &Option<BareMetalConfig>,
-) -> Result<(Project, Vec<LogMessage>), Error> {
+) -> Result<(Project, Vec<LogMessage>, Vec<PipelineError>), Error> {
     let bare_metal_base_address_opt = bare_metal_config_opt
         .as_ref()
         .map(|config| config.parse_binary_base_address());
-    let mut log_messages = pcode_project.normalize();
-    let project: Project = match RuntimeMemoryImage::get_base_address(binary) {
+    let mut log_messages = Vec::new();
+    let mut errors = pcode_project.normalize();
+    let (project, mut lifting_errors) = match RuntimeMemoryImage::get_base_address(binary) {
         Ok(binary_base_address) => pcode_project.into_ir_project(binary_base_address),
         Err(_err) => {
             if let Some(binary_base_address) = bare_metal_base_address_opt {
-                let mut project = pcode_project.into_ir_project(binary_base_address);
+                let (mut project, lifting_errors) =
+                    pcode_project.into_ir_project(binary_base_address);
                 project.program.term.address_base_offset = 0;
-                project
+                (project, lifting_errors)
             } else {
                 log_messages.push(LogMessage::new_info("Could not determine binary base address. Using base address of Ghidra output as fallback."));
-                let mut project = pcode_project.into_ir_project(0);
+                let (mut project, lifting_errors) = pcode_project.into_ir_project(0);
                 // For PE files setting the address_base_offset to zero is a hack, which worked for the tested PE files.
                 // But this hack will probably not work in general!
                 project.program.term.address_base_offset = 0;
-                project
+                (project, lifting_errors)
             }
         }
     };
+    errors.append(&mut lifting_errors);
+    log_messages.extend(errors.iter().map(PipelineError::to_log_message));
 
-    Ok((project, log_messages))
+    Ok((project, log_messages, errors))
 }
 
-/// Execute Ghidra with the P-Code plugin and return the parsed P-Code project.
+/// Execute Ghidra with the P-Code plugin and return the P-Code project as JSON.
 ///
 /// Note that this function will abort the program is the Ghidra execution does not succeed.
 fn execute_ghidra(
     mut ghidra_command: Command,
     fifo_path: &PathBuf,
     debug_settings: &debug::Settings,
-) -> Result<crate::pcode::Project, Error> {
+) -> Result<String, Error> {
     let should_print_ghidra_error = debug_settings.verbose();
     // Create a new fifo and give read and write rights to the owner
     unistd::mkfifo(fifo_path, stat::Mode::from_bits(0o600).unwrap())
@@ -136,22 +237,26 @@ fn execute_ghidra(
     file.read_to_string(&mut buf)
         .expect("Error while reading from FIFO.");
     debug_settings.print(&buf, debug::Stage::Pcode(debug::PcodeForm::Raw));
-    let pcode_parsing_result = serde_json::from_str(&buf);
 
     ghidra_subprocess
         .join()
         .expect("The Ghidra thread to be joined has panicked!");
-    // Clean up the FIFO pipe and propagate errors from the JSON parsing.
+    // Clean up the FIFO pipe.
     std::fs::remove_file(fifo_path).context("Could not clean up FIFO pipe")?;
-    Ok(pcode_parsing_result?)
+    Ok(buf)
 }
 
 /// Generate the command that is used to call Ghidra and execute the P-Code-Extractor plugin in it.
+///
+/// If the export settings contain a Ghidra project, the program is opened from this project
+/// without running the auto-analysis of Ghidra again.
+/// Otherwise the binary is imported into a temporary project that is deleted afterwards.
 fn generate_ghidra_call_command(
     file_path: &Path,
     fifo_path: &Path,
     timestamp_suffix: &str,
     bare_metal_config_opt: &Option<BareMetalConfig>,
+    export_settings: &ExportSettings,
 ) -> Result<Command, Error> {
     let ghidra_path: std::path::PathBuf =
         serde_json::from_value(read_config_file("ghidra.json")?["ghidra_path"].clone())
@@ -166,6 +271,23 @@ fn generate_ghidra_call_command(
     let ghidra_plugin_path = get_ghidra_plugin_path("p_code_extractor");
 
     let mut ghidra_command = Command::new(headless_path);
+    if let Some(ghidra_program) = &export_settings.ghidra_program {
+        let (project_location, project_name) = ghidra_program.get_project_location_and_name()?;
+        ghidra_command
+            .arg(project_location) // The folder containing the existing Ghidra project
+            .arg(project_name) // The name of the existing Ghidra project
+            .arg("-process") // Process a program that is already contained in the project
+            .arg(&ghidra_program.program_name) // The name of the program in the project
+            .arg("-noanalysis") // Do not run the standard analysis again
+            .arg("-readOnly") // Do not save any changes to the project
+            .arg("-postScript") // Execute a script after the program was opened
+            .arg(ghidra_plugin_path.join("PcodeExtractor.java")) // Path to the PcodeExtractor.java
+            .arg(fifo_path) // The path to the named pipe (fifo)
+            .args(&export_settings.function_filter) // The functions to export
+            .arg("-scriptPath") // Add a folder containing additional script files to the Ghidra script file search paths
+            .arg(ghidra_plugin_path); // Path to the folder containing the PcodeExtractor.java (so that the other java files can be found.)
+        return Ok(ghidra_command);
+    }
     ghidra_command
         .arg(&tmp_folder) // The folder where temporary files should be stored
         .arg(format!("PcodeExtractor_{filename}_{timestamp_suffix}")) // The name of the temporary Ghidra Project.
@@ -174,6 +296,7 @@ fn generate_ghidra_call_command(
         .arg("-postScript") // Execute a script after standard analysis by Ghidra finished
         .arg(ghidra_plugin_path.join("PcodeExtractor.java")) // Path to the PcodeExtractor.java
         .arg(fifo_path) // The path to the named pipe (fifo)
+        .args(&export_settings.function_filter) // The functions to export
         .arg("-scriptPath") // Add a folder containing additional script files to the Ghidra script file search paths
         .arg(ghidra_plugin_path) // Path to the folder containing the PcodeExtractor.java (so that the other java files can be found.)
         .arg("-deleteProject") // Delete the temporary project after the script finished
@@ -197,7 +320,7 @@ fn generate_ghidra_call_command(
 }
 
 /// Get the folder where temporary files should be stored for the program.
-fn get_tmp_folder() -> Result<PathBuf, Error> {
+pub(crate) fn get_tmp_folder() -> Result<PathBuf, Error> {
     let project_dirs = ProjectDirs::from("", "", "cwe_checker")
         .context("Could not determine path for temporary files")?;
     let tmp_folder = if let Some(folder) = project_dirs.runtime_dir() {
@@ -210,3 +333,28 @@ fn get_tmp_folder() -> Result<PathBuf, Error> {
     }
     Ok(tmp_folder.to_path_buf())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghidra_project_location_and_name() {
+        let program = GhidraProgram {
+            project_path: PathBuf::from("/home/user/projects/Firmware.gpr"),
+            program_name: "httpd".to_string(),
+        };
+        assert_eq!(
+            program.get_project_location_and_name().unwrap(),
+            (PathBuf::from("/home/user/projects"), "Firmware".to_string())
+        );
+        let program = GhidraProgram {
+            project_path: PathBuf::from("Firmware"),
+            program_name: "httpd".to_string(),
+        };
+        assert_eq!(
+            program.get_project_location_and_name().unwrap(),
+            (PathBuf::from("."), "Firmware".to_string())
+        );
+    }
+}
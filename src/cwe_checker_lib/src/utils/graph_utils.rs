@@ -5,7 +5,7 @@ use crate::intermediate_representation::Jmp;
 use crate::prelude::*;
 use petgraph::graph::NodeIndex;
 use petgraph::visit::EdgeRef;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 /// Check whether a call to the `sink_symbol` is reachable from the given `source_node`
 /// through a path of intraprocedural edges in the control flow graph.
@@ -59,3 +59,27 @@ pub fn is_sink_call_reachable_from_source_call(
     }
     None
 }
+
+/// Get the TID of the block of the same function that control flow reaches after the jump, if known.
+///
+/// For calls this is the return site of the call.
+pub fn get_successor_block(jmp: &Term<Jmp>) -> Option<&Tid> {
+    match &jmp.term {
+        Jmp::Branch(target) | Jmp::CBranch { target, .. } => Some(target),
+        Jmp::Call { return_, .. }
+        | Jmp::CallInd { return_, .. }
+        | Jmp::CallOther { return_, .. } => return_.as_ref(),
+        Jmp::BranchInd(_) | Jmp::Return(_) => None,
+    }
+}
+
+/// Map the TIDs of calls to extern symbols to the corresponding nodes of the control flow graph.
+pub fn get_extern_call_nodes(graph: &Graph) -> HashMap<Tid, NodeIndex> {
+    graph
+        .edge_references()
+        .filter_map(|edge| match edge.weight() {
+            Edge::ExternCallStub(jmp) => Some((jmp.tid.clone(), edge.source())),
+            _ => None,
+        })
+        .collect()
+}
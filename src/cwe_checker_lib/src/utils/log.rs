@@ -1,8 +1,15 @@
 //! Structs and functions for generating log messages and CWE warnings.
 
+use super::metrics::CheckMetrics;
+use crate::intermediate_representation::Provenance;
+use crate::pipeline::PipelineError;
 use crate::prelude::*;
-use std::{collections::BTreeMap, thread::JoinHandle};
-use std::time::SystemTime;
+use crate::CweModule;
+use std::time::{Duration, Instant, SystemTime};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    thread::JoinHandle,
+};
 
 /// A CWE warning message.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Default)]
@@ -25,6 +32,39 @@ pub struct CweWarning {
     /// Should contain all essential information necessary to understand the warning,
     /// including the address in the binary for which the warning was generated.
     pub description: String,
+    /// The confidence in the warning.
+    #[serde(default, skip_serializing_if = "Confidence::is_normal")]
+    pub confidence: Confidence,
+    /// The provenance of the terms in `tids`, in the same order.
+    /// Links terms created or moved by normalization passes back to the binary.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provenance: Vec<Provenance>,
+    /// The names of other checks that reported the same issue,
+    /// if their warnings were merged into this warning by the [`WarningAggregator`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merged_cwes: Vec<String>,
+}
+
+/// The confidence in a CWE warning.
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, Default,
+)]
+pub enum Confidence {
+    /// No information beyond the static analysis is known about the warning.
+    #[default]
+    Normal,
+    /// The program point of the warning was executed according to imported coverage data.
+    High,
+    /// The program point of the warning is probably not reachable
+    /// according to the [path feasibility analysis](crate::analysis::path_feasibility).
+    Low,
+}
+
+impl Confidence {
+    /// Returns true if the confidence is [`Confidence::Normal`].
+    pub fn is_normal(&self) -> bool {
+        *self == Confidence::Normal
+    }
 }
 
 impl CweWarning {
@@ -42,6 +82,9 @@ impl CweWarning {
             symbols: Vec::new(),
             other: Vec::new(),
             description: description.to_string(),
+            confidence: Confidence::Normal,
+            provenance: Vec::new(),
+            merged_cwes: Vec::new(),
         }
     }
 
@@ -68,16 +111,139 @@ impl CweWarning {
         self.other = other;
         self
     }
+
+    /// Sets the confidence field of the CweWarning
+    pub fn confidence(mut self, confidence: Confidence) -> CweWarning {
+        self.confidence = confidence;
+        self
+    }
 }
 
 impl std::fmt::Display for CweWarning {
     fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            formatter,
-            "[{}] ({}) {}",
-            self.name, self.version, self.description
-        )
+        write!(formatter, "[{}", self.name)?;
+        for name in self.merged_cwes.iter() {
+            write!(formatter, ", {name}")?;
+        }
+        write!(formatter, "] ({}) ", self.version)?;
+        match self.confidence {
+            Confidence::Normal => (),
+            Confidence::High => write!(formatter, "(high confidence) ")?,
+            Confidence::Low => write!(formatter, "(low confidence) ")?,
+        }
+        write!(formatter, "{}", self.description)
+    }
+}
+
+/// Merges CWE warnings of different checks that report the same issue into a single warning.
+///
+/// Two warnings are merged if they share the same first address
+/// and their checks belong to the same root cause,
+/// e.g. an out-of-bounds write reported by both the CWE-119 and the CWE-805 check.
+/// The merged warning keeps the name and description of the first of the warnings
+/// and lists the names of the other checks in [`CweWarning::merged_cwes`].
+/// Exact duplicates of warnings are always removed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WarningAggregator {
+    /// Sets of check names that report the same root cause.
+    root_causes: Vec<BTreeSet<String>>,
+}
+
+impl Default for WarningAggregator {
+    /// Create an aggregator with the root causes shared by the checks of the cwe_checker.
+    fn default() -> Self {
+        WarningAggregator::new()
+            .root_cause(&["CWE119", "CWE125", "CWE786", "CWE787", "CWE805"])
+            .root_cause(&["CWE415", "CWE416"])
+            .root_cause(&["CWE242", "CWE676"])
+            .root_cause(&["CWE252", "CWE476"])
+    }
+}
+
+impl WarningAggregator {
+    /// Create an aggregator without root causes, that only removes duplicate warnings.
+    pub fn new() -> WarningAggregator {
+        WarningAggregator {
+            root_causes: Vec::new(),
+        }
+    }
+
+    /// Add a root cause shared by the checks with the given names.
+    pub fn root_cause(mut self, names: &[&str]) -> WarningAggregator {
+        self.root_causes
+            .push(names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    /// Merge all warnings that report the same issue.
+    ///
+    /// The order of the warnings is kept,
+    /// where merged warnings take the position of the first of them.
+    pub fn aggregate(&self, warnings: Vec<CweWarning>) -> Vec<CweWarning> {
+        let mut aggregated: Vec<CweWarning> = Vec::new();
+        let mut merge_targets: BTreeMap<(String, usize), usize> = BTreeMap::new();
+        for warning in warnings {
+            if aggregated.contains(&warning) {
+                continue;
+            }
+            let key = warning.addresses.first().and_then(|address| {
+                let index = self
+                    .root_causes
+                    .iter()
+                    .position(|names| names.contains(&warning.name))?;
+                Some((address.clone(), index))
+            });
+            match key {
+                Some(key) => match merge_targets.get(&key) {
+                    Some(target) => merge_warning(&mut aggregated[*target], warning),
+                    None => {
+                        merge_targets.insert(key, aggregated.len());
+                        aggregated.push(warning);
+                    }
+                },
+                None => aggregated.push(warning),
+            }
+        }
+        aggregated
+    }
+}
+
+/// Merge the `other` warning into the `target` warning.
+fn merge_warning(target: &mut CweWarning, other: CweWarning) {
+    for name in std::iter::once(other.name).chain(other.merged_cwes) {
+        if name != target.name && !target.merged_cwes.contains(&name) {
+            target.merged_cwes.push(name);
+        }
+    }
+    for address in other.addresses {
+        if !target.addresses.contains(&address) {
+            target.addresses.push(address);
+        }
+    }
+    if !other.provenance.is_empty() && target.provenance.is_empty() {
+        target.provenance = vec![Provenance::default(); target.tids.len()];
+    }
+    for (index, tid) in other.tids.into_iter().enumerate() {
+        if !target.tids.contains(&tid) {
+            target.tids.push(tid);
+            if !target.provenance.is_empty() {
+                target
+                    .provenance
+                    .push(other.provenance.get(index).cloned().unwrap_or_default());
+            }
+        }
+    }
+    for symbol in other.symbols {
+        if !target.symbols.contains(&symbol) {
+            target.symbols.push(symbol);
+        }
     }
+    target.other.extend(other.other);
+    target.confidence = match (target.confidence, other.confidence) {
+        (Confidence::High, _) | (_, Confidence::High) => Confidence::High,
+        (Confidence::Low, Confidence::Low) => Confidence::Low,
+        _ => Confidence::Normal,
+    };
 }
 
 /// A generic log message.
@@ -165,29 +331,66 @@ impl std::fmt::Display for LogMessage {
     }
 }
 
+/// The output format for CWE warnings.
+#[derive(Debug, Clone, Copy)]
+pub enum OutputFormat<'a> {
+    /// One line of text per CWE warning.
+    Text,
+    /// The [`JsonOutput`] of the CWE warnings and pipeline errors.
+    Json,
+    /// A SARIF 2.1.0 log, see [`to_sarif`].
+    Sarif {
+        /// The path to the analyzed binary.
+        binary_path: &'a str,
+        /// The executed CWE checks.
+        modules: &'a [&'a CweModule],
+    },
+}
+
 /// Print all provided log- and CWE-messages.
 ///
 /// Log-messages will always be printed to `stdout`.
 /// CWE-warnings will either be printed to `stdout` or to the file path provided in `out_path`.
 ///
-/// If `emit_json` is set, the CWE-warnings will be converted to json for the output.
+/// For the JSON and SARIF output formats the output also contains the errors for functions
+/// that could not be translated (which are contained in the log messages otherwise)
+/// and the metrics of the executed checks.
 pub fn print_all_messages(
     logs: Vec<LogMessage>,
     cwes: Vec<CweWarning>,
+    pipeline_errors: Vec<PipelineError>,
+    metrics: BTreeMap<String, CheckMetrics>,
     out_path: Option<&str>,
-    emit_json: bool,
+    format: OutputFormat,
 ) {
     for log in logs {
         println!("{log}");
     }
-    let output: String = if emit_json {
-        serde_json::to_string_pretty(&cwes).unwrap()
-    } else {
-        cwes.iter()
-            .map(|cwe| format!("{cwe}"))
-            .collect::<Vec<String>>()
-            .join("\n")
-            + "\n"
+    let output: String = match format {
+        OutputFormat::Json => serde_json::to_string_pretty(&JsonOutput {
+            warnings: cwes,
+            pipeline_errors,
+            metrics,
+        })
+        .unwrap(),
+        OutputFormat::Sarif {
+            binary_path,
+            modules,
+        } => serde_json::to_string_pretty(&to_sarif(
+            &cwes,
+            &pipeline_errors,
+            &metrics,
+            modules,
+            binary_path,
+        ))
+        .unwrap(),
+        OutputFormat::Text => {
+            cwes.iter()
+                .map(|cwe| format!("{cwe}"))
+                .collect::<Vec<String>>()
+                .join("\n")
+                + "\n"
+        }
     };
     if let Some(file_path) = out_path {
         std::fs::write(file_path, output)
@@ -197,6 +400,155 @@ pub fn print_all_messages(
     }
 }
 
+/// Convert the CWE warnings for the given binary into a SARIF 2.1.0 log,
+/// e.g. for uploading the results to GitHub code scanning.
+///
+/// Each executed CWE check is described by a rule.
+/// The locations of a result are the addresses of the warning in the binary.
+/// The severity of a warning (if given in the `other` field of the warning) is mapped to the level of the result.
+/// Pipeline errors are reported as tool execution notifications.
+/// The metrics of each check are added to the properties of its rule.
+pub fn to_sarif(
+    cwes: &[CweWarning],
+    pipeline_errors: &[PipelineError],
+    metrics: &BTreeMap<String, CheckMetrics>,
+    modules: &[&CweModule],
+    binary_path: &str,
+) -> serde_json::Value {
+    let rule_indices: BTreeMap<&str, usize> = modules
+        .iter()
+        .enumerate()
+        .map(|(index, module)| (module.name, index))
+        .collect();
+    let rules: Vec<serde_json::Value> = modules
+        .iter()
+        .map(|module| {
+            let mut rule = get_sarif_rule(module.name, module.version);
+            if let Some(check_metrics) = metrics.get(module.name) {
+                rule["properties"]["metrics"] = serde_json::json!(check_metrics);
+            }
+            rule
+        })
+        .collect();
+    let results: Vec<serde_json::Value> = cwes
+        .iter()
+        .map(|cwe| {
+            let locations: Vec<serde_json::Value> = cwe
+                .addresses
+                .iter()
+                .filter_map(|address| u64::from_str_radix(address, 16).ok())
+                .map(|address| {
+                    serde_json::json!({
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": binary_path, "index": 0 },
+                            "address": { "absoluteAddress": address },
+                        }
+                    })
+                })
+                .collect();
+            let mut result = serde_json::json!({
+                "ruleId": cwe.name,
+                "level": get_sarif_level(cwe),
+                "message": { "text": cwe.description },
+                "locations": locations,
+                "properties": {
+                    "version": cwe.version,
+                    "tids": cwe.tids,
+                    "symbols": cwe.symbols,
+                    "other": cwe.other,
+                    "confidence": cwe.confidence,
+                    "mergedCwes": cwe.merged_cwes,
+                },
+            });
+            if let Some(index) = rule_indices.get(cwe.name.as_str()) {
+                result["ruleIndex"] = serde_json::json!(index);
+            }
+            result
+        })
+        .collect();
+    let notifications: Vec<serde_json::Value> = pipeline_errors
+        .iter()
+        .map(|error| {
+            serde_json::json!({
+                "level": "warning",
+                "message": { "text": error.to_string() },
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cwe_checker",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/fkie-cad/cwe_checker",
+                    "rules": rules,
+                }
+            },
+            "artifacts": [{ "location": { "uri": binary_path } }],
+            "invocations": [{
+                "executionSuccessful": true,
+                "toolExecutionNotifications": notifications,
+            }],
+            "results": results,
+        }]
+    })
+}
+
+/// Get the SARIF rule describing the CWE check with the given name.
+///
+/// Checks named after a CWE (e.g. `CWE476`) link to the description of the CWE
+/// and are tagged with the CWE identifier, which is recognized by GitHub code scanning.
+fn get_sarif_rule(name: &str, version: &str) -> serde_json::Value {
+    let mut rule = serde_json::json!({
+        "id": name,
+        "name": name,
+        "shortDescription": { "text": format!("cwe_checker check {name}") },
+        "properties": { "version": version },
+    });
+    if let Some(number) = name
+        .strip_prefix("CWE")
+        .filter(|number| number.parse::<u64>().is_ok())
+    {
+        rule["shortDescription"]["text"] = serde_json::json!(format!("CWE-{number}"));
+        rule["helpUri"] = serde_json::json!(format!(
+            "https://cwe.mitre.org/data/definitions/{number}.html"
+        ));
+        rule["properties"]["tags"] =
+            serde_json::json!(["security", format!("external/cwe/cwe-{number}")]);
+    }
+    rule
+}
+
+/// Get the SARIF level of a CWE warning from the severity in its `other` field.
+/// Warnings without a severity have the level `warning`.
+fn get_sarif_level(cwe: &CweWarning) -> &'static str {
+    let severity = cwe
+        .other
+        .iter()
+        .find(|entry| entry.first().is_some_and(|key| key == "severity"))
+        .and_then(|entry| entry.get(1));
+    match severity.map(|severity| severity.as_str()) {
+        Some("info" | "low") => "note",
+        Some("high" | "critical") => "error",
+        _ => "warning",
+    }
+}
+
+/// The json output of the cwe_checker.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct JsonOutput {
+    /// The CWE warnings.
+    pub warnings: Vec<CweWarning>,
+    /// The errors for functions that could not be translated.
+    pub pipeline_errors: Vec<PipelineError>,
+    /// The metrics of the executed checks, indexed by the check name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, CheckMetrics>,
+}
+
 /// For each analysis count the number of debug log messages in `all_logs`
 /// and add a (INFO level) log message with the resulting number to `all_logs`.
 /// Also count and log the number of general debug log messages.
@@ -379,12 +731,379 @@ impl LogThread {
     }
 }
 
-static mut START: SystemTime = SystemTime::UNIX_EPOCH;// = SystemTime::now();
-pub fn init_logging_timer(){
-    unsafe { START = SystemTime::now(); }
+impl LogLevel {
+    /// The severity of the log level, used for filtering log messages.
+    /// Debug messages have the lowest and errors the highest severity.
+    fn severity(&self) -> u8 {
+        match self {
+            LogLevel::Debug => 0,
+            LogLevel::Info => 1,
+            LogLevel::Error => 2,
+        }
+    }
+}
+
+/// A log message or CWE warning tagged with information about where it was generated.
+///
+/// The ordering of records first compares the origin, the function and the message.
+/// Timestamps are only compared last, so that sorting records yields a deterministic order
+/// independent of the order in which concurrently running analyses generated them.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct LogRecord<T> {
+    /// The name of the check or analysis that generated the message.
+    pub origin: String,
+    /// The TID of the function that the message is related to.
+    pub function: Option<Tid>,
+    /// The message itself.
+    pub message: T,
+    /// The time elapsed between the start of the log collector and the generation of the message.
+    pub timestamp: Duration,
+}
+
+/// A filter for the log messages accepted by a [`LogCollector`].
+///
+/// Log messages with a lower severity than the minimum level for their origin are discarded.
+/// CWE warnings are never discarded.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct LogFilter {
+    /// The minimum level for origins without a specific level.
+    min_level: LogLevel,
+    /// Minimum levels for specific origins.
+    origin_levels: BTreeMap<String, LogLevel>,
+}
+
+impl Default for LogFilter {
+    /// A filter accepting all log messages.
+    fn default() -> Self {
+        LogFilter::new(LogLevel::Debug)
+    }
+}
+
+impl LogFilter {
+    /// Create a filter that accepts all messages with at least the given level.
+    pub fn new(min_level: LogLevel) -> LogFilter {
+        LogFilter {
+            min_level,
+            origin_levels: BTreeMap::new(),
+        }
+    }
+
+    /// Set the minimum level for messages of the given origin.
+    pub fn origin(mut self, origin: impl Into<String>, min_level: LogLevel) -> LogFilter {
+        self.origin_levels.insert(origin.into(), min_level);
+        self
+    }
+
+    /// Check whether a message of the given origin and level passes the filter.
+    pub fn accepts(&self, origin: &str, level: &LogLevel) -> bool {
+        let min_level = self.origin_levels.get(origin).unwrap_or(&self.min_level);
+        level.severity() >= min_level.severity()
+    }
+}
+
+/// The message types that a [`LogCollector`] thread can receive.
+#[derive(Debug, Clone)]
+enum LogCollectorMsg {
+    Log(LogRecord<LogMessage>),
+    Cwe(LogRecord<CweWarning>),
+    Terminate,
+}
+
+/// All messages collected by a [`LogCollector`], sorted in a deterministic order.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct CollectedLogs {
+    /// The collected log messages.
+    pub logs: Vec<LogRecord<LogMessage>>,
+    /// The collected CWE warnings.
+    pub cwes: Vec<LogRecord<CweWarning>>,
+}
+
+impl CollectedLogs {
+    /// Strip the origin information from the collected messages.
+    pub fn into_messages(self) -> (Vec<LogMessage>, Vec<CweWarning>) {
+        (
+            self.logs.into_iter().map(|record| record.message).collect(),
+            self.cwes.into_iter().map(|record| record.message).collect(),
+        )
+    }
+}
+
+/// A channel-based collector for log messages and CWE warnings generated on several threads.
+///
+/// Messages are sent to the collector through [`Logger`] handles,
+/// which tag each message with its origin, the related function and a timestamp.
+/// The collector thread filters the log messages according to a [`LogFilter`].
+/// Calling [`collect()`](LogCollector::collect) stops the collector thread
+/// and returns the collected messages sorted in a deterministic order.
+pub struct LogCollector {
+    msg_sender: crossbeam_channel::Sender<LogCollectorMsg>,
+    thread_handle: Option<JoinHandle<CollectedLogs>>,
+    start: Instant,
+}
+
+impl Drop for LogCollector {
+    /// Stop the collector thread if it still exists.
+    fn drop(&mut self) {
+        let _ = self.msg_sender.send(LogCollectorMsg::Terminate);
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl LogCollector {
+    /// Spawn a new collector thread that only keeps log messages accepted by the given filter.
+    pub fn spawn(filter: LogFilter) -> LogCollector {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let thread_handle = std::thread::spawn(move || {
+            let mut collected = CollectedLogs::default();
+            while let Ok(msg) = receiver.recv() {
+                match msg {
+                    LogCollectorMsg::Log(record) => {
+                        if filter.accepts(&record.origin, &record.message.level) {
+                            collected.logs.push(record);
+                        }
+                    }
+                    LogCollectorMsg::Cwe(record) => collected.cwes.push(record),
+                    LogCollectorMsg::Terminate => break,
+                }
+            }
+            collected.logs.sort();
+            collected.cwes.sort();
+            collected
+        });
+        LogCollector {
+            msg_sender: sender,
+            thread_handle: Some(thread_handle),
+            start: Instant::now(),
+        }
+    }
+
+    /// Get a handle for sending messages of the given origin to the collector.
+    pub fn logger(&self, origin: impl Into<String>) -> Logger {
+        Logger {
+            origin: origin.into(),
+            function: None,
+            start: self.start,
+            msg_sender: self.msg_sender.clone(),
+        }
+    }
+
+    /// Stop the collector thread and return all messages collected until that point.
+    pub fn collect(mut self) -> CollectedLogs {
+        let _ = self.msg_sender.send(LogCollectorMsg::Terminate);
+        match self.thread_handle.take() {
+            Some(handle) => handle.join().unwrap(),
+            None => CollectedLogs::default(),
+        }
+    }
+}
+
+/// A handle for sending messages to a [`LogCollector`].
+///
+/// All messages sent through the handle are tagged with the origin and function of the handle.
+/// Handles can be cloned and sent to other threads.
+#[derive(Debug, Clone)]
+pub struct Logger {
+    origin: String,
+    function: Option<Tid>,
+    start: Instant,
+    msg_sender: crossbeam_channel::Sender<LogCollectorMsg>,
+}
+
+impl Logger {
+    /// Get a handle that additionally tags messages with the given function TID.
+    pub fn for_function(&self, function: &Tid) -> Logger {
+        Logger {
+            function: Some(function.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// Wrap the message into a record with the origin information of this handle.
+    fn record<T>(&self, message: T) -> LogRecord<T> {
+        LogRecord {
+            origin: self.origin.clone(),
+            function: self.function.clone(),
+            message,
+            timestamp: self.start.elapsed(),
+        }
+    }
+
+    /// Send a log message to the collector.
+    pub fn log(&self, message: LogMessage) {
+        let _ = self
+            .msg_sender
+            .send(LogCollectorMsg::Log(self.record(message)));
+    }
+
+    /// Send a CWE warning to the collector.
+    pub fn cwe(&self, warning: CweWarning) {
+        let _ = self
+            .msg_sender
+            .send(LogCollectorMsg::Cwe(self.record(warning)));
+    }
+
+    /// Send the log messages and CWE warnings returned by a check to the collector.
+    pub fn send_results(&self, (logs, cwes): (Vec<LogMessage>, Vec<CweWarning>)) {
+        for log in logs {
+            self.log(log);
+        }
+        for cwe in cwes {
+            self.cwe(cwe);
+        }
+    }
+}
+
+static mut START: SystemTime = SystemTime::UNIX_EPOCH; // = SystemTime::now();
+pub fn init_logging_timer() {
+    unsafe {
+        START = SystemTime::now();
+    }
     timed_logging("Program start");
 }
 pub fn timed_logging<S: std::fmt::Display>(arg: S) {
     // TODO: link to already existing logging system ?
-    unsafe { println!("{:?}\t [cwe_checker] {}", START.elapsed(), arg); }
+    unsafe {
+        println!("{:?}\t [cwe_checker] {}", START.elapsed(), arg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_collector() {
+        let filter = LogFilter::new(LogLevel::Info).origin("CWE119", LogLevel::Debug);
+        let collector = LogCollector::spawn(filter);
+        let threads: Vec<_> = ["CWE476", "CWE119"]
+            .into_iter()
+            .map(|origin| {
+                let logger = collector.logger(origin).for_function(&Tid::new("main"));
+                std::thread::spawn(move || {
+                    for i in 0..10 {
+                        logger.log(LogMessage::new_debug(format!("debug {i}")));
+                        logger.log(LogMessage::new_info(format!("info {i}")));
+                    }
+                    logger.cwe(CweWarning::new(origin, "0.1", "warning"));
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let collected = collector.collect();
+
+        // Debug messages are only kept for CWE119.
+        assert_eq!(collected.logs.len(), 30);
+        assert_eq!(collected.cwes.len(), 2);
+        assert!(collected.logs[..20]
+            .iter()
+            .all(|record| record.origin == "CWE119"));
+        assert!(collected.logs[20..]
+            .iter()
+            .all(|record| record.origin == "CWE476" && record.message.level == LogLevel::Info));
+        assert_eq!(collected.cwes[0].function, Some(Tid::new("main")));
+        let (logs, cwes) = collected.into_messages();
+        assert_eq!(logs[0], LogMessage::new_debug("debug 0"));
+        assert_eq!(cwes[1].name, "CWE476");
+    }
+
+    #[test]
+    fn warning_aggregation() {
+        let warning = |name: &str, address: &str| {
+            CweWarning::new(name, "0.1", format!("{name} warning"))
+                .addresses(vec![address.to_string()])
+                .tids(vec![format!("instr_{address}_{name}")])
+        };
+        let warnings = vec![
+            warning("CWE119", "00101000"),
+            warning("CWE476", "00101000"),
+            warning("CWE805", "00101000").confidence(Confidence::High),
+            warning("CWE119", "00102000"),
+            warning("CWE119", "00102000"),
+            CweWarning::new("CWE676", "0.1", "no address"),
+            CweWarning::new("CWE242", "0.1", "no address"),
+        ];
+        let aggregated = WarningAggregator::default().aggregate(warnings.clone());
+
+        assert_eq!(aggregated.len(), 5);
+        assert_eq!(aggregated[0].name, "CWE119");
+        assert_eq!(aggregated[0].merged_cwes, vec!["CWE805".to_string()]);
+        assert_eq!(aggregated[0].tids.len(), 2);
+        assert_eq!(aggregated[0].addresses.len(), 1);
+        assert_eq!(aggregated[0].confidence, Confidence::High);
+        assert_eq!(
+            format!("{}", aggregated[0]),
+            "[CWE119, CWE805] (0.1) (high confidence) CWE119 warning"
+        );
+        assert_eq!(aggregated[1], warnings[1]);
+        assert_eq!(aggregated[2], warnings[3]);
+        assert_eq!(aggregated[3].name, "CWE676");
+        assert_eq!(aggregated[4].name, "CWE242");
+
+        // Without root causes only duplicates are removed.
+        assert_eq!(WarningAggregator::new().aggregate(warnings).len(), 6);
+    }
+
+    #[test]
+    fn sarif_output() {
+        fn mock_run(
+            _: &AnalysisResults,
+            _: &serde_json::Value,
+        ) -> (Vec<LogMessage>, Vec<CweWarning>) {
+            (Vec::new(), Vec::new())
+        }
+        let modules = [
+            &CweModule {
+                name: "CWE476",
+                version: "0.3",
+                run: mock_run,
+            },
+            &CweModule {
+                name: "Memory",
+                version: "0.1",
+                run: mock_run,
+            },
+        ];
+        let cwes = vec![
+            CweWarning::new("CWE476", "0.3", "NULL pointer dereference")
+                .addresses(vec!["00101234".to_string(), "UNKNOWN".to_string()]),
+            CweWarning::new("CWE476", "0.3", "NULL pointer dereference")
+                .other(vec![vec!["severity".to_string(), "high".to_string()]]),
+        ];
+        let metrics = BTreeMap::from([(
+            "CWE476".to_string(),
+            CheckMetrics {
+                warnings: 2,
+                sites: None,
+            },
+        )]);
+        let sarif = to_sarif(&cwes, &[], &metrics, &modules, "/bin/binary");
+        let run = &sarif["runs"][0];
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "CWE476");
+        assert_eq!(
+            run["tool"]["driver"]["rules"][0]["helpUri"],
+            "https://cwe.mitre.org/data/definitions/476.html"
+        );
+        assert!(run["tool"]["driver"]["rules"][1]["helpUri"].is_null());
+        assert_eq!(
+            run["tool"]["driver"]["rules"][0]["properties"]["metrics"]["warnings"],
+            2
+        );
+        assert!(run["tool"]["driver"]["rules"][1]["properties"]["metrics"].is_null());
+        assert_eq!(run["artifacts"][0]["location"]["uri"], "/bin/binary");
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleIndex"], 0);
+        assert_eq!(result["level"], "warning");
+        assert_eq!(result["locations"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["address"]["absoluteAddress"],
+            0x101234
+        );
+        assert_eq!(run["results"][1]["level"], "error");
+    }
 }
@@ -0,0 +1,53 @@
+//! Serialize maps as lists of key-value pairs.
+//!
+//! JSON only allows strings as keys of objects,
+//! so maps with other keys (e.g. [`Tid`](crate::intermediate_representation::Tid)) cannot be serialized to JSON directly.
+//! Use this module with `#[serde(with = "crate::utils::map_as_list")]` on such map fields
+//! to serialize them as lists of `[key, value]` pairs instead.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// Serialize the map as a list of key-value pairs.
+pub fn serialize<K, V, S>(map: &BTreeMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Serialize,
+    V: Serialize,
+    S: Serializer,
+{
+    serializer.collect_seq(map.iter())
+}
+
+/// Deserialize the map from a list of key-value pairs.
+pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<BTreeMap<K, V>, D::Error>
+where
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let pairs: Vec<(K, V)> = Vec::deserialize(deserializer)?;
+    Ok(pairs.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::intermediate_representation::Tid;
+    use crate::prelude::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    struct TidMap {
+        #[serde(with = "crate::utils::map_as_list")]
+        map: BTreeMap<Tid, u64>,
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let map = TidMap {
+            map: BTreeMap::from([(Tid::new("a"), 1), (Tid::new("b"), 2)]),
+        };
+        let json = serde_json::to_string(&map).unwrap();
+        assert!(json.starts_with(r#"{"map":[[{"id":"a","#));
+        assert_eq!(serde_json::from_str::<TidMap>(&json).unwrap(), map);
+    }
+}
@@ -0,0 +1,237 @@
+//! Structured metrics describing how thoroughly the CWE checks analyzed a binary.
+//!
+//! A check without CWE warnings may have found no bugs
+//! or may have been unable to analyze the relevant code,
+//! e.g. because the values at the calls to sink functions were unknown.
+//! To tell both cases apart, checks count the candidate sites they examined in [`SiteCounts`]
+//! and report them through [`AnalysisResults::report_site_counts`](crate::AnalysisResults::report_site_counts).
+//! Checks that analyze whole functions instead of individual sites count the functions with [`count_function_sites`].
+//! The [`MetricsCollector`] combines the reported counts with the number of warnings of each check
+//! into [`CheckMetrics`], which are part of the JSON and SARIF output of the cwe_checker.
+
+use super::log::{CweWarning, LogRecord};
+use crate::intermediate_representation::Project;
+use crate::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+
+/// Counters for the candidate sites (e.g. calls to sink functions) examined by a CWE check.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct SiteCounts {
+    /// The number of examined sites.
+    pub examined: u64,
+    /// The number of examined sites for which the check could not decide whether they are vulnerable,
+    /// e.g. because the relevant values were unknown.
+    pub skipped: u64,
+    /// The number of examined sites that the check proved to be safe.
+    pub safe: u64,
+}
+
+impl SiteCounts {
+    /// Count an examined site for which a CWE warning was generated.
+    pub fn add_flagged(&mut self) {
+        self.examined += 1;
+    }
+
+    /// Count an examined site for which the check could not decide whether it is vulnerable.
+    pub fn add_skipped(&mut self) {
+        self.examined += 1;
+        self.skipped += 1;
+    }
+
+    /// Count an examined site that the check proved to be safe.
+    pub fn add_safe(&mut self) {
+        self.examined += 1;
+        self.safe += 1;
+    }
+
+    /// Add the counts of `other` to `self`.
+    pub fn merge(&mut self, other: &SiteCounts) {
+        self.examined += other.examined;
+        self.skipped += other.skipped;
+        self.safe += other.safe;
+    }
+}
+
+/// Count the functions of the project as the examined sites of a check that analyzes whole functions,
+/// e.g. with a fixpoint computation over the control flow graph.
+///
+/// Functions containing a block, `Def` or `Jmp` term referenced in the TIDs of one of the warnings are flagged.
+/// The other functions are counted as safe if they were analyzed according to `is_analyzed`
+/// and as skipped otherwise.
+pub fn count_function_sites(
+    project: &Project,
+    warnings: &[CweWarning],
+    is_analyzed: impl Fn(&Tid) -> bool,
+) -> SiteCounts {
+    let warning_tids: HashSet<&str> = warnings
+        .iter()
+        .flat_map(|warning| warning.tids.iter().map(String::as_str))
+        .collect();
+    let is_referenced = |tid: &Tid| warning_tids.contains(tid.to_string().as_str());
+    let mut site_counts = SiteCounts::default();
+    for sub in project.program.term.subs.values() {
+        let is_flagged = sub.term.blocks.iter().any(|blk| {
+            is_referenced(&blk.tid)
+                || blk.term.defs.iter().any(|def| is_referenced(&def.tid))
+                || blk.term.jmps.iter().any(|jmp| is_referenced(&jmp.tid))
+        });
+        if is_flagged {
+            site_counts.add_flagged();
+        } else if is_analyzed(&sub.tid) {
+            site_counts.add_safe();
+        } else {
+            site_counts.add_skipped();
+        }
+    }
+    site_counts
+}
+
+/// The metrics of a CWE check.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct CheckMetrics {
+    /// The number of CWE warnings generated by the check.
+    pub warnings: u64,
+    /// The candidate sites examined by the check.
+    /// `None` if the check does not count the sites it examines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sites: Option<SiteCounts>,
+}
+
+impl std::fmt::Display for CheckMetrics {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{} warnings", self.warnings)?;
+        match &self.sites {
+            Some(sites) => write!(
+                formatter,
+                ", {} examined sites, {} proven safe, {} skipped due to unknown values",
+                sites.examined, sites.safe, sites.skipped
+            ),
+            None => write!(formatter, ", examined sites not counted"),
+        }
+    }
+}
+
+/// Collects the site counts reported by the CWE checks, which may run on several threads.
+#[derive(Debug, Default)]
+pub struct MetricsCollector {
+    site_counts: Mutex<BTreeMap<String, SiteCounts>>,
+}
+
+impl MetricsCollector {
+    /// Create a new collector without any reported site counts.
+    pub fn new() -> MetricsCollector {
+        MetricsCollector::default()
+    }
+
+    /// Add the site counts reported by the check with the given name.
+    /// Counts reported several times by the same check are summed up.
+    pub fn report(&self, check: &str, counts: SiteCounts) {
+        let mut site_counts = self.site_counts.lock().unwrap();
+        site_counts
+            .entry(check.to_string())
+            .or_default()
+            .merge(&counts);
+    }
+
+    /// Get the metrics of the given checks,
+    /// where the warnings of each check are counted by the origin of the warning records.
+    pub fn get_check_metrics(
+        &self,
+        checks: &[&str],
+        warnings: &[LogRecord<CweWarning>],
+    ) -> BTreeMap<String, CheckMetrics> {
+        let site_counts = self.site_counts.lock().unwrap();
+        let mut metrics: BTreeMap<String, CheckMetrics> = checks
+            .iter()
+            .map(|check| {
+                let check_metrics = CheckMetrics {
+                    warnings: 0,
+                    sites: site_counts.get(*check).copied(),
+                };
+                (check.to_string(), check_metrics)
+            })
+            .collect();
+        for warning in warnings {
+            if let Some(check_metrics) = metrics.get_mut(&warning.origin) {
+                check_metrics.warnings += 1;
+            }
+        }
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intermediate_representation::{Blk, Sub};
+    use std::time::Duration;
+
+    #[test]
+    fn function_sites() {
+        let mut project = Project::mock_x64();
+        for (name, block) in [
+            ("flagged", "flagged_blk"),
+            ("safe", "safe_blk"),
+            ("skipped", "skipped_blk"),
+        ] {
+            let mut sub = Sub::mock(name);
+            sub.term.blocks.push(Blk::mock_with_tid(block));
+            project.program.term.subs.insert(sub.tid.clone(), sub);
+        }
+        let mut warning = CweWarning::new("CWE416", "0.1", "description");
+        warning.tids = vec!["flagged_blk".to_string()];
+
+        let site_counts = count_function_sites(&project, &[warning], |sub_tid| {
+            *sub_tid != Tid::new("skipped")
+        });
+        assert_eq!(
+            site_counts,
+            SiteCounts {
+                examined: 3,
+                skipped: 1,
+                safe: 1
+            }
+        );
+    }
+
+    #[test]
+    fn collect_check_metrics() {
+        let collector = MetricsCollector::new();
+        let mut counts = SiteCounts::default();
+        counts.add_flagged();
+        counts.add_safe();
+        counts.add_skipped();
+        collector.report("CWE134", counts);
+        collector.report("CWE134", counts);
+        let warning = LogRecord {
+            origin: "CWE134".to_string(),
+            function: None,
+            message: CweWarning::new("CWE134", "0.1", "description"),
+            timestamp: Duration::ZERO,
+        };
+
+        let metrics =
+            collector.get_check_metrics(&["CWE134", "CWE676"], &[warning.clone(), warning]);
+        assert_eq!(
+            metrics["CWE134"],
+            CheckMetrics {
+                warnings: 2,
+                sites: Some(SiteCounts {
+                    examined: 6,
+                    skipped: 2,
+                    safe: 2
+                }),
+            }
+        );
+        assert_eq!(
+            format!("{}", metrics["CWE134"]),
+            "2 warnings, 6 examined sites, 2 proven safe, 2 skipped due to unknown values"
+        );
+        assert_eq!(metrics["CWE676"], CheckMetrics::default());
+        assert_eq!(
+            format!("{}", metrics["CWE676"]),
+            "0 warnings, examined sites not counted"
+        );
+    }
+}
@@ -1,11 +1,24 @@
 //! This module contains various utility modules and helper functions.
 
 pub mod arguments;
+pub mod baseline;
 pub mod binary;
+pub mod buffer_utils;
+pub mod cache;
+pub mod cancellation;
+pub mod coverage;
 pub mod debug;
+#[cfg(feature = "dwarf")]
+pub mod debug_info;
+pub mod emulation;
 pub mod ghidra;
 pub mod graph_utils;
+pub mod key_material;
 pub mod log;
+pub mod map_as_list;
+pub mod metrics;
+pub mod pcode_cache;
+pub mod recording;
 pub mod symbol_utils;
 
 use crate::prelude::*;
@@ -28,3 +41,26 @@ pub fn get_ghidra_plugin_path(plugin_name: &str) -> std::path::PathBuf {
     let data_dir = project_dirs.data_dir();
     data_dir.join("ghidra").join(plugin_name)
 }
+
+/// Get the path to the directory from which CWE check plugins are loaded by default.
+pub fn get_plugin_dir() -> std::path::PathBuf {
+    let project_dirs = directories::ProjectDirs::from("", "", "cwe_checker")
+        .expect("Could not discern location of data directory.");
+    project_dirs.data_dir().join("plugins")
+}
+
+/// Get the resident memory of the current process in bytes.
+///
+/// Returns `None` if the memory usage cannot be determined, e.g. on operating systems other than Linux.
+pub fn get_resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
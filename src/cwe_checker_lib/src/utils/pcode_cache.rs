@@ -0,0 +1,77 @@
+//! A cache for the P-Code exported by Ghidra, so that the headless analysis of unchanged binaries can be skipped.
+//!
+//! The cache is a directory containing one file per cached export.
+//! Each file contains the P-Code JSON exactly as received from the `p_code_extractor` Ghidra plugin.
+//! The file name is the cache key computed by [`get_cache_key`],
+//! which consists of a hash of the binary and a hash of all settings influencing the export.
+//! Since the version of the cwe_checker is part of the settings hash,
+//! exports of older versions of the Ghidra plugin are never reused.
+//! Outdated cache files are not deleted automatically.
+
+use super::cache::fnv1a_hash;
+use crate::prelude::*;
+use crate::utils::binary::BareMetalConfig;
+use std::path::{Path, PathBuf};
+
+/// Compute the cache key of the P-Code export of the given binary with the given export settings.
+pub fn get_cache_key(
+    binary: &[u8],
+    bare_metal_config_opt: Option<&BareMetalConfig>,
+    function_filter: &[String],
+) -> String {
+    let settings = serde_json::json!({
+        "bare_metal_config": bare_metal_config_opt,
+        "function_filter": function_filter,
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    format!(
+        "{:016x}-{:016x}",
+        fnv1a_hash(binary),
+        fnv1a_hash(settings.to_string().as_bytes())
+    )
+}
+
+/// Get the path of the cache file for the given key.
+fn get_cache_file_path(directory: &Path, key: &str) -> PathBuf {
+    directory.join(format!("{key}.json"))
+}
+
+/// Read the cached P-Code JSON for the given key.
+/// Returns `None` if the cache contains no export for the key.
+pub fn load_cached_pcode(directory: &Path, key: &str) -> Option<String> {
+    std::fs::read_to_string(get_cache_file_path(directory, key)).ok()
+}
+
+/// Add the given P-Code JSON received from Ghidra to the cache.
+///
+/// The cache directory is created if it does not exist.
+/// The file is written under a temporary name first and then renamed,
+/// so that parallel runs of the cwe_checker never read a partially written export.
+pub fn store_pcode(directory: &Path, key: &str, pcode_json: &str) -> Result<(), Error> {
+    std::fs::create_dir_all(directory)?;
+    let tmp_file = directory.join(format!("{key}.{}.tmp", std::process::id()));
+    std::fs::write(&tmp_file, pcode_json)?;
+    std::fs::rename(&tmp_file, get_cache_file_path(directory, key))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_roundtrip() {
+        let directory =
+            std::env::temp_dir().join(format!("cwe_checker_pcode_cache_{}", std::process::id()));
+        let key = get_cache_key(b"\x7fELF", None, &[]);
+        assert_eq!(key, get_cache_key(b"\x7fELF", None, &[]));
+        assert_ne!(key, get_cache_key(b"\x7fELF\x02", None, &[]));
+        assert_ne!(key, get_cache_key(b"\x7fELF", None, &["main".to_string()]));
+
+        assert_eq!(load_cached_pcode(&directory, &key), None);
+        store_pcode(&directory, &key, "{}").unwrap();
+        assert_eq!(load_cached_pcode(&directory, &key), Some("{}".to_string()));
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,149 @@
+//! Recordings of the P-Code exported by Ghidra, so that analyses can be reproduced without Ghidra.
+//!
+//! A recording is a directory containing the following files:
+//!
+//! - `manifest.json`: A [`RecordingManifest`] describing the recording.
+//! - `pcode.json`: The P-Code project exactly as received from the `p_code_extractor` Ghidra plugin.
+//!   Its format is given by the `serde` representation of the [`Project`](crate::pcode::Project) type
+//!   of the [`pcode`](crate::pcode) module.
+//! - `binary`: A copy of the analyzed binary, which is needed to reconstruct the memory image of the program.
+//!
+//! Since the recording contains a copy of the binary, it should only be shared with people allowed to access the binary.
+//!
+//! The `format_version` of the manifest is incremented whenever the layout of the recording
+//! or the JSON format of the P-Code changes incompatibly.
+//! Recordings with a different format version are rejected when they are loaded.
+
+use crate::prelude::*;
+use crate::utils::binary::BareMetalConfig;
+use std::path::Path;
+
+/// The version of the recording format.
+pub const RECORDING_FORMAT_VERSION: u64 = 1;
+
+/// The name of the manifest file of a recording.
+const MANIFEST_FILE: &str = "manifest.json";
+/// The name of the file containing the P-Code JSON.
+const PCODE_FILE: &str = "pcode.json";
+/// The name of the copy of the analyzed binary.
+const BINARY_FILE: &str = "binary";
+
+/// The description of a recording.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct RecordingManifest {
+    /// The version of the recording format.
+    pub format_version: u64,
+    /// The version of the `cwe_checker_lib` crate that created the recording.
+    pub cwe_checker_version: String,
+    /// The file name of the analyzed binary.
+    pub binary_name: String,
+    /// The bare metal configuration used for the export, if the binary was analyzed as a bare metal binary.
+    pub bare_metal_config: Option<BareMetalConfig>,
+}
+
+/// The contents of a recording.
+pub struct Recording {
+    /// The description of the recording.
+    pub manifest: RecordingManifest,
+    /// The analyzed binary.
+    pub binary: Vec<u8>,
+    /// The P-Code project exported by Ghidra.
+    pub pcode_project: crate::pcode::Project,
+}
+
+/// Save a recording of the given P-Code JSON received from Ghidra to the given directory.
+///
+/// The directory is created if it does not exist.
+/// Existing recording files in the directory are overwritten.
+pub fn save_recording(
+    directory: &Path,
+    binary_name: &str,
+    binary: &[u8],
+    pcode_json: &str,
+    bare_metal_config: Option<&BareMetalConfig>,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(directory).context("Could not create the recording directory")?;
+    let manifest = RecordingManifest {
+        format_version: RECORDING_FORMAT_VERSION,
+        cwe_checker_version: env!("CARGO_PKG_VERSION").to_string(),
+        binary_name: binary_name.to_string(),
+        bare_metal_config: bare_metal_config.cloned(),
+    };
+    std::fs::write(
+        directory.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )
+    .context("Could not write the recording manifest")?;
+    std::fs::write(directory.join(PCODE_FILE), pcode_json)
+        .context("Could not write the recorded P-Code")?;
+    std::fs::write(directory.join(BINARY_FILE), binary)
+        .context("Could not write the copy of the binary")?;
+    Ok(())
+}
+
+/// Read the manifest of the recording in the given directory.
+///
+/// Returns an error if the recording has a different format version.
+pub fn load_manifest(directory: &Path) -> Result<RecordingManifest, Error> {
+    let manifest = std::fs::read_to_string(directory.join(MANIFEST_FILE))
+        .context("Could not read the recording manifest")?;
+    let manifest: RecordingManifest =
+        serde_json::from_str(&manifest).context("Invalid recording manifest")?;
+    if manifest.format_version != RECORDING_FORMAT_VERSION {
+        return Err(anyhow!(
+            "The recording has format version {}, but only version {} is supported",
+            manifest.format_version,
+            RECORDING_FORMAT_VERSION
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Load the recording in the given directory.
+pub fn load_recording(directory: &Path) -> Result<Recording, Error> {
+    let manifest = load_manifest(directory)?;
+    let binary = std::fs::read(directory.join(BINARY_FILE))
+        .context("Could not read the copy of the binary")?;
+    let pcode_file = std::fs::File::open(directory.join(PCODE_FILE))
+        .context("Could not open the recorded P-Code")?;
+    let pcode_project = serde_json::from_reader(std::io::BufReader::new(pcode_file))
+        .context("Invalid recorded P-Code")?;
+    Ok(Recording {
+        manifest,
+        binary,
+        pcode_project,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_roundtrip() {
+        let directory =
+            std::env::temp_dir().join(format!("cwe_checker_recording_{}", std::process::id()));
+        save_recording(&directory, "httpd", b"\x7fELF", "{}", None).unwrap();
+        let manifest = load_manifest(&directory).unwrap();
+        assert_eq!(manifest.binary_name, "httpd");
+        assert_eq!(manifest.bare_metal_config, None);
+        assert_eq!(
+            std::fs::read(directory.join(BINARY_FILE)).unwrap(),
+            b"\x7fELF"
+        );
+        // The recorded P-Code is not a valid project.
+        assert!(load_recording(&directory).is_err());
+
+        let manifest = RecordingManifest {
+            format_version: RECORDING_FORMAT_VERSION + 1,
+            ..manifest
+        };
+        std::fs::write(
+            directory.join(MANIFEST_FILE),
+            serde_json::to_string(&manifest).unwrap(),
+        )
+        .unwrap();
+        assert!(load_manifest(&directory).is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,9 +1,13 @@
 //! Helper functions for common tasks utilizing extern symbols,
 //! e.g. searching for calls to a specific extern symbol.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
+use crate::abstract_domain::TryToBitvec;
+use crate::analysis::pointer_inference::PointerInference;
+use crate::analysis::vsa_results::VsaResult;
 use crate::intermediate_representation::*;
+use crate::prelude::*;
 
 /// Find the extern symbol object for a symbol name and return the symbol tid and name.
 pub fn find_symbol<'a>(prog: &'a Term<Program>, name: &str) -> Option<(&'a Tid, &'a str)> {
@@ -44,7 +48,9 @@ pub fn get_calls_to_symbols<'a, 'b>(
 /// Get a map from TIDs to the corresponding extern symbol struct.
 ///
 /// Only symbols with names contained in `symbols_to_find` are contained in the
-/// map.
+/// map. Several extern symbols may share the same name, e.g. after
+/// [symbol normalization](crate::intermediate_representation::symbol_normalization)
+/// renamed a fortified variant of a function to the name of the function.
 ///
 /// This is O(|symbols_to_find| x |extern_symbols|), prefer
 /// [`get_symbol_map_fast`] if speed matters.
@@ -54,26 +60,29 @@ pub fn get_symbol_map<'a>(
 ) -> HashMap<Tid, &'a ExternSymbol> {
     let mut tid_map = HashMap::new();
     for symbol_name in symbols_to_find {
-        if let Some((tid, symbol)) =
-            project
-                .program
-                .term
-                .extern_symbols
-                .iter()
-                .find_map(|(_tid, symbol)| {
-                    if symbol.name == *symbol_name {
-                        Some((symbol.tid.clone(), symbol))
-                    } else {
-                        None
-                    }
-                })
-        {
-            tid_map.insert(tid, symbol);
+        for symbol in project.program.term.extern_symbols.values() {
+            if symbol.name == *symbol_name {
+                tid_map.insert(symbol.tid.clone(), symbol);
+            }
         }
     }
     tid_map
 }
 
+/// Get the value of the parameter with the given index at a call to the extern symbol
+/// if the Pointer Inference analysis knows it to be a constant.
+pub fn get_constant_parameter(
+    project: &Project,
+    pi_result: &PointerInference,
+    call_tid: &Tid,
+    symbol: &ExternSymbol,
+    index: usize,
+) -> Option<u64> {
+    let arg = project.get_parameter_arg(symbol, index)?;
+    let value = pi_result.eval_parameter_arg_at_call(call_tid, &arg)?;
+    value.try_to_bitvec().ok()?.try_to_u64().ok()
+}
+
 /// Get a map from TIDs to the corresponding extern symbol struct.
 ///
 /// Only symbols with names contained in `symbols_to_find` are contained in the
@@ -120,3 +129,125 @@ pub fn get_callsites<'a>(
     }
     callsites
 }
+
+/// Return the names of all extern symbols and of all functions contained in the project.
+///
+/// Useful for recognizing libraries or frameworks by characteristic symbols,
+/// regardless of whether they are linked dynamically or statically (but not stripped).
+pub fn get_symbol_and_function_names(project: &Project) -> BTreeSet<&str> {
+    project
+        .program
+        .term
+        .extern_symbols
+        .values()
+        .map(|symbol| symbol.name.as_str())
+        .chain(
+            project
+                .program
+                .term
+                .subs
+                .values()
+                .map(|sub| sub.term.name.as_str()),
+        )
+        .collect()
+}
+
+/// Get the TIDs of all internal functions whose name contains one of the given keywords (case-insensitive).
+pub fn get_subs_by_keywords(project: &Project, keywords: &[String]) -> HashSet<Tid> {
+    let keywords: Vec<String> = keywords
+        .iter()
+        .map(|keyword| keyword.to_lowercase())
+        .collect();
+    project
+        .program
+        .term
+        .subs
+        .values()
+        .filter(|sub| {
+            let name = sub.term.name.to_lowercase();
+            keywords.iter().any(|keyword| name.contains(keyword))
+        })
+        .map(|sub| sub.tid.clone())
+        .collect()
+}
+
+/// The symbols identifying a library or a framework, e.g. an embedded web server.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct FrameworkSignature {
+    /// The name of the framework.
+    pub name: String,
+    /// Symbols whose presence in the binary indicate the framework.
+    pub signatures: Vec<String>,
+}
+
+impl AsRef<FrameworkSignature> for FrameworkSignature {
+    fn as_ref(&self) -> &FrameworkSignature {
+        self
+    }
+}
+
+/// Return the frameworks for which at least one signature symbol is contained in the project,
+/// either as an extern symbol or as the name of a function.
+pub fn get_recognized_frameworks<'a, T: AsRef<FrameworkSignature>>(
+    project: &Project,
+    frameworks: &'a [T],
+) -> Vec<&'a T> {
+    let symbol_names = get_symbol_and_function_names(project);
+    frameworks
+        .iter()
+        .filter(|framework| {
+            framework
+                .as_ref()
+                .signatures
+                .iter()
+                .any(|signature| symbol_names.contains(signature.as_str()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_framework(name: &str, signature: &str) -> FrameworkSignature {
+        FrameworkSignature {
+            name: name.to_string(),
+            signatures: vec![signature.to_string()],
+        }
+    }
+
+    #[test]
+    fn framework_recognition() {
+        let mut project = Project::mock_x64();
+        let get_var = ExternSymbol::mock_x64("websGetVar");
+        project
+            .program
+            .term
+            .extern_symbols
+            .insert(get_var.tid.clone(), get_var);
+        let mongoose_sub = Term {
+            tid: Tid::new("mg_start"),
+            term: Sub {
+                name: "mg_start".to_string(),
+                blocks: Vec::new(),
+                calling_convention: None,
+            },
+        };
+        project
+            .program
+            .term
+            .subs
+            .insert(mongoose_sub.tid.clone(), mongoose_sub);
+        let frameworks = vec![
+            mock_framework("GoAhead", "websGetVar"),
+            mock_framework("boa", "add_cgi_env"),
+            mock_framework("mongoose", "mg_start"),
+        ];
+
+        let recognized: Vec<&str> = get_recognized_frameworks(&project, &frameworks)
+            .into_iter()
+            .map(|framework| framework.name.as_str())
+            .collect();
+        assert_eq!(recognized, vec!["GoAhead", "mongoose"]);
+    }
+}
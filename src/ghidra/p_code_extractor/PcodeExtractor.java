@@ -1,6 +1,8 @@
 import java.io.FileNotFoundException;
 import java.util.ArrayList;
+import java.util.Arrays;
 import java.util.HashMap;
+import java.util.HashSet;
 import java.util.stream.StreamSupport;
 
 import bil.*;
@@ -33,6 +35,13 @@ import ghidra.util.exception.CancelledException;
 
 public class PcodeExtractor extends GhidraScript {
 
+    // Names of the functions to export. If both sets are empty, all functions are exported.
+    private HashSet<String> selectedFunctionNames = new HashSet<String>();
+    // Entry addresses of the functions to export.
+    private HashSet<Long> selectedFunctionAddresses = new HashSet<Long>();
+    // Lengths of the exported assembly instructions in bytes, indexed by their addresses.
+    private HashMap<String, Integer> instructionLengths = new HashMap<String, Integer>();
+
     /**
      * 
      * Entry point to Ghidra Script. Calls serializer after processing of Terms.
@@ -43,6 +52,8 @@ public class PcodeExtractor extends GhidraScript {
         HelperFunctions.ghidraProgram = currentProgram;
         HelperFunctions.funcMan = currentProgram.getFunctionManager();
         HelperFunctions.context = new VarnodeContext(currentProgram, currentProgram.getProgramContext(), currentProgram.getProgramContext());
+        String[] scriptArgs = getScriptArgs();
+        parseFunctionFilter(Arrays.copyOfRange(scriptArgs, 1, scriptArgs.length));
         SimpleBlockModel simpleBM = new SimpleBlockModel(currentProgram);
         Listing listing = currentProgram.getListing();
 
@@ -53,8 +64,9 @@ public class PcodeExtractor extends GhidraScript {
         ExternSymbolCreator.createExternalSymbolMap(TermCreator.symTab);
         program = iterateFunctions(simpleBM, listing, program);
         program.getTerm().setExternSymbols(new ArrayList<ExternSymbol>(ExternSymbolCreator.externalSymbolMap.values()));
+        program.getTerm().setInstructionLengths(instructionLengths);
 
-        String jsonPath = getScriptArgs()[0];
+        String jsonPath = scriptArgs[0];
         Serializer ser = new Serializer(project, jsonPath);
         ser.serializeProject();
 
@@ -62,6 +74,42 @@ public class PcodeExtractor extends GhidraScript {
     }
 
 
+    /**
+     * 
+     * @param filter: function names and entry addresses (hexadecimal with 0x prefix)
+     * 
+     * Sets the functions to export. An empty filter selects all functions.
+     */
+    protected void parseFunctionFilter(String[] filter) {
+        for (String entry : filter) {
+            if (entry.startsWith("0x")) {
+                try {
+                    selectedFunctionAddresses.add(Long.parseUnsignedLong(entry.substring(2), 16));
+                    continue;
+                } catch (NumberFormatException e) {
+                    // Not an address, so it is treated as a function name.
+                }
+            }
+            selectedFunctionNames.add(entry);
+        }
+    }
+
+
+    /**
+     * 
+     * @param func: function to check
+     * @return: true if the function shall be exported
+     * 
+     * Checks whether the function is selected by its name or its entry address.
+     */
+    protected Boolean isSelectedFunction(Function func) {
+        if (selectedFunctionNames.isEmpty() && selectedFunctionAddresses.isEmpty()) {
+            return true;
+        }
+        return selectedFunctionNames.contains(func.getName()) || selectedFunctionAddresses.contains(func.getEntryPoint().getOffset());
+    }
+
+
     /**
      * 
      * @param simpleBM: Simple Block Model to iterate over blocks
@@ -70,10 +118,17 @@ public class PcodeExtractor extends GhidraScript {
      * @return: Processed Program Term
      * 
      * Iterates over functions to create sub terms and calls the block iterator to add all block terms to each subroutine.
+     * Functions not selected by the function filter are added as external symbols instead.
      */
     protected Term<Program> iterateFunctions(SimpleBlockModel simpleBM, Listing listing, Term<Program> program) {
         FunctionIterator functions = HelperFunctions.funcMan.getFunctions(true);
         for (Function func : functions) {
+            if(!isSelectedFunction(func)) {
+                if(!ExternSymbolCreator.externalSymbolMap.containsKey(func.getName())) {
+                    ExternSymbolCreator.addInternalFunctionAsSymbol(func);
+                }
+                continue;
+            }
             if(ExternSymbolCreator.externalSymbolMap.containsKey(func.getName())) {
                 ArrayList<String> addresses = ExternSymbolCreator.externalSymbolMap.get(func.getName()).getAddresses();
                 if(!addresses.stream().anyMatch(addr -> addr.equals(func.getEntryPoint().toString()))) {
@@ -128,6 +183,7 @@ public class PcodeExtractor extends GhidraScript {
      * @return: new array of Blk Terms
      * 
      * Iterates over assembly instructions and processes each of the pcode blocks.
+     * Records the length of each instruction.
      * Handles empty block by adding a jump Term with fallthrough address
      */
     protected ArrayList<Term<Blk>> iterateInstructions(Term<Blk> block, Listing listing, CodeBlock codeBlock) {
@@ -139,6 +195,7 @@ public class PcodeExtractor extends GhidraScript {
 
         for (Instruction instr : instructions) {
             PcodeBlockData.instruction = instr;
+            instructionLengths.put(instr.getAddress().toString(), instr.getLength());
             analysePcodeBlockOfAssemblyInstruction();
             PcodeBlockData.instructionIndex++;
         }
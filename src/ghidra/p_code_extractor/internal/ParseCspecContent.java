@@ -167,7 +167,7 @@ public class ParseCspecContent {
         if (processorDef.startsWith("AARCH64") && languageId.endsWith("AppleSilicon")) {
             processorDef = "AppleSilicon.ldefs";
         }
-        if(processorDef.startsWith("MIPS") || processorDef.startsWith("AVR")) {
+        if(processorDef.startsWith("MIPS") || processorDef.startsWith("AVR") || processorDef.startsWith("RISCV")) {
             processorDef = processorDef.toLowerCase();
         }
         if(processorDef.startsWith("PowerPC")) {
@@ -113,6 +113,26 @@ public class ExternSymbolCreator {
     }
 
 
+    /**
+     * 
+     * @param func: internal function that is not exported
+     * 
+     * Adds an internal function to the external symbol map,
+     * so that calls to it are handled like calls to external functions.
+     */
+    public static void addInternalFunctionAsSymbol(Function func) {
+        ExternSymbol extSym = new ExternSymbol();
+        extSym.setName(func.getName());
+        extSym.setTid(new Tid(String.format("sub_%s", func.getEntryPoint().toString()), func.getEntryPoint().toString()));
+        extSym.setNoReturn(func.hasNoReturn());
+        extSym.setArguments(createArguments(func));
+        extSym.setCallingConvention(HelperFunctions.funcMan.getDefaultCallingConvention().toString());
+        extSym.setHasVarArgs(func.hasVarArgs());
+        extSym.getAddresses().add(func.getEntryPoint().toString());
+        externalSymbolMap.put(func.getName(), extSym);
+    }
+
+
     /**
      * 
      * @param flow: flow from instruction to target
@@ -1,6 +1,7 @@
 package term;
 
 import java.util.ArrayList;
+import java.util.HashMap;
 
 import com.google.gson.annotations.SerializedName;
 
@@ -16,6 +17,8 @@ public class Program {
     private ArrayList<Tid> entryPoints;
     @SerializedName("image_base")
     private String imageBase;
+    @SerializedName("instruction_lengths")
+    private HashMap<String, Integer> instructionLengths;
 
     public Program() {
     }
@@ -66,4 +69,12 @@ public class Program {
     public void setImageBase(String imageBase) {
         this.imageBase = imageBase;
     }
+
+    public HashMap<String, Integer> getInstructionLengths() {
+        return instructionLengths;
+    }
+
+    public void setInstructionLengths(HashMap<String, Integer> instructionLengths) {
+        this.instructionLengths = instructionLengths;
+    }
 }
@@ -25,6 +25,8 @@ echo "Installing cross compiler for ELF PPC architecture."
 sudo apt install -y gcc-powerpc-linux-gnu g++-powerpc-linux-gnu
 sudo apt install -y gcc-powerpc64-linux-gnu g++-powerpc64-linux-gnu
 sudo apt install -y gcc-powerpc64le-linux-gnu g++-powerpc64le-linux-gnu
+echo "Installing cross compiler for ELF RISC-V architecture."
+sudo apt install -y gcc-riscv64-linux-gnu g++-riscv64-linux-gnu
 
 echo "Installing llvm compiler backend"
 sudo apt install -y llvm
@@ -5,8 +5,8 @@ use std::process::Command;
 
 /// CPU architectures contained in the test samples
 pub const ARCHITECTURES: &[&str] = &[
-    "aarch64", "arm", "mips64", "mips64el", "mips", "mipsel", "ppc64", "ppc64le", "ppc", "x64",
-    "x86",
+    "aarch64", "arm", "mips64", "mips64el", "mips", "mipsel", "ppc64", "ppc64le", "ppc", "riscv64",
+    "x64", "x86",
 ];
 /// Compilers contained in the test samples
 pub const COMPILERS: &[&str] = &["gcc", "clang"];
@@ -146,10 +146,12 @@ where
 }
 
 /// Return a list with all possible Linux test cases for the given CWE.
+///
+/// The samples for 32-bit PowerPC and RISC-V are only compiled with gcc.
 pub fn linux_test_cases(cwe: &'static str, check_name: &'static str) -> Vec<CweTestCase> {
     new_test_cases(cwe, ARCHITECTURES, COMPILERS, check_name, false)
         .into_iter()
-        .filter(|test| test.architecture != "ppc" || test.compiler != "clang")
+        .filter(|test| !["ppc", "riscv64"].contains(&test.architecture) || test.compiler != "clang")
         .collect()
 }
 
@@ -738,6 +740,7 @@ mod tests {
         mark_architecture_skipped(&mut tests, "ppc64le"); // Ghidra generates mangled function names here for some reason.
 
         mark_skipped(&mut tests, "ppc", "gcc"); // Needs tracking of linear dependencies between register values.
+        mark_skipped(&mut tests, "riscv64", "gcc"); // Needs tracking of linear dependencies between register values.
 
         mark_compiler_skipped(&mut tests, "mingw32-gcc"); // TODO: Check reason for failure!
 